@@ -27,6 +27,7 @@ use std::{
     io,
     io::{LineWriter, Write},
     path::{Path, PathBuf},
+    str::FromStr,
     time::{Duration, Instant},
 };
 
@@ -37,8 +38,16 @@ use log::*;
 use minotari_app_grpc::authentication::salted_password::create_salted_hashed_password;
 use minotari_wallet::{
     connectivity_service::WalletConnectivityInterface,
-    output_manager_service::{handle::OutputManagerHandle, UtxoSelectionCriteria},
-    transaction_service::handle::{TransactionEvent, TransactionServiceHandle},
+    error::WalletError,
+    output_manager_service::{handle::OutputManagerHandle, UtxoSelectionCriteria, UtxoSelectionOrdering},
+    transaction_service::{
+        handle::{TransactionEvent, TransactionServiceHandle},
+        ledger::{build_ledger, LedgerEntry},
+        payment_request::PaymentRequestUri,
+        scheduled_payment::PaymentSchedule,
+        storage::models::{LabelTarget, TransactionLabels},
+    },
+    utxo_scanner_service::handle::{UtxoScannerEvent, UtxoScannerHandle},
     TransactionStage,
     WalletConfig,
     WalletSqlite,
@@ -59,9 +68,12 @@ use tari_comms::{
     types::CommsPublicKey,
 };
 use tari_comms_dht::{envelope::NodeDestination, DhtDiscoveryRequester};
-use tari_core::transactions::{
-    tari_amount::{uT, MicroMinotari, Minotari},
-    transaction_components::{OutputFeatures, TransactionOutput, WalletOutput},
+use tari_core::{
+    covenants::Covenant,
+    transactions::{
+        tari_amount::{uT, MicroMinotari, Minotari},
+        transaction_components::{OutputFeatures, TransactionOutput, WalletOutput},
+    },
 };
 use tari_crypto::ristretto::RistrettoSecretKey;
 use tari_utilities::{hex::Hex, ByteArray};
@@ -73,6 +85,7 @@ use tokio::{
 use super::error::CommandError;
 use crate::{
     cli::{CliCommands, MakeItRainTransactionType},
+    init::get_new_passphrase,
     utils::db::{CUSTOM_BASE_NODE_ADDRESS_KEY, CUSTOM_BASE_NODE_PUBLIC_KEY_KEY},
 };
 
@@ -112,6 +125,7 @@ pub async fn send_tari(
     mut wallet_transaction_service: TransactionServiceHandle,
     fee_per_gram: u64,
     amount: MicroMinotari,
+    selection_criteria: UtxoSelectionCriteria,
     destination: TariAddress,
     message: String,
 ) -> Result<TxId, CommandError> {
@@ -119,7 +133,7 @@ pub async fn send_tari(
         .send_transaction(
             destination,
             amount,
-            UtxoSelectionCriteria::default(),
+            selection_criteria,
             OutputFeatures::default(),
             fee_per_gram * uT,
             message,
@@ -132,16 +146,11 @@ pub async fn burn_tari(
     mut wallet_transaction_service: TransactionServiceHandle,
     fee_per_gram: u64,
     amount: MicroMinotari,
+    selection_criteria: UtxoSelectionCriteria,
     message: String,
 ) -> Result<(TxId, BurntProof), CommandError> {
     wallet_transaction_service
-        .burn_tari(
-            amount,
-            UtxoSelectionCriteria::default(),
-            fee_per_gram * uT,
-            message,
-            None,
-        )
+        .burn_tari(amount, selection_criteria, fee_per_gram * uT, message, None)
         .await
         .map_err(CommandError::TransactionServiceError)
 }
@@ -227,6 +236,7 @@ pub async fn send_one_sided(
     selection_criteria: UtxoSelectionCriteria,
     dest_address: TariAddress,
     message: String,
+    covenant: Covenant,
 ) -> Result<TxId, CommandError> {
     wallet_transaction_service
         .send_one_sided_transaction(
@@ -234,6 +244,7 @@ pub async fn send_one_sided(
             amount,
             selection_criteria,
             OutputFeatures::default(),
+            covenant,
             fee_per_gram * uT,
             message,
         )
@@ -248,6 +259,7 @@ pub async fn send_one_sided_to_stealth_address(
     selection_criteria: UtxoSelectionCriteria,
     dest_address: TariAddress,
     message: String,
+    covenant: Covenant,
 ) -> Result<TxId, CommandError> {
     wallet_transaction_service
         .send_one_sided_to_stealth_address_transaction(
@@ -255,6 +267,7 @@ pub async fn send_one_sided_to_stealth_address(
             amount,
             selection_criteria,
             OutputFeatures::default(),
+            covenant,
             fee_per_gram * uT,
             message,
         )
@@ -338,6 +351,48 @@ pub async fn discover_peer(
 
     Ok(())
 }
+/// Rescans a range of blocks for outputs belonging to the wallet and waits for the rescan to complete
+pub async fn rescan(
+    mut utxo_scanner: UtxoScannerHandle,
+    from_height: Option<u64>,
+    to_height: Option<u64>,
+) -> Result<(), CommandError> {
+    let mut events = utxo_scanner.get_event_receiver();
+    utxo_scanner
+        .rescan(from_height, to_height)
+        .await
+        .map_err(WalletError::from)?;
+    println!("Rescan started");
+    loop {
+        match events.recv().await {
+            Ok(UtxoScannerEvent::RescanProgress {
+                current_height,
+                to_height,
+            }) => {
+                println!("Rescanned up to block {} of {}", current_height, to_height);
+            },
+            Ok(UtxoScannerEvent::RescanCompleted {
+                from_height,
+                to_height,
+                num_recovered,
+                value_recovered,
+                time_taken,
+            }) => {
+                println!(
+                    "Rescanned blocks {} to {} in {:.2?}, found {} new outputs worth {}",
+                    from_height, to_height, time_taken, num_recovered, value_recovered
+                );
+                return Ok(());
+            },
+            Ok(UtxoScannerEvent::RescanFailed { error }) => return Err(CommandError::General(error)),
+            Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {},
+            Err(broadcast::error::RecvError::Closed) => {
+                return Err(CommandError::General("UTXO scanner service has shut down".to_string()))
+            },
+        }
+    }
+}
+
 // casting here is okay. If the txns per second for this primary debug tool is a bit off its okay.
 #[allow(clippy::cast_possible_truncation)]
 #[allow(clippy::too_many_lines)]
@@ -419,7 +474,15 @@ pub async fn make_it_rain(
                     // Send transaction
                     let tx_id = match transaction_type {
                         MakeItRainTransactionType::Interactive => {
-                            send_tari(tx_service, fee, amount, address.clone(), msg.clone()).await
+                            send_tari(
+                                tx_service,
+                                fee,
+                                amount,
+                                UtxoSelectionCriteria::default(),
+                                address.clone(),
+                                msg.clone(),
+                            )
+                            .await
                         },
                         MakeItRainTransactionType::OneSided => {
                             send_one_sided(
@@ -429,6 +492,7 @@ pub async fn make_it_rain(
                                 UtxoSelectionCriteria::default(),
                                 address.clone(),
                                 msg.clone(),
+                                Covenant::default(),
                             )
                             .await
                         },
@@ -440,12 +504,15 @@ pub async fn make_it_rain(
                                 UtxoSelectionCriteria::default(),
                                 address.clone(),
                                 msg.clone(),
+                                Covenant::default(),
                             )
                             .await
                         },
-                        MakeItRainTransactionType::BurnTari => burn_tari(tx_service, fee, amount, msg.clone())
-                            .await
-                            .map(|(tx_id, _)| tx_id),
+                        MakeItRainTransactionType::BurnTari => {
+                            burn_tari(tx_service, fee, amount, UtxoSelectionCriteria::default(), msg.clone())
+                                .await
+                                .map(|(tx_id, _)| tx_id)
+                        },
                     };
                     let submit_time = Instant::now();
 
@@ -606,6 +673,26 @@ pub async fn monitor_transactions(
     results
 }
 
+/// Selection criteria that spend from the named account, or from the default account if no account is given
+async fn utxo_selection_criteria(
+    output_service: &mut OutputManagerHandle,
+    ordering: UtxoSelectionOrdering,
+    account: Option<&str>,
+) -> Result<UtxoSelectionCriteria, CommandError> {
+    let criteria = UtxoSelectionCriteria::with_ordering(ordering);
+    let name = match account {
+        Some(name) => name,
+        None => return Ok(criteria),
+    };
+    let account = output_service
+        .get_accounts()
+        .await?
+        .into_iter()
+        .find(|a| a.name == name)
+        .ok_or_else(|| CommandError::InvalidArgument(format!("Account `{}` does not exist", name)))?;
+    Ok(criteria.with_account(account.id))
+}
+
 #[allow(clippy::too_many_lines)]
 pub async fn command_runner(
     config: &WalletConfig,
@@ -659,6 +746,7 @@ pub async fn command_runner(
                     transaction_service.clone(),
                     config.fee_per_gram,
                     args.amount,
+                    UtxoSelectionCriteria::with_ordering(args.utxo_selection),
                     args.message,
                 )
                 .await
@@ -678,10 +766,25 @@ pub async fn command_runner(
                 }
             },
             SendMinotari(args) => {
+                let selection_criteria =
+                    match utxo_selection_criteria(&mut output_service, args.utxo_selection, args.account.as_deref())
+                        .await
+                    {
+                        Ok(criteria) => criteria,
+                        Err(e) => {
+                            eprintln!("SendMinotari error! {}", e);
+                            continue;
+                        },
+                    };
+                if args.covenant.is_some() {
+                    eprintln!("SendMinotari error! Only one-sided sends can carry a covenant");
+                    continue;
+                }
                 match send_tari(
                     transaction_service.clone(),
                     config.fee_per_gram,
                     args.amount,
+                    selection_criteria,
                     args.destination,
                     args.message,
                 )
@@ -695,13 +798,24 @@ pub async fn command_runner(
                 }
             },
             SendOneSided(args) => {
+                let selection_criteria =
+                    match utxo_selection_criteria(&mut output_service, args.utxo_selection, args.account.as_deref())
+                        .await
+                    {
+                        Ok(criteria) => criteria,
+                        Err(e) => {
+                            eprintln!("SendOneSided error! {}", e);
+                            continue;
+                        },
+                    };
                 match send_one_sided(
                     transaction_service.clone(),
                     config.fee_per_gram,
                     args.amount,
-                    UtxoSelectionCriteria::default(),
+                    selection_criteria,
                     args.destination,
                     args.message,
+                    args.covenant.unwrap_or_default(),
                 )
                 .await
                 {
@@ -713,13 +827,24 @@ pub async fn command_runner(
                 }
             },
             SendOneSidedToStealthAddress(args) => {
+                let selection_criteria =
+                    match utxo_selection_criteria(&mut output_service, args.utxo_selection, args.account.as_deref())
+                        .await
+                    {
+                        Ok(criteria) => criteria,
+                        Err(e) => {
+                            eprintln!("SendOneSidedToStealthAddress error! {}", e);
+                            continue;
+                        },
+                    };
                 match send_one_sided_to_stealth_address(
                     transaction_service.clone(),
                     config.fee_per_gram,
                     args.amount,
-                    UtxoSelectionCriteria::default(),
+                    selection_criteria,
                     args.destination,
                     args.message,
+                    args.covenant.unwrap_or_default(),
                 )
                 .await
                 {
@@ -883,11 +1008,25 @@ pub async fn command_runner(
                 }
             },
             InitShaAtomicSwap(args) => {
+                let selection_criteria =
+                    match utxo_selection_criteria(&mut output_service, args.utxo_selection, args.account.as_deref())
+                        .await
+                    {
+                        Ok(criteria) => criteria,
+                        Err(e) => {
+                            eprintln!("InitShaAtomicSwap error! {}", e);
+                            continue;
+                        },
+                    };
+                if args.covenant.is_some() {
+                    eprintln!("InitShaAtomicSwap error! Only one-sided sends can carry a covenant");
+                    continue;
+                }
                 match init_sha_atomic_swap(
                     transaction_service.clone(),
                     config.fee_per_gram,
                     args.amount,
-                    UtxoSelectionCriteria::default(),
+                    selection_criteria,
                     args.destination,
                     args.message,
                 )
@@ -1009,6 +1148,339 @@ pub async fn command_runner(
                 debug!(target: LOG_TARGET, "Registering VN tx_id {}", tx_id);
                 tx_ids.push(tx_id);
             },
+            FreezeUtxos(args) => match commitments_from_bytes(&args.commitments) {
+                Ok(commitments) => {
+                    let count = commitments.len();
+                    match output_service.freeze_outputs(commitments).await {
+                        Ok(()) => println!("Froze {} UTXO(s)", count),
+                        Err(e) => eprintln!("FreezeUtxos error! {}", e),
+                    }
+                },
+                Err(e) => eprintln!("FreezeUtxos error! {}", e),
+            },
+            UnfreezeUtxos(args) => match commitments_from_bytes(&args.commitments) {
+                Ok(commitments) => {
+                    let count = commitments.len();
+                    match output_service.unfreeze_outputs(commitments).await {
+                        Ok(()) => println!("Unfroze {} UTXO(s)", count),
+                        Err(e) => eprintln!("UnfreezeUtxos error! {}", e),
+                    }
+                },
+                Err(e) => eprintln!("UnfreezeUtxos error! {}", e),
+            },
+            ListFrozenUtxos => match output_service.get_frozen_outputs().await {
+                Ok(utxos) => {
+                    for (i, utxo) in utxos.iter().enumerate() {
+                        println!(
+                            "{}. Commitment: {} Value: {} Status: {}",
+                            i + 1,
+                            utxo.commitment.to_hex(),
+                            utxo.wallet_output.value,
+                            utxo.status
+                        );
+                    }
+                    let sum: MicroMinotari = utxos.iter().map(|utxo| utxo.wallet_output.value).sum();
+                    println!("Total number of frozen UTXOs: {}", utxos.len());
+                    println!("Total value of frozen UTXOs: {}", sum);
+                },
+                Err(e) => eprintln!("ListFrozenUtxos error! {}", e),
+            },
+            LabelTransaction(args) => {
+                let labels = TransactionLabels::new(args.label, args.tag);
+                match transaction_service
+                    .set_labels(LabelTarget::Transaction(args.tx_id.into()), labels.clone())
+                    .await
+                {
+                    Ok(()) => println!("Labelled transaction {}: {}", args.tx_id, labels),
+                    Err(e) => eprintln!("LabelTransaction error! {}", e),
+                }
+            },
+            LabelOutput(args) => match Commitment::from_bytes(&args.commitment) {
+                Ok(commitment) => {
+                    let labels = TransactionLabels::new(args.label, args.tag);
+                    match transaction_service
+                        .set_labels(LabelTarget::Output(commitment.clone()), labels.clone())
+                        .await
+                    {
+                        Ok(()) => println!("Labelled output {}: {}", commitment.to_hex(), labels),
+                        Err(e) => eprintln!("LabelOutput error! {}", e),
+                    }
+                },
+                Err(e) => eprintln!("LabelOutput error! {}", e),
+            },
+            ExportTransactions(args) => {
+                let ledger = match transaction_service.get_completed_transactions().await {
+                    Ok(transactions) => match transaction_service.get_all_transaction_labels().await {
+                        Ok(labels) => build_ledger(transactions.into_values(), &labels, &args.label, &args.tag),
+                        Err(e) => {
+                            eprintln!("ExportTransactions error! {}", e);
+                            continue;
+                        },
+                    },
+                    Err(e) => {
+                        eprintln!("ExportTransactions error! {}", e);
+                        continue;
+                    },
+                };
+                let result = if args.json {
+                    write_json_file(&args.output_file, &ledger)
+                } else {
+                    write_ledger_to_csv_file(&ledger, args.output_file.clone())
+                };
+                match result {
+                    Ok(()) => println!(
+                        "Exported {} transaction(s) to {}",
+                        ledger.len(),
+                        args.output_file.display()
+                    ),
+                    Err(e) => eprintln!("ExportTransactions error! {}", e),
+                }
+            },
+            CreateAccount(args) => match output_service.create_account(args.name).await {
+                Ok(account) => match output_service.get_account_address(account.id).await {
+                    Ok(address) => println!(
+                        "Created account {} '{}' with address {}",
+                        account.id,
+                        account.name,
+                        address.to_hex()
+                    ),
+                    Err(e) => eprintln!("CreateAccount error! {}", e),
+                },
+                Err(e) => eprintln!("CreateAccount error! {}", e),
+            },
+            ListAccounts => match output_service.get_accounts().await {
+                Ok(accounts) => {
+                    for account in accounts {
+                        let address = output_service.get_account_address(account.id).await;
+                        let balance = output_service.get_account_balance(account.id).await;
+                        match (address, balance) {
+                            (Ok(address), Ok(balance)) => {
+                                println!("{}. {} ({})", account.id, account.name, address.to_hex());
+                                println!("   {}", balance);
+                            },
+                            (Err(e), _) | (_, Err(e)) => eprintln!("ListAccounts error! {}", e),
+                        }
+                    }
+                },
+                Err(e) => eprintln!("ListAccounts error! {}", e),
+            },
+            BackupWallet(args) => {
+                let passphrase = match args.passphrase {
+                    Some(passphrase) => passphrase,
+                    None => match get_new_passphrase("Backup passphrase: ", "Confirm backup passphrase: ") {
+                        Ok(passphrase) => passphrase,
+                        Err(e) => {
+                            eprintln!("BackupWallet error! {}", e);
+                            continue;
+                        },
+                    },
+                };
+                match wallet.db.create_backup(&args.output_file, &passphrase) {
+                    Ok(()) => println!("Wallet backup written to {}", args.output_file.display()),
+                    Err(e) => eprintln!("BackupWallet error! {}", e),
+                }
+            },
+            Rescan(args) => {
+                if let Err(e) = rescan(wallet.utxo_scanner_service.clone(), args.from_height, args.to_height).await {
+                    eprintln!("Rescan error! {}", e);
+                }
+            },
+            CreatePaymentRequest(args) => {
+                let expires_at = match args.expires_in.map(chrono::Duration::from_std).transpose() {
+                    Ok(expires_in) => expires_in.map(|expires_in| Utc::now().naive_utc() + expires_in),
+                    Err(e) => {
+                        eprintln!("CreatePaymentRequest error! {}", e);
+                        continue;
+                    },
+                };
+                match transaction_service
+                    .create_payment_request(args.amount, args.memo, expires_at)
+                    .await
+                {
+                    Ok(request) => {
+                        println!("Created payment request {} for {}", request.reference, request.amount);
+                        println!("{}", request.to_uri());
+                        println!("{}", request.to_emoji_uri());
+                    },
+                    Err(e) => eprintln!("CreatePaymentRequest error! {}", e),
+                }
+            },
+            ListPaymentRequests => match transaction_service.get_payment_requests().await {
+                Ok(requests) => {
+                    if requests.is_empty() {
+                        println!("No payment requests");
+                    }
+                    for request in requests {
+                        let expires_at = request
+                            .expires_at
+                            .map(|expires_at| format!(", expires {}", expires_at.format("%Y-%m-%d %H:%M:%S")))
+                            .unwrap_or_default();
+                        let inferred = if request.amount_inferred > MicroMinotari::zero() {
+                            format!(" ({} matched by amount only)", request.amount_inferred)
+                        } else {
+                            String::new()
+                        };
+                        println!(
+                            "{}  {}  received {}{}  {}{}  {}",
+                            request.reference,
+                            request.amount,
+                            request.amount_received,
+                            inferred,
+                            request.status,
+                            expires_at,
+                            request.memo
+                        );
+                    }
+                },
+                Err(e) => eprintln!("ListPaymentRequests error! {}", e),
+            },
+            CancelPaymentRequest(args) => {
+                match transaction_service.cancel_payment_request(args.reference.clone()).await {
+                    Ok(()) => println!("Cancelled payment request {}", args.reference),
+                    Err(e) => eprintln!("CancelPaymentRequest error! {}", e),
+                }
+            },
+            PayPaymentRequest(args) => {
+                let request = match PaymentRequestUri::from_str(&args.uri) {
+                    Ok(request) => request,
+                    Err(e) => {
+                        eprintln!("PayPaymentRequest error! {}", e);
+                        continue;
+                    },
+                };
+                if request
+                    .expires_at
+                    .map_or(false, |expires_at| expires_at <= Utc::now().naive_utc())
+                {
+                    eprintln!(
+                        "PayPaymentRequest error! Payment request {} has expired",
+                        request.reference
+                    );
+                    continue;
+                }
+                let message = request.payment_message();
+                let result = if args.one_sided {
+                    send_one_sided(
+                        transaction_service.clone(),
+                        config.fee_per_gram,
+                        request.amount,
+                        UtxoSelectionCriteria::default(),
+                        request.address,
+                        message,
+                    )
+                    .await
+                } else {
+                    send_tari(
+                        transaction_service.clone(),
+                        config.fee_per_gram,
+                        request.amount,
+                        UtxoSelectionCriteria::default(),
+                        request.address,
+                        message,
+                    )
+                    .await
+                };
+                match result {
+                    Ok(tx_id) => {
+                        debug!(target: LOG_TARGET, "pay-payment-request concluded with tx_id {}", tx_id);
+                        tx_ids.push(tx_id);
+                    },
+                    Err(e) => eprintln!("PayPaymentRequest error! {}", e),
+                }
+            },
+            SchedulePayment(args) => {
+                let start = args.at.unwrap_or_else(Utc::now).naive_utc();
+                let schedule = match (args.at_height, args.every) {
+                    (Some(height), _) => PaymentSchedule::AtHeight(height),
+                    (None, Some(interval)) => PaymentSchedule::Recurring { start, interval },
+                    (None, None) => PaymentSchedule::AtTime(start),
+                };
+                match transaction_service
+                    .create_scheduled_payment(
+                        args.destination,
+                        args.amount,
+                        config.fee_per_gram * uT,
+                        args.message,
+                        args.one_sided,
+                        schedule,
+                        args.max_runs,
+                    )
+                    .await
+                {
+                    Ok(payment) => println!("Created scheduled payment {}", payment),
+                    Err(e) => eprintln!("SchedulePayment error! {}", e),
+                }
+            },
+            ListScheduledPayments => match transaction_service.get_scheduled_payments().await {
+                Ok(payments) => {
+                    if payments.is_empty() {
+                        println!("No scheduled payments");
+                    }
+                    for payment in payments {
+                        let next_run = payment
+                            .next_run_at
+                            .map(|next_run_at| format!(", next {}", next_run_at.format("%Y-%m-%d %H:%M:%S")))
+                            .unwrap_or_default();
+                        let last_error = payment
+                            .last_error
+                            .as_ref()
+                            .map(|e| format!(", last error: {}", e))
+                            .unwrap_or_default();
+                        println!(
+                            "{}  sent {} time(s){}{}",
+                            payment, payment.run_count, next_run, last_error
+                        );
+                    }
+                },
+                Err(e) => eprintln!("ListScheduledPayments error! {}", e),
+            },
+            CancelScheduledPayment(args) => match transaction_service.cancel_scheduled_payment(args.id).await {
+                Ok(()) => println!("Cancelled scheduled payment #{}", args.id),
+                Err(e) => eprintln!("CancelScheduledPayment error! {}", e),
+            },
+            CreateVault(args) => match transaction_service
+                .create_vault(
+                    args.amount,
+                    args.recovery_public_key.into(),
+                    args.lock_blocks,
+                    config.fee_per_gram * uT,
+                    args.message,
+                )
+                .await
+            {
+                Ok(vault) => {
+                    println!("Created vault {}", vault);
+                    tx_ids.push(vault.tx_id);
+                },
+                Err(e) => eprintln!("CreateVault error! {}", e),
+            },
+            ListVaults => match transaction_service.get_vaults().await {
+                Ok(vaults) => {
+                    if vaults.is_empty() {
+                        println!("No vaults");
+                    }
+                    for vault in vaults {
+                        let spend_tx = vault
+                            .spend_tx_id
+                            .map(|tx_id| format!(", spent in transaction {}", tx_id))
+                            .unwrap_or_default();
+                        println!(
+                            "{}  recovery key {}{}",
+                            vault,
+                            vault.recovery_public_key.to_hex(),
+                            spend_tx
+                        );
+                    }
+                },
+                Err(e) => eprintln!("ListVaults error! {}", e),
+            },
+            SpendVault(args) => match transaction_service.spend_vault(args.id, config.fee_per_gram * uT).await {
+                Ok(tx_id) => {
+                    println!("Spending vault #{} in transaction {}", args.id, tx_id);
+                    tx_ids.push(tx_id);
+                },
+                Err(e) => eprintln!("SpendVault error! {}", e),
+            },
         }
     }
 
@@ -1089,6 +1561,45 @@ fn write_utxos_to_csv_file(utxos: Vec<(WalletOutput, Commitment)>, file_path: Pa
     }
     Ok(())
 }
+
+fn write_ledger_to_csv_file(entries: &[LedgerEntry], file_path: PathBuf) -> Result<(), CommandError> {
+    let file = File::create(file_path).map_err(|e| CommandError::CSVFile(e.to_string()))?;
+    let mut csv_file = LineWriter::new(file);
+    writeln!(
+        csv_file,
+        r##""date","tx_id","direction","status","amount","fee","net_amount","counterparty","label","tags","confirmation_height","running_balance""##
+    )
+    .map_err(|e| CommandError::CSVFile(e.to_string()))?;
+    // Labels and tags are user input, so quotes in them are escaped by doubling them
+    let escape = |s: &str| s.replace('"', "\"\"");
+    for entry in entries {
+        writeln!(
+            csv_file,
+            r##""{}","{}","{}","{}","{}","{}","{}","{}","{}","{}","{}","{}""##,
+            entry.date.format("%Y-%m-%d %H:%M:%S"),
+            entry.tx_id,
+            entry.direction,
+            entry.status,
+            entry.amount,
+            entry.fee,
+            entry.net_amount,
+            entry.counterparty,
+            escape(entry.label.as_deref().unwrap_or_default()),
+            escape(&entry.tags.join(" ")),
+            entry.confirmation_height.map(|h| h.to_string()).unwrap_or_default(),
+            entry.running_balance
+        )
+        .map_err(|e| CommandError::CSVFile(e.to_string()))?;
+    }
+    Ok(())
+}
+
+fn commitments_from_bytes(commitments: &[Vec<u8>]) -> Result<Vec<Commitment>, CommandError> {
+    commitments
+        .iter()
+        .map(|c| Commitment::from_bytes(c).map_err(CommandError::from))
+        .collect()
+}
 #[allow(dead_code)]
 fn write_json_file<P: AsRef<Path>, T: Serialize>(path: P, data: &T) -> Result<(), CommandError> {
     fs::create_dir_all(path.as_ref().parent().unwrap()).map_err(|e| CommandError::JsonFile(e.to_string()))?;
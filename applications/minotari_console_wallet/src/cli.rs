@@ -29,10 +29,14 @@ use std::{
 use chrono::{DateTime, Utc};
 use clap::{Args, Parser, Subcommand};
 use minotari_app_utilities::{common_cli_args::CommonCliArgs, utilities::UniPublicKey};
+use minotari_wallet::output_manager_service::UtxoSelectionOrdering;
 use tari_common::configuration::{ConfigOverrideProvider, Network};
 use tari_common_types::tari_address::TariAddress;
 use tari_comms::multiaddr::Multiaddr;
-use tari_core::transactions::{tari_amount, tari_amount::MicroMinotari};
+use tari_core::{
+    covenants::Covenant,
+    transactions::{tari_amount, tari_amount::MicroMinotari},
+};
 use tari_key_manager::SeedWords;
 use tari_utilities::{
     hex::{Hex, HexError},
@@ -65,6 +69,13 @@ pub struct Cli {
     /// Supply the optional file name to save the wallet seed words into
     #[clap(long, aliases = &["seed_words_file_name", "seed-words-file"], parse(from_os_str))]
     pub seed_words_file_name: Option<PathBuf>,
+    /// Restore the wallet from an encrypted backup file before starting. The wallet database must not exist yet.
+    #[clap(long, parse(from_os_str))]
+    pub restore_backup: Option<PathBuf>,
+    /// Supply the passphrase of the wallet backup being restored. As with the wallet password, prefer the env var over
+    /// the command line.
+    #[clap(long, env = "MINOTARI_WALLET_BACKUP_PASSPHRASE", hide_env_values = true)]
+    pub backup_passphrase: Option<SafePassword>,
     /// Run in non-interactive mode, with no UI.
     #[clap(short, long, alias = "non-interactive")]
     pub non_interactive_mode: bool,
@@ -84,6 +95,10 @@ pub struct Cli {
     pub grpc_enabled: bool,
     #[clap(long, env = "MINOTARI_WALLET_GRPC_ADDRESS")]
     pub grpc_address: Option<String>,
+    #[clap(long, env = "MINOTARI_WALLET_ENABLE_JSON_RPC")]
+    pub json_rpc_enabled: bool,
+    #[clap(long, env = "MINOTARI_WALLET_JSON_RPC_ADDRESS")]
+    pub json_rpc_address: Option<String>,
     #[clap(subcommand)]
     pub command2: Option<CliCommands>,
     #[clap(long, alias = "profile")]
@@ -106,6 +121,14 @@ impl ConfigOverrideProvider for Cli {
         } else {
             // GRPC is disabled
         }
+        if let Some(ref addr) = self.json_rpc_address {
+            overrides.push(("wallet.json_rpc_enabled".to_string(), "true".to_string()));
+            overrides.push(("wallet.json_rpc_address".to_string(), addr.clone()));
+        } else if self.json_rpc_enabled {
+            overrides.push(("wallet.json_rpc_enabled".to_string(), "true".to_string()));
+        } else {
+            // JSON-RPC is disabled
+        }
         overrides
     }
 }
@@ -134,6 +157,26 @@ pub enum CliCommands {
     RevalidateWalletDb,
     HashGrpcPassword(HashPasswordArgs),
     RegisterValidatorNode(RegisterValidatorNodeArgs),
+    FreezeUtxos(FreezeUtxosArgs),
+    UnfreezeUtxos(FreezeUtxosArgs),
+    ListFrozenUtxos,
+    LabelTransaction(LabelTransactionArgs),
+    LabelOutput(LabelOutputArgs),
+    ExportTransactions(ExportTransactionsArgs),
+    CreateAccount(CreateAccountArgs),
+    ListAccounts,
+    BackupWallet(BackupWalletArgs),
+    Rescan(RescanArgs),
+    CreatePaymentRequest(CreatePaymentRequestArgs),
+    ListPaymentRequests,
+    CancelPaymentRequest(CancelPaymentRequestArgs),
+    PayPaymentRequest(PayPaymentRequestArgs),
+    SchedulePayment(SchedulePaymentArgs),
+    ListScheduledPayments,
+    CancelScheduledPayment(CancelScheduledPaymentArgs),
+    CreateVault(CreateVaultArgs),
+    ListVaults,
+    SpendVault(SpendVaultArgs),
 }
 
 #[derive(Debug, Args, Clone)]
@@ -147,6 +190,16 @@ pub struct SendMinotariArgs {
     pub destination: TariAddress,
     #[clap(short, long, default_value = "<No message>")]
     pub message: String,
+    /// The UTXO selection strategy: default, smallest, largest, branch-and-bound, privacy or minimise-fee
+    #[clap(long, default_value = "default")]
+    pub utxo_selection: UtxoSelectionOrdering,
+    /// Name of the account that funds the transaction. Defaults to the default account.
+    #[clap(long)]
+    pub account: Option<String>,
+    /// A covenant, in covenant text, that restricts how the recipient can spend the output. Only one-sided sends can
+    /// carry a covenant.
+    #[clap(long)]
+    pub covenant: Option<Covenant>,
 }
 
 #[derive(Debug, Args, Clone)]
@@ -154,6 +207,9 @@ pub struct BurnMinotariArgs {
     pub amount: MicroMinotari,
     #[clap(short, long, default_value = "Burn funds")]
     pub message: String,
+    /// The UTXO selection strategy: default, smallest, largest, branch-and-bound, privacy or minimise-fee
+    #[clap(long, default_value = "default")]
+    pub utxo_selection: UtxoSelectionOrdering,
 }
 
 #[derive(Debug, Args, Clone)]
@@ -281,6 +337,145 @@ pub struct ClaimShaAtomicSwapRefundArgs {
     pub message: String,
 }
 
+#[derive(Debug, Args, Clone)]
+pub struct FreezeUtxosArgs {
+    /// The hex encoded commitments of the UTXOs
+    #[clap(parse(try_from_str = parse_hex), required = true)]
+    pub commitments: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct LabelTransactionArgs {
+    pub tx_id: u64,
+    /// A short description of the transaction, e.g. an invoice reference. Omit to remove the label.
+    #[clap(short, long)]
+    pub label: Option<String>,
+    /// A tag used to group transactions, can be given multiple times. Replaces any existing tags.
+    #[clap(short, long)]
+    pub tag: Vec<String>,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct LabelOutputArgs {
+    /// The hex encoded commitment of the output
+    #[clap(parse(try_from_str = parse_hex))]
+    pub commitment: Vec<u8>,
+    /// A short description of the output. Omit to remove the label.
+    #[clap(short, long)]
+    pub label: Option<String>,
+    /// A tag used to group outputs, can be given multiple times. Replaces any existing tags.
+    #[clap(short, long)]
+    pub tag: Vec<String>,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct ExportTransactionsArgs {
+    #[clap(short, long)]
+    pub output_file: PathBuf,
+    /// Write the ledger as JSON instead of CSV
+    #[clap(long)]
+    pub json: bool,
+    /// Only export transactions whose label contains this text
+    #[clap(short, long, default_value = "")]
+    pub label: String,
+    /// Only export transactions that have this tag, can be given multiple times
+    #[clap(short, long)]
+    pub tag: Vec<String>,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct CreateAccountArgs {
+    pub name: String,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct BackupWalletArgs {
+    #[clap(short, long)]
+    pub output_file: PathBuf,
+    /// The passphrase that protects the backup. Prompted for if not given.
+    #[clap(long, env = "MINOTARI_WALLET_BACKUP_PASSPHRASE", hide_env_values = true)]
+    pub passphrase: Option<SafePassword>,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct RescanArgs {
+    /// The first block height to rescan. Defaults to the wallet birthday.
+    #[clap(long)]
+    pub from_height: Option<u64>,
+    /// The last block height to rescan. Defaults to the current chain tip.
+    #[clap(long)]
+    pub to_height: Option<u64>,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct CreatePaymentRequestArgs {
+    pub amount: MicroMinotari,
+    /// A description shown to the payer, e.g. the order number
+    #[clap(short, long, default_value = "")]
+    pub memo: String,
+    /// Number of seconds after which the request expires. Requests do not expire if omitted.
+    #[clap(long, parse(try_from_str = parse_duration))]
+    pub expires_in: Option<Duration>,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct CancelPaymentRequestArgs {
+    pub reference: String,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct PayPaymentRequestArgs {
+    /// The `tari://` payment request URI
+    pub uri: String,
+    /// Pay with a one-sided transaction. The payee can then only match the payment by its amount.
+    #[clap(long)]
+    pub one_sided: bool,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct SchedulePaymentArgs {
+    pub amount: MicroMinotari,
+    pub destination: TariAddress,
+    #[clap(short, long, default_value = "<No message>")]
+    pub message: String,
+    /// The time of the (first) payment, e.g. `2023-10-01T09:00:00Z`. Defaults to now.
+    #[clap(long, parse(try_from_str = parse_start_time), conflicts_with = "at_height")]
+    pub at: Option<DateTime<Utc>>,
+    /// Send the payment once the chain reaches this height
+    #[clap(long, conflicts_with = "every")]
+    pub at_height: Option<u64>,
+    /// Repeat the payment every given number of seconds
+    #[clap(long, parse(try_from_str = parse_duration))]
+    pub every: Option<Duration>,
+    /// Stop a recurring payment after this many payments. Recurring payments continue until cancelled if omitted.
+    #[clap(long, requires = "every")]
+    pub max_runs: Option<u64>,
+    #[clap(long)]
+    pub one_sided: bool,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct CancelScheduledPaymentArgs {
+    pub id: u64,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct CreateVaultArgs {
+    pub amount: MicroMinotari,
+    /// The public key of the address of the cold wallet, which can spend the vault at any time
+    pub recovery_public_key: UniPublicKey,
+    /// The number of blocks before the wallet can spend the vault
+    #[clap(long)]
+    pub lock_blocks: u64,
+    #[clap(short, long, default_value = "Vault deposit")]
+    pub message: String,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct SpendVaultArgs {
+    pub id: u64,
+}
+
 #[derive(Debug, Args, Clone)]
 pub struct HashPasswordArgs {
     /// If true, only output the hashed password and the salted password. Otherwise a usage explanation is output.
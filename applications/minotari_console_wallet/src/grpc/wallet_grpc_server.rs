@@ -20,8 +20,12 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::convert::{TryFrom, TryInto};
+use std::{
+    convert::{TryFrom, TryInto},
+    time::Duration,
+};
 
+use chrono::{NaiveDateTime, Utc};
 use futures::{
     channel::mpsc::{self, Sender},
     future,
@@ -34,6 +38,11 @@ use minotari_app_grpc::{
         self,
         payment_recipient::PaymentType,
         wallet_server,
+        BalanceHistoryRequest,
+        CancelPaymentRequestRequest,
+        CancelPaymentRequestResponse,
+        CancelScheduledPaymentRequest,
+        CancelScheduledPaymentResponse,
         CheckConnectivityResponse,
         ClaimHtlcRefundRequest,
         ClaimHtlcRefundResponse,
@@ -42,10 +51,21 @@ use minotari_app_grpc::{
         CoinSplitRequest,
         CoinSplitResponse,
         CommitmentSignature,
+        CreateAccountRequest,
+        CreateAccountResponse,
         CreateBurnTransactionRequest,
         CreateBurnTransactionResponse,
+        CreatePaymentRequestRequest,
+        CreatePaymentRequestResponse,
+        CreateScheduledPaymentRequest,
+        CreateScheduledPaymentResponse,
         CreateTemplateRegistrationRequest,
         CreateTemplateRegistrationResponse,
+        CreateVaultRequest,
+        CreateVaultResponse,
+        FreezeUtxosRequest,
+        FreezeUtxosResponse,
+        FrozenUtxo,
         GetAddressResponse,
         GetBalanceRequest,
         GetBalanceResponse,
@@ -54,52 +74,85 @@ use minotari_app_grpc::{
         GetCompletedTransactionsRequest,
         GetCompletedTransactionsResponse,
         GetConnectivityRequest,
+        GetFrozenUtxosResponse,
         GetIdentityRequest,
         GetIdentityResponse,
         GetTransactionInfoRequest,
         GetTransactionInfoResponse,
+        GetTransactionLedgerRequest,
+        GetTransactionLedgerResponse,
         GetUnspentAmountsResponse,
         GetVersionRequest,
         GetVersionResponse,
         ImportUtxosRequest,
         ImportUtxosResponse,
+        LedgerEntry,
+        ListAccountsResponse,
+        ListPaymentRequestsResponse,
+        ListScheduledPaymentsResponse,
+        ListVaultsResponse,
         RegisterValidatorNodeRequest,
         RegisterValidatorNodeResponse,
+        RescanRequest,
+        RescanResponse,
         RevalidateRequest,
         RevalidateResponse,
         SendShaAtomicSwapRequest,
         SendShaAtomicSwapResponse,
         SetBaseNodeRequest,
         SetBaseNodeResponse,
+        SetOutputLabelsRequest,
+        SetOutputLabelsResponse,
+        SetTransactionLabelsRequest,
+        SetTransactionLabelsResponse,
+        SpendVaultRequest,
+        SpendVaultResponse,
         TransactionDirection,
         TransactionEvent,
         TransactionEventRequest,
         TransactionEventResponse,
         TransactionInfo,
         TransactionStatus,
+        TransactionVolumeRequest,
+        TransactionVolumeResponse,
         TransferRequest,
         TransferResponse,
         TransferResult,
+        UnfreezeUtxosRequest,
+        UnfreezeUtxosResponse,
     },
 };
 use minotari_wallet::{
     connectivity_service::{OnlineStatus, WalletConnectivityInterface},
     error::WalletStorageError,
-    output_manager_service::{handle::OutputManagerHandle, UtxoSelectionCriteria},
+    output_manager_service::{
+        handle::{OutputManagerEvent, OutputManagerHandle},
+        service::Balance,
+        storage::models::{Account, AccountId, BalanceSnapshot},
+        UtxoSelectionCriteria,
+        UtxoSelectionOrdering,
+    },
     transaction_service::{
+        analytics::{volume_per_period, PeriodVolume, SECONDS_PER_DAY},
         handle::TransactionServiceHandle,
-        storage::models::{self, WalletTransaction},
+        ledger::build_ledger,
+        payment_request::{PaymentRequest, PaymentRequestStatus},
+        scheduled_payment::{PaymentSchedule, ScheduledPayment, ScheduledPaymentStatus},
+        storage::models::{self, LabelTarget, TransactionLabels, WalletTransaction},
+        vault::{Vault, VaultStatus},
     },
+    utxo_scanner_service::handle::UtxoScannerEvent,
     WalletSqlite,
 };
 use tari_common_types::{
     tari_address::TariAddress,
     transaction::TxId,
-    types::{BlockHash, PublicKey, Signature},
+    types::{BlockHash, Commitment, PublicKey, Signature},
 };
 use tari_comms::{multiaddr::Multiaddr, types::CommsPublicKey, CommsNode};
 use tari_core::{
     consensus::{ConsensusBuilderError, ConsensusConstants, ConsensusManager},
+    covenants::Covenant,
     transactions::{
         tari_amount::{MicroMinotari, T},
         transaction_components::{
@@ -157,6 +210,41 @@ impl WalletGrpcServer {
         self.wallet.output_manager_service.clone()
     }
 
+    /// Resolves an account name to its id. An empty name selects no account.
+    async fn find_account(&self, name: &str) -> Result<Option<AccountId>, Status> {
+        if name.is_empty() {
+            return Ok(None);
+        }
+        let accounts = self
+            .get_output_manager_service()
+            .get_accounts()
+            .await
+            .map_err(|e| Status::internal(format!("Could not fetch accounts: {}", e)))?;
+        accounts
+            .into_iter()
+            .find(|a| a.name == name)
+            .map(|a| Some(a.id))
+            .ok_or_else(|| Status::not_found(format!("Account `{}` does not exist", name)))
+    }
+
+    async fn convert_account(&self, account: Account) -> Result<tari_rpc::Account, Status> {
+        let mut output_service = self.get_output_manager_service();
+        let address = output_service
+            .get_account_address(account.id)
+            .await
+            .map_err(|e| Status::internal(format!("Could not get account address: {}", e)))?;
+        let balance = output_service
+            .get_account_balance(account.id)
+            .await
+            .map_err(|e| Status::internal(format!("Could not get account balance: {}", e)))?;
+        Ok(tari_rpc::Account {
+            id: account.id,
+            name: account.name,
+            address: address.to_bytes().to_vec(),
+            balance: Some(convert_balance(balance)),
+        })
+    }
+
     fn comms(&self) -> &CommsNode {
         &self.wallet.comms
     }
@@ -177,6 +265,9 @@ impl WalletGrpcServer {
 #[tonic::async_trait]
 impl wallet_server::Wallet for WalletGrpcServer {
     type GetCompletedTransactionsStream = mpsc::Receiver<Result<GetCompletedTransactionsResponse, Status>>;
+    type RescanStream = mpsc::Receiver<Result<RescanResponse, Status>>;
+    type StreamBalanceHistoryStream = mpsc::Receiver<Result<tari_rpc::BalanceSnapshot, Status>>;
+    type StreamPaymentRequestsStream = mpsc::Receiver<Result<tari_rpc::PaymentRequest, Status>>;
     type StreamTransactionEventsStream = mpsc::Receiver<Result<TransactionEventResponse, Status>>;
 
     async fn get_version(&self, _: Request<GetVersionRequest>) -> Result<Response<GetVersionResponse>, Status> {
@@ -260,21 +351,18 @@ impl wallet_server::Wallet for WalletGrpcServer {
         Ok(Response::new(SetBaseNodeResponse {}))
     }
 
-    async fn get_balance(&self, _request: Request<GetBalanceRequest>) -> Result<Response<GetBalanceResponse>, Status> {
+    async fn get_balance(&self, request: Request<GetBalanceRequest>) -> Result<Response<GetBalanceResponse>, Status> {
+        let account = self.find_account(&request.into_inner().account).await?;
         let mut output_service = self.get_output_manager_service();
-        let balance = match output_service.get_balance().await {
+        let balance = match account {
+            Some(account_id) => output_service.get_account_balance(account_id).await,
+            None => output_service.get_balance().await,
+        };
+        let balance = match balance {
             Ok(b) => b,
             Err(e) => return Err(Status::not_found(format!("GetBalance error! {}", e))),
         };
-        Ok(Response::new(GetBalanceResponse {
-            available_balance: balance
-                .available_balance
-                .saturating_sub(balance.time_locked_balance.unwrap_or_default())
-                .0,
-            pending_incoming_balance: balance.pending_incoming_balance.0,
-            pending_outgoing_balance: balance.pending_outgoing_balance.0,
-            timelocked_balance: balance.time_locked_balance.unwrap_or_default().0,
-        }))
+        Ok(Response::new(convert_balance(balance)))
     }
 
     async fn get_unspent_amounts(
@@ -340,13 +428,14 @@ impl wallet_server::Wallet for WalletGrpcServer {
             .ok_or_else(|| Status::internal("Request is malformed".to_string()))?;
         let address = TariAddress::from_hex(&message.address)
             .map_err(|_| Status::internal("Destination address is malformed".to_string()))?;
+        let selection_criteria = convert_utxo_selection_ordering(message.utxo_selection_ordering)?;
 
         let mut transaction_service = self.get_transaction_service();
         let response = match transaction_service
             .send_sha_atomic_swap_transaction(
                 address.clone(),
                 message.amount.into(),
-                UtxoSelectionCriteria::default(),
+                selection_criteria,
                 message.fee_per_gram.into(),
                 message.message,
             )
@@ -494,6 +583,7 @@ impl wallet_server::Wallet for WalletGrpcServer {
 
     async fn transfer(&self, request: Request<TransferRequest>) -> Result<Response<TransferResponse>, Status> {
         let message = request.into_inner();
+        let account = self.find_account(&message.account).await?.unwrap_or_default();
         let recipients = message
             .recipients
             .into_iter()
@@ -501,6 +591,17 @@ impl wallet_server::Wallet for WalletGrpcServer {
             .map(|(idx, dest)| -> Result<_, String> {
                 let address = TariAddress::from_hex(&dest.address)
                     .map_err(|_| format!("Destination address at index {} is malformed", idx))?;
+                let selection_criteria = convert_utxo_selection_ordering(dest.utxo_selection_ordering)
+                    .map_err(|_| format!("UTXO selection ordering at index {} is invalid", idx))?
+                    .with_account(account);
+                let covenant = Covenant::from_text(&dest.covenant)
+                    .map_err(|e| format!("Covenant at index {} is invalid: {}", idx, e))?;
+                if !covenant.is_empty() && dest.payment_type == PaymentType::StandardMimblewimble as i32 {
+                    return Err(format!(
+                        "Covenant at index {} can only be attached to a one-sided payment",
+                        idx
+                    ));
+                }
                 Ok((
                     dest.address,
                     address,
@@ -508,13 +609,17 @@ impl wallet_server::Wallet for WalletGrpcServer {
                     dest.fee_per_gram,
                     dest.message,
                     dest.payment_type,
+                    selection_criteria,
+                    covenant,
                 ))
             })
             .collect::<Result<Vec<_>, _>>()
             .map_err(Status::invalid_argument)?;
 
         let mut transfers = Vec::new();
-        for (hex_address, address, amount, fee_per_gram, message, payment_type) in recipients {
+        for (hex_address, address, amount, fee_per_gram, message, payment_type, selection_criteria, covenant) in
+            recipients
+        {
             let mut transaction_service = self.get_transaction_service();
             transfers.push(async move {
                 (
@@ -524,7 +629,7 @@ impl wallet_server::Wallet for WalletGrpcServer {
                             .send_transaction(
                                 address,
                                 amount.into(),
-                                UtxoSelectionCriteria::default(),
+                                selection_criteria,
                                 OutputFeatures::default(),
                                 fee_per_gram.into(),
                                 message,
@@ -535,8 +640,9 @@ impl wallet_server::Wallet for WalletGrpcServer {
                             .send_one_sided_transaction(
                                 address,
                                 amount.into(),
-                                UtxoSelectionCriteria::default(),
+                                selection_criteria,
                                 OutputFeatures::default(),
+                                covenant,
                                 fee_per_gram.into(),
                                 message,
                             )
@@ -546,8 +652,9 @@ impl wallet_server::Wallet for WalletGrpcServer {
                             .send_one_sided_to_stealth_address_transaction(
                                 address,
                                 amount.into(),
-                                UtxoSelectionCriteria::default(),
+                                selection_criteria,
                                 OutputFeatures::default(),
+                                covenant,
                                 fee_per_gram.into(),
                                 message,
                             )
@@ -591,13 +698,14 @@ impl wallet_server::Wallet for WalletGrpcServer {
         request: Request<CreateBurnTransactionRequest>,
     ) -> Result<Response<CreateBurnTransactionResponse>, Status> {
         let message = request.into_inner();
+        let selection_criteria = convert_utxo_selection_ordering(message.utxo_selection_ordering)?;
 
         let mut transaction_service = self.get_transaction_service();
         debug!(target: LOG_TARGET, "Trying to burn {} Minotari", message.amount);
         let response = match transaction_service
             .burn_tari(
                 message.amount.into(),
-                UtxoSelectionCriteria::default(),
+                selection_criteria,
                 message.fee_per_gram.into(),
                 message.message,
                 if message.claim_public_key.is_empty() {
@@ -658,12 +766,22 @@ impl wallet_server::Wallet for WalletGrpcServer {
             .map(|tx| tx.into_iter())
             .map_err(|err| Status::unknown(err.to_string()))?;
 
+        let mut labels = self
+            .get_transaction_service()
+            .get_all_transaction_labels()
+            .await
+            .map_err(|err| Status::unknown(err.to_string()))?;
+
         let wallet_pk = self.wallet.comms.node_identity_ref().public_key();
         let wallet_network = self.wallet.network.as_network();
         let wallet_address = TariAddress::new(wallet_pk.clone(), wallet_network);
         let transactions = transactions
             .map(|(tx_id, tx)| match tx {
-                Some(tx) => convert_wallet_transaction_into_transaction_info(tx, &wallet_address),
+                Some(tx) => convert_wallet_transaction_into_transaction_info(
+                    tx,
+                    &wallet_address,
+                    labels.remove(&tx_id).unwrap_or_default(),
+                ),
                 None => TransactionInfo::not_found(tx_id),
             })
             .collect();
@@ -752,21 +870,41 @@ impl wallet_server::Wallet for WalletGrpcServer {
 
     async fn get_completed_transactions(
         &self,
-        _request: Request<GetCompletedTransactionsRequest>,
+        request: Request<GetCompletedTransactionsRequest>,
     ) -> Result<Response<Self::GetCompletedTransactionsStream>, Status> {
         debug!(
             target: LOG_TARGET,
             "Incoming GRPC request for GetAllCompletedTransactions"
         );
+        let message = request.into_inner();
+        let account_tx_ids = match self.find_account(&message.account).await? {
+            Some(account_id) => Some(
+                self.get_output_manager_service()
+                    .get_account_transaction_ids(account_id)
+                    .await
+                    .map_err(|err| Status::internal(format!("Could not fetch account transactions: {:?}", err)))?,
+            ),
+            None => None,
+        };
         let mut transaction_service = self.get_transaction_service();
         let transactions = transaction_service
             .get_completed_transactions()
             .await
             .map_err(|err| Status::not_found(format!("No completed transactions found: {:?}", err)))?;
+        let mut labels = transaction_service
+            .get_all_transaction_labels()
+            .await
+            .map_err(|err| Status::internal(format!("Could not fetch transaction labels: {:?}", err)))?;
+        let transactions = transactions
+            .into_iter()
+            .filter(|(tx_id, _)| account_tx_ids.as_ref().map_or(true, |ids| ids.contains(tx_id)))
+            .map(|(tx_id, txn)| (txn, labels.remove(&tx_id).unwrap_or_default()))
+            .filter(|(_, tx_labels)| tx_labels.matches(&message.label, &message.tags))
+            .collect::<Vec<_>>();
 
         let (mut sender, receiver) = mpsc::channel(transactions.len());
         task::spawn(async move {
-            for (_, txn) in transactions {
+            for (txn, tx_labels) in transactions {
                 let response = GetCompletedTransactionsResponse {
                     transaction: Some(TransactionInfo {
                         tx_id: txn.tx_id.into(),
@@ -785,6 +923,8 @@ impl wallet_server::Wallet for WalletGrpcServer {
                             .get_signature()
                             .to_vec(),
                         message: txn.message,
+                        label: tx_labels.label.unwrap_or_default(),
+                        tags: tx_labels.tags,
                     }),
                 };
                 match sender.send(Ok(response)).await {
@@ -1045,6 +1185,468 @@ impl wallet_server::Wallet for WalletGrpcServer {
         };
         Ok(Response::new(response))
     }
+
+    async fn freeze_utxos(
+        &self,
+        request: Request<FreezeUtxosRequest>,
+    ) -> Result<Response<FreezeUtxosResponse>, Status> {
+        let commitments = convert_commitments(request.into_inner().commitments)?;
+        let mut output_service = self.get_output_manager_service();
+        output_service
+            .freeze_outputs(commitments)
+            .await
+            .map_err(|e| Status::invalid_argument(format!("FreezeUtxos error! {}", e)))?;
+        Ok(Response::new(FreezeUtxosResponse {}))
+    }
+
+    async fn unfreeze_utxos(
+        &self,
+        request: Request<UnfreezeUtxosRequest>,
+    ) -> Result<Response<UnfreezeUtxosResponse>, Status> {
+        let commitments = convert_commitments(request.into_inner().commitments)?;
+        let mut output_service = self.get_output_manager_service();
+        output_service
+            .unfreeze_outputs(commitments)
+            .await
+            .map_err(|e| Status::invalid_argument(format!("UnfreezeUtxos error! {}", e)))?;
+        Ok(Response::new(UnfreezeUtxosResponse {}))
+    }
+
+    async fn get_frozen_utxos(&self, _: Request<tari_rpc::Empty>) -> Result<Response<GetFrozenUtxosResponse>, Status> {
+        let mut output_service = self.get_output_manager_service();
+        let outputs = output_service
+            .get_frozen_outputs()
+            .await
+            .map_err(|e| Status::internal(format!("GetFrozenUtxos error! {}", e)))?;
+        Ok(Response::new(GetFrozenUtxosResponse {
+            utxos: outputs
+                .into_iter()
+                .map(|o| FrozenUtxo {
+                    commitment: o.commitment.to_vec(),
+                    value: o.wallet_output.value.as_u64(),
+                    status: o.status.to_string(),
+                    mined_height: o.mined_height.unwrap_or_default(),
+                })
+                .collect(),
+        }))
+    }
+
+    async fn set_transaction_labels(
+        &self,
+        request: Request<SetTransactionLabelsRequest>,
+    ) -> Result<Response<SetTransactionLabelsResponse>, Status> {
+        let message = request.into_inner();
+        let labels = TransactionLabels::new(Some(message.label), message.tags);
+        let mut transaction_service = self.get_transaction_service();
+        transaction_service
+            .set_labels(LabelTarget::Transaction(message.tx_id.into()), labels)
+            .await
+            .map_err(|e| Status::invalid_argument(format!("SetTransactionLabels error! {}", e)))?;
+        Ok(Response::new(SetTransactionLabelsResponse {}))
+    }
+
+    async fn set_output_labels(
+        &self,
+        request: Request<SetOutputLabelsRequest>,
+    ) -> Result<Response<SetOutputLabelsResponse>, Status> {
+        let message = request.into_inner();
+        let commitment = Commitment::from_bytes(&message.commitment)
+            .map_err(|_| Status::invalid_argument("Malformed commitment".to_string()))?;
+        let labels = TransactionLabels::new(Some(message.label), message.tags);
+        let mut transaction_service = self.get_transaction_service();
+        transaction_service
+            .set_labels(LabelTarget::Output(commitment), labels)
+            .await
+            .map_err(|e| Status::invalid_argument(format!("SetOutputLabels error! {}", e)))?;
+        Ok(Response::new(SetOutputLabelsResponse {}))
+    }
+
+    async fn get_transaction_ledger(
+        &self,
+        request: Request<GetTransactionLedgerRequest>,
+    ) -> Result<Response<GetTransactionLedgerResponse>, Status> {
+        let message = request.into_inner();
+        let mut transaction_service = self.get_transaction_service();
+        let transactions = transaction_service
+            .get_completed_transactions()
+            .await
+            .map_err(|e| Status::internal(format!("GetTransactionLedger error! {}", e)))?;
+        let labels = transaction_service
+            .get_all_transaction_labels()
+            .await
+            .map_err(|e| Status::internal(format!("GetTransactionLedger error! {}", e)))?;
+        let entries = build_ledger(transactions.into_values(), &labels, &message.label, &message.tags)
+            .into_iter()
+            .map(|entry| LedgerEntry {
+                date: Some(naive_datetime_to_timestamp(entry.date)),
+                tx_id: entry.tx_id.into(),
+                direction: TransactionDirection::from(entry.direction) as i32,
+                status: TransactionStatus::from(entry.status) as i32,
+                amount: entry.amount,
+                fee: entry.fee,
+                net_amount: entry.net_amount,
+                counterparty: entry.counterparty,
+                label: entry.label.unwrap_or_default(),
+                tags: entry.tags,
+                confirmation_height: entry.confirmation_height.unwrap_or_default(),
+                running_balance: entry.running_balance,
+            })
+            .collect();
+        Ok(Response::new(GetTransactionLedgerResponse { entries }))
+    }
+
+    async fn create_account(
+        &self,
+        request: Request<CreateAccountRequest>,
+    ) -> Result<Response<CreateAccountResponse>, Status> {
+        let mut output_service = self.get_output_manager_service();
+        let account = output_service
+            .create_account(request.into_inner().name)
+            .await
+            .map_err(|e| Status::invalid_argument(format!("CreateAccount error! {}", e)))?;
+        Ok(Response::new(CreateAccountResponse {
+            account: Some(self.convert_account(account).await?),
+        }))
+    }
+
+    async fn list_accounts(&self, _: Request<tari_rpc::Empty>) -> Result<Response<ListAccountsResponse>, Status> {
+        let mut output_service = self.get_output_manager_service();
+        let accounts = output_service
+            .get_accounts()
+            .await
+            .map_err(|e| Status::internal(format!("ListAccounts error! {}", e)))?;
+        let mut converted = Vec::with_capacity(accounts.len());
+        for account in accounts {
+            converted.push(self.convert_account(account).await?);
+        }
+        Ok(Response::new(ListAccountsResponse { accounts: converted }))
+    }
+
+    async fn rescan(&self, request: Request<RescanRequest>) -> Result<Response<Self::RescanStream>, Status> {
+        let message = request.into_inner();
+        let from_height = Some(message.from_height).filter(|h| *h > 0);
+        let to_height = Some(message.to_height).filter(|h| *h > 0);
+
+        let mut utxo_scanner = self.wallet.utxo_scanner_service.clone();
+        let mut events = utxo_scanner.get_event_receiver();
+        utxo_scanner
+            .rescan(from_height, to_height)
+            .await
+            .map_err(|e| Status::invalid_argument(format!("Rescan error! {}", e)))?;
+
+        let (mut sender, receiver) = mpsc::channel(100);
+        task::spawn(async move {
+            loop {
+                let response = match events.recv().await {
+                    Ok(UtxoScannerEvent::RescanProgress {
+                        current_height,
+                        to_height,
+                    }) => Ok(RescanResponse {
+                        current_height,
+                        to_height,
+                        ..Default::default()
+                    }),
+                    Ok(UtxoScannerEvent::RescanCompleted {
+                        to_height,
+                        num_recovered,
+                        value_recovered,
+                        ..
+                    }) => Ok(RescanResponse {
+                        current_height: to_height,
+                        to_height,
+                        is_complete: true,
+                        num_recovered,
+                        value_recovered: value_recovered.as_u64(),
+                    }),
+                    Ok(UtxoScannerEvent::RescanFailed { error }) => Err(Status::internal(error)),
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let is_final = response.as_ref().map(|r| r.is_complete).unwrap_or(true);
+                if sender.send(response).await.is_err() || is_final {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(receiver))
+    }
+
+    async fn create_payment_request(
+        &self,
+        request: Request<CreatePaymentRequestRequest>,
+    ) -> Result<Response<CreatePaymentRequestResponse>, Status> {
+        let message = request.into_inner();
+        let expires_at = match message.expires_in {
+            0 => None,
+            seconds => Some(
+                chrono::Duration::from_std(Duration::from_secs(seconds))
+                    .map(|expires_in| Utc::now().naive_utc() + expires_in)
+                    .map_err(|e| Status::invalid_argument(format!("Invalid expiry: {}", e)))?,
+            ),
+        };
+        let payment_request = self
+            .get_transaction_service()
+            .create_payment_request(message.amount.into(), message.memo, expires_at)
+            .await
+            .map_err(|e| Status::invalid_argument(format!("CreatePaymentRequest error! {}", e)))?;
+        Ok(Response::new(CreatePaymentRequestResponse {
+            request: Some(convert_payment_request(payment_request)),
+        }))
+    }
+
+    async fn list_payment_requests(
+        &self,
+        _: Request<tari_rpc::Empty>,
+    ) -> Result<Response<ListPaymentRequestsResponse>, Status> {
+        let requests = self
+            .get_transaction_service()
+            .get_payment_requests()
+            .await
+            .map_err(|e| Status::internal(format!("ListPaymentRequests error! {}", e)))?;
+        Ok(Response::new(ListPaymentRequestsResponse {
+            requests: requests.into_iter().map(convert_payment_request).collect(),
+        }))
+    }
+
+    async fn cancel_payment_request(
+        &self,
+        request: Request<CancelPaymentRequestRequest>,
+    ) -> Result<Response<CancelPaymentRequestResponse>, Status> {
+        self.get_transaction_service()
+            .cancel_payment_request(request.into_inner().reference)
+            .await
+            .map_err(|e| Status::invalid_argument(format!("CancelPaymentRequest error! {}", e)))?;
+        Ok(Response::new(CancelPaymentRequestResponse {}))
+    }
+
+    async fn stream_payment_requests(
+        &self,
+        _: Request<tari_rpc::Empty>,
+    ) -> Result<Response<Self::StreamPaymentRequestsStream>, Status> {
+        let mut transaction_service = self.get_transaction_service();
+        let mut events = transaction_service.get_event_stream();
+        let (mut sender, receiver) = mpsc::channel(100);
+        task::spawn(async move {
+            loop {
+                let reference = match events.recv().await {
+                    Ok(event) => match &*event {
+                        minotari_wallet::transaction_service::handle::TransactionEvent::PaymentRequestUpdated {
+                            reference,
+                            ..
+                        } => reference.clone(),
+                        _ => continue,
+                    },
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        warn!(target: LOG_TARGET, "Missed {} from Transaction events", n);
+                        continue;
+                    },
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let response = match transaction_service.get_payment_requests().await {
+                    Ok(requests) => match requests.into_iter().find(|r| r.reference == reference) {
+                        Some(request) => Ok(convert_payment_request(request)),
+                        None => continue,
+                    },
+                    Err(e) => Err(Status::internal(format!("StreamPaymentRequests error! {}", e))),
+                };
+                if sender.send(response).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(receiver))
+    }
+
+    async fn create_scheduled_payment(
+        &self,
+        request: Request<CreateScheduledPaymentRequest>,
+    ) -> Result<Response<CreateScheduledPaymentResponse>, Status> {
+        let message = request.into_inner();
+        let destination = TariAddress::from_hex(&message.destination)
+            .map_err(|_| Status::invalid_argument("Destination address is malformed".to_string()))?;
+        let start = match message.start_time {
+            0 => Utc::now().naive_utc(),
+            timestamp => i64::try_from(timestamp)
+                .ok()
+                .and_then(|timestamp| NaiveDateTime::from_timestamp_opt(timestamp, 0))
+                .ok_or_else(|| Status::invalid_argument("Invalid start time".to_string()))?,
+        };
+        let schedule = match (message.start_height, message.interval_secs) {
+            (0, 0) => PaymentSchedule::AtTime(start),
+            (0, interval_secs) => PaymentSchedule::Recurring {
+                start,
+                interval: Duration::from_secs(interval_secs),
+            },
+            (height, 0) => PaymentSchedule::AtHeight(height),
+            _ => {
+                return Err(Status::invalid_argument(
+                    "A payment at a block height cannot be recurring".to_string(),
+                ))
+            },
+        };
+        let max_runs = Some(message.max_runs).filter(|max_runs| *max_runs > 0);
+        let payment = self
+            .get_transaction_service()
+            .create_scheduled_payment(
+                destination,
+                message.amount.into(),
+                message.fee_per_gram.into(),
+                message.message,
+                message.one_sided,
+                schedule,
+                max_runs,
+            )
+            .await
+            .map_err(|e| Status::invalid_argument(format!("CreateScheduledPayment error! {}", e)))?;
+        Ok(Response::new(CreateScheduledPaymentResponse {
+            payment: Some(convert_scheduled_payment(payment)),
+        }))
+    }
+
+    async fn list_scheduled_payments(
+        &self,
+        _: Request<tari_rpc::Empty>,
+    ) -> Result<Response<ListScheduledPaymentsResponse>, Status> {
+        let payments = self
+            .get_transaction_service()
+            .get_scheduled_payments()
+            .await
+            .map_err(|e| Status::internal(format!("ListScheduledPayments error! {}", e)))?;
+        Ok(Response::new(ListScheduledPaymentsResponse {
+            payments: payments.into_iter().map(convert_scheduled_payment).collect(),
+        }))
+    }
+
+    async fn cancel_scheduled_payment(
+        &self,
+        request: Request<CancelScheduledPaymentRequest>,
+    ) -> Result<Response<CancelScheduledPaymentResponse>, Status> {
+        self.get_transaction_service()
+            .cancel_scheduled_payment(request.into_inner().id)
+            .await
+            .map_err(|e| Status::invalid_argument(format!("CancelScheduledPayment error! {}", e)))?;
+        Ok(Response::new(CancelScheduledPaymentResponse {}))
+    }
+
+    async fn stream_balance_history(
+        &self,
+        request: Request<BalanceHistoryRequest>,
+    ) -> Result<Response<Self::StreamBalanceHistoryStream>, Status> {
+        let message = request.into_inner();
+        let from_height = message.from_height;
+        let to_height = Some(message.to_height).filter(|h| *h > 0);
+        let mut output_manager_service = self.get_output_manager_service();
+        // Subscribe before reading the history so that no snapshot recorded in between is missed
+        let mut events = output_manager_service.get_event_stream();
+        let snapshots = output_manager_service
+            .get_balance_history(from_height, to_height)
+            .await
+            .map_err(|e| Status::internal(format!("StreamBalanceHistory error! {}", e)))?;
+
+        let (mut sender, receiver) = mpsc::channel(snapshots.len().max(1) + 100);
+        task::spawn(async move {
+            for snapshot in snapshots {
+                if sender.send(Ok(convert_balance_snapshot(snapshot))).await.is_err() {
+                    return;
+                }
+            }
+            if !message.follow {
+                return;
+            }
+            loop {
+                let snapshot = match events.recv().await {
+                    Ok(event) => match &*event {
+                        OutputManagerEvent::BalanceSnapshotRecorded(snapshot) => snapshot.clone(),
+                        _ => continue,
+                    },
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        warn!(target: LOG_TARGET, "Missed {} from Output Manager events", n);
+                        continue;
+                    },
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if snapshot.height < from_height || to_height.map_or(false, |h| snapshot.height > h) {
+                    continue;
+                }
+                if sender.send(Ok(convert_balance_snapshot(snapshot))).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(receiver))
+    }
+
+    async fn get_transaction_volume(
+        &self,
+        request: Request<TransactionVolumeRequest>,
+    ) -> Result<Response<TransactionVolumeResponse>, Status> {
+        let message = request.into_inner();
+        let period_secs = Some(message.period_seconds)
+            .filter(|p| *p > 0)
+            .unwrap_or(SECONDS_PER_DAY);
+        let since = match message.since {
+            0 => None,
+            since => Some(
+                i64::try_from(since)
+                    .ok()
+                    .and_then(|since| NaiveDateTime::from_timestamp_opt(since, 0))
+                    .ok_or_else(|| Status::invalid_argument("Invalid since timestamp"))?,
+            ),
+        };
+        let transactions = self
+            .get_transaction_service()
+            .get_completed_transactions()
+            .await
+            .map_err(|e| Status::internal(format!("GetTransactionVolume error! {}", e)))?;
+        let periods = volume_per_period(transactions.into_values(), period_secs, since);
+        Ok(Response::new(TransactionVolumeResponse {
+            periods: periods.into_iter().map(convert_period_volume).collect(),
+        }))
+    }
+
+    async fn create_vault(
+        &self,
+        request: Request<CreateVaultRequest>,
+    ) -> Result<Response<CreateVaultResponse>, Status> {
+        let message = request.into_inner();
+        let recovery_public_key = PublicKey::from_bytes(&message.recovery_public_key)
+            .map_err(|_| Status::invalid_argument("Recovery public key is malformed".to_string()))?;
+        let vault = self
+            .get_transaction_service()
+            .create_vault(
+                message.amount.into(),
+                recovery_public_key,
+                message.lock_blocks,
+                message.fee_per_gram.into(),
+                message.message,
+            )
+            .await
+            .map_err(|e| Status::invalid_argument(format!("CreateVault error! {}", e)))?;
+        Ok(Response::new(CreateVaultResponse {
+            vault: Some(convert_vault(vault)),
+        }))
+    }
+
+    async fn list_vaults(&self, _: Request<tari_rpc::Empty>) -> Result<Response<ListVaultsResponse>, Status> {
+        let vaults = self
+            .get_transaction_service()
+            .get_vaults()
+            .await
+            .map_err(|e| Status::internal(format!("ListVaults error! {}", e)))?;
+        Ok(Response::new(ListVaultsResponse {
+            vaults: vaults.into_iter().map(convert_vault).collect(),
+        }))
+    }
+
+    async fn spend_vault(&self, request: Request<SpendVaultRequest>) -> Result<Response<SpendVaultResponse>, Status> {
+        let message = request.into_inner();
+        let tx_id = self
+            .get_transaction_service()
+            .spend_vault(message.id, message.fee_per_gram.into())
+            .await
+            .map_err(|e| Status::invalid_argument(format!("SpendVault error! {}", e)))?;
+        Ok(Response::new(SpendVaultResponse { tx_id: tx_id.as_u64() }))
+    }
 }
 
 async fn handle_completed_tx(
@@ -1083,6 +1685,148 @@ async fn handle_pending_outbound(
     }
 }
 
+fn convert_utxo_selection_ordering(ordering: i32) -> Result<UtxoSelectionCriteria, Status> {
+    let ordering = match tari_rpc::UtxoSelectionOrdering::from_i32(ordering) {
+        Some(tari_rpc::UtxoSelectionOrdering::Default) => UtxoSelectionOrdering::Default,
+        Some(tari_rpc::UtxoSelectionOrdering::SmallestFirst) => UtxoSelectionOrdering::SmallestFirst,
+        Some(tari_rpc::UtxoSelectionOrdering::LargestFirst) => UtxoSelectionOrdering::LargestFirst,
+        Some(tari_rpc::UtxoSelectionOrdering::BranchAndBound) => UtxoSelectionOrdering::BranchAndBound,
+        Some(tari_rpc::UtxoSelectionOrdering::PrivacyPreserving) => UtxoSelectionOrdering::PrivacyPreserving,
+        Some(tari_rpc::UtxoSelectionOrdering::MinimiseFee) => UtxoSelectionOrdering::MinimiseFee,
+        None => {
+            return Err(Status::invalid_argument(format!(
+                "Invalid UTXO selection ordering: {}",
+                ordering
+            )))
+        },
+    };
+    Ok(UtxoSelectionCriteria::with_ordering(ordering))
+}
+
+fn convert_balance(balance: Balance) -> GetBalanceResponse {
+    GetBalanceResponse {
+        available_balance: balance
+            .available_balance
+            .saturating_sub(balance.time_locked_balance.unwrap_or_default())
+            .0,
+        pending_incoming_balance: balance.pending_incoming_balance.0,
+        pending_outgoing_balance: balance.pending_outgoing_balance.0,
+        timelocked_balance: balance.time_locked_balance.unwrap_or_default().0,
+        frozen_balance: balance.frozen_balance.0,
+    }
+}
+
+fn convert_commitments(commitments: Vec<Vec<u8>>) -> Result<Vec<Commitment>, Status> {
+    if commitments.is_empty() {
+        return Err(Status::invalid_argument("No output commitments provided"));
+    }
+    commitments
+        .iter()
+        .map(|c| {
+            Commitment::from_bytes(c)
+                .map_err(|_| Status::invalid_argument(format!("Malformed commitment: {}", c.to_hex())))
+        })
+        .collect()
+}
+
+fn convert_payment_request(request: PaymentRequest) -> tari_rpc::PaymentRequest {
+    let status = match request.status {
+        PaymentRequestStatus::Pending => tari_rpc::PaymentRequestStatus::Pending,
+        PaymentRequestStatus::Underpaid => tari_rpc::PaymentRequestStatus::Underpaid,
+        PaymentRequestStatus::Paid => tari_rpc::PaymentRequestStatus::Paid,
+        PaymentRequestStatus::Expired => tari_rpc::PaymentRequestStatus::Expired,
+        PaymentRequestStatus::Cancelled => tari_rpc::PaymentRequestStatus::Cancelled,
+    };
+    tari_rpc::PaymentRequest {
+        uri: request.to_uri(),
+        emoji_uri: request.to_emoji_uri(),
+        reference: request.reference,
+        address: request.address.to_bytes().to_vec(),
+        amount: request.amount.as_u64(),
+        memo: request.memo,
+        created_at: Some(naive_datetime_to_timestamp(request.created_at)),
+        expires_at: request.expires_at.map(naive_datetime_to_timestamp),
+        status: status as i32,
+        amount_received: request.amount_received.as_u64(),
+        amount_inferred: request.amount_inferred.as_u64(),
+    }
+}
+
+fn convert_scheduled_payment(payment: ScheduledPayment) -> tari_rpc::ScheduledPayment {
+    let status = match payment.status {
+        ScheduledPaymentStatus::Active => tari_rpc::ScheduledPaymentStatus::Active,
+        ScheduledPaymentStatus::Completed => tari_rpc::ScheduledPaymentStatus::Completed,
+        ScheduledPaymentStatus::Cancelled => tari_rpc::ScheduledPaymentStatus::Cancelled,
+        ScheduledPaymentStatus::Failed => tari_rpc::ScheduledPaymentStatus::Failed,
+    };
+    let (start_at, start_height, interval_secs) = match payment.schedule {
+        PaymentSchedule::AtTime(time) => (Some(time), 0, 0),
+        PaymentSchedule::AtHeight(height) => (None, height, 0),
+        PaymentSchedule::Recurring { start, interval } => (Some(start), 0, interval.as_secs()),
+    };
+    tari_rpc::ScheduledPayment {
+        id: payment.id,
+        destination: payment.destination.to_bytes().to_vec(),
+        amount: payment.amount.as_u64(),
+        fee_per_gram: payment.fee_per_gram.as_u64(),
+        message: payment.message,
+        one_sided: payment.one_sided,
+        start_at: start_at.map(naive_datetime_to_timestamp),
+        start_height,
+        interval_secs,
+        max_runs: payment.max_runs.unwrap_or_default(),
+        next_run_at: payment.next_run_at.map(naive_datetime_to_timestamp),
+        run_count: payment.run_count,
+        status: status as i32,
+        last_tx_id: payment.last_tx_id.map(|tx_id| tx_id.as_u64()).unwrap_or_default(),
+        last_error: payment.last_error.unwrap_or_default(),
+        created_at: Some(naive_datetime_to_timestamp(payment.created_at)),
+    }
+}
+
+fn convert_vault(vault: Vault) -> tari_rpc::Vault {
+    let status = match vault.status {
+        VaultStatus::Active => tari_rpc::VaultStatus::Active,
+        VaultStatus::Spent => tari_rpc::VaultStatus::Spent,
+        VaultStatus::Recovered => tari_rpc::VaultStatus::Recovered,
+    };
+    tari_rpc::Vault {
+        id: vault.id,
+        tx_id: vault.tx_id.as_u64(),
+        commitment: vault.commitment.to_vec(),
+        output_hash: vault.output_hash.to_vec(),
+        amount: vault.amount.as_u64(),
+        hot_public_key: vault.hot_public_key.to_vec(),
+        recovery_public_key: vault.recovery_public_key.to_vec(),
+        unlock_height: vault.unlock_height,
+        status: status as i32,
+        spend_tx_id: vault.spend_tx_id.map(|tx_id| tx_id.as_u64()).unwrap_or_default(),
+        created_at: Some(naive_datetime_to_timestamp(vault.created_at)),
+    }
+}
+
+fn convert_balance_snapshot(snapshot: BalanceSnapshot) -> tari_rpc::BalanceSnapshot {
+    tari_rpc::BalanceSnapshot {
+        height: snapshot.height,
+        available_balance: snapshot.available_balance.as_u64(),
+        time_locked_balance: snapshot.time_locked_balance.as_u64(),
+        pending_incoming_balance: snapshot.pending_incoming_balance.as_u64(),
+        pending_outgoing_balance: snapshot.pending_outgoing_balance.as_u64(),
+        timestamp: Some(naive_datetime_to_timestamp(snapshot.created_at)),
+    }
+}
+
+fn convert_period_volume(volume: PeriodVolume) -> tari_rpc::TransactionVolume {
+    tari_rpc::TransactionVolume {
+        period_start: Some(naive_datetime_to_timestamp(volume.period_start)),
+        incoming: volume.incoming,
+        outgoing: volume.outgoing,
+        fees: volume.fees,
+        incoming_count: volume.incoming_count,
+        outgoing_count: volume.outgoing_count,
+    }
+}
+
 fn simple_event(event: &str) -> TransactionEvent {
     TransactionEvent {
         event: event.to_string(),
@@ -1100,6 +1844,7 @@ fn simple_event(event: &str) -> TransactionEvent {
 fn convert_wallet_transaction_into_transaction_info(
     tx: models::WalletTransaction,
     wallet_address: &TariAddress,
+    labels: TransactionLabels,
 ) -> TransactionInfo {
     use models::WalletTransaction::{Completed, PendingInbound, PendingOutbound};
     match tx {
@@ -1115,6 +1860,8 @@ fn convert_wallet_transaction_into_transaction_info(
             excess_sig: Default::default(),
             timestamp: Some(naive_datetime_to_timestamp(tx.timestamp)),
             message: tx.message,
+            label: labels.label.unwrap_or_default(),
+            tags: labels.tags,
         },
         PendingOutbound(tx) => TransactionInfo {
             tx_id: tx.tx_id.into(),
@@ -1128,6 +1875,8 @@ fn convert_wallet_transaction_into_transaction_info(
             excess_sig: Default::default(),
             timestamp: Some(naive_datetime_to_timestamp(tx.timestamp)),
             message: tx.message,
+            label: labels.label.unwrap_or_default(),
+            tags: labels.tags,
         },
         Completed(tx) => TransactionInfo {
             tx_id: tx.tx_id.into(),
@@ -1145,6 +1894,8 @@ fn convert_wallet_transaction_into_transaction_info(
                 .map(|s| s.get_signature().to_vec())
                 .unwrap_or_default(),
             message: tx.message,
+            label: labels.label.unwrap_or_default(),
+            tags: labels.tags,
         },
     }
 }
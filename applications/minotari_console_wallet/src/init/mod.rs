@@ -22,7 +22,12 @@
 
 #![allow(dead_code, unused)]
 
-use std::{fs, path::PathBuf, str::FromStr, sync::Arc};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
 
 use log::*;
 use minotari_app_utilities::identity_management::setup_node_identity;
@@ -31,6 +36,7 @@ use minotari_wallet::{
     output_manager_service::storage::database::OutputManagerDatabase,
     storage::{
         database::{WalletBackend, WalletDatabase},
+        sqlite_db::backup::restore_backup,
         sqlite_utilities::initialize_sqlite_database_backends,
     },
     wallet::{derive_comms_secret_key, read_or_create_master_seed},
@@ -98,7 +104,7 @@ pub enum WalletBoot {
 ///
 /// If the passphrase and confirmation don't match, or if the user cancels, returns an error
 /// Otherwise, returns the passphrase as a `SafePassword`
-fn get_new_passphrase(prompt: &str, confirm: &str) -> Result<SafePassword, ExitError> {
+pub fn get_new_passphrase(prompt: &str, confirm: &str) -> Result<SafePassword, ExitError> {
     // We may need to prompt for a passphrase multiple times
     loop {
         // Prompt the user for a passphrase and confirm it, up to the defined limit
@@ -263,6 +269,32 @@ pub async fn change_password(
     })
 }
 
+/// Restores the wallet database from an encrypted backup file. The wallet database must not exist yet.
+pub fn restore_wallet_backup(
+    backup_path: &Path,
+    config: &WalletConfig,
+    arg_passphrase: Option<SafePassword>,
+) -> Result<(), ExitError> {
+    let passphrase = match arg_passphrase {
+        Some(passphrase) => passphrase,
+        None => prompt_password("Backup passphrase: ")?,
+    };
+
+    restore_backup(backup_path, &config.db_file, &passphrase).map_err(|e| match e {
+        WalletStorageError::InvalidPassphrase => ExitError::new(
+            ExitCode::IncorrectOrEmptyPassword,
+            "The backup could not be decrypted with this passphrase.",
+        ),
+        e => ExitError::new(
+            ExitCode::WalletError,
+            format!("Could not restore the wallet backup. {}", e),
+        ),
+    })?;
+
+    println!("Wallet restored from backup {}", backup_path.display());
+    Ok(())
+}
+
 /// Populates the PeerConfig struct from:
 /// 1. The custom peer in the wallet config if it exists
 /// 2. The custom peer in the wallet db if it exists
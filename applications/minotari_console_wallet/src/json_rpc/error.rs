@@ -0,0 +1,91 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use serde_json as json;
+use tonic::{Code, Status};
+
+/// Invalid JSON was received by the server
+pub const PARSE_ERROR: i64 = -32700;
+/// The JSON sent is not a valid request object
+pub const INVALID_REQUEST: i64 = -32600;
+/// The method does not exist
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// Invalid method parameters
+pub const INVALID_PARAMS: i64 = -32602;
+/// Internal JSON-RPC error
+pub const INTERNAL_ERROR: i64 = -32603;
+/// The wallet could not complete the request
+pub const WALLET_ERROR: i64 = -32000;
+
+/// A JSON-RPC 2.0 error object
+/// More info: <https://www.jsonrpc.org/specification#error_object>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonRpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl JsonRpcError {
+    pub fn new<T: Into<String>>(code: i64, message: T) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn parse_error<T: ToString>(err: T) -> Self {
+        Self::new(PARSE_ERROR, format!("Parse error: {}", err.to_string()))
+    }
+
+    pub fn invalid_request<T: Into<String>>(message: T) -> Self {
+        Self::new(INVALID_REQUEST, message)
+    }
+
+    pub fn method_not_found(method: &str) -> Self {
+        Self::new(METHOD_NOT_FOUND, format!("Method not found: {}", method))
+    }
+
+    pub fn invalid_params<T: ToString>(err: T) -> Self {
+        Self::new(INVALID_PARAMS, format!("Invalid params: {}", err.to_string()))
+    }
+
+    pub fn to_json(&self) -> json::Value {
+        json::json!({
+            "code": self.code,
+            "message": self.message,
+        })
+    }
+}
+
+impl From<Status> for JsonRpcError {
+    fn from(status: Status) -> Self {
+        let code = match status.code() {
+            Code::InvalidArgument => INVALID_PARAMS,
+            Code::Internal => INTERNAL_ERROR,
+            _ => WALLET_ERROR,
+        };
+        Self::new(code, status.message())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_maps_grpc_status_codes() {
+        let err = JsonRpcError::from(Status::invalid_argument("Invalid address"));
+        assert_eq!(err.code, INVALID_PARAMS);
+        assert_eq!(err.message, "Invalid address");
+        assert_eq!(JsonRpcError::from(Status::internal("db")).code, INTERNAL_ERROR);
+        assert_eq!(JsonRpcError::from(Status::not_found("tx")).code, WALLET_ERROR);
+        assert_eq!(JsonRpcError::from(Status::unknown("oops")).code, WALLET_ERROR);
+    }
+
+    #[test]
+    fn it_serializes_to_an_error_object() {
+        let value = JsonRpcError::method_not_found("foo").to_json();
+        assert_eq!(value["code"], METHOD_NOT_FOUND);
+        assert_eq!(value["message"], "Method not found: foo");
+    }
+}
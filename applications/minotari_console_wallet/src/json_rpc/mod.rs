@@ -0,0 +1,10 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! JSON-RPC 2.0 over HTTP interface of the console wallet. Every method is served by the wallet GRPC server, so both
+//! interfaces share the same service handles, behaviour and credentials.
+
+mod error;
+mod wallet_json_rpc_server;
+
+pub use self::{error::JsonRpcError, wallet_json_rpc_server::*};
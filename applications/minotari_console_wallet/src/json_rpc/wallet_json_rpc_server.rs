@@ -0,0 +1,607 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::{convert::TryFrom, str::FromStr};
+
+use futures::StreamExt;
+use hyper::{
+    header::{AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, WWW_AUTHENTICATE},
+    Body,
+    Method,
+    Request,
+    Response,
+    StatusCode,
+};
+use log::*;
+use minotari_app_grpc::{
+    authentication::BasicAuthCredentials,
+    tari_rpc::{
+        payment_recipient::PaymentType,
+        wallet_server::Wallet,
+        CancelTransactionRequest,
+        CoinSplitRequest,
+        Empty,
+        GetBalanceRequest,
+        GetCompletedTransactionsRequest,
+        GetTransactionInfoRequest,
+        PaymentRecipient,
+        TransactionDirection,
+        TransactionInfo,
+        TransactionStatus,
+        TransferRequest,
+    },
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json as json;
+use tari_common_types::grpc_authentication::GrpcAuthentication;
+use tari_utilities::hex::Hex;
+
+use crate::{grpc::WalletGrpcServer, json_rpc::JsonRpcError};
+
+const LOG_TARGET: &str = "wallet::ui::json_rpc";
+
+/// Requests with a larger body are rejected before it is read
+const MAX_REQUEST_BODY_SIZE: u64 = 1024 * 1024;
+
+/// The methods of the wallet JSON-RPC interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonRpcMethod {
+    GetBalance,
+    GetAddress,
+    Transfer,
+    GetTransactionHistory,
+    GetTransactionInfo,
+    CancelTransaction,
+    CoinSplit,
+}
+
+impl FromStr for JsonRpcMethod {
+    type Err = JsonRpcError;
+
+    fn from_str(method: &str) -> Result<Self, Self::Err> {
+        match method {
+            "get_balance" => Ok(JsonRpcMethod::GetBalance),
+            "get_address" => Ok(JsonRpcMethod::GetAddress),
+            "transfer" => Ok(JsonRpcMethod::Transfer),
+            "get_transaction_history" => Ok(JsonRpcMethod::GetTransactionHistory),
+            "get_transaction_info" => Ok(JsonRpcMethod::GetTransactionInfo),
+            "cancel_transaction" => Ok(JsonRpcMethod::CancelTransaction),
+            "coin_split" => Ok(JsonRpcMethod::CoinSplit),
+            _ => Err(JsonRpcError::method_not_found(method)),
+        }
+    }
+}
+
+/// A validated JSON-RPC 2.0 request object. Requests without an id are notifications and receive no response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonRpcCall {
+    pub id: Option<json::Value>,
+    pub method: String,
+    pub params: json::Value,
+}
+
+impl TryFrom<json::Value> for JsonRpcCall {
+    type Error = (json::Value, JsonRpcError);
+
+    fn try_from(value: json::Value) -> Result<Self, Self::Error> {
+        let mut object = match value {
+            json::Value::Object(object) => object,
+            _ => {
+                return Err((
+                    json::Value::Null,
+                    JsonRpcError::invalid_request("Request must be an object"),
+                ))
+            },
+        };
+        let id = object.remove("id");
+        let response_id = id.clone().unwrap_or(json::Value::Null);
+        if !matches!(
+            id,
+            None | Some(json::Value::Null | json::Value::String(_) | json::Value::Number(_))
+        ) {
+            return Err((
+                json::Value::Null,
+                JsonRpcError::invalid_request("Request id must be a string, number or null"),
+            ));
+        }
+        if object.get("jsonrpc").and_then(|v| v.as_str()) != Some("2.0") {
+            return Err((
+                response_id,
+                JsonRpcError::invalid_request("jsonrpc must be exactly \"2.0\""),
+            ));
+        }
+        let method = match object.remove("method") {
+            Some(json::Value::String(method)) => method,
+            _ => return Err((response_id, JsonRpcError::invalid_request("method must be a string"))),
+        };
+        let params = match object.remove("params") {
+            None | Some(json::Value::Null) => json::Value::Object(json::Map::new()),
+            Some(params @ json::Value::Object(_)) => params,
+            Some(_) => {
+                return Err((
+                    response_id,
+                    JsonRpcError::invalid_params("only named (object) parameters are supported"),
+                ))
+            },
+        };
+        Ok(Self { id, method, params })
+    }
+}
+
+/// Create a JSON-RPC success response
+/// More info: <https://www.jsonrpc.org/specification#response_object>
+pub fn success_response(id: json::Value, result: json::Value) -> json::Value {
+    json::json!({
+        "id": id,
+        "jsonrpc": "2.0",
+        "result": result,
+    })
+}
+
+/// Create a JSON-RPC error response
+/// More info: <https://www.jsonrpc.org/specification#error_object>
+pub fn error_response(id: json::Value, err: &JsonRpcError) -> json::Value {
+    json::json!({
+        "id": id,
+        "jsonrpc": "2.0",
+        "error": err.to_json(),
+    })
+}
+
+fn parse_params<T: DeserializeOwned>(params: json::Value) -> Result<T, JsonRpcError> {
+    json::from_value(params).map_err(JsonRpcError::invalid_params)
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct GetBalanceParams {
+    account: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum JsonPaymentType {
+    #[default]
+    Standard,
+    OneSided,
+    StealthOneSided,
+}
+
+impl From<JsonPaymentType> for PaymentType {
+    fn from(payment_type: JsonPaymentType) -> Self {
+        match payment_type {
+            JsonPaymentType::Standard => PaymentType::StandardMimblewimble,
+            JsonPaymentType::OneSided => PaymentType::OneSided,
+            JsonPaymentType::StealthOneSided => PaymentType::OneSidedToStealthAddress,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RecipientParams {
+    address: String,
+    amount: u64,
+    fee_per_gram: u64,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    payment_type: JsonPaymentType,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransferParams {
+    recipients: Vec<RecipientParams>,
+    #[serde(default)]
+    account: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TransactionHistoryParams {
+    label: String,
+    tags: Vec<String>,
+    account: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransactionInfoParams {
+    transaction_ids: Vec<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CancelTransactionParams {
+    tx_id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CoinSplitParams {
+    amount_per_split: u64,
+    split_count: u64,
+    fee_per_gram: u64,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    lock_height: u64,
+}
+
+fn transaction_info_to_json(info: TransactionInfo) -> json::Value {
+    json::json!({
+        "tx_id": info.tx_id,
+        "source_address": info.source_address.to_hex(),
+        "dest_address": info.dest_address.to_hex(),
+        "status": TransactionStatus::from_i32(info.status)
+            .map(|s| format!("{:?}", s))
+            .unwrap_or_default(),
+        "direction": TransactionDirection::from_i32(info.direction)
+            .map(|d| format!("{:?}", d))
+            .unwrap_or_default(),
+        "amount": info.amount,
+        "fee": info.fee,
+        "is_cancelled": info.is_cancelled,
+        "excess_sig": info.excess_sig.to_hex(),
+        "timestamp": info.timestamp.map(|t| t.seconds),
+        "message": info.message,
+        "label": info.label,
+        "tags": info.tags,
+    })
+}
+
+pub struct WalletJsonRpcServer {
+    grpc: WalletGrpcServer,
+    auth: GrpcAuthentication,
+}
+
+impl WalletJsonRpcServer {
+    pub fn new(grpc: WalletGrpcServer, auth: GrpcAuthentication) -> Self {
+        Self { grpc, auth }
+    }
+
+    /// Serves a single HTTP request. The body is either one JSON-RPC request object or a batch of them.
+    pub async fn handle_http_request(&self, req: Request<Body>) -> Response<Body> {
+        if req.method() != Method::POST {
+            return http_response(StatusCode::METHOD_NOT_ALLOWED, Body::empty());
+        }
+        if let Err(err) = self.authenticate(&req) {
+            warn!(target: LOG_TARGET, "JSON-RPC authentication failed: {}", err);
+            let mut response = http_response(StatusCode::UNAUTHORIZED, Body::empty());
+            response
+                .headers_mut()
+                .insert(WWW_AUTHENTICATE, "Basic".parse().expect("Valid header value"));
+            return response;
+        }
+        let content_length = req
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok());
+        match content_length {
+            None => return http_response(StatusCode::LENGTH_REQUIRED, Body::empty()),
+            Some(len) if len > MAX_REQUEST_BODY_SIZE => {
+                return http_response(StatusCode::PAYLOAD_TOO_LARGE, Body::empty())
+            },
+            Some(_) => {},
+        }
+        let body = match hyper::body::to_bytes(req.into_body()).await {
+            Ok(body) => body,
+            Err(err) => {
+                debug!(target: LOG_TARGET, "Could not read JSON-RPC request body: {}", err);
+                return http_response(StatusCode::BAD_REQUEST, Body::empty());
+            },
+        };
+
+        match self.handle_body(&body).await {
+            Some(response) => {
+                let mut response = http_response(StatusCode::OK, Body::from(response.to_string()));
+                response
+                    .headers_mut()
+                    .insert(CONTENT_TYPE, "application/json".parse().expect("Valid header value"));
+                response
+            },
+            None => http_response(StatusCode::NO_CONTENT, Body::empty()),
+        }
+    }
+
+    fn authenticate(&self, req: &Request<Body>) -> Result<(), String> {
+        match &self.auth {
+            GrpcAuthentication::None => Ok(()),
+            GrpcAuthentication::Basic { username, password } => {
+                let header = req
+                    .headers()
+                    .get(AUTHORIZATION)
+                    .ok_or_else(|| "Missing authorization header".to_string())?
+                    .to_str()
+                    .map_err(|e| e.to_string())?;
+                BasicAuthCredentials::from_header(header)
+                    .and_then(|credentials| credentials.validate(username, password.reveal()))
+                    .map_err(|e| e.to_string())
+            },
+        }
+    }
+
+    async fn handle_body(&self, body: &[u8]) -> Option<json::Value> {
+        let value = match json::from_slice::<json::Value>(body) {
+            Ok(value) => value,
+            Err(err) => return Some(error_response(json::Value::Null, &JsonRpcError::parse_error(err))),
+        };
+        match value {
+            json::Value::Array(calls) if calls.is_empty() => Some(error_response(
+                json::Value::Null,
+                &JsonRpcError::invalid_request("Batch must not be empty"),
+            )),
+            json::Value::Array(calls) => {
+                let mut responses = Vec::with_capacity(calls.len());
+                for call in calls {
+                    if let Some(response) = self.handle_call(call).await {
+                        responses.push(response);
+                    }
+                }
+                if responses.is_empty() {
+                    None
+                } else {
+                    Some(json::Value::Array(responses))
+                }
+            },
+            call => self.handle_call(call).await,
+        }
+    }
+
+    async fn handle_call(&self, value: json::Value) -> Option<json::Value> {
+        let call = match JsonRpcCall::try_from(value) {
+            Ok(call) => call,
+            Err((id, err)) => return Some(error_response(id, &err)),
+        };
+        debug!(target: LOG_TARGET, "Incoming JSON-RPC request for {}", call.method);
+        let result = match call.method.parse() {
+            Ok(method) => self.dispatch(method, call.params).await,
+            Err(err) => Err(err),
+        };
+        let id = call.id?;
+        match result {
+            Ok(result) => Some(success_response(id, result)),
+            Err(err) => Some(error_response(id, &err)),
+        }
+    }
+
+    async fn dispatch(&self, method: JsonRpcMethod, params: json::Value) -> Result<json::Value, JsonRpcError> {
+        match method {
+            JsonRpcMethod::GetBalance => self.get_balance(parse_params(params)?).await,
+            JsonRpcMethod::GetAddress => self.get_address().await,
+            JsonRpcMethod::Transfer => self.transfer(parse_params(params)?).await,
+            JsonRpcMethod::GetTransactionHistory => self.get_transaction_history(parse_params(params)?).await,
+            JsonRpcMethod::GetTransactionInfo => self.get_transaction_info(parse_params(params)?).await,
+            JsonRpcMethod::CancelTransaction => self.cancel_transaction(parse_params(params)?).await,
+            JsonRpcMethod::CoinSplit => self.coin_split(parse_params(params)?).await,
+        }
+    }
+
+    async fn get_balance(&self, params: GetBalanceParams) -> Result<json::Value, JsonRpcError> {
+        let balance = self
+            .grpc
+            .get_balance(tonic::Request::new(GetBalanceRequest {
+                account: params.account,
+            }))
+            .await?
+            .into_inner();
+        Ok(json::json!({
+            "available_balance": balance.available_balance,
+            "pending_incoming_balance": balance.pending_incoming_balance,
+            "pending_outgoing_balance": balance.pending_outgoing_balance,
+            "timelocked_balance": balance.timelocked_balance,
+            "frozen_balance": balance.frozen_balance,
+        }))
+    }
+
+    async fn get_address(&self) -> Result<json::Value, JsonRpcError> {
+        let address = self.grpc.get_address(tonic::Request::new(Empty {})).await?.into_inner();
+        Ok(json::json!({ "address": address.address.to_hex() }))
+    }
+
+    async fn transfer(&self, params: TransferParams) -> Result<json::Value, JsonRpcError> {
+        let recipients = params
+            .recipients
+            .into_iter()
+            .map(|r| PaymentRecipient {
+                address: r.address,
+                amount: r.amount,
+                fee_per_gram: r.fee_per_gram,
+                message: r.message,
+                payment_type: PaymentType::from(r.payment_type) as i32,
+                ..Default::default()
+            })
+            .collect();
+        let response = self
+            .grpc
+            .transfer(tonic::Request::new(TransferRequest {
+                recipients,
+                account: params.account,
+            }))
+            .await?
+            .into_inner();
+        let results = response
+            .results
+            .into_iter()
+            .map(|r| {
+                json::json!({
+                    "address": r.address,
+                    "transaction_id": r.transaction_id,
+                    "is_success": r.is_success,
+                    "failure_message": r.failure_message,
+                })
+            })
+            .collect::<Vec<_>>();
+        Ok(json::json!({ "results": results }))
+    }
+
+    async fn get_transaction_history(&self, params: TransactionHistoryParams) -> Result<json::Value, JsonRpcError> {
+        let mut stream = self
+            .grpc
+            .get_completed_transactions(tonic::Request::new(GetCompletedTransactionsRequest {
+                label: params.label,
+                tags: params.tags,
+                account: params.account,
+            }))
+            .await?
+            .into_inner();
+        let mut transactions = Vec::new();
+        while let Some(response) = stream.next().await {
+            if let Some(info) = response?.transaction {
+                transactions.push(transaction_info_to_json(info));
+            }
+        }
+        Ok(json::json!({ "transactions": transactions }))
+    }
+
+    async fn get_transaction_info(&self, params: TransactionInfoParams) -> Result<json::Value, JsonRpcError> {
+        let response = self
+            .grpc
+            .get_transaction_info(tonic::Request::new(GetTransactionInfoRequest {
+                transaction_ids: params.transaction_ids,
+            }))
+            .await?
+            .into_inner();
+        let transactions = response
+            .transactions
+            .into_iter()
+            .map(transaction_info_to_json)
+            .collect::<Vec<_>>();
+        Ok(json::json!({ "transactions": transactions }))
+    }
+
+    async fn cancel_transaction(&self, params: CancelTransactionParams) -> Result<json::Value, JsonRpcError> {
+        let response = self
+            .grpc
+            .cancel_transaction(tonic::Request::new(CancelTransactionRequest { tx_id: params.tx_id }))
+            .await?
+            .into_inner();
+        Ok(json::json!({
+            "is_success": response.is_success,
+            "failure_message": response.failure_message,
+        }))
+    }
+
+    async fn coin_split(&self, params: CoinSplitParams) -> Result<json::Value, JsonRpcError> {
+        let response = self
+            .grpc
+            .coin_split(tonic::Request::new(CoinSplitRequest {
+                amount_per_split: params.amount_per_split,
+                split_count: params.split_count,
+                fee_per_gram: params.fee_per_gram,
+                message: params.message,
+                lock_height: params.lock_height,
+            }))
+            .await?
+            .into_inner();
+        Ok(json::json!({ "tx_id": response.tx_id }))
+    }
+}
+
+fn http_response(status: StatusCode, body: Body) -> Response<Body> {
+    let mut response = Response::new(body);
+    *response.status_mut() = status;
+    response
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::json_rpc::error::{INVALID_PARAMS, INVALID_REQUEST, METHOD_NOT_FOUND};
+
+    #[test]
+    fn it_parses_method_names() {
+        assert_eq!(
+            "get_balance".parse::<JsonRpcMethod>().unwrap(),
+            JsonRpcMethod::GetBalance
+        );
+        assert_eq!(
+            "get_transaction_history".parse::<JsonRpcMethod>().unwrap(),
+            JsonRpcMethod::GetTransactionHistory
+        );
+        assert_eq!("coin_split".parse::<JsonRpcMethod>().unwrap(), JsonRpcMethod::CoinSplit);
+        let err = "send_all_my_funds".parse::<JsonRpcMethod>().unwrap_err();
+        assert_eq!(err.code, METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn it_parses_request_objects() {
+        let call = JsonRpcCall::try_from(json::json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "method": "get_balance",
+            "params": {"account": "savings"},
+        }))
+        .unwrap();
+        assert_eq!(call.id, Some(json::json!(7)));
+        assert_eq!(call.method, "get_balance");
+        let params: GetBalanceParams = parse_params(call.params).unwrap();
+        assert_eq!(params.account, "savings");
+
+        // Notifications have no id and missing params are empty
+        let call = JsonRpcCall::try_from(json::json!({"jsonrpc": "2.0", "method": "get_address"})).unwrap();
+        assert_eq!(call.id, None);
+        assert_eq!(call.params, json::json!({}));
+    }
+
+    #[test]
+    fn it_rejects_invalid_request_objects() {
+        let (id, err) = JsonRpcCall::try_from(json::json!([1, 2])).unwrap_err();
+        assert_eq!(id, json::Value::Null);
+        assert_eq!(err.code, INVALID_REQUEST);
+
+        let (id, err) = JsonRpcCall::try_from(json::json!({"jsonrpc": "1.0", "id": "a", "method": "x"})).unwrap_err();
+        assert_eq!(id, json::json!("a"));
+        assert_eq!(err.code, INVALID_REQUEST);
+
+        let (_, err) = JsonRpcCall::try_from(json::json!({"jsonrpc": "2.0", "id": 1, "method": 5})).unwrap_err();
+        assert_eq!(err.code, INVALID_REQUEST);
+
+        let (_, err) = JsonRpcCall::try_from(json::json!({"jsonrpc": "2.0", "id": {}, "method": "x"})).unwrap_err();
+        assert_eq!(err.code, INVALID_REQUEST);
+
+        let (_, err) =
+            JsonRpcCall::try_from(json::json!({"jsonrpc": "2.0", "id": 1, "method": "x", "params": [1]})).unwrap_err();
+        assert_eq!(err.code, INVALID_PARAMS);
+    }
+
+    #[test]
+    fn it_validates_method_params() {
+        let params: TransferParams = parse_params(json::json!({
+            "recipients": [{
+                "address": "abcd",
+                "amount": 1000,
+                "fee_per_gram": 5,
+                "payment_type": "stealth_one_sided",
+            }],
+        }))
+        .unwrap();
+        assert_eq!(params.recipients.len(), 1);
+        assert_eq!(
+            PaymentType::from(params.recipients[0].payment_type),
+            PaymentType::OneSidedToStealthAddress
+        );
+        assert!(params.account.is_empty());
+
+        let err = parse_params::<TransferParams>(json::json!({"recipients": [{"address": "abcd"}]})).unwrap_err();
+        assert_eq!(err.code, INVALID_PARAMS);
+        let err = parse_params::<CancelTransactionParams>(json::json!({"tx_id": 1, "force": true})).unwrap_err();
+        assert_eq!(err.code, INVALID_PARAMS);
+    }
+
+    #[test]
+    fn it_builds_response_objects() {
+        let response = success_response(json::json!(1), json::json!({"tx_id": 5}));
+        assert_eq!(response["jsonrpc"], "2.0");
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["tx_id"], 5);
+        assert!(response.get("error").is_none());
+
+        let response = error_response(json::Value::Null, &JsonRpcError::parse_error("eof"));
+        assert_eq!(response["id"], json::Value::Null);
+        assert_eq!(response["error"]["code"], -32700);
+        assert!(response.get("result").is_none());
+    }
+}
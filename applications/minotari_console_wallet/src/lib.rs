@@ -28,6 +28,7 @@ mod cli;
 mod config;
 mod grpc;
 mod init;
+mod json_rpc;
 mod notifier;
 mod recovery;
 mod ui;
@@ -49,6 +50,7 @@ pub use cli::{
 use init::{change_password, get_base_node_peer_config, init_wallet, start_wallet, tari_splash_screen, WalletBoot};
 use log::*;
 use minotari_app_utilities::{common_cli_args::CommonCliArgs, consts, network_check::is_network_choice_valid};
+use notifier::WebhookNotifier;
 use recovery::{get_seed_from_seed_words, prompt_private_key_from_seed_words};
 use tari_common::{
     configuration::bootstrap::ApplicationType,
@@ -63,7 +65,7 @@ use tokio::runtime::Runtime;
 use wallet_modes::{command_mode, grpc_mode, recovery_mode, script_mode, tui_mode, WalletMode};
 
 pub use crate::config::ApplicationConfig;
-use crate::init::{boot_with_password, confirm_seed_words, wallet_mode};
+use crate::init::{boot_with_password, confirm_seed_words, restore_wallet_backup, wallet_mode};
 
 pub const LOG_TARGET: &str = "wallet::console_wallet::main";
 
@@ -88,6 +90,8 @@ pub fn run_wallet(shutdown: &mut Shutdown, runtime: Runtime, config: &mut Applic
         recovery: false,
         seed_words: None,
         seed_words_file_name: None,
+        restore_backup: None,
+        backup_passphrase: None,
         non_interactive_mode: true,
         input_file: None,
         command: None,
@@ -95,6 +99,8 @@ pub fn run_wallet(shutdown: &mut Shutdown, runtime: Runtime, config: &mut Applic
         command_mode_auto_exit: false,
         grpc_enabled: true,
         grpc_address: None,
+        json_rpc_enabled: false,
+        json_rpc_address: None,
         command2: None,
         profile_with_tokio_console: false,
     };
@@ -123,6 +129,11 @@ pub fn run_wallet_with_cli(
         tari_splash_screen("Console Wallet");
     }
 
+    if let Some(ref backup_path) = cli.restore_backup {
+        info!(target: LOG_TARGET, "Restore from backup requested.");
+        restore_wallet_backup(backup_path, &config.wallet, cli.backup_passphrase.clone())?;
+    }
+
     // check for recovery based on existence of wallet file
     let (mut boot_mode, password) = boot_with_password(&cli, &config.wallet)?;
 
@@ -197,6 +208,21 @@ pub fn run_wallet_with_cli(
     // start wallet
     runtime.block_on(start_wallet(&mut wallet, &base_node_selected, &wallet_mode))?;
 
+    if !config.wallet.webhook_config.endpoints.is_empty() {
+        let webhook_notifier = WebhookNotifier::new(
+            config.wallet.webhook_config.clone(),
+            &config.wallet.data_dir,
+            wallet.transaction_service.get_event_stream(),
+        )
+        .map_err(|e| {
+            ExitError::new(
+                ExitCode::ConfigError,
+                format!("Could not start the webhook notifier. {}", e),
+            )
+        })?;
+        runtime.spawn(webhook_notifier.run(shutdown.to_signal()));
+    }
+
     debug!(target: LOG_TARGET, "Starting app");
 
     let handle = runtime.handle().clone();
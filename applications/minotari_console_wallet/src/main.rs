@@ -114,4 +114,14 @@ fn setup_grpc_config(config: &mut ApplicationConfig) {
             .unwrap(),
         );
     }
+    if config.wallet.json_rpc_address.is_none() {
+        config.wallet.json_rpc_address = Some(
+            format!(
+                "/ip4/127.0.0.1/tcp/{}",
+                grpc_default_port(ApplicationType::ConsoleWallet, config.wallet.network) + 1000
+            )
+            .parse()
+            .unwrap(),
+        );
+    }
 }
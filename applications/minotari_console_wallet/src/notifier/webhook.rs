@@ -0,0 +1,789 @@
+// Copyright 2023. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Delivers every transaction event of the wallet to the configured HTTP endpoints.
+//!
+//! Events are POSTed as JSON and, when a secret is configured, signed with HMAC-SHA256. Every delivery is kept in its
+//! own file in the queue directory of the wallet data directory until it succeeds, so nothing is lost when an endpoint
+//! is down or the wallet restarts. Deliveries are sent from separate tasks, so a slow endpoint does not hold up the
+//! intake of new events. Failed deliveries are retried with exponential backoff and, once `max_attempts` is reached,
+//! appended to a dead-letter log next to the queue.
+
+use std::{
+    collections::HashSet,
+    fs,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use hmac::{Hmac, Mac};
+use log::*;
+use minotari_wallet::{
+    transaction_service::handle::{TransactionEvent, TransactionEventReceiver},
+    WebhookConfig,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use tari_shutdown::ShutdownSignal;
+use tari_utilities::{hex::Hex, SafePassword};
+use thiserror::Error;
+use tokio::{
+    sync::{broadcast::error::RecvError, mpsc},
+    time::{interval, MissedTickBehavior},
+};
+
+const LOG_TARGET: &str = "wallet::notifier::webhook";
+
+const WEBHOOK_DIR: &str = "webhooks";
+const QUEUE_DIR: &str = "queue";
+const NEXT_EVENT_ID_FILE: &str = "next_event_id";
+const DEAD_LETTER_FILE: &str = "dead_letter.log";
+
+pub const EVENT_HEADER: &str = "X-Tari-Event";
+pub const DELIVERY_HEADER: &str = "X-Tari-Delivery";
+pub const SIGNATURE_HEADER: &str = "X-Tari-Signature";
+
+/// How often the queue is checked for deliveries that are due
+const QUEUE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// The most deliveries that are sent at the same time
+const MAX_IN_FLIGHT: usize = 16;
+
+#[derive(Debug, Error)]
+pub enum WebhookError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("HTTP client error: {0}")]
+    HttpError(#[from] reqwest::Error),
+}
+
+/// Identifies a delivery by its event and the position of its endpoint in the config
+type DeliveryKey = (u64, usize);
+
+/// A single event waiting to be delivered to a single endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Delivery {
+    event_id: u64,
+    endpoint_index: usize,
+    event: String,
+    endpoint: String,
+    payload: String,
+    attempts: u32,
+    /// Unix timestamp in seconds of the next attempt
+    next_attempt: u64,
+    last_error: Option<String>,
+}
+
+impl Delivery {
+    fn key(&self) -> DeliveryKey {
+        (self.event_id, self.endpoint_index)
+    }
+
+    fn file_name(&self) -> String {
+        format!("{:020}-{}.json", self.event_id, self.endpoint_index)
+    }
+}
+
+/// The queued deliveries, stored as one file per delivery so that every change only rewrites the delivery it affects
+#[derive(Debug, PartialEq, Eq)]
+struct DeliveryQueue {
+    dir: PathBuf,
+    next_event_id: u64,
+    deliveries: Vec<Delivery>,
+}
+
+impl DeliveryQueue {
+    fn load(dir: &Path) -> Result<Self, WebhookError> {
+        fs::create_dir_all(dir)?;
+        let next_event_id_path = dir.join(NEXT_EVENT_ID_FILE);
+        let mut next_event_id = if next_event_id_path.exists() {
+            serde_json::from_slice(&fs::read(next_event_id_path)?)?
+        } else {
+            0
+        };
+
+        let mut deliveries = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().map_or(true, |ext| ext != "json") {
+                continue;
+            }
+            let delivery: Delivery = serde_json::from_slice(&fs::read(&path)?)?;
+            next_event_id = next_event_id.max(delivery.event_id + 1);
+            deliveries.push(delivery);
+        }
+        deliveries.sort_by_key(Delivery::key);
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            next_event_id,
+            deliveries,
+        })
+    }
+
+    /// Add a delivery of `event` to every endpoint and return the id of the event
+    fn push(&mut self, event: &TransactionEvent, endpoints: &[String], now: u64) -> Result<u64, WebhookError> {
+        let event_id = self.next_event_id;
+        self.next_event_id += 1;
+        write_atomically(
+            &self.dir.join(NEXT_EVENT_ID_FILE),
+            &serde_json::to_vec(&self.next_event_id)?,
+        )?;
+
+        let (name, payload) = event_payload(event, event_id, now);
+        let payload = serde_json::to_string(&payload)?;
+        for (endpoint_index, endpoint) in endpoints.iter().enumerate() {
+            let delivery = Delivery {
+                event_id,
+                endpoint_index,
+                event: name.to_string(),
+                endpoint: endpoint.clone(),
+                payload: payload.clone(),
+                attempts: 0,
+                next_attempt: now,
+                last_error: None,
+            };
+            self.save(&delivery)?;
+            self.deliveries.push(delivery);
+        }
+        Ok(event_id)
+    }
+
+    fn get(&self, key: DeliveryKey) -> Option<&Delivery> {
+        self.deliveries.iter().find(|d| d.key() == key)
+    }
+
+    fn save(&self, delivery: &Delivery) -> Result<(), WebhookError> {
+        write_atomically(&self.dir.join(delivery.file_name()), &serde_json::to_vec(delivery)?)
+    }
+
+    /// Replace the queued delivery with the same key
+    fn update(&mut self, delivery: Delivery) -> Result<(), WebhookError> {
+        self.save(&delivery)?;
+        if let Some(existing) = self.deliveries.iter_mut().find(|d| d.key() == delivery.key()) {
+            *existing = delivery;
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, key: DeliveryKey) -> Result<(), WebhookError> {
+        if let Some(pos) = self.deliveries.iter().position(|d| d.key() == key) {
+            let delivery = self.deliveries.remove(pos);
+            fs::remove_file(self.dir.join(delivery.file_name()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Replace the file atomically, so a crash cannot leave a truncated file behind
+fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), WebhookError> {
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, contents)?;
+    fs::rename(temp_path, path)?;
+    Ok(())
+}
+
+/// Sends deliveries. It is cloned into every delivery task.
+#[derive(Clone)]
+struct WebhookClient {
+    client: reqwest::Client,
+    secret: Option<SafePassword>,
+}
+
+impl WebhookClient {
+    async fn send(&self, delivery: &Delivery) -> Result<(), String> {
+        let mut request = self
+            .client
+            .post(&delivery.endpoint)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, &delivery.event)
+            .header(DELIVERY_HEADER, delivery.event_id.to_string());
+        if let Some(secret) = &self.secret {
+            let signature = hmac_sha256(secret.reveal(), delivery.payload.as_bytes());
+            request = request.header(SIGNATURE_HEADER, format!("sha256={}", signature.to_hex()));
+        }
+
+        let response = request
+            .body(delivery.payload.clone())
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("Endpoint responded with status {}", response.status()))
+        }
+    }
+}
+
+pub struct WebhookNotifier {
+    config: WebhookConfig,
+    client: WebhookClient,
+    queue: DeliveryQueue,
+    in_flight: HashSet<DeliveryKey>,
+    dead_letter_path: PathBuf,
+    events: TransactionEventReceiver,
+}
+
+impl WebhookNotifier {
+    /// Create the notifier, picking up any deliveries that were still queued when the wallet last stopped
+    pub fn new(config: WebhookConfig, data_dir: &Path, events: TransactionEventReceiver) -> Result<Self, WebhookError> {
+        let webhook_dir = data_dir.join(WEBHOOK_DIR);
+        let queue = DeliveryQueue::load(&webhook_dir.join(QUEUE_DIR))?;
+        let client = WebhookClient {
+            client: reqwest::Client::builder().timeout(config.request_timeout).build()?,
+            secret: config.secret.clone(),
+        };
+
+        Ok(Self {
+            config,
+            client,
+            queue,
+            in_flight: HashSet::new(),
+            dead_letter_path: webhook_dir.join(DEAD_LETTER_FILE),
+            events,
+        })
+    }
+
+    pub async fn run(mut self, mut shutdown_signal: ShutdownSignal) {
+        info!(
+            target: LOG_TARGET,
+            "Webhook notifier started for {} endpoint(s) with {} queued deliveries",
+            self.config.endpoints.len(),
+            self.queue.deliveries.len()
+        );
+        let mut poll = interval(QUEUE_POLL_INTERVAL);
+        poll.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let (results_tx, mut results_rx) = mpsc::unbounded_channel();
+
+        loop {
+            tokio::select! {
+                result = self.events.recv() => match result {
+                    Ok(event) => self.enqueue(&event),
+                    Err(RecvError::Lagged(n)) => {
+                        warn!(target: LOG_TARGET, "Webhook notifier missed {} transaction events", n);
+                    },
+                    Err(RecvError::Closed) => break,
+                },
+                Some((key, result)) = results_rx.recv() => self.handle_result(key, result),
+                _ = poll.tick() => self.dispatch_due(&results_tx),
+                _ = shutdown_signal.wait() => break,
+            }
+        }
+        // Deliveries that are still in flight stay in the queue and are sent again on the next start
+        info!(target: LOG_TARGET, "Webhook notifier stopped");
+    }
+
+    fn enqueue(&mut self, event: &TransactionEvent) {
+        match self.queue.push(event, &self.config.endpoints, unix_now()) {
+            Ok(event_id) => debug!(target: LOG_TARGET, "Queued webhook event {}: {}", event_id, event),
+            Err(e) => error!(target: LOG_TARGET, "Could not queue webhook event {}: {}", event, e),
+        }
+    }
+
+    /// Start a delivery task for every delivery that is due and not already in flight
+    fn dispatch_due(&mut self, results_tx: &mpsc::UnboundedSender<(DeliveryKey, Result<(), String>)>) {
+        let now = unix_now();
+        let due = self
+            .queue
+            .deliveries
+            .iter()
+            .filter(|d| d.next_attempt <= now && !self.in_flight.contains(&d.key()))
+            .take(MAX_IN_FLIGHT.saturating_sub(self.in_flight.len()))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for delivery in due {
+            self.in_flight.insert(delivery.key());
+            let client = self.client.clone();
+            let results_tx = results_tx.clone();
+            tokio::spawn(async move {
+                let result = client.send(&delivery).await;
+                let _result = results_tx.send((delivery.key(), result));
+            });
+        }
+    }
+
+    fn handle_result(&mut self, key: DeliveryKey, result: Result<(), String>) {
+        self.in_flight.remove(&key);
+        match result {
+            Ok(()) => {
+                debug!(target: LOG_TARGET, "Delivered webhook event {} to endpoint {}", key.0, key.1);
+                if let Err(e) = self.queue.remove(key) {
+                    error!(target: LOG_TARGET, "Could not remove a delivered webhook event: {}", e);
+                }
+            },
+            Err(e) => {
+                let mut delivery = match self.queue.get(key) {
+                    Some(delivery) => delivery.clone(),
+                    None => return,
+                };
+                delivery.attempts += 1;
+                delivery.last_error = Some(e);
+                if delivery.attempts >= self.config.max_attempts {
+                    self.dead_letter(&delivery);
+                    if let Err(e) = self.queue.remove(key) {
+                        error!(target: LOG_TARGET, "Could not remove a dead-lettered webhook event: {}", e);
+                    }
+                } else {
+                    delivery.next_attempt = unix_now() + self.retry_delay(delivery.attempts).as_secs();
+                    debug!(
+                        target: LOG_TARGET,
+                        "Webhook event {} to {} failed (attempt {}), retrying at {}",
+                        delivery.event_id,
+                        delivery.endpoint,
+                        delivery.attempts,
+                        delivery.next_attempt
+                    );
+                    if let Err(e) = self.queue.update(delivery) {
+                        error!(target: LOG_TARGET, "Could not persist a webhook delivery: {}", e);
+                    }
+                }
+            },
+        }
+    }
+
+    /// The backoff before retry number `attempts`: the base delay doubled for every earlier retry, up to the maximum
+    fn retry_delay(&self, attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        self.config
+            .retry_base_delay
+            .saturating_mul(factor)
+            .min(self.config.retry_max_delay)
+    }
+
+    fn dead_letter(&self, delivery: &Delivery) {
+        warn!(
+            target: LOG_TARGET,
+            "Webhook event {} to {} failed {} times, moving it to the dead-letter log: {}",
+            delivery.event_id,
+            delivery.endpoint,
+            delivery.attempts,
+            delivery.last_error.as_deref().unwrap_or_default()
+        );
+        let result = serde_json::to_string(delivery)
+            .map_err(WebhookError::from)
+            .and_then(|line| {
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.dead_letter_path)?;
+                writeln!(file, "{}", line)?;
+                Ok(())
+            });
+        if let Err(e) = result {
+            error!(target: LOG_TARGET, "Could not write to the webhook dead-letter log: {}", e);
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// The name of the event and the JSON body that is delivered for it
+fn event_payload(event: &TransactionEvent, event_id: u64, timestamp: u64) -> (&'static str, Value) {
+    let (name, tx_id, details) = match event {
+        TransactionEvent::MempoolBroadcastTimedOut(tx_id) => ("MempoolBroadcastTimedOut", Some(*tx_id), json!({})),
+        TransactionEvent::NewBlockMined(tx_id) => ("NewBlockMined", Some(*tx_id), json!({})),
+        TransactionEvent::ReceivedTransaction(tx_id) => ("ReceivedTransaction", Some(*tx_id), json!({})),
+        TransactionEvent::ReceivedTransactionReply(tx_id) => ("ReceivedTransactionReply", Some(*tx_id), json!({})),
+        TransactionEvent::ReceivedFinalizedTransaction(tx_id) => {
+            ("ReceivedFinalizedTransaction", Some(*tx_id), json!({}))
+        },
+        TransactionEvent::TransactionDiscoveryInProgress(tx_id) => {
+            ("TransactionDiscoveryInProgress", Some(*tx_id), json!({}))
+        },
+        TransactionEvent::TransactionSendResult(tx_id, status) => (
+            "TransactionSendResult",
+            Some(*tx_id),
+            json!({
+                "direct_send_result": status.direct_send_result,
+                "store_and_forward_send_result": status.store_and_forward_send_result,
+                "queued_for_retry": status.queued_for_retry,
+            }),
+        ),
+        TransactionEvent::TransactionCompletedImmediately(tx_id) => {
+            ("TransactionCompletedImmediately", Some(*tx_id), json!({}))
+        },
+        TransactionEvent::TransactionCancelled(tx_id, reason) => {
+            ("TransactionCancelled", Some(*tx_id), json!({ "reason": reason }))
+        },
+        TransactionEvent::TransactionBroadcast(tx_id) => ("TransactionBroadcast", Some(*tx_id), json!({})),
+        TransactionEvent::TransactionImported(tx_id) => ("TransactionImported", Some(*tx_id), json!({})),
+        TransactionEvent::FauxTransactionUnconfirmed {
+            tx_id,
+            num_confirmations,
+            is_valid,
+        } => (
+            "FauxTransactionUnconfirmed",
+            Some(*tx_id),
+            json!({ "num_confirmations": num_confirmations, "is_valid": is_valid }),
+        ),
+        TransactionEvent::FauxTransactionConfirmed { tx_id, is_valid } => (
+            "FauxTransactionConfirmed",
+            Some(*tx_id),
+            json!({ "is_valid": is_valid }),
+        ),
+        TransactionEvent::TransactionMined { tx_id, is_valid } => {
+            ("TransactionMined", Some(*tx_id), json!({ "is_valid": is_valid }))
+        },
+        TransactionEvent::TransactionMinedRequestTimedOut(tx_id) => {
+            ("TransactionMinedRequestTimedOut", Some(*tx_id), json!({}))
+        },
+        TransactionEvent::TransactionMinedUnconfirmed {
+            tx_id,
+            num_confirmations,
+            is_valid,
+        } => (
+            "TransactionMinedUnconfirmed",
+            Some(*tx_id),
+            json!({ "num_confirmations": num_confirmations, "is_valid": is_valid }),
+        ),
+        TransactionEvent::TransactionValidationStateChanged(operation_id) => (
+            "TransactionValidationStateChanged",
+            None,
+            json!({ "operation_id": operation_id.as_u64() }),
+        ),
+        TransactionEvent::TransactionValidationCompleted(operation_id) => (
+            "TransactionValidationCompleted",
+            None,
+            json!({ "operation_id": operation_id.as_u64() }),
+        ),
+        TransactionEvent::TransactionValidationFailed(operation_id, reason) => (
+            "TransactionValidationFailed",
+            None,
+            json!({ "operation_id": operation_id.as_u64(), "reason": reason }),
+        ),
+        TransactionEvent::PaymentRequestUpdated { reference, status } => (
+            "PaymentRequestUpdated",
+            None,
+            json!({ "reference": reference, "status": status.to_string() }),
+        ),
+        TransactionEvent::ScheduledPaymentExecuted { id, tx_id } => (
+            "ScheduledPaymentExecuted",
+            Some(*tx_id),
+            json!({ "scheduled_payment_id": id }),
+        ),
+        TransactionEvent::ScheduledPaymentFailed { id, reason } => (
+            "ScheduledPaymentFailed",
+            None,
+            json!({ "scheduled_payment_id": id, "reason": reason }),
+        ),
+        TransactionEvent::SpendHeldForApproval(id) => ("SpendHeldForApproval", None, json!({ "pending_spend_id": id })),
+        TransactionEvent::SpendDenied(reason) => ("SpendDenied", None, json!({ "reason": reason })),
+        TransactionEvent::DustConsolidated(report) => (
+            "DustConsolidated",
+            Some(report.tx_id),
+            json!({
+                "num_inputs": report.num_inputs,
+                "amount": report.amount.as_u64(),
+                "fee": report.fee.as_u64(),
+                "fee_per_gram": report.fee_per_gram.as_u64(),
+                "estimated_savings": report.estimated_savings.as_u64(),
+            }),
+        ),
+        TransactionEvent::VaultUnlocked(id) => ("VaultUnlocked", None, json!({ "vault_id": id })),
+        TransactionEvent::Error(error) => ("Error", None, json!({ "error": error })),
+    };
+
+    let payload = json!({
+        "id": event_id,
+        "event": name,
+        "tx_id": tx_id.map(|tx_id| tx_id.as_u64()),
+        "timestamp": timestamp,
+        "description": event.to_string(),
+        "details": details,
+    });
+    (name, payload)
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
+
+#[cfg(test)]
+mod test {
+    use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+
+    use hyper::{
+        header::HeaderMap,
+        service::{make_service_fn, service_fn},
+        Body,
+        Request,
+        Response,
+        Server,
+        StatusCode,
+    };
+    use minotari_wallet::transaction_service::handle::TransactionSendStatus;
+    use tari_common_types::transaction::TxId;
+    use tari_shutdown::Shutdown;
+    use tokio::{
+        sync::broadcast,
+        time::{sleep, timeout},
+    };
+
+    use super::*;
+
+    /// An HTTP endpoint that reports the headers and body of every request it receives and answers after `delay`
+    async fn test_endpoint(
+        delay: Duration,
+        status: StatusCode,
+    ) -> (String, mpsc::UnboundedReceiver<(HeaderMap, Vec<u8>)>) {
+        let (requests_tx, requests_rx) = mpsc::unbounded_channel();
+        let service = make_service_fn(move |_conn| {
+            let requests_tx = requests_tx.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                    let requests_tx = requests_tx.clone();
+                    async move {
+                        let (parts, body) = req.into_parts();
+                        let body = hyper::body::to_bytes(body).await.unwrap_or_default().to_vec();
+                        let _result = requests_tx.send((parts.headers, body));
+                        sleep(delay).await;
+                        Ok::<_, Infallible>(Response::builder().status(status).body(Body::empty()).unwrap())
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(service);
+        let url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        (url, requests_rx)
+    }
+
+    fn delivery_files(dir: &Path) -> Vec<String> {
+        let mut files = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|name| name.ends_with(".json"))
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn it_computes_hmac_sha256() {
+        // RFC 4231 test cases 2 and 6
+        assert_eq!(
+            hmac_sha256(b"Jefe", b"what do ya want for nothing?").to_hex(),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            hmac_sha256(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First").to_hex(),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    fn temp_queue_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("webhook_queue_{}", rand::random::<u64>()))
+    }
+
+    #[test]
+    fn it_queues_an_event_for_every_endpoint() {
+        let dir = temp_queue_dir();
+        let endpoints = vec!["http://a.example".to_string(), "http://b.example".to_string()];
+        let mut queue = DeliveryQueue::load(&dir).unwrap();
+        let event = TransactionEvent::TransactionSendResult(TxId::from(7u64), TransactionSendStatus {
+            direct_send_result: true,
+            store_and_forward_send_result: false,
+            queued_for_retry: false,
+        });
+        assert_eq!(queue.push(&event, &endpoints, 100).unwrap(), 0);
+        assert_eq!(
+            queue
+                .push(&TransactionEvent::Error("oops".to_string()), &endpoints, 101)
+                .unwrap(),
+            1
+        );
+
+        assert_eq!(queue.deliveries.len(), 4);
+        assert_eq!(queue.deliveries[1].endpoint, "http://b.example");
+        assert_eq!(queue.deliveries[0].event, "TransactionSendResult");
+        let payload: Value = serde_json::from_str(&queue.deliveries[0].payload).unwrap();
+        assert_eq!(payload["tx_id"], 7);
+        assert_eq!(payload["details"]["direct_send_result"], true);
+        let payload: Value = serde_json::from_str(&queue.deliveries[2].payload).unwrap();
+        assert_eq!(payload["id"], 1);
+        assert_eq!(payload["tx_id"], Value::Null);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_persists_every_delivery_separately() {
+        let dir = temp_queue_dir();
+        let mut queue = DeliveryQueue::load(&dir).unwrap();
+        assert!(queue.deliveries.is_empty());
+        let endpoints = ["http://a.example".to_string(), "http://b.example".to_string()];
+        queue
+            .push(
+                &TransactionEvent::ReceivedFinalizedTransaction(TxId::from(1u64)),
+                &endpoints,
+                100,
+            )
+            .unwrap();
+        assert_eq!(DeliveryQueue::load(&dir).unwrap(), queue);
+
+        let mut delivery = queue.deliveries[1].clone();
+        delivery.attempts = 1;
+        delivery.next_attempt = 200;
+        queue.update(delivery).unwrap();
+        queue.remove((0, 0)).unwrap();
+        let loaded = DeliveryQueue::load(&dir).unwrap();
+        assert_eq!(loaded, queue);
+        assert_eq!(loaded.deliveries.len(), 1);
+        assert_eq!(loaded.deliveries[0].attempts, 1);
+
+        // Event ids are not reused once the queue has drained
+        queue.remove((0, 1)).unwrap();
+        assert_eq!(DeliveryQueue::load(&dir).unwrap().next_event_id, 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn it_signs_the_payload_with_the_secret() {
+        let (url, mut requests) = test_endpoint(Duration::ZERO, StatusCode::OK).await;
+        let client = WebhookClient {
+            client: reqwest::Client::new(),
+            secret: Some(SafePassword::from("Jefe")),
+        };
+        let delivery = Delivery {
+            event_id: 3,
+            endpoint_index: 0,
+            event: "NewBlockMined".to_string(),
+            endpoint: url,
+            payload: "what do ya want for nothing?".to_string(),
+            attempts: 0,
+            next_attempt: 0,
+            last_error: None,
+        };
+        client.send(&delivery).await.unwrap();
+
+        let (headers, body) = requests.recv().await.unwrap();
+        assert_eq!(body, delivery.payload.as_bytes());
+        assert_eq!(headers[EVENT_HEADER], "NewBlockMined");
+        assert_eq!(headers[DELIVERY_HEADER], "3");
+        // RFC 4231 test case 2
+        assert_eq!(
+            headers[SIGNATURE_HEADER],
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[tokio::test]
+    async fn a_slow_endpoint_does_not_hold_up_new_events() {
+        let (url, mut requests) = test_endpoint(Duration::from_secs(60), StatusCode::OK).await;
+        let data_dir = temp_queue_dir();
+        let queue_dir = data_dir.join(WEBHOOK_DIR).join(QUEUE_DIR);
+        let config = WebhookConfig {
+            endpoints: vec![url].into(),
+            ..Default::default()
+        };
+        let (events_tx, events_rx) = broadcast::channel(10);
+        let notifier = WebhookNotifier::new(config, &data_dir, events_rx).unwrap();
+        let mut shutdown = Shutdown::new();
+        tokio::spawn(notifier.run(shutdown.to_signal()));
+
+        // The first delivery is stuck at the endpoint
+        events_tx
+            .send(Arc::new(TransactionEvent::NewBlockMined(TxId::from(1u64))))
+            .unwrap();
+        timeout(Duration::from_secs(5), requests.recv()).await.unwrap().unwrap();
+
+        // Later events are still queued and sent while it is in flight
+        for tx_id in 2..=4u64 {
+            events_tx
+                .send(Arc::new(TransactionEvent::NewBlockMined(TxId::from(tx_id))))
+                .unwrap();
+        }
+        timeout(Duration::from_secs(5), async {
+            while delivery_files(&queue_dir).len() < 4 {
+                sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("The events were not queued");
+        for _ in 2..=4 {
+            timeout(Duration::from_secs(5), requests.recv()).await.unwrap().unwrap();
+        }
+
+        shutdown.trigger();
+        fs::remove_dir_all(data_dir).unwrap();
+    }
+
+    #[test]
+    fn it_removes_only_the_file_of_the_finished_delivery() {
+        let data_dir = temp_queue_dir();
+        let queue_dir = data_dir.join(WEBHOOK_DIR).join(QUEUE_DIR);
+        let config = WebhookConfig {
+            endpoints: vec![
+                "http://a.example".to_string(),
+                "http://b.example".to_string(),
+                "http://c.example".to_string(),
+            ]
+            .into(),
+            max_attempts: 2,
+            ..Default::default()
+        };
+        let (_events_tx, events_rx) = broadcast::channel(1);
+        let mut notifier = WebhookNotifier::new(config, &data_dir, events_rx).unwrap();
+        notifier.enqueue(&TransactionEvent::NewBlockMined(TxId::from(1u64)));
+        let files = delivery_files(&queue_dir);
+        assert_eq!(files.len(), 3);
+        let untouched = fs::read(queue_dir.join(&files[2])).unwrap();
+
+        // A successful delivery removes its own file
+        notifier.handle_result((0, 0), Ok(()));
+        assert_eq!(delivery_files(&queue_dir), files[1..].to_vec());
+
+        // A failed delivery only rewrites its own file
+        notifier.handle_result((0, 1), Err("Endpoint is down".to_string()));
+        assert_eq!(delivery_files(&queue_dir), files[1..].to_vec());
+        let retried: Delivery = serde_json::from_slice(&fs::read(queue_dir.join(&files[1])).unwrap()).unwrap();
+        assert_eq!(retried.attempts, 1);
+        assert_eq!(retried.last_error.as_deref(), Some("Endpoint is down"));
+        assert_eq!(fs::read(queue_dir.join(&files[2])).unwrap(), untouched);
+
+        // Once it runs out of attempts it is moved to the dead-letter log
+        notifier.handle_result((0, 1), Err("Endpoint is down".to_string()));
+        assert_eq!(delivery_files(&queue_dir), files[2..].to_vec());
+        assert_eq!(fs::read(queue_dir.join(&files[2])).unwrap(), untouched);
+        let dead_letters = fs::read_to_string(data_dir.join(WEBHOOK_DIR).join(DEAD_LETTER_FILE)).unwrap();
+        let dead_letters = dead_letters
+            .lines()
+            .map(|line| serde_json::from_str::<Delivery>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].key(), (0, 1));
+        assert_eq!(dead_letters[0].attempts, 2);
+        fs::remove_dir_all(data_dir).unwrap();
+    }
+}
@@ -88,12 +88,14 @@ pub fn get_seed_from_seed_words(seed_words: &SeedWords) -> Result<CipherSeed, Ex
 
 /// Recovers wallet funds by connecting to a given base node peer, downloading the transaction outputs stored in the
 /// blockchain, and attempting to rewind them. Any outputs that are successfully rewound are then imported into the
-/// wallet.
+/// wallet. When more than one peer is configured, the height range is split into `segment_size` block segments that
+/// are scanned concurrently from all peers.
 #[allow(clippy::too_many_lines)]
 pub async fn wallet_recovery(
     wallet: &WalletSqlite,
     base_node_config: &PeerConfig,
     retry_limit: usize,
+    segment_size: u64,
 ) -> Result<(), ExitError> {
     println!("\nPress Ctrl-C to stop the recovery process\n");
     // We dont care about the shutdown signal here, so we just create one
@@ -122,6 +124,7 @@ pub async fn wallet_recovery(
         .with_peers(peer_public_keys)
         // Do not make this a small number as wallet recovery needs to be resilient
         .with_retry_limit(retry_limit)
+        .with_recovery_segment_size(segment_size)
         .build_with_wallet(wallet, shutdown_signal);
 
     let mut event_stream = recovery_task.get_event_receiver();
@@ -207,6 +210,10 @@ pub async fn wallet_recovery(
             Ok(UtxoScannerEvent::ScanningFailed) => {
                 error!(target: LOG_TARGET, "Wallet Recovery process failed and is exiting");
             },
+            // Rescans are requested through the wallet's scanner service handle and are never run by recovery
+            Ok(UtxoScannerEvent::RescanProgress { .. }) |
+            Ok(UtxoScannerEvent::RescanCompleted { .. }) |
+            Ok(UtxoScannerEvent::RescanFailed { .. }) => {},
         }
     }
 
@@ -20,6 +20,7 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use log::*;
 use minotari_wallet::{util::wallet_identity::WalletIdentity, WalletConfig, WalletSqlite};
 use tari_comms::peer_manager::Peer;
 use tokio::runtime::Handle;
@@ -33,6 +34,7 @@ use crate::{
     notifier::Notifier,
     ui::{
         components::{
+            analytics_tab::AnalyticsTab,
             base_node::BaseNode,
             burn_tab::BurnTab,
             contacts_tab::ContactsTab,
@@ -41,8 +43,10 @@ use crate::{
             menu::Menu,
             network_tab::NetworkTab,
             notification_tab::NotificationTab,
+            pending_spends_tab::PendingSpendsTab,
             receive_tab::ReceiveTab,
             register_template_tab::RegisterTemplateTab,
+            scheduled_payments_tab::ScheduledPaymentsTab,
             send_tab::SendTab,
             tabs_container::TabsContainer,
             transactions_tab::TransactionsTab,
@@ -93,6 +97,9 @@ impl<B: Backend> App<B> {
             .add("Burn".into(), Box::new(BurnTab::new(&app_state)))
             .add("Templates".into(), Box::new(RegisterTemplateTab::new(&app_state)))
             .add("Contacts".into(), Box::new(ContactsTab::new()))
+            .add("Scheduled".into(), Box::new(ScheduledPaymentsTab::new()))
+            .add("Approvals".into(), Box::new(PendingSpendsTab::new()))
+            .add("Analytics".into(), Box::new(AnalyticsTab::new()))
             .add("Network".into(), Box::new(NetworkTab::new(base_node_selected)))
             .add("Events".into(), Box::new(EventsComponent::new()))
             .add("Log".into(), Box::new(LogTab::new()))
@@ -117,6 +124,11 @@ impl<B: Backend> App<B> {
             'q' | 'c' => {
                 self.should_quit = true;
             },
+            'a' => {
+                if let Err(e) = Handle::current().block_on(self.app_state.select_next_account()) {
+                    warn!(target: LOG_TARGET, "Could not switch account: {}", e);
+                }
+            },
             _ => (),
         }
     }
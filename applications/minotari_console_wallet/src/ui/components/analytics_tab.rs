@@ -0,0 +1,231 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Charts the balance history of the wallet and lists the incoming and outgoing volume and fees paid per day.
+
+use minotari_wallet::{
+    output_manager_service::storage::models::BalanceSnapshot,
+    transaction_service::analytics::PeriodVolume,
+};
+use tari_core::transactions::tari_amount::MicroMinotari;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    symbols,
+    text::Span,
+    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, ListItem, Paragraph},
+    Frame,
+};
+
+use crate::ui::{
+    components::Component,
+    state::{AppState, ANALYTICS_DAYS},
+    widgets::{MultiColumnList, WindowedListState},
+    MAX_WIDTH,
+};
+
+/// A day is highlighted when it sent more than this many times the average daily outgoing volume
+const OUTGOING_ANOMALY_FACTOR: u64 = 3;
+
+pub struct AnalyticsTab {
+    volume_list_state: WindowedListState,
+}
+
+impl AnalyticsTab {
+    pub fn new() -> Self {
+        Self {
+            volume_list_state: WindowedListState::new(),
+        }
+    }
+
+    fn draw_balance_chart<B>(&self, f: &mut Frame<B>, area: Rect, app_state: &AppState)
+    where B: Backend {
+        let block = Block::default().borders(Borders::ALL).title(Span::styled(
+            "Balance History",
+            Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+        ));
+        let history = app_state.get_balance_history();
+        if history.is_empty() {
+            let paragraph =
+                Paragraph::new("No balance history yet. A snapshot is recorded every time the balance changes.")
+                    .block(block);
+            f.render_widget(paragraph, area);
+            return;
+        }
+
+        let available = step_points(history, |s| s.available_balance);
+        let total = step_points(history, BalanceSnapshot::total_balance);
+        let min_height = history.first().map(|s| s.height).unwrap_or_default() as f64;
+        let max_height = history.last().map(|s| s.height).unwrap_or_default() as f64;
+        let max_balance = total.iter().map(|(_, b)| *b).fold(0.0, f64::max);
+        // Leave some headroom above the highest balance and never collapse an axis to a single point
+        let max_height = if max_height > min_height {
+            max_height
+        } else {
+            min_height + 1.0
+        };
+        let max_balance = if max_balance > 0.0 { max_balance * 1.1 } else { 1.0 };
+
+        let datasets = vec![
+            Dataset::default()
+                .name("Available")
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::Green))
+                .data(&available),
+            Dataset::default()
+                .name("Incl. pending incoming")
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::Cyan))
+                .data(&total),
+        ];
+        let chart = Chart::new(datasets)
+            .block(block)
+            .x_axis(
+                Axis::default()
+                    .title("Height")
+                    .style(Style::default().fg(Color::Gray))
+                    .bounds([min_height, max_height])
+                    .labels(vec![
+                        Span::raw(format!("{:.0}", min_height)),
+                        Span::raw(format!("{:.0}", (min_height + max_height) / 2.0)),
+                        Span::raw(format!("{:.0}", max_height)),
+                    ]),
+            )
+            .y_axis(
+                Axis::default()
+                    .title("Tari")
+                    .style(Style::default().fg(Color::Gray))
+                    .bounds([0.0, max_balance])
+                    .labels(vec![
+                        Span::raw("0"),
+                        Span::raw(format!("{:.2}", max_balance / 2.0)),
+                        Span::raw(format!("{:.2}", max_balance)),
+                    ]),
+            );
+        f.render_widget(chart, area);
+    }
+
+    fn draw_volume<B>(&mut self, f: &mut Frame<B>, area: Rect, app_state: &AppState)
+    where B: Backend {
+        let block = Block::default().borders(Borders::ALL).title(Span::styled(
+            format!("Daily Volume (last {} days)", ANALYTICS_DAYS),
+            Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+        ));
+        f.render_widget(block, area);
+        let list_area = Layout::default()
+            .constraints([Constraint::Min(1)].as_ref())
+            .margin(1)
+            .split(area);
+
+        let volume = app_state.get_transaction_volume();
+        let average_outgoing = if volume.is_empty() {
+            0
+        } else {
+            volume.iter().map(|v| v.outgoing).sum::<u64>() / volume.len() as u64
+        };
+        self.volume_list_state.set_num_items(volume.len());
+        let mut list_state = self
+            .volume_list_state
+            .update_list_state((list_area[0].height as usize).saturating_sub(1));
+        let window = self.volume_list_state.get_start_end();
+        let windowed_view = app_state.get_transaction_volume_slice(window.0, window.1);
+
+        let column_list = AnalyticsTab::create_column_view(windowed_view, average_outgoing, volume.len());
+        column_list.render(f, list_area[0], &mut list_state);
+    }
+
+    // Helper function to create the column list to be rendered
+    fn create_column_view(
+        windowed_view: &[PeriodVolume],
+        average_outgoing: u64,
+        num_periods: usize,
+    ) -> MultiColumnList<Vec<ListItem>> {
+        let mut column0_items = Vec::new();
+        let mut column1_items = Vec::new();
+        let mut column2_items = Vec::new();
+        let mut column3_items = Vec::new();
+        let mut column4_items = Vec::new();
+        for v in windowed_view {
+            // The average is meaningless with only a couple of days of history
+            let is_anomaly = num_periods >= 3 && v.outgoing > average_outgoing.saturating_mul(OUTGOING_ANOMALY_FACTOR);
+            let outgoing_style = if is_anomaly {
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            column0_items.push(ListItem::new(Span::raw(v.period_start.format("%Y-%m-%d").to_string())));
+            column1_items.push(ListItem::new(Span::styled(
+                format!("{} ({})", MicroMinotari::from(v.incoming), v.incoming_count),
+                Style::default().fg(Color::Green),
+            )));
+            column2_items.push(ListItem::new(Span::styled(
+                format!("{} ({})", MicroMinotari::from(v.outgoing), v.outgoing_count),
+                outgoing_style,
+            )));
+            column3_items.push(ListItem::new(Span::raw(MicroMinotari::from(v.fees).to_string())));
+            column4_items.push(ListItem::new(Span::raw(if is_anomaly {
+                format!("Over {}x the daily average", OUTGOING_ANOMALY_FACTOR)
+            } else {
+                String::new()
+            })));
+        }
+        MultiColumnList::new()
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD).fg(Color::Magenta))
+            .heading_style(Style::default().fg(Color::Magenta))
+            .max_width(MAX_WIDTH)
+            .add_column(Some("Day"), Some(11), column0_items)
+            .add_column(None, Some(1), Vec::new())
+            .add_column(Some("Incoming (count)"), Some(26), column1_items)
+            .add_column(None, Some(1), Vec::new())
+            .add_column(Some("Outgoing (count)"), Some(26), column2_items)
+            .add_column(None, Some(1), Vec::new())
+            .add_column(Some("Fees"), Some(18), column3_items)
+            .add_column(None, Some(1), Vec::new())
+            .add_column(Some("Note"), Some(28), column4_items)
+    }
+}
+
+/// The points of a step chart of `value`, in Tari. A snapshot is only recorded when the balance changes, so the value
+/// holds until the next snapshot.
+fn step_points<F>(history: &[BalanceSnapshot], value: F) -> Vec<(f64, f64)>
+where F: Fn(&BalanceSnapshot) -> MicroMinotari {
+    let to_tari = |s: &BalanceSnapshot| value(s).as_u64() as f64 / 1_000_000.0;
+    let mut points = Vec::with_capacity(history.len() * 2);
+    for (i, snapshot) in history.iter().enumerate() {
+        if i > 0 {
+            points.push((snapshot.height as f64, to_tari(&history[i - 1])));
+        }
+        points.push((snapshot.height as f64, to_tari(snapshot)));
+    }
+    points
+}
+
+impl<B: Backend> Component<B> for AnalyticsTab {
+    fn draw(&mut self, f: &mut Frame<B>, area: Rect, app_state: &AppState) {
+        let areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(55), Constraint::Percentage(45)].as_ref())
+            .split(area);
+        self.draw_balance_chart(f, areas[0], app_state);
+        self.draw_volume(f, areas[1], app_state);
+    }
+
+    fn on_up(&mut self, app_state: &mut AppState) {
+        self.volume_list_state
+            .set_num_items(app_state.get_transaction_volume().len());
+        self.volume_list_state.previous();
+    }
+
+    fn on_down(&mut self, app_state: &mut AppState) {
+        self.volume_list_state
+            .set_num_items(app_state.get_transaction_volume().len());
+        self.volume_list_state.next();
+    }
+
+    fn on_esc(&mut self, _: &mut AppState) {
+        self.volume_list_state.select(None);
+    }
+}
@@ -42,8 +42,14 @@ impl<B: Backend> Component<B> for Balance {
             .horizontal_margin(1)
             .split(block_title_body[1]);
 
+        let title = match app_state.get_selected_account() {
+            Some(account) if app_state.get_accounts().len() > 1 => {
+                format!("Balance - Account: {} (Ctrl-A to switch)", account.name)
+            },
+            _ => "Balance".to_string(),
+        };
         let block = Block::default().borders(Borders::ALL).title(Span::styled(
-            "Balance",
+            title,
             Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
         ));
         f.render_widget(block, area);
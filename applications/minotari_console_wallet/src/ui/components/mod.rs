@@ -33,10 +33,13 @@ mod styles;
 pub mod tabs_container;
 pub mod transactions_tab;
 pub use self::component::*;
+pub mod analytics_tab;
 pub mod burn_tab;
 pub mod contacts_tab;
 pub mod events_component;
+pub mod pending_spends_tab;
 pub mod register_template_tab;
+pub mod scheduled_payments_tab;
 
 #[derive(PartialEq, Eq)]
 pub enum KeyHandled {
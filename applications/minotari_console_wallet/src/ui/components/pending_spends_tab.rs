@@ -0,0 +1,247 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Lists the transactions held by the spending policy and lets the user approve or reject them.
+
+use minotari_wallet::transaction_service::spending_policy::PendingSpend;
+use tokio::runtime::Handle;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, ListItem, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::{
+    ui::{
+        components::{Component, KeyHandled},
+        state::AppState,
+        widgets::{draw_dialog, MultiColumnList, WindowedListState},
+        MAX_WIDTH,
+    },
+    utils::formatting::display_compressed_string,
+};
+
+pub struct PendingSpendsTab {
+    error_message: Option<String>,
+    spends_list_state: WindowedListState,
+    confirmation_dialog: Option<ConfirmationDialogType>,
+}
+
+impl PendingSpendsTab {
+    pub fn new() -> Self {
+        Self {
+            error_message: None,
+            spends_list_state: WindowedListState::new(),
+            confirmation_dialog: None,
+        }
+    }
+
+    fn draw_pending_spends<B>(&mut self, f: &mut Frame<B>, area: Rect, app_state: &AppState)
+    where B: Backend {
+        let block = Block::default().borders(Borders::ALL).title(Span::styled(
+            "Transactions Held For Approval",
+            Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+        ));
+        f.render_widget(block, area);
+        let list_areas = Layout::default()
+            .constraints([Constraint::Length(1), Constraint::Min(42)].as_ref())
+            .margin(1)
+            .split(area);
+
+        let instructions = Paragraph::new(Spans::from(vec![
+            Span::raw("Use "),
+            Span::styled("Up↑/Down↓ Keys", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" to select a transaction, "),
+            Span::styled("A", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" to (a)pprove and send it and "),
+            Span::styled("R", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" to (r)eject it."),
+        ]))
+        .wrap(Wrap { trim: true });
+        f.render_widget(instructions, list_areas[0]);
+        self.spends_list_state
+            .set_num_items(app_state.get_pending_spends().len());
+        let mut list_state = self
+            .spends_list_state
+            .update_list_state((list_areas[1].height as usize).saturating_sub(3));
+        let window = self.spends_list_state.get_start_end();
+        let windowed_view = app_state.get_pending_spends_slice(window.0, window.1);
+
+        let column_list = PendingSpendsTab::create_column_view(windowed_view);
+        column_list.render(f, list_areas[1], &mut list_state);
+    }
+
+    // Helper function to create the column list to be rendered
+    pub fn create_column_view(windowed_view: &[PendingSpend]) -> MultiColumnList<Vec<ListItem>> {
+        let mut column0_items = Vec::new();
+        let mut column1_items = Vec::new();
+        let mut column2_items = Vec::new();
+        let mut column3_items = Vec::new();
+        let mut column4_items = Vec::new();
+        let mut column5_items = Vec::new();
+        for s in windowed_view.iter() {
+            column0_items.push(ListItem::new(Span::raw(format!("#{}", s.id))));
+            column1_items.push(ListItem::new(Span::raw(s.kind.to_string())));
+            column2_items.push(ListItem::new(Span::raw(
+                s.destination
+                    .as_ref()
+                    .map(|d| display_compressed_string(d.to_hex(), 8, 8))
+                    .unwrap_or_default(),
+            )));
+            column3_items.push(ListItem::new(Span::raw(s.amount.to_string())));
+            column4_items.push(ListItem::new(Span::raw(s.message.clone())));
+            column5_items.push(ListItem::new(Span::raw(
+                s.created_at.format("%Y-%m-%d %H:%M").to_string(),
+            )));
+        }
+        let column_list = MultiColumnList::new()
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD).fg(Color::Magenta))
+            .heading_style(Style::default().fg(Color::Magenta))
+            .max_width(MAX_WIDTH)
+            .add_column(Some("Id"), Some(5), column0_items)
+            .add_column(None, Some(1), Vec::new())
+            .add_column(Some("Kind"), Some(17), column1_items)
+            .add_column(None, Some(1), Vec::new())
+            .add_column(Some("Destination"), Some(19), column2_items)
+            .add_column(None, Some(1), Vec::new())
+            .add_column(Some("Amount"), Some(18), column3_items)
+            .add_column(None, Some(1), Vec::new())
+            .add_column(Some("Message"), Some(40), column4_items)
+            .add_column(None, Some(1), Vec::new())
+            .add_column(Some("Held Since"), Some(17), column5_items);
+
+        column_list
+    }
+
+    fn on_key_confirmation_dialog(&mut self, c: char, app_state: &mut AppState) -> KeyHandled {
+        let dialog = match self.confirmation_dialog {
+            None => return KeyHandled::NotHandled,
+            Some(dialog) => dialog,
+        };
+        if 'y' == c {
+            if let Some(s) = self
+                .spends_list_state
+                .selected()
+                .and_then(|i| app_state.get_pending_spend(i))
+                .cloned()
+            {
+                let result = match dialog {
+                    ConfirmationDialogType::Approve => {
+                        Handle::current().block_on(app_state.approve_pending_spend(s.id))
+                    },
+                    ConfirmationDialogType::Reject => Handle::current().block_on(app_state.reject_pending_spend(s.id)),
+                };
+                if let Err(e) = result {
+                    self.error_message = Some(format!(
+                        "Could not update the selected transaction: {}\nPress Enter to continue.",
+                        e
+                    ));
+                }
+                self.spends_list_state.select(None);
+            }
+            self.confirmation_dialog = None;
+        } else if 'n' == c {
+            self.confirmation_dialog = None;
+        } else {
+            // dont care
+        }
+        KeyHandled::Handled
+    }
+}
+
+impl<B: Backend> Component<B> for PendingSpendsTab {
+    fn draw(&mut self, f: &mut Frame<B>, area: Rect, app_state: &AppState) {
+        self.draw_pending_spends(f, area, app_state);
+
+        if let Some(msg) = self.error_message.clone() {
+            draw_dialog(f, area, "Error!".to_string(), msg, Color::Red, 120, 9);
+        }
+
+        let reason = self
+            .spends_list_state
+            .selected()
+            .and_then(|i| app_state.get_pending_spend(i))
+            .map(|s| s.reason.clone())
+            .unwrap_or_default();
+        match self.confirmation_dialog {
+            None => (),
+            Some(ConfirmationDialogType::Approve) => {
+                draw_dialog(
+                    f,
+                    area,
+                    "Confirm Approval".to_string(),
+                    format!(
+                        "{}.\nAre you sure you want to approve and send this transaction?\n(Y)es / (N)o",
+                        reason
+                    ),
+                    Color::Red,
+                    120,
+                    9,
+                );
+            },
+            Some(ConfirmationDialogType::Reject) => {
+                draw_dialog(
+                    f,
+                    area,
+                    "Confirm Rejection".to_string(),
+                    "Are you sure you want to reject this transaction?\n(Y)es / (N)o".to_string(),
+                    Color::Red,
+                    120,
+                    9,
+                );
+            },
+        }
+    }
+
+    fn on_key(&mut self, app_state: &mut AppState, c: char) {
+        if self.error_message.is_some() {
+            if '\n' == c {
+                self.error_message = None;
+            }
+            return;
+        }
+
+        if self.on_key_confirmation_dialog(c, app_state) == KeyHandled::Handled {
+            return;
+        }
+
+        let selected = self
+            .spends_list_state
+            .selected()
+            .and_then(|i| app_state.get_pending_spend(i))
+            .is_some();
+        match c {
+            'a' if selected => self.confirmation_dialog = Some(ConfirmationDialogType::Approve),
+            'r' if selected => self.confirmation_dialog = Some(ConfirmationDialogType::Reject),
+            _ => {},
+        }
+    }
+
+    fn on_up(&mut self, app_state: &mut AppState) {
+        self.spends_list_state
+            .set_num_items(app_state.get_pending_spends().len());
+        self.spends_list_state.previous();
+    }
+
+    fn on_down(&mut self, app_state: &mut AppState) {
+        self.spends_list_state
+            .set_num_items(app_state.get_pending_spends().len());
+        self.spends_list_state.next();
+    }
+
+    fn on_esc(&mut self, _: &mut AppState) {
+        if self.confirmation_dialog.is_some() {
+            return;
+        }
+        self.spends_list_state.select(None);
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ConfirmationDialogType {
+    Approve,
+    Reject,
+}
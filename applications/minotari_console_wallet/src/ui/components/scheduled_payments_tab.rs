@@ -0,0 +1,465 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Lists the wallet's scheduled payments and lets the user create and cancel them.
+
+use std::{convert::TryFrom, time::Duration};
+
+use chrono::Utc;
+use minotari_wallet::transaction_service::scheduled_payment::{
+    PaymentSchedule,
+    ScheduledPayment,
+    ScheduledPaymentStatus,
+};
+use tari_core::transactions::tari_amount::MicroMinotari;
+use tokio::runtime::Handle;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, ListItem, Paragraph, Wrap},
+    Frame,
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    ui::{
+        components::{Component, KeyHandled},
+        state::AppState,
+        widgets::{centered_rect_absolute, draw_dialog, MultiColumnList, WindowedListState},
+        MAX_WIDTH,
+    },
+    utils::formatting::display_compressed_string,
+};
+
+pub struct ScheduledPaymentsTab {
+    input_mode: ScheduledPaymentInputMode,
+    show_new_payment: bool,
+    address_field: String,
+    amount_field: String,
+    start_field: String,
+    interval_field: String,
+    one_sided_field: String,
+    error_message: Option<String>,
+    payments_list_state: WindowedListState,
+    confirmation_dialog: Option<ConfirmationDialogType>,
+}
+
+impl ScheduledPaymentsTab {
+    pub fn new() -> Self {
+        Self {
+            input_mode: ScheduledPaymentInputMode::None,
+            show_new_payment: false,
+            address_field: String::new(),
+            amount_field: String::new(),
+            start_field: String::new(),
+            interval_field: String::new(),
+            one_sided_field: String::new(),
+            error_message: None,
+            payments_list_state: WindowedListState::new(),
+            confirmation_dialog: None,
+        }
+    }
+
+    fn draw_scheduled_payments<B>(&mut self, f: &mut Frame<B>, area: Rect, app_state: &AppState)
+    where B: Backend {
+        let block = Block::default().borders(Borders::ALL).title(Span::styled(
+            "Scheduled Payments",
+            Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+        ));
+        f.render_widget(block, area);
+        let list_areas = Layout::default()
+            .constraints([Constraint::Length(1), Constraint::Min(42)].as_ref())
+            .margin(1)
+            .split(area);
+
+        let instructions = Paragraph::new(Spans::from(vec![
+            Span::raw("Use "),
+            Span::styled("Up↑/Down↓ Keys", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" to select a payment, "),
+            Span::styled("D", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" to cancel (d)elete a payment and "),
+            Span::styled("N", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" to schedule a (n)ew payment."),
+        ]))
+        .wrap(Wrap { trim: true });
+        f.render_widget(instructions, list_areas[0]);
+        self.payments_list_state
+            .set_num_items(app_state.get_scheduled_payments().len());
+        let mut list_state = self
+            .payments_list_state
+            .update_list_state((list_areas[1].height as usize).saturating_sub(3));
+        let window = self.payments_list_state.get_start_end();
+        let windowed_view = app_state.get_scheduled_payments_slice(window.0, window.1);
+
+        let column_list = ScheduledPaymentsTab::create_column_view(windowed_view);
+        column_list.render(f, list_areas[1], &mut list_state);
+    }
+
+    // Helper function to create the column list to be rendered
+    pub fn create_column_view(windowed_view: &[ScheduledPayment]) -> MultiColumnList<Vec<ListItem>> {
+        let mut column0_items = Vec::new();
+        let mut column1_items = Vec::new();
+        let mut column2_items = Vec::new();
+        let mut column3_items = Vec::new();
+        let mut column4_items = Vec::new();
+        let mut column5_items = Vec::new();
+        let mut column6_items = Vec::new();
+        for p in windowed_view.iter() {
+            let status_style = match p.status {
+                ScheduledPaymentStatus::Active if p.last_error.is_some() => Style::default().fg(Color::Yellow),
+                ScheduledPaymentStatus::Active => Style::default().fg(Color::Green),
+                ScheduledPaymentStatus::Failed => Style::default().fg(Color::Red),
+                ScheduledPaymentStatus::Completed | ScheduledPaymentStatus::Cancelled => {
+                    Style::default().fg(Color::DarkGray)
+                },
+            };
+            column0_items.push(ListItem::new(Span::raw(format!("#{}", p.id))));
+            column1_items.push(ListItem::new(Span::raw(display_compressed_string(
+                p.destination.to_hex(),
+                8,
+                8,
+            ))));
+            column2_items.push(ListItem::new(Span::raw(p.amount.to_string())));
+            column3_items.push(ListItem::new(Span::raw(p.schedule.to_string())));
+            column4_items.push(ListItem::new(Span::raw(
+                p.next_run_at
+                    .map(|next_run_at| next_run_at.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default(),
+            )));
+            column5_items.push(ListItem::new(Span::raw(p.run_count.to_string())));
+            column6_items.push(ListItem::new(Span::styled(p.status.to_string(), status_style)));
+        }
+        let column_list = MultiColumnList::new()
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD).fg(Color::Magenta))
+            .heading_style(Style::default().fg(Color::Magenta))
+            .max_width(MAX_WIDTH)
+            .add_column(Some("Id"), Some(5), column0_items)
+            .add_column(None, Some(1), Vec::new())
+            .add_column(Some("Destination"), Some(19), column1_items)
+            .add_column(None, Some(1), Vec::new())
+            .add_column(Some("Amount"), Some(18), column2_items)
+            .add_column(None, Some(1), Vec::new())
+            .add_column(Some("Schedule"), Some(40), column3_items)
+            .add_column(None, Some(1), Vec::new())
+            .add_column(Some("Next Payment"), Some(17), column4_items)
+            .add_column(None, Some(1), Vec::new())
+            .add_column(Some("Sent"), Some(5), column5_items)
+            .add_column(None, Some(1), Vec::new())
+            .add_column(Some("Status"), Some(10), column6_items);
+
+        column_list
+    }
+
+    // casting here is okay as we only use it to draw widths
+    #[allow(clippy::cast_possible_truncation)]
+    fn draw_new_payment<B>(&mut self, f: &mut Frame<B>, area: Rect)
+    where B: Backend {
+        let popup_area = centered_rect_absolute(120, 19, area);
+
+        f.render_widget(Clear, popup_area);
+
+        let block = Block::default().borders(Borders::ALL).title(Span::styled(
+            "Schedule Payment",
+            Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+        ));
+        f.render_widget(block, popup_area);
+        let vert_chunks = Layout::default()
+            .constraints(
+                [
+                    Constraint::Length(2),
+                    Constraint::Length(3),
+                    Constraint::Length(3),
+                    Constraint::Length(3),
+                    Constraint::Length(3),
+                    Constraint::Length(3),
+                ]
+                .as_ref(),
+            )
+            .margin(1)
+            .split(popup_area);
+
+        let instructions = Paragraph::new(Spans::from(vec![
+            Span::raw("Press "),
+            Span::styled("Tab", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" or "),
+            Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" to move to the next field, "),
+            Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" on the last field to schedule the payment."),
+        ]))
+        .block(Block::default());
+        f.render_widget(instructions, vert_chunks[0]);
+
+        let fields = [
+            (
+                ScheduledPaymentInputMode::Address,
+                &self.address_field,
+                "Tari Address or Emoji ID:",
+            ),
+            (ScheduledPaymentInputMode::Amount, &self.amount_field, "Amount:"),
+            (
+                ScheduledPaymentInputMode::Start,
+                &self.start_field,
+                "Starts in (minutes, empty for now):",
+            ),
+            (
+                ScheduledPaymentInputMode::Interval,
+                &self.interval_field,
+                "Repeat every (hours, empty for once):",
+            ),
+            (
+                ScheduledPaymentInputMode::OneSided,
+                &self.one_sided_field,
+                "One-sided (y/n, default n):",
+            ),
+        ];
+        for (i, (mode, value, title)) in fields.iter().enumerate() {
+            let input = Paragraph::new(value.as_str())
+                .style(if self.input_mode == *mode {
+                    Style::default().fg(Color::Magenta)
+                } else {
+                    Style::default()
+                })
+                .block(Block::default().borders(Borders::ALL).title(*title));
+            f.render_widget(input, vert_chunks[i + 1]);
+            if self.input_mode == *mode {
+                f.set_cursor(
+                    // Put cursor past the end of the input text
+                    vert_chunks[i + 1].x + value.width() as u16 + 1,
+                    // Move one line down, from the border to the input line
+                    vert_chunks[i + 1].y + 1,
+                );
+            }
+        }
+    }
+
+    fn schedule_payment(&mut self, app_state: &mut AppState) -> Result<(), String> {
+        let amount = self
+            .amount_field
+            .parse::<MicroMinotari>()
+            .map_err(|_| "Invalid amount".to_string())?;
+        let start_minutes = parse_optional_number(&self.start_field)
+            .and_then(|minutes| u32::try_from(minutes).ok())
+            .ok_or_else(|| "Invalid start".to_string())?;
+        let interval_hours =
+            parse_optional_number(&self.interval_field).ok_or_else(|| "Invalid interval".to_string())?;
+        let start = Utc::now().naive_utc() + chrono::Duration::minutes(i64::from(start_minutes));
+        let schedule = match interval_hours {
+            0 => PaymentSchedule::AtTime(start),
+            hours => PaymentSchedule::Recurring {
+                start,
+                interval: Duration::from_secs(hours.saturating_mul(60 * 60)),
+            },
+        };
+        let one_sided = self.one_sided_field.trim().eq_ignore_ascii_case("y");
+        Handle::current()
+            .block_on(app_state.create_scheduled_payment(self.address_field.clone(), amount, schedule, one_sided))
+            .map_err(|e| format!("Could not schedule the payment: {}", e))
+    }
+
+    fn clear_fields(&mut self) {
+        self.address_field.clear();
+        self.amount_field.clear();
+        self.start_field.clear();
+        self.interval_field.clear();
+        self.one_sided_field.clear();
+    }
+
+    fn selected_field(&mut self) -> Option<&mut String> {
+        match self.input_mode {
+            ScheduledPaymentInputMode::None => None,
+            ScheduledPaymentInputMode::Address => Some(&mut self.address_field),
+            ScheduledPaymentInputMode::Amount => Some(&mut self.amount_field),
+            ScheduledPaymentInputMode::Start => Some(&mut self.start_field),
+            ScheduledPaymentInputMode::Interval => Some(&mut self.interval_field),
+            ScheduledPaymentInputMode::OneSided => Some(&mut self.one_sided_field),
+        }
+    }
+
+    fn on_key_confirmation_dialog(&mut self, c: char, app_state: &mut AppState) -> KeyHandled {
+        match self.confirmation_dialog {
+            None => KeyHandled::NotHandled,
+            Some(ConfirmationDialogType::CancelPayment) => {
+                if 'y' == c {
+                    if let Some(p) = self
+                        .payments_list_state
+                        .selected()
+                        .and_then(|i| app_state.get_scheduled_payment(i))
+                        .cloned()
+                    {
+                        if let Err(e) = Handle::current().block_on(app_state.cancel_scheduled_payment(p.id)) {
+                            self.error_message = Some(format!(
+                                "Could not cancel the selected payment: {}\nPress Enter to continue.",
+                                e
+                            ));
+                        }
+                    }
+                    self.confirmation_dialog = None;
+                } else if 'n' == c {
+                    self.confirmation_dialog = None;
+                } else {
+                    // dont care
+                }
+                KeyHandled::Handled
+            },
+        }
+    }
+
+    fn on_key_new_payment(&mut self, c: char, app_state: &mut AppState) -> KeyHandled {
+        if !self.show_new_payment {
+            return KeyHandled::NotHandled;
+        }
+        match (c, self.input_mode.next()) {
+            ('\n', None) => {
+                self.input_mode = ScheduledPaymentInputMode::None;
+                self.show_new_payment = false;
+                if let Err(e) = self.schedule_payment(app_state) {
+                    self.error_message = Some(format!("{}\nPress Enter to continue.", e));
+                }
+                self.clear_fields();
+            },
+            ('\n' | '\t', Some(next)) => self.input_mode = next,
+            ('\t', None) => self.input_mode = ScheduledPaymentInputMode::Address,
+            (c, _) => {
+                if let Some(field) = self.selected_field() {
+                    field.push(c);
+                }
+            },
+        }
+        KeyHandled::Handled
+    }
+}
+
+impl<B: Backend> Component<B> for ScheduledPaymentsTab {
+    fn draw(&mut self, f: &mut Frame<B>, area: Rect, app_state: &AppState) {
+        self.draw_scheduled_payments(f, area, app_state);
+        if self.show_new_payment {
+            self.draw_new_payment(f, area);
+        }
+
+        if let Some(msg) = self.error_message.clone() {
+            draw_dialog(f, area, "Error!".to_string(), msg, Color::Red, 120, 9);
+        }
+
+        match self.confirmation_dialog {
+            None => (),
+            Some(ConfirmationDialogType::CancelPayment) => {
+                draw_dialog(
+                    f,
+                    area,
+                    "Confirm Cancel".to_string(),
+                    "Are you sure you want to cancel this scheduled payment?\n(Y)es / (N)o".to_string(),
+                    Color::Red,
+                    120,
+                    9,
+                );
+            },
+        }
+    }
+
+    fn on_key(&mut self, app_state: &mut AppState, c: char) {
+        if self.error_message.is_some() {
+            if '\n' == c {
+                self.error_message = None;
+            }
+            return;
+        }
+
+        if self.on_key_confirmation_dialog(c, app_state) == KeyHandled::Handled {
+            return;
+        }
+
+        if self.on_key_new_payment(c, app_state) == KeyHandled::Handled {
+            return;
+        }
+
+        match c {
+            'd' => {
+                if let Some(p) = self
+                    .payments_list_state
+                    .selected()
+                    .and_then(|i| app_state.get_scheduled_payment(i))
+                {
+                    if p.status == ScheduledPaymentStatus::Active {
+                        self.confirmation_dialog = Some(ConfirmationDialogType::CancelPayment);
+                    }
+                }
+            },
+            'n' => {
+                self.show_new_payment = true;
+                self.input_mode = ScheduledPaymentInputMode::Address;
+            },
+            _ => {},
+        }
+    }
+
+    fn on_up(&mut self, app_state: &mut AppState) {
+        self.payments_list_state
+            .set_num_items(app_state.get_scheduled_payments().len());
+        self.payments_list_state.previous();
+    }
+
+    fn on_down(&mut self, app_state: &mut AppState) {
+        self.payments_list_state
+            .set_num_items(app_state.get_scheduled_payments().len());
+        self.payments_list_state.next();
+    }
+
+    fn on_esc(&mut self, _: &mut AppState) {
+        if self.confirmation_dialog.is_some() {
+            return;
+        }
+        if self.show_new_payment {
+            self.show_new_payment = false;
+            self.input_mode = ScheduledPaymentInputMode::None;
+            self.clear_fields();
+        } else {
+            self.payments_list_state.select(None);
+        }
+    }
+
+    fn on_backspace(&mut self, _app_state: &mut AppState) {
+        if let Some(field) = self.selected_field() {
+            let _ = field.pop();
+        }
+    }
+}
+
+/// Parses a number of minutes or hours, treating an empty field as zero
+fn parse_optional_number(value: &str) -> Option<u64> {
+    match value.trim() {
+        "" => Some(0),
+        value => value.parse().ok(),
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ScheduledPaymentInputMode {
+    None,
+    Address,
+    Amount,
+    Start,
+    Interval,
+    OneSided,
+}
+
+impl ScheduledPaymentInputMode {
+    /// The field after this one in the form, or `None` for the last field
+    fn next(self) -> Option<Self> {
+        match self {
+            ScheduledPaymentInputMode::None => None,
+            ScheduledPaymentInputMode::Address => Some(ScheduledPaymentInputMode::Amount),
+            ScheduledPaymentInputMode::Amount => Some(ScheduledPaymentInputMode::Start),
+            ScheduledPaymentInputMode::Start => Some(ScheduledPaymentInputMode::Interval),
+            ScheduledPaymentInputMode::Interval => Some(ScheduledPaymentInputMode::OneSided),
+            ScheduledPaymentInputMode::OneSided => None,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum ConfirmationDialogType {
+    CancelPayment,
+}
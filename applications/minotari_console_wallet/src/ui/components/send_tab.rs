@@ -2,7 +2,6 @@
 // SPDX-License-Identifier: BSD-3-Clause
 
 use log::*;
-use minotari_wallet::output_manager_service::UtxoSelectionCriteria;
 use tari_core::transactions::tari_amount::MicroMinotari;
 use tari_utilities::hex::Hex;
 use tokio::{runtime::Handle, sync::watch};
@@ -264,6 +263,7 @@ impl SendTab {
                                 return KeyHandled::Handled;
                             };
 
+                            let selection_criteria = app_state.get_selection_criteria();
                             let (tx, rx) = watch::channel(UiTransactionSendStatus::Initiated);
 
                             let mut reset_fields = false;
@@ -272,7 +272,7 @@ impl SendTab {
                                     match Handle::current().block_on(app_state.send_one_sided_transaction(
                                         self.to_field.clone(),
                                         amount.into(),
-                                        UtxoSelectionCriteria::default(),
+                                        selection_criteria,
                                         fee_per_gram,
                                         self.message_field.clone(),
                                         tx,
@@ -291,7 +291,7 @@ impl SendTab {
                                         app_state.send_one_sided_to_stealth_address_transaction(
                                             self.to_field.clone(),
                                             amount.into(),
-                                            UtxoSelectionCriteria::default(),
+                                            selection_criteria,
                                             fee_per_gram,
                                             self.message_field.clone(),
                                             tx,
@@ -311,7 +311,7 @@ impl SendTab {
                                     match Handle::current().block_on(app_state.send_transaction(
                                         self.to_field.clone(),
                                         amount.into(),
-                                        UtxoSelectionCriteria::default(),
+                                        selection_criteria,
                                         fee_per_gram,
                                         self.message_field.clone(),
                                         tx,
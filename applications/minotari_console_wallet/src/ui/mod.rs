@@ -61,6 +61,14 @@ pub fn run(app: App<CrosstermBackend<Stdout>>) -> Result<(), ExitError> {
             app.app_state.refresh_contacts_state().await?;
             trace!(target: LOG_TARGET, "Refreshing burnt proofs state");
             app.app_state.refresh_burnt_proofs_state().await?;
+            trace!(target: LOG_TARGET, "Refreshing accounts state");
+            app.app_state.refresh_accounts_state().await?;
+            trace!(target: LOG_TARGET, "Refreshing scheduled payments state");
+            app.app_state.refresh_scheduled_payments_state().await?;
+            trace!(target: LOG_TARGET, "Refreshing pending spends state");
+            app.app_state.refresh_pending_spends_state().await?;
+            trace!(target: LOG_TARGET, "Refreshing analytics state");
+            app.app_state.refresh_analytics_state().await?;
             trace!(target: LOG_TARGET, "Refreshing connected peers state");
             app.app_state.refresh_connected_peers_state().await?;
             trace!(target: LOG_TARGET, "Checking connectivity");
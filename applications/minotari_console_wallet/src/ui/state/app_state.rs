@@ -28,14 +28,22 @@ use std::{
 };
 
 use bitflags::bitflags;
-use chrono::{DateTime, Local, NaiveDateTime};
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
 use log::*;
 use minotari_wallet::{
     base_node_service::{handle::BaseNodeEventReceiver, service::BaseNodeState},
     connectivity_service::{OnlineStatus, WalletConnectivityHandle, WalletConnectivityInterface},
-    output_manager_service::{handle::OutputManagerEventReceiver, service::Balance, UtxoSelectionCriteria},
+    output_manager_service::{
+        handle::OutputManagerEventReceiver,
+        service::Balance,
+        storage::models::{Account, AccountId, BalanceSnapshot, DEFAULT_ACCOUNT_ID},
+        UtxoSelectionCriteria,
+    },
     transaction_service::{
+        analytics::{volume_per_period, PeriodVolume, SECONDS_PER_DAY},
         handle::TransactionEventReceiver,
+        scheduled_payment::{PaymentSchedule, ScheduledPayment, ScheduledPaymentId},
+        spending_policy::{PendingSpend, PendingSpendId},
         storage::models::{CompletedTransaction, TxCancellationReason},
     },
     util::wallet_identity::WalletIdentity,
@@ -92,6 +100,9 @@ use crate::{
 
 const LOG_TARGET: &str = "wallet::console_wallet::app_state";
 
+/// The number of days of transaction volume shown in the analytics tab
+pub const ANALYTICS_DAYS: i64 = 30;
+
 #[derive(Clone)]
 pub struct AppState {
     inner: Arc<RwLock<AppStateInner>>,
@@ -179,6 +190,53 @@ impl AppState {
         Ok(())
     }
 
+    pub async fn refresh_accounts_state(&mut self) -> Result<(), UiError> {
+        let mut inner = self.inner.write().await;
+        inner.refresh_accounts_state().await?;
+        drop(inner);
+        self.update_cache().await;
+        Ok(())
+    }
+
+    pub async fn refresh_scheduled_payments_state(&mut self) -> Result<(), UiError> {
+        let mut inner = self.inner.write().await;
+        inner.refresh_scheduled_payments_state().await?;
+        drop(inner);
+        self.update_cache().await;
+        Ok(())
+    }
+
+    pub async fn refresh_pending_spends_state(&mut self) -> Result<(), UiError> {
+        let mut inner = self.inner.write().await;
+        inner.refresh_pending_spends_state().await?;
+        drop(inner);
+        self.update_cache().await;
+        Ok(())
+    }
+
+    pub async fn refresh_analytics_state(&mut self) -> Result<(), UiError> {
+        let mut inner = self.inner.write().await;
+        inner.refresh_analytics_state().await?;
+        drop(inner);
+        self.update_cache().await;
+        Ok(())
+    }
+
+    /// Switches to the next account, wrapping around to the default account, and requests its balance
+    pub async fn select_next_account(&mut self) -> Result<(), UiError> {
+        let mut inner = self.inner.write().await;
+        inner.refresh_accounts_state().await?;
+        inner.select_next_account();
+        drop(inner);
+        self.balance_enquiry_debouncer
+            .clone()
+            .get_sender()
+            .send(())
+            .map_err(|e| UiError::SendError(e.to_string()))?;
+        self.update_cache().await;
+        Ok(())
+    }
+
     pub async fn refresh_connected_peers_state(&mut self) -> Result<(), UiError> {
         self.check_connectivity().await;
         let mut inner = self.inner.write().await;
@@ -290,6 +348,72 @@ impl AppState {
         Ok(())
     }
 
+    pub async fn create_scheduled_payment(
+        &mut self,
+        address: String,
+        amount: MicroMinotari,
+        schedule: PaymentSchedule,
+        one_sided: bool,
+    ) -> Result<(), UiError> {
+        let mut inner = self.inner.write().await;
+        let address = match TariAddress::from_emoji_string(&address) {
+            Ok(address) => address,
+            Err(_) => TariAddress::from_bytes(&from_hex(&address).map_err(|_| UiError::PublicKeyParseError)?)
+                .map_err(|_| UiError::PublicKeyParseError)?,
+        };
+
+        inner
+            .wallet
+            .transaction_service
+            .create_scheduled_payment(
+                address,
+                amount,
+                self.wallet_config.fee_per_gram.into(),
+                "Scheduled payment".to_string(),
+                one_sided,
+                schedule,
+                None,
+            )
+            .await?;
+
+        inner.refresh_scheduled_payments_state().await?;
+        drop(inner);
+        self.update_cache().await;
+        Ok(())
+    }
+
+    pub async fn cancel_scheduled_payment(&mut self, id: ScheduledPaymentId) -> Result<(), UiError> {
+        let mut inner = self.inner.write().await;
+        inner.wallet.transaction_service.cancel_scheduled_payment(id).await?;
+
+        inner.refresh_scheduled_payments_state().await?;
+        drop(inner);
+        self.update_cache().await;
+        Ok(())
+    }
+
+    pub async fn approve_pending_spend(&mut self, id: PendingSpendId) -> Result<(), UiError> {
+        let mut inner = self.inner.write().await;
+        let result = inner.wallet.transaction_service.approve_pending_spend(id).await;
+
+        // The spend is no longer held, even if sending it failed
+        inner.refresh_pending_spends_state().await?;
+        drop(inner);
+        self.update_cache().await;
+        result?;
+        Ok(())
+    }
+
+    pub async fn reject_pending_spend(&mut self, id: PendingSpendId) -> Result<(), UiError> {
+        let mut inner = self.inner.write().await;
+        inner.wallet.transaction_service.reject_pending_spend(id).await?;
+
+        inner.refresh_pending_spends_state().await?;
+        drop(inner);
+        self.update_cache().await;
+        Ok(())
+    }
+
     pub async fn send_transaction(
         &mut self,
         address: String,
@@ -595,6 +719,70 @@ impl AppState {
         &self.cached_data.balance
     }
 
+    pub fn get_accounts(&self) -> &[Account] {
+        &self.cached_data.accounts
+    }
+
+    pub fn get_scheduled_payments(&self) -> &[ScheduledPayment] {
+        &self.cached_data.scheduled_payments
+    }
+
+    pub fn get_scheduled_payment(&self, index: usize) -> Option<&ScheduledPayment> {
+        self.cached_data.scheduled_payments.get(index)
+    }
+
+    pub fn get_scheduled_payments_slice(&self, start: usize, end: usize) -> &[ScheduledPayment] {
+        if self.cached_data.scheduled_payments.is_empty() || start >= end {
+            return &[];
+        }
+        &self.cached_data.scheduled_payments[start..end]
+    }
+
+    pub fn get_pending_spends(&self) -> &[PendingSpend] {
+        &self.cached_data.pending_spends
+    }
+
+    pub fn get_pending_spend(&self, index: usize) -> Option<&PendingSpend> {
+        self.cached_data.pending_spends.get(index)
+    }
+
+    pub fn get_pending_spends_slice(&self, start: usize, end: usize) -> &[PendingSpend] {
+        if self.cached_data.pending_spends.is_empty() || start >= end {
+            return &[];
+        }
+        &self.cached_data.pending_spends[start..end]
+    }
+
+    /// The recorded balance snapshots, ordered by height
+    pub fn get_balance_history(&self) -> &[BalanceSnapshot] {
+        &self.cached_data.balance_history
+    }
+
+    /// The transaction volume per day of the last `ANALYTICS_DAYS` days, most recent first
+    pub fn get_transaction_volume(&self) -> &[PeriodVolume] {
+        &self.cached_data.transaction_volume
+    }
+
+    pub fn get_transaction_volume_slice(&self, start: usize, end: usize) -> &[PeriodVolume] {
+        if self.cached_data.transaction_volume.is_empty() || start >= end {
+            return &[];
+        }
+        &self.cached_data.transaction_volume[start..end]
+    }
+
+    /// The account that the balance is shown for and that transactions are funded from
+    pub fn get_selected_account(&self) -> Option<&Account> {
+        self.cached_data
+            .accounts
+            .iter()
+            .find(|a| a.id == self.cached_data.selected_account)
+    }
+
+    /// Selection criteria that only spend from the selected account
+    pub fn get_selection_criteria(&self) -> UtxoSelectionCriteria {
+        UtxoSelectionCriteria::default().with_account(self.cached_data.selected_account)
+    }
+
     pub fn get_base_node_state(&self) -> &BaseNodeState {
         &self.cached_data.base_node_state
     }
@@ -954,6 +1142,54 @@ impl AppStateInner {
         Ok(())
     }
 
+    pub async fn refresh_accounts_state(&mut self) -> Result<(), UiError> {
+        let accounts = self.wallet.output_manager_service.get_accounts().await?;
+        if !accounts.iter().any(|a| a.id == self.data.selected_account) {
+            self.data.selected_account = DEFAULT_ACCOUNT_ID;
+        }
+        self.data.accounts = accounts;
+        self.updated = true;
+        Ok(())
+    }
+
+    pub async fn refresh_scheduled_payments_state(&mut self) -> Result<(), UiError> {
+        self.data.scheduled_payments = self.wallet.transaction_service.get_scheduled_payments().await?;
+        self.updated = true;
+        Ok(())
+    }
+
+    pub async fn refresh_pending_spends_state(&mut self) -> Result<(), UiError> {
+        self.data.pending_spends = self.wallet.transaction_service.get_pending_spends().await?;
+        self.updated = true;
+        Ok(())
+    }
+
+    pub async fn refresh_analytics_state(&mut self) -> Result<(), UiError> {
+        self.data.balance_history = self.wallet.output_manager_service.get_balance_history(0, None).await?;
+        let transactions = self.wallet.transaction_service.get_completed_transactions().await?;
+        let since = Utc::now().naive_utc() - chrono::Duration::days(ANALYTICS_DAYS);
+        let mut volume = volume_per_period(transactions.into_values(), SECONDS_PER_DAY, Some(since));
+        volume.reverse();
+        self.data.transaction_volume = volume;
+        self.updated = true;
+        Ok(())
+    }
+
+    fn select_next_account(&mut self) {
+        let accounts = &self.data.accounts;
+        let next = accounts
+            .iter()
+            .position(|a| a.id == self.data.selected_account)
+            .and_then(|i| accounts.get(i + 1))
+            .map_or(DEFAULT_ACCOUNT_ID, |a| a.id);
+        self.data.selected_account = next;
+        self.updated = true;
+    }
+
+    pub fn selected_account(&self) -> AccountId {
+        self.data.selected_account
+    }
+
     pub fn has_time_locked_balance(&self) -> bool {
         if let Some(time_locked_balance) = self.data.balance.time_locked_balance {
             if time_locked_balance > MicroMinotari::from(0) {
@@ -1226,6 +1462,12 @@ struct AppStateData {
     burnt_proofs: Vec<UiBurntProof>,
     connected_peers: Vec<Peer>,
     balance: Balance,
+    accounts: Vec<Account>,
+    selected_account: AccountId,
+    scheduled_payments: Vec<ScheduledPayment>,
+    pending_spends: Vec<PendingSpend>,
+    balance_history: Vec<BalanceSnapshot>,
+    transaction_volume: Vec<PeriodVolume>,
     base_node_state: BaseNodeState,
     base_node_selected: Peer,
     base_node_previous: Peer,
@@ -1305,6 +1547,12 @@ impl AppStateData {
             burnt_proofs: vec![],
             connected_peers: Vec::new(),
             balance: Balance::zero(),
+            accounts: Vec::new(),
+            selected_account: DEFAULT_ACCOUNT_ID,
+            scheduled_payments: Vec::new(),
+            pending_spends: Vec::new(),
+            balance_history: Vec::new(),
+            transaction_volume: Vec::new(),
             base_node_state: BaseNodeState::default(),
             base_node_selected,
             base_node_previous,
@@ -26,7 +26,11 @@ use std::{
 };
 
 use log::*;
-use minotari_wallet::output_manager_service::handle::OutputManagerHandle;
+use minotari_wallet::output_manager_service::{
+    error::OutputManagerError,
+    handle::OutputManagerHandle,
+    service::Balance,
+};
 use tokio::{
     sync::{broadcast, RwLock},
     time,
@@ -69,7 +73,7 @@ impl BalanceEnquiryDebouncer {
         tokio::pin!(interval);
 
         debug!(target: LOG_TARGET, "Balance enquiry debouncer starting");
-        if let Ok(balance) = self.output_manager_service.get_balance().await {
+        if let Ok(balance) = self.get_balance().await {
             trace!(
                 target: LOG_TARGET,
                 "Initial balance: available {}, incoming {}, outgoing {}",
@@ -95,7 +99,7 @@ impl BalanceEnquiryDebouncer {
                         match result {
                             Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {
                                 let start_time = Instant::now();
-                                match self.output_manager_service.get_balance().await {
+                                match self.get_balance().await {
                                     Ok(balance) => {
                                         trace!(
                                             target: LOG_TARGET,
@@ -136,6 +140,12 @@ impl BalanceEnquiryDebouncer {
         }
     }
 
+    /// The balance of the account selected in the UI
+    async fn get_balance(&mut self) -> Result<Balance, OutputManagerError> {
+        let account_id = self.app_state_inner.read().await.selected_account();
+        self.output_manager_service.get_account_balance(account_id).await
+    }
+
     pub fn get_sender(self) -> broadcast::Sender<()> {
         self.tx
     }
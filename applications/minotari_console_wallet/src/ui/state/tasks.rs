@@ -37,6 +37,7 @@ use tari_common_types::{
 };
 use tari_core::{
     consensus::{DomainSeparatedConsensusHasher, MaxSizeBytes, MaxSizeString},
+    covenants::Covenant,
     transactions::{
         tari_amount::MicroMinotari,
         transaction_components::{BuildInfo, OutputFeatures, TemplateType},
@@ -149,6 +150,7 @@ pub async fn send_one_sided_transaction_task(
             amount,
             selection_criteria,
             output_features,
+            Covenant::default(),
             fee_per_gram,
             message,
         )
@@ -203,6 +205,7 @@ pub async fn send_one_sided_to_stealth_address_transaction(
             amount,
             selection_criteria,
             output_features,
+            Covenant::default(),
             fee_per_gram,
             message,
         )
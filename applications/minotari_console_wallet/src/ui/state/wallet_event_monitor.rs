@@ -173,6 +173,37 @@ impl WalletEventMonitor {
                                     self.trigger_full_tx_state_refresh().await;
                                     self.trigger_balance_refresh();
                                 },
+                                TransactionEvent::ScheduledPaymentExecuted{id, tx_id} => {
+                                    self.trigger_scheduled_payments_refresh().await;
+                                    self.add_notification(
+                                        format!("Scheduled Payment #{} Sent - TxId: {}", id, tx_id)
+                                    ).await;
+                                },
+                                TransactionEvent::ScheduledPaymentFailed{id, reason} => {
+                                    self.trigger_scheduled_payments_refresh().await;
+                                    self.add_notification(
+                                        format!("Scheduled Payment #{} Failed: {}", id, reason)
+                                    ).await;
+                                },
+                                TransactionEvent::SpendHeldForApproval(id) => {
+                                    self.trigger_pending_spends_refresh().await;
+                                    self.add_notification(
+                                        format!("Transaction held for approval as Pending Spend #{}", id)
+                                    ).await;
+                                },
+                                TransactionEvent::SpendDenied(reason) => {
+                                    self.add_notification(format!("Spending Policy Denied a Transaction: {}", reason)).await;
+                                },
+                                TransactionEvent::DustConsolidated(report) => {
+                                    self.trigger_tx_state_refresh(report.tx_id).await;
+                                    self.trigger_balance_refresh();
+                                    self.add_notification(format!(
+                                        "Consolidated {} Dust Outputs - TxId: {}", report.num_inputs, report.tx_id
+                                    )).await;
+                                },
+                                TransactionEvent::VaultUnlocked(id) => {
+                                    self.add_notification(format!("Vault #{} Can Now Be Spent", id)).await;
+                                },
                                 // Only the above variants trigger state refresh
                                 _ => (),
                             }
@@ -245,8 +276,12 @@ impl WalletEventMonitor {
                     match result {
                         Ok(msg) => {
                             trace!(target: LOG_TARGET, "Output Manager Service Callback Handler event {:?}", msg);
-                            if let OutputManagerEvent::TxoValidationSuccess(_) = &*msg {
-                                self.trigger_balance_refresh();
+                            match &*msg {
+                                OutputManagerEvent::TxoValidationSuccess(_) => self.trigger_balance_refresh(),
+                                OutputManagerEvent::BalanceSnapshotRecorded(_) => {
+                                    self.trigger_analytics_refresh().await;
+                                },
+                                _ => {},
                             }
                         },
                         Err(broadcast::error::RecvError::Lagged(n)) => {
@@ -318,6 +353,30 @@ impl WalletEventMonitor {
         }
     }
 
+    async fn trigger_scheduled_payments_refresh(&mut self) {
+        let mut inner = self.app_state_inner.write().await;
+
+        if let Err(e) = inner.refresh_scheduled_payments_state().await {
+            warn!(target: LOG_TARGET, "Error refresh app_state: {}", e);
+        }
+    }
+
+    async fn trigger_analytics_refresh(&mut self) {
+        let mut inner = self.app_state_inner.write().await;
+
+        if let Err(e) = inner.refresh_analytics_state().await {
+            warn!(target: LOG_TARGET, "Error refresh app_state: {}", e);
+        }
+    }
+
+    async fn trigger_pending_spends_refresh(&mut self) {
+        let mut inner = self.app_state_inner.write().await;
+
+        if let Err(e) = inner.refresh_pending_spends_state().await {
+            warn!(target: LOG_TARGET, "Error refresh app_state: {}", e);
+        }
+    }
+
     async fn trigger_peer_state_refresh(&mut self) {
         let mut inner = self.app_state_inner.write().await;
 
@@ -22,9 +22,14 @@
 
 #![allow(dead_code, unused)]
 
-use std::{fs, io::Stdout, path::PathBuf};
+use std::{convert::Infallible, fs, io::Stdout, path::PathBuf, sync::Arc};
 
 use clap::Parser;
+use futures::future;
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Server as HttpServer,
+};
 use log::*;
 use minotari_app_grpc::authentication::ServerAuthenticationInterceptor;
 use minotari_wallet::{WalletConfig, WalletSqlite};
@@ -40,6 +45,7 @@ use crate::{
     automation::commands::command_runner,
     cli::{Cli, CliCommands},
     grpc::WalletGrpcServer,
+    json_rpc::WalletJsonRpcServer,
     notifier::Notifier,
     recovery::wallet_recovery,
     ui,
@@ -282,6 +288,9 @@ pub fn tui_mode(
             ));
         }
     }
+    if let Some((json_rpc, address)) = create_json_rpc_server(config, &wallet)? {
+        handle.spawn(run_json_rpc(json_rpc, address, wallet.clone()));
+    }
 
     let notifier = Notifier::new(
         config.notify_file.clone(),
@@ -345,6 +354,7 @@ pub fn recovery_mode(
         &wallet,
         base_node_config,
         wallet_config.recovery_retry_limit,
+        wallet_config.recovery_segment_size,
     )) {
         Ok(_) => println!("Wallet recovered!"),
         Err(e) => {
@@ -376,15 +386,24 @@ pub fn recovery_mode(
 
 pub fn grpc_mode(handle: Handle, config: &WalletConfig, wallet: WalletSqlite) -> Result<(), ExitError> {
     info!(target: LOG_TARGET, "Starting grpc server");
+    let json_rpc = create_json_rpc_server(config, &wallet)?;
     if let Some(address) = config.grpc_address.as_ref().filter(|_| config.grpc_enabled).cloned() {
         let grpc = WalletGrpcServer::new(wallet.clone()).map_err(|e| ExitError {
             exit_code: ExitCode::UnknownError,
             details: Some(e.to_string()),
         })?;
+        if let Some((json_rpc, json_rpc_address)) = json_rpc {
+            handle.spawn(run_json_rpc(json_rpc, json_rpc_address, wallet.clone()));
+        }
         let auth = config.grpc_authentication.clone();
         handle
             .block_on(run_grpc(grpc, address, auth, wallet))
             .map_err(|e| ExitError::new(ExitCode::GrpcError, e))?;
+    } else if let Some((json_rpc, json_rpc_address)) = json_rpc {
+        println!("GRPC server is disabled");
+        handle
+            .block_on(run_json_rpc(json_rpc, json_rpc_address, wallet))
+            .map_err(|e| ExitError::new(ExitCode::InterfaceError, e))?;
     } else {
         println!("GRPC server is disabled");
     }
@@ -421,8 +440,58 @@ async fn run_grpc(
     Ok(())
 }
 
+/// Creates the JSON-RPC server if it is enabled. It shares the GRPC credentials of the wallet.
+fn create_json_rpc_server(
+    config: &WalletConfig,
+    wallet: &WalletSqlite,
+) -> Result<Option<(WalletJsonRpcServer, Multiaddr)>, ExitError> {
+    let address = match config.json_rpc_address.as_ref().filter(|_| config.json_rpc_enabled) {
+        Some(address) => address.clone(),
+        None => return Ok(None),
+    };
+    let grpc = WalletGrpcServer::new(wallet.clone()).map_err(|e| ExitError {
+        exit_code: ExitCode::UnknownError,
+        details: Some(e.to_string()),
+    })?;
+    Ok(Some((
+        WalletJsonRpcServer::new(grpc, config.grpc_authentication.clone()),
+        address,
+    )))
+}
+
+async fn run_json_rpc(
+    json_rpc: WalletJsonRpcServer,
+    json_rpc_listener_addr: Multiaddr,
+    wallet: WalletSqlite,
+) -> Result<(), String> {
+    info!(target: LOG_TARGET, "Starting JSON-RPC on {}", json_rpc_listener_addr);
+    let address = multiaddr_to_socketaddr(&json_rpc_listener_addr).map_err(|e| e.to_string())?;
+    let json_rpc = Arc::new(json_rpc);
+    let service = make_service_fn(move |_conn| {
+        let json_rpc = json_rpc.clone();
+        future::ready(Result::<_, Infallible>::Ok(service_fn(move |req| {
+            let json_rpc = json_rpc.clone();
+            async move { Result::<_, Infallible>::Ok(json_rpc.handle_http_request(req).await) }
+        })))
+    });
+
+    HttpServer::try_bind(&address)
+        .map_err(|e| format!("JSON-RPC server could not bind to {}: {}", address, e))?
+        .serve(service)
+        .with_graceful_shutdown(wallet.wait_until_shutdown())
+        .await
+        .map_err(|e| format!("JSON-RPC server returned error:{}", e))?;
+
+    info!(target: LOG_TARGET, "Stopping JSON-RPC");
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
+    use std::time::Duration;
+
+    use minotari_wallet::output_manager_service::UtxoSelectionOrdering;
+    use tari_core::transactions::tari_amount::MicroMinotari;
 
     use crate::{cli::CliCommands, wallet_modes::parse_command_file};
 
@@ -437,12 +506,28 @@ mod test {
 
             discover-peer f6b2ca781342a3ebe30ee1643655c96f1d7c14f4d49f077695395de98ae73665
 
-            send-minotari --message Our_secret! 125T 5c4f2a4b3f3f84e047333218a84fd24f581a9d7e4f23b78e3714e9d174427d615e
+            send-minotari --message Our_secret! --account savings 125T \
+                      5c4f2a4b3f3f84e047333218a84fd24f581a9d7e4f23b78e3714e9d174427d615e
             
             burn-minotari --message Ups_these_funds_will_be_burned! 100T
 
+            send-one-sided --utxo-selection privacy 1T \
+                      5c4f2a4b3f3f84e047333218a84fd24f581a9d7e4f23b78e3714e9d174427d615e
+
             coin-split --message Make_many_dust_UTXOs! --fee-per-gram 2 0.001T 499
 
+            freeze-utxos 5c4f2a4b3f3f84e047333218a84fd24f581a9d7e4f23b78e3714e9d174427d61 \
+                      f6b2ca781342a3ebe30ee1643655c96f1d7c14f4d49f077695395de98ae73665
+
+            label-transaction --label Invoice_17 --tag rent --tag 2023 12345
+
+            create-account savings
+
+            create-payment-request --memo Order_42 --expires-in 3600 25T
+
+            schedule-payment --every 2592000 --max-runs 12 --message Rent 1000T \
+                      5c4f2a4b3f3f84e047333218a84fd24f581a9d7e4f23b78e3714e9d174427d615e
+
             make-it-rain --duration 100 --transactions-per-second 10 --start-amount 0.009200T --increase-amount 0T \
                       --start-time now --message Stressing_it_a_bit...!_(from_Feeling-a-bit-Generous) \
                       5c4f2a4b3f3f84e047333218a84fd24f581a9d7e4f23b78e3714e9d174427d615e
@@ -460,12 +545,24 @@ mod test {
         let mut coin_split = false;
         let mut discover_peer = false;
         let mut whois = false;
+        let mut send_one_sided = false;
+        let mut freeze_utxos = false;
+        let mut label_transaction = false;
+        let mut create_account = false;
+        let mut create_payment_request = false;
+        let mut schedule_payment = false;
         for command in commands {
             match command {
                 CliCommands::GetBalance => get_balance = true,
-                CliCommands::SendMinotari(_) => send_tari = true,
+                CliCommands::SendMinotari(args) => {
+                    assert_eq!(args.account.as_deref(), Some("savings"));
+                    send_tari = true;
+                },
                 CliCommands::BurnMinotari(_) => burn_tari = true,
-                CliCommands::SendOneSided(_) => {},
+                CliCommands::SendOneSided(args) => {
+                    assert_eq!(args.utxo_selection, UtxoSelectionOrdering::PrivacyPreserving);
+                    send_one_sided = true;
+                },
                 CliCommands::SendOneSidedToStealthAddress(_) => {},
                 CliCommands::MakeItRain(_) => make_it_rain = true,
                 CliCommands::CoinSplit(_) => coin_split = true,
@@ -483,8 +580,64 @@ mod test {
                 CliCommands::RevalidateWalletDb => {},
                 CliCommands::HashGrpcPassword(_) => {},
                 CliCommands::RegisterValidatorNode(_) => {},
+                CliCommands::FreezeUtxos(args) => {
+                    assert_eq!(args.commitments.len(), 2);
+                    freeze_utxos = true;
+                },
+                CliCommands::UnfreezeUtxos(_) => {},
+                CliCommands::ListFrozenUtxos => {},
+                CliCommands::LabelTransaction(args) => {
+                    assert_eq!(args.tx_id, 12345);
+                    assert_eq!(args.label.as_deref(), Some("Invoice_17"));
+                    assert_eq!(args.tag, vec!["rent".to_string(), "2023".to_string()]);
+                    label_transaction = true;
+                },
+                CliCommands::LabelOutput(_) => {},
+                CliCommands::ExportTransactions(_) => {},
+                CliCommands::CreateAccount(args) => {
+                    assert_eq!(args.name, "savings");
+                    create_account = true;
+                },
+                CliCommands::ListAccounts => {},
+                CliCommands::BackupWallet(_) => {},
+                CliCommands::Rescan(_) => {},
+                CliCommands::CreatePaymentRequest(args) => {
+                    assert_eq!(args.amount, MicroMinotari::from(25_000_000));
+                    assert_eq!(args.memo, "Order_42");
+                    assert_eq!(args.expires_in, Some(Duration::from_secs(3600)));
+                    create_payment_request = true;
+                },
+                CliCommands::ListPaymentRequests => {},
+                CliCommands::CancelPaymentRequest(_) => {},
+                CliCommands::PayPaymentRequest(_) => {},
+                CliCommands::SchedulePayment(args) => {
+                    assert_eq!(args.every, Some(Duration::from_secs(2_592_000)));
+                    assert_eq!(args.max_runs, Some(12));
+                    assert_eq!(args.at_height, None);
+                    assert_eq!(args.message, "Rent");
+                    schedule_payment = true;
+                },
+                CliCommands::ListScheduledPayments => {},
+                CliCommands::CancelScheduledPayment(_) => {},
+                CliCommands::CreateVault(_) => {},
+                CliCommands::ListVaults => {},
+                CliCommands::SpendVault(_) => {},
             }
         }
-        assert!(get_balance && send_tari && burn_tari && make_it_rain && coin_split && discover_peer && whois);
+        assert!(
+            get_balance &&
+                send_tari &&
+                burn_tari &&
+                send_one_sided &&
+                make_it_rain &&
+                coin_split &&
+                discover_peer &&
+                whois &&
+                freeze_utxos &&
+                label_transaction &&
+                create_account &&
+                create_payment_request &&
+                schedule_payment
+        );
     }
 }
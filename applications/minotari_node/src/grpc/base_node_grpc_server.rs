@@ -49,7 +49,7 @@ use tari_core::{
     iterators::NonOverlappingIntegerPairIter,
     mempool::{service::LocalMempoolService, TxStorageResponse},
     proof_of_work::PowAlgorithm,
-    transactions::transaction_components::Transaction,
+    transactions::transaction_components::{Transaction, TransactionInput, TransactionOutput},
 };
 use tari_p2p::{auto_update::SoftwareUpdaterHandle, services::liveness::LivenessHandle};
 use tari_utilities::{hex::Hex, message_format::MessageFormat, ByteArray};
@@ -1744,6 +1744,89 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
         );
         Ok(Response::new(rx))
     }
+
+    async fn dry_run_covenant(
+        &self,
+        request: Request<tari_rpc::DryRunCovenantRequest>,
+    ) -> Result<Response<tari_rpc::DryRunCovenantResponse>, Status> {
+        let request = request.into_inner();
+        let report_error_flag = self.report_error_flag();
+        debug!(target: LOG_TARGET, "Incoming GRPC request for DryRunCovenant");
+        let mut handler = self.node_service.clone();
+
+        let mut input = match request.input {
+            Some(input) => TransactionInput::try_from(input)
+                .map_err(|e| Status::invalid_argument(format!("Invalid input: {}", e)))?,
+            None => TransactionInput::new_with_output_hash(
+                FixedHash::try_from(request.output_hash)
+                    .map_err(|_| Status::invalid_argument("Invalid output_hash"))?,
+                Default::default(),
+                Default::default(),
+            ),
+        };
+        // A compact input only has the hash of the output it spends, so the covenant is read from the chain
+        if input.is_compact() {
+            let output = handler
+                .fetch_matching_utxos(vec![input.output_hash()])
+                .await
+                .map_err(|e| obscure_error_if_true(report_error_flag, Status::internal(e.to_string())))?
+                .pop()
+                .ok_or_else(|| Status::not_found("The input does not spend an unspent output in the chain"))?;
+            let rangeproof_hash = output.proof.as_ref().map_or_else(FixedHash::zero, |proof| proof.hash());
+            input.add_output_data(
+                output.version,
+                output.features,
+                output.commitment,
+                output.script,
+                output.sender_offset_public_key,
+                output.covenant,
+                output.encrypted_data,
+                output.metadata_signature,
+                rangeproof_hash,
+                output.minimum_value_promise,
+            );
+        }
+        let outputs = request
+            .outputs
+            .into_iter()
+            .map(TransactionOutput::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| Status::invalid_argument(format!("Invalid output: {}", e)))?;
+
+        let block_height = if request.block_height == 0 {
+            handler
+                .get_metadata()
+                .await
+                .map_err(|e| obscure_error_if_true(report_error_flag, Status::internal(e.to_string())))?
+                .height_of_longest_chain() +
+                1
+        } else {
+            request.block_height
+        };
+        let covenant = input
+            .covenant()
+            .map_err(|e| Status::invalid_argument(format!("Invalid input: {}", e)))?;
+        let trace = covenant.execute_with_trace(block_height, &input, &outputs);
+
+        Ok(Response::new(tari_rpc::DryRunCovenantResponse {
+            is_success: trace.is_success(),
+            failure_message: trace.result.as_ref().err().map(ToString::to_string).unwrap_or_default(),
+            covenant: covenant.to_string(),
+            matched_outputs: trace.matched_outputs.iter().map(|i| *i as u64).collect(),
+            steps: trace
+                .steps
+                .iter()
+                .map(|step| tari_rpc::CovenantFilterStep {
+                    depth: step.depth as u64,
+                    filter: step.filter_text(),
+                    outputs_in: step.outputs_in.iter().map(|i| *i as u64).collect(),
+                    outputs_out: step.outputs_out.iter().map(|i| *i as u64).collect(),
+                    error: step.error.as_ref().map(ToString::to_string).unwrap_or_default(),
+                })
+                .collect(),
+            block_height,
+        }))
+    }
 }
 
 enum BlockGroupType {
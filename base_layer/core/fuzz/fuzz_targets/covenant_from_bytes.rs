@@ -0,0 +1,40 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Decodes arbitrary bytes as a covenant. Any covenant that decodes must re-encode to canonical bytes that are no
+//! longer than the input and that decode to the same covenant, through both `from_bytes` and Borsh. Covenants that can
+//! be written as text must compile back to the same covenant.
+
+#![no_main]
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use libfuzzer_sys::fuzz_target;
+use tari_core::covenants::Covenant;
+
+/// The largest covenant that will be decoded, mirrors `MAX_COVENANT_BYTES` in `tari_core`
+const MAX_COVENANT_BYTES: usize = 4096;
+
+fuzz_target!(|data: &[u8]| {
+    let covenant = match Covenant::from_bytes(&mut &data[..]) {
+        Ok(covenant) => covenant,
+        Err(_) => return,
+    };
+    assert!(data.len() <= MAX_COVENANT_BYTES);
+
+    // Lengths may be decoded from non-canonical varints, so the input is only required to be at least as long as the
+    // canonical encoding, which must be a fixed point
+    let bytes = covenant.to_bytes();
+    assert!(bytes.len() <= data.len());
+    let decoded = Covenant::from_bytes(&mut bytes.as_slice()).expect("canonical covenant bytes must decode");
+    assert_eq!(decoded, covenant);
+    assert_eq!(decoded.to_bytes(), bytes);
+
+    let serialized = covenant.try_to_vec().expect("covenants always serialize");
+    let deserialized = Covenant::try_from_slice(&serialized).expect("decoded covenants must deserialize");
+    assert_eq!(deserialized, covenant);
+
+    // Tokens that do not form a single filter expression are written as text that does not compile
+    if let Ok(compiled) = Covenant::from_text(&covenant.to_text()) {
+        assert_eq!(compiled, covenant);
+    }
+});
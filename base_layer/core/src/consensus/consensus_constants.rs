@@ -34,6 +34,7 @@ use tari_utilities::epoch_time::EpochTime;
 use crate::{
     borsh::SerializedSize,
     consensus::network::NetworkConsensus,
+    covenants::CovenantFilterVersion,
     proof_of_work::{Difficulty, PowAlgorithm},
     transactions::{
         tari_amount::{uT, MicroMinotari, T},
@@ -122,6 +123,7 @@ pub struct OutputVersionRange {
     pub outputs: RangeInclusive<TransactionOutputVersion>,
     pub features: RangeInclusive<OutputFeaturesVersion>,
     pub opcode: RangeInclusive<OpcodeVersion>,
+    pub covenant_filter: RangeInclusive<CovenantFilterVersion>,
 }
 
 /// All V0 for Inputs, Outputs + Features, Kernels
@@ -136,6 +138,7 @@ fn version_zero() -> (
         outputs: TransactionOutputVersion::V0..=TransactionOutputVersion::V0,
         features: OutputFeaturesVersion::V0..=OutputFeaturesVersion::V0,
         opcode: OpcodeVersion::V0..=OpcodeVersion::V0,
+        covenant_filter: CovenantFilterVersion::V0..=CovenantFilterVersion::V0,
     };
 
     (input_version_range, output_version_range, kernel_version_range)
@@ -150,6 +153,8 @@ pub struct PowAlgorithmConstants {
 }
 
 const ESMERALDA_FAUCET_VALUE: u64 = 3_798_996_893_688_987;
+/// The igor height from which outputs may use the V1 opcodes and covenant filters
+const IGOR_SCRIPT_V1_HEIGHT: u64 = 25_000;
 
 // The target time used by the difficulty adjustment algorithms, their target time is the target block interval * PoW
 // algorithm count
@@ -357,7 +362,9 @@ impl ConsensusConstants {
             max_difficulty: Difficulty::min(),
             target_time: 200,
         });
-        let (input_version_range, output_version_range, kernel_version_range) = version_zero();
+        let (input_version_range, mut output_version_range, kernel_version_range) = version_zero();
+        output_version_range.covenant_filter = CovenantFilterVersion::V0..=CovenantFilterVersion::V1;
+        output_version_range.opcode = OpcodeVersion::V0..=OpcodeVersion::V1;
         let consensus_constants = vec![ConsensusConstants {
             effective_from_height: 0,
             coinbase_min_maturity: 2,
@@ -417,7 +424,7 @@ impl ConsensusConstants {
             target_time: randomx_target_time,
         });
         let (input_version_range, output_version_range, kernel_version_range) = version_zero();
-        let consensus_constants = vec![ConsensusConstants {
+        let con_1 = ConsensusConstants {
             effective_from_height: 0,
             coinbase_min_maturity: 6,
             blockchain_version: 0,
@@ -450,13 +457,20 @@ impl ConsensusConstants {
             vn_registration_lock_height: 0,
             vn_registration_shuffle_interval: VnEpoch(100),
             coinbase_output_features_extra_max_length: 64,
-        }];
+        };
+        // The transaction check opcodes and the covenant filters for scripts, value promises, output counts and
+        // relative heights
+        let mut con_2 = con_1.clone();
+        con_2.effective_from_height = IGOR_SCRIPT_V1_HEIGHT;
+        con_2.output_version_range.opcode = OpcodeVersion::V0..=OpcodeVersion::V1;
+        con_2.output_version_range.covenant_filter = CovenantFilterVersion::V0..=CovenantFilterVersion::V1;
+        let consensus_constants = vec![con_1, con_2];
         #[cfg(any(test, debug_assertions))]
         assert_hybrid_pow_constants(
             &consensus_constants,
-            &[target_time],
-            &[randomx_split],
-            &[sha3x_split],
+            &[target_time, target_time],
+            &[randomx_split, randomx_split],
+            &[sha3x_split, sha3x_split],
             CheckDifficultyRatio::No,
         );
         consensus_constants
@@ -857,11 +871,14 @@ impl ConsensusConstantsBuilder {
 mod test {
     use std::convert::TryFrom;
 
+    use tari_script::OpcodeVersion;
+
     use crate::{
         consensus::{
             emission::{Emission, EmissionSchedule},
             ConsensusConstants,
         },
+        covenants::CovenantFilterVersion,
         transactions::tari_amount::{uT, MicroMinotari},
     };
 
@@ -875,6 +892,23 @@ mod test {
         ConsensusConstants::mainnet();
     }
 
+    #[test]
+    fn igor_activates_script_v1_after_genesis() {
+        let igor = ConsensusConstants::igor();
+        assert_eq!(igor[0].effective_from_height(), 0);
+        assert_eq!(*igor[0].output_version_range().opcode.end(), OpcodeVersion::V0);
+        assert_eq!(
+            *igor[0].output_version_range().covenant_filter.end(),
+            CovenantFilterVersion::V0
+        );
+        assert!(igor[1].effective_from_height() > 0);
+        assert_eq!(*igor[1].output_version_range().opcode.end(), OpcodeVersion::V1);
+        assert_eq!(
+            *igor[1].output_version_range().covenant_filter.end(),
+            CovenantFilterVersion::V1
+        );
+    }
+
     #[test]
     fn esmeralda_schedule() {
         let esmeralda = ConsensusConstants::esmeralda();
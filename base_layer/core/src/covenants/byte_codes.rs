@@ -67,7 +67,7 @@ pub(super) fn is_valid_filter_code(code: u8) -> bool {
 }
 
 /// Array with all possible covenant filter bytecodes.
-pub(super) const ALL_FILTERS: [u8; 10] = [
+pub(super) const ALL_FILTERS: [u8; 14] = [
     FILTER_IDENTITY,
     FILTER_AND,
     FILTER_OR,
@@ -78,6 +78,10 @@ pub(super) const ALL_FILTERS: [u8; 10] = [
     FILTER_FIELDS_HASHED_EQ,
     FILTER_FIELD_EQ,
     FILTER_ABSOLUTE_HEIGHT,
+    FILTER_SCRIPT_MATCHES,
+    FILTER_MIN_VALUE_PROMISE_GE,
+    FILTER_OUTPUT_COUNT,
+    FILTER_RELATIVE_HEIGHT,
 ];
 
 /// Identity filter.
@@ -101,6 +105,15 @@ pub const FILTER_FIELDS_HASHED_EQ: u8 = 0x32;
 pub const FILTER_FIELD_EQ: u8 = 0x33;
 /// Absolute height filter.
 pub const FILTER_ABSOLUTE_HEIGHT: u8 = 0x34;
+/// Script template filter, matching the opcodes of the output script and ignoring their data.
+pub const FILTER_SCRIPT_MATCHES: u8 = 0x35;
+/// Minimum value promise filter, keeping outputs whose minimum value promise is at least the argument.
+pub const FILTER_MIN_VALUE_PROMISE_GE: u8 = 0x36;
+/// Output count filter, keeping the output set only if its size is within the inclusive bounds.
+pub const FILTER_OUTPUT_COUNT: u8 = 0x37;
+/// Relative height filter, keeping outputs with a maturity of at least the spend height plus N. It does not constrain
+/// the age of the input.
+pub const FILTER_RELATIVE_HEIGHT: u8 = 0x38;
 
 //---------------------------------- FIELD byte codes --------------------------------------------//
 /// Field commitment.
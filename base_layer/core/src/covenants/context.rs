@@ -24,10 +24,12 @@ use crate::{
     covenants::{
         arguments::CovenantArg,
         error::CovenantError,
-        filters::CovenantFilter,
+        filters::{CovenantFilter, Filter},
+        output_set::OutputSet,
         token::{CovenantToken, CovenantTokenCollection},
+        trace::CovenantTraceStep,
     },
-    transactions::transaction_components::TransactionInput,
+    transactions::transaction_components::{TransactionInput, TransactionOutput},
 };
 
 /// The covenant execution context provides a reference to the transaction input being verified, the tokenized covenant
@@ -36,6 +38,10 @@ pub struct CovenantContext<'a> {
     input: &'a TransactionInput,
     tokens: CovenantTokenCollection,
     block_height: u64,
+    /// The filters that have run, if the execution is traced
+    trace: Option<Vec<CovenantTraceStep>>,
+    /// The positions in `trace` of the filters that are running, innermost last
+    running_steps: Vec<usize>,
 }
 
 impl<'a> CovenantContext<'a> {
@@ -44,9 +50,35 @@ impl<'a> CovenantContext<'a> {
             input,
             tokens,
             block_height,
+            trace: None,
+            running_steps: Vec::new(),
         }
     }
 
+    /// Records a step for every filter that runs in this context
+    pub fn with_trace(mut self) -> Self {
+        self.trace = Some(Vec::new());
+        self
+    }
+
+    /// Runs the root filter of the covenant over the outputs and returns the outputs that match. It errors if tokens
+    /// remain after the root filter or if no output matches.
+    pub(super) fn filter_outputs<'o>(
+        &mut self,
+        outputs: &'o [TransactionOutput],
+    ) -> Result<OutputSet<'o>, CovenantError> {
+        let root = self.require_next_filter()?;
+        let mut output_set = OutputSet::new(outputs);
+        root.filter(self, &mut output_set)?;
+        if self.has_more_tokens() {
+            return Err(CovenantError::RemainingTokens);
+        }
+        if output_set.is_empty() {
+            return Err(CovenantError::NoMatchingOutputs);
+        }
+        Ok(output_set)
+    }
+
     /// Returns true if there are more tokens to consume, otherwise false
     pub fn has_more_tokens(&self) -> bool {
         !self.tokens.is_empty()
@@ -55,7 +87,12 @@ impl<'a> CovenantContext<'a> {
     /// Outputs the next token argument
     pub fn next_arg(&mut self) -> Result<CovenantArg, CovenantError> {
         match self.tokens.next().ok_or(CovenantError::UnexpectedEndOfTokens)? {
-            CovenantToken::Arg(arg) => Ok(*arg),
+            CovenantToken::Arg(arg) => {
+                if let (Some(trace), Some(position)) = (self.trace.as_mut(), self.running_steps.last()) {
+                    trace[*position].args.push((*arg).clone());
+                }
+                Ok(*arg)
+            },
             CovenantToken::Filter(_) => Err(CovenantError::ExpectedArgButGotFilter),
         }
     }
@@ -87,4 +124,41 @@ impl<'a> CovenantContext<'a> {
     pub fn input(&self) -> &TransactionInput {
         self.input
     }
+
+    /// Records the start of a filter in a traced execution
+    pub(super) fn begin_step(&mut self, filter: &CovenantFilter, output_set: &OutputSet<'_>) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push(CovenantTraceStep {
+                depth: self.running_steps.len(),
+                filter: filter.clone(),
+                args: Vec::new(),
+                outputs_in: output_set.get_selected_indexes(),
+                outputs_out: Vec::new(),
+                error: None,
+            });
+            self.running_steps.push(trace.len() - 1);
+        }
+    }
+
+    /// Records the outputs kept by the innermost running filter in a traced execution, or the error it failed with
+    pub(super) fn end_step(&mut self, output_set: &OutputSet<'_>, result: &Result<(), CovenantError>) {
+        let (trace, position) = match (self.trace.as_mut(), self.running_steps.pop()) {
+            (Some(trace), Some(position)) => (trace, position),
+            _ => return,
+        };
+        match result {
+            Ok(()) => trace[position].outputs_out = output_set.get_selected_indexes(),
+            // The error is recorded on the filter that raised it, not on the filters that contain it
+            Err(err) => {
+                if trace[position..].iter().all(|step| step.error.is_none()) {
+                    trace[position].error = Some(err.clone());
+                }
+            },
+        }
+    }
+
+    /// Takes the steps recorded in a traced execution
+    pub(super) fn take_trace(&mut self) -> Vec<CovenantTraceStep> {
+        self.trace.take().unwrap_or_default()
+    }
 }
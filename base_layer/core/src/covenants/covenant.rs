@@ -36,14 +36,13 @@ use crate::{
         decoder::CovenantTokenDecoder,
         encoder::CovenantTokenEncoder,
         error::CovenantError,
-        filters::Filter,
-        output_set::OutputSet,
+        filters::CovenantFilter,
         token::{CovenantToken, CovenantTokenCollection},
     },
     transactions::transaction_components::{TransactionInput, TransactionOutput},
 };
 
-const MAX_COVENANT_BYTES: usize = 4096;
+pub(super) const MAX_COVENANT_BYTES: usize = 4096;
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 /// A covenant allows a UTXO to specify some restrictions on how it is spent in a future transaction.
@@ -135,15 +134,7 @@ impl Covenant {
 
         let tokens = CovenantTokenCollection::from_iter(self.tokens.clone());
         let mut cx = CovenantContext::new(tokens, input, block_height);
-        let root = cx.require_next_filter()?;
-        let mut output_set = OutputSet::new(outputs);
-        root.filter(&mut cx, &mut output_set)?;
-        if cx.has_more_tokens() {
-            return Err(CovenantError::RemainingTokens);
-        }
-        if output_set.is_empty() {
-            return Err(CovenantError::NoMatchingOutputs);
-        }
+        let output_set = cx.filter_outputs(outputs)?;
 
         Ok(output_set.len())
     }
@@ -153,12 +144,16 @@ impl Covenant {
         self.tokens.push(token);
     }
 
-    #[cfg(test)]
     /// Outputs a slice of the instance existing `CovenantToken`'s.
     pub(super) fn tokens(&self) -> &[CovenantToken] {
         &self.tokens
     }
 
+    /// Iterates over the filters in the covenant, skipping their arguments.
+    pub fn filters(&self) -> impl Iterator<Item = &CovenantFilter> + '_ {
+        self.tokens.iter().filter_map(CovenantToken::as_filter)
+    }
+
     /// Outputs the length of `tokens` field.
     pub fn num_tokens(&self) -> usize {
         self.tokens.len()
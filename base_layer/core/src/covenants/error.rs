@@ -20,7 +20,7 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, Clone, thiserror::Error, PartialEq, Eq)]
 pub enum CovenantError {
     #[error("Reached the end of tokens but another token was expected")]
     UnexpectedEndOfTokens,
@@ -37,3 +37,22 @@ pub enum CovenantError {
     #[error("Invalid argument for filter {filter}: {details}")]
     InvalidArgument { filter: &'static str, details: String },
 }
+
+/// An error in covenant text, with the 1-based line and column where it was found
+#[derive(Debug, Clone, thiserror::Error, PartialEq, Eq)]
+#[error("{line}:{column}: {message}")]
+pub struct CovenantParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl CovenantParseError {
+    pub fn new<T: Into<String>>(line: usize, column: usize, message: T) -> Self {
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
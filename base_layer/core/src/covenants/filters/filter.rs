@@ -29,9 +29,13 @@ use super::{
     fields_hashed_eq::FieldsHashedEqFilter,
     fields_preserved::FieldsPreservedFilter,
     identity::IdentityFilter,
+    min_value_promise_ge::MinValuePromiseGeFilter,
     not::NotFilter,
     or::OrFilter,
+    output_count::OutputCountFilter,
     output_hash_eq::OutputHashEqFilter,
+    relative_height::RelativeHeightFilter,
+    script_matches::ScriptMatchesFilter,
     xor::XorFilter,
 };
 use crate::covenants::{
@@ -61,6 +65,19 @@ pub enum CovenantFilter {
     FieldEq(FieldEqFilter),
     FieldsHashedEq(FieldsHashedEqFilter),
     AbsoluteHeight(AbsoluteHeightFilter),
+    ScriptMatches(ScriptMatchesFilter),
+    MinValuePromiseGe(MinValuePromiseGeFilter),
+    OutputCount(OutputCountFilter),
+    RelativeHeight(RelativeHeightFilter),
+}
+
+/// The consensus version of a covenant filter. Filters added after the first release have a higher version, and are
+/// only valid in outputs once the consensus constants allow that version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum CovenantFilterVersion {
+    V0 = 0,
+    V1 = 1,
 }
 
 impl CovenantFilter {
@@ -91,6 +108,22 @@ impl CovenantFilter {
             FieldEq(_) => FILTER_FIELD_EQ,
             FieldsHashedEq(_) => FILTER_FIELDS_HASHED_EQ,
             AbsoluteHeight(_) => FILTER_ABSOLUTE_HEIGHT,
+            ScriptMatches(_) => FILTER_SCRIPT_MATCHES,
+            MinValuePromiseGe(_) => FILTER_MIN_VALUE_PROMISE_GE,
+            OutputCount(_) => FILTER_OUTPUT_COUNT,
+            RelativeHeight(_) => FILTER_RELATIVE_HEIGHT,
+        }
+    }
+
+    /// The consensus version of the filter.
+    pub fn get_version(&self) -> CovenantFilterVersion {
+        #[allow(clippy::enum_glob_use)]
+        use CovenantFilter::*;
+
+        match self {
+            Identity(_) | And(_) | Or(_) | Xor(_) | Not(_) | OutputHashEq(_) | FieldsPreserved(_) | FieldEq(_) |
+            FieldsHashedEq(_) | AbsoluteHeight(_) => CovenantFilterVersion::V0,
+            ScriptMatches(_) | MinValuePromiseGe(_) | OutputCount(_) | RelativeHeight(_) => CovenantFilterVersion::V1,
         }
     }
 
@@ -108,6 +141,10 @@ impl CovenantFilter {
             FILTER_FIELD_EQ => Ok(Self::field_eq()),
             FILTER_FIELDS_HASHED_EQ => Ok(Self::fields_hashed_eq()),
             FILTER_ABSOLUTE_HEIGHT => Ok(Self::absolute_height()),
+            FILTER_SCRIPT_MATCHES => Ok(Self::script_matches()),
+            FILTER_MIN_VALUE_PROMISE_GE => Ok(Self::min_value_promise_ge()),
+            FILTER_OUTPUT_COUNT => Ok(Self::output_count()),
+            FILTER_RELATIVE_HEIGHT => Ok(Self::relative_height()),
             _ => Err(CovenantDecodeError::UnknownFilterByteCode { code }),
         }
     }
@@ -161,6 +198,26 @@ impl CovenantFilter {
     pub fn absolute_height() -> Self {
         CovenantFilter::AbsoluteHeight(AbsoluteHeightFilter)
     }
+
+    /// Return the "script matches" covenant filter.
+    pub fn script_matches() -> Self {
+        CovenantFilter::ScriptMatches(ScriptMatchesFilter)
+    }
+
+    /// Return the "min value promise ge" covenant filter.
+    pub fn min_value_promise_ge() -> Self {
+        CovenantFilter::MinValuePromiseGe(MinValuePromiseGeFilter)
+    }
+
+    /// Return the "output count" covenant filter.
+    pub fn output_count() -> Self {
+        CovenantFilter::OutputCount(OutputCountFilter)
+    }
+
+    /// Return the "relative height" covenant filter.
+    pub fn relative_height() -> Self {
+        CovenantFilter::RelativeHeight(RelativeHeightFilter)
+    }
 }
 
 impl Filter for CovenantFilter {
@@ -168,7 +225,8 @@ impl Filter for CovenantFilter {
     fn filter(&self, context: &mut CovenantContext<'_>, output_set: &mut OutputSet<'_>) -> Result<(), CovenantError> {
         #[allow(clippy::enum_glob_use)]
         use CovenantFilter::*;
-        match self {
+        context.begin_step(self, output_set);
+        let result = match self {
             Identity(identity) => identity.filter(context, output_set),
             And(and) => and.filter(context, output_set),
             Or(or) => or.filter(context, output_set),
@@ -179,6 +237,12 @@ impl Filter for CovenantFilter {
             FieldEq(fields_eq) => fields_eq.filter(context, output_set),
             FieldsHashedEq(fields_hashed_eq) => fields_hashed_eq.filter(context, output_set),
             AbsoluteHeight(abs_height) => abs_height.filter(context, output_set),
-        }
+            ScriptMatches(script_matches) => script_matches.filter(context, output_set),
+            MinValuePromiseGe(min_value_promise_ge) => min_value_promise_ge.filter(context, output_set),
+            OutputCount(output_count) => output_count.filter(context, output_set),
+            RelativeHeight(rel_height) => rel_height.filter(context, output_set),
+        };
+        context.end_step(output_set, &result);
+        result
     }
 }
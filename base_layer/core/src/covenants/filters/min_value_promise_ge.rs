@@ -0,0 +1,52 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use crate::covenants::{context::CovenantContext, error::CovenantError, filters::Filter, output_set::OutputSet};
+
+/// Holding struct for the "minimum value promise greater or equal" filter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinValuePromiseGeFilter;
+
+impl Filter for MinValuePromiseGeFilter {
+    // The minimum value promise filter keeps the outputs whose minimum value promise is at least the given value in
+    // MicroMinotari. The value of an output is hidden in its commitment, but the range proof proves that it is no less
+    // than the minimum value promise, so this is how a covenant can require an output to carry a minimum amount.
+    fn filter(&self, context: &mut CovenantContext<'_>, output_set: &mut OutputSet<'_>) -> Result<(), CovenantError> {
+        let min_value = context.next_arg()?.require_uint()?;
+        output_set.retain(|output| Ok(output.minimum_value_promise.as_u64() >= min_value))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        covenant,
+        covenants::{filters::test::setup_filter_test, test::create_input},
+        transactions::test_helpers::create_test_core_key_manager_with_memory_db,
+    };
+
+    #[tokio::test]
+    async fn it_filters_outputs_with_a_minimum_value_promise_of_at_least_the_value() {
+        let key_manager = create_test_core_key_manager_with_memory_db();
+        let covenant = covenant!(min_value_promise_ge(@uint(1000)));
+        let input = create_input(&key_manager).await;
+        let (mut context, outputs) = setup_filter_test(
+            &covenant,
+            &input,
+            0,
+            |outputs| {
+                outputs[2].minimum_value_promise = 999.into();
+                outputs[4].minimum_value_promise = 1000.into();
+                outputs[6].minimum_value_promise = 5000.into();
+            },
+            &key_manager,
+        )
+        .await;
+
+        let mut output_set = OutputSet::new(&outputs);
+        MinValuePromiseGeFilter.filter(&mut context, &mut output_set).unwrap();
+
+        assert_eq!(output_set.get_selected_indexes(), vec![4, 6]);
+    }
+}
@@ -26,9 +26,13 @@ mod field_eq;
 mod fields_hashed_eq;
 mod fields_preserved;
 mod identity;
+mod min_value_promise_ge;
 mod not;
 mod or;
+mod output_count;
 mod output_hash_eq;
+mod relative_height;
+mod script_matches;
 mod xor;
 
 pub use absolute_height::AbsoluteHeightFilter;
@@ -37,13 +41,17 @@ pub use field_eq::FieldEqFilter;
 pub use fields_hashed_eq::FieldsHashedEqFilter;
 pub use fields_preserved::FieldsPreservedFilter;
 pub use identity::IdentityFilter;
+pub use min_value_promise_ge::MinValuePromiseGeFilter;
 pub use not::NotFilter;
 pub use or::OrFilter;
+pub use output_count::OutputCountFilter;
 pub use output_hash_eq::OutputHashEqFilter;
+pub use relative_height::RelativeHeightFilter;
+pub use script_matches::ScriptMatchesFilter;
 pub use xor::XorFilter;
 
 mod filter;
-pub use filter::{CovenantFilter, Filter};
+pub use filter::{CovenantFilter, CovenantFilterVersion, Filter};
 
 #[cfg(test)]
 mod test;
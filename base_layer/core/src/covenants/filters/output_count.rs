@@ -0,0 +1,62 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use crate::covenants::{context::CovenantContext, error::CovenantError, filters::Filter, output_set::OutputSet};
+
+/// Holding struct for the "output count" filter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputCountFilter;
+
+impl Filter for OutputCountFilter {
+    // The output count filter takes a minimum and a maximum count. It keeps the output set if the number of outputs
+    // in it is within these bounds (inclusive) and removes all outputs otherwise.
+    fn filter(&self, context: &mut CovenantContext<'_>, output_set: &mut OutputSet<'_>) -> Result<(), CovenantError> {
+        let min = context.next_arg()?.require_uint()?;
+        let max = context.next_arg()?.require_uint()?;
+        let count = output_set.len() as u64;
+        if count < min || count > max {
+            output_set.clear();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        covenant,
+        covenants::{filters::test::setup_filter_test, test::create_input},
+        transactions::test_helpers::create_test_core_key_manager_with_memory_db,
+    };
+
+    #[tokio::test]
+    async fn it_filters_all_in_if_the_count_is_within_bounds() {
+        let key_manager = create_test_core_key_manager_with_memory_db();
+        let input = create_input(&key_manager).await;
+        for (min, max) in [(1, 10), (10, 10), (0, u64::MAX)] {
+            let covenant = covenant!(output_count(@uint(min), @uint(max)));
+            let (mut context, outputs) = setup_filter_test(&covenant, &input, 0, |_| {}, &key_manager).await;
+
+            let mut output_set = OutputSet::new(&outputs);
+            OutputCountFilter.filter(&mut context, &mut output_set).unwrap();
+
+            assert_eq!(output_set.len(), 10);
+        }
+    }
+
+    #[tokio::test]
+    async fn it_filters_all_out_if_the_count_is_out_of_bounds() {
+        let key_manager = create_test_core_key_manager_with_memory_db();
+        let input = create_input(&key_manager).await;
+        for (min, max) in [(1, 9), (11, 20), (10, 1)] {
+            let covenant = covenant!(output_count(@uint(min), @uint(max)));
+            let (mut context, outputs) = setup_filter_test(&covenant, &input, 0, |_| {}, &key_manager).await;
+
+            let mut output_set = OutputSet::new(&outputs);
+            OutputCountFilter.filter(&mut context, &mut output_set).unwrap();
+
+            assert!(output_set.is_empty());
+        }
+    }
+}
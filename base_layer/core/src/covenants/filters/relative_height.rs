@@ -0,0 +1,54 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use crate::covenants::{context::CovenantContext, error::CovenantError, filters::Filter, output_set::OutputSet};
+
+/// Holding struct for the "relative height" filter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelativeHeightFilter;
+
+impl Filter for RelativeHeightFilter {
+    // The relative height filter keeps the outputs that cannot be spent until the given number of blocks after the
+    // block that spends the input, i.e. outputs with a maturity of at least the current block height plus the given
+    // number of blocks. Covenants are checked without access to the height at which the input was mined, so the lock
+    // is placed on the new outputs rather than on the input.
+    fn filter(&self, context: &mut CovenantContext<'_>, output_set: &mut OutputSet<'_>) -> Result<(), CovenantError> {
+        let blocks = context.next_arg()?.require_uint()?;
+        let min_maturity = context.block_height().saturating_add(blocks);
+        output_set.retain(|output| Ok(output.features.maturity >= min_maturity))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        covenant,
+        covenants::{filters::test::setup_filter_test, test::create_input},
+        transactions::test_helpers::create_test_core_key_manager_with_memory_db,
+    };
+
+    #[tokio::test]
+    async fn it_filters_outputs_that_mature_at_least_the_number_of_blocks_later() {
+        let key_manager = create_test_core_key_manager_with_memory_db();
+        let covenant = covenant!(relative_height(@uint(10)));
+        let input = create_input(&key_manager).await;
+        let (mut context, outputs) = setup_filter_test(
+            &covenant,
+            &input,
+            100,
+            |outputs| {
+                outputs[1].features.maturity = 109;
+                outputs[2].features.maturity = 110;
+                outputs[5].features.maturity = 500;
+            },
+            &key_manager,
+        )
+        .await;
+
+        let mut output_set = OutputSet::new(&outputs);
+        RelativeHeightFilter.filter(&mut context, &mut output_set).unwrap();
+
+        assert_eq!(output_set.get_selected_indexes(), vec![2, 5]);
+    }
+}
@@ -0,0 +1,55 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use crate::covenants::{context::CovenantContext, error::CovenantError, filters::Filter, output_set::OutputSet};
+
+/// Holding struct for the "script matches" filter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptMatchesFilter;
+
+impl Filter for ScriptMatchesFilter {
+    // The script matches filter keeps the outputs whose script has the same opcodes as the script argument. The data
+    // the opcodes carry, such as public keys and heights, is ignored (see `TariScript::pattern_match`).
+    fn filter(&self, context: &mut CovenantContext<'_>, output_set: &mut OutputSet<'_>) -> Result<(), CovenantError> {
+        let pattern = context.next_arg()?.require_tariscript()?;
+        output_set.retain(|output| Ok(pattern.pattern_match(&output.script)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tari_script::script;
+
+    use super::*;
+    use crate::{
+        covenant,
+        covenants::{filters::test::setup_filter_test, test::create_input},
+        transactions::test_helpers::create_test_core_key_manager_with_memory_db,
+    };
+
+    #[tokio::test]
+    async fn it_filters_outputs_with_a_matching_script() {
+        let key_manager = create_test_core_key_manager_with_memory_db();
+        let pattern = script!(PushPubKey(Box::default()) CheckHeightVerify(0));
+        let covenant = covenant!(script_matches(@script(pattern)));
+        let input = create_input(&key_manager).await;
+        let (mut context, outputs) = setup_filter_test(
+            &covenant,
+            &input,
+            0,
+            |outputs| {
+                outputs[3].script = script!(PushPubKey(Box::default()) CheckHeightVerify(100));
+                outputs[5].script = script!(PushPubKey(Box::default()) CheckHeight(100));
+                outputs[7].script = script!(PushPubKey(Box::default()) CheckHeightVerify(200) Drop);
+                outputs[8].script = script!(PushPubKey(Box::default()) CheckHeightVerify(200));
+            },
+            &key_manager,
+        )
+        .await;
+
+        let mut output_set = OutputSet::new(&outputs);
+        ScriptMatchesFilter.filter(&mut context, &mut output_set).unwrap();
+
+        assert_eq!(output_set.get_selected_indexes(), vec![3, 8]);
+    }
+}
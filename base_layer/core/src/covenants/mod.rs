@@ -37,14 +37,18 @@ mod fields;
 mod filters;
 mod output_set;
 mod serde;
+mod text;
 mod token;
+mod trace;
 
 pub use covenant::Covenant;
-pub use error::CovenantError;
+pub use error::{CovenantError, CovenantParseError};
 // Used in macro
 #[allow(unused_imports)]
 pub(crate) use fields::OutputField;
+pub use filters::{CovenantFilter, CovenantFilterVersion};
 pub use token::CovenantToken;
+pub use trace::{CovenantTrace, CovenantTraceStep};
 
 #[macro_use]
 mod macros;
@@ -0,0 +1,854 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! A textual language for covenants.
+//!
+//! A covenant is a single filter expression, written with the same filter and argument names as the `covenant!`
+//! macro:
+//!
+//! ```text
+//! # Before height 42 the funds can only be spent into an output with the same script and output type
+//! or(
+//!     absolute_height(@uint(42)),
+//!     fields_preserved(@fields(@field::script, @field::features_output_type))
+//! )
+//! ```
+//!
+//! The filters and the arguments they take are
+//! - `identity()`
+//! - `and(A, B)`, `or(A, B)`, `xor(A, B)` and `not(A)`, where `A` and `B` are filters
+//! - `output_hash_eq(@hash(..))`
+//! - `fields_preserved(@fields(..))`
+//! - `field_eq(@field::name, value)`, where the value is any argument other than a field or a list of fields
+//! - `fields_hashed_eq(@fields(..), @hash(..))`
+//! - `absolute_height(@uint(..))`
+//! - `script_matches(@script(..))`
+//! - `min_value_promise_ge(@uint(..))`
+//! - `output_count(@uint(min), @uint(max))`
+//! - `relative_height(@uint(..))`
+//!
+//! and the arguments are
+//! - `@hash(..)`, `@public_key(..)` and `@commitment(..)`: 32 bytes as 64 hex digits with an optional `0x` prefix
+//! - `@bytes(..)`: any number of bytes as hex digits with an optional `0x` prefix
+//! - `@uint(..)`: a decimal or `0x`-prefixed hexadecimal `u64`
+//! - `@output_type(..)`: `Standard`, `Coinbase`, `Burn`, `ValidatorNodeRegistration` or `CodeTemplateRegistration`
+//! - `@field::name` and `@fields(@field::name, ..)`, where the field names are `commitment`, `script`,
+//!   `sender_offset_public_key`, `covenant`, `features`, `features_output_type` (also `features_flags`),
+//!   `features_maturity`, `features_sidechain_feature`, `features_range_proof_type` and `minimum_value_promise`
+//! - `@script(..)`: a script in TariScript assembly
+//! - `@covenant(..)`: a nested covenant, for comparing with the covenant field of an output
+//!
+//! Comments start with `#` or `//` and run to the end of the line. An empty text is the empty covenant, which matches
+//! every output. Covenants longer than the 4096 bytes a transaction output can hold are rejected. Parse errors carry
+//! the line and column of the offending text.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use tari_common_types::types::{Commitment, FixedHash, PublicKey};
+use tari_script::TariScript;
+use tari_utilities::{
+    hex::{from_hex, Hex},
+    ByteArray,
+};
+
+use crate::{
+    common::byte_counter::ByteCounter,
+    covenants::{
+        arguments::CovenantArg,
+        byte_codes,
+        covenant::{Covenant, MAX_COVENANT_BYTES},
+        error::CovenantParseError,
+        fields::{OutputField, OutputFields},
+        filters::CovenantFilter,
+        token::CovenantToken,
+    },
+    transactions::transaction_components::OutputType,
+};
+
+const INDENT: &str = "    ";
+/// Filters that are longer than this are broken over several lines by `to_text`
+const MAX_LINE_WIDTH: usize = 100;
+
+impl Covenant {
+    /// Compiles a covenant from its text
+    pub fn from_text(text: &str) -> Result<Covenant, CovenantParseError> {
+        Parser::new(text).parse()
+    }
+
+    /// Writes the covenant as text. Filters that do not fit on a line are broken over several indented lines.
+    /// Covenants whose tokens do not form a single filter expression, which can only be decoded from bytes, are
+    /// written as a flat list of tokens that does not parse.
+    pub fn to_text(&self) -> String {
+        match Node::read_covenant(self.tokens()) {
+            Some(Some(node)) => {
+                let mut text = String::new();
+                node.write_pretty(&mut text, 0);
+                text.push('\n');
+                text
+            },
+            Some(None) => String::new(),
+            None => write_flat(self.tokens()),
+        }
+    }
+}
+
+impl FromStr for Covenant {
+    type Err = CovenantParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Covenant::from_text(s)
+    }
+}
+
+/// Writes the covenant as text on a single line
+impl Display for Covenant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match Node::read_covenant(self.tokens()) {
+            Some(Some(node)) => f.write_str(&node.compact()),
+            Some(None) => Ok(()),
+            None => f.write_str(&write_flat(self.tokens())),
+        }
+    }
+}
+
+/// Writes the name of the filter
+impl Display for CovenantFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(filter_name(self))
+    }
+}
+
+/// The parameters of a filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Param {
+    Filter,
+    Hash,
+    Uint,
+    Script,
+    Field,
+    Fields,
+    /// Any argument that can be compared with a field
+    Value,
+}
+
+impl Param {
+    fn describe(self) -> &'static str {
+        match self {
+            Param::Filter => "a filter",
+            Param::Hash => "a `@hash`",
+            Param::Uint => "a `@uint`",
+            Param::Script => "a `@script`",
+            Param::Field => "a `@field`",
+            Param::Fields => "a `@fields` list",
+            Param::Value => "a value",
+        }
+    }
+
+    fn accepts(self, arg: &CovenantArg) -> bool {
+        match self {
+            Param::Filter => false,
+            Param::Hash => matches!(arg, CovenantArg::Hash(_)),
+            Param::Uint => matches!(arg, CovenantArg::Uint(_)),
+            Param::Script => matches!(arg, CovenantArg::TariScript(_)),
+            Param::Field => matches!(arg, CovenantArg::OutputField(_)),
+            Param::Fields => matches!(arg, CovenantArg::OutputFields(_)),
+            Param::Value => !matches!(arg, CovenantArg::OutputField(_) | CovenantArg::OutputFields(_)),
+        }
+    }
+}
+
+fn filter_name(filter: &CovenantFilter) -> &'static str {
+    match filter {
+        CovenantFilter::Identity(_) => "identity",
+        CovenantFilter::And(_) => "and",
+        CovenantFilter::Or(_) => "or",
+        CovenantFilter::Xor(_) => "xor",
+        CovenantFilter::Not(_) => "not",
+        CovenantFilter::OutputHashEq(_) => "output_hash_eq",
+        CovenantFilter::FieldsPreserved(_) => "fields_preserved",
+        CovenantFilter::FieldEq(_) => "field_eq",
+        CovenantFilter::FieldsHashedEq(_) => "fields_hashed_eq",
+        CovenantFilter::AbsoluteHeight(_) => "absolute_height",
+        CovenantFilter::ScriptMatches(_) => "script_matches",
+        CovenantFilter::MinValuePromiseGe(_) => "min_value_promise_ge",
+        CovenantFilter::OutputCount(_) => "output_count",
+        CovenantFilter::RelativeHeight(_) => "relative_height",
+    }
+}
+
+fn filter_params(filter: &CovenantFilter) -> &'static [Param] {
+    match filter {
+        CovenantFilter::Identity(_) => &[],
+        CovenantFilter::And(_) | CovenantFilter::Or(_) | CovenantFilter::Xor(_) => &[Param::Filter, Param::Filter],
+        CovenantFilter::Not(_) => &[Param::Filter],
+        CovenantFilter::OutputHashEq(_) => &[Param::Hash],
+        CovenantFilter::FieldsPreserved(_) => &[Param::Fields],
+        CovenantFilter::FieldEq(_) => &[Param::Field, Param::Value],
+        CovenantFilter::FieldsHashedEq(_) => &[Param::Fields, Param::Hash],
+        CovenantFilter::AbsoluteHeight(_) |
+        CovenantFilter::MinValuePromiseGe(_) |
+        CovenantFilter::RelativeHeight(_) => &[Param::Uint],
+        CovenantFilter::ScriptMatches(_) => &[Param::Script],
+        CovenantFilter::OutputCount(_) => &[Param::Uint, Param::Uint],
+    }
+}
+
+fn find_filter(name: &str) -> Option<CovenantFilter> {
+    byte_codes::ALL_FILTERS
+        .iter()
+        .filter_map(|code| CovenantFilter::try_from_byte_code(*code).ok())
+        .find(|filter| filter_name(filter) == name)
+}
+
+fn field_name(field: OutputField) -> &'static str {
+    match field {
+        OutputField::Commitment => "commitment",
+        OutputField::Script => "script",
+        OutputField::SenderOffsetPublicKey => "sender_offset_public_key",
+        OutputField::Covenant => "covenant",
+        OutputField::Features => "features",
+        OutputField::FeaturesOutputType => "features_output_type",
+        OutputField::FeaturesMaturity => "features_maturity",
+        OutputField::FeaturesSideChainFeatures => "features_sidechain_feature",
+        OutputField::FeaturesRangeProofType => "features_range_proof_type",
+        OutputField::MinimumValuePromise => "minimum_value_promise",
+    }
+}
+
+fn find_field(name: &str) -> Option<OutputField> {
+    if name == "features_flags" {
+        return Some(OutputField::FeaturesOutputType);
+    }
+    (0..OutputFields::NUM_FIELDS)
+        .filter_map(|byte| u8::try_from(byte).ok())
+        .filter_map(|byte| OutputField::from_byte(byte).ok())
+        .find(|field| field_name(*field) == name)
+}
+
+fn arg_text(arg: &CovenantArg) -> String {
+    match arg {
+        CovenantArg::Hash(hash) => format!("@hash({})", hash.to_hex()),
+        CovenantArg::PublicKey(public_key) => format!("@public_key({})", public_key.to_hex()),
+        CovenantArg::Commitment(commitment) => format!("@commitment({})", commitment.to_hex()),
+        CovenantArg::TariScript(script) => format!("@script({})", script),
+        CovenantArg::Covenant(covenant) => format!("@covenant({})", covenant),
+        CovenantArg::OutputType(output_type) => format!("@output_type({})", output_type),
+        CovenantArg::Uint(value) => format!("@uint({})", value),
+        CovenantArg::OutputField(field) => format!("@field::{}", field_name(*field)),
+        CovenantArg::OutputFields(fields) => format!(
+            "@fields({})",
+            fields
+                .iter()
+                .map(|field| format!("@field::{}", field_name(*field)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        CovenantArg::Bytes(bytes) => format!("@bytes({})", bytes.to_hex()),
+    }
+}
+
+/// Writes a filter with the arguments it read, with `..` in place of the filters it takes
+pub(super) fn filter_call_text(filter: &CovenantFilter, args: &[CovenantArg]) -> String {
+    let mut args = args.iter();
+    let params = filter_params(filter)
+        .iter()
+        .filter_map(|param| match param {
+            Param::Filter => Some("..".to_string()),
+            _ => args.next().map(arg_text),
+        })
+        .collect::<Vec<_>>();
+    format!("{}({})", filter_name(filter), params.join(", "))
+}
+
+fn write_flat(tokens: &[CovenantToken]) -> String {
+    tokens
+        .iter()
+        .map(|token| match token {
+            CovenantToken::Filter(filter) => filter_name(filter).to_string(),
+            CovenantToken::Arg(arg) => arg_text(arg),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A filter expression read from the tokens of a covenant
+enum Node {
+    Filter { name: &'static str, children: Vec<Node> },
+    Arg(String),
+}
+
+impl Node {
+    /// Returns `Some(None)` for the empty covenant and `None` if the tokens are not a single filter expression
+    fn read_covenant(tokens: &[CovenantToken]) -> Option<Option<Node>> {
+        if tokens.is_empty() {
+            return Some(None);
+        }
+        let mut position = 0;
+        let node = Node::read_filter(tokens, &mut position)?;
+        if position == tokens.len() {
+            Some(Some(node))
+        } else {
+            None
+        }
+    }
+
+    fn read_filter(tokens: &[CovenantToken], position: &mut usize) -> Option<Node> {
+        let filter = tokens.get(*position)?.as_filter()?;
+        *position += 1;
+        let mut children = Vec::new();
+        for param in filter_params(filter) {
+            if *param == Param::Filter {
+                children.push(Node::read_filter(tokens, position)?);
+            } else {
+                let arg = tokens.get(*position)?.as_arg()?;
+                if !param.accepts(arg) {
+                    return None;
+                }
+                *position += 1;
+                children.push(Node::Arg(arg_text(arg)));
+            }
+        }
+        Some(Node::Filter {
+            name: filter_name(filter),
+            children,
+        })
+    }
+
+    fn compact(&self) -> String {
+        match self {
+            Node::Filter { name, children } => format!(
+                "{}({})",
+                name,
+                children.iter().map(Node::compact).collect::<Vec<_>>().join(", ")
+            ),
+            Node::Arg(text) => text.clone(),
+        }
+    }
+
+    fn write_pretty(&self, text: &mut String, depth: usize) {
+        let compact = self.compact();
+        let children = match self {
+            Node::Filter { name, children } if INDENT.len() * depth + compact.len() > MAX_LINE_WIDTH => {
+                text.push_str(name);
+                children
+            },
+            _ => {
+                text.push_str(&compact);
+                return;
+            },
+        };
+        text.push_str("(\n");
+        for (i, child) in children.iter().enumerate() {
+            text.push_str(&INDENT.repeat(depth + 1));
+            child.write_pretty(text, depth + 1);
+            if i + 1 < children.len() {
+                text.push(',');
+            }
+            text.push('\n');
+        }
+        text.push_str(&INDENT.repeat(depth));
+        text.push(')');
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    /// The byte offset and character of every character in the text
+    chars: Vec<(usize, char)>,
+    position: usize,
+    tokens: Vec<CovenantToken>,
+    size: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            chars: text.char_indices().collect(),
+            position: 0,
+            tokens: Vec::new(),
+            size: 0,
+        }
+    }
+
+    fn parse(mut self) -> Result<Covenant, CovenantParseError> {
+        self.skip_trivia();
+        if self.peek().is_none() {
+            return Ok(Covenant::new());
+        }
+        self.filter()?;
+        self.skip_trivia();
+        if let Some(c) = self.peek() {
+            return Err(self.error_at(self.position, format!("Unexpected `{}` after the covenant", c)));
+        }
+        Ok(self.tokens.into_iter().collect())
+    }
+
+    fn filter(&mut self) -> Result<(), CovenantParseError> {
+        self.skip_trivia();
+        let start = self.position;
+        if self.peek() == Some('@') {
+            return Err(self.error_at(start, "Expected a filter, found an argument"));
+        }
+        let name = self.identifier()?;
+        let filter = find_filter(&name).ok_or_else(|| self.error_at(start, format!("Unknown filter `{}`", name)))?;
+        let params = filter_params(&filter);
+        self.push(start, filter.into())?;
+        self.expect('(')?;
+        for (i, param) in params.iter().enumerate() {
+            if i > 0 {
+                self.skip_trivia();
+                if self.peek() == Some(')') {
+                    return Err(self.error_at(
+                        self.position,
+                        format!("`{}` takes {} arguments but {} were given", name, params.len(), i),
+                    ));
+                }
+                self.expect(',')?;
+            }
+            if *param == Param::Filter {
+                self.filter()?;
+            } else {
+                self.skip_trivia();
+                let arg_start = self.position;
+                let arg = self.argument()?;
+                if !param.accepts(&arg) {
+                    return Err(self.error_at(
+                        arg_start,
+                        format!(
+                            "`{}` expects {} here, found `{}`",
+                            name,
+                            param.describe(),
+                            arg_text(&arg)
+                        ),
+                    ));
+                }
+                self.push(arg_start, arg.into())?;
+            }
+        }
+        self.skip_trivia();
+        match self.peek() {
+            Some(')') => {
+                self.position += 1;
+                Ok(())
+            },
+            Some(',') => Err(self.error_at(self.position, format!("`{}` takes {} argument(s)", name, params.len()))),
+            _ => self.expect(')'),
+        }
+    }
+
+    fn argument(&mut self) -> Result<CovenantArg, CovenantParseError> {
+        let start = self.position;
+        if self.peek() != Some('@') {
+            return Err(self.error_at(start, "Expected an argument starting with `@`"));
+        }
+        self.position += 1;
+        let name = self.identifier()?;
+        match name.as_str() {
+            "field" => Ok(CovenantArg::OutputField(self.field_path()?)),
+            "fields" => {
+                self.expect('(')?;
+                let mut fields = OutputFields::new();
+                loop {
+                    self.skip_trivia();
+                    let field_start = self.position;
+                    if self.peek() != Some('@') || self.identifier_at(field_start + 1) != "field" {
+                        return Err(self.error_at(field_start, "Expected a `@field::name`"));
+                    }
+                    self.position += "@field".len();
+                    let field = self.field_path()?;
+                    if fields.len() == OutputFields::NUM_FIELDS {
+                        return Err(self.error_at(
+                            field_start,
+                            format!("A list can hold at most {} fields", OutputFields::NUM_FIELDS),
+                        ));
+                    }
+                    fields.push(field);
+                    self.skip_trivia();
+                    match self.peek() {
+                        Some(',') => self.position += 1,
+                        Some(')') => {
+                            self.position += 1;
+                            break;
+                        },
+                        _ => return Err(self.unexpected("`,` or `)`")),
+                    }
+                }
+                Ok(CovenantArg::OutputFields(fields))
+            },
+            "hash" => {
+                let (word_start, bytes) = self.hex_argument()?;
+                FixedHash::try_from(bytes.as_slice())
+                    .map(CovenantArg::Hash)
+                    .map_err(|_| self.error_at(word_start, format!("Expected 32 bytes but found {}", bytes.len())))
+            },
+            "public_key" => {
+                let (word_start, bytes) = self.hex_argument()?;
+                PublicKey::from_bytes(&bytes)
+                    .map(CovenantArg::PublicKey)
+                    .map_err(|_| self.error_at(word_start, "Not a valid public key"))
+            },
+            "commitment" => {
+                let (word_start, bytes) = self.hex_argument()?;
+                Commitment::from_bytes(&bytes)
+                    .map(CovenantArg::Commitment)
+                    .map_err(|_| self.error_at(word_start, "Not a valid commitment"))
+            },
+            "bytes" => Ok(CovenantArg::Bytes(self.hex_argument()?.1)),
+            "uint" => {
+                self.expect('(')?;
+                let (word_start, word) = self.word();
+                let value = match word.strip_prefix("0x") {
+                    Some(hex) => u64::from_str_radix(hex, 16),
+                    None => word.parse::<u64>(),
+                }
+                .map_err(|_| self.error_at(word_start, format!("`{}` is not a u64", word)))?;
+                self.expect(')')?;
+                Ok(CovenantArg::Uint(value))
+            },
+            "output_type" => {
+                self.expect('(')?;
+                let (word_start, word) = self.word();
+                let output_type = OutputType::all()
+                    .iter()
+                    .find(|output_type| output_type.to_string() == word)
+                    .copied()
+                    .ok_or_else(|| self.error_at(word_start, format!("Unknown output type `{}`", word)))?;
+                self.expect(')')?;
+                Ok(CovenantArg::OutputType(output_type))
+            },
+            "script" => {
+                let (inner_start, inner) = self.enclosed()?;
+                TariScript::from_assembly(inner)
+                    .map(CovenantArg::TariScript)
+                    .map_err(|e| self.inner_error(inner_start, e.line, e.column, e.message))
+            },
+            "covenant" | "covenant_lit" => {
+                let (inner_start, inner) = self.enclosed()?;
+                Covenant::from_text(inner)
+                    .map(CovenantArg::Covenant)
+                    .map_err(|e| self.inner_error(inner_start, e.line, e.column, e.message))
+            },
+            _ => Err(self.error_at(start, format!("Unknown argument `@{}`", name))),
+        }
+    }
+
+    /// Parses the `::name` of a field
+    fn field_path(&mut self) -> Result<OutputField, CovenantParseError> {
+        if self.peek() != Some(':') || self.chars.get(self.position + 1).map(|(_, c)| *c) != Some(':') {
+            return Err(self.error_at(self.position, "Expected `::` followed by a field name"));
+        }
+        self.position += 2;
+        let name_start = self.position;
+        let name = self.identifier()?;
+        find_field(&name).ok_or_else(|| self.error_at(name_start, format!("Unknown field `{}`", name)))
+    }
+
+    /// Parses `(hex)`, returning the start of the hex and the bytes
+    fn hex_argument(&mut self) -> Result<(usize, Vec<u8>), CovenantParseError> {
+        self.expect('(')?;
+        let (word_start, word) = self.word();
+        let bytes = from_hex(word.strip_prefix("0x").unwrap_or(&word))
+            .map_err(|_| self.error_at(word_start, format!("`{}` is not valid hex", word)))?;
+        self.expect(')')?;
+        Ok((word_start, bytes))
+    }
+
+    /// Parses `(...)` with balanced parentheses, returning the start of the enclosed text and the text
+    fn enclosed(&mut self) -> Result<(usize, &'a str), CovenantParseError> {
+        self.skip_trivia();
+        let open = self.position;
+        self.expect('(')?;
+        let inner_start = self.position;
+        let mut depth = 0usize;
+        while let Some(c) = self.peek() {
+            match c {
+                '(' => depth += 1,
+                ')' if depth == 0 => {
+                    let inner = &self.text[self.offset(inner_start)..self.offset(self.position)];
+                    self.position += 1;
+                    return Ok((inner_start, inner));
+                },
+                ')' => depth -= 1,
+                _ => {},
+            }
+            self.position += 1;
+        }
+        Err(self.error_at(open, "`(` is never closed"))
+    }
+
+    fn identifier(&mut self) -> Result<String, CovenantParseError> {
+        let name = self.identifier_at(self.position);
+        if name.is_empty() {
+            return Err(self.unexpected("a name"));
+        }
+        self.position += name.chars().count();
+        Ok(name)
+    }
+
+    fn identifier_at(&self, position: usize) -> String {
+        self.chars
+            .iter()
+            .skip(position)
+            .map(|(_, c)| *c)
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+            .collect()
+    }
+
+    /// Reads a value up to the next delimiter, returning its start and text
+    fn word(&mut self) -> (usize, String) {
+        self.skip_trivia();
+        let start = self.position;
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || matches!(c, '(' | ')' | ',' | '#') {
+                break;
+            }
+            self.position += 1;
+        }
+        let word = self.text[self.offset(start)..self.offset(self.position)].to_string();
+        (start, word)
+    }
+
+    fn push(&mut self, start: usize, token: CovenantToken) -> Result<(), CovenantParseError> {
+        let mut counter = ByteCounter::new();
+        token
+            .write_to(&mut counter)
+            .map_err(|e| self.error_at(start, e.to_string()))?;
+        self.size += counter.get();
+        if self.size > MAX_COVENANT_BYTES {
+            return Err(self.error_at(
+                start,
+                format!(
+                    "The covenant is longer than the maximum of {} bytes",
+                    MAX_COVENANT_BYTES
+                ),
+            ));
+        }
+        self.tokens.push(token);
+        Ok(())
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), CovenantParseError> {
+        self.skip_trivia();
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{}`", expected)))
+        }
+    }
+
+    fn skip_trivia(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() {
+                self.position += 1;
+            } else if c == '#' || (c == '/' && self.chars.get(self.position + 1).map(|(_, c)| *c) == Some('/')) {
+                while self.peek().map_or(false, |c| c != '\n') {
+                    self.position += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).map(|(_, c)| *c)
+    }
+
+    /// The byte offset of the character at `position`
+    fn offset(&self, position: usize) -> usize {
+        self.chars.get(position).map_or(self.text.len(), |(offset, _)| *offset)
+    }
+
+    /// The 1-based line and column of the character at `position`
+    fn location(&self, position: usize) -> (usize, usize) {
+        let before = &self.chars[..position.min(self.chars.len())];
+        let line = before.iter().filter(|(_, c)| *c == '\n').count() + 1;
+        let column = before.iter().rev().take_while(|(_, c)| *c != '\n').count() + 1;
+        (line, column)
+    }
+
+    fn error_at<T: Into<String>>(&self, position: usize, message: T) -> CovenantParseError {
+        let (line, column) = self.location(position);
+        CovenantParseError::new(line, column, message)
+    }
+
+    fn unexpected(&self, expected: &str) -> CovenantParseError {
+        match self.peek() {
+            Some(c) => self.error_at(self.position, format!("Expected {}, found `{}`", expected, c)),
+            None => self.error_at(
+                self.position,
+                format!("Expected {}, found the end of the covenant", expected),
+            ),
+        }
+    }
+
+    /// Moves an error in text that starts at `inner_start` to its location in the whole text
+    fn inner_error(&self, inner_start: usize, line: usize, column: usize, message: String) -> CovenantParseError {
+        let (start_line, start_column) = self.location(inner_start);
+        let column = if line == 1 { start_column + column - 1 } else { column };
+        CovenantParseError::new(start_line + line - 1, column, message)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tari_script::script;
+
+    use super::*;
+    use crate::covenant;
+
+    fn public_key() -> PublicKey {
+        PublicKey::from_hex("b0c1f788f137ba0cdc0b61e89ee43b80ebf5cca4136d3229561bf11eba347849").unwrap()
+    }
+
+    #[test]
+    fn it_parses_covenant_text() {
+        let text = "# Before height 42 the funds can only be spent into an output with the same script
+            or(
+                absolute_height(@uint(42)), // or anything after it
+                fields_preserved(@fields(@field::script, @field::features_flags))
+            )";
+        let expected = covenant!(or(
+            absolute_height(@uint(42)),
+            fields_preserved(@fields(@field::script, @field::features_output_type))
+        ));
+        assert_eq!(Covenant::from_text(text).unwrap(), expected);
+        assert_eq!(text.parse::<Covenant>().unwrap(), expected);
+        assert_eq!(Covenant::from_text(" # nothing\n").unwrap(), Covenant::new());
+    }
+
+    #[test]
+    fn it_round_trips_every_filter_and_argument() {
+        let hash = FixedHash::from([7u8; 32]);
+        let script = script!(CheckHeightVerify(10) PushPubKey(Box::new(public_key())));
+        let covenant = covenant!(and(
+            xor(
+                not(identity()),
+                output_hash_eq(@hash(hash))
+            ),
+            or(
+                and(
+                    field_eq(@field::sender_offset_public_key, @public_key(public_key())),
+                    field_eq(@field::commitment, @commitment(Commitment::default()))
+                ),
+                or(
+                    and(
+                        field_eq(@field::script, @script(script.clone())),
+                        field_eq(@field::covenant, @covenant(covenant!(absolute_height(@uint(3)))))
+                    ),
+                    and(
+                        field_eq(@field::features_output_type, @output_type(OutputType::Burn)),
+                        and(
+                            field_eq(@field::features_maturity, @uint(0x10)),
+                            and(
+                                field_eq(@field::features_sidechain_feature, @bytes(vec![1, 2, 3])),
+                                fields_hashed_eq(@fields(@field::features, @field::minimum_value_promise), @hash(hash))
+                            )
+                        )
+                    )
+                )
+            )
+        ));
+        let text = covenant.to_text();
+        assert!(text.starts_with("and(\n    xor(\n        not(identity()),\n        output_hash_eq(@hash(0707"));
+        assert_eq!(Covenant::from_text(&text).unwrap(), covenant);
+        assert_eq!(Covenant::from_text(&covenant.to_string()).unwrap(), covenant);
+        assert!(!covenant.to_string().contains('\n'));
+
+        let short = covenant!(fields_preserved(@fields(@field::features_range_proof_type, @field::covenant)));
+        assert_eq!(
+            short.to_text(),
+            "fields_preserved(@fields(@field::features_range_proof_type, @field::covenant))\n"
+        );
+        assert_eq!(Covenant::new().to_text(), "");
+    }
+
+    #[test]
+    fn it_parses_value_and_script_filters() {
+        let text = "and(script_matches(@script(CheckHeightVerify(0))), and(min_value_promise_ge(@uint(1000)), \
+                    and(output_count(@uint(1), @uint(2)), relative_height(@uint(720)))))";
+        let pattern = script!(CheckHeightVerify(0));
+        let covenant = covenant!(and(
+            script_matches(@script(pattern)),
+            and(
+                min_value_promise_ge(@uint(1000)),
+                and(output_count(@uint(1), @uint(2)), relative_height(@uint(720)))
+            )
+        ));
+        assert_eq!(Covenant::from_text(text).unwrap(), covenant);
+        assert_eq!(Covenant::from_text(&covenant.to_text()).unwrap(), covenant);
+        assert_eq!(
+            error("output_count(@uint(1), @bytes(00))").2,
+            "`output_count` expects a `@uint` here, found `@bytes(00)`".to_string()
+        );
+    }
+
+    #[test]
+    fn it_writes_malformed_covenants_as_a_flat_token_list() {
+        let mut covenant = covenant!(identity());
+        covenant.push_token(CovenantToken::uint(1));
+        assert_eq!(covenant.to_text(), "identity @uint(1)");
+        assert!(Covenant::from_text(&covenant.to_text()).is_err());
+    }
+
+    fn error(text: &str) -> (usize, usize, String) {
+        let err = Covenant::from_text(text).unwrap_err();
+        (err.line, err.column, err.message)
+    }
+
+    #[test]
+    fn it_reports_error_locations() {
+        assert_eq!(
+            error("and(identity(),\n  bogus())"),
+            (2, 3, "Unknown filter `bogus`".to_string())
+        );
+        assert_eq!(
+            error("not(identity(), identity())"),
+            (1, 15, "`not` takes 1 argument(s)".to_string())
+        );
+        assert_eq!(
+            error("and(identity())"),
+            (1, 15, "`and` takes 2 arguments but 1 were given".to_string())
+        );
+        assert_eq!(
+            error("absolute_height(@hash(00))").2,
+            "Expected 32 bytes but found 1".to_string()
+        );
+        assert_eq!(
+            error("absolute_height(@bytes(00))"),
+            (
+                1,
+                17,
+                "`absolute_height` expects a `@uint` here, found `@bytes(00)`".to_string()
+            )
+        );
+        assert_eq!(error("field_eq(@field::script, @field::covenant)").1, 26);
+        assert_eq!(error("field_eq(@field::bogus, @uint(1))").1, 18);
+        assert_eq!(error("absolute_height(@uint(-1))").1, 23);
+        assert_eq!(error("identity() identity()").1, 12);
+        assert_eq!(
+            error("identity("),
+            (1, 10, "Expected `)`, found the end of the covenant".to_string())
+        );
+        assert_eq!(
+            error("field_eq(@field::script,\n  @script(Dup Bogus))"),
+            (2, 15, "Unknown opcode `Bogus`".to_string())
+        );
+        assert_eq!(error("field_eq(@field::covenant, @covenant(not()))").1, 42);
+        assert_eq!(error("field_eq(@field::script, @script(Dup").2, "`(` is never closed");
+    }
+
+    #[test]
+    fn it_rejects_covenants_that_are_too_long() {
+        let text = format!(
+            "field_eq(@field::features, @bytes({}))",
+            "00".repeat(MAX_COVENANT_BYTES)
+        );
+        assert!(error(&text).2.contains("maximum of 4096 bytes"));
+    }
+}
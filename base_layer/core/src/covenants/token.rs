@@ -38,9 +38,13 @@ use crate::{
             FieldsHashedEqFilter,
             FieldsPreservedFilter,
             IdentityFilter,
+            MinValuePromiseGeFilter,
             NotFilter,
             OrFilter,
+            OutputCountFilter,
             OutputHashEqFilter,
+            RelativeHeightFilter,
+            ScriptMatchesFilter,
             XorFilter,
         },
         Covenant,
@@ -162,6 +166,30 @@ impl CovenantToken {
         CovenantFilter::AbsoluteHeight(AbsoluteHeightFilter).into()
     }
 
+    #[allow(dead_code)]
+    /// Helper for creating a new instance wrapping an `ScriptMatchesFilter`.
+    pub fn script_matches() -> Self {
+        CovenantFilter::ScriptMatches(ScriptMatchesFilter).into()
+    }
+
+    #[allow(dead_code)]
+    /// Helper for creating a new instance wrapping an `MinValuePromiseGeFilter`.
+    pub fn min_value_promise_ge() -> Self {
+        CovenantFilter::MinValuePromiseGe(MinValuePromiseGeFilter).into()
+    }
+
+    #[allow(dead_code)]
+    /// Helper for creating a new instance wrapping an `OutputCountFilter`.
+    pub fn output_count() -> Self {
+        CovenantFilter::OutputCount(OutputCountFilter).into()
+    }
+
+    #[allow(dead_code)]
+    /// Helper for creating a new instance wrapping an `RelativeHeightFilter`.
+    pub fn relative_height() -> Self {
+        CovenantFilter::RelativeHeight(RelativeHeightFilter).into()
+    }
+
     #[allow(dead_code)]
     /// Helper for creating a new instance wrapping an `HashFilter`.
     pub fn hash(hash: FixedHash) -> Self {
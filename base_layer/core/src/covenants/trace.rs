@@ -0,0 +1,209 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Traced covenant execution, for checking a spending transaction against the covenant of an input before it is
+//! broadcast.
+//!
+//! [Covenant::execute_with_trace] runs a covenant like [Covenant::execute] and records a [CovenantTraceStep] for every
+//! filter, with the outputs the filter was given and the outputs it kept.
+
+use std::fmt;
+
+use crate::{
+    covenants::{
+        arguments::CovenantArg,
+        context::CovenantContext,
+        error::CovenantError,
+        filters::CovenantFilter,
+        text::filter_call_text,
+        Covenant,
+    },
+    transactions::transaction_components::{TransactionInput, TransactionOutput},
+};
+
+/// The record of a single filter in a traced execution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CovenantTraceStep {
+    /// The nesting depth of the filter, 0 for the root filter
+    pub depth: usize,
+    pub filter: CovenantFilter,
+    /// The arguments the filter read
+    pub args: Vec<CovenantArg>,
+    /// The indexes of the outputs the filter was given
+    pub outputs_in: Vec<usize>,
+    /// The indexes of the outputs the filter kept
+    pub outputs_out: Vec<usize>,
+    /// The error that aborted the covenant in this filter
+    pub error: Option<CovenantError>,
+}
+
+impl CovenantTraceStep {
+    /// The filter and its arguments in covenant text, with `..` in place of the filters it takes
+    pub fn filter_text(&self) -> String {
+        filter_call_text(&self.filter, &self.args)
+    }
+}
+
+impl fmt::Display for CovenantTraceStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}  {:?}",
+            "  ".repeat(self.depth),
+            self.filter_text(),
+            self.outputs_in
+        )?;
+        match &self.error {
+            Some(err) => write!(f, " => {}", err),
+            None => write!(f, " => {:?}", self.outputs_out),
+        }
+    }
+}
+
+/// The full record of a traced execution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CovenantTrace {
+    pub steps: Vec<CovenantTraceStep>,
+    /// The indexes of the outputs that match the covenant
+    pub matched_outputs: Vec<usize>,
+    /// The number of matching outputs, as returned by [Covenant::execute]
+    pub result: Result<usize, CovenantError>,
+}
+
+impl CovenantTrace {
+    pub fn is_success(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    /// The filter that aborted the covenant. This is `None` if the covenant succeeded or ran to the end and then
+    /// failed, e.g. because no output matched.
+    pub fn failed_step(&self) -> Option<&CovenantTraceStep> {
+        self.steps.iter().find(|step| step.error.is_some())
+    }
+}
+
+impl fmt::Display for CovenantTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            writeln!(f, "{}", step)?;
+        }
+        match &self.result {
+            Ok(_) => write!(f, "Success: outputs {:?} match", self.matched_outputs),
+            Err(err) => write!(f, "Failed: {}", err),
+        }
+    }
+}
+
+impl Covenant {
+    /// Executes the covenant like [Covenant::execute], recording the outputs that each filter keeps
+    pub fn execute_with_trace(
+        &self,
+        block_height: u64,
+        input: &TransactionInput,
+        outputs: &[TransactionOutput],
+    ) -> CovenantTrace {
+        if self.is_empty() {
+            // Empty covenants always pass
+            return CovenantTrace {
+                steps: Vec::new(),
+                matched_outputs: (0..outputs.len()).collect(),
+                result: Ok(outputs.len()),
+            };
+        }
+
+        let mut cx = CovenantContext::new(self.tokens().to_vec().into(), input, block_height).with_trace();
+        let result = cx.filter_outputs(outputs);
+        let steps = cx.take_trace();
+        match result {
+            Ok(output_set) => CovenantTrace {
+                steps,
+                matched_outputs: output_set.get_selected_indexes(),
+                result: Ok(output_set.len()),
+            },
+            Err(err) => CovenantTrace {
+                steps,
+                matched_outputs: Vec::new(),
+                result: Err(err),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tari_script::script;
+
+    use super::*;
+    use crate::{
+        covenant,
+        covenants::test::{create_input, create_outputs},
+        transactions::test_helpers::{create_test_core_key_manager_with_memory_db, UtxoTestParams},
+    };
+
+    #[tokio::test]
+    async fn it_records_the_outputs_kept_by_each_filter() {
+        let key_manager = create_test_core_key_manager_with_memory_db();
+        let mut outputs = create_outputs(5, UtxoTestParams::default(), &key_manager).await;
+        let script = script!(CheckHeight(101));
+        outputs[1].features.maturity = 42;
+        outputs[3].features.maturity = 42;
+        outputs[3].script = script.clone();
+        let input = create_input(&key_manager).await;
+        let covenant = covenant!(and(
+            field_eq(@field::features_maturity, @uint(42)),
+            field_eq(@field::script, @script(script.clone()))
+        ));
+
+        let trace = covenant.execute_with_trace(0, &input, &outputs);
+        assert_eq!(trace.result, Ok(1));
+        assert_eq!(trace.result, covenant.execute(0, &input, &outputs));
+        assert_eq!(trace.matched_outputs, vec![3]);
+        let steps = trace
+            .steps
+            .iter()
+            .map(|step| (step.depth, step.outputs_in.clone(), step.outputs_out.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(steps, vec![
+            (0, vec![0, 1, 2, 3, 4], vec![3]),
+            (1, vec![0, 1, 2, 3, 4], vec![1, 3]),
+            (1, vec![1, 3], vec![3]),
+        ]);
+        assert_eq!(trace.steps[0].filter_text(), "and(.., ..)");
+        assert_eq!(
+            trace.steps[1].filter_text(),
+            "field_eq(@field::features_maturity, @uint(42))"
+        );
+    }
+
+    #[tokio::test]
+    async fn it_records_the_filter_that_failed() {
+        let key_manager = create_test_core_key_manager_with_memory_db();
+        let outputs = create_outputs(2, UtxoTestParams::default(), &key_manager).await;
+        let input = create_input(&key_manager).await;
+
+        let covenant = covenant!(not(field_eq(@field::script, @uint(1))));
+        let trace = covenant.execute_with_trace(0, &input, &outputs);
+        assert!(!trace.is_success());
+        assert!(trace.steps[0].error.is_none());
+        let failed_step = trace.failed_step().unwrap();
+        assert_eq!(failed_step.depth, 1);
+        assert_eq!(failed_step.error, covenant.execute(0, &input, &outputs).err());
+
+        let covenant = covenant!(not(identity()));
+        let trace = covenant.execute_with_trace(0, &input, &outputs);
+        assert_eq!(trace.result, Err(CovenantError::NoMatchingOutputs));
+        assert!(trace.failed_step().is_none());
+        assert_eq!(trace.steps[0].outputs_out, Vec::<usize>::new());
+    }
+
+    #[tokio::test]
+    async fn it_matches_every_output_with_an_empty_covenant() {
+        let key_manager = create_test_core_key_manager_with_memory_db();
+        let outputs = create_outputs(3, UtxoTestParams::default(), &key_manager).await;
+        let input = create_input(&key_manager).await;
+        let trace = Covenant::new().execute_with_trace(0, &input, &outputs);
+        assert_eq!(trace.result, Ok(3));
+        assert_eq!(trace.matched_outputs, vec![0, 1, 2]);
+        assert!(trace.steps.is_empty());
+    }
+}
@@ -60,6 +60,10 @@ pub enum TransactionKeyManagerBranch {
     KernelNonce,
     ScriptKey,
     SenderOffset,
+    /// Account `n` of the wallet uses the key at index `n` of this branch for its address
+    Account,
+    /// Each payment channel derives its keys from a key of this branch
+    PaymentChannel,
 }
 
 impl TransactionKeyManagerBranch {
@@ -75,6 +79,8 @@ impl TransactionKeyManagerBranch {
             TransactionKeyManagerBranch::KernelNonce => "kernel nonce".to_string(),
             TransactionKeyManagerBranch::ScriptKey => "script key".to_string(),
             TransactionKeyManagerBranch::SenderOffset => "sender offset".to_string(),
+            TransactionKeyManagerBranch::Account => "account".to_string(),
+            TransactionKeyManagerBranch::PaymentChannel => "payment channel".to_string(),
         }
     }
 }
@@ -20,7 +20,7 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::{collections::HashSet, convert::TryInto};
+use std::{collections::HashSet, convert::TryInto, sync::Arc};
 
 use log::{trace, warn};
 use tari_common_types::types::{Commitment, CommitmentFactory, HashOutput, PrivateKey, PublicKey, RangeProofService};
@@ -270,8 +270,20 @@ fn validate_script_and_script_offset(
     // lets count up the input script public keys
     let mut input_keys = PublicKey::default();
     let prev_hash: [u8; 32] = prev_header.unwrap_or_default().as_slice().try_into().unwrap_or([0; 32]);
+    // Inputs are not linked to kernels, so scripts see the kernels of the whole body. The script checks only ever pass
+    // more easily as kernels are added, so a transaction that is valid alone stays valid in a block.
+    let kernel_excesses = Arc::new(body.kernels().iter().map(|k| k.excess.clone()).collect::<Vec<_>>());
+    let lock_height = body.kernels().iter().map(|k| k.lock_height).max().unwrap_or(0);
+    let total_fee = body
+        .kernels()
+        .iter()
+        .fold(0u64, |total, k| total.saturating_add(k.fee.as_u64()));
     for input in body.inputs() {
-        let context = ScriptContext::new(height, &prev_hash, input.commitment()?);
+        let context = ScriptContext::new(height, &prev_hash, input.commitment()?).with_transaction_data(
+            kernel_excesses.clone(),
+            lock_height,
+            total_fee,
+        );
         input_keys = input_keys + input.run_and_verify_script(factory, Some(context))?;
     }
 
@@ -597,4 +609,78 @@ mod test {
             assert!(matches!(err, ValidationError::UnsortedOrDuplicateOutput));
         }
     }
+
+    mod transaction_data_scripts {
+        use tari_script::ScriptError;
+
+        use super::*;
+        use crate::{
+            transactions::{
+                tari_amount::T,
+                test_helpers::{spend_utxos, TestParams, UtxoTestParams},
+                transaction_components::Transaction,
+            },
+            txn_schema,
+        };
+
+        fn validate(tx: &Transaction) -> Result<(), ValidationError> {
+            AggregateBodyInternalConsistencyValidator::new(
+                true,
+                ConsensusManager::builder(Network::LocalNet).build().unwrap(),
+                CryptoFactories::new(RANGE_PROOF_AGGREGATION_FACTOR),
+            )
+            .validate(tx.body(), &tx.offset, &tx.script_offset, None, None, 10)
+        }
+
+        #[tokio::test]
+        async fn it_validates_the_same_transaction_alone_and_in_a_block() {
+            let key_manager = create_test_core_key_manager_with_memory_db();
+            let input = TestParams::new(&key_manager)
+                .await
+                .create_input(
+                    UtxoTestParams {
+                        value: 10 * T,
+                        script: script!(CheckLockHeightVerify(5) CheckTotalFeeVerify(100) Nop),
+                        ..Default::default()
+                    },
+                    &key_manager,
+                )
+                .await;
+            let schema = txn_schema!(
+                from: vec![input.clone()],
+                to: vec![5 * T],
+                fee: 20.into(),
+                lock: 5,
+                features: OutputFeatures::default()
+            );
+            let (tx, _) = spend_utxos(schema, &key_manager).await;
+            validate(&tx).unwrap();
+
+            let other_input = TestParams::new(&key_manager)
+                .await
+                .create_input(UtxoTestParams::with_value(10 * T), &key_manager)
+                .await;
+            let (other_tx, _) = spend_utxos(txn_schema!(from: vec![other_input]), &key_manager).await;
+            validate(&other_tx).unwrap();
+
+            // Adding kernels never breaks the checks, so the transaction is still valid when mined with another one
+            let mut block_tx = tx + other_tx;
+            block_tx.body.sort();
+            validate(&block_tx).unwrap();
+
+            let schema = txn_schema!(
+                from: vec![input],
+                to: vec![5 * T],
+                fee: 20.into(),
+                lock: 4,
+                features: OutputFeatures::default()
+            );
+            let (tx, _) = spend_utxos(schema, &key_manager).await;
+            let err = validate(&tx).unwrap_err();
+            assert!(matches!(
+                err,
+                ValidationError::TransactionError(TransactionError::ScriptError(ScriptError::VerifyFailed))
+            ));
+        }
+    }
 }
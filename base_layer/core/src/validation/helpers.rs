@@ -422,6 +422,20 @@ pub fn validate_output_version(
         }
     }
 
+    for filter in output.covenant.filters() {
+        if !consensus_constants
+            .output_version_range()
+            .covenant_filter
+            .contains(&filter.get_version())
+        {
+            let msg = format!(
+                "Transaction output covenant filter is not allowed by consensus ({})",
+                filter
+            );
+            return Err(ValidationError::ConsensusError(msg));
+        }
+    }
+
     Ok(())
 }
 
@@ -583,4 +597,62 @@ mod test {
             unpack_enum!(TransactionError::InvalidCoinbase = err);
         }
     }
+
+    mod validate_output_version {
+        use tari_script::script;
+
+        use super::*;
+        use crate::{
+            covenant,
+            transactions::test_helpers::{create_test_core_key_manager_with_memory_db, UtxoTestParams},
+        };
+
+        #[tokio::test]
+        async fn it_only_allows_covenant_filters_enabled_by_consensus() {
+            let key_manager = create_test_core_key_manager_with_memory_db();
+            let test_params = TestParams::new(&key_manager).await;
+            let output = test_params
+                .create_output(
+                    UtxoTestParams {
+                        covenant: covenant!(output_count(@uint(1), @uint(1))),
+                        ..Default::default()
+                    },
+                    &key_manager,
+                )
+                .await
+                .unwrap()
+                .to_transaction_output(&key_manager)
+                .await
+                .unwrap();
+
+            validate_output_version(&ConsensusConstants::localnet()[0], &output).unwrap();
+            let err = validate_output_version(&ConsensusConstants::mainnet()[0], &output).unwrap_err();
+            unpack_enum!(ValidationError::ConsensusError(msg) = err);
+            assert!(msg.contains("output_count"));
+        }
+
+        #[tokio::test]
+        async fn it_only_allows_opcodes_enabled_by_consensus() {
+            let key_manager = create_test_core_key_manager_with_memory_db();
+            let test_params = TestParams::new(&key_manager).await;
+            let output = test_params
+                .create_output(
+                    UtxoTestParams {
+                        script: script!(CheckTotalFeeVerify(1) Nop),
+                        ..Default::default()
+                    },
+                    &key_manager,
+                )
+                .await
+                .unwrap()
+                .to_transaction_output(&key_manager)
+                .await
+                .unwrap();
+
+            validate_output_version(&ConsensusConstants::localnet()[0], &output).unwrap();
+            let err = validate_output_version(&ConsensusConstants::mainnet()[0], &output).unwrap_err();
+            unpack_enum!(ValidationError::ConsensusError(msg) = err);
+            assert!(msg.contains("CheckTotalFeeVerify"));
+        }
+    }
 }
@@ -7,4 +7,11 @@ fn main() {
     // generate version info
     let gen = StaticApplicationInfo::initialize().unwrap();
     gen.write_consts_to_outdir("consts.rs").unwrap();
+
+    tari_common::build::ProtobufCompiler::new()
+        .proto_paths(&["proto"])
+        .include_paths(&["proto"])
+        .emit_rerun_if_changed_directives()
+        .compile()
+        .unwrap();
 }
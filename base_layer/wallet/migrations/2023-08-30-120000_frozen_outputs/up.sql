@@ -0,0 +1 @@
+ALTER TABLE outputs ADD frozen INTEGER NOT NULL DEFAULT 0;
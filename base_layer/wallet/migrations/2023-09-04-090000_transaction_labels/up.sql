@@ -0,0 +1,13 @@
+-- User defined labels and tags, attached to either a transaction (`tx_id`) or an output (`commitment`). `kind` is 0 for
+-- the label and 1 for a tag.
+CREATE TABLE transaction_labels
+(
+    id         INTEGER PRIMARY KEY NOT NULL,
+    tx_id      BIGINT              NULL,
+    commitment BLOB                NULL,
+    kind       INTEGER             NOT NULL,
+    value      TEXT                NOT NULL
+);
+
+CREATE INDEX idx_transaction_labels_tx_id ON transaction_labels (tx_id);
+CREATE INDEX idx_transaction_labels_commitment ON transaction_labels (commitment);
@@ -0,0 +1,5 @@
+DROP INDEX idx_outputs_account_id;
+
+ALTER TABLE outputs DROP COLUMN account_id;
+
+DROP TABLE accounts;
@@ -0,0 +1,14 @@
+-- Named accounts that partition the outputs of the wallet. Account 0 is the default account that existing outputs
+-- belong to.
+CREATE TABLE accounts
+(
+    id         BIGINT PRIMARY KEY NOT NULL,
+    name       TEXT UNIQUE        NOT NULL,
+    created_at DATETIME           NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO accounts (id, name) VALUES (0, 'default');
+
+ALTER TABLE outputs ADD account_id BIGINT NOT NULL DEFAULT 0;
+
+CREATE INDEX idx_outputs_account_id ON outputs (account_id);
@@ -0,0 +1,8 @@
+CREATE TABLE recovery_segments (
+    start_height    BIGINT PRIMARY KEY NOT NULL,
+    end_height      BIGINT             NOT NULL,
+    end_header_hash BLOB               NOT NULL,
+    next_height     BIGINT             NOT NULL,
+    num_outputs     BIGINT             NOT NULL DEFAULT 0,
+    amount          BIGINT             NOT NULL DEFAULT 0
+);
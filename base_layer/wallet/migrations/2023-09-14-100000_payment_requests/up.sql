@@ -0,0 +1,22 @@
+-- Payment requests issued by the wallet. `status` is 0 pending, 1 underpaid, 2 paid, 3 expired and 4 cancelled.
+CREATE TABLE payment_requests
+(
+    reference  TEXT PRIMARY KEY NOT NULL,
+    address    BLOB             NOT NULL,
+    amount     BIGINT           NOT NULL,
+    memo       TEXT             NOT NULL,
+    created_at TIMESTAMP        NOT NULL,
+    expires_at TIMESTAMP        NULL,
+    status     INTEGER          NOT NULL
+);
+
+-- The incoming transactions that were matched to a payment request. A transaction can only pay a single request.
+CREATE TABLE payment_request_payments
+(
+    tx_id       BIGINT PRIMARY KEY NOT NULL,
+    reference   TEXT               NOT NULL,
+    amount      BIGINT             NOT NULL,
+    received_at TIMESTAMP          NOT NULL
+);
+
+CREATE INDEX idx_payment_request_payments_reference ON payment_request_payments (reference);
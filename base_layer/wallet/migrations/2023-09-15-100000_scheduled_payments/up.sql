@@ -0,0 +1,23 @@
+-- Payments that are sent at a later time or height, once or on a recurring interval. `status` is 0 active,
+-- 1 completed, 2 cancelled and 3 failed. Exactly one of `start_at` and `start_height` is set; `interval_secs` is only
+-- set for recurring payments.
+CREATE TABLE scheduled_payments
+(
+    id            BIGINT PRIMARY KEY NOT NULL,
+    destination   BLOB               NOT NULL,
+    amount        BIGINT             NOT NULL,
+    fee_per_gram  BIGINT             NOT NULL,
+    message       TEXT               NOT NULL,
+    one_sided     INTEGER            NOT NULL,
+    start_at      TIMESTAMP          NULL,
+    start_height  BIGINT             NULL,
+    interval_secs BIGINT             NULL,
+    max_runs      BIGINT             NULL,
+    next_run_at   TIMESTAMP          NULL,
+    run_count     BIGINT             NOT NULL,
+    attempts      INTEGER            NOT NULL,
+    status        INTEGER            NOT NULL,
+    last_tx_id    BIGINT             NULL,
+    last_error    TEXT               NULL,
+    created_at    TIMESTAMP          NOT NULL
+);
@@ -0,0 +1,15 @@
+-- The spending policy decision log. `kind` is 0 interactive, 1 one-sided, 2 stealth one-sided, 3 burn and 4 SHA atomic
+-- swap. `decision` is 0 allowed, 1 denied, 2 queued for approval, 3 approved and 4 rejected. `destination` is null for
+-- burns.
+CREATE TABLE spending_decisions
+(
+    id          BIGINT PRIMARY KEY NOT NULL,
+    kind        INTEGER            NOT NULL,
+    destination BLOB               NULL,
+    amount      BIGINT             NOT NULL,
+    decision    INTEGER            NOT NULL,
+    reason      TEXT               NULL,
+    created_at  TIMESTAMP          NOT NULL
+);
+
+CREATE INDEX spending_decisions_created_at_index ON spending_decisions (created_at);
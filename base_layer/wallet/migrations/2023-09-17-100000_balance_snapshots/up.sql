@@ -0,0 +1,11 @@
+-- The balance of the wallet after the output manager validated its outputs against the chain tip at `height`. A row is
+-- only written when the balance changed, and rows above a new tip are removed when the chain reorgs.
+CREATE TABLE balance_snapshots
+(
+    height                   BIGINT PRIMARY KEY NOT NULL,
+    available_balance        BIGINT             NOT NULL,
+    time_locked_balance      BIGINT             NOT NULL,
+    pending_incoming_balance BIGINT             NOT NULL,
+    pending_outgoing_balance BIGINT             NOT NULL,
+    created_at               TIMESTAMP          NOT NULL
+);
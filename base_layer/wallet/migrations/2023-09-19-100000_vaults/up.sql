@@ -0,0 +1,16 @@
+-- Timelocked vaults that a hot key can spend from `unlock_height` and a recovery key can spend at any time. `status` is
+-- 0 active, 1 spent with the hot key and 2 recovered with the recovery key.
+CREATE TABLE vaults
+(
+    id                  BIGINT PRIMARY KEY NOT NULL,
+    tx_id               BIGINT             NOT NULL,
+    commitment          BLOB               NOT NULL,
+    output_hash         BLOB               NOT NULL,
+    amount              BIGINT             NOT NULL,
+    hot_public_key      BLOB               NOT NULL,
+    recovery_public_key BLOB               NOT NULL,
+    unlock_height       BIGINT             NOT NULL,
+    status              INTEGER            NOT NULL,
+    spend_tx_id         BIGINT             NULL,
+    created_at          TIMESTAMP          NOT NULL
+);
@@ -0,0 +1,14 @@
+-- Two-party payment channels. `role` is 0 for the opener and 1 for the acceptor, `state` follows
+-- `PaymentChannelState`. The id is chosen by the opener and shared by both parties. `channel_data` holds the encrypted
+-- channel, including the channel seed and the counterparty's revocation secrets.
+CREATE TABLE payment_channels
+(
+    id           BIGINT PRIMARY KEY NOT NULL,
+    role         INTEGER            NOT NULL,
+    state        INTEGER            NOT NULL,
+    counterparty TEXT               NOT NULL,
+    capacity     BIGINT             NOT NULL,
+    channel_data TEXT               NOT NULL,
+    created_at   TIMESTAMP          NOT NULL,
+    updated_at   TIMESTAMP          NOT NULL
+);
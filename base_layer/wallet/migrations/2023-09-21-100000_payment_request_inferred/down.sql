@@ -0,0 +1 @@
+ALTER TABLE payment_request_payments DROP COLUMN inferred;
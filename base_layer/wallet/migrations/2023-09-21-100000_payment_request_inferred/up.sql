@@ -0,0 +1,2 @@
+-- Set when the payment was matched to its request by amount alone, because a one-sided payment carries no reference
+ALTER TABLE payment_request_payments ADD inferred INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1 @@
+ALTER TABLE scheduled_payments DROP COLUMN in_flight_tx_id;
@@ -0,0 +1,2 @@
+-- The transaction of the occurrence that is being sent, stored before the transaction is created
+ALTER TABLE scheduled_payments ADD in_flight_tx_id BIGINT NULL;
@@ -0,0 +1,47 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tari_common::configuration::serializers;
+use tari_core::transactions::tari_amount::MicroMinotari;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChannelServiceConfig {
+    /// Whether channels opened by other wallets are accepted
+    pub accept_incoming_channels: bool,
+    /// The smallest dispute delay, in blocks, of an incoming channel that is accepted
+    pub min_dispute_delay: u64,
+    /// The longest state lifetime, in blocks, of an incoming channel that is accepted
+    pub max_state_lifetime: u64,
+    /// A channel is force closed when the chain tip is this many blocks from the deadline of its latest state
+    pub force_close_margin: u64,
+    /// The fee per gram of the funding transactions and of the transactions that claim the outputs of a closed
+    /// channel
+    pub fee_per_gram: MicroMinotari,
+    /// An update that the counterparty has not signed within this time is abandoned
+    #[serde(with = "serializers::seconds")]
+    pub update_timeout: Duration,
+    /// The interval at which open channels are checked against the chain
+    #[serde(with = "serializers::seconds")]
+    pub monitoring_interval: Duration,
+    /// This is the size of the event channel used to communicate channel events to the wallet
+    pub event_channel_size: usize,
+}
+
+impl Default for ChannelServiceConfig {
+    fn default() -> Self {
+        Self {
+            accept_incoming_channels: true,
+            min_dispute_delay: 720,
+            max_state_lifetime: 1440,
+            force_close_margin: 60,
+            fee_per_gram: MicroMinotari::from(5),
+            update_timeout: Duration::from_secs(60),
+            monitoring_interval: Duration::from_secs(60),
+            event_channel_size: 250,
+        }
+    }
+}
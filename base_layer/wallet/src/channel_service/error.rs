@@ -0,0 +1,90 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use tari_comms::protocol::rpc::RpcError;
+use tari_comms_dht::outbound::DhtOutboundError;
+use tari_core::transactions::{tari_amount::MicroMinotari, transaction_components::TransactionError};
+use tari_crypto::signatures::{CommitmentAndPublicKeySignatureError, SchnorrSignatureError};
+use tari_key_manager::key_manager_service::KeyManagerServiceError;
+use tari_service_framework::reply_channel::TransportChannelError;
+use thiserror::Error;
+
+use crate::{
+    base_node_service::error::BaseNodeServiceError,
+    channel_service::protocol::{PaymentChannelId, PaymentChannelRole, PaymentChannelState},
+    output_manager_service::error::OutputManagerError,
+    transaction_service::error::{TransactionServiceError, TransactionStorageError},
+};
+
+#[derive(Debug, Error)]
+pub enum PaymentChannelError {
+    #[error("Invalid channel parameters: {0}")]
+    InvalidParameters(String),
+    #[error("Cannot {action} while the channel is {state}")]
+    InvalidState {
+        action: &'static str,
+        state: PaymentChannelState,
+    },
+    #[error("Only the {0} can do this")]
+    WrongRole(PaymentChannelRole),
+    #[error("Invalid {0} from the counterparty")]
+    InvalidCounterpartyData(&'static str),
+    #[error("Unexpected {0} from the counterparty")]
+    UnexpectedMessage(&'static str),
+    #[error("Cannot {0} while an update is in progress")]
+    UpdateInProgress(&'static str),
+    #[error("The balance of {0} is not enough")]
+    InsufficientBalance(MicroMinotari),
+    #[error("The counterparty has not revoked state {0}")]
+    NotRevoked(u64),
+    #[error("No signed state {0} to close with")]
+    UnknownSequence(u64),
+    #[error("Signature error: {0}")]
+    SignatureError(#[from] SchnorrSignatureError),
+    #[error("Commitment and public key signature error: {0}")]
+    ComAndPubSignatureError(#[from] CommitmentAndPublicKeySignatureError),
+    #[error("Transaction error: {0}")]
+    TransactionError(#[from] TransactionError),
+    #[error("Unknown channel role {0}")]
+    UnknownRole(i32),
+    #[error("Unknown channel state {0}")]
+    UnknownState(i32),
+}
+
+#[derive(Debug, Error)]
+pub enum ChannelServiceError {
+    #[error("Payment channel error: `{0}`")]
+    PaymentChannelError(#[from] PaymentChannelError),
+    #[error("Transaction storage error: `{0}`")]
+    TransactionStorageError(#[from] TransactionStorageError),
+    #[error("Output manager error: `{0}`")]
+    OutputManagerError(#[from] OutputManagerError),
+    #[error("Transaction service error: `{0}`")]
+    TransactionServiceError(#[from] TransactionServiceError),
+    #[error("Key manager service error: `{0}`")]
+    KeyManagerServiceError(#[from] KeyManagerServiceError),
+    #[error("Outbound error: `{0}`")]
+    OutboundError(#[from] DhtOutboundError),
+    #[error("Transport channel error: `{0}`")]
+    TransportChannelError(#[from] TransportChannelError),
+    #[error("Base node service error: `{0}`")]
+    BaseNodeServiceError(#[from] BaseNodeServiceError),
+    #[error("RPC error: `{0}`")]
+    RpcError(#[from] RpcError),
+    #[error("Payment channel {0} not found")]
+    NotFound(PaymentChannelId),
+    #[error("The message was not sent by the channel counterparty")]
+    UnknownCounterparty,
+    #[error("Invalid channel message: {0}")]
+    InvalidMessage(String),
+    #[error("The funding transaction has no funding output")]
+    MissingFundingOutput,
+    #[error("The chain tip is not known yet")]
+    ChainTipUnknown,
+    #[error("Invalid base node response: {0}")]
+    InvalidBaseNodeResponse(String),
+    #[error("Unexpected API response")]
+    UnexpectedApiResponse,
+    #[error("The channel service is shutting down")]
+    Shutdown,
+}
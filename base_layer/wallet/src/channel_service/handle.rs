@@ -0,0 +1,208 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::sync::Arc;
+
+use tari_common_types::{tari_address::TariAddress, transaction::TxId};
+use tari_core::transactions::tari_amount::MicroMinotari;
+use tari_service_framework::reply_channel::SenderService;
+use tokio::sync::broadcast;
+use tower::Service;
+
+use crate::channel_service::{
+    error::ChannelServiceError,
+    protocol::{ChannelBalances, ChannelParameters, PaymentChannel, PaymentChannelId},
+};
+
+pub type ChannelEventSender = broadcast::Sender<Arc<ChannelEvent>>;
+pub type ChannelEventReceiver = broadcast::Receiver<Arc<ChannelEvent>>;
+
+/// API Request enum
+#[derive(Debug)]
+pub enum ChannelServiceRequest {
+    OpenChannel {
+        counterparty: TariAddress,
+        parameters: ChannelParameters,
+    },
+    Pay {
+        channel_id: PaymentChannelId,
+        amount: MicroMinotari,
+    },
+    CloseChannel(PaymentChannelId),
+    ForceCloseChannel(PaymentChannelId),
+    GetChannel(PaymentChannelId),
+    GetChannels,
+}
+
+/// API Response enum
+#[derive(Debug)]
+pub enum ChannelServiceResponse {
+    ChannelOpened(PaymentChannelId),
+    PaymentProposed(u64),
+    CloseProposed,
+    ForceClosed(TxId),
+    Channel(Box<PaymentChannel>),
+    Channels(Vec<PaymentChannel>),
+}
+
+#[derive(Clone, Debug)]
+pub enum ChannelEvent {
+    /// A channel was opened by either party and waits for the counterparty's signatures
+    ChannelOpened(PaymentChannelId),
+    /// The first state was signed by both parties and the opener published the funding transaction
+    ChannelSigned(PaymentChannelId),
+    /// The funding transaction was mined
+    ChannelFunded(PaymentChannelId),
+    ChannelUpdated {
+        channel_id: PaymentChannelId,
+        sequence: u64,
+        balances: ChannelBalances,
+    },
+    UpdateRejected {
+        channel_id: PaymentChannelId,
+        sequence: u64,
+        reason: String,
+    },
+    UpdateAbandoned {
+        channel_id: PaymentChannelId,
+        sequence: u64,
+    },
+    /// This party published the cooperative close transaction with this id
+    ChannelClosing {
+        channel_id: PaymentChannelId,
+        tx_id: TxId,
+    },
+    /// The cooperative close transaction was mined. This party's output is claimed with the transaction with this
+    /// id, if its balance was positive.
+    ChannelClosed {
+        channel_id: PaymentChannelId,
+        tx_id: Option<TxId>,
+    },
+    /// This party published its latest state with the transaction with this id
+    ChannelForceClosed {
+        channel_id: PaymentChannelId,
+        tx_id: TxId,
+    },
+    /// The dispute delay of the state this party published passed. Its output is claimed with the transaction with
+    /// this id, if its balance was positive.
+    ChannelSettled {
+        channel_id: PaymentChannelId,
+        tx_id: Option<TxId>,
+    },
+    /// The counterparty published a state that it had not revoked. This party's output is claimed with the
+    /// transaction with this id, if its balance was positive.
+    CounterpartyClosed {
+        channel_id: PaymentChannelId,
+        sequence: u64,
+        tx_id: Option<TxId>,
+    },
+    /// The counterparty published a revoked state. The outputs of both parties are claimed with the transaction with
+    /// this id, if there is any.
+    ChannelPunished {
+        channel_id: PaymentChannelId,
+        sequence: u64,
+        tx_id: Option<TxId>,
+    },
+    ChannelAborted(PaymentChannelId),
+}
+
+/// The Channel Service Handle is a struct that contains the interfaces used to communicate with a running Channel
+/// Service
+#[derive(Clone)]
+pub struct ChannelServiceHandle {
+    handle: SenderService<ChannelServiceRequest, Result<ChannelServiceResponse, ChannelServiceError>>,
+    event_stream_sender: ChannelEventSender,
+}
+
+impl ChannelServiceHandle {
+    pub fn new(
+        handle: SenderService<ChannelServiceRequest, Result<ChannelServiceResponse, ChannelServiceError>>,
+        event_stream_sender: ChannelEventSender,
+    ) -> Self {
+        Self {
+            handle,
+            event_stream_sender,
+        }
+    }
+
+    pub fn get_event_stream(&self) -> ChannelEventReceiver {
+        self.event_stream_sender.subscribe()
+    }
+
+    /// Proposes a channel funded by this wallet to the counterparty
+    pub async fn open_channel(
+        &mut self,
+        counterparty: TariAddress,
+        parameters: ChannelParameters,
+    ) -> Result<PaymentChannelId, ChannelServiceError> {
+        match self
+            .handle
+            .call(ChannelServiceRequest::OpenChannel {
+                counterparty,
+                parameters,
+            })
+            .await??
+        {
+            ChannelServiceResponse::ChannelOpened(channel_id) => Ok(channel_id),
+            _ => Err(ChannelServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Proposes an update that pays `amount` to the counterparty and returns its sequence
+    pub async fn pay(
+        &mut self,
+        channel_id: PaymentChannelId,
+        amount: MicroMinotari,
+    ) -> Result<u64, ChannelServiceError> {
+        match self
+            .handle
+            .call(ChannelServiceRequest::Pay { channel_id, amount })
+            .await??
+        {
+            ChannelServiceResponse::PaymentProposed(sequence) => Ok(sequence),
+            _ => Err(ChannelServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Proposes a cooperative close to the counterparty
+    pub async fn close_channel(&mut self, channel_id: PaymentChannelId) -> Result<(), ChannelServiceError> {
+        match self
+            .handle
+            .call(ChannelServiceRequest::CloseChannel(channel_id))
+            .await??
+        {
+            ChannelServiceResponse::CloseProposed => Ok(()),
+            _ => Err(ChannelServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Publishes the latest state without the counterparty and returns the id of the close transaction
+    pub async fn force_close_channel(&mut self, channel_id: PaymentChannelId) -> Result<TxId, ChannelServiceError> {
+        match self
+            .handle
+            .call(ChannelServiceRequest::ForceCloseChannel(channel_id))
+            .await??
+        {
+            ChannelServiceResponse::ForceClosed(tx_id) => Ok(tx_id),
+            _ => Err(ChannelServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_channel(&mut self, channel_id: PaymentChannelId) -> Result<PaymentChannel, ChannelServiceError> {
+        match self
+            .handle
+            .call(ChannelServiceRequest::GetChannel(channel_id))
+            .await??
+        {
+            ChannelServiceResponse::Channel(channel) => Ok(*channel),
+            _ => Err(ChannelServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_channels(&mut self) -> Result<Vec<PaymentChannel>, ChannelServiceError> {
+        match self.handle.call(ChannelServiceRequest::GetChannels).await?? {
+            ChannelServiceResponse::Channels(channels) => Ok(channels),
+            _ => Err(ChannelServiceError::UnexpectedApiResponse),
+        }
+    }
+}
@@ -0,0 +1,174 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Two-party payment channels.
+//!
+//! A channel locks the opener's funds in a funding output shared by both parties. The parties then move the balance
+//! between them with off-chain updates, each of which revokes the previous state, and finally settle the latest state
+//! on chain with a single cooperative close transaction. See [`protocol`] for the construction.
+//!
+//! The service funds the channels this wallet opens through the output manager, and publishes the funding, close and
+//! claim transactions through the transaction service. It watches the base node for the close transactions of its
+//! channels, and claims this wallet's outputs of whichever one is mined, including the counterparty's output when the
+//! counterparty published a revoked state. The keys of each channel are derived from a key of the key manager.
+//!
+//! Limitations:
+//! - Tari lock heights are absolute, so every state carries a deadline by which it must be published, and its
+//!   settlement unlocks `dispute_delay` blocks after that deadline. The opener refreshes the deadline while the channel
+//!   is idle, and the service force closes a channel when the chain tip comes within `force_close_margin` blocks of the
+//!   deadline of its latest state.
+//! - A revoked state is only punished if the wallet is online to claim the counterparty's output before the settlement
+//!   height of that state.
+//! - Every revocation secret of the counterparty is kept, so the stored channel grows with the number of updates.
+
+pub mod config;
+pub mod error;
+pub mod handle;
+pub mod proto;
+pub mod protocol;
+pub mod service;
+
+use std::{marker::PhantomData, sync::Arc};
+
+use futures::{Stream, StreamExt};
+use log::*;
+use tari_common::configuration::Network;
+use tari_comms_dht::Dht;
+use tari_core::transactions::{key_manager::TransactionKeyManagerInterface, CryptoFactories};
+use tari_p2p::{
+    comms_connector::SubscriptionFactory,
+    domain_message::DomainMessage,
+    services::utils::map_decode,
+    tari_message::TariMessageType,
+};
+use tari_service_framework::{
+    async_trait,
+    reply_channel,
+    ServiceInitializationError,
+    ServiceInitializer,
+    ServiceInitializerContext,
+};
+use tokio::sync::broadcast;
+
+use crate::{
+    base_node_service::handle::BaseNodeServiceHandle,
+    channel_service::{config::ChannelServiceConfig, handle::ChannelServiceHandle, service::ChannelService},
+    connectivity_service::WalletConnectivityHandle,
+    output_manager_service::handle::OutputManagerHandle,
+    transaction_service::{
+        handle::TransactionServiceHandle,
+        storage::database::{TransactionBackend, TransactionDatabase},
+    },
+};
+
+const LOG_TARGET: &str = "wallet::channel_service";
+const SUBSCRIPTION_LABEL: &str = "Channel Service";
+
+pub struct ChannelServiceInitializer<T, TKeyManagerInterface>
+where T: TransactionBackend
+{
+    config: ChannelServiceConfig,
+    subscription_factory: Arc<SubscriptionFactory>,
+    backend: Option<T>,
+    factories: CryptoFactories,
+    network: Network,
+    _phantom_data: PhantomData<TKeyManagerInterface>,
+}
+
+impl<T, TKeyManagerInterface> ChannelServiceInitializer<T, TKeyManagerInterface>
+where T: TransactionBackend
+{
+    pub fn new(
+        config: ChannelServiceConfig,
+        subscription_factory: Arc<SubscriptionFactory>,
+        backend: T,
+        factories: CryptoFactories,
+        network: Network,
+    ) -> Self {
+        Self {
+            config,
+            subscription_factory,
+            backend: Some(backend),
+            factories,
+            network,
+            _phantom_data: Default::default(),
+        }
+    }
+
+    /// Get a stream of inbound payment channel messages
+    fn channel_message_stream(
+        &self,
+    ) -> impl Stream<Item = DomainMessage<Result<proto::ChannelMessage, prost::DecodeError>>> {
+        trace!(
+            target: LOG_TARGET,
+            "Subscription '{}' for topic '{:?}' created.",
+            SUBSCRIPTION_LABEL,
+            TariMessageType::PaymentChannel
+        );
+        self.subscription_factory
+            .get_subscription(TariMessageType::PaymentChannel, SUBSCRIPTION_LABEL)
+            .map(map_decode::<proto::ChannelMessage>)
+    }
+}
+
+#[async_trait]
+impl<T, TKeyManagerInterface> ServiceInitializer for ChannelServiceInitializer<T, TKeyManagerInterface>
+where
+    T: TransactionBackend + 'static,
+    TKeyManagerInterface: TransactionKeyManagerInterface,
+{
+    async fn initialize(&mut self, context: ServiceInitializerContext) -> Result<(), ServiceInitializationError> {
+        let (sender, receiver) = reply_channel::unbounded();
+        let message_stream = self.channel_message_stream();
+
+        let (publisher, _) = broadcast::channel(self.config.event_channel_size);
+
+        let channel_handle = ChannelServiceHandle::new(sender, publisher.clone());
+
+        // Register handle before waiting for handles to be ready
+        context.register_handle(channel_handle);
+
+        let backend = self
+            .backend
+            .take()
+            .expect("Cannot start Channel Service without providing a backend");
+        let config = self.config.clone();
+        let factories = self.factories.clone();
+        let network = self.network;
+
+        context.spawn_when_ready(move |handles| async move {
+            let outbound_message_service = handles.expect_handle::<Dht>().outbound_requester();
+            let connectivity = handles.expect_handle::<WalletConnectivityHandle>();
+            let base_node_service_handle = handles.expect_handle::<BaseNodeServiceHandle>();
+            let output_manager_service = handles.expect_handle::<OutputManagerHandle>();
+            let transaction_service = handles.expect_handle::<TransactionServiceHandle>();
+            let key_manager = handles.expect_handle::<TKeyManagerInterface>();
+
+            let result = ChannelService::new(
+                config,
+                TransactionDatabase::new(backend),
+                receiver,
+                message_stream,
+                outbound_message_service,
+                connectivity,
+                base_node_service_handle,
+                output_manager_service,
+                transaction_service,
+                key_manager,
+                factories,
+                publisher,
+                network,
+                handles.get_shutdown_signal(),
+            )
+            .start()
+            .await;
+
+            if let Err(e) = result {
+                error!(target: LOG_TARGET, "Channel Service error: {}", e);
+            }
+            info!(target: LOG_TARGET, "Channel Service shutdown");
+        });
+
+        Ok(())
+    }
+}
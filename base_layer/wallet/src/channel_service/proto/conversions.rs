@@ -0,0 +1,374 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::convert::{TryFrom, TryInto};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use tari_common_types::types::{PrivateKey, PublicKey};
+use tari_core::transactions::tari_amount::MicroMinotari;
+use tari_utilities::ByteArray;
+
+use crate::channel_service::{
+    proto,
+    protocol::{
+        AcceptChannel,
+        AcceptClose,
+        AcceptUpdate,
+        ChannelBalances,
+        ChannelKeys,
+        ChannelMessage,
+        ChannelParameters,
+        ConfirmUpdate,
+        OpenChannel,
+        PaymentChannelId,
+        ProposeClose,
+        ProposeUpdate,
+        RejectUpdate,
+        RevokeUpdate,
+        StateKeys,
+        StateSignatures,
+    },
+};
+
+impl proto::ChannelMessage {
+    pub fn new(channel_id: PaymentChannelId, message: ChannelMessage) -> Self {
+        Self {
+            channel_id,
+            message: Some(message.into()),
+        }
+    }
+}
+
+impl TryFrom<proto::ChannelMessage> for ChannelMessage {
+    type Error = String;
+
+    fn try_from(message: proto::ChannelMessage) -> Result<Self, Self::Error> {
+        use proto::channel_message::Message;
+        Ok(
+            match message.message.ok_or_else(|| "Channel message is empty".to_string())? {
+                Message::Open(open) => ChannelMessage::Open(OpenChannel {
+                    parameters: required(open.parameters, "parameters")?.into(),
+                    deadline: open.deadline,
+                    keys: required(open.keys, "keys")?.try_into()?,
+                    funding_mask: private_key(&open.funding_mask)?,
+                    state: required(open.state, "state")?.try_into()?,
+                }),
+                Message::Accept(accept) => ChannelMessage::Accept(AcceptChannel {
+                    keys: required(accept.keys, "keys")?.try_into()?,
+                    state: required(accept.state, "state")?.try_into()?,
+                    signatures: required(accept.signatures, "signatures")?.try_into()?,
+                }),
+                Message::ProposeUpdate(proposal) => ChannelMessage::ProposeUpdate(ProposeUpdate {
+                    sequence: proposal.sequence,
+                    balances: required(proposal.balances, "balances")?.into(),
+                    deadline: proposal.deadline,
+                    state: required(proposal.state, "state")?.try_into()?,
+                }),
+                Message::AcceptUpdate(accept) => ChannelMessage::AcceptUpdate(AcceptUpdate {
+                    sequence: accept.sequence,
+                    state: required(accept.state, "state")?.try_into()?,
+                    signatures: required(accept.signatures, "signatures")?.try_into()?,
+                }),
+                Message::ConfirmUpdate(confirm) => ChannelMessage::ConfirmUpdate(ConfirmUpdate {
+                    sequence: confirm.sequence,
+                    signatures: required(confirm.signatures, "signatures")?.try_into()?,
+                    revocation: if confirm.revocation.is_empty() {
+                        None
+                    } else {
+                        Some(hash(&confirm.revocation)?)
+                    },
+                    funding_output: optional(&confirm.funding_output)?,
+                }),
+                Message::RevokeUpdate(revoke) => ChannelMessage::RevokeUpdate(RevokeUpdate {
+                    revoked_sequence: revoke.revoked_sequence,
+                    revocation: hash(&revoke.revocation)?,
+                }),
+                Message::RejectUpdate(reject) => ChannelMessage::RejectUpdate(RejectUpdate {
+                    sequence: reject.sequence,
+                    reason: reject.reason,
+                }),
+                Message::ProposeClose(proposal) => ChannelMessage::ProposeClose(ProposeClose {
+                    sequence: proposal.sequence,
+                    nonce: public_key(&proposal.nonce)?,
+                }),
+                Message::AcceptClose(accept) => ChannelMessage::AcceptClose(AcceptClose {
+                    sequence: accept.sequence,
+                    signatures: required(accept.signatures, "signatures")?.try_into()?,
+                }),
+            },
+        )
+    }
+}
+
+impl From<ChannelMessage> for proto::channel_message::Message {
+    fn from(message: ChannelMessage) -> Self {
+        use proto::channel_message::Message;
+        match message {
+            ChannelMessage::Open(open) => Message::Open(proto::OpenChannel {
+                parameters: Some(open.parameters.into()),
+                keys: Some(open.keys.into()),
+                state: Some(open.state.into()),
+                deadline: open.deadline,
+                funding_mask: open.funding_mask.to_vec(),
+            }),
+            ChannelMessage::Accept(accept) => Message::Accept(proto::AcceptChannel {
+                keys: Some(accept.keys.into()),
+                state: Some(accept.state.into()),
+                signatures: Some(accept.signatures.into()),
+            }),
+            ChannelMessage::ProposeUpdate(proposal) => Message::ProposeUpdate(proto::ProposeUpdate {
+                sequence: proposal.sequence,
+                balances: Some(proposal.balances.into()),
+                state: Some(proposal.state.into()),
+                deadline: proposal.deadline,
+            }),
+            ChannelMessage::AcceptUpdate(accept) => Message::AcceptUpdate(proto::AcceptUpdate {
+                sequence: accept.sequence,
+                state: Some(accept.state.into()),
+                signatures: Some(accept.signatures.into()),
+            }),
+            ChannelMessage::ConfirmUpdate(confirm) => Message::ConfirmUpdate(proto::ConfirmUpdate {
+                sequence: confirm.sequence,
+                signatures: Some(confirm.signatures.into()),
+                revocation: confirm.revocation.map(|secret| secret.to_vec()).unwrap_or_default(),
+                funding_output: confirm.funding_output.as_ref().map(encode).unwrap_or_default(),
+            }),
+            ChannelMessage::RevokeUpdate(revoke) => Message::RevokeUpdate(proto::RevokeUpdate {
+                revoked_sequence: revoke.revoked_sequence,
+                revocation: revoke.revocation.to_vec(),
+            }),
+            ChannelMessage::RejectUpdate(reject) => Message::RejectUpdate(proto::RejectUpdate {
+                sequence: reject.sequence,
+                reason: reject.reason,
+            }),
+            ChannelMessage::ProposeClose(proposal) => Message::ProposeClose(proto::ProposeClose {
+                sequence: proposal.sequence,
+                nonce: proposal.nonce.to_vec(),
+            }),
+            ChannelMessage::AcceptClose(accept) => Message::AcceptClose(proto::AcceptClose {
+                sequence: accept.sequence,
+                signatures: Some(accept.signatures.into()),
+            }),
+        }
+    }
+}
+
+impl From<ChannelParameters> for proto::ChannelParameters {
+    fn from(parameters: ChannelParameters) -> Self {
+        Self {
+            capacity: parameters.capacity.as_u64(),
+            fee: parameters.fee.as_u64(),
+            state_lifetime: parameters.state_lifetime,
+            dispute_delay: parameters.dispute_delay,
+        }
+    }
+}
+
+impl From<proto::ChannelParameters> for ChannelParameters {
+    fn from(parameters: proto::ChannelParameters) -> Self {
+        Self {
+            capacity: MicroMinotari::from(parameters.capacity),
+            fee: MicroMinotari::from(parameters.fee),
+            state_lifetime: parameters.state_lifetime,
+            dispute_delay: parameters.dispute_delay,
+        }
+    }
+}
+
+impl From<ChannelBalances> for proto::ChannelBalances {
+    fn from(balances: ChannelBalances) -> Self {
+        Self {
+            opener: balances.opener.as_u64(),
+            acceptor: balances.acceptor.as_u64(),
+        }
+    }
+}
+
+impl From<proto::ChannelBalances> for ChannelBalances {
+    fn from(balances: proto::ChannelBalances) -> Self {
+        Self {
+            opener: MicroMinotari::from(balances.opener),
+            acceptor: MicroMinotari::from(balances.acceptor),
+        }
+    }
+}
+
+impl From<ChannelKeys> for proto::ChannelKeys {
+    fn from(keys: ChannelKeys) -> Self {
+        Self {
+            script_public_key: keys.script_public_key.to_vec(),
+        }
+    }
+}
+
+impl TryFrom<proto::ChannelKeys> for ChannelKeys {
+    type Error = String;
+
+    fn try_from(keys: proto::ChannelKeys) -> Result<Self, Self::Error> {
+        Ok(Self {
+            script_public_key: public_key(&keys.script_public_key)?,
+        })
+    }
+}
+
+impl From<StateKeys> for proto::StateKeys {
+    fn from(keys: StateKeys) -> Self {
+        Self {
+            payout_public_key: keys.payout_public_key.to_vec(),
+            revocation_hash: keys.revocation_hash.to_vec(),
+            script_nonce: keys.script_nonce.to_vec(),
+        }
+    }
+}
+
+impl TryFrom<proto::StateKeys> for StateKeys {
+    type Error = String;
+
+    fn try_from(keys: proto::StateKeys) -> Result<Self, Self::Error> {
+        Ok(Self {
+            payout_public_key: public_key(&keys.payout_public_key)?,
+            revocation_hash: hash(&keys.revocation_hash)?,
+            script_nonce: public_key(&keys.script_nonce)?,
+        })
+    }
+}
+
+impl From<StateSignatures> for proto::StateSignatures {
+    fn from(signatures: StateSignatures) -> Self {
+        Self {
+            publisher_output: signatures.publisher_output.as_ref().map(encode).unwrap_or_default(),
+            publisher_mask: signatures.publisher_mask.to_vec(),
+            signer_output: signatures.signer_output.as_ref().map(encode).unwrap_or_default(),
+            kernel: encode(&signatures.kernel),
+            script_signature: encode(&signatures.script_signature),
+            script_offset: signatures.script_offset.to_vec(),
+        }
+    }
+}
+
+impl TryFrom<proto::StateSignatures> for StateSignatures {
+    type Error = String;
+
+    fn try_from(signatures: proto::StateSignatures) -> Result<Self, Self::Error> {
+        Ok(Self {
+            publisher_output: optional(&signatures.publisher_output)?,
+            publisher_mask: private_key(&signatures.publisher_mask)?,
+            signer_output: optional(&signatures.signer_output)?,
+            kernel: decode(&signatures.kernel)?,
+            script_signature: decode(&signatures.script_signature)?,
+            script_offset: private_key(&signatures.script_offset)?,
+        })
+    }
+}
+
+fn required<T>(field: Option<T>, name: &str) -> Result<T, String> {
+    field.ok_or_else(|| format!("Channel message field '{}' is missing", name))
+}
+
+fn public_key(bytes: &[u8]) -> Result<PublicKey, String> {
+    PublicKey::from_bytes(bytes).map_err(|e| e.to_string())
+}
+
+fn private_key(bytes: &[u8]) -> Result<PrivateKey, String> {
+    PrivateKey::from_bytes(bytes).map_err(|e| e.to_string())
+}
+
+fn hash(bytes: &[u8]) -> Result<[u8; 32], String> {
+    bytes
+        .try_into()
+        .map_err(|_| format!("Expected 32 bytes, got {}", bytes.len()))
+}
+
+fn encode<T: BorshSerialize>(value: &T) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    BorshSerialize::serialize(value, &mut bytes).expect("Borsh serialization into a vector cannot fail");
+    bytes
+}
+
+fn decode<T: BorshDeserialize>(bytes: &[u8]) -> Result<T, String> {
+    T::try_from_slice(bytes).map_err(|e| e.to_string())
+}
+
+/// Decodes a value that is encoded as empty bytes when absent
+fn optional<T: BorshDeserialize>(bytes: &[u8]) -> Result<Option<T>, String> {
+    if bytes.is_empty() {
+        Ok(None)
+    } else {
+        decode(bytes).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::rngs::OsRng;
+    use tari_core::transactions::CryptoFactories;
+    use tari_crypto::keys::SecretKey;
+
+    use super::*;
+    use crate::channel_service::protocol::PaymentChannel;
+
+    #[test]
+    fn it_converts_channel_messages() {
+        let factories = CryptoFactories::default();
+        let parameters = ChannelParameters {
+            capacity: MicroMinotari::from(10_000_000),
+            fee: MicroMinotari::from(1_000),
+            state_lifetime: 1_000,
+            dispute_delay: 100,
+        };
+        let seed = PrivateKey::random(&mut OsRng);
+        let (mut opener, open) = PaymentChannel::open(Default::default(), parameters, 100, &seed).unwrap();
+        let seed = PrivateKey::random(&mut OsRng);
+        let (_, accept) =
+            PaymentChannel::accept(opener.id, Default::default(), open.clone(), 100, &seed, &factories).unwrap();
+        let confirm = opener.accept_channel(accept.clone(), &factories).unwrap();
+        opener.funding_confirmed().unwrap();
+        let close = opener.clone().propose_close().unwrap();
+        let proposal = opener.propose_payment(MicroMinotari::from(1_000), 150).unwrap();
+        // The first state pays the whole balance to the opener, so only the publisher output of its version exists
+        assert!(accept.signatures.signer_output.is_none());
+        for message in [
+            ChannelMessage::Open(open),
+            ChannelMessage::ProposeUpdate(proposal),
+            ChannelMessage::Accept(accept.clone()),
+            ChannelMessage::ConfirmUpdate(confirm.clone()),
+            ChannelMessage::ConfirmUpdate(ConfirmUpdate {
+                sequence: 3,
+                signatures: accept.signatures.clone(),
+                revocation: Some([7u8; 32]),
+                funding_output: accept.signatures.publisher_output.clone(),
+            }),
+            ChannelMessage::RevokeUpdate(RevokeUpdate {
+                revoked_sequence: 2,
+                revocation: [9u8; 32],
+            }),
+            ChannelMessage::ProposeClose(close),
+            ChannelMessage::AcceptClose(AcceptClose {
+                sequence: 0,
+                signatures: confirm.signatures,
+            }),
+        ] {
+            let proto = proto::ChannelMessage::new(opener.id, message.clone());
+            assert_eq!(proto.channel_id, opener.id);
+            assert_eq!(ChannelMessage::try_from(proto).unwrap(), message);
+        }
+
+        let mut proto = proto::ChannelMessage::new(
+            opener.id,
+            ChannelMessage::RevokeUpdate(RevokeUpdate {
+                revoked_sequence: 2,
+                revocation: [9u8; 32],
+            }),
+        );
+        if let Some(proto::channel_message::Message::RevokeUpdate(revoke)) = proto.message.as_mut() {
+            revoke.revocation.truncate(31);
+        }
+        assert!(ChannelMessage::try_from(proto).is_err());
+
+        let mut proto = proto::ChannelMessage::new(opener.id, ChannelMessage::Accept(accept));
+        if let Some(proto::channel_message::Message::Accept(accept)) = proto.message.as_mut() {
+            accept.signatures.as_mut().unwrap().kernel.truncate(10);
+        }
+        assert!(ChannelMessage::try_from(proto).is_err());
+    }
+}
@@ -0,0 +1,6 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+include!(concat!(env!("OUT_DIR"), "/tari.wallet.payment_channel.rs"));
+
+mod conversions;
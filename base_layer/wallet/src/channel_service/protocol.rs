@@ -0,0 +1,2629 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! The payment channel state machine.
+//!
+//! The opener funds the channel with a funding output of value `capacity`. Its mask `x_F` is chosen by the opener and
+//! shared with the acceptor, and its script is
+//!
+//! ```text
+//! CompareHeight LeZero IfThen PushPubKey(K) Else Return EndIf
+//! ```
+//!
+//! where `K = c_o * K_o + c_a * K_a` aggregates the script keys of both parties. The coefficients commit to both keys,
+//! so that neither party can choose its key to cancel out the other's. The input data of the funding output is the
+//! height up to which the spending transaction can be mined, so that knowing `x_F` is not enough to spend it.
+//!
+//! Every state `n` of the channel has a deadline `d_n`, the last height at which it can be published, and exists in
+//! two versions, one that each party can publish. The version published by party `P` is a close transaction that
+//! spends the funding output with the input data `d_n` into an output for each party with a positive balance.
+//! `P`'s output has the script
+//!
+//! ```text
+//! HashSha256 PushHash(h_P) Equal IfThen
+//!     PushPubKey(Q_C)
+//! Else
+//!     CheckHeightVerify(d_n + dispute_delay) PushPubKey(Q_P)
+//! EndIf
+//! ```
+//!
+//! where `h_P` is the SHA-256 hash of `P`'s revocation secret for the state, and `Q_P` and `Q_C` are the payout keys of
+//! `P` and the counterparty `C` for the state. `C`'s output has the script `PushPubKey(Q_C)`. The close transaction
+//! pays the channel fee, and each party pays the fee of the transaction that claims its output.
+//!
+//! `C` builds the whole close transaction of `P`'s version: both outputs with their range proofs and metadata
+//! signatures, the kernel, and its share of the script signature and script offset of the funding input. `P` verifies
+//! the transaction and adds its own share when it publishes the state.
+//!
+//! Tari lock heights are absolute, so the dispute window cannot start at the height the close transaction is mined.
+//! Since the funding script rejects a close transaction mined after `d_n`, the counterparty still has at least
+//! `dispute_delay` blocks to react to any published state. The proposer of a state sets its deadline `state_lifetime`
+//! blocks after the chain tip, and the opener renews the deadline with an update to the same balances before it
+//! runs out.
+//!
+//! Moving to a new state, each party hands over the revocation secret of its previous state, so that if it publishes
+//! a revoked state the counterparty can take its output with the penalty path before the dispute delay passes.
+//!
+//! Message flow of an update proposed by `X` to `Y`:
+//! 1. `X` sends a [`ProposeUpdate`] with the new balances, the deadline and its keys for the state.
+//! 2. `Y` sends an [`AcceptUpdate`] with its keys and the close transaction of `X`'s version.
+//! 3. `X` sends a [`ConfirmUpdate`] with the close transaction of `Y`'s version and its previous revocation secret.
+//! 4. `Y` sends a [`RevokeUpdate`] with its previous revocation secret.
+//!
+//! Opening the channel follows the same flow for state `0` with an [`OpenChannel`] and an [`AcceptChannel`], and has
+//! no revocation. The opener publishes the funding transaction once its version of state `0` is signed, and sends the
+//! funding output to the acceptor in its [`ConfirmUpdate`]. The cooperative close is built in the same way by the
+//! party that accepts the close, with plain payout outputs and the input data `i64::MAX`, which has no deadline.
+//!
+//! All keys, nonces and revocation secrets of a party are derived from a per-channel seed. A party completes the
+//! script signature of one of its versions at most once: the counterparty's share of a state is fixed by the first
+//! message that carries it.
+
+use std::{
+    cmp::max,
+    convert::TryFrom,
+    fmt,
+    fmt::{Display, Formatter},
+};
+
+use blake2::Blake2b;
+use chrono::{NaiveDateTime, Utc};
+use digest::consts::U32;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tari_common_types::{
+    tari_address::TariAddress,
+    types::{ComAndPubSignature, Commitment, FixedHash, PrivateKey, PublicKey, RangeProof, Signature},
+};
+use tari_core::{
+    covenants::Covenant,
+    transactions::{
+        tari_amount::MicroMinotari,
+        transaction_components::{
+            EncryptedData,
+            KernelFeatures,
+            OutputFeatures,
+            Transaction,
+            TransactionError,
+            TransactionInput,
+            TransactionInputVersion,
+            TransactionKernel,
+            TransactionKernelVersion,
+            TransactionOutput,
+            TransactionOutputVersion,
+        },
+        CryptoFactories,
+    },
+};
+use tari_crypto::{
+    commitment::HomomorphicCommitmentFactory,
+    hash_domain,
+    hashing::DomainSeparatedHasher,
+    keys::{PublicKey as PublicKeyTrait, SecretKey},
+    range_proof::RangeProofService,
+};
+use tari_script::{script, ExecutionStack, StackItem, TariScript};
+use tari_utilities::ByteArray;
+
+use crate::channel_service::error::PaymentChannelError;
+
+hash_domain!(
+    PaymentChannelHashDomain,
+    "com.tari.base_layer.wallet.payment_channel",
+    0
+);
+
+pub type PaymentChannelId = u64;
+
+/// The number of blocks the counterparty's chain tip may be ahead of this party's when it sets the deadline of a state
+const DEADLINE_TOLERANCE: u64 = 5;
+
+/// The side of the channel this wallet is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PaymentChannelRole {
+    /// Funds the channel and starts with the whole balance
+    Opener,
+    Acceptor,
+}
+
+impl PaymentChannelRole {
+    pub fn counterparty(self) -> Self {
+        match self {
+            PaymentChannelRole::Opener => PaymentChannelRole::Acceptor,
+            PaymentChannelRole::Acceptor => PaymentChannelRole::Opener,
+        }
+    }
+}
+
+impl Display for PaymentChannelRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PaymentChannelRole::Opener => write!(f, "opener"),
+            PaymentChannelRole::Acceptor => write!(f, "acceptor"),
+        }
+    }
+}
+
+impl From<PaymentChannelRole> for i32 {
+    fn from(role: PaymentChannelRole) -> Self {
+        match role {
+            PaymentChannelRole::Opener => 0,
+            PaymentChannelRole::Acceptor => 1,
+        }
+    }
+}
+
+impl TryFrom<i32> for PaymentChannelRole {
+    type Error = PaymentChannelError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(PaymentChannelRole::Opener),
+            1 => Ok(PaymentChannelRole::Acceptor),
+            v => Err(PaymentChannelError::UnknownRole(v)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PaymentChannelState {
+    /// Waiting for both parties to sign the first state
+    Opening,
+    /// The first state is signed by both parties, waiting for the funding transaction to be mined
+    Funding,
+    Open,
+    /// A cooperative close was proposed or accepted
+    Closing,
+    /// The cooperative close transaction was mined
+    Closed,
+    /// This party published its latest state and waits for the dispute delay to pass
+    ForceClosing,
+    /// This party claimed its output of the state it published
+    Settled,
+    /// The counterparty published a revoked state and this party took both outputs
+    Punished,
+    /// Abandoned before the channel was funded
+    Aborted,
+    /// The counterparty published one of its versions that was not revoked
+    ClosedByCounterparty,
+}
+
+impl Display for PaymentChannelState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PaymentChannelState::Opening => write!(f, "Opening"),
+            PaymentChannelState::Funding => write!(f, "Funding"),
+            PaymentChannelState::Open => write!(f, "Open"),
+            PaymentChannelState::Closing => write!(f, "Closing"),
+            PaymentChannelState::Closed => write!(f, "Closed"),
+            PaymentChannelState::ForceClosing => write!(f, "Force closing"),
+            PaymentChannelState::Settled => write!(f, "Settled"),
+            PaymentChannelState::Punished => write!(f, "Punished"),
+            PaymentChannelState::Aborted => write!(f, "Aborted"),
+            PaymentChannelState::ClosedByCounterparty => write!(f, "Closed by counterparty"),
+        }
+    }
+}
+
+impl From<PaymentChannelState> for i32 {
+    fn from(state: PaymentChannelState) -> Self {
+        match state {
+            PaymentChannelState::Opening => 0,
+            PaymentChannelState::Funding => 1,
+            PaymentChannelState::Open => 2,
+            PaymentChannelState::Closing => 3,
+            PaymentChannelState::Closed => 4,
+            PaymentChannelState::ForceClosing => 5,
+            PaymentChannelState::Settled => 6,
+            PaymentChannelState::Punished => 7,
+            PaymentChannelState::Aborted => 8,
+            PaymentChannelState::ClosedByCounterparty => 9,
+        }
+    }
+}
+
+impl TryFrom<i32> for PaymentChannelState {
+    type Error = PaymentChannelError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(PaymentChannelState::Opening),
+            1 => Ok(PaymentChannelState::Funding),
+            2 => Ok(PaymentChannelState::Open),
+            3 => Ok(PaymentChannelState::Closing),
+            4 => Ok(PaymentChannelState::Closed),
+            5 => Ok(PaymentChannelState::ForceClosing),
+            6 => Ok(PaymentChannelState::Settled),
+            7 => Ok(PaymentChannelState::Punished),
+            8 => Ok(PaymentChannelState::Aborted),
+            9 => Ok(PaymentChannelState::ClosedByCounterparty),
+            v => Err(PaymentChannelError::UnknownState(v)),
+        }
+    }
+}
+
+/// The terms of a channel, proposed by the opener
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelParameters {
+    /// The value of the funding output
+    pub capacity: MicroMinotari,
+    /// The fee of each transaction that spends the funding output
+    pub fee: MicroMinotari,
+    /// The number of blocks from the signing of a state to its deadline, the last height at which it can be published
+    pub state_lifetime: u64,
+    /// The number of blocks after the deadline of a published state during which it can still be disputed
+    pub dispute_delay: u64,
+}
+
+impl ChannelParameters {
+    pub fn validate(&self) -> Result<(), PaymentChannelError> {
+        if self.capacity <= self.fee {
+            return Err(PaymentChannelError::InvalidParameters(
+                "The capacity must be more than the fee".to_string(),
+            ));
+        }
+        if self.state_lifetime == 0 {
+            return Err(PaymentChannelError::InvalidParameters(
+                "The state lifetime must be at least one block".to_string(),
+            ));
+        }
+        if self.dispute_delay == 0 {
+            return Err(PaymentChannelError::InvalidParameters(
+                "The dispute delay must be at least one block".to_string(),
+            ));
+        }
+        if self
+            .state_lifetime
+            .checked_add(self.dispute_delay)
+            .and_then(|blocks| i64::try_from(blocks).ok())
+            .is_none()
+        {
+            return Err(PaymentChannelError::InvalidParameters(
+                "The state lifetime and dispute delay are too long".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The amount shared between the parties: the capacity less the fee of the close transaction
+    pub fn spendable(&self) -> MicroMinotari {
+        self.capacity - self.fee
+    }
+
+    /// The height from which the publisher of a state with the given deadline can claim its output
+    pub fn settlement_height(&self, deadline: u64) -> u64 {
+        deadline + self.dispute_delay
+    }
+
+    /// The deadline of a state proposed at the given chain tip
+    fn deadline(&self, tip_height: u64) -> u64 {
+        tip_height.saturating_add(self.state_lifetime)
+    }
+
+    /// Checks the deadline of a state proposed by the counterparty, whose chain tip may be a few blocks ahead
+    fn check_deadline(&self, deadline: u64, tip_height: u64) -> Result<(), PaymentChannelError> {
+        let latest = self.deadline(tip_height).saturating_add(DEADLINE_TOLERANCE);
+        let settlement_height = deadline
+            .checked_add(self.dispute_delay)
+            .and_then(|height| i64::try_from(height).ok());
+        if deadline <= tip_height || deadline > latest || settlement_height.is_none() {
+            return Err(PaymentChannelError::InvalidCounterpartyData("deadline"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelBalances {
+    pub opener: MicroMinotari,
+    pub acceptor: MicroMinotari,
+}
+
+impl ChannelBalances {
+    pub fn of(&self, role: PaymentChannelRole) -> MicroMinotari {
+        match role {
+            PaymentChannelRole::Opener => self.opener,
+            PaymentChannelRole::Acceptor => self.acceptor,
+        }
+    }
+
+    fn with(role: PaymentChannelRole, own: MicroMinotari, counterparty: MicroMinotari) -> Self {
+        match role {
+            PaymentChannelRole::Opener => Self {
+                opener: own,
+                acceptor: counterparty,
+            },
+            PaymentChannelRole::Acceptor => Self {
+                opener: counterparty,
+                acceptor: own,
+            },
+        }
+    }
+}
+
+/// The keys a party contributes for the lifetime of the channel
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelKeys {
+    /// The party's share of the key the funding script requires
+    pub script_public_key: PublicKey,
+}
+
+/// The keys a party contributes to one state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateKeys {
+    /// The key of the party's outputs of the state and of the penalty path of the counterparty's version
+    pub payout_public_key: PublicKey,
+    /// The hash of the secret that revokes the party's version
+    pub revocation_hash: [u8; 32],
+    /// The nonce of the party's share of the script signature of its version
+    pub script_nonce: PublicKey,
+}
+
+/// The close transaction of a version of a state, built by the party that does not publish it. It lacks the
+/// publisher's share of the script signature and script offset of the funding input.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateSignatures {
+    /// The publisher's output, absent if its balance is zero
+    pub publisher_output: Option<TransactionOutput>,
+    /// The mask of the publisher's output
+    pub publisher_mask: PrivateKey,
+    /// The counterparty's output, absent if its balance is zero
+    pub signer_output: Option<TransactionOutput>,
+    pub kernel: TransactionKernel,
+    /// The counterparty's share of the script signature of the funding input
+    pub script_signature: ComAndPubSignature,
+    /// The counterparty's share of the script offset
+    pub script_offset: PrivateKey,
+}
+
+/// Sent by the opener to propose the channel
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenChannel {
+    pub parameters: ChannelParameters,
+    /// The deadline of the first state
+    pub deadline: u64,
+    pub keys: ChannelKeys,
+    /// The mask of the funding output
+    pub funding_mask: PrivateKey,
+    pub state: StateKeys,
+}
+
+/// The acceptor's answer to [`OpenChannel`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AcceptChannel {
+    pub keys: ChannelKeys,
+    pub state: StateKeys,
+    pub signatures: StateSignatures,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProposeUpdate {
+    pub sequence: u64,
+    pub balances: ChannelBalances,
+    pub deadline: u64,
+    pub state: StateKeys,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AcceptUpdate {
+    pub sequence: u64,
+    pub state: StateKeys,
+    pub signatures: StateSignatures,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfirmUpdate {
+    pub sequence: u64,
+    pub signatures: StateSignatures,
+    /// The revocation secret of the sender's previous state, absent for the first state
+    pub revocation: Option<[u8; 32]>,
+    /// The funding output, sent by the opener with the first state
+    pub funding_output: Option<TransactionOutput>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevokeUpdate {
+    pub revoked_sequence: u64,
+    pub revocation: [u8; 32],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RejectUpdate {
+    pub sequence: u64,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProposeClose {
+    pub sequence: u64,
+    /// The nonce of the proposer's share of the script signature of the cooperative close
+    pub nonce: PublicKey,
+}
+
+/// The cooperative close transaction, built by the party that accepts the close for the party that proposed it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AcceptClose {
+    pub sequence: u64,
+    pub signatures: StateSignatures,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChannelMessage {
+    Open(OpenChannel),
+    Accept(AcceptChannel),
+    ProposeUpdate(ProposeUpdate),
+    AcceptUpdate(AcceptUpdate),
+    ConfirmUpdate(ConfirmUpdate),
+    RevokeUpdate(RevokeUpdate),
+    RejectUpdate(RejectUpdate),
+    ProposeClose(ProposeClose),
+    AcceptClose(AcceptClose),
+}
+
+impl Display for ChannelMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ChannelMessage::Open(_) => write!(f, "OpenChannel"),
+            ChannelMessage::Accept(_) => write!(f, "AcceptChannel"),
+            ChannelMessage::ProposeUpdate(m) => write!(f, "ProposeUpdate({})", m.sequence),
+            ChannelMessage::AcceptUpdate(m) => write!(f, "AcceptUpdate({})", m.sequence),
+            ChannelMessage::ConfirmUpdate(m) => write!(f, "ConfirmUpdate({})", m.sequence),
+            ChannelMessage::RevokeUpdate(m) => write!(f, "RevokeUpdate({})", m.revoked_sequence),
+            ChannelMessage::RejectUpdate(m) => write!(f, "RejectUpdate({})", m.sequence),
+            ChannelMessage::ProposeClose(m) => write!(f, "ProposeClose({})", m.sequence),
+            ChannelMessage::AcceptClose(m) => write!(f, "AcceptClose({})", m.sequence),
+        }
+    }
+}
+
+/// A transaction that spends the funding output, identified by the signature of its kernel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelClose {
+    /// This party's version of a state
+    Own(u64),
+    /// A version of the counterparty that it has not revoked
+    Counterparty(u64),
+    /// A version of the counterparty that it revoked
+    Revoked(u64),
+    Cooperative,
+}
+
+/// An output of a mined close transaction that this party can spend into its wallet
+#[derive(Debug, Clone)]
+pub struct ChannelClaim {
+    pub output: TransactionOutput,
+    pub value: MicroMinotari,
+    pub spending_key: PrivateKey,
+    pub script_private_key: PrivateKey,
+    pub input_data: ExecutionStack,
+    /// The height from which the output script can be satisfied
+    pub script_lock_height: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CloseKind {
+    Unilateral,
+    Cooperative,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CloseOutput {
+    Publisher,
+    Signer,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct ChannelSecrets {
+    /// Derives this party's keys, nonces and revocation secrets
+    seed: [u8; 32],
+    /// The nonce of this party's share of the cooperative close script signature, while a close it proposed is being
+    /// negotiated
+    close_nonce: Option<PrivateKey>,
+}
+
+impl ChannelSecrets {
+    fn new(seed: &PrivateKey) -> Self {
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(seed.as_bytes());
+        Self {
+            seed: bytes,
+            close_nonce: None,
+        }
+    }
+
+    fn channel_keys(&self) -> ChannelKeys {
+        ChannelKeys {
+            script_public_key: PublicKey::from_secret_key(&self.script_key()),
+        }
+    }
+
+    fn state_keys(&self, sequence: u64) -> StateKeys {
+        StateKeys {
+            payout_public_key: PublicKey::from_secret_key(&self.payout_key(sequence)),
+            revocation_hash: revocation_hash(&self.revocation_secret(sequence)),
+            script_nonce: PublicKey::from_secret_key(&self.script_nonce(sequence)),
+        }
+    }
+
+    fn script_key(&self) -> PrivateKey {
+        self.key("script", 0)
+    }
+
+    /// The mask of the funding output, when this party is the opener
+    fn funding_mask(&self) -> PrivateKey {
+        self.key("funding_mask", 0)
+    }
+
+    fn payout_key(&self, sequence: u64) -> PrivateKey {
+        self.key("payout", sequence)
+    }
+
+    fn revocation_secret(&self, sequence: u64) -> [u8; 32] {
+        self.derive("revocation", sequence)
+    }
+
+    fn script_nonce(&self, sequence: u64) -> PrivateKey {
+        self.key("script_nonce", sequence)
+    }
+
+    /// The mask of an output of a close transaction this party builds for the counterparty
+    fn output_mask(&self, kind: CloseKind, output: CloseOutput, sequence: u64) -> PrivateKey {
+        let label = match (kind, output) {
+            (CloseKind::Unilateral, CloseOutput::Publisher) => "publisher_mask",
+            (CloseKind::Unilateral, CloseOutput::Signer) => "signer_mask",
+            (CloseKind::Cooperative, CloseOutput::Publisher) => "close_publisher_mask",
+            (CloseKind::Cooperative, CloseOutput::Signer) => "close_signer_mask",
+        };
+        self.key(label, sequence)
+    }
+
+    /// The sender offset of an output of a close transaction this party builds for the counterparty
+    fn sender_offset(&self, kind: CloseKind, output: CloseOutput, sequence: u64) -> PrivateKey {
+        let label = match (kind, output) {
+            (CloseKind::Unilateral, CloseOutput::Publisher) => "publisher_offset",
+            (CloseKind::Unilateral, CloseOutput::Signer) => "signer_offset",
+            (CloseKind::Cooperative, CloseOutput::Publisher) => "close_publisher_offset",
+            (CloseKind::Cooperative, CloseOutput::Signer) => "close_signer_offset",
+        };
+        self.key(label, sequence)
+    }
+
+    fn key(&self, label: &'static str, sequence: u64) -> PrivateKey {
+        PrivateKey::from_bytes(&self.derive(label, sequence))
+            .expect("'DomainSeparatedHash<Blake2b<U32>>' has correct size")
+    }
+
+    fn derive(&self, label: &'static str, sequence: u64) -> [u8; 32] {
+        let hash = DomainSeparatedHasher::<Blake2b<U32>, PaymentChannelHashDomain>::new_with_label(label)
+            .chain(self.seed)
+            .chain(sequence.to_le_bytes())
+            .finalize();
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(hash.as_ref());
+        bytes
+    }
+}
+
+impl fmt::Debug for ChannelSecrets {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "ChannelSecrets {{ .. }}")
+    }
+}
+
+/// A state signed by both parties
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SignedState {
+    sequence: u64,
+    balances: ChannelBalances,
+    deadline: u64,
+    /// The counterparty's keys for the state
+    counterparty: StateKeys,
+    /// The close transaction of this party's version, built by the counterparty
+    close: StateSignatures,
+    /// The close transaction of the counterparty's version, built by this party
+    issued: StateSignatures,
+}
+
+/// A version of the counterparty that this party built and that was never revoked nor superseded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IssuedState {
+    sequence: u64,
+    balances: ChannelBalances,
+    issued: StateSignatures,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum PendingUpdate {
+    /// This party proposed the update and waits for the counterparty's close transaction
+    Proposed {
+        sequence: u64,
+        balances: ChannelBalances,
+        deadline: u64,
+    },
+    /// This party built the close transaction of the counterparty's proposal and waits for its own
+    Accepted {
+        sequence: u64,
+        balances: ChannelBalances,
+        deadline: u64,
+        counterparty: StateKeys,
+        issued: StateSignatures,
+    },
+    /// The update is complete on this side, waiting for the counterparty to revoke its previous state
+    Confirmed { previous: SignedState },
+}
+
+/// A version of the counterparty that it revoked by handing over its revocation secret
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RevokedState {
+    sequence: u64,
+    balances: ChannelBalances,
+    secret: [u8; 32],
+    issued: StateSignatures,
+}
+
+/// The cooperative close transaction, once the accepting party built it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CooperativeClose {
+    /// The party that proposed the close and publishes the transaction
+    publisher: PaymentChannelRole,
+    sequence: u64,
+    signatures: StateSignatures,
+}
+
+/// The public keys of both parties for one state, together with its balances
+struct StateTerms {
+    sequence: u64,
+    balances: ChannelBalances,
+    deadline: u64,
+    opener: StateKeys,
+    acceptor: StateKeys,
+}
+
+impl StateTerms {
+    fn keys(&self, role: PaymentChannelRole) -> &StateKeys {
+        match role {
+            PaymentChannelRole::Opener => &self.opener,
+            PaymentChannelRole::Acceptor => &self.acceptor,
+        }
+    }
+}
+
+/// Everything both parties agree on about a close transaction before it is built
+struct CloseTerms {
+    kind: CloseKind,
+    sequence: u64,
+    publisher_balance: MicroMinotari,
+    signer_balance: MicroMinotari,
+    publisher_script: TariScript,
+    signer_script: TariScript,
+    input_data: ExecutionStack,
+    /// The nonce of the publisher's share of the script signature
+    publisher_nonce: PublicKey,
+}
+
+impl CloseTerms {
+    fn output(&self, output: CloseOutput) -> (MicroMinotari, &TariScript) {
+        match output {
+            CloseOutput::Publisher => (self.publisher_balance, &self.publisher_script),
+            CloseOutput::Signer => (self.signer_balance, &self.signer_script),
+        }
+    }
+}
+
+/// One side of a payment channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentChannel {
+    /// Chosen by the opener and shared by both parties
+    pub id: PaymentChannelId,
+    pub role: PaymentChannelRole,
+    pub state: PaymentChannelState,
+    pub counterparty: TariAddress,
+    pub parameters: ChannelParameters,
+    secrets: ChannelSecrets,
+    funding_mask: PrivateKey,
+    counterparty_keys: Option<ChannelKeys>,
+    funding_output: Option<TransactionOutput>,
+    current: Option<SignedState>,
+    pending: Option<PendingUpdate>,
+    /// The highest sequence this party proposed or signed. A sequence is never used twice.
+    last_sequence: Option<u64>,
+    revocations: Vec<RevokedState>,
+    /// Versions of the counterparty for updates that were abandoned after this party built them
+    abandoned: Vec<IssuedState>,
+    close: Option<CooperativeClose>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl PaymentChannel {
+    /// Creates the opener's side of a new channel and the message that proposes it. The seed derives all of this
+    /// party's keys for the channel.
+    pub fn open(
+        counterparty: TariAddress,
+        parameters: ChannelParameters,
+        tip_height: u64,
+        seed: &PrivateKey,
+    ) -> Result<(Self, OpenChannel), PaymentChannelError> {
+        parameters.validate()?;
+        let deadline = parameters.deadline(tip_height);
+        let secrets = ChannelSecrets::new(seed);
+        let funding_mask = secrets.funding_mask();
+        let mut channel = Self::new(
+            OsRng.next_u64(),
+            PaymentChannelRole::Opener,
+            counterparty,
+            parameters,
+            secrets,
+            funding_mask,
+        );
+        channel.pending = Some(PendingUpdate::Proposed {
+            sequence: 0,
+            balances: channel.initial_balances(),
+            deadline,
+        });
+        channel.last_sequence = Some(0);
+        let message = OpenChannel {
+            parameters: channel.parameters.clone(),
+            deadline,
+            keys: channel.secrets.channel_keys(),
+            funding_mask: channel.funding_mask.clone(),
+            state: channel.secrets.state_keys(0),
+        };
+        Ok((channel, message))
+    }
+
+    /// Creates the acceptor's side of a channel proposed by the counterparty and the message that accepts it
+    pub fn accept(
+        id: PaymentChannelId,
+        counterparty: TariAddress,
+        open: OpenChannel,
+        tip_height: u64,
+        seed: &PrivateKey,
+        factories: &CryptoFactories,
+    ) -> Result<(Self, AcceptChannel), PaymentChannelError> {
+        open.parameters.validate()?;
+        open.parameters.check_deadline(open.deadline, tip_height)?;
+        let mut channel = Self::new(
+            id,
+            PaymentChannelRole::Acceptor,
+            counterparty,
+            open.parameters,
+            ChannelSecrets::new(seed),
+            open.funding_mask,
+        );
+        channel.set_counterparty_keys(open.keys)?;
+        let balances = channel.initial_balances();
+        let terms = channel.terms(0, balances, open.deadline, open.state.clone());
+        let signatures =
+            channel.sign_close(&channel.unilateral_close(&terms, PaymentChannelRole::Opener), factories)?;
+        channel.pending = Some(PendingUpdate::Accepted {
+            sequence: 0,
+            balances,
+            deadline: open.deadline,
+            counterparty: open.state,
+            issued: signatures.clone(),
+        });
+        channel.last_sequence = Some(0);
+        let message = AcceptChannel {
+            keys: channel.secrets.channel_keys(),
+            state: channel.secrets.state_keys(0),
+            signatures,
+        };
+        Ok((channel, message))
+    }
+
+    /// Completes the opener's first state and returns the close transaction of the acceptor's version. The funding
+    /// output is added to the message once the funding transaction is built.
+    pub fn accept_channel(
+        &mut self,
+        accept: AcceptChannel,
+        factories: &CryptoFactories,
+    ) -> Result<ConfirmUpdate, PaymentChannelError> {
+        self.expect_role(PaymentChannelRole::Opener)?;
+        self.expect_state("accept the channel", &[PaymentChannelState::Opening])?;
+        let (balances, deadline) = match &self.pending {
+            Some(PendingUpdate::Proposed {
+                sequence: 0,
+                balances,
+                deadline,
+            }) => (*balances, *deadline),
+            _ => return Err(PaymentChannelError::UnexpectedMessage("channel acceptance")),
+        };
+        let mut channel = self.clone();
+        channel.set_counterparty_keys(accept.keys)?;
+        let terms = channel.terms(0, balances, deadline, accept.state);
+        let state = channel.complete_state(&terms, accept.signatures, factories)?;
+        let signatures = state.issued.clone();
+        channel.current = Some(state);
+        channel.pending = None;
+        channel.set_state(PaymentChannelState::Funding);
+        *self = channel;
+        Ok(ConfirmUpdate {
+            sequence: 0,
+            signatures,
+            revocation: None,
+            funding_output: None,
+        })
+    }
+
+    /// Records the funding output of the funding transaction the opener built
+    pub fn set_funding_output(
+        &mut self,
+        output: TransactionOutput,
+        factories: &CryptoFactories,
+    ) -> Result<(), PaymentChannelError> {
+        self.expect_state("set the funding output", &[PaymentChannelState::Funding])?;
+        self.check_funding_output(&output, factories)?;
+        self.funding_output = Some(output);
+        self.touch();
+        Ok(())
+    }
+
+    /// Proposes new balances with a new deadline. The counterparty only accepts updates that do not lower its own
+    /// balance.
+    pub fn propose_update(
+        &mut self,
+        balances: ChannelBalances,
+        tip_height: u64,
+    ) -> Result<ProposeUpdate, PaymentChannelError> {
+        self.expect_updatable("update the channel")?;
+        if self.pending.is_some() {
+            return Err(PaymentChannelError::UpdateInProgress("update the channel"));
+        }
+        self.check_balances(&balances)?;
+        let sequence = self.next_sequence();
+        let deadline = self.parameters.deadline(tip_height);
+        self.pending = Some(PendingUpdate::Proposed {
+            sequence,
+            balances,
+            deadline,
+        });
+        self.last_sequence = Some(sequence);
+        self.touch();
+        Ok(ProposeUpdate {
+            sequence,
+            balances,
+            deadline,
+            state: self.secrets.state_keys(sequence),
+        })
+    }
+
+    /// Proposes an update that moves `amount` from this party's balance to the counterparty's
+    pub fn propose_payment(
+        &mut self,
+        amount: MicroMinotari,
+        tip_height: u64,
+    ) -> Result<ProposeUpdate, PaymentChannelError> {
+        let balances = self.current()?.balances;
+        let own = balances
+            .of(self.role)
+            .checked_sub(amount)
+            .ok_or(PaymentChannelError::InsufficientBalance(balances.of(self.role)))?;
+        let counterparty = balances.of(self.role.counterparty()) + amount;
+        self.propose_update(ChannelBalances::with(self.role, own, counterparty), tip_height)
+    }
+
+    /// Proposes an update to the balances of the latest state, which only moves its deadline
+    pub fn propose_refresh(&mut self, tip_height: u64) -> Result<ProposeUpdate, PaymentChannelError> {
+        let balances = self.current()?.balances;
+        self.propose_update(balances, tip_height)
+    }
+
+    /// Builds the close transaction of the counterparty's version of a proposed state
+    pub fn accept_update(
+        &mut self,
+        proposal: ProposeUpdate,
+        tip_height: u64,
+        factories: &CryptoFactories,
+    ) -> Result<AcceptUpdate, PaymentChannelError> {
+        self.expect_updatable("accept an update")?;
+        // A proposal the counterparty abandoned is replaced by its next one
+        if matches!(
+            self.pending,
+            Some(PendingUpdate::Proposed { .. }) | Some(PendingUpdate::Confirmed { .. })
+        ) {
+            return Err(PaymentChannelError::UpdateInProgress("accept an update"));
+        }
+        if self.last_sequence.map_or(false, |last| proposal.sequence <= last) {
+            return Err(PaymentChannelError::InvalidCounterpartyData("sequence"));
+        }
+        self.check_balances(&proposal.balances)?;
+        if proposal.balances.of(self.role) < self.current()?.balances.of(self.role) {
+            return Err(PaymentChannelError::InvalidCounterpartyData("balances"));
+        }
+        self.parameters.check_deadline(proposal.deadline, tip_height)?;
+        let terms = self.terms(
+            proposal.sequence,
+            proposal.balances,
+            proposal.deadline,
+            proposal.state.clone(),
+        );
+        let signatures = self.sign_close(&self.unilateral_close(&terms, self.role.counterparty()), factories)?;
+        self.drop_pending();
+        self.pending = Some(PendingUpdate::Accepted {
+            sequence: proposal.sequence,
+            balances: proposal.balances,
+            deadline: proposal.deadline,
+            counterparty: proposal.state,
+            issued: signatures.clone(),
+        });
+        self.last_sequence = Some(proposal.sequence);
+        self.touch();
+        Ok(AcceptUpdate {
+            sequence: proposal.sequence,
+            state: self.secrets.state_keys(proposal.sequence),
+            signatures,
+        })
+    }
+
+    /// Completes this party's version of the state it proposed, builds the counterparty's version and revokes this
+    /// party's previous state
+    pub fn confirm_update(
+        &mut self,
+        accept: AcceptUpdate,
+        factories: &CryptoFactories,
+    ) -> Result<ConfirmUpdate, PaymentChannelError> {
+        self.expect_updatable("confirm an update")?;
+        let (balances, deadline) = match &self.pending {
+            Some(PendingUpdate::Proposed {
+                sequence,
+                balances,
+                deadline,
+            }) if *sequence == accept.sequence => (*balances, *deadline),
+            _ => return Err(PaymentChannelError::UnexpectedMessage("update acceptance")),
+        };
+        let terms = self.terms(accept.sequence, balances, deadline, accept.state);
+        let state = self.complete_state(&terms, accept.signatures, factories)?;
+        let signatures = state.issued.clone();
+        let previous = self.current()?.clone();
+        let revocation = self.secrets.revocation_secret(previous.sequence);
+        self.pending = Some(PendingUpdate::Confirmed { previous });
+        self.current = Some(state);
+        self.touch();
+        Ok(ConfirmUpdate {
+            sequence: accept.sequence,
+            signatures,
+            revocation: Some(revocation),
+            funding_output: None,
+        })
+    }
+
+    /// Completes this party's version of a state it accepted and, except for the first state, revokes its previous
+    /// state
+    pub fn accept_confirmation(
+        &mut self,
+        confirm: ConfirmUpdate,
+        factories: &CryptoFactories,
+    ) -> Result<Option<RevokeUpdate>, PaymentChannelError> {
+        self.expect_state("accept the counterparty's signatures", &[
+            PaymentChannelState::Opening,
+            PaymentChannelState::Funding,
+            PaymentChannelState::Open,
+        ])?;
+        let (balances, deadline, counterparty, issued) = match &self.pending {
+            Some(PendingUpdate::Accepted {
+                sequence,
+                balances,
+                deadline,
+                counterparty,
+                issued,
+            }) if *sequence == confirm.sequence => (*balances, *deadline, counterparty.clone(), issued.clone()),
+            _ => return Err(PaymentChannelError::UnexpectedMessage("update confirmation")),
+        };
+        // The first state comes with the funding output
+        let funding_output = match (&self.current, confirm.funding_output) {
+            (None, Some(output)) => {
+                self.check_funding_output(&output, factories)?;
+                Some(output)
+            },
+            (None, None) => return Err(PaymentChannelError::InvalidCounterpartyData("funding output")),
+            (Some(_), _) => None,
+        };
+        let terms = self.terms(confirm.sequence, balances, deadline, counterparty);
+        let own_close = self.unilateral_close(&terms, self.role);
+        self.check_close(&own_close, &confirm.signatures, factories)?;
+        let state = SignedState {
+            sequence: terms.sequence,
+            balances,
+            deadline,
+            counterparty: terms.keys(self.role.counterparty()).clone(),
+            close: confirm.signatures,
+            issued,
+        };
+        let revoked = match (&self.current, confirm.revocation) {
+            (None, None) => None,
+            (Some(previous), Some(secret)) if revocation_hash(&secret) == previous.counterparty.revocation_hash => {
+                Some(RevokedState {
+                    sequence: previous.sequence,
+                    balances: previous.balances,
+                    secret,
+                    issued: previous.issued.clone(),
+                })
+            },
+            _ => return Err(PaymentChannelError::InvalidCounterpartyData("revocation secret")),
+        };
+        let revoke = revoked.as_ref().map(|revoked| RevokeUpdate {
+            revoked_sequence: revoked.sequence,
+            revocation: self.secrets.revocation_secret(revoked.sequence),
+        });
+        self.revocations.extend(revoked);
+        if funding_output.is_some() {
+            self.funding_output = funding_output;
+        }
+        self.current = Some(state);
+        self.pending = None;
+        if self.state == PaymentChannelState::Opening {
+            self.set_state(PaymentChannelState::Funding);
+        } else {
+            self.touch();
+        }
+        Ok(revoke)
+    }
+
+    /// Records the counterparty's revocation of its previous state, which completes the update
+    pub fn accept_revocation(&mut self, revoke: RevokeUpdate) -> Result<(), PaymentChannelError> {
+        let previous = match &self.pending {
+            Some(PendingUpdate::Confirmed { previous }) if previous.sequence == revoke.revoked_sequence => previous,
+            _ => return Err(PaymentChannelError::UnexpectedMessage("revocation")),
+        };
+        if revocation_hash(&revoke.revocation) != previous.counterparty.revocation_hash {
+            return Err(PaymentChannelError::InvalidCounterpartyData("revocation secret"));
+        }
+        let revoked = RevokedState {
+            sequence: previous.sequence,
+            balances: previous.balances,
+            secret: revoke.revocation,
+            issued: previous.issued.clone(),
+        };
+        self.revocations.push(revoked);
+        self.pending = None;
+        self.touch();
+        Ok(())
+    }
+
+    /// Drops an update that has not been signed by both parties. Dropping the opening aborts the channel.
+    pub fn abandon_update(&mut self, sequence: u64) -> Result<(), PaymentChannelError> {
+        match &self.pending {
+            Some(PendingUpdate::Proposed { sequence: pending, .. }) |
+            Some(PendingUpdate::Accepted { sequence: pending, .. })
+                if *pending == sequence => {},
+            _ => return Err(PaymentChannelError::UnexpectedMessage("update rejection")),
+        }
+        self.drop_pending();
+        if self.state == PaymentChannelState::Opening {
+            self.set_state(PaymentChannelState::Aborted);
+        } else {
+            self.touch();
+        }
+        Ok(())
+    }
+
+    /// Whether an update is in progress, including one waiting for the revocation of the previous state
+    pub fn is_updating(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// The sequence of the update in progress that has not been signed by both parties
+    pub fn unsigned_update(&self) -> Option<u64> {
+        match &self.pending {
+            Some(PendingUpdate::Proposed { sequence, .. }) | Some(PendingUpdate::Accepted { sequence, .. }) => {
+                Some(*sequence)
+            },
+            _ => None,
+        }
+    }
+
+    /// Records that the funding transaction was mined
+    pub fn funding_confirmed(&mut self) -> Result<(), PaymentChannelError> {
+        self.expect_state("confirm the funding", &[PaymentChannelState::Funding])?;
+        self.set_state(PaymentChannelState::Open);
+        Ok(())
+    }
+
+    /// Proposes to close the channel cooperatively with the balances of the latest state
+    pub fn propose_close(&mut self) -> Result<ProposeClose, PaymentChannelError> {
+        self.expect_updatable("close the channel")?;
+        if self.pending.is_some() {
+            return Err(PaymentChannelError::UpdateInProgress("close the channel"));
+        }
+        let sequence = self.current()?.sequence;
+        let nonce = PrivateKey::random(&mut OsRng);
+        let public_nonce = PublicKey::from_secret_key(&nonce);
+        self.secrets.close_nonce = Some(nonce);
+        self.set_state(PaymentChannelState::Closing);
+        Ok(ProposeClose {
+            sequence,
+            nonce: public_nonce,
+        })
+    }
+
+    /// Builds the cooperative close transaction proposed by the counterparty. If both parties proposed a close at the
+    /// same time, the opener's proposal wins.
+    pub fn accept_close(
+        &mut self,
+        proposal: ProposeClose,
+        factories: &CryptoFactories,
+    ) -> Result<AcceptClose, PaymentChannelError> {
+        if self.state == PaymentChannelState::Closing && self.role == PaymentChannelRole::Opener {
+            return Err(PaymentChannelError::UnexpectedMessage("close proposal"));
+        }
+        self.expect_state("accept a close", &[
+            PaymentChannelState::Funding,
+            PaymentChannelState::Open,
+            PaymentChannelState::Closing,
+        ])?;
+        if matches!(self.pending, Some(PendingUpdate::Confirmed { .. })) {
+            return Err(PaymentChannelError::UpdateInProgress("accept a close"));
+        }
+        let sequence = self.current()?.sequence;
+        if proposal.sequence != sequence {
+            return Err(PaymentChannelError::InvalidCounterpartyData("close sequence"));
+        }
+        let close = self.cooperative_close(self.role.counterparty(), proposal.nonce)?;
+        let signatures = self.sign_close(&close, factories)?;
+        self.close = Some(CooperativeClose {
+            publisher: self.role.counterparty(),
+            sequence,
+            signatures: signatures.clone(),
+        });
+        self.secrets.close_nonce = None;
+        self.drop_pending();
+        self.set_state(PaymentChannelState::Closing);
+        Ok(AcceptClose { sequence, signatures })
+    }
+
+    /// Completes the cooperative close transaction this party proposed, to be published by this party
+    pub fn complete_close(
+        &mut self,
+        accept: AcceptClose,
+        factories: &CryptoFactories,
+    ) -> Result<Transaction, PaymentChannelError> {
+        self.expect_state("complete the close", &[PaymentChannelState::Closing])?;
+        let nonce = self
+            .secrets
+            .close_nonce
+            .clone()
+            .ok_or(PaymentChannelError::UnexpectedMessage("close acceptance"))?;
+        let sequence = self.current()?.sequence;
+        if accept.sequence != sequence {
+            return Err(PaymentChannelError::InvalidCounterpartyData("close sequence"));
+        }
+        let close = self.cooperative_close(self.role, PublicKey::from_secret_key(&nonce))?;
+        self.check_close(&close, &accept.signatures, factories)?;
+        let transaction = self.complete_close_transaction(&close, &accept.signatures, &nonce, factories)?;
+        // The nonce signs this close only
+        self.secrets.close_nonce = None;
+        self.close = Some(CooperativeClose {
+            publisher: self.role,
+            sequence,
+            signatures: accept.signatures,
+        });
+        self.touch();
+        Ok(transaction)
+    }
+
+    /// The close transaction of this party's latest state, to publish the state without the counterparty. It must be
+    /// mined by the deadline of the state. Calling it again returns the same transaction.
+    pub fn force_close(&mut self, factories: &CryptoFactories) -> Result<Transaction, PaymentChannelError> {
+        self.expect_state("force close", &[
+            PaymentChannelState::Funding,
+            PaymentChannelState::Open,
+            PaymentChannelState::Closing,
+            PaymentChannelState::ForceClosing,
+        ])?;
+        let current = self.current()?;
+        let terms = self.terms(
+            current.sequence,
+            current.balances,
+            current.deadline,
+            current.counterparty.clone(),
+        );
+        let close = self.unilateral_close(&terms, self.role);
+        let nonce = self.secrets.script_nonce(current.sequence);
+        let transaction = self.complete_close_transaction(&close, &current.close, &nonce, factories)?;
+        self.secrets.close_nonce = None;
+        self.set_state(PaymentChannelState::ForceClosing);
+        Ok(transaction)
+    }
+
+    /// The kernel signatures of the close transactions that can spend the funding output, to find the one that was
+    /// mined
+    pub fn close_kernels(&self) -> Vec<(ChannelClose, Signature)> {
+        let mut kernels = Vec::new();
+        if let Some(current) = &self.current {
+            kernels.push((
+                ChannelClose::Own(current.sequence),
+                current.close.kernel.excess_sig.clone(),
+            ));
+            kernels.push((
+                ChannelClose::Counterparty(current.sequence),
+                current.issued.kernel.excess_sig.clone(),
+            ));
+        }
+        match &self.pending {
+            Some(PendingUpdate::Accepted { sequence, issued, .. }) => {
+                kernels.push((ChannelClose::Counterparty(*sequence), issued.kernel.excess_sig.clone()));
+            },
+            Some(PendingUpdate::Confirmed { previous }) => {
+                kernels.push((
+                    ChannelClose::Counterparty(previous.sequence),
+                    previous.issued.kernel.excess_sig.clone(),
+                ));
+            },
+            _ => {},
+        }
+        kernels.extend(self.abandoned.iter().map(|abandoned| {
+            (
+                ChannelClose::Counterparty(abandoned.sequence),
+                abandoned.issued.kernel.excess_sig.clone(),
+            )
+        }));
+        kernels.extend(self.revocations.iter().map(|revoked| {
+            (
+                ChannelClose::Revoked(revoked.sequence),
+                revoked.issued.kernel.excess_sig.clone(),
+            )
+        }));
+        if let Some(close) = &self.close {
+            kernels.push((ChannelClose::Cooperative, close.signatures.kernel.excess_sig.clone()));
+        }
+        kernels
+    }
+
+    /// Records that a close transaction was mined and returns the outputs of it that this party can claim right away.
+    /// This party's output of its own version is claimed with [`Self::settle`] once the dispute delay has passed.
+    pub fn close_mined(&mut self, close: ChannelClose) -> Result<Vec<ChannelClaim>, PaymentChannelError> {
+        self.expect_state("record a close", &[
+            PaymentChannelState::Funding,
+            PaymentChannelState::Open,
+            PaymentChannelState::Closing,
+            PaymentChannelState::ForceClosing,
+        ])?;
+        let (claims, state) = match close {
+            ChannelClose::Own(sequence) => {
+                if self.current()?.sequence != sequence {
+                    return Err(PaymentChannelError::UnknownSequence(sequence));
+                }
+                (Vec::new(), PaymentChannelState::ForceClosing)
+            },
+            ChannelClose::Counterparty(sequence) => {
+                let (balances, issued) = self.issued_state(sequence)?;
+                let claim = self.claim(
+                    issued.signer_output.as_ref(),
+                    balances.of(self.role),
+                    self.secrets
+                        .output_mask(CloseKind::Unilateral, CloseOutput::Signer, sequence),
+                    sequence,
+                    ExecutionStack::default(),
+                    0,
+                );
+                (claim.into_iter().collect(), PaymentChannelState::ClosedByCounterparty)
+            },
+            ChannelClose::Revoked(sequence) => {
+                let revoked = self.revoked_state(sequence)?;
+                let penalty = self.claim(
+                    revoked.issued.publisher_output.as_ref(),
+                    revoked.balances.of(self.role.counterparty()),
+                    self.secrets
+                        .output_mask(CloseKind::Unilateral, CloseOutput::Publisher, sequence),
+                    sequence,
+                    ExecutionStack::new(vec![StackItem::Hash(revoked.secret)]),
+                    0,
+                );
+                let own = self.claim(
+                    revoked.issued.signer_output.as_ref(),
+                    revoked.balances.of(self.role),
+                    self.secrets
+                        .output_mask(CloseKind::Unilateral, CloseOutput::Signer, sequence),
+                    sequence,
+                    ExecutionStack::default(),
+                    0,
+                );
+                (penalty.into_iter().chain(own).collect(), PaymentChannelState::Punished)
+            },
+            ChannelClose::Cooperative => {
+                let close = self.close.as_ref().ok_or(PaymentChannelError::InvalidState {
+                    action: "claim a cooperative close",
+                    state: self.state,
+                })?;
+                let (output, mask) = if close.publisher == self.role {
+                    (
+                        close.signatures.publisher_output.as_ref(),
+                        close.signatures.publisher_mask.clone(),
+                    )
+                } else {
+                    (
+                        close.signatures.signer_output.as_ref(),
+                        self.secrets
+                            .output_mask(CloseKind::Cooperative, CloseOutput::Signer, close.sequence),
+                    )
+                };
+                let claim = self.claim(
+                    output,
+                    self.current()?.balances.of(self.role),
+                    mask,
+                    close.sequence,
+                    ExecutionStack::default(),
+                    0,
+                );
+                (claim.into_iter().collect(), PaymentChannelState::Closed)
+            },
+        };
+        self.secrets.close_nonce = None;
+        self.set_state(state);
+        Ok(claims)
+    }
+
+    /// Claims this party's output of the state it published, which is possible from the settlement height. There is
+    /// nothing to claim if this party's balance was zero.
+    pub fn settle(&mut self) -> Result<Option<ChannelClaim>, PaymentChannelError> {
+        self.expect_state("settle", &[PaymentChannelState::ForceClosing])?;
+        let current = self.current()?;
+        let claim = self.claim(
+            current.close.publisher_output.as_ref(),
+            current.balances.of(self.role),
+            current.close.publisher_mask.clone(),
+            current.sequence,
+            ExecutionStack::new(vec![StackItem::Hash([0u8; 32])]),
+            self.parameters.settlement_height(current.deadline),
+        );
+        self.set_state(PaymentChannelState::Settled);
+        Ok(claim)
+    }
+
+    /// Gives up on a channel before it was funded
+    pub fn abort(&mut self) -> Result<(), PaymentChannelError> {
+        self.expect_state("abort", &[PaymentChannelState::Opening, PaymentChannelState::Funding])?;
+        self.set_state(PaymentChannelState::Aborted);
+        Ok(())
+    }
+
+    /// The sequence of the latest state signed by both parties
+    pub fn sequence(&self) -> Option<u64> {
+        self.current.as_ref().map(|current| current.sequence)
+    }
+
+    /// The balances of the latest state signed by both parties
+    pub fn balances(&self) -> Option<ChannelBalances> {
+        self.current.as_ref().map(|current| current.balances)
+    }
+
+    /// The last height at which the latest state signed by both parties can be published
+    pub fn deadline(&self) -> Option<u64> {
+        self.current.as_ref().map(|current| current.deadline)
+    }
+
+    /// The height from which the publisher of the latest state signed by both parties can claim its output
+    pub fn settlement_height(&self) -> Option<u64> {
+        self.deadline()
+            .map(|deadline| self.parameters.settlement_height(deadline))
+    }
+
+    /// The mask of the funding output, which the opener's wallet uses to build the funding transaction
+    pub fn funding_mask(&self) -> &PrivateKey {
+        &self.funding_mask
+    }
+
+    /// The funding output, once the opener built the funding transaction
+    pub fn funding_output(&self) -> Option<&TransactionOutput> {
+        self.funding_output.as_ref()
+    }
+
+    /// The script of the funding output. The input data is the last height at which the spending transaction can be
+    /// mined.
+    pub fn funding_script(&self) -> Result<TariScript, PaymentChannelError> {
+        let key = self.funding_script_key()?;
+        Ok(script!(CompareHeight LeZero IfThen PushPubKey(Box::new(key)) Else Return EndIf))
+    }
+
+    /// Whether this side of the channel has run its course
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.state,
+            PaymentChannelState::Closed |
+                PaymentChannelState::Settled |
+                PaymentChannelState::Punished |
+                PaymentChannelState::Aborted |
+                PaymentChannelState::ClosedByCounterparty
+        )
+    }
+
+    fn new(
+        id: PaymentChannelId,
+        role: PaymentChannelRole,
+        counterparty: TariAddress,
+        parameters: ChannelParameters,
+        secrets: ChannelSecrets,
+        funding_mask: PrivateKey,
+    ) -> Self {
+        let now = Utc::now().naive_utc();
+        Self {
+            id,
+            role,
+            state: PaymentChannelState::Opening,
+            counterparty,
+            parameters,
+            secrets,
+            funding_mask,
+            counterparty_keys: None,
+            funding_output: None,
+            current: None,
+            pending: None,
+            last_sequence: None,
+            revocations: Vec::new(),
+            abandoned: Vec::new(),
+            close: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn initial_balances(&self) -> ChannelBalances {
+        ChannelBalances {
+            opener: self.parameters.spendable(),
+            acceptor: MicroMinotari::zero(),
+        }
+    }
+
+    fn next_sequence(&self) -> u64 {
+        max(self.last_sequence, self.sequence()).map_or(0, |sequence| sequence + 1)
+    }
+
+    fn check_balances(&self, balances: &ChannelBalances) -> Result<(), PaymentChannelError> {
+        if balances.opener.checked_add(balances.acceptor) != Some(self.parameters.spendable()) {
+            return Err(PaymentChannelError::InvalidParameters(format!(
+                "The balances must add up to {}",
+                self.parameters.spendable()
+            )));
+        }
+        Ok(())
+    }
+
+    fn set_counterparty_keys(&mut self, keys: ChannelKeys) -> Result<(), PaymentChannelError> {
+        if keys.script_public_key == self.secrets.channel_keys().script_public_key {
+            return Err(PaymentChannelError::InvalidCounterpartyData("keys"));
+        }
+        self.counterparty_keys = Some(keys);
+        Ok(())
+    }
+
+    /// Drops the pending update. The counterparty can still publish its version of an update this party accepted,
+    /// so that version is kept.
+    fn drop_pending(&mut self) {
+        if let Some(PendingUpdate::Accepted {
+            sequence,
+            balances,
+            issued,
+            ..
+        }) = self.pending.take()
+        {
+            self.abandoned.push(IssuedState {
+                sequence,
+                balances,
+                issued,
+            });
+        }
+    }
+
+    fn terms(&self, sequence: u64, balances: ChannelBalances, deadline: u64, counterparty: StateKeys) -> StateTerms {
+        let own = self.secrets.state_keys(sequence);
+        let (opener, acceptor) = match self.role {
+            PaymentChannelRole::Opener => (own, counterparty),
+            PaymentChannelRole::Acceptor => (counterparty, own),
+        };
+        StateTerms {
+            sequence,
+            balances,
+            deadline,
+            opener,
+            acceptor,
+        }
+    }
+
+    /// Verifies the close transaction of this party's version of a state and builds the counterparty's version
+    fn complete_state(
+        &self,
+        terms: &StateTerms,
+        signatures: StateSignatures,
+        factories: &CryptoFactories,
+    ) -> Result<SignedState, PaymentChannelError> {
+        self.check_close(&self.unilateral_close(terms, self.role), &signatures, factories)?;
+        let issued = self.sign_close(&self.unilateral_close(terms, self.role.counterparty()), factories)?;
+        Ok(SignedState {
+            sequence: terms.sequence,
+            balances: terms.balances,
+            deadline: terms.deadline,
+            counterparty: terms.keys(self.role.counterparty()).clone(),
+            close: signatures,
+            issued,
+        })
+    }
+
+    /// The close transaction of the given party's version of a state
+    fn unilateral_close(&self, terms: &StateTerms, publisher: PaymentChannelRole) -> CloseTerms {
+        let publisher_keys = terms.keys(publisher);
+        let signer_keys = terms.keys(publisher.counterparty());
+        let settlement_height = self.parameters.settlement_height(terms.deadline);
+        CloseTerms {
+            kind: CloseKind::Unilateral,
+            sequence: terms.sequence,
+            publisher_balance: terms.balances.of(publisher),
+            signer_balance: terms.balances.of(publisher.counterparty()),
+            publisher_script: script!(
+                HashSha256 PushHash(Box::new(publisher_keys.revocation_hash)) Equal IfThen
+                    PushPubKey(Box::new(signer_keys.payout_public_key.clone()))
+                Else
+                    CheckHeightVerify(settlement_height) PushPubKey(Box::new(publisher_keys.payout_public_key.clone()))
+                EndIf
+            ),
+            signer_script: script!(PushPubKey(Box::new(signer_keys.payout_public_key.clone()))),
+            input_data: ExecutionStack::new(vec![StackItem::Number(height_item(terms.deadline))]),
+            publisher_nonce: publisher_keys.script_nonce.clone(),
+        }
+    }
+
+    /// The cooperative close of the latest state, published by the given party
+    fn cooperative_close(
+        &self,
+        publisher: PaymentChannelRole,
+        publisher_nonce: PublicKey,
+    ) -> Result<CloseTerms, PaymentChannelError> {
+        let current = self.current()?;
+        let terms = self.terms(
+            current.sequence,
+            current.balances,
+            current.deadline,
+            current.counterparty.clone(),
+        );
+        let payout_script = |party| script!(PushPubKey(Box::new(terms.keys(party).payout_public_key.clone())));
+        Ok(CloseTerms {
+            kind: CloseKind::Cooperative,
+            sequence: current.sequence,
+            publisher_balance: current.balances.of(publisher),
+            signer_balance: current.balances.of(publisher.counterparty()),
+            publisher_script: payout_script(publisher),
+            signer_script: payout_script(publisher.counterparty()),
+            input_data: ExecutionStack::new(vec![StackItem::Number(i64::MAX)]),
+            publisher_nonce,
+        })
+    }
+
+    /// Builds a close transaction for the counterparty to publish: both outputs, the kernel and this party's share of
+    /// the script signature and script offset of the funding input
+    fn sign_close(
+        &self,
+        close: &CloseTerms,
+        factories: &CryptoFactories,
+    ) -> Result<StateSignatures, PaymentChannelError> {
+        let build = |output| -> Result<Option<(TransactionOutput, PrivateKey, PrivateKey)>, PaymentChannelError> {
+            let (balance, script) = close.output(output);
+            if balance == MicroMinotari::zero() {
+                return Ok(None);
+            }
+            let mask = self.secrets.output_mask(close.kind, output, close.sequence);
+            let sender_offset = self.secrets.sender_offset(close.kind, output, close.sequence);
+            let output = build_output(factories, balance, &mask, script.clone(), &sender_offset)?;
+            Ok(Some((output, mask, sender_offset)))
+        };
+        let publisher = build(CloseOutput::Publisher)?;
+        let signer = build(CloseOutput::Signer)?;
+
+        let script_share = self.script_share()?;
+        let mut excess = &PrivateKey::default() - &self.funding_mask;
+        let mut script_offset = script_share.clone();
+        for (_, mask, sender_offset) in publisher.iter().chain(signer.iter()) {
+            excess = &excess + mask;
+            script_offset = &script_offset - sender_offset;
+        }
+        let kernel = sign_kernel(&excess, self.parameters.fee)?;
+
+        let r_a = PrivateKey::random(&mut OsRng);
+        let r_x = PrivateKey::random(&mut OsRng);
+        let r_y = PrivateKey::random(&mut OsRng);
+        let ephemeral_commitment = factories.commitment.commit(&r_x, &r_a);
+        let ephemeral_pubkey = &PublicKey::from_secret_key(&r_y) + &close.publisher_nonce;
+        let challenge = self.script_challenge(close, &ephemeral_commitment, &ephemeral_pubkey, factories)?;
+        let script_signature = ComAndPubSignature::sign(
+            &PrivateKey::from(self.parameters.capacity.as_u64()),
+            &self.funding_mask,
+            &script_share,
+            &r_a,
+            &r_x,
+            &r_y,
+            &challenge,
+            &*factories.commitment,
+        )?;
+
+        Ok(StateSignatures {
+            publisher_output: publisher.map(|(output, _, _)| output),
+            publisher_mask: self
+                .secrets
+                .output_mask(close.kind, CloseOutput::Publisher, close.sequence),
+            signer_output: signer.map(|(output, _, _)| output),
+            kernel,
+            script_signature,
+            script_offset,
+        })
+    }
+
+    /// Verifies a close transaction the counterparty built for this party to publish
+    fn check_close(
+        &self,
+        close: &CloseTerms,
+        signatures: &StateSignatures,
+        factories: &CryptoFactories,
+    ) -> Result<(), PaymentChannelError> {
+        let invalid = PaymentChannelError::InvalidCounterpartyData("close transaction");
+        let zero = MicroMinotari::zero();
+        if signatures.publisher_output.is_some() != (close.publisher_balance > zero) ||
+            signatures.signer_output.is_some() != (close.signer_balance > zero)
+        {
+            return Err(invalid);
+        }
+        if let Some(output) = &signatures.publisher_output {
+            let commitment = factories
+                .commitment
+                .commit_value(&signatures.publisher_mask, close.publisher_balance.as_u64());
+            if output.commitment != commitment {
+                return Err(invalid);
+            }
+            check_output(factories, output, &close.publisher_script)?;
+        }
+        if let Some(output) = &signatures.signer_output {
+            check_output(factories, output, &close.signer_script)?;
+        }
+
+        let kernel = &signatures.kernel;
+        if kernel.fee != self.parameters.fee ||
+            kernel.lock_height != 0 ||
+            kernel.features != KernelFeatures::empty() ||
+            kernel.burn_commitment.is_some() ||
+            kernel.verify_signature().is_err()
+        {
+            return Err(invalid);
+        }
+        let outputs = || {
+            signatures
+                .publisher_output
+                .iter()
+                .chain(signatures.signer_output.iter())
+        };
+        let fee = factories
+            .commitment
+            .commit_value(&PrivateKey::default(), self.parameters.fee.as_u64());
+        let total = outputs().fold(fee, |total, output| &total + &output.commitment);
+        let funding_commitment = self.funding_commitment(factories);
+        if total != &funding_commitment + &kernel.excess {
+            return Err(invalid);
+        }
+
+        let signer_share = self.public_script_share(self.role.counterparty())?;
+        let partial = &signatures.script_signature;
+        let ephemeral_pubkey = partial.ephemeral_pubkey() + &close.publisher_nonce;
+        let challenge = self.script_challenge(close, partial.ephemeral_commitment(), &ephemeral_pubkey, factories)?;
+        if !partial.verify_challenge(
+            &funding_commitment,
+            &signer_share,
+            &challenge,
+            &*factories.commitment,
+            &mut OsRng,
+        ) {
+            return Err(PaymentChannelError::InvalidCounterpartyData("script signature"));
+        }
+        let sender_offsets = outputs().fold(PublicKey::default(), |total, output| {
+            &total + &output.sender_offset_public_key
+        });
+        if PublicKey::from_secret_key(&signatures.script_offset) != &signer_share - &sender_offsets {
+            return Err(PaymentChannelError::InvalidCounterpartyData("script offset"));
+        }
+        Ok(())
+    }
+
+    /// Adds this party's share of the script signature and script offset to a close transaction it publishes
+    fn complete_close_transaction(
+        &self,
+        close: &CloseTerms,
+        signatures: &StateSignatures,
+        nonce: &PrivateKey,
+        factories: &CryptoFactories,
+    ) -> Result<Transaction, PaymentChannelError> {
+        let funding_output = self.funding_output.as_ref().ok_or(PaymentChannelError::InvalidState {
+            action: "close the channel without the funding output",
+            state: self.state,
+        })?;
+        let partial = &signatures.script_signature;
+        let ephemeral_pubkey = partial.ephemeral_pubkey() + &PublicKey::from_secret_key(nonce);
+        let challenge = self.script_challenge(close, partial.ephemeral_commitment(), &ephemeral_pubkey, factories)?;
+        let zero = PrivateKey::default();
+        let script_share = self.script_share()?;
+        let own = ComAndPubSignature::sign(
+            &zero,
+            &zero,
+            &script_share,
+            &zero,
+            &zero,
+            nonce,
+            &challenge,
+            &*factories.commitment,
+        )?;
+        let input = TransactionInput::new_with_output_data(
+            TransactionInputVersion::get_current_version(),
+            funding_output.features.clone(),
+            funding_output.commitment.clone(),
+            funding_output.script.clone(),
+            close.input_data.clone(),
+            partial + &own,
+            funding_output.sender_offset_public_key.clone(),
+            funding_output.covenant.clone(),
+            funding_output.encrypted_data.clone(),
+            funding_output.metadata_signature.clone(),
+            funding_output
+                .proof
+                .as_ref()
+                .map_or(FixedHash::zero(), |proof| proof.hash()),
+            funding_output.minimum_value_promise,
+        );
+        let outputs = signatures
+            .publisher_output
+            .iter()
+            .chain(signatures.signer_output.iter())
+            .cloned()
+            .collect();
+        let mut transaction = Transaction::new(
+            vec![input],
+            outputs,
+            vec![signatures.kernel.clone()],
+            PrivateKey::default(),
+            &signatures.script_offset + &script_share,
+        );
+        transaction.body.sort();
+        Ok(transaction)
+    }
+
+    fn script_challenge(
+        &self,
+        close: &CloseTerms,
+        ephemeral_commitment: &Commitment,
+        ephemeral_pubkey: &PublicKey,
+        factories: &CryptoFactories,
+    ) -> Result<[u8; 32], PaymentChannelError> {
+        Ok(TransactionInput::build_script_signature_challenge(
+            &TransactionInputVersion::get_current_version(),
+            ephemeral_commitment,
+            ephemeral_pubkey,
+            &self.funding_script()?,
+            &close.input_data,
+            &self.funding_script_key()?,
+            &self.funding_commitment(factories),
+        ))
+    }
+
+    fn check_funding_output(
+        &self,
+        output: &TransactionOutput,
+        factories: &CryptoFactories,
+    ) -> Result<(), PaymentChannelError> {
+        let invalid = PaymentChannelError::InvalidCounterpartyData("funding output");
+        if output.commitment != self.funding_commitment(factories) ||
+            output.script != self.funding_script()? ||
+            output.covenant != Covenant::default()
+        {
+            return Err(invalid);
+        }
+        output
+            .verify_metadata_signature()
+            .and_then(|_| output.verify_range_proof(&factories.range_proof))
+            .map_err(|_| invalid)
+    }
+
+    fn funding_commitment(&self, factories: &CryptoFactories) -> Commitment {
+        factories
+            .commitment
+            .commit_value(&self.funding_mask, self.parameters.capacity.as_u64())
+    }
+
+    fn funding_script_key(&self) -> Result<PublicKey, PaymentChannelError> {
+        Ok(&self.public_script_share(PaymentChannelRole::Opener)? +
+            &self.public_script_share(PaymentChannelRole::Acceptor)?)
+    }
+
+    /// A party's share of the funding script key
+    fn public_script_share(&self, party: PaymentChannelRole) -> Result<PublicKey, PaymentChannelError> {
+        let (opener, acceptor) = self.channel_parties()?;
+        let key = match party {
+            PaymentChannelRole::Opener => &opener.script_public_key,
+            PaymentChannelRole::Acceptor => &acceptor.script_public_key,
+        };
+        Ok(&key_coefficient(&opener.script_public_key, &acceptor.script_public_key, party) * key)
+    }
+
+    /// This party's share of the funding script private key
+    fn script_share(&self) -> Result<PrivateKey, PaymentChannelError> {
+        let (opener, acceptor) = self.channel_parties()?;
+        let coefficient = key_coefficient(&opener.script_public_key, &acceptor.script_public_key, self.role);
+        Ok(&coefficient * &self.secrets.script_key())
+    }
+
+    /// An output of a mined close transaction, spent with this party's payout key of the state
+    fn claim(
+        &self,
+        output: Option<&TransactionOutput>,
+        value: MicroMinotari,
+        spending_key: PrivateKey,
+        sequence: u64,
+        input_data: ExecutionStack,
+        script_lock_height: u64,
+    ) -> Option<ChannelClaim> {
+        output.map(|output| ChannelClaim {
+            output: output.clone(),
+            value,
+            spending_key,
+            script_private_key: self.secrets.payout_key(sequence),
+            input_data,
+            script_lock_height,
+        })
+    }
+
+    /// A version of the counterparty that it has not revoked, with its balances
+    fn issued_state(&self, sequence: u64) -> Result<(ChannelBalances, &StateSignatures), PaymentChannelError> {
+        if let Some(current) = self.current.as_ref().filter(|current| current.sequence == sequence) {
+            return Ok((current.balances, &current.issued));
+        }
+        match &self.pending {
+            Some(PendingUpdate::Accepted {
+                sequence: pending,
+                balances,
+                issued,
+                ..
+            }) if *pending == sequence => return Ok((*balances, issued)),
+            Some(PendingUpdate::Confirmed { previous }) if previous.sequence == sequence => {
+                return Ok((previous.balances, &previous.issued))
+            },
+            _ => {},
+        }
+        self.abandoned
+            .iter()
+            .find(|abandoned| abandoned.sequence == sequence)
+            .map(|abandoned| (abandoned.balances, &abandoned.issued))
+            .ok_or(PaymentChannelError::UnknownSequence(sequence))
+    }
+
+    fn revoked_state(&self, sequence: u64) -> Result<&RevokedState, PaymentChannelError> {
+        self.revocations
+            .iter()
+            .find(|revoked| revoked.sequence == sequence)
+            .ok_or(PaymentChannelError::NotRevoked(sequence))
+    }
+
+    /// The channel keys of the opener and the acceptor
+    fn channel_parties(&self) -> Result<(ChannelKeys, ChannelKeys), PaymentChannelError> {
+        Ok((
+            self.channel_keys(PaymentChannelRole::Opener)?,
+            self.channel_keys(PaymentChannelRole::Acceptor)?,
+        ))
+    }
+
+    fn channel_keys(&self, party: PaymentChannelRole) -> Result<ChannelKeys, PaymentChannelError> {
+        if party == self.role {
+            return Ok(self.secrets.channel_keys());
+        }
+        self.counterparty_keys.clone().ok_or(PaymentChannelError::InvalidState {
+            action: "continue without the counterparty's keys",
+            state: self.state,
+        })
+    }
+
+    fn current(&self) -> Result<&SignedState, PaymentChannelError> {
+        self.current.as_ref().ok_or(PaymentChannelError::InvalidState {
+            action: "continue without a signed state",
+            state: self.state,
+        })
+    }
+
+    fn expect_role(&self, role: PaymentChannelRole) -> Result<(), PaymentChannelError> {
+        if self.role == role {
+            Ok(())
+        } else {
+            Err(PaymentChannelError::WrongRole(role))
+        }
+    }
+
+    fn expect_state(&self, action: &'static str, states: &[PaymentChannelState]) -> Result<(), PaymentChannelError> {
+        if states.contains(&self.state) {
+            Ok(())
+        } else {
+            Err(PaymentChannelError::InvalidState {
+                action,
+                state: self.state,
+            })
+        }
+    }
+
+    fn expect_updatable(&self, action: &'static str) -> Result<(), PaymentChannelError> {
+        self.expect_state(action, &[PaymentChannelState::Funding, PaymentChannelState::Open])
+    }
+
+    fn set_state(&mut self, state: PaymentChannelState) {
+        self.state = state;
+        self.touch();
+    }
+
+    fn touch(&mut self) {
+        self.updated_at = Utc::now().naive_utc();
+    }
+}
+
+/// The coefficient of a party's share of the funding script key. It commits to both keys, so that a party cannot
+/// choose its key to cancel out the other's.
+fn key_coefficient(opener: &PublicKey, acceptor: &PublicKey, party: PaymentChannelRole) -> PrivateKey {
+    let hash = DomainSeparatedHasher::<Blake2b<U32>, PaymentChannelHashDomain>::new_with_label("key_coefficient")
+        .chain(opener.as_bytes())
+        .chain(acceptor.as_bytes())
+        .chain(i32::from(party).to_le_bytes())
+        .finalize();
+    PrivateKey::from_bytes(hash.as_ref()).expect("'DomainSeparatedHash<Blake2b<U32>>' has correct size")
+}
+
+/// Builds an output with its range proof and metadata signature
+fn build_output(
+    factories: &CryptoFactories,
+    value: MicroMinotari,
+    mask: &PrivateKey,
+    script: TariScript,
+    sender_offset: &PrivateKey,
+) -> Result<TransactionOutput, PaymentChannelError> {
+    let version = TransactionOutputVersion::get_current_version();
+    let features = OutputFeatures::default();
+    let covenant = Covenant::default();
+    let encrypted_data = EncryptedData::default();
+    let minimum_value_promise = MicroMinotari::zero();
+    let commitment = factories.commitment.commit_value(mask, value.as_u64());
+    let proof = factories
+        .range_proof
+        .construct_proof(mask, value.as_u64())
+        .map_err(|e| TransactionError::RangeProofError(e.to_string()))?;
+    let sender_offset_public_key = PublicKey::from_secret_key(sender_offset);
+    let r_a = PrivateKey::random(&mut OsRng);
+    let r_x = PrivateKey::random(&mut OsRng);
+    let r_y = PrivateKey::random(&mut OsRng);
+    let ephemeral_commitment = factories.commitment.commit(&r_x, &r_a);
+    let ephemeral_pubkey = PublicKey::from_secret_key(&r_y);
+    let challenge = TransactionOutput::build_metadata_signature_challenge(
+        &version,
+        &script,
+        &features,
+        &sender_offset_public_key,
+        &ephemeral_commitment,
+        &ephemeral_pubkey,
+        &commitment,
+        &covenant,
+        &encrypted_data,
+        minimum_value_promise,
+    );
+    let metadata_signature = ComAndPubSignature::sign(
+        &PrivateKey::from(value.as_u64()),
+        mask,
+        sender_offset,
+        &r_a,
+        &r_x,
+        &r_y,
+        &challenge,
+        &*factories.commitment,
+    )?;
+    Ok(TransactionOutput::new(
+        version,
+        features,
+        commitment,
+        Some(RangeProof::from(proof)),
+        script,
+        sender_offset_public_key,
+        metadata_signature,
+        covenant,
+        encrypted_data,
+        minimum_value_promise,
+    ))
+}
+
+/// Checks an output the counterparty built for this party
+fn check_output(
+    factories: &CryptoFactories,
+    output: &TransactionOutput,
+    script: &TariScript,
+) -> Result<(), PaymentChannelError> {
+    let invalid = PaymentChannelError::InvalidCounterpartyData("close output");
+    if output.script != *script ||
+        output.features != OutputFeatures::default() ||
+        output.covenant != Covenant::default() ||
+        output.minimum_value_promise != MicroMinotari::zero()
+    {
+        return Err(invalid);
+    }
+    output
+        .verify_metadata_signature()
+        .and_then(|_| output.verify_range_proof(&factories.range_proof))
+        .map_err(|_| invalid)
+}
+
+/// Signs a kernel with the whole excess
+fn sign_kernel(excess: &PrivateKey, fee: MicroMinotari) -> Result<TransactionKernel, PaymentChannelError> {
+    let public_excess = PublicKey::from_secret_key(excess);
+    let nonce = PrivateKey::random(&mut OsRng);
+    let challenge = TransactionKernel::build_kernel_signature_challenge(
+        &TransactionKernelVersion::get_current_version(),
+        &PublicKey::from_secret_key(&nonce),
+        &public_excess,
+        fee,
+        0,
+        &KernelFeatures::empty(),
+        &None,
+    );
+    let signature = Signature::sign_raw(excess, nonce, &challenge)?;
+    Ok(TransactionKernel::new_current_version(
+        KernelFeatures::empty(),
+        fee,
+        0,
+        Commitment::from_public_key(&public_excess),
+        signature,
+        None,
+    ))
+}
+
+fn revocation_hash(secret: &[u8; 32]) -> [u8; 32] {
+    Sha256::digest(secret).into()
+}
+
+/// A height as a script stack item. Deadlines are checked to fit when a state is accepted.
+fn height_item(height: u64) -> i64 {
+    i64::try_from(height).unwrap_or(i64::MAX)
+}
+
+#[cfg(test)]
+mod test {
+    use tari_core::validation::aggregate_body::AggregateBodyInternalConsistencyValidator;
+    use tari_script::ScriptContext;
+
+    use super::*;
+    use crate::test_utils::create_consensus_rules;
+
+    fn parameters() -> ChannelParameters {
+        ChannelParameters {
+            capacity: MicroMinotari::from(10_000_000),
+            fee: MicroMinotari::from(1_000),
+            state_lifetime: 1_000,
+            dispute_delay: 100,
+        }
+    }
+
+    const TIP_HEIGHT: u64 = 100;
+
+    fn seed() -> PrivateKey {
+        PrivateKey::random(&mut OsRng)
+    }
+
+    /// Runs the opening of a channel up to the point where it is funded
+    fn open_channels(factories: &CryptoFactories) -> (PaymentChannel, PaymentChannel) {
+        let (mut opener, open) =
+            PaymentChannel::open(TariAddress::default(), parameters(), TIP_HEIGHT, &seed()).unwrap();
+        let (mut acceptor, accept) =
+            PaymentChannel::accept(opener.id, TariAddress::default(), open, TIP_HEIGHT, &seed(), factories).unwrap();
+        assert_eq!(acceptor.state, PaymentChannelState::Opening);
+        let mut confirm = opener.accept_channel(accept, factories).unwrap();
+        assert!(confirm.revocation.is_none());
+        assert_eq!(opener.state, PaymentChannelState::Funding);
+
+        // The opener's wallet builds the funding output
+        let funding_output = build_output(
+            factories,
+            parameters().capacity,
+            opener.funding_mask(),
+            opener.funding_script().unwrap(),
+            &PrivateKey::random(&mut OsRng),
+        )
+        .unwrap();
+        opener.set_funding_output(funding_output.clone(), factories).unwrap();
+        confirm.funding_output = Some(funding_output);
+        assert!(acceptor.accept_confirmation(confirm, factories).unwrap().is_none());
+        assert_eq!(acceptor.state, PaymentChannelState::Funding);
+        assert_eq!(opener.funding_script().unwrap(), acceptor.funding_script().unwrap());
+        assert_eq!(opener.funding_output(), acceptor.funding_output());
+
+        opener.funding_confirmed().unwrap();
+        acceptor.funding_confirmed().unwrap();
+        (opener, acceptor)
+    }
+
+    /// Runs an update from the proposer's side to the point where both parties have revoked their previous states
+    fn pay(proposer: &mut PaymentChannel, responder: &mut PaymentChannel, amount: u64, factories: &CryptoFactories) {
+        let proposal = proposer
+            .propose_payment(MicroMinotari::from(amount), TIP_HEIGHT)
+            .unwrap();
+        let accept = responder.accept_update(proposal, TIP_HEIGHT, factories).unwrap();
+        let confirm = proposer.confirm_update(accept, factories).unwrap();
+        let revoke = responder.accept_confirmation(confirm, factories).unwrap().unwrap();
+        proposer.accept_revocation(revoke).unwrap();
+        assert_eq!(proposer.balances(), responder.balances());
+        assert_eq!(proposer.sequence(), responder.sequence());
+    }
+
+    /// Validates a transaction as a base node would at the given height
+    fn validate(transaction: &Transaction, height: u64, factories: &CryptoFactories) -> bool {
+        AggregateBodyInternalConsistencyValidator::new(false, create_consensus_rules(), factories.clone())
+            .validate(
+                &transaction.body,
+                &transaction.offset,
+                &transaction.script_offset,
+                None,
+                None,
+                height,
+            )
+            .is_ok()
+    }
+
+    /// The kernel signature of a close transaction
+    fn kernel_signature(transaction: &Transaction) -> Signature {
+        transaction.body.kernels()[0].excess_sig.clone()
+    }
+
+    /// Checks that a claim opens the output and satisfies its script at the given height
+    fn assert_claim(claim: &ChannelClaim, height: u64, factories: &CryptoFactories) {
+        assert_eq!(
+            claim.output.commitment,
+            factories
+                .commitment
+                .commit_value(&claim.spending_key, claim.value.as_u64())
+        );
+        let context = ScriptContext::new(height, &[0u8; 32], &claim.output.commitment);
+        assert_eq!(
+            claim
+                .output
+                .script
+                .execute_with_context(&claim.input_data, &context)
+                .unwrap(),
+            StackItem::PublicKey(PublicKey::from_secret_key(&claim.script_private_key))
+        );
+    }
+
+    #[test]
+    fn it_validates_parameters() {
+        for parameters in [
+            ChannelParameters {
+                capacity: MicroMinotari::from(1_000),
+                ..parameters()
+            },
+            ChannelParameters {
+                dispute_delay: 0,
+                ..parameters()
+            },
+            ChannelParameters {
+                state_lifetime: 0,
+                ..parameters()
+            },
+            ChannelParameters {
+                dispute_delay: u64::MAX,
+                ..parameters()
+            },
+        ] {
+            assert!(PaymentChannel::open(TariAddress::default(), parameters, TIP_HEIGHT, &seed()).is_err());
+        }
+    }
+
+    #[test]
+    fn it_derives_the_keys_from_the_seed() {
+        let seed = seed();
+        let (_, first) = PaymentChannel::open(TariAddress::default(), parameters(), TIP_HEIGHT, &seed).unwrap();
+        let (_, second) = PaymentChannel::open(TariAddress::default(), parameters(), TIP_HEIGHT, &seed).unwrap();
+        assert_eq!(first.keys, second.keys);
+        assert_eq!(first.funding_mask, second.funding_mask);
+        assert_eq!(first.state, second.state);
+
+        let (_, other) = PaymentChannel::open(TariAddress::default(), parameters(), TIP_HEIGHT, &self::seed()).unwrap();
+        assert_ne!(first.keys, other.keys);
+    }
+
+    #[test]
+    fn it_checks_the_deadline_of_a_proposed_state() {
+        let factories = CryptoFactories::default();
+        let (mut opener, mut acceptor) = open_channels(&factories);
+        assert_eq!(opener.deadline(), Some(TIP_HEIGHT + parameters().state_lifetime));
+        assert_eq!(acceptor.deadline(), opener.deadline());
+
+        // The acceptor's tip may be a few blocks behind the opener's
+        let proposal = opener
+            .propose_payment(MicroMinotari::from(1_000), TIP_HEIGHT + DEADLINE_TOLERANCE)
+            .unwrap();
+        acceptor.accept_update(proposal, TIP_HEIGHT, &factories).unwrap();
+        opener.abandon_update(1).unwrap();
+
+        // A deadline further ahead is rejected
+        let proposal = opener
+            .propose_payment(MicroMinotari::from(1_000), TIP_HEIGHT + DEADLINE_TOLERANCE + 1)
+            .unwrap();
+        assert!(matches!(
+            acceptor.accept_update(proposal.clone(), TIP_HEIGHT, &factories),
+            Err(PaymentChannelError::InvalidCounterpartyData("deadline"))
+        ));
+        opener.abandon_update(proposal.sequence).unwrap();
+
+        // So is a deadline that has passed
+        let mut proposal = opener.propose_payment(MicroMinotari::from(1_000), TIP_HEIGHT).unwrap();
+        proposal.deadline = TIP_HEIGHT;
+        assert!(matches!(
+            acceptor.accept_update(proposal, TIP_HEIGHT, &factories),
+            Err(PaymentChannelError::InvalidCounterpartyData("deadline"))
+        ));
+    }
+
+    #[test]
+    fn it_refreshes_the_deadline() {
+        let factories = CryptoFactories::default();
+        let (mut opener, mut acceptor) = open_channels(&factories);
+        let balances = opener.balances();
+        let tip_height = TIP_HEIGHT + 600;
+        let proposal = opener.propose_refresh(tip_height).unwrap();
+        let accept = acceptor.accept_update(proposal, tip_height, &factories).unwrap();
+        let confirm = opener.confirm_update(accept, &factories).unwrap();
+        let revoke = acceptor.accept_confirmation(confirm, &factories).unwrap().unwrap();
+        opener.accept_revocation(revoke).unwrap();
+
+        assert_eq!(opener.balances(), balances);
+        assert_eq!(opener.sequence(), Some(1));
+        let deadline = tip_height + parameters().state_lifetime;
+        assert_eq!(opener.deadline(), Some(deadline));
+        assert_eq!(acceptor.deadline(), opener.deadline());
+
+        // The refreshed state can be published until its new deadline, and no later
+        let transaction = opener.clone().force_close(&factories).unwrap();
+        assert!(validate(&transaction, deadline, &factories));
+        assert!(!validate(&transaction, deadline + 1, &factories));
+    }
+
+    #[test]
+    fn it_opens_a_channel() {
+        let factories = CryptoFactories::default();
+        let (opener, acceptor) = open_channels(&factories);
+        let params = parameters();
+        let balances = opener.balances().unwrap();
+        assert_eq!(balances.opener, params.spendable());
+        assert_eq!(balances.acceptor, MicroMinotari::zero());
+        assert_eq!(opener.sequence(), Some(0));
+        assert_eq!(acceptor.sequence(), Some(0));
+
+        // Both versions of the first state spend the funding output into the opener's output
+        for channel in [&opener, &acceptor] {
+            let transaction = channel.clone().force_close(&factories).unwrap();
+            assert!(validate(&transaction, TIP_HEIGHT + 1, &factories));
+            let funding_output = channel.funding_output().unwrap();
+            assert_eq!(transaction.body.inputs()[0].output_hash(), funding_output.hash());
+            assert_eq!(transaction.body.outputs().len(), 1);
+            assert_eq!(transaction.body.kernels()[0].fee, params.fee);
+        }
+
+        // A funding output with another value is rejected
+        let (mut opener, open) =
+            PaymentChannel::open(TariAddress::default(), parameters(), TIP_HEIGHT, &seed()).unwrap();
+        let (mut acceptor, accept) =
+            PaymentChannel::accept(opener.id, TariAddress::default(), open, TIP_HEIGHT, &seed(), &factories).unwrap();
+        let mut confirm = opener.accept_channel(accept, &factories).unwrap();
+        assert!(matches!(
+            acceptor.accept_confirmation(confirm.clone(), &factories),
+            Err(PaymentChannelError::InvalidCounterpartyData("funding output"))
+        ));
+        let funding_output = build_output(
+            &factories,
+            params.spendable(),
+            opener.funding_mask(),
+            opener.funding_script().unwrap(),
+            &PrivateKey::random(&mut OsRng),
+        )
+        .unwrap();
+        assert!(opener.set_funding_output(funding_output.clone(), &factories).is_err());
+        confirm.funding_output = Some(funding_output);
+        assert!(matches!(
+            acceptor.accept_confirmation(confirm, &factories),
+            Err(PaymentChannelError::InvalidCounterpartyData("funding output"))
+        ));
+        assert_eq!(acceptor.state, PaymentChannelState::Opening);
+    }
+
+    #[test]
+    fn it_updates_the_balances_in_both_directions() {
+        let factories = CryptoFactories::default();
+        let (mut opener, mut acceptor) = open_channels(&factories);
+        pay(&mut opener, &mut acceptor, 1_000_000, &factories);
+        pay(&mut opener, &mut acceptor, 500_000, &factories);
+        pay(&mut acceptor, &mut opener, 200_000, &factories);
+
+        let balances = opener.balances().unwrap();
+        assert_eq!(balances.acceptor, MicroMinotari::from(1_300_000));
+        assert_eq!(
+            balances.opener,
+            parameters().spendable() - MicroMinotari::from(1_300_000)
+        );
+        assert_eq!(opener.sequence(), Some(3));
+        assert_eq!(opener.revocations.len(), 3);
+        assert_eq!(acceptor.revocations.len(), 3);
+
+        // Both versions of the latest state are valid and pay each party its balance
+        for channel in [&opener, &acceptor] {
+            let transaction = channel.clone().force_close(&factories).unwrap();
+            assert!(validate(&transaction, TIP_HEIGHT + 1, &factories));
+            assert_eq!(transaction.body.outputs().len(), 2);
+        }
+
+        // Neither party can pay more than its balance
+        assert!(matches!(
+            acceptor.propose_payment(MicroMinotari::from(1_300_001), TIP_HEIGHT),
+            Err(PaymentChannelError::InsufficientBalance(_))
+        ));
+    }
+
+    #[test]
+    fn it_rejects_invalid_updates() {
+        let factories = CryptoFactories::default();
+        let (mut opener, mut acceptor) = open_channels(&factories);
+        pay(&mut opener, &mut acceptor, 1_000_000, &factories);
+
+        // The opener cannot take back what it paid
+        let mut proposal = opener.propose_update(opener.balances().unwrap(), TIP_HEIGHT).unwrap();
+        proposal.balances = ChannelBalances {
+            opener: parameters().spendable(),
+            acceptor: MicroMinotari::zero(),
+        };
+        assert!(matches!(
+            acceptor.accept_update(proposal.clone(), TIP_HEIGHT, &factories),
+            Err(PaymentChannelError::InvalidCounterpartyData("balances"))
+        ));
+        opener.abandon_update(proposal.sequence).unwrap();
+
+        // Balances that do not add up are rejected
+        let mut proposal = opener.propose_payment(MicroMinotari::from(1), TIP_HEIGHT).unwrap();
+        proposal.balances.acceptor = proposal.balances.acceptor + MicroMinotari::from(1);
+        assert!(matches!(
+            acceptor.accept_update(proposal.clone(), TIP_HEIGHT, &factories),
+            Err(PaymentChannelError::InvalidParameters(_))
+        ));
+        opener.abandon_update(proposal.sequence).unwrap();
+
+        // A tampered close transaction is rejected and the proposal stays pending
+        let proposal = opener.propose_payment(MicroMinotari::from(1), TIP_HEIGHT).unwrap();
+        let accept = acceptor
+            .accept_update(proposal.clone(), TIP_HEIGHT, &factories)
+            .unwrap();
+        let mut tampered = accept.clone();
+        tampered.signatures.publisher_mask = PrivateKey::random(&mut OsRng);
+        assert!(matches!(
+            opener.confirm_update(tampered, &factories),
+            Err(PaymentChannelError::InvalidCounterpartyData("close transaction"))
+        ));
+        let mut tampered = accept.clone();
+        tampered.signatures.script_offset = PrivateKey::random(&mut OsRng);
+        assert!(matches!(
+            opener.confirm_update(tampered, &factories),
+            Err(PaymentChannelError::InvalidCounterpartyData("script offset"))
+        ));
+        let mut tampered = accept;
+        std::mem::swap(
+            &mut tampered.signatures.publisher_output,
+            &mut tampered.signatures.signer_output,
+        );
+        assert!(opener.confirm_update(tampered, &factories).is_err());
+        assert_eq!(opener.unsigned_update(), Some(proposal.sequence));
+
+        // A sequence is never signed twice
+        assert!(matches!(
+            acceptor.accept_update(proposal, TIP_HEIGHT, &factories),
+            Err(PaymentChannelError::InvalidCounterpartyData("sequence"))
+        ));
+    }
+
+    #[test]
+    fn it_rejects_a_wrong_revocation_secret() {
+        let factories = CryptoFactories::default();
+        let (mut opener, mut acceptor) = open_channels(&factories);
+        let proposal = opener.propose_payment(MicroMinotari::from(1_000), TIP_HEIGHT).unwrap();
+        let accept = acceptor.accept_update(proposal, TIP_HEIGHT, &factories).unwrap();
+        let mut confirm = opener.confirm_update(accept, &factories).unwrap();
+        let revocation = confirm.revocation.unwrap();
+        confirm.revocation = Some([1u8; 32]);
+        assert!(matches!(
+            acceptor.accept_confirmation(confirm.clone(), &factories),
+            Err(PaymentChannelError::InvalidCounterpartyData("revocation secret"))
+        ));
+        assert_eq!(acceptor.sequence(), Some(0));
+
+        confirm.revocation = Some(revocation);
+        let mut revoke = acceptor.accept_confirmation(confirm, &factories).unwrap().unwrap();
+        let secret = revoke.revocation;
+        revoke.revocation = [2u8; 32];
+        assert!(opener.accept_revocation(revoke.clone()).is_err());
+        // No new update can start until the previous state is revoked
+        assert!(matches!(
+            opener.propose_payment(MicroMinotari::from(1), TIP_HEIGHT),
+            Err(PaymentChannelError::UpdateInProgress(_))
+        ));
+        revoke.revocation = secret;
+        opener.accept_revocation(revoke).unwrap();
+        assert!(opener.propose_payment(MicroMinotari::from(1), TIP_HEIGHT).is_ok());
+    }
+
+    #[test]
+    fn it_skips_abandoned_sequences() {
+        let factories = CryptoFactories::default();
+        let (mut opener, mut acceptor) = open_channels(&factories);
+        let proposal = opener.propose_payment(MicroMinotari::from(1_000), TIP_HEIGHT).unwrap();
+        assert_eq!(proposal.sequence, 1);
+        // The acceptance is lost and the opener gives up on the update
+        acceptor.accept_update(proposal, TIP_HEIGHT, &factories).unwrap();
+        opener.abandon_update(1).unwrap();
+
+        // The acceptor drops the update it signed when the next proposal arrives
+        let proposal = opener.propose_payment(MicroMinotari::from(2_000), TIP_HEIGHT).unwrap();
+        assert_eq!(proposal.sequence, 2);
+        let accept = acceptor.accept_update(proposal, TIP_HEIGHT, &factories).unwrap();
+        let confirm = opener.confirm_update(accept, &factories).unwrap();
+        let revoke = acceptor.accept_confirmation(confirm, &factories).unwrap().unwrap();
+        assert_eq!(revoke.revoked_sequence, 0);
+        opener.accept_revocation(revoke).unwrap();
+        assert_eq!(acceptor.balances().unwrap().acceptor, MicroMinotari::from(2_000));
+
+        // The opener never revoked its version of the abandoned update, so the acceptor keeps watching for it
+        assert!(acceptor
+            .close_kernels()
+            .iter()
+            .any(|(close, _)| *close == ChannelClose::Counterparty(1)));
+    }
+
+    #[test]
+    fn it_punishes_a_revoked_state() {
+        let factories = CryptoFactories::default();
+        let (mut opener, mut acceptor) = open_channels(&factories);
+        let mut cheater = opener.clone();
+        pay(&mut opener, &mut acceptor, 1_000_000, &factories);
+
+        // The opener publishes its revoked first state, in which the acceptor had nothing
+        let transaction = cheater.force_close(&factories).unwrap();
+        assert!(validate(&transaction, TIP_HEIGHT + 1, &factories));
+        let (close, _) = acceptor
+            .close_kernels()
+            .into_iter()
+            .find(|(_, signature)| *signature == kernel_signature(&transaction))
+            .unwrap();
+        assert_eq!(close, ChannelClose::Revoked(0));
+
+        // The acceptor takes the opener's output with the revocation secret, well before the settlement height
+        let claims = acceptor.close_mined(close).unwrap();
+        assert_eq!(acceptor.state, PaymentChannelState::Punished);
+        assert_eq!(claims.len(), 1);
+        assert_eq!(claims[0].value, parameters().spendable());
+        assert_eq!(&claims[0].output, &transaction.body.outputs()[0]);
+        assert_claim(&claims[0], TIP_HEIGHT + 1, &factories);
+
+        // The latest state has not been revoked
+        assert!(matches!(
+            opener.close_mined(ChannelClose::Revoked(1)),
+            Err(PaymentChannelError::NotRevoked(1))
+        ));
+    }
+
+    #[test]
+    fn it_claims_the_output_of_a_state_the_counterparty_published() {
+        let factories = CryptoFactories::default();
+        let (mut opener, mut acceptor) = open_channels(&factories);
+        pay(&mut opener, &mut acceptor, 1_000_000, &factories);
+
+        let transaction = opener.force_close(&factories).unwrap();
+        let (close, _) = acceptor
+            .close_kernels()
+            .into_iter()
+            .find(|(_, signature)| *signature == kernel_signature(&transaction))
+            .unwrap();
+        assert_eq!(close, ChannelClose::Counterparty(1));
+        let claims = acceptor.close_mined(close).unwrap();
+        assert_eq!(acceptor.state, PaymentChannelState::ClosedByCounterparty);
+        assert!(acceptor.is_finished());
+        assert_eq!(claims.len(), 1);
+        assert_eq!(claims[0].value, MicroMinotari::from(1_000_000));
+        assert!(transaction.body.outputs().contains(&claims[0].output));
+        assert_claim(&claims[0], TIP_HEIGHT + 1, &factories);
+    }
+
+    #[test]
+    fn it_force_closes_and_settles() {
+        let factories = CryptoFactories::default();
+        let (mut opener, mut acceptor) = open_channels(&factories);
+        pay(&mut opener, &mut acceptor, 1_000_000, &factories);
+        let transaction = acceptor.force_close(&factories).unwrap();
+        assert_eq!(acceptor.state, PaymentChannelState::ForceClosing);
+        assert!(validate(&transaction, TIP_HEIGHT + 1, &factories));
+        // The same transaction is rebuilt
+        assert_eq!(acceptor.force_close(&factories).unwrap(), transaction);
+        assert!(acceptor.propose_payment(MicroMinotari::from(1), TIP_HEIGHT).is_err());
+
+        assert!(acceptor.close_mined(ChannelClose::Own(1)).unwrap().is_empty());
+        let settlement_height = acceptor.settlement_height().unwrap();
+        let claim = acceptor.settle().unwrap().unwrap();
+        assert_eq!(acceptor.state, PaymentChannelState::Settled);
+        assert_eq!(claim.script_lock_height, settlement_height);
+        assert_eq!(claim.value, MicroMinotari::from(1_000_000));
+        assert!(transaction.body.outputs().contains(&claim.output));
+        assert_claim(&claim, settlement_height, &factories);
+        let context = ScriptContext::new(settlement_height - 1, &[0u8; 32], &claim.output.commitment);
+        assert!(claim
+            .output
+            .script
+            .execute_with_context(&claim.input_data, &context)
+            .is_err());
+    }
+
+    #[test]
+    fn it_closes_cooperatively() {
+        let factories = CryptoFactories::default();
+        let (mut opener, mut acceptor) = open_channels(&factories);
+        pay(&mut opener, &mut acceptor, 1_000_000, &factories);
+        let proposal = acceptor.propose_close().unwrap();
+        // No updates while closing
+        assert!(acceptor.propose_payment(MicroMinotari::from(1), TIP_HEIGHT).is_err());
+        let accept = opener.accept_close(proposal, &factories).unwrap();
+        let transaction = acceptor.complete_close(accept.clone(), &factories).unwrap();
+        // The cooperative close has no deadline
+        let deadline = acceptor.deadline().unwrap();
+        assert!(validate(&transaction, deadline * 1_000, &factories));
+        // The proposer's nonce signs a single close
+        assert!(acceptor.complete_close(accept, &factories).is_err());
+
+        let signature = kernel_signature(&transaction);
+        for channel in [&mut opener, &mut acceptor] {
+            assert!(channel
+                .close_kernels()
+                .contains(&(ChannelClose::Cooperative, signature.clone())));
+            let claims = channel.close_mined(ChannelClose::Cooperative).unwrap();
+            assert_eq!(channel.state, PaymentChannelState::Closed);
+            assert_eq!(claims.len(), 1);
+            assert_eq!(claims[0].value, channel.balances().unwrap().of(channel.role));
+            assert!(transaction.body.outputs().contains(&claims[0].output));
+            assert_claim(&claims[0], deadline * 1_000, &factories);
+        }
+        assert!(opener.is_finished() && acceptor.is_finished());
+    }
+
+    #[test]
+    fn it_resolves_simultaneous_close_proposals() {
+        let factories = CryptoFactories::default();
+        let (mut opener, mut acceptor) = open_channels(&factories);
+        let from_opener = opener.propose_close().unwrap();
+        let from_acceptor = acceptor.propose_close().unwrap();
+        assert!(opener.accept_close(from_acceptor, &factories).is_err());
+        let accept = acceptor.accept_close(from_opener, &factories).unwrap();
+        let transaction = opener.complete_close(accept, &factories).unwrap();
+        assert!(validate(&transaction, TIP_HEIGHT + 1, &factories));
+    }
+
+    #[test]
+    fn it_enforces_roles_and_states() {
+        let factories = CryptoFactories::default();
+        let (mut opener, mut acceptor) = open_channels(&factories);
+        let (_, open) = PaymentChannel::open(TariAddress::default(), parameters(), TIP_HEIGHT, &seed()).unwrap();
+        let (_, accept) =
+            PaymentChannel::accept(1, TariAddress::default(), open, TIP_HEIGHT, &seed(), &factories).unwrap();
+        assert!(matches!(
+            acceptor.accept_channel(accept.clone(), &factories),
+            Err(PaymentChannelError::WrongRole(_))
+        ));
+        assert!(matches!(
+            opener.accept_channel(accept, &factories),
+            Err(PaymentChannelError::InvalidState { .. })
+        ));
+        assert!(opener.settle().is_err());
+        assert!(opener.abort().is_err());
+
+        let (mut fresh, _) = PaymentChannel::open(TariAddress::default(), parameters(), TIP_HEIGHT, &seed()).unwrap();
+        assert!(fresh.propose_payment(MicroMinotari::from(1), TIP_HEIGHT).is_err());
+        assert!(fresh.force_close(&factories).is_err());
+        fresh.abandon_update(0).unwrap();
+        assert_eq!(fresh.state, PaymentChannelState::Aborted);
+        assert!(fresh.is_finished());
+    }
+}
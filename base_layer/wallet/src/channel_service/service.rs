@@ -0,0 +1,834 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::{convert::TryFrom, sync::Arc, time::Duration};
+
+use chrono::{NaiveDateTime, Utc};
+use futures::{pin_mut, Stream, StreamExt};
+use log::*;
+use tari_common::configuration::Network;
+use tari_common_types::{
+    tari_address::TariAddress,
+    transaction::TxId,
+    types::{FixedHash, PrivateKey},
+};
+use tari_comms::types::CommsPublicKey;
+use tari_comms_dht::{
+    domain_message::OutboundDomainMessage,
+    outbound::{OutboundEncryption, OutboundMessageRequester},
+};
+use tari_core::{
+    base_node::proto::wallet_rpc::{TxLocation, TxQueryBatchResponse},
+    proto::{
+        base_node::{Signatures as SignaturesProto, UtxoQueryRequest},
+        types::Signature as SignatureProto,
+    },
+    transactions::{
+        key_manager::{TransactionKeyManagerBranch, TransactionKeyManagerInterface},
+        tari_amount::MicroMinotari,
+        transaction_components::{OutputFeatures, Transaction, WalletOutput, WalletOutputBuilder},
+        CryptoFactories,
+    },
+};
+use tari_p2p::{domain_message::DomainMessage, tari_message::TariMessageType};
+use tari_script::ExecutionStack;
+use tari_service_framework::reply_channel;
+use tari_shutdown::ShutdownSignal;
+use tokio::time::MissedTickBehavior;
+
+use crate::{
+    base_node_service::handle::BaseNodeServiceHandle,
+    channel_service::{
+        config::ChannelServiceConfig,
+        error::{ChannelServiceError, PaymentChannelError},
+        handle::{ChannelEvent, ChannelEventSender, ChannelServiceRequest, ChannelServiceResponse},
+        proto,
+        protocol::{
+            ChannelClaim,
+            ChannelClose,
+            ChannelMessage,
+            ChannelParameters,
+            OpenChannel,
+            PaymentChannel,
+            PaymentChannelId,
+            PaymentChannelRole,
+            PaymentChannelState,
+            RejectUpdate,
+        },
+    },
+    connectivity_service::{WalletConnectivityHandle, WalletConnectivityInterface},
+    output_manager_service::{handle::OutputManagerHandle, UtxoSelectionCriteria},
+    transaction_service::{
+        handle::TransactionServiceHandle,
+        storage::database::{TransactionBackend, TransactionDatabase},
+    },
+};
+
+const LOG_TARGET: &str = "wallet::channel_service::service";
+const BASE_NODE_RPC_TIMEOUT: Duration = Duration::from_secs(30);
+/// The number of kernel signatures sent to the base node in one query
+const KERNEL_QUERY_BATCH_SIZE: usize = 100;
+
+/// The Channel Service runs the payment channel protocol with the counterparties of this wallet's channels and
+/// watches the chain for the funding, deadlines and closing transactions of its channels
+pub struct ChannelService<TBackend, TMessageStream, TKeyManagerInterface> {
+    config: ChannelServiceConfig,
+    db: TransactionDatabase<TBackend>,
+    request_stream:
+        Option<reply_channel::Receiver<ChannelServiceRequest, Result<ChannelServiceResponse, ChannelServiceError>>>,
+    message_stream: Option<TMessageStream>,
+    outbound_message_service: OutboundMessageRequester,
+    connectivity: WalletConnectivityHandle,
+    base_node_service: BaseNodeServiceHandle,
+    output_manager: OutputManagerHandle,
+    transaction_service: TransactionServiceHandle,
+    key_manager: TKeyManagerInterface,
+    factories: CryptoFactories,
+    event_publisher: ChannelEventSender,
+    network: Network,
+    shutdown_signal: ShutdownSignal,
+}
+
+impl<TBackend, TMessageStream, TKeyManagerInterface> ChannelService<TBackend, TMessageStream, TKeyManagerInterface>
+where
+    TBackend: TransactionBackend + 'static,
+    TMessageStream: Stream<Item = DomainMessage<Result<proto::ChannelMessage, prost::DecodeError>>>,
+    TKeyManagerInterface: TransactionKeyManagerInterface,
+{
+    pub fn new(
+        config: ChannelServiceConfig,
+        db: TransactionDatabase<TBackend>,
+        request_stream: reply_channel::Receiver<
+            ChannelServiceRequest,
+            Result<ChannelServiceResponse, ChannelServiceError>,
+        >,
+        message_stream: TMessageStream,
+        outbound_message_service: OutboundMessageRequester,
+        connectivity: WalletConnectivityHandle,
+        base_node_service: BaseNodeServiceHandle,
+        output_manager: OutputManagerHandle,
+        transaction_service: TransactionServiceHandle,
+        key_manager: TKeyManagerInterface,
+        factories: CryptoFactories,
+        event_publisher: ChannelEventSender,
+        network: Network,
+        shutdown_signal: ShutdownSignal,
+    ) -> Self {
+        Self {
+            config,
+            db,
+            request_stream: Some(request_stream),
+            message_stream: Some(message_stream),
+            outbound_message_service,
+            connectivity,
+            base_node_service,
+            output_manager,
+            transaction_service,
+            key_manager,
+            factories,
+            event_publisher,
+            network,
+            shutdown_signal,
+        }
+    }
+
+    pub async fn start(mut self) -> Result<(), ChannelServiceError> {
+        let request_stream = self
+            .request_stream
+            .take()
+            .expect("Channel Service initialized without request_stream")
+            .fuse();
+        pin_mut!(request_stream);
+        let message_stream = self
+            .message_stream
+            .take()
+            .expect("Channel Service initialized without message_stream")
+            .fuse();
+        pin_mut!(message_stream);
+
+        let mut shutdown = self.shutdown_signal.clone();
+        let mut monitoring_interval = tokio::time::interval(self.config.monitoring_interval);
+        monitoring_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        debug!(target: LOG_TARGET, "Channel Service started");
+        loop {
+            tokio::select! {
+                // Incoming request
+                Some(request_context) = request_stream.next() => {
+                    let (request, reply_tx) = request_context.split();
+                    let response = self.handle_request(request).await.map_err(|e| {
+                        warn!(target: LOG_TARGET, "Error handling request: {:?}", e);
+                        e
+                    });
+                    let _result = reply_tx.send(response).map_err(|e| {
+                        warn!(target: LOG_TARGET, "Failed to send reply");
+                        e
+                    });
+                },
+                // Incoming channel messages from the Comms layer
+                Some(msg) = message_stream.next() => {
+                    let (origin_public_key, inner_msg) = msg.clone().into_origin_and_inner();
+                    trace!(target: LOG_TARGET, "Handling Channel Message, Trace: {}", msg.dht_header.message_tag);
+                    let result = match inner_msg {
+                        Ok(message) => self.handle_message(origin_public_key, message).await,
+                        Err(e) => Err(ChannelServiceError::InvalidMessage(e.to_string())),
+                    };
+                    if let Err(e) = result {
+                        warn!(
+                            target: LOG_TARGET,
+                            "Failed to handle incoming Channel message: {}, Trace: {}", e, msg.dht_header.message_tag
+                        );
+                    }
+                },
+                _ = monitoring_interval.tick() => {
+                    if let Err(e) = self.monitor_channels().await {
+                        warn!(target: LOG_TARGET, "Error monitoring payment channels: {}", e);
+                    }
+                },
+                _ = shutdown.wait() => {
+                    info!(target: LOG_TARGET, "Channel Service shutting down because it received the shutdown signal");
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_request(
+        &mut self,
+        request: ChannelServiceRequest,
+    ) -> Result<ChannelServiceResponse, ChannelServiceError> {
+        trace!(target: LOG_TARGET, "Handling Service Request: {:?}", request);
+        match request {
+            ChannelServiceRequest::OpenChannel {
+                counterparty,
+                parameters,
+            } => {
+                self.check_state_lifetime(&parameters)?;
+                let tip_height = self.tip_height().await?;
+                let seed = self.new_channel_seed().await?;
+                let (channel, open) = PaymentChannel::open(counterparty, parameters, tip_height, &seed)?;
+                self.db.insert_payment_channel(&channel)?;
+                self.send_message(&channel, ChannelMessage::Open(open)).await?;
+                self.publish_event(ChannelEvent::ChannelOpened(channel.id));
+                Ok(ChannelServiceResponse::ChannelOpened(channel.id))
+            },
+            ChannelServiceRequest::Pay { channel_id, amount } => {
+                let mut channel = self.get_channel(channel_id)?;
+                let tip_height = self.tip_height().await?;
+                let proposal = channel.propose_payment(amount, tip_height)?;
+                let sequence = proposal.sequence;
+                self.db.update_payment_channel(&channel)?;
+                self.send_message(&channel, ChannelMessage::ProposeUpdate(proposal))
+                    .await?;
+                Ok(ChannelServiceResponse::PaymentProposed(sequence))
+            },
+            ChannelServiceRequest::CloseChannel(channel_id) => {
+                let mut channel = self.get_channel(channel_id)?;
+                let proposal = channel.propose_close()?;
+                self.db.update_payment_channel(&channel)?;
+                self.send_message(&channel, ChannelMessage::ProposeClose(proposal))
+                    .await?;
+                Ok(ChannelServiceResponse::CloseProposed)
+            },
+            ChannelServiceRequest::ForceCloseChannel(channel_id) => {
+                let mut channel = self.get_channel(channel_id)?;
+                let tx_id = self.force_close(&mut channel).await?;
+                Ok(ChannelServiceResponse::ForceClosed(tx_id))
+            },
+            ChannelServiceRequest::GetChannel(channel_id) => {
+                Ok(ChannelServiceResponse::Channel(Box::new(self.get_channel(channel_id)?)))
+            },
+            ChannelServiceRequest::GetChannels => Ok(ChannelServiceResponse::Channels(self.db.get_payment_channels()?)),
+        }
+    }
+
+    async fn handle_message(
+        &mut self,
+        source_public_key: CommsPublicKey,
+        message: proto::ChannelMessage,
+    ) -> Result<(), ChannelServiceError> {
+        let channel_id = message.channel_id;
+        let message = ChannelMessage::try_from(message).map_err(ChannelServiceError::InvalidMessage)?;
+        debug!(
+            target: LOG_TARGET,
+            "Received {} for channel {} from {}", message, channel_id, source_public_key
+        );
+        match message {
+            ChannelMessage::Open(open) => self.accept_channel(source_public_key, channel_id, open).await,
+            message => {
+                let mut channel = self.get_channel(channel_id)?;
+                if channel.counterparty.public_key() != &source_public_key {
+                    return Err(ChannelServiceError::UnknownCounterparty);
+                }
+                self.handle_channel_message(&mut channel, message).await
+            },
+        }
+    }
+
+    async fn accept_channel(
+        &mut self,
+        source_public_key: CommsPublicKey,
+        channel_id: PaymentChannelId,
+        open: OpenChannel,
+    ) -> Result<(), ChannelServiceError> {
+        if !self.config.accept_incoming_channels {
+            return Err(ChannelServiceError::InvalidMessage(
+                "Incoming channels are not accepted".to_string(),
+            ));
+        }
+        if open.parameters.dispute_delay < self.config.min_dispute_delay {
+            return Err(ChannelServiceError::InvalidMessage(format!(
+                "The dispute delay of {} blocks is below the minimum of {}",
+                open.parameters.dispute_delay, self.config.min_dispute_delay
+            )));
+        }
+        if open.parameters.state_lifetime > self.config.max_state_lifetime {
+            return Err(ChannelServiceError::InvalidMessage(format!(
+                "The state lifetime of {} blocks is above the maximum of {}",
+                open.parameters.state_lifetime, self.config.max_state_lifetime
+            )));
+        }
+        self.check_state_lifetime(&open.parameters)?;
+        if self.db.get_payment_channel(channel_id)?.is_some() {
+            return Err(ChannelServiceError::InvalidMessage(format!(
+                "Channel {} already exists",
+                channel_id
+            )));
+        }
+        let tip_height = self.tip_height().await?;
+        let seed = self.new_channel_seed().await?;
+        let counterparty = TariAddress::new(source_public_key, self.network);
+        let (channel, accept) =
+            PaymentChannel::accept(channel_id, counterparty, open, tip_height, &seed, &self.factories)?;
+        self.db.insert_payment_channel(&channel)?;
+        self.send_message(&channel, ChannelMessage::Accept(accept)).await?;
+        self.publish_event(ChannelEvent::ChannelOpened(channel_id));
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_lines)]
+    async fn handle_channel_message(
+        &mut self,
+        channel: &mut PaymentChannel,
+        message: ChannelMessage,
+    ) -> Result<(), ChannelServiceError> {
+        match message {
+            ChannelMessage::Open(_) => {
+                return Err(ChannelServiceError::InvalidMessage(format!(
+                    "Channel {} already exists",
+                    channel.id
+                )));
+            },
+            ChannelMessage::Accept(accept) => {
+                let mut confirm = channel.accept_channel(accept, &self.factories)?;
+                let (tx_id, transaction) = self.fund_channel(channel).await?;
+                confirm.funding_output = channel.funding_output().cloned();
+                // The channel is stored with the funding output before the funding transaction can be mined, so that
+                // it can always be closed
+                self.db.update_payment_channel(channel)?;
+                self.transaction_service
+                    .submit_transaction(
+                        tx_id,
+                        transaction,
+                        channel.parameters.capacity,
+                        format!("Payment channel {} funding", channel.id),
+                    )
+                    .await?;
+                self.send_message(channel, ChannelMessage::ConfirmUpdate(confirm))
+                    .await?;
+                self.publish_event(ChannelEvent::ChannelSigned(channel.id));
+            },
+            ChannelMessage::ProposeUpdate(proposal) => {
+                let sequence = proposal.sequence;
+                let accepted = match self.tip_height().await {
+                    Ok(tip_height) => channel
+                        .accept_update(proposal, tip_height, &self.factories)
+                        .map_err(Into::into),
+                    Err(e) => Err(e),
+                };
+                match accepted {
+                    Ok(accept) => {
+                        self.db.update_payment_channel(channel)?;
+                        self.send_message(channel, ChannelMessage::AcceptUpdate(accept)).await?;
+                    },
+                    Err(e) => {
+                        let reject = RejectUpdate {
+                            sequence,
+                            reason: e.to_string(),
+                        };
+                        self.send_message(channel, ChannelMessage::RejectUpdate(reject)).await?;
+                        return Err(e);
+                    },
+                }
+            },
+            ChannelMessage::AcceptUpdate(accept) => {
+                let confirm = channel.confirm_update(accept, &self.factories)?;
+                self.db.update_payment_channel(channel)?;
+                self.send_message(channel, ChannelMessage::ConfirmUpdate(confirm))
+                    .await?;
+            },
+            ChannelMessage::ConfirmUpdate(confirm) => {
+                let revoke = channel.accept_confirmation(confirm, &self.factories)?;
+                self.db.update_payment_channel(channel)?;
+                match revoke {
+                    Some(revoke) => {
+                        self.send_message(channel, ChannelMessage::RevokeUpdate(revoke)).await?;
+                        self.publish_updated(channel);
+                    },
+                    // The first state has nothing to revoke
+                    None => self.publish_event(ChannelEvent::ChannelSigned(channel.id)),
+                }
+            },
+            ChannelMessage::RevokeUpdate(revoke) => {
+                channel.accept_revocation(revoke)?;
+                self.db.update_payment_channel(channel)?;
+                self.publish_updated(channel);
+            },
+            ChannelMessage::RejectUpdate(reject) => {
+                channel.abandon_update(reject.sequence)?;
+                self.db.update_payment_channel(channel)?;
+                self.publish_event(ChannelEvent::UpdateRejected {
+                    channel_id: channel.id,
+                    sequence: reject.sequence,
+                    reason: reject.reason,
+                });
+                if channel.state == PaymentChannelState::Aborted {
+                    self.publish_event(ChannelEvent::ChannelAborted(channel.id));
+                }
+            },
+            ChannelMessage::ProposeClose(proposal) => {
+                let accept = channel.accept_close(proposal, &self.factories)?;
+                self.db.update_payment_channel(channel)?;
+                self.send_message(channel, ChannelMessage::AcceptClose(accept)).await?;
+            },
+            ChannelMessage::AcceptClose(accept) => {
+                let transaction = channel.complete_close(accept, &self.factories)?;
+                self.db.update_payment_channel(channel)?;
+                let tx_id = TxId::new_random();
+                self.transaction_service
+                    .submit_transaction(
+                        tx_id,
+                        transaction,
+                        Self::own_balance(channel),
+                        format!("Payment channel {} close", channel.id),
+                    )
+                    .await?;
+                self.publish_event(ChannelEvent::ChannelClosing {
+                    channel_id: channel.id,
+                    tx_id,
+                });
+            },
+        }
+        Ok(())
+    }
+
+    /// Abandons stalled updates, and follows the channels through funding, deadlines and closing transactions
+    async fn monitor_channels(&mut self) -> Result<(), ChannelServiceError> {
+        let tip_height = match self.base_node_service.get_chain_metadata().await? {
+            Some(metadata) => metadata.height_of_longest_chain(),
+            None => {
+                debug!(target: LOG_TARGET, "Channel monitoring skipped, the chain tip is not known yet");
+                return Ok(());
+            },
+        };
+        let now = Utc::now().naive_utc();
+        for mut channel in self.db.get_payment_channels()? {
+            if channel.is_finished() {
+                continue;
+            }
+            if let Err(e) = self.monitor_channel(&mut channel, tip_height, now).await {
+                warn!(target: LOG_TARGET, "Error monitoring payment channel {}: {}", channel.id, e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn monitor_channel(
+        &mut self,
+        channel: &mut PaymentChannel,
+        tip_height: u64,
+        now: NaiveDateTime,
+    ) -> Result<(), ChannelServiceError> {
+        if let Some(sequence) = channel.unsigned_update() {
+            let timeout = chrono::Duration::from_std(self.config.update_timeout)
+                .unwrap_or_else(|_| chrono::Duration::max_value());
+            if now - channel.updated_at > timeout {
+                info!(
+                    target: LOG_TARGET,
+                    "Abandoning update {} of channel {}, the counterparty did not sign it in time", sequence, channel.id
+                );
+                channel.abandon_update(sequence)?;
+                self.db.update_payment_channel(channel)?;
+                self.publish_event(ChannelEvent::UpdateAbandoned {
+                    channel_id: channel.id,
+                    sequence,
+                });
+                if channel.state == PaymentChannelState::Aborted {
+                    self.publish_event(ChannelEvent::ChannelAborted(channel.id));
+                    return Ok(());
+                }
+            }
+        }
+
+        if channel.state == PaymentChannelState::Funding && self.is_funding_mined(channel).await? {
+            channel.funding_confirmed()?;
+            self.db.update_payment_channel(channel)?;
+            self.publish_event(ChannelEvent::ChannelFunded(channel.id));
+        }
+
+        if matches!(
+            channel.state,
+            PaymentChannelState::Funding |
+                PaymentChannelState::Open |
+                PaymentChannelState::Closing |
+                PaymentChannelState::ForceClosing
+        ) {
+            if let Some(close) = self.find_mined_close(channel).await? {
+                return self.handle_mined_close(channel, close, tip_height).await;
+            }
+        }
+
+        let deadline = match channel.deadline() {
+            Some(deadline) => deadline,
+            None => return Ok(()),
+        };
+        match channel.state {
+            PaymentChannelState::Funding | PaymentChannelState::Open | PaymentChannelState::Closing
+                if tip_height.saturating_add(self.config.force_close_margin) >= deadline =>
+            {
+                info!(
+                    target: LOG_TARGET,
+                    "Force closing channel {}, its latest state must be published by height {}", channel.id, deadline
+                );
+                self.force_close(channel).await?;
+            },
+            // The opener moves the deadline forward once half of the state lifetime has passed, so that both parties
+            // do not propose an update at the same time
+            PaymentChannelState::Funding | PaymentChannelState::Open
+                if channel.role == PaymentChannelRole::Opener &&
+                    !channel.is_updating() &&
+                    tip_height.saturating_add(channel.parameters.state_lifetime / 2) >= deadline =>
+            {
+                debug!(
+                    target: LOG_TARGET,
+                    "Refreshing the deadline of channel {} at height {}", channel.id, deadline
+                );
+                let proposal = channel.propose_refresh(tip_height)?;
+                self.db.update_payment_channel(channel)?;
+                self.send_message(channel, ChannelMessage::ProposeUpdate(proposal))
+                    .await?;
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+
+    /// Claims this party's outputs of a mined close transaction. This party's own version is settled once the dispute
+    /// delay has passed.
+    async fn handle_mined_close(
+        &mut self,
+        channel: &mut PaymentChannel,
+        close: ChannelClose,
+        tip_height: u64,
+    ) -> Result<(), ChannelServiceError> {
+        if let ChannelClose::Own(_) = close {
+            if channel.state != PaymentChannelState::ForceClosing {
+                channel.close_mined(close)?;
+                self.db.update_payment_channel(channel)?;
+            }
+            if channel
+                .settlement_height()
+                .map_or(false, |settlement_height| tip_height >= settlement_height)
+            {
+                let claims = channel.settle()?.into_iter().collect();
+                let tx_id = self.claim(channel, claims).await?;
+                self.db.update_payment_channel(channel)?;
+                self.publish_event(ChannelEvent::ChannelSettled {
+                    channel_id: channel.id,
+                    tx_id,
+                });
+            }
+            return Ok(());
+        }
+
+        info!(target: LOG_TARGET, "Close {:?} of channel {} was mined", close, channel.id);
+        let claims = channel.close_mined(close)?;
+        let tx_id = self.claim(channel, claims).await?;
+        self.db.update_payment_channel(channel)?;
+        let channel_id = channel.id;
+        self.publish_event(match close {
+            ChannelClose::Counterparty(sequence) => ChannelEvent::CounterpartyClosed {
+                channel_id,
+                sequence,
+                tx_id,
+            },
+            ChannelClose::Revoked(sequence) => ChannelEvent::ChannelPunished {
+                channel_id,
+                sequence,
+                tx_id,
+            },
+            ChannelClose::Own(_) | ChannelClose::Cooperative => ChannelEvent::ChannelClosed { channel_id, tx_id },
+        });
+        Ok(())
+    }
+
+    /// Publishes this party's version of the latest state
+    async fn force_close(&mut self, channel: &mut PaymentChannel) -> Result<TxId, ChannelServiceError> {
+        let transaction = channel.force_close(&self.factories)?;
+        self.db.update_payment_channel(channel)?;
+        let tx_id = TxId::new_random();
+        self.transaction_service
+            .submit_transaction(
+                tx_id,
+                transaction,
+                Self::own_balance(channel),
+                format!("Payment channel {} force close", channel.id),
+            )
+            .await?;
+        self.publish_event(ChannelEvent::ChannelForceClosed {
+            channel_id: channel.id,
+            tx_id,
+        });
+        Ok(tx_id)
+    }
+
+    /// Builds the funding transaction of a channel this wallet opened and records its funding output in the channel.
+    /// The funding output is frozen, so that the wallet does not spend it.
+    async fn fund_channel(&mut self, channel: &mut PaymentChannel) -> Result<(TxId, Transaction), ChannelServiceError> {
+        let script = channel.funding_script()?;
+        let spending_key_id = self.key_manager.import_key(channel.funding_mask().clone()).await?;
+        // The funding output is spent with the channel's own script signature, so this script key is never used
+        let (_, _, script_key_id, _) = self.key_manager.get_next_spend_and_script_key_ids().await?;
+        let (sender_offset_key_id, _) = self
+            .key_manager
+            .get_next_key(&TransactionKeyManagerBranch::SenderOffset.get_branch_key())
+            .await?;
+        let output = WalletOutputBuilder::new(channel.parameters.capacity, spending_key_id)
+            .with_features(OutputFeatures::default())
+            .with_script(script.clone())
+            .encrypt_data_for_recovery(&self.key_manager, None)
+            .await
+            .map_err(PaymentChannelError::from)?
+            .with_input_data(ExecutionStack::default())
+            .with_script_key(script_key_id);
+
+        let (tx_id, transaction) = self
+            .output_manager
+            .create_send_to_self_with_sender_offset_output(
+                output,
+                sender_offset_key_id,
+                self.config.fee_per_gram,
+                UtxoSelectionCriteria::default(),
+            )
+            .await?;
+        self.output_manager.confirm_pending_transaction(tx_id).await?;
+        let funding_output = transaction
+            .body
+            .outputs()
+            .iter()
+            .find(|output| output.script == script)
+            .cloned()
+            .ok_or(ChannelServiceError::MissingFundingOutput)?;
+        self.output_manager
+            .freeze_outputs(vec![funding_output.commitment.clone()])
+            .await?;
+        channel.set_funding_output(funding_output, &self.factories)?;
+        Ok((tx_id, transaction))
+    }
+
+    /// Spends the outputs of a mined close transaction into the wallet
+    async fn claim(
+        &mut self,
+        channel: &PaymentChannel,
+        claims: Vec<ChannelClaim>,
+    ) -> Result<Option<TxId>, ChannelServiceError> {
+        if claims.is_empty() {
+            return Ok(None);
+        }
+        let mut outputs = Vec::with_capacity(claims.len());
+        for claim in claims {
+            let spending_key_id = self.key_manager.import_key(claim.spending_key).await?;
+            let script_key_id = self.key_manager.import_key(claim.script_private_key).await?;
+            let output = claim.output;
+            // The output is spent with the range proof it was mined with
+            outputs.push(WalletOutput::new_with_rangeproof(
+                output.version,
+                claim.value,
+                spending_key_id,
+                output.features,
+                output.script,
+                claim.input_data,
+                script_key_id,
+                output.sender_offset_public_key,
+                output.metadata_signature,
+                claim.script_lock_height,
+                output.covenant,
+                output.encrypted_data,
+                output.minimum_value_promise,
+                output.proof,
+            ));
+        }
+        let (tx_id, _, amount, transaction) = self
+            .output_manager
+            .create_payment_channel_claim_transaction(outputs, self.config.fee_per_gram)
+            .await?;
+        self.transaction_service
+            .submit_transaction(
+                tx_id,
+                transaction,
+                amount,
+                format!("Payment channel {} claim", channel.id),
+            )
+            .await?;
+        Ok(Some(tx_id))
+    }
+
+    /// A new seed for the keys of a channel
+    async fn new_channel_seed(&self) -> Result<PrivateKey, ChannelServiceError> {
+        let (key_id, _) = self
+            .key_manager
+            .get_next_key(&TransactionKeyManagerBranch::PaymentChannel.get_branch_key())
+            .await?;
+        Ok(self.key_manager.get_private_key(&key_id).await?)
+    }
+
+    fn own_balance(channel: &PaymentChannel) -> MicroMinotari {
+        channel
+            .balances()
+            .map_or(MicroMinotari::zero(), |balances| balances.of(channel.role))
+    }
+
+    /// The state lifetime must leave room to refresh a state before the channel is force closed
+    fn check_state_lifetime(&self, parameters: &ChannelParameters) -> Result<(), ChannelServiceError> {
+        if parameters.state_lifetime / 2 <= self.config.force_close_margin {
+            return Err(PaymentChannelError::InvalidParameters(format!(
+                "The state lifetime must be more than twice the force close margin of {} blocks",
+                self.config.force_close_margin
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    async fn tip_height(&mut self) -> Result<u64, ChannelServiceError> {
+        Ok(self
+            .base_node_service
+            .get_chain_metadata()
+            .await?
+            .ok_or(ChannelServiceError::ChainTipUnknown)?
+            .height_of_longest_chain())
+    }
+
+    async fn is_funding_mined(&mut self, channel: &PaymentChannel) -> Result<bool, ChannelServiceError> {
+        let output_hash = match channel.funding_output() {
+            Some(output) => output.hash(),
+            None => return Ok(false),
+        };
+        let mut client = match self
+            .connectivity
+            .obtain_base_node_wallet_rpc_client_timeout(BASE_NODE_RPC_TIMEOUT)
+            .await
+        {
+            Some(client) => client,
+            None => return Ok(false),
+        };
+        let response = client
+            .utxo_query(UtxoQueryRequest {
+                output_hashes: vec![output_hash.to_vec()],
+            })
+            .await?;
+        // Spent outputs are returned as well
+        Ok(response
+            .responses
+            .iter()
+            .any(|output| FixedHash::try_from(output.output_hash.clone()).ok() == Some(output_hash)))
+    }
+
+    /// Finds the transaction that spent the funding output among the close transactions of the channel
+    async fn find_mined_close(
+        &mut self,
+        channel: &PaymentChannel,
+    ) -> Result<Option<ChannelClose>, ChannelServiceError> {
+        let kernels = channel.close_kernels();
+        if kernels.is_empty() {
+            return Ok(None);
+        }
+        let mut client = match self
+            .connectivity
+            .obtain_base_node_wallet_rpc_client_timeout(BASE_NODE_RPC_TIMEOUT)
+            .await
+        {
+            Some(client) => client,
+            None => return Ok(None),
+        };
+        for batch in kernels.chunks(KERNEL_QUERY_BATCH_SIZE) {
+            let response = client
+                .transaction_batch_query(SignaturesProto {
+                    sigs: batch
+                        .iter()
+                        .map(|(_, signature)| SignatureProto::from(signature.clone()))
+                        .collect(),
+                })
+                .await?;
+            for response in response.responses {
+                let response =
+                    TxQueryBatchResponse::try_from(response).map_err(ChannelServiceError::InvalidBaseNodeResponse)?;
+                if response.location != TxLocation::Mined {
+                    continue;
+                }
+                if let Some((close, _)) = batch.iter().find(|(_, signature)| *signature == response.signature) {
+                    return Ok(Some(*close));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn get_channel(&self, channel_id: PaymentChannelId) -> Result<PaymentChannel, ChannelServiceError> {
+        self.db
+            .get_payment_channel(channel_id)?
+            .ok_or(ChannelServiceError::NotFound(channel_id))
+    }
+
+    async fn send_message(
+        &mut self,
+        channel: &PaymentChannel,
+        message: ChannelMessage,
+    ) -> Result<(), ChannelServiceError> {
+        let destination = channel.counterparty.public_key().clone();
+        debug!(
+            target: LOG_TARGET,
+            "Sending {} for channel {} to {}", message, channel.id, destination
+        );
+        self.outbound_message_service
+            .send_direct_encrypted(
+                destination.clone(),
+                OutboundDomainMessage::new(
+                    &TariMessageType::PaymentChannel,
+                    proto::ChannelMessage::new(channel.id, message),
+                ),
+                OutboundEncryption::encrypt_for(destination),
+                "payment channel".to_string(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    fn publish_updated(&self, channel: &PaymentChannel) {
+        if let (Some(sequence), Some(balances)) = (channel.sequence(), channel.balances()) {
+            self.publish_event(ChannelEvent::ChannelUpdated {
+                channel_id: channel.id,
+                sequence,
+                balances,
+            });
+        }
+    }
+
+    fn publish_event(&self, event: ChannelEvent) {
+        let _size = self.event_publisher.send(Arc::new(event)).map_err(|e| {
+            trace!(target: LOG_TARGET, "Error sending event, no subscribers: {:?}", e);
+            e
+        });
+    }
+}
@@ -39,6 +39,7 @@ use tari_utilities::SafePassword;
 
 use crate::{
     base_node_service::config::BaseNodeServiceConfig,
+    channel_service::config::ChannelServiceConfig,
     output_manager_service::config::OutputManagerServiceConfig,
     transaction_service::config::TransactionServiceConfig,
 };
@@ -63,6 +64,9 @@ pub struct WalletConfig {
     /// The output_manager_service_config config settings
     #[serde(rename = "outputs")]
     pub output_manager_service_config: OutputManagerServiceConfig,
+    /// The channel_service_config config settings
+    #[serde(rename = "channels")]
+    pub channel_service_config: ChannelServiceConfig,
     /// The buffer size for the publish/subscribe connector channel, connecting comms messages to the domain layer
     pub buffer_size: usize,
     /// Selected network
@@ -93,18 +97,29 @@ pub struct WalletConfig {
     /// transaction events are received by the console wallet .
     /// (see example at 'applications/tari_console_wallet/src/notifier/notify_example.sh')
     pub notify_file: Option<PathBuf>,
+    /// HTTP webhook endpoints that receive every transaction event of the console wallet
+    #[serde(rename = "webhooks")]
+    pub webhook_config: WebhookConfig,
     /// If true, a GRPC server will bind to the configured address and listen for incoming GRPC requests.
     pub grpc_enabled: bool,
     /// GRPC bind address of the wallet
     pub grpc_address: Option<Multiaddr>,
     /// GRPC authentication mode
     pub grpc_authentication: GrpcAuthentication,
+    /// If true, a JSON-RPC 2.0 server will bind to the configured address and listen for HTTP requests. It uses the
+    /// same credentials as the GRPC server.
+    pub json_rpc_enabled: bool,
+    /// JSON-RPC bind address of the wallet
+    pub json_rpc_address: Option<Multiaddr>,
     /// A custom base node peer that will be used to obtain metadata from
     pub custom_base_node: Option<String>,
     /// A list of base node peers that the wallet should use for service requests and tracking chain state
     pub base_node_service_peers: StringList,
     /// The amount of times wallet recovery will be retried before being abandoned
     pub recovery_retry_limit: usize,
+    /// The number of blocks in each height segment that wallet recovery scans concurrently when more than one base
+    /// node peer is configured. Set to 0 to always scan sequentially from a single peer.
+    pub recovery_segment_size: u64,
     /// The default uT fee per gram to use for transaction fees
     pub fee_per_gram: u64,
     /// Number of required transaction confirmations used for UI purposes
@@ -128,6 +143,7 @@ impl Default for WalletConfig {
             p2p,
             transaction_service_config: Default::default(),
             output_manager_service_config: Default::default(),
+            channel_service_config: Default::default(),
             buffer_size: 50_000,
             network: Default::default(),
             base_node_service_config: Default::default(),
@@ -140,12 +156,16 @@ impl Default for WalletConfig {
             command_send_wait_stage: TransactionStage::Broadcast,
             command_send_wait_timeout: Duration::from_secs(300),
             notify_file: None,
+            webhook_config: WebhookConfig::default(),
             grpc_enabled: false,
             grpc_address: None,
             grpc_authentication: GrpcAuthentication::default(),
+            json_rpc_enabled: false,
+            json_rpc_address: None,
             custom_base_node: None,
             base_node_service_peers: StringList::default(),
             recovery_retry_limit: 3,
+            recovery_segment_size: 10_000,
             fee_per_gram: 5,
             num_required_confirmations: 3,
             use_libtor: false,
@@ -172,6 +192,40 @@ impl WalletConfig {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    /// The URLs that a JSON payload is POSTed to for every transaction event. Webhooks are disabled when empty.
+    pub endpoints: StringList,
+    /// When set, every payload is signed with HMAC-SHA256 using this secret, in the `X-Tari-Signature` header
+    #[serde(deserialize_with = "deserialize_safe_password_option")]
+    pub secret: Option<SafePassword>,
+    /// How many times a delivery is attempted before it is moved to the dead-letter log
+    pub max_attempts: u32,
+    /// The delay before the first retry of a failed delivery, doubled for every subsequent retry
+    #[serde(with = "serializers::seconds")]
+    pub retry_base_delay: Duration,
+    /// The longest delay between retries of a failed delivery
+    #[serde(with = "serializers::seconds")]
+    pub retry_max_delay: Duration,
+    /// How long to wait for an endpoint to respond
+    #[serde(with = "serializers::seconds")]
+    pub request_timeout: Duration,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: StringList::default(),
+            secret: None,
+            max_attempts: 10,
+            retry_base_delay: Duration::from_secs(5),
+            retry_max_delay: Duration::from_secs(3600),
+            request_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
 #[derive(Debug, EnumString, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum TransactionStage {
     Initiated,
@@ -183,6 +183,12 @@ pub enum WalletStorageError {
     RecoverySeedError(String),
     #[error("Bad encryption version: `{0}`")]
     BadEncryptionVersion(String),
+    #[error("Invalid wallet backup file: {0}")]
+    InvalidBackupFile(String),
+    #[error("Unsupported wallet backup version: `{0}`")]
+    UnsupportedBackupVersion(u8),
+    #[error("Cannot restore a backup over an existing wallet database")]
+    BackupRestoreTargetExists,
 }
 
 impl From<HexError> for WalletStorageError {
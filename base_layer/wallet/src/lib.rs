@@ -10,6 +10,7 @@
 #[macro_use]
 mod macros;
 pub mod base_node_service;
+pub mod channel_service;
 pub mod connectivity_service;
 pub mod error;
 mod operation_id;
@@ -33,7 +34,7 @@ extern crate diesel_migrations;
 mod config;
 pub mod schema;
 pub mod utxo_scanner_service;
-pub use config::{TransactionStage, WalletConfig};
+pub use config::{TransactionStage, WalletConfig, WebhookConfig};
 use tari_contacts::contacts_service::storage::sqlite_db::ContactsServiceSqliteDatabase;
 use tari_core::transactions::key_manager::TransactionKeyManagerWrapper;
 use tari_key_manager::key_manager_service::storage::sqlite_db::KeyManagerSqliteDatabase;
@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use serde::{Deserialize, Serialize};
+use tari_core::transactions::tari_amount::MicroMinotari;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -43,6 +44,8 @@ pub struct OutputManagerServiceConfig {
     pub autoignore_onesided_utxos: bool,
     /// The number of seconds that have to pass for the wallet to run revalidation of invalid UTXOs on startup.
     pub num_of_seconds_to_revalidate_invalid_utxos: u64,
+    /// Automatic consolidation of small outputs while fees are low
+    pub dust_consolidation: DustConsolidationConfig,
 }
 
 impl Default for OutputManagerServiceConfig {
@@ -54,6 +57,42 @@ impl Default for OutputManagerServiceConfig {
             tx_validator_batch_size: 100,
             autoignore_onesided_utxos: false,
             num_of_seconds_to_revalidate_invalid_utxos: 60 * 60 * 24 * 3,
+            dust_consolidation: DustConsolidationConfig::default(),
+        }
+    }
+}
+
+/// Consolidation of outputs below `dust_threshold` into a single output, done in the background while the mempool
+/// fee-per-gram is at most `max_fee_per_gram`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DustConsolidationConfig {
+    /// Set to `true` to consolidate dust automatically
+    pub enabled: bool,
+    /// Outputs worth less than this are consolidated
+    pub dust_threshold: MicroMinotari,
+    /// Only consolidate while the fee-per-gram needed to get into the next block is at most this
+    pub max_fee_per_gram: MicroMinotari,
+    /// The fee-per-gram the dust would otherwise be spent at, used to estimate the savings of a consolidation
+    pub reference_fee_per_gram: MicroMinotari,
+    /// The fewest outputs worth consolidating in one transaction
+    pub min_inputs_per_transaction: usize,
+    /// The most outputs consolidated in one transaction
+    pub max_inputs_per_transaction: usize,
+    /// The most consolidation transactions created in one run
+    pub max_transactions_per_run: usize,
+}
+
+impl Default for DustConsolidationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dust_threshold: MicroMinotari::from(100_000),
+            max_fee_per_gram: MicroMinotari::from(1),
+            reference_fee_per_gram: MicroMinotari::from(5),
+            min_inputs_per_transaction: 10,
+            max_inputs_per_transaction: 100,
+            max_transactions_per_run: 1,
         }
     }
 }
@@ -0,0 +1,128 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Planning of the automatic consolidation of dust outputs. The output manager creates the consolidation transactions
+//! and the transaction service submits them.
+
+use std::{fmt, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+use tari_common_types::transaction::TxId;
+use tari_core::transactions::{fee::Fee, tari_amount::MicroMinotari, transaction_components::Transaction};
+
+use crate::output_manager_service::config::DustConsolidationConfig;
+
+/// The outcome of a single consolidation transaction
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DustConsolidationReport {
+    pub tx_id: TxId,
+    /// The number of dust outputs spent
+    pub num_inputs: usize,
+    /// The value of the consolidated output
+    pub amount: MicroMinotari,
+    /// The fee paid for the consolidation
+    pub fee: MicroMinotari,
+    pub fee_per_gram: MicroMinotari,
+    /// The fee saved when the consolidated output is spent at the reference fee-per-gram instead of the dust outputs,
+    /// less the fee paid for the consolidation
+    pub estimated_savings: MicroMinotari,
+}
+
+impl Display for DustConsolidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Consolidated {} outputs into {} (TxId: {}) for a fee of {} at {}/g, saving an estimated {}",
+            self.num_inputs, self.amount, self.tx_id, self.fee, self.fee_per_gram, self.estimated_savings
+        )
+    }
+}
+
+/// A consolidation transaction created by the output manager, ready to be submitted
+#[derive(Debug, Clone)]
+pub struct DustConsolidation {
+    pub transaction: Transaction,
+    pub report: DustConsolidationReport,
+}
+
+/// Splits the dust `candidates`, smallest first, into the inputs of the consolidation transactions of one run. Every
+/// batch has between `min_inputs_per_transaction` and `max_inputs_per_transaction` inputs, and there are at most
+/// `max_transactions_per_run` batches.
+pub fn dust_batches<T>(candidates: Vec<T>, config: &DustConsolidationConfig) -> Vec<Vec<T>> {
+    let min_inputs = config.min_inputs_per_transaction.max(2);
+    let max_inputs = config.max_inputs_per_transaction.max(min_inputs);
+    let mut batches = Vec::new();
+    let mut candidates = candidates.into_iter().peekable();
+    while batches.len() < config.max_transactions_per_run && candidates.peek().is_some() {
+        let batch = candidates.by_ref().take(max_inputs).collect::<Vec<_>>();
+        if batch.len() < min_inputs {
+            break;
+        }
+        batches.push(batch);
+    }
+    batches
+}
+
+/// The fee saved by spending one output instead of `num_inputs` outputs at `reference_fee_per_gram`, less the fee
+/// paid to consolidate them at `fee_per_gram`. Returns `None` if the consolidation costs more than it saves.
+pub fn estimate_savings(
+    fee_calc: &Fee,
+    num_inputs: usize,
+    fee_per_gram: MicroMinotari,
+    reference_fee_per_gram: MicroMinotari,
+    features_and_scripts_size: usize,
+) -> Option<MicroMinotari> {
+    let spend_dust = fee_calc.calculate(reference_fee_per_gram, 1, num_inputs, 1, features_and_scripts_size);
+    let spend_consolidated = fee_calc.calculate(reference_fee_per_gram, 1, 1, 1, features_and_scripts_size);
+    let consolidation_fee = fee_calc.calculate(fee_per_gram, 1, num_inputs, 1, features_and_scripts_size);
+    spend_dust
+        .checked_sub(spend_consolidated)?
+        .checked_sub(consolidation_fee)
+        .filter(|savings| *savings > MicroMinotari::zero())
+}
+
+#[cfg(test)]
+mod test {
+    use tari_core::transactions::weight::TransactionWeight;
+
+    use super::*;
+
+    fn config(min: usize, max: usize, transactions: usize) -> DustConsolidationConfig {
+        DustConsolidationConfig {
+            min_inputs_per_transaction: min,
+            max_inputs_per_transaction: max,
+            max_transactions_per_run: transactions,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn it_splits_dust_into_batches() {
+        let candidates = (0..25).collect::<Vec<_>>();
+        let batches = dust_batches(candidates.clone(), &config(5, 10, 5));
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[0], (0..10).collect::<Vec<_>>());
+        assert_eq!(batches[2], (20..25).collect::<Vec<_>>());
+
+        // The last batch is dropped when it is too small
+        assert_eq!(dust_batches(candidates.clone(), &config(6, 10, 5)).len(), 2);
+        // Limited per run
+        assert_eq!(dust_batches(candidates.clone(), &config(5, 10, 1)).len(), 1);
+        // Too few outputs to be worth it
+        assert!(dust_batches(candidates[..4].to_vec(), &config(5, 10, 5)).is_empty());
+        assert!(dust_batches(vec![1], &config(0, 0, 5)).is_empty());
+    }
+
+    #[test]
+    fn it_only_consolidates_when_it_saves_fees() {
+        let fee_calc = Fee::new(TransactionWeight::latest());
+        let savings = estimate_savings(&fee_calc, 20, 1.into(), 5.into(), 0).unwrap();
+        let spend_dust = fee_calc.calculate(5.into(), 1, 20, 1, 0);
+        assert!(savings < spend_dust);
+        assert!(savings > MicroMinotari::zero());
+
+        // Consolidating at the same fee-per-gram as spending later never saves anything
+        assert!(estimate_savings(&fee_calc, 20, 5.into(), 5.into(), 0).is_none());
+        assert!(estimate_savings(&fee_calc, 1, 1.into(), 5.into(), 0).is_none());
+    }
+}
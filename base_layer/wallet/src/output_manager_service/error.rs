@@ -43,7 +43,7 @@ use thiserror::Error;
 use crate::{
     base_node_service::error::BaseNodeServiceError,
     error::WalletStorageError,
-    output_manager_service::UtxoSelectionCriteria,
+    output_manager_service::{storage::models::AccountId, UtxoSelectionCriteria},
 };
 
 #[derive(Debug, Error)]
@@ -116,6 +116,8 @@ pub enum OutputManagerError {
     NodeIdError(#[from] NodeIdError),
     #[error("Script hash does not match expected script")]
     InvalidScriptHash,
+    #[error("The recipient script can never be spent: {0}")]
+    UnspendableScript(String),
     #[error("Unsupported Covenant")]
     InvalidCovenant,
     #[error("Unsupported Output Features")]
@@ -145,6 +147,8 @@ pub enum OutputManagerError {
     InvalidArgument(String),
     #[error("Validation in progress")]
     ValidationInProgress,
+    #[error("Account {0} does not exist")]
+    AccountNotFound(AccountId),
     #[error("Invalid data: `{0}`")]
     RangeProofError(String),
 }
@@ -205,6 +209,8 @@ pub enum OutputManagerStorageError {
     AeadError(String),
     #[error("Tried to insert a script that already exists in the database")]
     DuplicateScript,
+    #[error("An account named `{0}` already exists")]
+    DuplicateAccount(String),
     #[error("Tari script error: {0}")]
     ScriptError(#[from] ScriptError),
     #[error("Binary not stored as valid hex:{0}")]
@@ -23,12 +23,14 @@
 use std::{fmt, fmt::Formatter, sync::Arc};
 
 use tari_common_types::{
+    tari_address::TariAddress,
     transaction::TxId,
     types::{Commitment, HashOutput, PublicKey},
 };
 use tari_core::{
     covenants::Covenant,
     transactions::{
+        key_manager::TariKeyId,
         tari_amount::MicroMinotari,
         transaction_components::{OutputFeatures, Transaction, TransactionOutput, WalletOutput, WalletOutputBuilder},
         transaction_protocol::{sender::TransactionSenderMessage, TransactionMetadata},
@@ -43,11 +45,12 @@ use tokio::sync::broadcast;
 use tower::Service;
 
 use crate::output_manager_service::{
+    dust_consolidation::DustConsolidation,
     error::OutputManagerError,
     service::{Balance, OutputStatusesByTxId},
     storage::{
         database::OutputBackendQuery,
-        models::{DbWalletOutput, KnownOneSidedPaymentScript, SpendingPriority},
+        models::{Account, AccountId, BalanceSnapshot, DbWalletOutput, KnownOneSidedPaymentScript, SpendingPriority},
     },
     UtxoSelectionCriteria,
 };
@@ -90,7 +93,8 @@ pub enum OutputManagerRequest {
         lock_height: Option<u64>,
     },
     CreatePayToSelfWithOutputs {
-        outputs: Vec<WalletOutputBuilder>,
+        /// Each output with the sender offset key to sign it with, or `None` to use the next sender offset key
+        outputs: Vec<(WalletOutputBuilder, Option<TariKeyId>)>,
         fee_per_gram: MicroMinotari,
         selection_criteria: UtxoSelectionCriteria,
     },
@@ -130,6 +134,21 @@ pub enum OutputManagerRequest {
     CreateClaimShaAtomicSwapTransaction(HashOutput, PublicKey, MicroMinotari),
     CreateHtlcRefundTransaction(HashOutput, MicroMinotari),
     GetOutputStatusesByTxId(TxId),
+    FreezeOutputs(Vec<Commitment>),
+    UnfreezeOutputs(Vec<Commitment>),
+    GetFrozenOutputs,
+    CreateAccount(String),
+    GetAccounts,
+    GetAccountBalance(AccountId),
+    GetAccountAddress(AccountId),
+    GetAccountTransactionIds(AccountId),
+    GetBalanceHistory {
+        from_height: u64,
+        to_height: Option<u64>,
+    },
+    ConsolidateDust,
+    CreateVaultSpendTransaction(HashOutput, MicroMinotari),
+    CreatePaymentChannelClaimTransaction(Vec<WalletOutput>, MicroMinotari),
 }
 
 impl fmt::Display for OutputManagerRequest {
@@ -220,6 +239,33 @@ impl fmt::Display for OutputManagerRequest {
             ),
 
             GetOutputStatusesByTxId(t) => write!(f, "GetOutputStatusesByTxId: {}", t),
+            FreezeOutputs(v) => write!(f, "FreezeOutputs ({:?})", v),
+            UnfreezeOutputs(v) => write!(f, "UnfreezeOutputs ({:?})", v),
+            GetFrozenOutputs => write!(f, "GetFrozenOutputs"),
+            CreateAccount(name) => write!(f, "CreateAccount ({})", name),
+            GetAccounts => write!(f, "GetAccounts"),
+            GetAccountBalance(id) => write!(f, "GetAccountBalance ({})", id),
+            GetAccountAddress(id) => write!(f, "GetAccountAddress ({})", id),
+            GetAccountTransactionIds(id) => write!(f, "GetAccountTransactionIds ({})", id),
+            GetBalanceHistory { from_height, to_height } => write!(
+                f,
+                "GetBalanceHistory (from {} to {})",
+                from_height,
+                to_height.map_or_else(|| "tip".to_string(), |h| h.to_string())
+            ),
+            ConsolidateDust => write!(f, "ConsolidateDust"),
+            CreateVaultSpendTransaction(output, fee_per_gram) => write!(
+                f,
+                "CreateVaultSpendTransaction (output hash: {}, fee_per_gram: {})",
+                output.to_hex(),
+                fee_per_gram
+            ),
+            CreatePaymentChannelClaimTransaction(outputs, fee_per_gram) => write!(
+                f,
+                "CreatePaymentChannelClaimTransaction (outputs: {}, fee_per_gram: {})",
+                outputs.len(),
+                fee_per_gram
+            ),
         }
     }
 }
@@ -258,6 +304,17 @@ pub enum OutputManagerResponse {
     ClaimHtlcTransaction((TxId, MicroMinotari, MicroMinotari, Transaction)),
     OutputStatusesByTxId(OutputStatusesByTxId),
     CoinPreview((Vec<MicroMinotari>, MicroMinotari)),
+    OutputsFrozen,
+    OutputsUnfrozen,
+    FrozenOutputs(Vec<DbWalletOutput>),
+    AccountCreated(Account),
+    Accounts(Vec<Account>),
+    AccountAddress(TariAddress),
+    AccountTransactionIds(Vec<TxId>),
+    BalanceHistory(Vec<BalanceSnapshot>),
+    DustConsolidations(Vec<DustConsolidation>),
+    VaultSpendTransaction((TxId, MicroMinotari, MicroMinotari, Transaction)),
+    PaymentChannelClaimTransaction((TxId, MicroMinotari, MicroMinotari, Transaction)),
 }
 
 pub type OutputManagerEventSender = broadcast::Sender<Arc<OutputManagerEvent>>;
@@ -270,6 +327,7 @@ pub enum OutputManagerEvent {
     TxoValidationInternalFailure(u64),
     TxoValidationCommunicationFailure(u64),
     TxoValidationAlreadyBusy(u64),
+    BalanceSnapshotRecorded(BalanceSnapshot),
 }
 
 impl fmt::Display for OutputManagerEvent {
@@ -287,6 +345,9 @@ impl fmt::Display for OutputManagerEvent {
             OutputManagerEvent::TxoValidationAlreadyBusy(tx) => {
                 write!(f, "Txo is already running, stopping {}", tx)
             },
+            OutputManagerEvent::BalanceSnapshotRecorded(snapshot) => {
+                write!(f, "BalanceSnapshotRecorded at height {}", snapshot.height)
+            },
         }
     }
 }
@@ -749,7 +810,30 @@ impl OutputManagerHandle {
         match self
             .handle
             .call(OutputManagerRequest::CreatePayToSelfWithOutputs {
-                outputs,
+                outputs: outputs.into_iter().map(|output| (output, None)).collect(),
+                fee_per_gram,
+                selection_criteria: input_selection,
+            })
+            .await??
+        {
+            OutputManagerResponse::CreatePayToSelfWithOutputs { transaction, tx_id } => Ok((tx_id, *transaction)),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Creates a transaction to self containing `output`, signed with the given sender offset key. This is needed when
+    /// the keys of the output are derived from the sender offset key.
+    pub async fn create_send_to_self_with_sender_offset_output(
+        &mut self,
+        output: WalletOutputBuilder,
+        sender_offset_key_id: TariKeyId,
+        fee_per_gram: MicroMinotari,
+        input_selection: UtxoSelectionCriteria,
+    ) -> Result<(TxId, Transaction), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::CreatePayToSelfWithOutputs {
+                outputs: vec![(output, Some(sender_offset_key_id))],
                 fee_per_gram,
                 selection_criteria: input_selection,
             })
@@ -824,4 +908,156 @@ impl OutputManagerHandle {
             _ => Err(OutputManagerError::UnexpectedApiResponse),
         }
     }
+
+    /// Freeze the given outputs so that they are not selected as inputs by any transaction until they are unfrozen
+    pub async fn freeze_outputs(&mut self, commitments: Vec<Commitment>) -> Result<(), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::FreezeOutputs(commitments))
+            .await??
+        {
+            OutputManagerResponse::OutputsFrozen => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn unfreeze_outputs(&mut self, commitments: Vec<Commitment>) -> Result<(), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::UnfreezeOutputs(commitments))
+            .await??
+        {
+            OutputManagerResponse::OutputsUnfrozen => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Sorted from lowest value to highest
+    pub async fn get_frozen_outputs(&mut self) -> Result<Vec<DbWalletOutput>, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetFrozenOutputs).await?? {
+            OutputManagerResponse::FrozenOutputs(outputs) => Ok(outputs),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn create_account(&mut self, name: String) -> Result<Account, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::CreateAccount(name)).await?? {
+            OutputManagerResponse::AccountCreated(account) => Ok(account),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Sorted by account id, starting with the default account
+    pub async fn get_accounts(&mut self) -> Result<Vec<Account>, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetAccounts).await?? {
+            OutputManagerResponse::Accounts(accounts) => Ok(accounts),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_account_balance(&mut self, account_id: AccountId) -> Result<Balance, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::GetAccountBalance(account_id))
+            .await??
+        {
+            OutputManagerResponse::Balance(b) => Ok(b),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// The address that one-sided payments to the account are sent to
+    pub async fn get_account_address(&mut self, account_id: AccountId) -> Result<TariAddress, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::GetAccountAddress(account_id))
+            .await??
+        {
+            OutputManagerResponse::AccountAddress(address) => Ok(address),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// The ids of the transactions that received or spent outputs of the account
+    pub async fn get_account_transaction_ids(
+        &mut self,
+        account_id: AccountId,
+    ) -> Result<Vec<TxId>, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::GetAccountTransactionIds(account_id))
+            .await??
+        {
+            OutputManagerResponse::AccountTransactionIds(tx_ids) => Ok(tx_ids),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// The recorded balance snapshots from `from_height` up to and including `to_height`, ordered by height. A
+    /// snapshot is only recorded when the balance changed, so the balance at a height without a snapshot is that of the
+    /// snapshot before it.
+    pub async fn get_balance_history(
+        &mut self,
+        from_height: u64,
+        to_height: Option<u64>,
+    ) -> Result<Vec<BalanceSnapshot>, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::GetBalanceHistory { from_height, to_height })
+            .await??
+        {
+            OutputManagerResponse::BalanceHistory(snapshots) => Ok(snapshots),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Creates the dust consolidation transactions of one run, if dust consolidation is enabled and the mempool
+    /// fee-per-gram is low enough. The spent outputs are encumbered, so the caller must submit or cancel every
+    /// transaction.
+    pub async fn consolidate_dust(&mut self) -> Result<Vec<DustConsolidation>, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::ConsolidateDust).await?? {
+            OutputManagerResponse::DustConsolidations(consolidations) => Ok(consolidations),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Creates a transaction that spends a vault output with its hot key and returns the funds to the wallet, as
+    /// `(tx_id, fee, amount, transaction)`
+    pub async fn create_vault_spend_transaction(
+        &mut self,
+        output_hash: HashOutput,
+        fee_per_gram: MicroMinotari,
+    ) -> Result<(TxId, MicroMinotari, MicroMinotari, Transaction), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::CreateVaultSpendTransaction(
+                output_hash,
+                fee_per_gram,
+            ))
+            .await??
+        {
+            OutputManagerResponse::VaultSpendTransaction(tx) => Ok(tx),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Creates a transaction that spends the outputs of a mined payment channel transaction into the wallet, as
+    /// `(tx_id, fee, amount, transaction)`
+    pub async fn create_payment_channel_claim_transaction(
+        &mut self,
+        outputs: Vec<WalletOutput>,
+        fee_per_gram: MicroMinotari,
+    ) -> Result<(TxId, MicroMinotari, MicroMinotari, Transaction), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::CreatePaymentChannelClaimTransaction(
+                outputs,
+                fee_per_gram,
+            ))
+            .await??
+        {
+            OutputManagerResponse::PaymentChannelClaimTransaction(tx) => Ok(tx),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
 }
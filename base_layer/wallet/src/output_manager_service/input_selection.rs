@@ -21,12 +21,21 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use std::{
+    collections::HashMap,
     fmt,
     fmt::{Display, Formatter},
+    hash::Hash,
+    str::FromStr,
 };
 
 use tari_common_types::types::Commitment;
 
+use crate::output_manager_service::storage::models::{AccountId, DbWalletOutput};
+
+/// The maximum number of search steps the branch-and-bound strategy will take before giving up on finding a changeless
+/// input set.
+const BRANCH_AND_BOUND_MAX_TRIES: usize = 100_000;
+
 #[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
 pub enum UtxoSelectionMode {
     #[default]
@@ -41,6 +50,10 @@ pub struct UtxoSelectionCriteria {
     pub ordering: UtxoSelectionOrdering,
     pub excluding: Vec<Commitment>,
     pub excluding_onesided: bool,
+    /// Only outputs belonging to this account are selected
+    pub account: AccountId,
+    /// Only outputs with at least this many confirmations are selected, 0 to include unconfirmed outputs
+    pub min_confirmations: u64,
 }
 
 impl UtxoSelectionCriteria {
@@ -60,6 +73,37 @@ impl UtxoSelectionCriteria {
         }
     }
 
+    pub fn branch_and_bound() -> Self {
+        Self {
+            filter: UtxoSelectionFilter::Standard,
+            ordering: UtxoSelectionOrdering::BranchAndBound,
+            ..Default::default()
+        }
+    }
+
+    pub fn privacy_preserving() -> Self {
+        Self {
+            filter: UtxoSelectionFilter::Standard,
+            ordering: UtxoSelectionOrdering::PrivacyPreserving,
+            ..Default::default()
+        }
+    }
+
+    pub fn minimise_fee() -> Self {
+        Self {
+            filter: UtxoSelectionFilter::Standard,
+            ordering: UtxoSelectionOrdering::MinimiseFee,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_ordering(ordering: UtxoSelectionOrdering) -> Self {
+        Self {
+            ordering,
+            ..Default::default()
+        }
+    }
+
     pub fn specific(commitments: Vec<Commitment>) -> Self {
         Self {
             filter: UtxoSelectionFilter::SpecificOutputs { commitments },
@@ -67,11 +111,20 @@ impl UtxoSelectionCriteria {
             ..Default::default()
         }
     }
+
+    pub fn with_account(mut self, account: AccountId) -> Self {
+        self.account = account;
+        self
+    }
 }
 
 impl Display for UtxoSelectionCriteria {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "filter: {}, ordering: {}", self.filter, self.ordering)
+        write!(
+            f,
+            "filter: {}, ordering: {}, account: {}, min confirmations: {}",
+            self.filter, self.ordering, self.account, self.min_confirmations
+        )
     }
 }
 
@@ -88,6 +141,30 @@ pub enum UtxoSelectionOrdering {
     SmallestFirst,
     /// A strategy that selects the largest UTXOs first. Preferred when the amount is large
     LargestFirst,
+    /// Search for a set of UTXOs that covers the amount and fee without requiring a change output. Saves the fee of
+    /// the change output and does not reveal which output is the change. Falls back to the Default ordering if no
+    /// changeless set can be found.
+    BranchAndBound,
+    /// Avoid merging UTXOs received in different transactions (and therefore likely from different counterparties).
+    /// A single UTXO that covers the amount is preferred, then all the UTXOs received in a single transaction. If
+    /// neither is possible, as few transactions as possible are merged.
+    PrivacyPreserving,
+    /// Minimise the fee paid at the current fee-per-gram. UTXOs that cost more to spend than they are worth are
+    /// ignored, a changeless set is used if one exists, otherwise the largest UTXOs are used to keep the input count
+    /// low.
+    MinimiseFee,
+}
+
+impl UtxoSelectionOrdering {
+    /// Returns true if this ordering needs the fee parameters to select UTXOs.
+    pub fn is_cost_aware(self) -> bool {
+        matches!(
+            self,
+            UtxoSelectionOrdering::BranchAndBound |
+                UtxoSelectionOrdering::PrivacyPreserving |
+                UtxoSelectionOrdering::MinimiseFee
+        )
+    }
 }
 
 impl Display for UtxoSelectionOrdering {
@@ -96,6 +173,25 @@ impl Display for UtxoSelectionOrdering {
             UtxoSelectionOrdering::SmallestFirst => write!(f, "Smallest"),
             UtxoSelectionOrdering::LargestFirst => write!(f, "Largest"),
             UtxoSelectionOrdering::Default => write!(f, "Default"),
+            UtxoSelectionOrdering::BranchAndBound => write!(f, "BranchAndBound"),
+            UtxoSelectionOrdering::PrivacyPreserving => write!(f, "PrivacyPreserving"),
+            UtxoSelectionOrdering::MinimiseFee => write!(f, "MinimiseFee"),
+        }
+    }
+}
+
+impl FromStr for UtxoSelectionOrdering {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace(['-', '_'], "").as_str() {
+            "default" => Ok(UtxoSelectionOrdering::Default),
+            "smallest" | "smallestfirst" => Ok(UtxoSelectionOrdering::SmallestFirst),
+            "largest" | "largestfirst" => Ok(UtxoSelectionOrdering::LargestFirst),
+            "bnb" | "branchandbound" => Ok(UtxoSelectionOrdering::BranchAndBound),
+            "privacy" | "privacypreserving" => Ok(UtxoSelectionOrdering::PrivacyPreserving),
+            "minfee" | "minimisefee" | "minimizefee" => Ok(UtxoSelectionOrdering::MinimiseFee),
+            _ => Err(format!("Unknown UTXO selection ordering '{}'", s)),
         }
     }
 }
@@ -126,3 +222,323 @@ impl Display for UtxoSelectionFilter {
         }
     }
 }
+
+/// The fee components, at the current fee-per-gram, used by the cost-aware selection strategies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SelectionCosts {
+    /// The fee of the transaction without any inputs and without a change output
+    pub base_fee: u64,
+    /// The fee added by each input
+    pub cost_per_input: u64,
+    /// The fee added by a change output
+    pub cost_of_change: u64,
+}
+
+/// The result of applying a cost-aware ordering to a set of candidate UTXOs
+#[derive(Debug, Clone)]
+pub(crate) struct OrderedCandidates {
+    pub outputs: Vec<DbWalletOutput>,
+    /// True if `outputs` is a set that covers the amount and fee without a change output, in which case any excess
+    /// below the cost of a change output goes to the fee.
+    pub is_changeless: bool,
+}
+
+/// Reorders (and possibly reduces) the candidate UTXOs according to a cost-aware `ordering`. Orderings that are fully
+/// handled by the database query are returned as-is.
+pub(crate) fn order_candidates(
+    ordering: UtxoSelectionOrdering,
+    candidates: Vec<DbWalletOutput>,
+    amount: u64,
+    costs: &SelectionCosts,
+) -> OrderedCandidates {
+    let values = candidates
+        .iter()
+        .map(|o| o.wallet_output.value.as_u64())
+        .collect::<Vec<_>>();
+    let (order, is_changeless) = match ordering {
+        UtxoSelectionOrdering::Default | UtxoSelectionOrdering::SmallestFirst | UtxoSelectionOrdering::LargestFirst => {
+            return OrderedCandidates {
+                outputs: candidates,
+                is_changeless: false,
+            };
+        },
+        UtxoSelectionOrdering::BranchAndBound => match branch_and_bound(&values, amount, costs) {
+            Some(selected) => (selected, true),
+            None => (default_order(&values, amount), false),
+        },
+        UtxoSelectionOrdering::PrivacyPreserving => {
+            let groups = candidates.iter().map(|o| o.received_in_tx_id).collect::<Vec<_>>();
+            (privacy_preserving_order(&values, &groups, amount, costs), false)
+        },
+        UtxoSelectionOrdering::MinimiseFee => minimise_fee_order(&values, amount, costs),
+    };
+
+    OrderedCandidates {
+        outputs: take_in_order(candidates, &order),
+        is_changeless,
+    }
+}
+
+/// Searches for a subset of `values` that pays `amount` plus the fee without needing a change output, i.e. where the
+/// excess is no more than the cost of a change output. The search is depth-first over the values sorted largest first,
+/// and keeps the subset with the least excess. Returns the indices of the selected values, or `None` if no such subset
+/// was found within the search limit.
+pub fn branch_and_bound(values: &[u64], amount: u64, costs: &SelectionCosts) -> Option<Vec<usize>> {
+    // Work with effective values, i.e. the value of an input net of the fee to spend it
+    let mut candidates = values
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| **v > costs.cost_per_input)
+        .map(|(i, v)| (i, v - costs.cost_per_input))
+        .collect::<Vec<_>>();
+    candidates.sort_by(|a, b| b.1.cmp(&a.1));
+
+    let target = amount.saturating_add(costs.base_fee);
+    let upper_bound = target.saturating_add(costs.cost_of_change);
+    // suffix_sums[i] is the total effective value of candidates[i..]
+    let mut suffix_sums = vec![0u64; candidates.len() + 1];
+    for (i, (_, value)) in candidates.iter().enumerate().rev() {
+        suffix_sums[i] = suffix_sums[i + 1].saturating_add(*value);
+    }
+    if suffix_sums[0] < target {
+        return None;
+    }
+
+    let mut best: Option<(u64, Vec<usize>)> = None;
+    let mut included = Vec::<usize>::new();
+    let mut current_value = 0u64;
+    let mut depth = 0usize;
+    for _ in 0..BRANCH_AND_BOUND_MAX_TRIES {
+        let mut backtrack = false;
+        if current_value.saturating_add(suffix_sums[depth]) < target || current_value > upper_bound {
+            backtrack = true;
+        } else if current_value >= target {
+            let excess = current_value - target;
+            if best.as_ref().map_or(true, |(best_excess, _)| excess < *best_excess) {
+                best = Some((excess, included.clone()));
+            }
+            if excess == 0 {
+                break;
+            }
+            backtrack = true;
+        }
+
+        if backtrack {
+            // Exclude the most recently included candidate and continue the search from the one after it
+            let last = match included.pop() {
+                Some(last) => last,
+                None => break,
+            };
+            current_value -= candidates[last].1;
+            depth = last + 1;
+            // Excluding a candidate and including the next one with the same value leads to the same sums, so skip
+            // over them
+            while depth < candidates.len() && candidates[depth].1 == candidates[last].1 {
+                depth += 1;
+            }
+            continue;
+        }
+
+        included.push(depth);
+        current_value += candidates[depth].1;
+        depth += 1;
+    }
+
+    best.map(|(_, selected)| selected.into_iter().map(|pos| candidates[pos].0).collect())
+}
+
+/// Orders `values` so that spending from the front merges as few groups as possible. `groups` holds the group (e.g.
+/// the transaction in which the output was received) for each value; values without a group are each treated as their
+/// own group.
+pub fn privacy_preserving_order<K: Eq + Hash + Clone>(
+    values: &[u64],
+    groups: &[Option<K>],
+    amount: u64,
+    costs: &SelectionCosts,
+) -> Vec<usize> {
+    let covers = |total: u64, num_inputs: usize| {
+        total >
+            amount
+                .saturating_add(costs.base_fee)
+                .saturating_add(costs.cost_per_input.saturating_mul(num_inputs as u64))
+                .saturating_add(costs.cost_of_change)
+    };
+
+    let mut grouped = HashMap::<K, Vec<usize>>::new();
+    let mut ungrouped = Vec::new();
+    for (i, group) in groups.iter().enumerate() {
+        match group {
+            Some(key) => grouped.entry(key.clone()).or_default().push(i),
+            None => ungrouped.push(vec![i]),
+        }
+    }
+    let mut all_groups = grouped.into_values().chain(ungrouped).collect::<Vec<_>>();
+    for group in &mut all_groups {
+        group.sort_by(|a, b| values[*b].cmp(&values[*a]));
+    }
+    let group_total = |group: &Vec<usize>| group.iter().map(|i| values[*i]).sum::<u64>();
+    // Fallback order: the largest groups first, so that as few groups as possible are merged
+    all_groups.sort_by_key(|group| std::cmp::Reverse(group_total(group)));
+
+    let mut order = Vec::with_capacity(values.len());
+    // Best case: a single output pays for everything
+    if let Some(single) = (0..values.len())
+        .filter(|i| covers(values[*i], 1))
+        .min_by_key(|i| values[*i])
+    {
+        order.push(single);
+    } else if let Some(group) = all_groups
+        .iter()
+        .filter(|group| covers(group_total(group), group.len()))
+        .min_by_key(|group| group_total(group))
+    {
+        // Next best: the smallest group that pays for everything on its own
+        order.extend(group.iter().copied());
+    } else {
+        // No other option
+    }
+    for i in all_groups.into_iter().flatten() {
+        if !order.contains(&i) {
+            order.push(i);
+        }
+    }
+    order
+}
+
+/// Orders `values` to minimise the fee paid. Values that cost more to spend than they are worth are dropped. If a
+/// changeless set exists it is used, otherwise values are ordered largest first. The returned flag is true if the
+/// order is a changeless set.
+pub fn minimise_fee_order(values: &[u64], amount: u64, costs: &SelectionCosts) -> (Vec<usize>, bool) {
+    if let Some(selected) = branch_and_bound(values, amount, costs) {
+        return (selected, true);
+    }
+    let mut order = (0..values.len())
+        .filter(|i| values[*i] > costs.cost_per_input)
+        .collect::<Vec<_>>();
+    order.sort_by(|a, b| values[*b].cmp(&values[*a]));
+    (order, false)
+}
+
+/// The Default ordering heuristic: largest first if the amount is more than the largest value, otherwise smallest first
+fn default_order(values: &[u64], amount: u64) -> Vec<usize> {
+    let mut order = (0..values.len()).collect::<Vec<_>>();
+    let max = values.iter().max().copied().unwrap_or_default();
+    if amount > max {
+        order.sort_by(|a, b| values[*b].cmp(&values[*a]));
+    } else {
+        order.sort_by(|a, b| values[*a].cmp(&values[*b]));
+    }
+    order
+}
+
+fn take_in_order<T>(items: Vec<T>, order: &[usize]) -> Vec<T> {
+    let mut slots = items.into_iter().map(Some).collect::<Vec<_>>();
+    order
+        .iter()
+        .filter_map(|i| slots.get_mut(*i).and_then(Option::take))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const COSTS: SelectionCosts = SelectionCosts {
+        base_fee: 100,
+        cost_per_input: 10,
+        cost_of_change: 50,
+    };
+
+    fn total(values: &[u64], selected: &[usize]) -> u64 {
+        selected.iter().map(|i| values[*i]).sum()
+    }
+
+    #[test]
+    fn branch_and_bound_finds_exact_match() {
+        let values = [5_000, 1_010, 3_010, 2_000, 7_000];
+        // 1_000 + 3_000 effective, plus the base fee
+        let selected = branch_and_bound(&values, 3_900, &COSTS).unwrap();
+        let fee = COSTS.base_fee + COSTS.cost_per_input * selected.len() as u64;
+        assert_eq!(total(&values, &selected), 3_900 + fee);
+        assert_eq!(selected.len(), 2);
+    }
+
+    #[test]
+    fn branch_and_bound_accepts_excess_below_cost_of_change() {
+        let values = [2_000, 9_000];
+        let selected = branch_and_bound(&values, 1_850, &COSTS).unwrap();
+        assert_eq!(selected, vec![0]);
+    }
+
+    #[test]
+    fn branch_and_bound_fails_without_changeless_solution() {
+        let values = [2_000, 9_000];
+        assert!(branch_and_bound(&values, 1_000, &COSTS).is_none());
+        assert!(branch_and_bound(&values, 20_000, &COSTS).is_none());
+        assert!(branch_and_bound(&[], 1, &COSTS).is_none());
+    }
+
+    #[test]
+    fn branch_and_bound_ignores_uneconomic_values() {
+        let values = [10, 5, 1_110];
+        let selected = branch_and_bound(&values, 1_000, &COSTS).unwrap();
+        assert_eq!(selected, vec![2]);
+    }
+
+    #[test]
+    fn privacy_preserving_prefers_single_output() {
+        let values = [1_000, 1_000, 5_000, 20_000];
+        let groups = [Some(1), Some(1), Some(2), Some(3)];
+        let order = privacy_preserving_order(&values, &groups, 4_000, &COSTS);
+        assert_eq!(order[0], 2);
+        assert_eq!(order.len(), values.len());
+    }
+
+    #[test]
+    fn privacy_preserving_prefers_single_group() {
+        let values = [3_000, 3_000, 4_000, 2_000, 1_000];
+        let groups = [Some(1), Some(1), Some(2), Some(3), None];
+        let order = privacy_preserving_order(&values, &groups, 5_000, &COSTS);
+        assert_eq!(&order[..2], &[0, 1]);
+        assert_eq!(order.len(), values.len());
+    }
+
+    #[test]
+    fn privacy_preserving_merges_largest_groups_first() {
+        let values = [1_000, 1_000, 4_000, 2_000];
+        let groups = [Some(1), Some(1), Some(2), None];
+        let order = privacy_preserving_order(&values, &groups, 7_000, &COSTS);
+        assert_eq!(order, vec![2, 0, 1, 3]);
+    }
+
+    #[test]
+    fn minimise_fee_drops_dust_and_prefers_largest() {
+        let values = [10, 2_000, 500, 9_000];
+        let (order, is_changeless) = minimise_fee_order(&values, 5_000, &COSTS);
+        assert!(!is_changeless);
+        assert_eq!(order, vec![3, 1, 2]);
+
+        let (order, is_changeless) = minimise_fee_order(&values, 1_890, &COSTS);
+        assert!(is_changeless);
+        assert_eq!(order, vec![1]);
+    }
+
+    #[test]
+    fn it_parses_orderings() {
+        for ordering in [
+            UtxoSelectionOrdering::Default,
+            UtxoSelectionOrdering::SmallestFirst,
+            UtxoSelectionOrdering::LargestFirst,
+            UtxoSelectionOrdering::BranchAndBound,
+            UtxoSelectionOrdering::PrivacyPreserving,
+            UtxoSelectionOrdering::MinimiseFee,
+        ] {
+            assert_eq!(ordering.to_string().parse::<UtxoSelectionOrdering>().unwrap(), ordering);
+        }
+        assert_eq!(
+            "branch-and-bound".parse::<UtxoSelectionOrdering>().unwrap(),
+            UtxoSelectionOrdering::BranchAndBound
+        );
+        assert!("random".parse::<UtxoSelectionOrdering>().is_err());
+    }
+}
@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 pub mod config;
+pub mod dust_consolidation;
 pub mod error;
 pub mod handle;
 
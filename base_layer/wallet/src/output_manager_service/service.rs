@@ -20,13 +20,16 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::{convert::TryInto, fmt, sync::Arc};
+use std::{convert::TryInto, fmt, sync::Arc, time::Duration};
 
+use blake2::Blake2b;
 use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use digest::consts::U32;
 use futures::{pin_mut, StreamExt};
 use log::*;
 use rand::{rngs::OsRng, RngCore};
 use tari_common_types::{
+    tari_address::TariAddress,
     transaction::TxId,
     types::{BlockHash, Commitment, HashOutput, PrivateKey, PublicKey},
 };
@@ -36,7 +39,7 @@ use tari_core::{
     consensus::ConsensusConstants,
     covenants::Covenant,
     one_sided::{shared_secret_to_output_encryption_key, stealth_address_script_spending_key},
-    proto::base_node::FetchMatchingUtxos,
+    proto::base_node::{FetchMatchingUtxos, GetMempoolFeePerGramStatsRequest},
     transactions::{
         fee::Fee,
         key_manager::{TariKeyId, TransactionKeyManagerBranch, TransactionKeyManagerInterface},
@@ -59,7 +62,7 @@ use tari_core::{
         SenderTransactionProtocol,
     },
 };
-use tari_script::{inputs, script, ExecutionStack, Opcode, TariScript};
+use tari_script::{inputs, one_sided_payment_script, script, ExecutionStack, Opcode, TariScript};
 use tari_service_framework::reply_channel;
 use tari_shutdown::ShutdownSignal;
 use tari_utilities::{hex::Hex, ByteArray};
@@ -70,6 +73,7 @@ use crate::{
     connectivity_service::WalletConnectivityInterface,
     output_manager_service::{
         config::OutputManagerServiceConfig,
+        dust_consolidation::{dust_batches, estimate_savings, DustConsolidation, DustConsolidationReport},
         error::{OutputManagerError, OutputManagerProtocolError, OutputManagerStorageError},
         handle::{
             OutputManagerEvent,
@@ -78,21 +82,33 @@ use crate::{
             OutputManagerResponse,
             RecoveredOutput,
         },
-        input_selection::UtxoSelectionCriteria,
+        input_selection::{order_candidates, SelectionCosts, UtxoSelectionCriteria},
         recovery::StandardUtxoRecoverer,
         resources::OutputManagerResources,
         storage::{
             database::{OutputBackendQuery, OutputManagerBackend, OutputManagerDatabase},
-            models::{DbWalletOutput, KnownOneSidedPaymentScript, SpendingPriority},
+            models::{
+                account_id_from_key_id,
+                Account,
+                AccountId,
+                DbWalletOutput,
+                KnownOneSidedPaymentScript,
+                SpendingPriority,
+                DEFAULT_ACCOUNT_ID,
+            },
             OutputSource,
             OutputStatus,
         },
         tasks::TxoValidationTask,
     },
+    transaction_service::vault::{recovery_path_input_data, vault_recovery_public_key},
     util::wallet_identity::WalletIdentity,
 };
 
 const LOG_TARGET: &str = "wallet::output_manager_service";
+const MAX_ACCOUNT_NAME_LENGTH: usize = 64;
+/// Dust consolidation is skipped when no base node connection is available within this time
+const DUST_CONSOLIDATION_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// This service will manage a wallet's available outputs and the key manager that produces the keys for these outputs.
 /// The service will assemble transactions to be sent from the wallets available outputs and provide keys to receive
@@ -425,7 +441,126 @@ where
                 let output_statuses_by_tx_id = self.get_output_status_by_tx_id(tx_id)?;
                 Ok(OutputManagerResponse::OutputStatusesByTxId(output_statuses_by_tx_id))
             },
+            OutputManagerRequest::FreezeOutputs(commitments) => self
+                .set_outputs_frozen(&commitments, true)
+                .map(|_| OutputManagerResponse::OutputsFrozen),
+            OutputManagerRequest::UnfreezeOutputs(commitments) => self
+                .set_outputs_frozen(&commitments, false)
+                .map(|_| OutputManagerResponse::OutputsUnfrozen),
+            OutputManagerRequest::GetFrozenOutputs => Ok(OutputManagerResponse::FrozenOutputs(
+                self.resources.db.fetch_frozen_outputs()?,
+            )),
+            OutputManagerRequest::CreateAccount(name) => self
+                .create_account(name)
+                .await
+                .map(OutputManagerResponse::AccountCreated),
+            OutputManagerRequest::GetAccounts => {
+                Ok(OutputManagerResponse::Accounts(self.resources.db.fetch_accounts()?))
+            },
+            OutputManagerRequest::GetAccountBalance(account_id) => {
+                self.get_account(account_id)?;
+                let current_tip_for_time_lock_calculation = match self.base_node_service.get_chain_metadata().await {
+                    Ok(metadata) => metadata.map(|m| m.height_of_longest_chain()),
+                    Err(_) => None,
+                };
+                let balance = self
+                    .resources
+                    .db
+                    .get_account_balance(account_id, current_tip_for_time_lock_calculation)?;
+                Ok(OutputManagerResponse::Balance(balance))
+            },
+            OutputManagerRequest::GetAccountAddress(account_id) => self
+                .get_account_address(account_id)
+                .await
+                .map(OutputManagerResponse::AccountAddress),
+            OutputManagerRequest::GetAccountTransactionIds(account_id) => {
+                self.get_account(account_id)?;
+                Ok(OutputManagerResponse::AccountTransactionIds(
+                    self.resources.db.fetch_account_tx_ids(account_id)?,
+                ))
+            },
+            OutputManagerRequest::GetBalanceHistory { from_height, to_height } => {
+                Ok(OutputManagerResponse::BalanceHistory(
+                    self.resources.db.fetch_balance_snapshots(from_height, to_height)?,
+                ))
+            },
+            OutputManagerRequest::ConsolidateDust => self
+                .consolidate_dust()
+                .await
+                .map(OutputManagerResponse::DustConsolidations),
+            OutputManagerRequest::CreateVaultSpendTransaction(output_hash, fee_per_gram) => self
+                .create_vault_spend_transaction(output_hash, fee_per_gram)
+                .await
+                .map(OutputManagerResponse::VaultSpendTransaction),
+            OutputManagerRequest::CreatePaymentChannelClaimTransaction(outputs, fee_per_gram) => self
+                .create_payment_channel_claim_transaction(outputs, fee_per_gram)
+                .await
+                .map(OutputManagerResponse::PaymentChannelClaimTransaction),
+        }
+    }
+
+    /// Creates a new account and registers the one-sided payment script of its address, so that payments to the
+    /// account are found when scanning
+    async fn create_account(&mut self, name: String) -> Result<Account, OutputManagerError> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_ACCOUNT_NAME_LENGTH {
+            return Err(OutputManagerError::InvalidArgument(format!(
+                "account name must be between 1 and {} characters",
+                MAX_ACCOUNT_NAME_LENGTH
+            )));
+        }
+        let account = self.resources.db.create_account(name)?;
+        let key_id = account.key_id();
+        let public_key = self.resources.key_manager.get_public_key_at_key_id(&key_id).await?;
+        let script = one_sided_payment_script(&public_key);
+        self.add_known_script(KnownOneSidedPaymentScript {
+            script_hash: script.as_hash::<Blake2b<U32>>()?.to_vec(),
+            script_key_id: key_id,
+            script,
+            input: ExecutionStack::default(),
+            script_lock_height: 0,
+        })?;
+        info!(target: LOG_TARGET, "Created account {} '{}'", account.id, account.name);
+        Ok(account)
+    }
+
+    fn get_account(&self, account_id: AccountId) -> Result<Account, OutputManagerError> {
+        self.resources
+            .db
+            .fetch_accounts()?
+            .into_iter()
+            .find(|a| a.id == account_id)
+            .ok_or(OutputManagerError::AccountNotFound(account_id))
+    }
+
+    /// The default account receives on the wallet address, all other accounts on the address of their account key
+    async fn get_account_address(&self, account_id: AccountId) -> Result<TariAddress, OutputManagerError> {
+        let account = self.get_account(account_id)?;
+        if account.is_default() {
+            return Ok(self.resources.wallet_identity.address.clone());
+        }
+        let public_key = self
+            .resources
+            .key_manager
+            .get_public_key_at_key_id(&account.key_id())
+            .await?;
+        Ok(TariAddress::new(public_key, self.resources.wallet_identity.network))
+    }
+
+    fn set_outputs_frozen(&self, commitments: &[Commitment], frozen: bool) -> Result<(), OutputManagerError> {
+        if commitments.is_empty() {
+            return Err(OutputManagerError::InvalidArgument(
+                "at least one output commitment must be provided".to_string(),
+            ));
         }
+        self.resources.db.set_outputs_frozen(commitments, frozen)?;
+        info!(
+            target: LOG_TARGET,
+            "{} {} output(s)",
+            if frozen { "Froze" } else { "Unfroze" },
+            commitments.len()
+        );
+        Ok(())
     }
 
     fn get_output_status_by_tx_id(&self, tx_id: TxId) -> Result<OutputStatusesByTxId, OutputManagerError> {
@@ -886,6 +1021,7 @@ where
             selection_criteria,
             fee_per_gram,
         );
+        check_recipient_script(&recipient_script)?;
         let features_and_scripts_byte_size = self
             .resources
             .consensus_constants
@@ -1056,15 +1192,15 @@ where
     #[allow(clippy::too_many_lines)]
     async fn create_pay_to_self_containing_outputs(
         &mut self,
-        outputs: Vec<WalletOutputBuilder>,
+        outputs: Vec<(WalletOutputBuilder, Option<TariKeyId>)>,
         selection_criteria: UtxoSelectionCriteria,
         fee_per_gram: MicroMinotari,
     ) -> Result<(TxId, Transaction), OutputManagerError> {
-        let total_value = outputs.iter().map(|o| o.value()).sum();
+        let total_value = outputs.iter().map(|(o, _)| o.value()).sum();
         let nop_script = script![Nop];
         let weighting = self.resources.consensus_constants.transaction_weight_params();
         let mut features_and_scripts_byte_size = 0;
-        for output in &outputs {
+        for (output, _) in &outputs {
             let (features, covenant, script) = (
                 output
                     .features()
@@ -1122,12 +1258,17 @@ where
         }
 
         let mut db_outputs = vec![];
-        for mut wallet_output in outputs {
-            let (sender_offset_key_id, _) = self
-                .resources
-                .key_manager
-                .get_next_key(&TransactionKeyManagerBranch::SenderOffset.get_branch_key())
-                .await?;
+        for (mut wallet_output, sender_offset_key_id) in outputs {
+            let sender_offset_key_id = match sender_offset_key_id {
+                Some(key_id) => key_id,
+                None => {
+                    self.resources
+                        .key_manager
+                        .get_next_key(&TransactionKeyManagerBranch::SenderOffset.get_branch_key())
+                        .await?
+                        .0
+                },
+            };
             wallet_output = wallet_output
                 .sign_as_sender_and_receiver(&self.resources.key_manager, &sender_offset_key_id)
                 .await?;
@@ -1380,6 +1521,36 @@ where
 
         trace!(target: LOG_TARGET, "We found {} UTXOs to select from", uo.len());
 
+        let cost_of_change = fee_calc.calculate(fee_per_gram, 0, 0, 1, default_features_and_scripts_size);
+        let mut is_changeless = false;
+        let uo = if selection_criteria.ordering.is_cost_aware() {
+            let costs = SelectionCosts {
+                base_fee: fee_calc
+                    .calculate(
+                        fee_per_gram,
+                        1,
+                        0,
+                        num_outputs,
+                        total_output_features_and_scripts_byte_size,
+                    )
+                    .as_u64(),
+                cost_per_input: fee_calc.calculate(fee_per_gram, 0, 1, 0, 0).as_u64(),
+                cost_of_change: cost_of_change.as_u64(),
+            };
+            let ordered = order_candidates(selection_criteria.ordering, uo, amount.as_u64(), &costs);
+            debug!(
+                target: LOG_TARGET,
+                "{} ordering selected {} candidate UTXOs (changeless: {})",
+                selection_criteria.ordering,
+                ordered.outputs.len(),
+                ordered.is_changeless
+            );
+            is_changeless = ordered.is_changeless;
+            ordered.outputs
+        } else {
+            uo
+        };
+
         let mut requires_change_output = false;
         let mut utxos_total_value = MicroMinotari::from(0);
         let mut fee_without_change = MicroMinotari::from(0);
@@ -1400,6 +1571,13 @@ where
             if utxos_total_value == amount + fee_without_change {
                 break;
             }
+            // Any excess that is not worth a change output is added to the fee by the transaction builder
+            if is_changeless &&
+                utxos_total_value > amount + fee_without_change &&
+                utxos_total_value <= amount + fee_without_change + cost_of_change
+            {
+                break;
+            }
             fee_with_change = fee_calc.calculate(
                 fee_per_gram,
                 1,
@@ -1415,12 +1593,21 @@ where
             }
         }
 
-        let perfect_utxo_selection = utxos_total_value == amount + fee_without_change;
+        let perfect_utxo_selection = utxos_total_value == amount + fee_without_change ||
+            (is_changeless &&
+                utxos_total_value > amount + fee_without_change &&
+                utxos_total_value <= amount + fee_without_change + cost_of_change);
+        if is_changeless && perfect_utxo_selection {
+            fee_without_change = utxos_total_value - amount;
+        }
         let enough_spendable = utxos_total_value > amount + fee_with_change;
 
         if !perfect_utxo_selection && !enough_spendable {
             let current_tip_for_time_lock_calculation = chain_metadata.map(|cm| cm.height_of_longest_chain());
-            let balance = self.get_balance(current_tip_for_time_lock_calculation)?;
+            let balance = self
+                .resources
+                .db
+                .get_account_balance(selection_criteria.account, current_tip_for_time_lock_calculation)?;
             let pending_incoming = balance.pending_incoming_balance;
             if utxos_total_value + pending_incoming >= amount + fee_with_change {
                 return Err(OutputManagerError::FundsPending);
@@ -2095,6 +2282,110 @@ where
         Ok((tx_id, stp.into_transaction()?, accumulated_amount + fee))
     }
 
+    /// Consolidates the smallest outputs below the dust threshold, as configured in `DustConsolidationConfig`, while
+    /// the fee-per-gram needed to get into the next block is low. Nothing is done if the consolidation would not save
+    /// fees later on.
+    async fn consolidate_dust(&mut self) -> Result<Vec<DustConsolidation>, OutputManagerError> {
+        let config = self.resources.config.dust_consolidation.clone();
+        if !config.enabled {
+            return Ok(Vec::new());
+        }
+        let tip_height = match self.base_node_service.get_chain_metadata().await {
+            Ok(Some(metadata)) => metadata.height_of_longest_chain(),
+            _ => {
+                debug!(target: LOG_TARGET, "Dust consolidation skipped, the chain tip is not known yet");
+                return Ok(Vec::new());
+            },
+        };
+        let mut client = match self
+            .resources
+            .connectivity
+            .obtain_base_node_wallet_rpc_client_timeout(DUST_CONSOLIDATION_CONNECT_TIMEOUT)
+            .await
+        {
+            Some(client) => client,
+            None => {
+                debug!(target: LOG_TARGET, "Dust consolidation skipped, not connected to a base node");
+                return Ok(Vec::new());
+            },
+        };
+        let stats = client
+            .get_mempool_fee_per_gram_stats(GetMempoolFeePerGramStatsRequest { count: 1 })
+            .await?
+            .stats;
+        // An empty mempool takes any fee
+        let fee_per_gram = MicroMinotari::from(stats.first().map(|s| s.min_fee_per_gram).unwrap_or_default().max(1));
+        if fee_per_gram > config.max_fee_per_gram {
+            debug!(
+                target: LOG_TARGET,
+                "Dust consolidation skipped, the fee-per-gram of {} is above {}", fee_per_gram, config.max_fee_per_gram
+            );
+            return Ok(Vec::new());
+        }
+
+        let mut selection_criteria = UtxoSelectionCriteria::smallest_first();
+        selection_criteria.excluding_onesided = self.resources.config.autoignore_onesided_utxos;
+        let mut candidates = self
+            .resources
+            .db
+            .fetch_unspent_outputs_for_spending(&selection_criteria, MicroMinotari::zero(), Some(tip_height))?
+            .into_iter()
+            .filter(|o| o.wallet_output.value < config.dust_threshold)
+            .collect::<Vec<_>>();
+        // The outputs are ordered by spending priority first
+        candidates.sort_by_key(|o| o.wallet_output.value);
+
+        let fee_calc = self.get_fee_calc();
+        let features_and_scripts_size = self.default_features_and_scripts_size()?;
+        let mut consolidations = Vec::new();
+        for batch in dust_batches(candidates, &config) {
+            let num_inputs = batch.len();
+            let estimated_savings = match estimate_savings(
+                &fee_calc,
+                num_inputs,
+                fee_per_gram,
+                config.reference_fee_per_gram,
+                features_and_scripts_size,
+            ) {
+                Some(savings) => savings,
+                None => {
+                    debug!(
+                        target: LOG_TARGET,
+                        "Dust consolidation of {} outputs skipped, it would not save fees", num_inputs
+                    );
+                    break;
+                },
+            };
+            let commitments = batch.into_iter().map(|o| o.commitment).collect();
+            let (tx_id, transaction, input_value) = match self.create_coin_join(commitments, fee_per_gram).await {
+                Ok(coin_join) => coin_join,
+                // The consolidations built so far encumber their inputs, so they are returned to be submitted
+                Err(e) if !consolidations.is_empty() => {
+                    warn!(
+                        target: LOG_TARGET,
+                        "Dust consolidation stopped after {} transactions: {}",
+                        consolidations.len(),
+                        e
+                    );
+                    break;
+                },
+                Err(e) => return Err(e),
+            };
+            let fee = transaction.body.get_total_fee();
+            let report = DustConsolidationReport {
+                tx_id,
+                num_inputs,
+                amount: input_value.saturating_sub(fee),
+                fee,
+                fee_per_gram,
+                estimated_savings,
+            };
+            info!(target: LOG_TARGET, "{}", report);
+            consolidations.push(DustConsolidation { transaction, report });
+        }
+        Ok(consolidations)
+    }
+
     async fn fetch_outputs_from_node(
         &mut self,
         hashes: Vec<HashOutput>,
@@ -2242,10 +2533,57 @@ where
         fee_per_gram: MicroMinotari,
     ) -> Result<(TxId, MicroMinotari, MicroMinotari, Transaction), OutputManagerError> {
         let output = self.resources.db.get_unspent_output(output_hash)?.wallet_output;
+        self.spend_outputs_to_self(
+            vec![output],
+            fee_per_gram,
+            "SHA-XTR atomic refund".to_string(),
+            OutputSource::Refund,
+        )
+        .await
+    }
 
-        let amount = output.value;
+    /// Spends a vault output with its hot key. The vault output is stored with the hot path input data and the hot
+    /// script key, so it is spent as it is.
+    pub async fn create_vault_spend_transaction(
+        &mut self,
+        output_hash: HashOutput,
+        fee_per_gram: MicroMinotari,
+    ) -> Result<(TxId, MicroMinotari, MicroMinotari, Transaction), OutputManagerError> {
+        let output = self.resources.db.get_unspent_output(output_hash)?.wallet_output;
+        self.spend_outputs_to_self(
+            vec![output],
+            fee_per_gram,
+            "Vault withdrawal".to_string(),
+            OutputSource::Standard,
+        )
+        .await
+    }
+
+    /// Spends the outputs of a mined payment channel transaction that belong to this wallet. The outputs are not in the
+    /// database, so they are spent as they are given.
+    pub async fn create_payment_channel_claim_transaction(
+        &mut self,
+        outputs: Vec<WalletOutput>,
+        fee_per_gram: MicroMinotari,
+    ) -> Result<(TxId, MicroMinotari, MicroMinotari, Transaction), OutputManagerError> {
+        self.spend_outputs_to_self(
+            outputs,
+            fee_per_gram,
+            "Payment channel claim".to_string(),
+            OutputSource::Standard,
+        )
+        .await
+    }
 
-        let message = "SHA-XTR atomic refund".to_string();
+    /// Spends outputs back into the wallet as a single change output, returning `(tx_id, fee, amount, transaction)`
+    async fn spend_outputs_to_self(
+        &mut self,
+        outputs: Vec<WalletOutput>,
+        fee_per_gram: MicroMinotari,
+        message: String,
+        source: OutputSource,
+    ) -> Result<(TxId, MicroMinotari, MicroMinotari, Transaction), OutputManagerError> {
+        let amount = outputs.iter().map(|output| output.value).sum::<MicroMinotari>();
 
         // Create builder with no recipients (other than ourselves)
         let mut builder = SenderTransactionProtocol::builder(
@@ -2257,9 +2595,10 @@ where
             .with_fee_per_gram(fee_per_gram)
             .with_message(message)
             .with_kernel_features(KernelFeatures::empty())
-            .with_prevent_fee_gt_amount(self.resources.config.prevent_fee_gt_amount)
-            .with_input(output)
-            .await?;
+            .with_prevent_fee_gt_amount(self.resources.config.prevent_fee_gt_amount);
+        for output in outputs {
+            builder.with_input(output).await?;
+        }
 
         let mut outputs = Vec::new();
 
@@ -2288,14 +2627,14 @@ where
             wallet_output,
             &self.resources.key_manager,
             None,
-            OutputSource::Refund,
+            source,
             Some(tx_id),
             None,
         )
         .await?;
         outputs.push(change_output);
 
-        trace!(target: LOG_TARGET, "Claiming HTLC refund with transaction ({}).", tx_id);
+        trace!(target: LOG_TARGET, "Spending output to self with transaction ({}).", tx_id);
 
         let fee = stp.get_fee_amount()?;
 
@@ -2367,11 +2706,15 @@ where
                                 .key_manager
                                 .get_diffie_hellman_shared_secret(&matched_key.1, &output.sender_offset_public_key)
                                 .await?;
+                            // Payments to the address of an account are credited to that account
+                            let account_id = account_id_from_key_id(&matched_key.1).unwrap_or(DEFAULT_ACCOUNT_ID);
                             scanned_outputs.push((
                                 output.clone(),
                                 OutputSource::OneSided,
                                 matched_key.1.clone(),
+                                ExecutionStack::default(),
                                 shared_secret,
+                                account_id,
                             ));
                         },
                     }
@@ -2411,11 +2754,36 @@ where
                         output.clone(),
                         OutputSource::StealthOneSided,
                         stealth_key,
+                        ExecutionStack::default(),
                         shared_secret,
+                        DEFAULT_ACCOUNT_ID,
                     ));
                 },
 
-                _ => {},
+                // ----------------------------------------------------------------------------
+                // vault with one of our keys as its recovery key, spent through the recovery path
+                _ => {
+                    let matched_key = match vault_recovery_public_key(&output.script)
+                        .and_then(|recovery_pk| known_keys.iter().find(|x| &x.0 == recovery_pk))
+                    {
+                        Some(matched_key) => matched_key,
+                        None => continue,
+                    };
+                    let shared_secret = self
+                        .resources
+                        .key_manager
+                        .get_diffie_hellman_shared_secret(&matched_key.1, &output.sender_offset_public_key)
+                        .await?;
+                    let account_id = account_id_from_key_id(&matched_key.1).unwrap_or(DEFAULT_ACCOUNT_ID);
+                    scanned_outputs.push((
+                        output.clone(),
+                        OutputSource::OneSided,
+                        matched_key.1.clone(),
+                        recovery_path_input_data(),
+                        shared_secret,
+                        account_id,
+                    ));
+                },
             }
         }
 
@@ -2425,11 +2793,18 @@ where
     // Import scanned outputs into the wallet
     async fn import_onesided_outputs(
         &self,
-        scanned_outputs: Vec<(TransactionOutput, OutputSource, TariKeyId, CommsDHKE)>,
+        scanned_outputs: Vec<(
+            TransactionOutput,
+            OutputSource,
+            TariKeyId,
+            ExecutionStack,
+            CommsDHKE,
+            AccountId,
+        )>,
     ) -> Result<Vec<RecoveredOutput>, OutputManagerError> {
         let mut rewound_outputs = Vec::with_capacity(scanned_outputs.len());
 
-        for (output, output_source, script_private_key, shared_secret) in scanned_outputs {
+        for (output, output_source, script_private_key, input_data, shared_secret, account_id) in scanned_outputs {
             let encryption_key = shared_secret_to_output_encryption_key(&shared_secret)?;
             if let Ok((committed_value, spending_key)) =
                 EncryptedData::decrypt_data(&encryption_key, &output.commitment, &output.encrypted_data)
@@ -2446,7 +2821,7 @@ where
                         spending_key_id,
                         output.features,
                         output.script,
-                        tari_script::ExecutionStack::new(vec![]),
+                        input_data,
                         script_private_key,
                         output.sender_offset_public_key,
                         output.metadata_signature,
@@ -2458,7 +2833,7 @@ where
                     );
 
                     let tx_id = TxId::new_random();
-                    let db_output = DbWalletOutput::from_wallet_output(
+                    let mut db_output = DbWalletOutput::from_wallet_output(
                         rewound_output.clone(),
                         &self.resources.key_manager,
                         None,
@@ -2467,6 +2842,7 @@ where
                         None,
                     )
                     .await?;
+                    db_output.account_id = account_id;
 
                     match self.resources.db.add_unspent_output_with_tx_id(tx_id, db_output) {
                         Ok(_) => {
@@ -2516,6 +2892,8 @@ pub struct Balance {
     pub pending_incoming_balance: MicroMinotari,
     /// The current balance of funds encumbered in pending outbound transactions that have not been confirmed
     pub pending_outgoing_balance: MicroMinotari,
+    /// The value of the unspent outputs frozen by the user, which is not part of the available balance
+    pub frozen_balance: MicroMinotari,
 }
 
 impl Balance {
@@ -2525,6 +2903,7 @@ impl Balance {
             time_locked_balance: None,
             pending_incoming_balance: Default::default(),
             pending_outgoing_balance: Default::default(),
+            frozen_balance: Default::default(),
         }
     }
 }
@@ -2537,6 +2916,7 @@ impl fmt::Display for Balance {
         }
         writeln!(f, "Pending incoming balance: {}", self.pending_incoming_balance)?;
         writeln!(f, "Pending outgoing balance: {}", self.pending_outgoing_balance)?;
+        writeln!(f, "Frozen balance: {}", self.frozen_balance)?;
         Ok(())
     }
 }
@@ -2587,3 +2967,29 @@ pub struct OutputStatusesByTxId {
     pub(crate) mined_height: Option<u64>,
     pub(crate) block_hash: Option<BlockHash>,
 }
+
+/// Refuses to send to a script that fails whatever the inputs, since the funds could never be spent
+fn check_recipient_script(script: &TariScript) -> Result<(), OutputManagerError> {
+    let analysis = script.analyse();
+    if analysis.is_standard() {
+        return Ok(());
+    }
+    if analysis.is_unspendable() {
+        let reasons = analysis
+            .failing_paths()
+            .filter_map(|p| p.failure.as_ref().map(|f| f.to_string()))
+            .collect::<Vec<_>>();
+        return Err(OutputManagerError::UnspendableScript(reasons.join("; ")));
+    }
+    debug!(
+        target: LOG_TARGET,
+        "Sending to non-standard script `{}` with {} spendable path(s), {} unreachable branch(es) and a maximum \
+         execution cost of {}{}",
+        script,
+        analysis.spendable_paths().count(),
+        analysis.unreachable_branches.len(),
+        analysis.max_cost(),
+        if analysis.is_malleable() { ", some inputs are malleable" } else { "" },
+    );
+    Ok(())
+}
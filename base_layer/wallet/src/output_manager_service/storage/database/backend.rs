@@ -13,7 +13,7 @@ use crate::output_manager_service::{
     service::Balance,
     storage::{
         database::{DbKey, DbValue, OutputBackendQuery, WriteOperation},
-        models::DbWalletOutput,
+        models::{Account, AccountId, BalanceSnapshot, DbWalletOutput},
     },
 };
 
@@ -93,8 +93,13 @@ pub trait OutputManagerBackend: Send + Sync + Clone {
     fn set_coinbase_abandoned(&self, tx_id: TxId, abandoned: bool) -> Result<(), OutputManagerStorageError>;
     /// Reinstate a cancelled inbound output
     fn reinstate_cancelled_inbound_output(&self, tx_id: TxId) -> Result<(), OutputManagerStorageError>;
-    /// Return the available, time locked, pending incoming and pending outgoing balance
-    fn get_balance(&self, tip: Option<u64>) -> Result<Balance, OutputManagerStorageError>;
+    /// Return the available, time locked, pending incoming and pending outgoing balance of a single account, or of the
+    /// whole wallet if no account is specified
+    fn get_balance(
+        &self,
+        tip: Option<u64>,
+        account_id: Option<AccountId>,
+    ) -> Result<Balance, OutputManagerStorageError>;
     /// Import unvalidated output
     fn add_unvalidated_output(&self, output: DbWalletOutput, tx_id: TxId) -> Result<(), OutputManagerStorageError>;
     fn fetch_unspent_outputs_for_spending(
@@ -105,4 +110,32 @@ pub trait OutputManagerBackend: Send + Sync + Clone {
     ) -> Result<Vec<DbWalletOutput>, OutputManagerStorageError>;
     fn fetch_outputs_by_tx_id(&self, tx_id: TxId) -> Result<Vec<DbWalletOutput>, OutputManagerStorageError>;
     fn fetch_outputs_by(&self, q: OutputBackendQuery) -> Result<Vec<DbWalletOutput>, OutputManagerStorageError>;
+    /// Freeze or unfreeze the outputs with the given commitments. Frozen outputs are never selected as inputs. If any
+    /// of the commitments is unknown no output is updated.
+    fn set_outputs_frozen(&self, commitments: &[Commitment], frozen: bool) -> Result<(), OutputManagerStorageError>;
+    /// Retrieve all outputs that have been frozen
+    fn fetch_frozen_outputs(&self) -> Result<Vec<DbWalletOutput>, OutputManagerStorageError>;
+    /// Create a new account with the next available id. Account names must be unique.
+    fn create_account(&self, name: &str) -> Result<Account, OutputManagerStorageError>;
+    /// Retrieve all accounts, ordered by id
+    fn fetch_accounts(&self) -> Result<Vec<Account>, OutputManagerStorageError>;
+    /// Retrieve the ids of the transactions that received or spent outputs of the account
+    fn fetch_account_tx_ids(&self, account_id: AccountId) -> Result<Vec<TxId>, OutputManagerStorageError>;
+    /// Store a balance snapshot, replacing any snapshot at the same height and removing the snapshots above it
+    fn insert_balance_snapshot(&self, snapshot: &BalanceSnapshot) -> Result<(), OutputManagerStorageError>;
+    /// Retrieve the snapshots from `from_height` up to and including `to_height`, ordered by height
+    fn fetch_balance_snapshots(
+        &self,
+        from_height: u64,
+        to_height: Option<u64>,
+    ) -> Result<Vec<BalanceSnapshot>, OutputManagerStorageError>;
+    /// Retrieve the snapshot with the highest height
+    fn fetch_latest_balance_snapshot(&self) -> Result<Option<BalanceSnapshot>, OutputManagerStorageError>;
+    /// Derive the confirmed balance at every height from `from_height` up to and including `to_height` at which an
+    /// output was mined or spent, ordered by height
+    fn fetch_confirmed_balance_changes(
+        &self,
+        from_height: u64,
+        to_height: u64,
+    ) -> Result<Vec<BalanceSnapshot>, OutputManagerStorageError>;
 }
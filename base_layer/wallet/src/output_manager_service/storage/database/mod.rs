@@ -27,6 +27,7 @@ use std::{
 };
 
 pub use backend::OutputManagerBackend;
+use chrono::Utc;
 use log::*;
 use tari_common_types::{
     transaction::TxId,
@@ -43,7 +44,7 @@ use crate::output_manager_service::{
     input_selection::UtxoSelectionCriteria,
     service::Balance,
     storage::{
-        models::{DbWalletOutput, KnownOneSidedPaymentScript},
+        models::{Account, AccountId, BalanceSnapshot, DbWalletOutput, KnownOneSidedPaymentScript},
         OutputStatus,
     },
 };
@@ -196,17 +197,32 @@ where T: OutputManagerBackend + 'static
         &self,
         current_tip_for_time_lock_calculation: Option<u64>,
     ) -> Result<Balance, OutputManagerStorageError> {
-        self.db.get_balance(current_tip_for_time_lock_calculation)
+        self.db.get_balance(current_tip_for_time_lock_calculation, None)
+    }
+
+    pub fn get_account_balance(
+        &self,
+        account_id: AccountId,
+        current_tip_for_time_lock_calculation: Option<u64>,
+    ) -> Result<Balance, OutputManagerStorageError> {
+        self.db
+            .get_balance(current_tip_for_time_lock_calculation, Some(account_id))
     }
 
     /// This method is called when a transaction is built to be sent. It will encumber unspent outputs against a pending
-    /// transaction in the short term.
+    /// transaction in the short term. Outputs to be received (change) belong to the same account as the outputs being
+    /// spent.
     pub fn encumber_outputs(
         &self,
         tx_id: TxId,
         outputs_to_send: Vec<DbWalletOutput>,
-        outputs_to_receive: Vec<DbWalletOutput>,
+        mut outputs_to_receive: Vec<DbWalletOutput>,
     ) -> Result<(), OutputManagerStorageError> {
+        if let Some(account_id) = outputs_to_send.first().map(|o| o.account_id) {
+            for output in &mut outputs_to_receive {
+                output.account_id = account_id;
+            }
+        }
         self.db
             .short_term_encumber_outputs(tx_id, &outputs_to_send, &outputs_to_receive)
     }
@@ -467,6 +483,66 @@ where T: OutputManagerBackend + 'static
     pub fn fetch_outputs_by(&self, q: OutputBackendQuery) -> Result<Vec<DbWalletOutput>, OutputManagerStorageError> {
         self.db.fetch_outputs_by(q)
     }
+
+    pub fn set_outputs_frozen(
+        &self,
+        commitments: &[Commitment],
+        frozen: bool,
+    ) -> Result<(), OutputManagerStorageError> {
+        self.db.set_outputs_frozen(commitments, frozen)
+    }
+
+    pub fn fetch_frozen_outputs(&self) -> Result<Vec<DbWalletOutput>, OutputManagerStorageError> {
+        self.db.fetch_frozen_outputs()
+    }
+
+    pub fn create_account(&self, name: &str) -> Result<Account, OutputManagerStorageError> {
+        self.db.create_account(name)
+    }
+
+    pub fn fetch_accounts(&self) -> Result<Vec<Account>, OutputManagerStorageError> {
+        self.db.fetch_accounts()
+    }
+
+    pub fn fetch_account_tx_ids(&self, account_id: AccountId) -> Result<Vec<TxId>, OutputManagerStorageError> {
+        self.db.fetch_account_tx_ids(account_id)
+    }
+
+    /// Records the balance history of the wallet up to chain tip `height`. Every height since the latest snapshot, or
+    /// since genesis if there is none, at which an output was mined or spent gets a snapshot of the confirmed balance
+    /// at that height. The balance at the tip is recorded as well. A snapshot is only written if the balance changed
+    /// since the snapshot before it, or if the chain reorged below the latest snapshot. Returns the snapshots that were
+    /// written, oldest first.
+    pub fn record_balance_snapshots(&self, height: u64) -> Result<Vec<BalanceSnapshot>, OutputManagerStorageError> {
+        let mut previous = self.db.fetch_latest_balance_snapshot()?;
+        let mut snapshots = Vec::new();
+        let from_height = previous.as_ref().map_or(0, |latest| latest.height + 1);
+        if from_height < height {
+            for snapshot in self.db.fetch_confirmed_balance_changes(from_height, height - 1)? {
+                if previous.as_ref().map_or(true, |p| !p.has_same_balance(&snapshot)) {
+                    previous = Some(snapshot.clone());
+                    snapshots.push(snapshot);
+                }
+            }
+        }
+        let balance = self.get_balance(Some(height))?;
+        let tip = BalanceSnapshot::new(height, &balance, Utc::now().naive_utc());
+        if previous.map_or(true, |p| p.height > height || !p.has_same_balance(&tip)) {
+            snapshots.push(tip);
+        }
+        for snapshot in &snapshots {
+            self.db.insert_balance_snapshot(snapshot)?;
+        }
+        Ok(snapshots)
+    }
+
+    pub fn fetch_balance_snapshots(
+        &self,
+        from_height: u64,
+        to_height: Option<u64>,
+    ) -> Result<Vec<BalanceSnapshot>, OutputManagerStorageError> {
+        self.db.fetch_balance_snapshots(from_height, to_height)
+    }
 }
 
 fn unexpected_result<T>(req: DbKey, res: DbValue) -> Result<T, OutputManagerStorageError> {
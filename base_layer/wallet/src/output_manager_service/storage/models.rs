@@ -29,13 +29,15 @@ use tari_common_types::{
     types::{BlockHash, Commitment, HashOutput},
 };
 use tari_core::transactions::{
-    key_manager::{TariKeyId, TransactionKeyManagerInterface},
+    key_manager::{TariKeyId, TransactionKeyManagerBranch, TransactionKeyManagerInterface},
+    tari_amount::MicroMinotari,
     transaction_components::WalletOutput,
 };
 use tari_script::{ExecutionStack, TariScript};
 
 use crate::output_manager_service::{
     error::OutputManagerStorageError,
+    service::Balance,
     storage::{OutputSource, OutputStatus},
 };
 
@@ -57,6 +59,8 @@ pub struct DbWalletOutput {
     pub source: OutputSource,
     pub received_in_tx_id: Option<TxId>,
     pub spent_in_tx_id: Option<TxId>,
+    pub frozen: bool,
+    pub account_id: AccountId,
 }
 
 impl DbWalletOutput {
@@ -84,6 +88,8 @@ impl DbWalletOutput {
             source,
             received_in_tx_id,
             spent_in_tx_id,
+            frozen: false,
+            account_id: DEFAULT_ACCOUNT_ID,
         })
     }
 }
@@ -157,3 +163,87 @@ impl PartialEq for KnownOneSidedPaymentScript {
         self.script_hash == other.script_hash
     }
 }
+
+// ---------------------------------------------------------------------------
+
+/// Identifies an account of the wallet
+pub type AccountId = u32;
+
+/// Every wallet has this account. It holds the outputs that were not received on a specific account and uses the
+/// wallet address.
+pub const DEFAULT_ACCOUNT_ID: AccountId = 0;
+
+/// A named partition of the outputs of the wallet, with its own address and balance
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub id: AccountId,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl Account {
+    pub fn is_default(&self) -> bool {
+        self.id == DEFAULT_ACCOUNT_ID
+    }
+
+    /// The key that the address of this account is derived from. Not used for the default account.
+    pub fn key_id(&self) -> TariKeyId {
+        account_key_id(self.id)
+    }
+}
+
+pub fn account_key_id(account_id: AccountId) -> TariKeyId {
+    TariKeyId::Managed {
+        branch: TransactionKeyManagerBranch::Account.get_branch_key(),
+        index: u64::from(account_id),
+    }
+}
+
+/// Returns the account that `key_id` is the address key of, if any
+pub fn account_id_from_key_id(key_id: &TariKeyId) -> Option<AccountId> {
+    match key_id {
+        TariKeyId::Managed { branch, index } if *branch == TransactionKeyManagerBranch::Account.get_branch_key() => {
+            AccountId::try_from(*index).ok()
+        },
+        _ => None,
+    }
+}
+
+// ---------------------------------------------------------------------------
+
+/// The balance of the wallet after its outputs were validated against the chain tip at `height`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceSnapshot {
+    pub height: u64,
+    pub available_balance: MicroMinotari,
+    pub time_locked_balance: MicroMinotari,
+    pub pending_incoming_balance: MicroMinotari,
+    pub pending_outgoing_balance: MicroMinotari,
+    pub created_at: NaiveDateTime,
+}
+
+impl BalanceSnapshot {
+    pub fn new(height: u64, balance: &Balance, created_at: NaiveDateTime) -> Self {
+        Self {
+            height,
+            available_balance: balance.available_balance,
+            time_locked_balance: balance.time_locked_balance.unwrap_or_default(),
+            pending_incoming_balance: balance.pending_incoming_balance,
+            pending_outgoing_balance: balance.pending_outgoing_balance,
+            created_at,
+        }
+    }
+
+    /// True if both snapshots hold the same amounts, regardless of height and time
+    pub fn has_same_balance(&self, other: &BalanceSnapshot) -> bool {
+        self.available_balance == other.available_balance &&
+            self.time_locked_balance == other.time_locked_balance &&
+            self.pending_incoming_balance == other.pending_incoming_balance &&
+            self.pending_outgoing_balance == other.pending_outgoing_balance
+    }
+
+    /// The available balance plus the funds that are still pending
+    pub fn total_balance(&self) -> MicroMinotari {
+        self.available_balance + self.pending_incoming_balance
+    }
+}
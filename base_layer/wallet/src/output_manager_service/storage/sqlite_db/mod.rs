@@ -40,6 +40,7 @@ use tari_common_types::{
 };
 use tari_core::transactions::{
     key_manager::TariKeyId,
+    tari_amount::MicroMinotari,
     transaction_components::{OutputType, TransactionOutput},
 };
 use tari_crypto::tari_utilities::{hex::Hex, ByteArray};
@@ -52,12 +53,12 @@ use crate::{
         service::Balance,
         storage::{
             database::{DbKey, DbKeyValuePair, DbValue, OutputBackendQuery, OutputManagerBackend, WriteOperation},
-            models::{DbWalletOutput, KnownOneSidedPaymentScript},
+            models::{Account, AccountId, BalanceSnapshot, DbWalletOutput, KnownOneSidedPaymentScript},
             OutputStatus,
         },
         UtxoSelectionCriteria,
     },
-    schema::{known_one_sided_payment_scripts, outputs},
+    schema::{accounts, balance_snapshots, known_one_sided_payment_scripts, outputs},
     storage::sqlite_utilities::wallet_db_connection::WalletDbConnection,
 };
 mod new_output_sql;
@@ -803,12 +804,13 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
     fn get_balance(
         &self,
         current_tip_for_time_lock_calculation: Option<u64>,
+        account_id: Option<AccountId>,
     ) -> Result<Balance, OutputManagerStorageError> {
         let start = Instant::now();
         let mut conn = self.database_connection.get_pooled_connection()?;
         let acquire_lock = start.elapsed();
 
-        let result = OutputSql::get_balance(current_tip_for_time_lock_calculation, &mut conn);
+        let result = OutputSql::get_balance(current_tip_for_time_lock_calculation, account_id, &mut conn);
         if start.elapsed().as_millis() > 0 {
             trace!(
                 target: LOG_TARGET,
@@ -1102,6 +1104,161 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
             })
             .collect())
     }
+
+    fn set_outputs_frozen(&self, commitments: &[Commitment], frozen: bool) -> Result<(), OutputManagerStorageError> {
+        let start = Instant::now();
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        let acquire_lock = start.elapsed();
+
+        let mut commitments = commitments.iter().map(|c| c.as_bytes()).collect::<Vec<_>>();
+        commitments.sort_unstable();
+        commitments.dedup();
+        conn.transaction::<_, _, _>(|conn| {
+            let count = OutputSql::update_by_commitments(
+                commitments.clone(),
+                UpdateOutput {
+                    frozen: Some(frozen),
+                    ..Default::default()
+                },
+                conn,
+            )?;
+            // Either every requested output is updated or none of them are
+            if count != commitments.len() {
+                return Err(OutputManagerStorageError::ValuesNotFound);
+            }
+
+            Ok(())
+        })?;
+        if start.elapsed().as_millis() > 0 {
+            trace!(
+                target: LOG_TARGET,
+                "sqlite profile - set_outputs_frozen ({} outputs, frozen: {}): lock {} + db_op {} = {} ms",
+                commitments.len(),
+                frozen,
+                acquire_lock.as_millis(),
+                (start.elapsed() - acquire_lock).as_millis(),
+                start.elapsed().as_millis()
+            );
+        }
+
+        Ok(())
+    }
+
+    fn fetch_frozen_outputs(&self) -> Result<Vec<DbWalletOutput>, OutputManagerStorageError> {
+        let start = Instant::now();
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        let acquire_lock = start.elapsed();
+        let outputs = OutputSql::index_frozen(&mut conn)?;
+
+        if start.elapsed().as_millis() > 0 {
+            trace!(
+                target: LOG_TARGET,
+                "sqlite profile - fetch_frozen_outputs: lock {} + db_op {} = {} ms",
+                acquire_lock.as_millis(),
+                (start.elapsed() - acquire_lock).as_millis(),
+                start.elapsed().as_millis()
+            );
+        }
+
+        outputs
+            .into_iter()
+            .map(|o| o.to_db_wallet_output())
+            .collect::<Result<Vec<_>, _>>()
+    }
+
+    fn create_account(&self, name: &str) -> Result<Account, OutputManagerStorageError> {
+        let start = Instant::now();
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        let acquire_lock = start.elapsed();
+
+        let account = conn.transaction::<_, OutputManagerStorageError, _>(|conn| {
+            if AccountSql::find_by_name(name, conn)?.is_some() {
+                return Err(OutputManagerStorageError::DuplicateAccount(name.to_string()));
+            }
+            let id = AccountSql::max_id(conn)?.map_or(0, |id| id + 1);
+            NewAccountSql {
+                id,
+                name: name.to_string(),
+            }
+            .commit(conn)?;
+            AccountSql::find(id, conn)?.to_account()
+        })?;
+        if start.elapsed().as_millis() > 0 {
+            trace!(
+                target: LOG_TARGET,
+                "sqlite profile - create_account: lock {} + db_op {} = {} ms",
+                acquire_lock.as_millis(),
+                (start.elapsed() - acquire_lock).as_millis(),
+                start.elapsed().as_millis()
+            );
+        }
+
+        Ok(account)
+    }
+
+    fn fetch_accounts(&self) -> Result<Vec<Account>, OutputManagerStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        AccountSql::index(&mut conn)?
+            .into_iter()
+            .map(AccountSql::to_account)
+            .collect()
+    }
+
+    fn fetch_account_tx_ids(&self, account_id: AccountId) -> Result<Vec<TxId>, OutputManagerStorageError> {
+        let start = Instant::now();
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        let acquire_lock = start.elapsed();
+
+        let tx_ids = OutputSql::tx_ids_by_account(account_id, &mut conn)?;
+        if start.elapsed().as_millis() > 0 {
+            trace!(
+                target: LOG_TARGET,
+                "sqlite profile - fetch_account_tx_ids: lock {} + db_op {} = {} ms",
+                acquire_lock.as_millis(),
+                (start.elapsed() - acquire_lock).as_millis(),
+                start.elapsed().as_millis()
+            );
+        }
+
+        Ok(tx_ids)
+    }
+
+    fn insert_balance_snapshot(&self, snapshot: &BalanceSnapshot) -> Result<(), OutputManagerStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        conn.transaction::<_, OutputManagerStorageError, _>(|conn| {
+            diesel::delete(balance_snapshots::table.filter(balance_snapshots::height.ge(snapshot.height as i64)))
+                .execute(conn)?;
+            BalanceSnapshotSql::from(snapshot).commit(conn)
+        })
+    }
+
+    fn fetch_balance_snapshots(
+        &self,
+        from_height: u64,
+        to_height: Option<u64>,
+    ) -> Result<Vec<BalanceSnapshot>, OutputManagerStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        BalanceSnapshotSql::index(from_height as i64, to_height.map(|h| h as i64), &mut conn)?
+            .into_iter()
+            .map(BalanceSnapshotSql::to_balance_snapshot)
+            .collect()
+    }
+
+    fn fetch_latest_balance_snapshot(&self) -> Result<Option<BalanceSnapshot>, OutputManagerStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        BalanceSnapshotSql::latest(&mut conn)?
+            .map(BalanceSnapshotSql::to_balance_snapshot)
+            .transpose()
+    }
+
+    fn fetch_confirmed_balance_changes(
+        &self,
+        from_height: u64,
+        to_height: u64,
+    ) -> Result<Vec<BalanceSnapshot>, OutputManagerStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        OutputSql::confirmed_balance_changes(from_height, to_height, &mut conn)
+    }
 }
 
 fn update_outputs_with_tx_id_and_status_to_new_status(
@@ -1139,6 +1296,7 @@ pub struct UpdateOutput {
     mined_height: Option<Option<u64>>,
     mined_in_block: Option<Option<Vec<u8>>>,
     last_validation_timestamp: Option<Option<NaiveDateTime>>,
+    frozen: Option<bool>,
 }
 
 #[derive(AsChangeset)]
@@ -1156,6 +1314,7 @@ pub struct UpdateOutputSql {
     mined_height: Option<Option<i64>>,
     mined_in_block: Option<Option<Vec<u8>>>,
     last_validation_timestamp: Option<Option<NaiveDateTime>>,
+    frozen: Option<i32>,
 }
 
 /// Map a Rust friendly UpdateOutput to the Sql data type form
@@ -1174,6 +1333,7 @@ impl From<UpdateOutput> for UpdateOutputSql {
             mined_height: u.mined_height.map(|t| t.map(|h| h as i64)),
             mined_in_block: u.mined_in_block,
             last_validation_timestamp: u.last_validation_timestamp,
+            frozen: u.frozen.map(i32::from),
         }
     }
 }
@@ -1306,12 +1466,144 @@ impl KnownOneSidedPaymentScriptSql {
     }
 }
 
+#[derive(Clone, Debug, Queryable)]
+#[diesel(table_name = accounts)]
+pub struct AccountSql {
+    pub id: i64,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = accounts)]
+pub struct NewAccountSql {
+    pub id: i64,
+    pub name: String,
+}
+
+impl NewAccountSql {
+    /// Write this struct to the database
+    pub fn commit(&self, conn: &mut SqliteConnection) -> Result<(), OutputManagerStorageError> {
+        diesel::insert_into(accounts::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+}
+
+impl AccountSql {
+    pub fn find(id: i64, conn: &mut SqliteConnection) -> Result<AccountSql, OutputManagerStorageError> {
+        Ok(accounts::table.filter(accounts::id.eq(id)).first::<AccountSql>(conn)?)
+    }
+
+    pub fn find_by_name(
+        name: &str,
+        conn: &mut SqliteConnection,
+    ) -> Result<Option<AccountSql>, OutputManagerStorageError> {
+        Ok(accounts::table
+            .filter(accounts::name.eq(name))
+            .first::<AccountSql>(conn)
+            .optional()?)
+    }
+
+    pub fn max_id(conn: &mut SqliteConnection) -> Result<Option<i64>, OutputManagerStorageError> {
+        Ok(accounts::table
+            .select(diesel::dsl::max(accounts::id))
+            .first::<Option<i64>>(conn)?)
+    }
+
+    /// Return all accounts, ordered by id
+    pub fn index(conn: &mut SqliteConnection) -> Result<Vec<AccountSql>, OutputManagerStorageError> {
+        Ok(accounts::table.order(accounts::id.asc()).load::<AccountSql>(conn)?)
+    }
+
+    pub fn to_account(self) -> Result<Account, OutputManagerStorageError> {
+        Ok(Account {
+            id: AccountId::try_from(self.id).map_err(|_| OutputManagerStorageError::ConversionError {
+                reason: format!("Invalid account id {}", self.id),
+            })?,
+            name: self.name,
+            created_at: self.created_at,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable)]
+#[diesel(table_name = balance_snapshots)]
+pub struct BalanceSnapshotSql {
+    pub height: i64,
+    pub available_balance: i64,
+    pub time_locked_balance: i64,
+    pub pending_incoming_balance: i64,
+    pub pending_outgoing_balance: i64,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<&BalanceSnapshot> for BalanceSnapshotSql {
+    fn from(snapshot: &BalanceSnapshot) -> Self {
+        Self {
+            height: snapshot.height as i64,
+            available_balance: snapshot.available_balance.as_u64() as i64,
+            time_locked_balance: snapshot.time_locked_balance.as_u64() as i64,
+            pending_incoming_balance: snapshot.pending_incoming_balance.as_u64() as i64,
+            pending_outgoing_balance: snapshot.pending_outgoing_balance.as_u64() as i64,
+            created_at: snapshot.created_at,
+        }
+    }
+}
+
+impl BalanceSnapshotSql {
+    /// Write this struct to the database
+    pub fn commit(&self, conn: &mut SqliteConnection) -> Result<(), OutputManagerStorageError> {
+        diesel::insert_into(balance_snapshots::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Return the snapshots from `from_height` up to and including `to_height`, ordered by height
+    pub fn index(
+        from_height: i64,
+        to_height: Option<i64>,
+        conn: &mut SqliteConnection,
+    ) -> Result<Vec<BalanceSnapshotSql>, OutputManagerStorageError> {
+        let mut query = balance_snapshots::table
+            .filter(balance_snapshots::height.ge(from_height))
+            .into_boxed();
+        if let Some(to_height) = to_height {
+            query = query.filter(balance_snapshots::height.le(to_height));
+        }
+        Ok(query
+            .order(balance_snapshots::height.asc())
+            .load::<BalanceSnapshotSql>(conn)?)
+    }
+
+    pub fn latest(conn: &mut SqliteConnection) -> Result<Option<BalanceSnapshotSql>, OutputManagerStorageError> {
+        Ok(balance_snapshots::table
+            .order(balance_snapshots::height.desc())
+            .first::<BalanceSnapshotSql>(conn)
+            .optional()?)
+    }
+
+    pub fn to_balance_snapshot(self) -> Result<BalanceSnapshot, OutputManagerStorageError> {
+        Ok(BalanceSnapshot {
+            height: self.height as u64,
+            available_balance: MicroMinotari::from(self.available_balance as u64),
+            time_locked_balance: MicroMinotari::from(self.time_locked_balance as u64),
+            pending_incoming_balance: MicroMinotari::from(self.pending_incoming_balance as u64),
+            pending_outgoing_balance: MicroMinotari::from(self.pending_outgoing_balance as u64),
+            created_at: self.created_at,
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
 
     use diesel::{sql_query, Connection, RunQueryDsl, SqliteConnection};
     use diesel_migrations::{EmbeddedMigrations, MigrationHarness};
     use rand::{rngs::OsRng, RngCore};
+    use tari_common_types::types::FixedHash;
     use tari_core::transactions::{
         tari_amount::MicroMinotari,
         test_helpers::{
@@ -1326,10 +1618,20 @@ mod test {
     use tari_test_utils::random;
     use tempfile::tempdir;
 
-    use crate::output_manager_service::storage::{
-        models::DbWalletOutput,
-        sqlite_db::{new_output_sql::NewOutputSql, output_sql::OutputSql, OutputStatus, UpdateOutput},
-        OutputSource,
+    use crate::{
+        output_manager_service::storage::{
+            database::{OutputManagerBackend, OutputManagerDatabase},
+            models::DbWalletOutput,
+            sqlite_db::{
+                new_output_sql::NewOutputSql,
+                output_sql::OutputSql,
+                OutputManagerSqliteDatabase,
+                OutputStatus,
+                UpdateOutput,
+            },
+            OutputSource,
+        },
+        storage::sqlite_utilities::run_migration_and_create_sqlite_connection,
     };
 
     pub async fn make_input(val: MicroMinotari, key_manager: &TestKeyManager) -> (TransactionInput, WalletOutput) {
@@ -1481,4 +1783,69 @@ mod test {
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].spending_key, outputs[1].spending_key);
     }
+
+    #[tokio::test]
+    async fn test_balance_snapshots() {
+        let db_name = format!("{}.sqlite3", random::string(8).as_str());
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join(db_name);
+        let connection = run_migration_and_create_sqlite_connection(db_path, 1).unwrap();
+        let backend = OutputManagerSqliteDatabase::new(connection);
+        let db = OutputManagerDatabase::new(backend.clone());
+
+        // One output is mined at height 3 and spent at 7, another is mined at 5
+        let key_manager = create_test_core_key_manager_with_memory_db();
+        let mut outputs = Vec::new();
+        for value in [1000, 2000] {
+            let (_, uo) = make_input(MicroMinotari::from(value), &key_manager).await;
+            let uo = DbWalletOutput::from_wallet_output(uo, &key_manager, None, OutputSource::Unknown, None, None)
+                .await
+                .unwrap();
+            db.add_unspent_output(uo.clone()).unwrap();
+            outputs.push(uo);
+        }
+        db.set_received_output_mined_height_and_status(outputs[0].hash, 3, FixedHash::zero(), 0, true, 0)
+            .unwrap();
+        db.set_received_output_mined_height_and_status(outputs[1].hash, 5, FixedHash::zero(), 1, true, 0)
+            .unwrap();
+        db.mark_output_as_spent(outputs[0].hash, 7, FixedHash::zero(), true)
+            .unwrap();
+
+        // The history is built from genesis. The balance at the tip equals that at height 7, so it is not recorded.
+        let recorded = db.record_balance_snapshots(10).unwrap();
+        let balances = recorded
+            .iter()
+            .map(|s| (s.height, s.available_balance))
+            .collect::<Vec<_>>();
+        assert_eq!(balances, vec![
+            (3, MicroMinotari::from(1000)),
+            (5, MicroMinotari::from(3000)),
+            (7, MicroMinotari::from(2000))
+        ]);
+        assert_eq!(db.fetch_balance_snapshots(0, None).unwrap(), recorded);
+        // Nothing changed, so no new snapshot is needed
+        assert!(db.record_balance_snapshots(11).unwrap().is_empty());
+
+        let mut later = recorded[2].clone();
+        later.height = 20;
+        later.available_balance = MicroMinotari::from(5000);
+        backend.insert_balance_snapshot(&later).unwrap();
+        assert_eq!(db.fetch_balance_snapshots(6, Some(20)).unwrap(), vec![
+            recorded[2].clone(),
+            later
+        ]);
+
+        // The chain reorged to below the latest snapshot, which is replaced
+        let reorged = db.record_balance_snapshots(15).unwrap();
+        assert_eq!(reorged.len(), 1);
+        assert_eq!(reorged[0].height, 15);
+        assert!(reorged[0].has_same_balance(&recorded[2]));
+        let heights = db
+            .fetch_balance_snapshots(0, None)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.height)
+            .collect::<Vec<_>>();
+        assert_eq!(heights, vec![3, 5, 7, 15]);
+    }
 }
@@ -65,6 +65,7 @@ pub struct NewOutputSql {
     pub encrypted_data: Vec<u8>,
     pub minimum_value_promise: i64,
     pub source: i32,
+    pub account_id: i64,
 }
 
 impl NewOutputSql {
@@ -112,6 +113,7 @@ impl NewOutputSql {
             encrypted_data: output.wallet_output.encrypted_data.to_byte_vec(),
             minimum_value_promise: output.wallet_output.minimum_value_promise.as_u64() as i64,
             source: output.source as i32,
+            account_id: i64::from(output.account_id),
         };
 
         Ok(output)
@@ -21,12 +21,13 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use std::{
+    collections::BTreeSet,
     convert::{TryFrom, TryInto},
     str::FromStr,
 };
 
 use borsh::BorshDeserialize;
-use chrono::NaiveDateTime;
+use chrono::{NaiveDateTime, Utc};
 use derivative::Derivative;
 use diesel::{prelude::*, sql_query, SqliteConnection};
 use log::*;
@@ -50,7 +51,7 @@ use crate::{
         service::Balance,
         storage::{
             database::{OutputBackendQuery, SortDirection},
-            models::DbWalletOutput,
+            models::{AccountId, BalanceSnapshot, DbWalletOutput},
             sqlite_db::{UpdateOutput, UpdateOutputSql},
             OutputSource,
             OutputStatus,
@@ -102,6 +103,8 @@ pub struct OutputSql {
     pub minimum_value_promise: i64,
     pub source: i32,
     pub last_validation_timestamp: Option<NaiveDateTime>,
+    pub frozen: i32,
+    pub account_id: i64,
 }
 
 impl OutputSql {
@@ -120,6 +123,32 @@ impl OutputSql {
             .load(conn)?)
     }
 
+    /// Return all outputs that have been frozen by the user
+    pub fn index_frozen(conn: &mut SqliteConnection) -> Result<Vec<OutputSql>, OutputManagerStorageError> {
+        Ok(outputs::table
+            .filter(outputs::frozen.ne(0))
+            .order_by(outputs::value.asc())
+            .load(conn)?)
+    }
+
+    /// Return the ids of all transactions that received or spent outputs of the account, in ascending order
+    #[allow(clippy::cast_sign_loss)]
+    pub fn tx_ids_by_account(
+        account_id: AccountId,
+        conn: &mut SqliteConnection,
+    ) -> Result<Vec<TxId>, OutputManagerStorageError> {
+        let rows: Vec<(Option<i64>, Option<i64>)> = outputs::table
+            .filter(outputs::account_id.eq(i64::from(account_id)))
+            .select((outputs::received_in_tx_id, outputs::spent_in_tx_id))
+            .load(conn)?;
+        let tx_ids = rows
+            .into_iter()
+            .flat_map(|(received, spent)| received.into_iter().chain(spent))
+            .map(|id| id as u64)
+            .collect::<BTreeSet<_>>();
+        Ok(tx_ids.into_iter().map(TxId::from).collect())
+    }
+
     /// Retrieves UTXOs by a set of given rules
     #[allow(clippy::cast_sign_loss)]
     pub fn fetch_outputs_by(
@@ -201,6 +230,8 @@ impl OutputSql {
         let mut query = outputs::table
             .into_boxed()
             .filter(outputs::status.eq(OutputStatus::Unspent as i32))
+            .filter(outputs::frozen.eq(0))
+            .filter(outputs::account_id.eq(i64::from(selection_criteria.account)))
             .order_by(outputs::spending_priority.desc());
 
         // NOTE: Safe mode presets `script_lock_height` and `maturity` filters for all queries
@@ -210,6 +241,15 @@ impl OutputSql {
                 .filter(outputs::maturity.le(i64_tip_height));
         };
 
+        if selection_criteria.min_confirmations > 0 {
+            // An output mined at the tip has one confirmation. Nothing is selected while the tip is unknown.
+            let max_mined_height = tip_height
+                .and_then(|h| (h + 1).checked_sub(selection_criteria.min_confirmations))
+                .and_then(|h| i64::try_from(h).ok())
+                .unwrap_or(-1);
+            query = query.filter(outputs::mined_height.le(max_mined_height));
+        }
+
         match &selection_criteria.filter {
             UtxoSelectionFilter::Standard => {
                 query = query.filter(
@@ -240,12 +280,18 @@ impl OutputSql {
 
         query = match selection_criteria.ordering {
             UtxoSelectionOrdering::SmallestFirst => query.then_order_by(outputs::value.asc()),
-            UtxoSelectionOrdering::LargestFirst => query.then_order_by(outputs::value.desc()),
+            // The cost-aware orderings are applied to the candidate set by the service, starting from largest first
+            UtxoSelectionOrdering::LargestFirst |
+            UtxoSelectionOrdering::BranchAndBound |
+            UtxoSelectionOrdering::PrivacyPreserving |
+            UtxoSelectionOrdering::MinimiseFee => query.then_order_by(outputs::value.desc()),
             UtxoSelectionOrdering::Default => {
                 // NOTE: keeping filtering by `script_lock_height` and `maturity` for all modes
                 // lets get the max value for all utxos
                 let max: Option<i64> = outputs::table
                     .filter(outputs::status.eq(OutputStatus::Unspent as i32))
+                    .filter(outputs::frozen.eq(0))
+                    .filter(outputs::account_id.eq(i64::from(selection_criteria.account)))
                     .filter(outputs::script_lock_height.le(i64_tip_height))
                     .filter(outputs::maturity.le(i64_tip_height))
                     .order(outputs::value.desc())
@@ -384,10 +430,12 @@ impl OutputSql {
             .load(conn)?)
     }
 
-    /// Return the available, time locked, pending incoming and pending outgoing balance
+    /// Return the available, time locked, pending incoming, pending outgoing and frozen balance, either of a single
+    /// account or of the whole wallet. Frozen outputs are not part of the available balance.
     #[allow(clippy::cast_possible_wrap)]
     pub fn get_balance(
         current_tip_for_time_lock_calculation: Option<u64>,
+        account_id: Option<AccountId>,
         conn: &mut SqliteConnection,
     ) -> Result<Balance, OutputManagerStorageError> {
         #[derive(QueryableByName, Clone)]
@@ -397,64 +445,97 @@ impl OutputSql {
             #[diesel(sql_type = diesel::sql_types::Text)]
             category: String,
         }
+        let account_id = account_id.map(i64::from);
         let balance_query_result = if let Some(current_tip) = current_tip_for_time_lock_calculation {
             let balance_query = sql_query(
                 "SELECT coalesce(sum(value), 0) as amount, 'available_balance' as category \
-                 FROM outputs WHERE status = ? \
+                 FROM outputs WHERE (status = ?) AND frozen = 0 AND (? IS NULL OR account_id = ?) \
+                 UNION ALL \
+                 SELECT coalesce(sum(value), 0) as amount, 'frozen_balance' as category \
+                 FROM outputs WHERE (status = ?) AND frozen != 0 AND (? IS NULL OR account_id = ?) \
                  UNION ALL \
                  SELECT coalesce(sum(value), 0) as amount, 'time_locked_balance' as category \
-                 FROM outputs WHERE status = ? AND maturity > ? OR script_lock_height > ? \
+                 FROM outputs WHERE (status = ? AND maturity > ? OR script_lock_height > ?) \
+                 AND frozen = 0 AND (? IS NULL OR account_id = ?) \
                  UNION ALL \
                  SELECT coalesce(sum(value), 0) as amount, 'pending_incoming_balance' as category \
-                 FROM outputs WHERE source != ? AND status = ? OR status = ? OR status = ? \
+                 FROM outputs WHERE (source != ? AND status = ? OR status = ? OR status = ?) \
+                 AND (? IS NULL OR account_id = ?) \
                  UNION ALL \
                  SELECT coalesce(sum(value), 0) as amount, 'pending_outgoing_balance' as category \
-                 FROM outputs WHERE status = ? OR status = ? OR status = ?",
+                 FROM outputs WHERE (status = ? OR status = ? OR status = ?) AND (? IS NULL OR account_id = ?)",
             )
                 // available_balance
                 .bind::<diesel::sql_types::Integer, _>(OutputStatus::Unspent as i32)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::BigInt>, _>(account_id)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::BigInt>, _>(account_id)
+                // frozen_balance
+                .bind::<diesel::sql_types::Integer, _>(OutputStatus::Unspent as i32)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::BigInt>, _>(account_id)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::BigInt>, _>(account_id)
                 // time_locked_balance
                 .bind::<diesel::sql_types::Integer, _>(OutputStatus::Unspent as i32)
                 .bind::<diesel::sql_types::BigInt, _>(current_tip as i64)
                 .bind::<diesel::sql_types::BigInt, _>(current_tip as i64)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::BigInt>, _>(account_id)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::BigInt>, _>(account_id)
                 // pending_incoming_balance
                 .bind::<diesel::sql_types::Integer, _>(OutputSource::Coinbase as i32)
                 .bind::<diesel::sql_types::Integer, _>(OutputStatus::EncumberedToBeReceived as i32)
                 .bind::<diesel::sql_types::Integer, _>(OutputStatus::ShortTermEncumberedToBeReceived as i32)
                 .bind::<diesel::sql_types::Integer, _>(OutputStatus::UnspentMinedUnconfirmed as i32)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::BigInt>, _>(account_id)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::BigInt>, _>(account_id)
                 // pending_outgoing_balance
                 .bind::<diesel::sql_types::Integer, _>(OutputStatus::EncumberedToBeSpent as i32)
                 .bind::<diesel::sql_types::Integer, _>(OutputStatus::ShortTermEncumberedToBeSpent as i32)
-                .bind::<diesel::sql_types::Integer, _>(OutputStatus::SpentMinedUnconfirmed as i32);
+                .bind::<diesel::sql_types::Integer, _>(OutputStatus::SpentMinedUnconfirmed as i32)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::BigInt>, _>(account_id)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::BigInt>, _>(account_id);
             balance_query.load::<BalanceQueryResult>(conn)?
         } else {
             let balance_query = sql_query(
                 "SELECT coalesce(sum(value), 0) as amount, 'available_balance' as category \
-                 FROM outputs WHERE status = ? \
+                 FROM outputs WHERE (status = ?) AND frozen = 0 AND (? IS NULL OR account_id = ?) \
+                 UNION ALL \
+                 SELECT coalesce(sum(value), 0) as amount, 'frozen_balance' as category \
+                 FROM outputs WHERE (status = ?) AND frozen != 0 AND (? IS NULL OR account_id = ?) \
                  UNION ALL \
                  SELECT coalesce(sum(value), 0) as amount, 'pending_incoming_balance' as category \
-                 FROM outputs WHERE source != ? AND status = ? OR status = ? OR status = ? \
+                 FROM outputs WHERE (source != ? AND status = ? OR status = ? OR status = ?) \
+                 AND (? IS NULL OR account_id = ?) \
                  UNION ALL \
                  SELECT coalesce(sum(value), 0) as amount, 'pending_outgoing_balance' as category \
-                 FROM outputs WHERE status = ? OR status = ? OR status = ?",
+                 FROM outputs WHERE (status = ? OR status = ? OR status = ?) AND (? IS NULL OR account_id = ?)",
             )
                 // available_balance
                 .bind::<diesel::sql_types::Integer, _>(OutputStatus::Unspent as i32)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::BigInt>, _>(account_id)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::BigInt>, _>(account_id)
+                // frozen_balance
+                .bind::<diesel::sql_types::Integer, _>(OutputStatus::Unspent as i32)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::BigInt>, _>(account_id)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::BigInt>, _>(account_id)
                 // pending_incoming_balance
                 .bind::<diesel::sql_types::Integer, _>(OutputSource::Coinbase as i32)
                 .bind::<diesel::sql_types::Integer, _>(OutputStatus::EncumberedToBeReceived as i32)
                 .bind::<diesel::sql_types::Integer, _>(OutputStatus::ShortTermEncumberedToBeReceived as i32)
                 .bind::<diesel::sql_types::Integer, _>(OutputStatus::UnspentMinedUnconfirmed as i32)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::BigInt>, _>(account_id)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::BigInt>, _>(account_id)
                 // pending_outgoing_balance
                 .bind::<diesel::sql_types::Integer, _>(OutputStatus::EncumberedToBeSpent as i32)
                 .bind::<diesel::sql_types::Integer, _>(OutputStatus::ShortTermEncumberedToBeSpent as i32)
-                .bind::<diesel::sql_types::Integer, _>(OutputStatus::SpentMinedUnconfirmed as i32);
+                .bind::<diesel::sql_types::Integer, _>(OutputStatus::SpentMinedUnconfirmed as i32)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::BigInt>, _>(account_id)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::BigInt>, _>(account_id);
             balance_query.load::<BalanceQueryResult>(conn)?
         };
         let mut available_balance = None;
         let mut time_locked_balance = Some(None);
         let mut pending_incoming_balance = None;
         let mut pending_outgoing_balance = None;
+        let mut frozen_balance = None;
         for balance in balance_query_result {
             match balance.category.as_str() {
                 "available_balance" => available_balance = Some(MicroMinotari::from(balance.amount as u64)),
@@ -465,6 +546,7 @@ impl OutputSql {
                 "pending_outgoing_balance" => {
                     pending_outgoing_balance = Some(MicroMinotari::from(balance.amount as u64))
                 },
+                "frozen_balance" => frozen_balance = Some(MicroMinotari::from(balance.amount as u64)),
                 _ => {
                     return Err(OutputManagerStorageError::UnexpectedResult(
                         "Unexpected category in balance query".to_string(),
@@ -490,9 +572,64 @@ impl OutputSql {
                     "Pending outgoing balance could not be calculated".to_string(),
                 )
             })?,
+            frozen_balance: frozen_balance.ok_or_else(|| {
+                OutputManagerStorageError::UnexpectedResult("Frozen balance could not be calculated".to_string())
+            })?,
         })
     }
 
+    /// Return the confirmed balance at every height from `from_height` up to and including `to_height` at which an
+    /// output of the wallet was mined or spent, ordered by height. The balance is derived from the mined and spent
+    /// heights of the outputs, so it has no pending amounts.
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn confirmed_balance_changes(
+        from_height: u64,
+        to_height: u64,
+        conn: &mut SqliteConnection,
+    ) -> Result<Vec<BalanceSnapshot>, OutputManagerStorageError> {
+        #[derive(QueryableByName, Clone)]
+        struct BalanceChangeQueryResult {
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            height: i64,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            available_balance: i64,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            time_locked_balance: i64,
+        }
+        let changes = sql_query(
+            "WITH heights(height) AS ( SELECT mined_height FROM outputs WHERE mined_height BETWEEN ? AND ? UNION \
+             SELECT marked_deleted_at_height FROM outputs WHERE marked_deleted_at_height BETWEEN ? AND ? ) SELECT \
+             heights.height AS height, coalesce(sum(CASE WHEN o.mined_height <= heights.height AND \
+             (o.marked_deleted_at_height IS NULL OR o.marked_deleted_at_height > heights.height) THEN o.value ELSE 0 \
+             END), 0) AS available_balance, coalesce(sum(CASE WHEN o.mined_height <= heights.height AND \
+             (o.marked_deleted_at_height IS NULL OR o.marked_deleted_at_height > heights.height) AND (o.maturity > \
+             heights.height OR o.script_lock_height > heights.height) THEN o.value ELSE 0 END), 0) AS \
+             time_locked_balance FROM heights LEFT JOIN outputs o ON o.mined_height IS NOT NULL AND o.status NOT IN \
+             (?, ?, ?, ?) GROUP BY heights.height ORDER BY heights.height",
+        )
+        .bind::<diesel::sql_types::BigInt, _>(from_height as i64)
+        .bind::<diesel::sql_types::BigInt, _>(to_height as i64)
+        .bind::<diesel::sql_types::BigInt, _>(from_height as i64)
+        .bind::<diesel::sql_types::BigInt, _>(to_height as i64)
+        .bind::<diesel::sql_types::Integer, _>(OutputStatus::Invalid as i32)
+        .bind::<diesel::sql_types::Integer, _>(OutputStatus::CancelledInbound as i32)
+        .bind::<diesel::sql_types::Integer, _>(OutputStatus::AbandonedCoinbase as i32)
+        .bind::<diesel::sql_types::Integer, _>(OutputStatus::NotStored as i32)
+        .load::<BalanceChangeQueryResult>(conn)?;
+        let created_at = Utc::now().naive_utc();
+        Ok(changes
+            .into_iter()
+            .map(|change| BalanceSnapshot {
+                height: change.height as u64,
+                available_balance: MicroMinotari::from(change.available_balance as u64),
+                time_locked_balance: MicroMinotari::from(change.time_locked_balance as u64),
+                pending_incoming_balance: MicroMinotari::zero(),
+                pending_outgoing_balance: MicroMinotari::zero(),
+                created_at,
+            })
+            .collect())
+    }
+
     pub fn find_by_commitment(
         commitment: &[u8],
         conn: &mut SqliteConnection,
@@ -785,6 +922,12 @@ impl OutputSql {
             source: self.source.try_into()?,
             received_in_tx_id: self.received_in_tx_id.map(|d| (d as u64).into()),
             spent_in_tx_id: self.spent_in_tx_id.map(|d| (d as u64).into()),
+            frozen: self.frozen != 0,
+            account_id: AccountId::try_from(self.account_id).map_err(|_| {
+                OutputManagerStorageError::ConversionError {
+                    reason: format!("Invalid account id {}", self.account_id),
+                }
+            })?,
         })
     }
 }
@@ -111,6 +111,8 @@ where
 
         self.update_invalid_outputs(&mut base_node_client).await?;
 
+        self.record_balance_snapshot(&mut base_node_client).await;
+
         self.publish_event(OutputManagerEvent::TxoValidationSuccess(self.operation_id));
         debug!(
             target: LOG_TARGET,
@@ -603,6 +605,46 @@ where
         Ok(())
     }
 
+    /// Records the balance history of the wallet up to the current chain tip. This is best effort and does not fail the
+    /// validation.
+    async fn record_balance_snapshot(&self, client: &mut BaseNodeWalletRpcClient) {
+        let tip_height = match client.get_tip_info().await {
+            Ok(tip_info) => tip_info.metadata.map(|m| m.height_of_longest_chain()),
+            Err(e) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Could not get the chain tip for the balance history: {} (Operation ID: {})", e, self.operation_id
+                );
+                return;
+            },
+        };
+        let tip_height = match tip_height {
+            Some(height) => height,
+            None => return,
+        };
+        match self.db.record_balance_snapshots(tip_height) {
+            Ok(snapshots) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Recorded {} balance snapshots up to height {} (Operation ID: {})",
+                    snapshots.len(),
+                    tip_height,
+                    self.operation_id
+                );
+                for snapshot in snapshots {
+                    self.publish_event(OutputManagerEvent::BalanceSnapshotRecorded(snapshot));
+                }
+            },
+            Err(e) => warn!(
+                target: LOG_TARGET,
+                "Could not record the balance snapshot at height {}: {} (Operation ID: {})",
+                tip_height,
+                e,
+                self.operation_id
+            ),
+        }
+    }
+
     fn publish_event(&self, event: OutputManagerEvent) {
         if let Err(e) = self.event_publisher.send(Arc::new(event)) {
             debug!(
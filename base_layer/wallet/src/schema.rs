@@ -1,5 +1,24 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    accounts (id) {
+        id -> BigInt,
+        name -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    balance_snapshots (height) {
+        height -> BigInt,
+        available_balance -> BigInt,
+        time_locked_balance -> BigInt,
+        pending_incoming_balance -> BigInt,
+        pending_outgoing_balance -> BigInt,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     burnt_proofs (id) {
         id -> Integer,
@@ -120,6 +139,54 @@ diesel::table! {
         minimum_value_promise -> BigInt,
         source -> Integer,
         last_validation_timestamp -> Nullable<Timestamp>,
+        frozen -> Integer,
+        account_id -> BigInt,
+    }
+}
+
+diesel::table! {
+    payment_channels (id) {
+        id -> BigInt,
+        role -> Integer,
+        state -> Integer,
+        counterparty -> Text,
+        capacity -> BigInt,
+        channel_data -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    payment_request_payments (tx_id) {
+        tx_id -> BigInt,
+        reference -> Text,
+        amount -> BigInt,
+        received_at -> Timestamp,
+        inferred -> Integer,
+    }
+}
+
+diesel::table! {
+    payment_requests (reference) {
+        reference -> Text,
+        address -> Binary,
+        amount -> BigInt,
+        memo -> Text,
+        created_at -> Timestamp,
+        expires_at -> Nullable<Timestamp>,
+        status -> Integer,
+    }
+}
+
+diesel::table! {
+    recovery_segments (start_height) {
+        start_height -> BigInt,
+        end_height -> BigInt,
+        end_header_hash -> Binary,
+        next_height -> BigInt,
+        num_outputs -> BigInt,
+        amount -> BigInt,
     }
 }
 
@@ -133,6 +200,67 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    scheduled_payments (id) {
+        id -> BigInt,
+        destination -> Binary,
+        amount -> BigInt,
+        fee_per_gram -> BigInt,
+        message -> Text,
+        one_sided -> Integer,
+        start_at -> Nullable<Timestamp>,
+        start_height -> Nullable<BigInt>,
+        interval_secs -> Nullable<BigInt>,
+        max_runs -> Nullable<BigInt>,
+        next_run_at -> Nullable<Timestamp>,
+        run_count -> BigInt,
+        attempts -> Integer,
+        status -> Integer,
+        last_tx_id -> Nullable<BigInt>,
+        last_error -> Nullable<Text>,
+        created_at -> Timestamp,
+        in_flight_tx_id -> Nullable<BigInt>,
+    }
+}
+
+diesel::table! {
+    spending_decisions (id) {
+        id -> BigInt,
+        kind -> Integer,
+        destination -> Nullable<Binary>,
+        amount -> BigInt,
+        decision -> Integer,
+        reason -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    transaction_labels (id) {
+        id -> Integer,
+        tx_id -> Nullable<BigInt>,
+        commitment -> Nullable<Binary>,
+        kind -> Integer,
+        value -> Text,
+    }
+}
+
+diesel::table! {
+    vaults (id) {
+        id -> BigInt,
+        tx_id -> BigInt,
+        commitment -> Binary,
+        output_hash -> Binary,
+        amount -> BigInt,
+        hot_public_key -> Binary,
+        recovery_public_key -> Binary,
+        unlock_height -> BigInt,
+        status -> Integer,
+        spend_tx_id -> Nullable<BigInt>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     wallet_settings (key) {
         key -> Text,
@@ -141,6 +269,8 @@ diesel::table! {
 }
 
 diesel::allow_tables_to_appear_in_same_query!(
+    accounts,
+    balance_snapshots,
     burnt_proofs,
     client_key_values,
     completed_transactions,
@@ -148,6 +278,14 @@ diesel::allow_tables_to_appear_in_same_query!(
     known_one_sided_payment_scripts,
     outbound_transactions,
     outputs,
+    payment_channels,
+    payment_request_payments,
+    payment_requests,
+    recovery_segments,
     scanned_blocks,
+    scheduled_payments,
+    spending_decisions,
+    transaction_labels,
+    vaults,
     wallet_settings,
 );
@@ -22,6 +22,7 @@
 
 use std::{
     fmt::{Display, Error, Formatter},
+    path::Path,
     sync::Arc,
 };
 
@@ -36,7 +37,10 @@ use tari_comms::{
 use tari_key_manager::cipher_seed::CipherSeed;
 use tari_utilities::SafePassword;
 
-use crate::{error::WalletStorageError, utxo_scanner_service::service::ScannedBlock};
+use crate::{
+    error::WalletStorageError,
+    utxo_scanner_service::service::{RecoverySegment, ScannedBlock},
+};
 
 const LOG_TARGET: &str = "wallet::database";
 
@@ -60,9 +64,18 @@ pub trait WalletBackend: Send + Sync + Clone {
         exclude_recovered: bool,
     ) -> Result<(), WalletStorageError>;
 
+    /// Fetch the checkpointed height segments of an interrupted parallel recovery scan
+    fn get_recovery_segments(&self) -> Result<Vec<RecoverySegment>, WalletStorageError>;
+    /// Insert or update the checkpoint of a recovery segment
+    fn save_recovery_segment(&self, segment: RecoverySegment) -> Result<(), WalletStorageError>;
+    fn clear_recovery_segments(&self) -> Result<(), WalletStorageError>;
+
     /// Change the passphrase used to encrypt the database
     fn change_passphrase(&self, existing: &SafePassword, new: &SafePassword) -> Result<(), WalletStorageError>;
 
+    /// Write an encrypted backup of the entire wallet state to `backup_path`, protected by `passphrase`
+    fn create_backup(&self, backup_path: &Path, passphrase: &SafePassword) -> Result<(), WalletStorageError>;
+
     fn create_burnt_proof(
         &self,
         id: u32,
@@ -165,6 +178,10 @@ where T: WalletBackend + 'static
         Ok(())
     }
 
+    pub fn create_backup(&self, backup_path: &Path, passphrase: &SafePassword) -> Result<(), WalletStorageError> {
+        self.db.create_backup(backup_path, passphrase)
+    }
+
     pub fn get_master_seed(&self) -> Result<Option<CipherSeed>, WalletStorageError> {
         let c = match self.db.fetch(&DbKey::MasterSeed) {
             Ok(None) => Ok(None),
@@ -363,6 +380,21 @@ where T: WalletBackend + 'static
         Ok(())
     }
 
+    pub fn get_recovery_segments(&self) -> Result<Vec<RecoverySegment>, WalletStorageError> {
+        let result = self.db.get_recovery_segments()?;
+        Ok(result)
+    }
+
+    pub fn save_recovery_segment(&self, segment: RecoverySegment) -> Result<(), WalletStorageError> {
+        self.db.save_recovery_segment(segment)?;
+        Ok(())
+    }
+
+    pub fn clear_recovery_segments(&self) -> Result<(), WalletStorageError> {
+        self.db.clear_recovery_segments()?;
+        Ok(())
+    }
+
     pub fn create_burnt_proof(
         &self,
         id: u32,
@@ -0,0 +1,277 @@
+// Copyright 2023. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Encrypted wallet backups.
+//!
+//! A backup is a consistent snapshot of the entire wallet database - outputs and their key ids, transactions,
+//! contacts, key manager state and wallet settings - encrypted with a key derived from a backup passphrase. The file
+//! layout is:
+//!
+//! `magic (8) | backup version (1) | argon2 version (1) | salt length (1) | salt | nonce | ciphertext | tag`
+//!
+//! The header is included in the authenticated data, so it cannot be altered without the decryption failing.
+
+use std::{fs, mem::size_of, path::Path, str::from_utf8};
+
+use argon2::password_hash::{rand_core::OsRng, SaltString};
+use blake2::Blake2b;
+use chacha20poly1305::{Key, KeyInit, XChaCha20Poly1305};
+use diesel::{sql_query, sql_types::Text, RunQueryDsl};
+use digest::{consts::U32, generic_array::GenericArray, FixedOutput};
+use log::*;
+use tari_common_sqlite::sqlite_connection_pool::PooledDbConnection;
+use tari_common_types::encryption::{decrypt_bytes_integral_nonce, encrypt_bytes_integral_nonce};
+use tari_crypto::{hash_domain, hashing::DomainSeparatedHasher};
+use tari_utilities::{hidden_type, safe_array::SafeArray, Hidden, SafePassword};
+
+use crate::{
+    error::WalletStorageError,
+    storage::{sqlite_db::wallet::Argon2Parameters, sqlite_utilities::wallet_db_connection::WalletDbConnection},
+};
+
+const LOG_TARGET: &str = "wallet::storage::backup";
+
+/// Identifies a file as a wallet backup
+const BACKUP_MAGIC: &[u8; 8] = b"TARIWBAK";
+/// The current version of the backup file format
+pub const BACKUP_VERSION: u8 = 1;
+// Authenticated data prefix for backup encryption; the file header is appended to it
+const BACKUP_AAD_PREFIX: &[u8] = b"wallet_backup_encryption";
+// Every SQLite database file starts with this string
+const SQLITE_FILE_HEADER: &[u8] = b"SQLite format 3\0";
+
+// The key produced from the backup passphrase, used to derive the backup encryption key
+hidden_type!(BackupDerivationKey, SafeArray<u8, { size_of::<Key>() }>);
+
+// The `XChaCha20-Poly1305` key used to encrypt the backup
+hidden_type!(BackupEncryptionKey, SafeArray<u8, { size_of::<Key>() }>);
+
+hash_domain!(BackupKeyDomain, "com.tari.base_layer.wallet.backup_key", 0);
+
+/// Writes an encrypted backup of the wallet database behind `connection` to `backup_path`. The database stays
+/// usable while the backup is taken.
+pub fn create_backup(
+    connection: &WalletDbConnection,
+    backup_path: &Path,
+    passphrase: &SafePassword,
+) -> Result<(), WalletStorageError> {
+    // Take the snapshot next to the backup, so the unencrypted copy never leaves the destination file system
+    let snapshot_dir = match backup_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => tempfile::tempdir_in(parent)?,
+        _ => tempfile::tempdir_in(".")?,
+    };
+    let snapshot_path = snapshot_dir.path().join("wallet_snapshot.sqlite3");
+    let snapshot_path_str = snapshot_path.to_str().ok_or(WalletStorageError::InvalidUnicodePath)?;
+
+    let mut conn = connection.get_pooled_connection()?;
+    sql_query("VACUUM INTO ?")
+        .bind::<Text, _>(snapshot_path_str)
+        .execute(&mut conn)?;
+    drop(conn);
+
+    let snapshot = Hidden::hide(fs::read(&snapshot_path)?);
+    let backup = encrypt_backup(snapshot, passphrase)?;
+    fs::write(backup_path, backup)?;
+
+    info!(
+        target: LOG_TARGET,
+        "Wallet backup written to '{}'",
+        backup_path.display()
+    );
+    Ok(())
+}
+
+/// Decrypts the backup at `backup_path` and writes the wallet database it contains to `db_path`. An existing
+/// database is never overwritten. Pending migrations are applied when the restored database is next opened.
+pub fn restore_backup(backup_path: &Path, db_path: &Path, passphrase: &SafePassword) -> Result<(), WalletStorageError> {
+    if db_path.exists() {
+        return Err(WalletStorageError::BackupRestoreTargetExists);
+    }
+
+    let backup = fs::read(backup_path)?;
+    let snapshot = decrypt_backup(&backup, passphrase)?;
+    if !snapshot.reveal().starts_with(SQLITE_FILE_HEADER) {
+        return Err(WalletStorageError::InvalidBackupFile(
+            "The backup does not contain a wallet database".to_string(),
+        ));
+    }
+
+    if let Some(parent) = db_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(db_path, snapshot.reveal())?;
+
+    info!(
+        target: LOG_TARGET,
+        "Wallet backup '{}' restored to '{}'",
+        backup_path.display(),
+        db_path.display()
+    );
+    Ok(())
+}
+
+/// Encrypts `plaintext` into the backup file format, using the most recent backup and key derivation versions
+pub fn encrypt_backup(plaintext: Hidden<Vec<u8>>, passphrase: &SafePassword) -> Result<Vec<u8>, WalletStorageError> {
+    let argon2_params = Argon2Parameters::from_version(None)?;
+    let salt = SaltString::generate(&mut OsRng).to_string();
+    let salt_len = u8::try_from(salt.len())
+        .map_err(|_| WalletStorageError::ConversionError("Backup salt is too long".to_string()))?;
+
+    let mut header = BACKUP_MAGIC.to_vec();
+    header.push(BACKUP_VERSION);
+    header.push(argon2_params.id());
+    header.push(salt_len);
+    header.extend_from_slice(salt.as_bytes());
+
+    let cipher = derive_backup_cipher(passphrase, &argon2_params, &salt)?;
+    let ciphertext =
+        encrypt_bytes_integral_nonce(&cipher, backup_aad(&header), plaintext).map_err(WalletStorageError::AeadError)?;
+
+    header.extend(ciphertext);
+    Ok(header)
+}
+
+/// Decrypts data in the backup file format. A wrong passphrase and a modified file cannot be told apart and are both
+/// reported as [WalletStorageError::InvalidPassphrase].
+pub fn decrypt_backup(backup: &[u8], passphrase: &SafePassword) -> Result<Hidden<Vec<u8>>, WalletStorageError> {
+    let fixed_len = BACKUP_MAGIC.len() + 3;
+    if backup.len() < fixed_len || !backup.starts_with(BACKUP_MAGIC) {
+        return Err(WalletStorageError::InvalidBackupFile(
+            "The file is not a wallet backup".to_string(),
+        ));
+    }
+    let version = backup[BACKUP_MAGIC.len()];
+    if version != BACKUP_VERSION {
+        return Err(WalletStorageError::UnsupportedBackupVersion(version));
+    }
+    let argon2_params = Argon2Parameters::from_version(Some(backup[BACKUP_MAGIC.len() + 1]))?;
+    let header_len = fixed_len + usize::from(backup[BACKUP_MAGIC.len() + 2]);
+    if backup.len() < header_len {
+        return Err(WalletStorageError::InvalidBackupFile(
+            "The backup is truncated".to_string(),
+        ));
+    }
+    let (header, ciphertext) = backup.split_at(header_len);
+    let salt = from_utf8(&header[fixed_len..])
+        .map_err(|_| WalletStorageError::InvalidBackupFile("The backup salt is not valid".to_string()))?;
+
+    let cipher = derive_backup_cipher(passphrase, &argon2_params, salt)?;
+    decrypt_bytes_integral_nonce(&cipher, backup_aad(header), ciphertext)
+        .map(Hidden::hide)
+        .map_err(|_| WalletStorageError::InvalidPassphrase)
+}
+
+fn backup_aad(header: &[u8]) -> Vec<u8> {
+    let mut aad = BACKUP_AAD_PREFIX.to_vec();
+    aad.extend_from_slice(header);
+    aad
+}
+
+fn derive_backup_cipher(
+    passphrase: &SafePassword,
+    argon2_params: &Argon2Parameters,
+    salt: &str,
+) -> Result<XChaCha20Poly1305, WalletStorageError> {
+    let mut derivation_key = BackupDerivationKey::from(SafeArray::default());
+    argon2_params.hash_password_into(passphrase, salt, derivation_key.reveal_mut())?;
+
+    let mut key = BackupEncryptionKey::from(SafeArray::default());
+    DomainSeparatedHasher::<Blake2b<U32>, BackupKeyDomain>::new()
+        .chain(derivation_key.reveal())
+        .finalize_into(GenericArray::from_mut_slice(key.reveal_mut()));
+
+    Ok(XChaCha20Poly1305::new(Key::from_slice(key.reveal())))
+}
+
+#[cfg(test)]
+mod test {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::storage::{
+        database::{DbKey, DbKeyValuePair, DbValue, WalletBackend, WriteOperation},
+        sqlite_db::wallet::WalletSqliteDatabase,
+        sqlite_utilities::run_migration_and_create_sqlite_connection,
+    };
+
+    #[test]
+    fn it_round_trips_backup_data() {
+        let passphrase = SafePassword::from("backup passphrase");
+        let backup = encrypt_backup(Hidden::hide(b"wallet state".to_vec()), &passphrase).unwrap();
+        assert!(backup.starts_with(BACKUP_MAGIC));
+
+        let plaintext = decrypt_backup(&backup, &passphrase).unwrap();
+        assert_eq!(plaintext.reveal(), b"wallet state");
+
+        // The wrong passphrase fails
+        let err = decrypt_backup(&backup, &SafePassword::from("wrong")).unwrap_err();
+        assert!(matches!(err, WalletStorageError::InvalidPassphrase));
+
+        // The header is authenticated
+        let mut tampered = backup.clone();
+        tampered[BACKUP_MAGIC.len() + 3] ^= 1;
+        assert!(decrypt_backup(&tampered, &passphrase).is_err());
+
+        // Unknown versions and other files are rejected
+        let mut future = backup;
+        future[BACKUP_MAGIC.len()] = BACKUP_VERSION + 1;
+        let err = decrypt_backup(&future, &passphrase).unwrap_err();
+        assert!(matches!(err, WalletStorageError::UnsupportedBackupVersion(_)));
+        let err = decrypt_backup(b"not a backup", &passphrase).unwrap_err();
+        assert!(matches!(err, WalletStorageError::InvalidBackupFile(_)));
+    }
+
+    #[test]
+    fn it_restores_a_wallet_database() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("wallet.sqlite3");
+        let backup_path = temp_dir.path().join("wallet.backup");
+        let restored_path = temp_dir.path().join("restored").join("wallet.sqlite3");
+        let passphrase = SafePassword::from("backup passphrase");
+
+        let connection = run_migration_and_create_sqlite_connection(&db_path, 4).unwrap();
+        let db = WalletSqliteDatabase::new(connection.clone(), "wallet passphrase".to_string().into()).unwrap();
+        db.write(WriteOperation::Insert(DbKeyValuePair::ClientKeyValue(
+            "label".to_string(),
+            "savings".to_string(),
+        )))
+        .unwrap();
+
+        create_backup(&connection, &backup_path, &passphrase).unwrap();
+        assert!(matches!(
+            restore_backup(&backup_path, &db_path, &passphrase),
+            Err(WalletStorageError::BackupRestoreTargetExists)
+        ));
+        assert!(matches!(
+            restore_backup(&backup_path, &restored_path, &SafePassword::from("wrong")),
+            Err(WalletStorageError::InvalidPassphrase)
+        ));
+        restore_backup(&backup_path, &restored_path, &passphrase).unwrap();
+
+        let restored_connection = run_migration_and_create_sqlite_connection(&restored_path, 4).unwrap();
+        let restored = WalletSqliteDatabase::new(restored_connection, "wallet passphrase".to_string().into()).unwrap();
+        match restored.fetch(&DbKey::ClientKey("label".to_string())).unwrap() {
+            Some(DbValue::ClientValue(value)) => assert_eq!(value, "savings"),
+            _ => panic!("The restored wallet should contain the client value"),
+        }
+    }
+}
@@ -0,0 +1,90 @@
+// Copyright 2023. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::convert::TryFrom;
+
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, SqliteConnection};
+use tari_common_types::types::FixedHash;
+use tari_core::transactions::tari_amount::MicroMinotari;
+use tari_utilities::ByteArray;
+
+use crate::{error::WalletStorageError, schema::recovery_segments, utxo_scanner_service::service::RecoverySegment};
+
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[diesel(table_name = recovery_segments)]
+pub struct RecoverySegmentSql {
+    start_height: i64,
+    end_height: i64,
+    end_header_hash: Vec<u8>,
+    next_height: i64,
+    num_outputs: i64,
+    amount: i64,
+}
+
+impl RecoverySegmentSql {
+    pub fn index(conn: &mut SqliteConnection) -> Result<Vec<RecoverySegmentSql>, WalletStorageError> {
+        Ok(recovery_segments::table
+            .order(recovery_segments::start_height.asc())
+            .load::<RecoverySegmentSql>(conn)?)
+    }
+
+    /// Insert the segment, replacing the checkpoint of an existing segment with the same start height
+    pub fn commit(&self, conn: &mut SqliteConnection) -> Result<(), WalletStorageError> {
+        diesel::replace_into(recovery_segments::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn clear_all(conn: &mut SqliteConnection) -> Result<(), WalletStorageError> {
+        diesel::delete(recovery_segments::table).execute(conn)?;
+        Ok(())
+    }
+}
+
+impl From<RecoverySegment> for RecoverySegmentSql {
+    fn from(segment: RecoverySegment) -> Self {
+        Self {
+            start_height: segment.start_height as i64,
+            end_height: segment.end_height as i64,
+            end_header_hash: segment.end_header_hash.to_vec(),
+            next_height: segment.next_height as i64,
+            num_outputs: segment.num_outputs as i64,
+            amount: segment.amount.as_u64() as i64,
+        }
+    }
+}
+
+impl TryFrom<RecoverySegmentSql> for RecoverySegment {
+    type Error = String;
+
+    fn try_from(segment: RecoverySegmentSql) -> Result<Self, Self::Error> {
+        Ok(Self {
+            start_height: segment.start_height as u64,
+            end_height: segment.end_height as u64,
+            end_header_hash: FixedHash::try_from(segment.end_header_hash).map_err(|err| err.to_string())?,
+            next_height: segment.next_height as u64,
+            num_outputs: segment.num_outputs as u64,
+            amount: MicroMinotari::from(segment.amount as u64),
+        })
+    }
+}
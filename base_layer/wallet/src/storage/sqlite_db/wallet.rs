@@ -23,6 +23,7 @@
 use std::{
     convert::TryFrom,
     mem::size_of,
+    path::Path,
     str::{from_utf8, FromStr},
     sync::{Arc, RwLock},
 };
@@ -66,10 +67,10 @@ use crate::{
     schema::{burnt_proofs, client_key_values, wallet_settings},
     storage::{
         database::{DbKey, DbKeyValuePair, DbValue, WalletBackend, WriteOperation},
-        sqlite_db::scanned_blocks::ScannedBlockSql,
+        sqlite_db::{backup, recovery_segments::RecoverySegmentSql, scanned_blocks::ScannedBlockSql},
         sqlite_utilities::wallet_db_connection::WalletDbConnection,
     },
-    utxo_scanner_service::service::ScannedBlock,
+    utxo_scanner_service::service::{RecoverySegment, ScannedBlock},
 };
 
 const LOG_TARGET: &str = "wallet::storage::wallet";
@@ -122,6 +123,23 @@ impl Argon2Parameters {
             Some(id) => Err(WalletStorageError::BadEncryptionVersion(id.to_string())),
         }
     }
+
+    /// The version identifier of these parameters
+    pub(crate) fn id(&self) -> u8 {
+        self.id
+    }
+
+    /// Hash a passphrase and salt into `output` using these parameters
+    pub(crate) fn hash_password_into(
+        &self,
+        passphrase: &SafePassword,
+        salt: &str,
+        output: &mut [u8],
+    ) -> Result<(), WalletStorageError> {
+        argon2::Argon2::new(self.algorithm, self.version, self.params.clone())
+            .hash_password_into(passphrase.reveal(), salt.as_bytes(), output)
+            .map_err(|e| WalletStorageError::AeadError(e.to_string()))
+    }
 }
 
 /// A structure to hold encryption-related database field data, to make atomic operations cleaner
@@ -573,6 +591,30 @@ impl WalletBackend for WalletSqliteDatabase {
         ScannedBlockSql::clear_before_height(height, exclude_recovered, &mut conn)
     }
 
+    fn get_recovery_segments(&self) -> Result<Vec<RecoverySegment>, WalletStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        let sql_segments = RecoverySegmentSql::index(&mut conn)?;
+        sql_segments
+            .into_iter()
+            .map(RecoverySegment::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(WalletStorageError::ConversionError)
+    }
+
+    fn save_recovery_segment(&self, segment: RecoverySegment) -> Result<(), WalletStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        RecoverySegmentSql::from(segment).commit(&mut conn)
+    }
+
+    fn clear_recovery_segments(&self) -> Result<(), WalletStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        RecoverySegmentSql::clear_all(&mut conn)
+    }
+
+    fn create_backup(&self, backup_path: &Path, passphrase: &SafePassword) -> Result<(), WalletStorageError> {
+        backup::create_backup(&self.database_connection, backup_path, passphrase)
+    }
+
     fn change_passphrase(&self, existing: &SafePassword, new: &SafePassword) -> Result<(), WalletStorageError> {
         let mut conn = self.database_connection.get_pooled_connection()?;
 
@@ -727,13 +769,7 @@ fn derive_secondary_key(
 ) -> Result<(WalletSecondaryEncryptionKey, Vec<u8>), WalletStorageError> {
     // Produce the secondary derivation key from the passphrase and salt
     let mut secondary_derivation_key = WalletSecondaryDerivationKey::from(SafeArray::default());
-    argon2::Argon2::new(params.algorithm, params.version, params.params)
-        .hash_password_into(
-            passphrase.reveal(),
-            salt.as_bytes(),
-            secondary_derivation_key.reveal_mut(),
-        )
-        .map_err(|e| WalletStorageError::AeadError(e.to_string()))?;
+    params.hash_password_into(passphrase, salt, secondary_derivation_key.reveal_mut())?;
 
     // Derive the secondary key
     let mut secondary_key = WalletSecondaryEncryptionKey::from(SafeArray::default());
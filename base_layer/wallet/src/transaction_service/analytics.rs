@@ -0,0 +1,159 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Aggregates the completed transactions of the wallet into the incoming and outgoing volume and the fees paid per
+//! period.
+
+use std::{collections::BTreeMap, convert::TryFrom};
+
+use chrono::NaiveDateTime;
+use tari_common_types::transaction::TransactionDirection;
+
+use crate::transaction_service::storage::models::CompletedTransaction;
+
+/// The length of a day in seconds, the usual period
+pub const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// The transaction volume of one period. Amounts are in µT.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PeriodVolume {
+    pub period_start: NaiveDateTime,
+    pub incoming: u64,
+    pub outgoing: u64,
+    pub fees: u64,
+    pub incoming_count: u64,
+    pub outgoing_count: u64,
+}
+
+/// Sums up the volume of `transactions` per period of `period_secs` seconds, counted from the Unix epoch. Periods
+/// without transactions are left out and the result is ordered by period. Cancelled transactions and transactions
+/// before `since` are ignored. A transaction belongs to the period it was mined in, or was created in if it has not
+/// been mined yet. Payments to ourselves only add their fee to the outgoing volume.
+pub fn volume_per_period<I>(transactions: I, period_secs: u64, since: Option<NaiveDateTime>) -> Vec<PeriodVolume>
+where I: IntoIterator<Item = CompletedTransaction> {
+    let period_secs = i64::try_from(period_secs.max(1)).unwrap_or(i64::MAX);
+    let mut periods = BTreeMap::<i64, PeriodVolume>::new();
+    for tx in transactions.into_iter().filter(|tx| tx.cancelled.is_none()) {
+        let date = tx.mined_timestamp.unwrap_or(tx.timestamp);
+        if since.map_or(false, |since| date < since) {
+            continue;
+        }
+        let start = date.timestamp() - date.timestamp().rem_euclid(period_secs);
+        let volume = periods.entry(start).or_insert_with(|| PeriodVolume {
+            period_start: NaiveDateTime::from_timestamp_opt(start, 0).unwrap_or_default(),
+            ..Default::default()
+        });
+        let is_self_payment = tx.source_address == tx.destination_address;
+        match tx.direction {
+            TransactionDirection::Inbound => {
+                volume.incoming = volume.incoming.saturating_add(tx.amount.as_u64());
+                volume.incoming_count += 1;
+            },
+            TransactionDirection::Outbound => {
+                if !is_self_payment {
+                    volume.outgoing = volume.outgoing.saturating_add(tx.amount.as_u64());
+                }
+                volume.fees = volume.fees.saturating_add(tx.fee.as_u64());
+                volume.outgoing_count += 1;
+            },
+            TransactionDirection::Unknown => {},
+        }
+    }
+    periods.into_values().collect()
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+    use rand::rngs::OsRng;
+    use tari_common::configuration::Network;
+    use tari_common_types::{
+        tari_address::TariAddress,
+        transaction::{TransactionStatus, TxId},
+        types::{PrivateKey, PublicKey},
+    };
+    use tari_core::transactions::{tari_amount::MicroMinotari, transaction_components::Transaction};
+    use tari_crypto::keys::{PublicKey as PublicKeyTrait, SecretKey};
+
+    use super::*;
+    use crate::transaction_service::storage::models::TxCancellationReason;
+
+    fn address() -> TariAddress {
+        TariAddress::new(
+            PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+            Network::LocalNet,
+        )
+    }
+
+    fn transaction(
+        tx_id: u64,
+        direction: TransactionDirection,
+        amount: u64,
+        fee: u64,
+        date: NaiveDateTime,
+    ) -> CompletedTransaction {
+        CompletedTransaction::new(
+            TxId::from(tx_id),
+            address(),
+            address(),
+            MicroMinotari::from(amount),
+            MicroMinotari::from(fee),
+            Transaction::new(vec![], vec![], vec![], PrivateKey::default(), PrivateKey::default()),
+            TransactionStatus::MinedConfirmed,
+            String::new(),
+            date,
+            direction,
+            None,
+            None,
+            None,
+        )
+    }
+
+    fn date(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 9, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn it_sums_volume_per_day() {
+        let self_address = address();
+        let mut self_payment = transaction(4, TransactionDirection::Outbound, 700, 3, date(2, 8));
+        self_payment.source_address = self_address.clone();
+        self_payment.destination_address = self_address;
+        let mut cancelled = transaction(5, TransactionDirection::Inbound, 9000, 0, date(2, 9));
+        cancelled.cancelled = Some(TxCancellationReason::UserCancelled);
+        let transactions = vec![
+            transaction(1, TransactionDirection::Inbound, 1000, 0, date(1, 10)),
+            transaction(2, TransactionDirection::Outbound, 300, 20, date(1, 23)),
+            transaction(3, TransactionDirection::Inbound, 50, 0, date(2, 1)),
+            self_payment,
+            cancelled,
+        ];
+
+        let volumes = volume_per_period(transactions.clone(), SECONDS_PER_DAY, None);
+        assert_eq!(volumes, vec![
+            PeriodVolume {
+                period_start: date(1, 0),
+                incoming: 1000,
+                outgoing: 300,
+                fees: 20,
+                incoming_count: 1,
+                outgoing_count: 1,
+            },
+            PeriodVolume {
+                period_start: date(2, 0),
+                incoming: 50,
+                outgoing: 0,
+                fees: 3,
+                incoming_count: 1,
+                outgoing_count: 1,
+            },
+        ]);
+
+        let volumes = volume_per_period(transactions, SECONDS_PER_DAY, Some(date(2, 0)));
+        assert_eq!(volumes.len(), 1);
+        assert_eq!(volumes[0].period_start, date(2, 0));
+    }
+}
@@ -24,7 +24,8 @@ use std::{fmt, time::Duration};
 
 use log::*;
 use serde::{Deserialize, Serialize};
-use tari_common::configuration::serializers;
+use tari_common::configuration::{serializers, StringList};
+use tari_core::transactions::tari_amount::MicroMinotari;
 
 const LOG_TARGET: &str = "wallet::transaction_service::config";
 
@@ -69,6 +70,8 @@ pub struct TransactionServiceConfig {
     /// This is the timeout period that will be used to re-submit transactions not found in the mempool
     #[serde(with = "serializers::seconds")]
     pub transaction_mempool_resubmission_window: Duration,
+    /// Limits applied to every transaction this wallet sends
+    pub spending_policy: SpendingPolicyConfig,
 }
 
 impl Default for TransactionServiceConfig {
@@ -87,10 +90,29 @@ impl Default for TransactionServiceConfig {
             transaction_routing_mechanism: TransactionRoutingMechanism::default(),
             transaction_event_channel_size: 1000,
             transaction_mempool_resubmission_window: Duration::from_secs(600),
+            spending_policy: SpendingPolicyConfig::default(),
         }
     }
 }
 
+/// The spending policy is disabled when every limit is unset
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpendingPolicyConfig {
+    /// Transactions for more than this amount are rejected
+    pub max_transaction_amount: Option<MicroMinotari>,
+    /// The most that may be sent in any rolling 24 hour window
+    pub max_daily_amount: Option<MicroMinotari>,
+    /// When not empty, only these addresses (hex or emoji) may be paid
+    pub allowed_destinations: StringList,
+    /// These addresses (hex or emoji) may never be paid
+    pub denied_destinations: StringList,
+    /// The number of confirmations an output needs before it may be spent, 0 to spend unconfirmed outputs
+    pub min_input_confirmations: u64,
+    /// Transactions for more than this amount are held until they are approved in the console wallet
+    pub approval_threshold: Option<MicroMinotari>,
+}
+
 #[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub enum TransactionRoutingMechanism {
     DirectOnly,
@@ -47,8 +47,12 @@ use crate::{
     error::WalletStorageError,
     output_manager_service::error::OutputManagerError,
     transaction_service::{
+        payment_request::PaymentRequestError,
+        scheduled_payment::ScheduledPaymentError,
+        spending_policy::SpendingPolicyError,
         storage::{database::DbKey, sqlite_db::CompletedTransactionConversionError},
         utc::NegativeDurationError,
+        vault::VaultError,
     },
 };
 
@@ -189,6 +193,16 @@ pub enum TransactionServiceError {
     InvalidKeyId(String),
     #[error("Invalid key manager data: `{0}`")]
     KeyManagerServiceError(#[from] KeyManagerServiceError),
+    #[error("Invalid label: `{0}`")]
+    InvalidLabel(String),
+    #[error("Payment request error: {0}")]
+    PaymentRequestError(#[from] PaymentRequestError),
+    #[error("Scheduled payment error: {0}")]
+    ScheduledPaymentError(#[from] ScheduledPaymentError),
+    #[error("Spending policy error: {0}")]
+    SpendingPolicyError(#[from] SpendingPolicyError),
+    #[error("Vault error: {0}")]
+    VaultError(#[from] VaultError),
 }
 
 impl From<RangeProofError> for TransactionServiceError {
@@ -271,6 +285,14 @@ pub enum TransactionStorageError {
     NotCoinbase,
     #[error("Db error: `{0}`")]
     SqliteStorageError(#[from] SqliteStorageError),
+    #[error("Payment request error: {0}")]
+    PaymentRequestError(#[from] PaymentRequestError),
+    #[error("Scheduled payment error: {0}")]
+    ScheduledPaymentError(#[from] ScheduledPaymentError),
+    #[error("Spending policy error: {0}")]
+    SpendingPolicyError(#[from] SpendingPolicyError),
+    #[error("Vault error: {0}")]
+    VaultError(#[from] VaultError),
 }
 
 impl From<ByteArrayError> for TransactionStorageError {
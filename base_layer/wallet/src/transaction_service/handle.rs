@@ -37,6 +37,7 @@ use tari_common_types::{
 use tari_comms::types::CommsPublicKey;
 use tari_core::{
     consensus::{MaxSizeBytes, MaxSizeString},
+    covenants::Covenant,
     mempool::FeePerGramStat,
     proto,
     transactions::{
@@ -56,16 +57,22 @@ use tokio::sync::broadcast;
 use tower::Service;
 
 use crate::{
-    output_manager_service::UtxoSelectionCriteria,
+    output_manager_service::{dust_consolidation::DustConsolidationReport, UtxoSelectionCriteria},
     transaction_service::{
         error::TransactionServiceError,
+        payment_request::{PaymentRequest, PaymentRequestStatus},
+        scheduled_payment::{PaymentSchedule, ScheduledPayment, ScheduledPaymentId},
+        spending_policy::{PendingSpend, PendingSpendId},
         storage::models::{
             CompletedTransaction,
             InboundTransaction,
+            LabelTarget,
             OutboundTransaction,
+            TransactionLabels,
             TxCancellationReason,
             WalletTransaction,
         },
+        vault::{Vault, VaultId},
     },
     OperationId,
 };
@@ -121,6 +128,7 @@ pub enum TransactionServiceRequest {
         amount: MicroMinotari,
         selection_criteria: UtxoSelectionCriteria,
         output_features: Box<OutputFeatures>,
+        covenant: Box<Covenant>,
         fee_per_gram: MicroMinotari,
         message: String,
     },
@@ -129,6 +137,7 @@ pub enum TransactionServiceRequest {
         amount: MicroMinotari,
         selection_criteria: UtxoSelectionCriteria,
         output_features: Box<OutputFeatures>,
+        covenant: Box<Covenant>,
         fee_per_gram: MicroMinotari,
         message: String,
     },
@@ -163,6 +172,43 @@ pub enum TransactionServiceRequest {
     GetFeePerGramStatsPerBlock {
         count: usize,
     },
+    SetLabels(LabelTarget, TransactionLabels),
+    GetLabels(LabelTarget),
+    GetAllTransactionLabels,
+    CreatePaymentRequest {
+        amount: MicroMinotari,
+        memo: String,
+        expires_at: Option<NaiveDateTime>,
+    },
+    GetPaymentRequests,
+    CancelPaymentRequest(String),
+    CreateScheduledPayment {
+        destination: TariAddress,
+        amount: MicroMinotari,
+        fee_per_gram: MicroMinotari,
+        message: String,
+        one_sided: bool,
+        schedule: PaymentSchedule,
+        max_runs: Option<u64>,
+    },
+    GetScheduledPayments,
+    CancelScheduledPayment(ScheduledPaymentId),
+    GetPendingSpends,
+    ApprovePendingSpend(PendingSpendId),
+    RejectPendingSpend(PendingSpendId),
+    CreateVault {
+        amount: MicroMinotari,
+        recovery_public_key: PublicKey,
+        lock_blocks: u64,
+        selection_criteria: UtxoSelectionCriteria,
+        fee_per_gram: MicroMinotari,
+        message: String,
+    },
+    GetVaults,
+    SpendVault {
+        id: VaultId,
+        fee_per_gram: MicroMinotari,
+    },
 }
 
 impl fmt::Display for TransactionServiceRequest {
@@ -251,6 +297,34 @@ impl fmt::Display for TransactionServiceRequest {
             TransactionServiceRequest::RegisterCodeTemplate { template_name, .. } => {
                 write!(f, "RegisterCodeTemplate: {}", template_name)
             },
+            Self::SetLabels(target, labels) => write!(f, "SetLabels ({}: {})", target, labels),
+            Self::GetLabels(target) => write!(f, "GetLabels ({})", target),
+            Self::GetAllTransactionLabels => write!(f, "GetAllTransactionLabels"),
+            Self::CreatePaymentRequest { amount, memo, .. } => {
+                write!(f, "CreatePaymentRequest ({}, {})", amount, memo)
+            },
+            Self::GetPaymentRequests => write!(f, "GetPaymentRequests"),
+            Self::CancelPaymentRequest(reference) => write!(f, "CancelPaymentRequest ({})", reference),
+            Self::CreateScheduledPayment {
+                destination,
+                amount,
+                schedule,
+                ..
+            } => write!(
+                f,
+                "CreateScheduledPayment (to {}, {}, {})",
+                destination, amount, schedule
+            ),
+            Self::GetScheduledPayments => write!(f, "GetScheduledPayments"),
+            Self::CancelScheduledPayment(id) => write!(f, "CancelScheduledPayment ({})", id),
+            Self::GetPendingSpends => write!(f, "GetPendingSpends"),
+            Self::ApprovePendingSpend(id) => write!(f, "ApprovePendingSpend ({})", id),
+            Self::RejectPendingSpend(id) => write!(f, "RejectPendingSpend ({})", id),
+            Self::CreateVault {
+                amount, lock_blocks, ..
+            } => write!(f, "CreateVault ({}, locked for {} blocks)", amount, lock_blocks),
+            Self::GetVaults => write!(f, "GetVaults"),
+            Self::SpendVault { id, .. } => write!(f, "SpendVault ({})", id),
         }
     }
 }
@@ -286,6 +360,19 @@ pub enum TransactionServiceResponse {
     CompletedTransactionValidityChanged,
     ShaAtomicSwapTransactionSent(Box<(TxId, PublicKey, TransactionOutput)>),
     FeePerGramStatsPerBlock(FeePerGramStatsResponse),
+    LabelsSet,
+    Labels(TransactionLabels),
+    AllTransactionLabels(HashMap<TxId, TransactionLabels>),
+    PaymentRequestCreated(Box<PaymentRequest>),
+    PaymentRequests(Vec<PaymentRequest>),
+    PaymentRequestCancelled,
+    ScheduledPaymentCreated(Box<ScheduledPayment>),
+    ScheduledPayments(Vec<ScheduledPayment>),
+    ScheduledPaymentCancelled,
+    PendingSpends(Vec<PendingSpend>),
+    PendingSpendRejected,
+    VaultCreated(Box<Vault>),
+    Vaults(Vec<Vault>),
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq, Default)]
@@ -341,6 +428,23 @@ pub enum TransactionEvent {
     TransactionValidationStateChanged(OperationId),
     TransactionValidationCompleted(OperationId),
     TransactionValidationFailed(OperationId, u64),
+    PaymentRequestUpdated {
+        reference: String,
+        status: PaymentRequestStatus,
+    },
+    ScheduledPaymentExecuted {
+        id: ScheduledPaymentId,
+        tx_id: TxId,
+    },
+    ScheduledPaymentFailed {
+        id: ScheduledPaymentId,
+        reason: String,
+    },
+    SpendHeldForApproval(PendingSpendId),
+    SpendDenied(String),
+    DustConsolidated(DustConsolidationReport),
+    /// The chain reached the unlock height of an active vault
+    VaultUnlocked(VaultId),
     Error(String),
 }
 
@@ -423,6 +527,23 @@ impl fmt::Display for TransactionEvent {
             TransactionEvent::NewBlockMined(tx_id) => {
                 write!(f, "New block mined {tx_id}")
             },
+            TransactionEvent::PaymentRequestUpdated { reference, status } => {
+                write!(f, "Payment request {reference} is {status}")
+            },
+            TransactionEvent::ScheduledPaymentExecuted { id, tx_id } => {
+                write!(f, "Scheduled payment #{id} sent as transaction {tx_id}")
+            },
+            TransactionEvent::ScheduledPaymentFailed { id, reason } => {
+                write!(f, "Scheduled payment #{id} failed: {reason}")
+            },
+            TransactionEvent::SpendHeldForApproval(id) => {
+                write!(f, "Transaction held for approval as pending spend #{id}")
+            },
+            TransactionEvent::SpendDenied(reason) => {
+                write!(f, "Spending policy denied a transaction: {reason}")
+            },
+            TransactionEvent::DustConsolidated(report) => write!(f, "{report}"),
+            TransactionEvent::VaultUnlocked(id) => write!(f, "Vault #{id} can be spent with its hot key"),
         }
     }
 }
@@ -556,6 +677,7 @@ impl TransactionServiceHandle {
         amount: MicroMinotari,
         selection_criteria: UtxoSelectionCriteria,
         output_features: OutputFeatures,
+        covenant: Covenant,
         fee_per_gram: MicroMinotari,
         message: String,
     ) -> Result<TxId, TransactionServiceError> {
@@ -566,6 +688,7 @@ impl TransactionServiceHandle {
                 amount,
                 selection_criteria,
                 output_features: Box::new(output_features),
+                covenant: Box::new(covenant),
                 fee_per_gram,
                 message,
             })
@@ -607,6 +730,7 @@ impl TransactionServiceHandle {
         amount: MicroMinotari,
         selection_criteria: UtxoSelectionCriteria,
         output_features: OutputFeatures,
+        covenant: Covenant,
         fee_per_gram: MicroMinotari,
         message: String,
     ) -> Result<TxId, TransactionServiceError> {
@@ -617,6 +741,7 @@ impl TransactionServiceHandle {
                 amount,
                 selection_criteria,
                 output_features: Box::new(output_features),
+                covenant: Box::new(covenant),
                 fee_per_gram,
                 message,
             })
@@ -941,4 +1066,216 @@ impl TransactionServiceHandle {
             _ => Err(TransactionServiceError::UnexpectedApiResponse),
         }
     }
+
+    /// Replaces the label and tags attached to a transaction or an output. Passing empty labels removes them.
+    pub async fn set_labels(
+        &mut self,
+        target: LabelTarget,
+        labels: TransactionLabels,
+    ) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SetLabels(target, labels))
+            .await??
+        {
+            TransactionServiceResponse::LabelsSet => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_labels(&mut self, target: LabelTarget) -> Result<TransactionLabels, TransactionServiceError> {
+        match self.handle.call(TransactionServiceRequest::GetLabels(target)).await?? {
+            TransactionServiceResponse::Labels(labels) => Ok(labels),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_all_transaction_labels(
+        &mut self,
+    ) -> Result<HashMap<TxId, TransactionLabels>, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::GetAllTransactionLabels)
+            .await??
+        {
+            TransactionServiceResponse::AllTransactionLabels(labels) => Ok(labels),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Issues a new payment request to this wallet's address. Incoming payments are matched to it automatically.
+    pub async fn create_payment_request(
+        &mut self,
+        amount: MicroMinotari,
+        memo: String,
+        expires_at: Option<NaiveDateTime>,
+    ) -> Result<PaymentRequest, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::CreatePaymentRequest {
+                amount,
+                memo,
+                expires_at,
+            })
+            .await??
+        {
+            TransactionServiceResponse::PaymentRequestCreated(request) => Ok(*request),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_payment_requests(&mut self) -> Result<Vec<PaymentRequest>, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::GetPaymentRequests)
+            .await??
+        {
+            TransactionServiceResponse::PaymentRequests(requests) => Ok(requests),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn cancel_payment_request(&mut self, reference: String) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::CancelPaymentRequest(reference))
+            .await??
+        {
+            TransactionServiceResponse::PaymentRequestCancelled => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Schedules a payment to be sent at a later time or height, or repeatedly. Payments are sent by the transaction
+    /// service while the wallet is running.
+    pub async fn create_scheduled_payment(
+        &mut self,
+        destination: TariAddress,
+        amount: MicroMinotari,
+        fee_per_gram: MicroMinotari,
+        message: String,
+        one_sided: bool,
+        schedule: PaymentSchedule,
+        max_runs: Option<u64>,
+    ) -> Result<ScheduledPayment, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::CreateScheduledPayment {
+                destination,
+                amount,
+                fee_per_gram,
+                message,
+                one_sided,
+                schedule,
+                max_runs,
+            })
+            .await??
+        {
+            TransactionServiceResponse::ScheduledPaymentCreated(payment) => Ok(*payment),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_scheduled_payments(&mut self) -> Result<Vec<ScheduledPayment>, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::GetScheduledPayments)
+            .await??
+        {
+            TransactionServiceResponse::ScheduledPayments(payments) => Ok(payments),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn cancel_scheduled_payment(&mut self, id: ScheduledPaymentId) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::CancelScheduledPayment(id))
+            .await??
+        {
+            TransactionServiceResponse::ScheduledPaymentCancelled => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Returns the transactions that the spending policy is holding until they are approved or rejected
+    pub async fn get_pending_spends(&mut self) -> Result<Vec<PendingSpend>, TransactionServiceError> {
+        match self.handle.call(TransactionServiceRequest::GetPendingSpends).await?? {
+            TransactionServiceResponse::PendingSpends(spends) => Ok(spends),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Sends a held transaction. This is intentionally not exposed through gRPC.
+    pub async fn approve_pending_spend(&mut self, id: PendingSpendId) -> Result<TxId, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::ApprovePendingSpend(id))
+            .await??
+        {
+            TransactionServiceResponse::TransactionSent(tx_id) => Ok(tx_id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn reject_pending_spend(&mut self, id: PendingSpendId) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::RejectPendingSpend(id))
+            .await??
+        {
+            TransactionServiceResponse::PendingSpendRejected => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Locks `amount` in a vault that the wallet can spend once `lock_blocks` blocks have been mined, and that the
+    /// holder of the recovery key can spend at any time
+    pub async fn create_vault(
+        &mut self,
+        amount: MicroMinotari,
+        recovery_public_key: PublicKey,
+        lock_blocks: u64,
+        fee_per_gram: MicroMinotari,
+        message: String,
+    ) -> Result<Vault, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::CreateVault {
+                amount,
+                recovery_public_key,
+                lock_blocks,
+                selection_criteria: UtxoSelectionCriteria::default(),
+                fee_per_gram,
+                message,
+            })
+            .await??
+        {
+            TransactionServiceResponse::VaultCreated(vault) => Ok(*vault),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_vaults(&mut self) -> Result<Vec<Vault>, TransactionServiceError> {
+        match self.handle.call(TransactionServiceRequest::GetVaults).await?? {
+            TransactionServiceResponse::Vaults(vaults) => Ok(vaults),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Returns the funds of an unlocked vault to the wallet
+    pub async fn spend_vault(
+        &mut self,
+        id: VaultId,
+        fee_per_gram: MicroMinotari,
+    ) -> Result<TxId, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SpendVault { id, fee_per_gram })
+            .await??
+        {
+            TransactionServiceResponse::TransactionSent(tx_id) => Ok(tx_id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
 }
@@ -0,0 +1,153 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Builds an accounting ledger from the completed transactions of the wallet, suitable for exporting to CSV or JSON.
+
+use std::{collections::HashMap, convert::TryFrom};
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use tari_common_types::transaction::{TransactionDirection, TransactionStatus, TxId};
+
+use crate::transaction_service::storage::models::{CompletedTransaction, TransactionLabels};
+
+/// A single line of the accounting ledger. Amounts are in µT.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LedgerEntry {
+    /// The mined timestamp, or the creation timestamp if the transaction has not been mined yet
+    pub date: NaiveDateTime,
+    pub tx_id: TxId,
+    pub direction: TransactionDirection,
+    pub status: TransactionStatus,
+    pub amount: u64,
+    pub fee: u64,
+    /// The effect of the transaction on the wallet balance, i.e. received amounts are positive and sent amounts
+    /// (including the fee) are negative
+    pub net_amount: i64,
+    /// Hex encoded address of the other party
+    pub counterparty: String,
+    pub label: Option<String>,
+    pub tags: Vec<String>,
+    pub confirmation_height: Option<u64>,
+    /// Sum of the net amounts of this and all preceding entries in the ledger
+    pub running_balance: i64,
+}
+
+impl LedgerEntry {
+    fn new(tx: CompletedTransaction, labels: TransactionLabels) -> Self {
+        let amount = tx.amount.as_u64();
+        let fee = tx.fee.as_u64();
+        let is_self_payment = tx.source_address == tx.destination_address;
+        let net_amount = net_amount(&tx.direction, is_self_payment, amount, fee);
+        let counterparty = match tx.direction {
+            TransactionDirection::Inbound => tx.source_address.to_hex(),
+            _ => tx.destination_address.to_hex(),
+        };
+        Self {
+            date: tx.mined_timestamp.unwrap_or(tx.timestamp),
+            tx_id: tx.tx_id,
+            direction: tx.direction,
+            status: tx.status,
+            amount,
+            fee,
+            net_amount,
+            counterparty,
+            label: labels.label,
+            tags: labels.tags,
+            confirmation_height: tx.mined_height,
+            running_balance: 0,
+        }
+    }
+}
+
+/// Builds the ledger from `transactions`, ordered by date. Cancelled transactions are left out, as are transactions
+/// whose labels do not match `label` and `tags` (see [TransactionLabels::matches]). The running balance is
+/// accumulated over the entries that end up in the ledger.
+pub fn build_ledger<I>(
+    transactions: I,
+    labels: &HashMap<TxId, TransactionLabels>,
+    label: &str,
+    tags: &[String],
+) -> Vec<LedgerEntry>
+where
+    I: IntoIterator<Item = CompletedTransaction>,
+{
+    let mut entries = transactions
+        .into_iter()
+        .filter(|tx| tx.cancelled.is_none())
+        .filter_map(|tx| {
+            let tx_labels = labels.get(&tx.tx_id).cloned().unwrap_or_default();
+            if tx_labels.matches(label, tags) {
+                Some(LedgerEntry::new(tx, tx_labels))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|e| (e.date, e.tx_id.as_u64()));
+    apply_running_balance(&mut entries);
+    entries
+}
+
+fn apply_running_balance(entries: &mut [LedgerEntry]) {
+    let mut balance = 0i64;
+    for entry in entries {
+        balance = balance.saturating_add(entry.net_amount);
+        entry.running_balance = balance;
+    }
+}
+
+fn net_amount(direction: &TransactionDirection, is_self_payment: bool, amount: u64, fee: u64) -> i64 {
+    let to_signed = |v: u64| i64::try_from(v).unwrap_or(i64::MAX);
+    match direction {
+        TransactionDirection::Inbound => to_signed(amount),
+        // Only the fee leaves the wallet when paying to ourselves
+        TransactionDirection::Outbound if is_self_payment => -to_signed(fee),
+        TransactionDirection::Outbound => -to_signed(amount.saturating_add(fee)),
+        TransactionDirection::Unknown => 0,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn entry(day: u32, tx_id: u64, net_amount: i64) -> LedgerEntry {
+        LedgerEntry {
+            date: NaiveDate::from_ymd_opt(2023, 9, day)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap(),
+            tx_id: tx_id.into(),
+            direction: TransactionDirection::Inbound,
+            status: TransactionStatus::MinedConfirmed,
+            amount: 0,
+            fee: 0,
+            net_amount,
+            counterparty: String::new(),
+            label: None,
+            tags: vec![],
+            confirmation_height: None,
+            running_balance: 0,
+        }
+    }
+
+    #[test]
+    fn it_calculates_net_amounts() {
+        assert_eq!(net_amount(&TransactionDirection::Inbound, false, 1000, 25), 1000);
+        assert_eq!(net_amount(&TransactionDirection::Outbound, false, 1000, 25), -1025);
+        assert_eq!(net_amount(&TransactionDirection::Outbound, true, 1000, 25), -25);
+        assert_eq!(net_amount(&TransactionDirection::Unknown, false, 1000, 25), 0);
+        assert_eq!(net_amount(&TransactionDirection::Inbound, false, u64::MAX, 0), i64::MAX);
+    }
+
+    #[test]
+    fn it_accumulates_the_running_balance() {
+        let mut entries = vec![entry(1, 1, 1000), entry(2, 2, -300), entry(3, 3, -25), entry(4, 4, 50)];
+        apply_running_balance(&mut entries);
+        let balances = entries.iter().map(|e| e.running_balance).collect::<Vec<_>>();
+        assert_eq!(balances, vec![1000, 700, 675, 725]);
+    }
+}
@@ -58,19 +58,27 @@ use crate::{
         config::TransactionServiceConfig,
         handle::TransactionServiceHandle,
         service::TransactionService,
+        spending_policy::SpendingPolicy,
         storage::database::{TransactionBackend, TransactionDatabase},
     },
     util::wallet_identity::WalletIdentity,
 };
 
+pub mod analytics;
 pub mod config;
 pub mod error;
 pub mod handle;
+pub mod ledger;
+pub mod payment_request;
 pub mod protocols;
+pub mod scheduled_payment;
 pub mod service;
+pub mod spending_policy;
 pub mod storage;
 pub mod tasks;
 mod utc;
+pub mod vault;
+pub mod xmr_swap;
 
 const LOG_TARGET: &str = "wallet::transaction_service";
 const SUBSCRIPTION_LABEL: &str = "Transaction Service";
@@ -198,6 +206,8 @@ where
     TKeyManagerInterface: TransactionKeyManagerInterface,
 {
     async fn initialize(&mut self, context: ServiceInitializerContext) -> Result<(), ServiceInitializationError> {
+        // An invalid policy stops the wallet from starting rather than leaving it without one
+        let spending_policy = SpendingPolicy::new(&self.config.spending_policy)?;
         let (sender, receiver) = reply_channel::unbounded();
         let transaction_stream = self.transaction_stream();
         let transaction_reply_stream = self.transaction_reply_stream();
@@ -236,6 +246,7 @@ where
 
             let result = TransactionService::new(
                 config,
+                spending_policy,
                 TransactionDatabase::new(tx_backend),
                 wallet_database,
                 receiver,
@@ -0,0 +1,477 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Payment requests (invoices) issued by the wallet, the URI used to share them and the rules used to match incoming
+//! payments against them.
+
+use std::{
+    convert::TryFrom,
+    fmt,
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use chrono::NaiveDateTime;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use tari_common_types::tari_address::TariAddress;
+use tari_core::transactions::tari_amount::MicroMinotari;
+use thiserror::Error;
+
+const URI_SCHEME: &str = "tari://";
+const URI_PATH: &str = "/transactions/send";
+
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum PaymentRequestError {
+    #[error("Invalid payment request URI: `{0}`")]
+    InvalidUri(String),
+    #[error("Invalid payment request: `{0}`")]
+    InvalidRequest(String),
+    #[error("Payment request `{0}` does not exist")]
+    NotFound(String),
+    #[error("Payment request `{0}` is {1} and can no longer be changed")]
+    NotOpen(String, PaymentRequestStatus),
+}
+
+/// The state of a payment request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PaymentRequestStatus {
+    /// Nothing has been received yet
+    Pending,
+    /// Some, but not all, of the requested amount has been received
+    Underpaid,
+    /// The requested amount (or more) has been received
+    Paid,
+    /// The request expired before it was paid in full
+    Expired,
+    /// The request was cancelled by the wallet owner
+    Cancelled,
+}
+
+impl PaymentRequestStatus {
+    /// Returns true if the request can still receive payments
+    pub fn is_open(self) -> bool {
+        matches!(self, PaymentRequestStatus::Pending | PaymentRequestStatus::Underpaid)
+    }
+}
+
+impl Display for PaymentRequestStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PaymentRequestStatus::Pending => write!(f, "Pending"),
+            PaymentRequestStatus::Underpaid => write!(f, "Underpaid"),
+            PaymentRequestStatus::Paid => write!(f, "Paid"),
+            PaymentRequestStatus::Expired => write!(f, "Expired"),
+            PaymentRequestStatus::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+impl From<PaymentRequestStatus> for i32 {
+    fn from(status: PaymentRequestStatus) -> Self {
+        match status {
+            PaymentRequestStatus::Pending => 0,
+            PaymentRequestStatus::Underpaid => 1,
+            PaymentRequestStatus::Paid => 2,
+            PaymentRequestStatus::Expired => 3,
+            PaymentRequestStatus::Cancelled => 4,
+        }
+    }
+}
+
+impl TryFrom<i32> for PaymentRequestStatus {
+    type Error = PaymentRequestError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(PaymentRequestStatus::Pending),
+            1 => Ok(PaymentRequestStatus::Underpaid),
+            2 => Ok(PaymentRequestStatus::Paid),
+            3 => Ok(PaymentRequestStatus::Expired),
+            4 => Ok(PaymentRequestStatus::Cancelled),
+            v => Err(PaymentRequestError::InvalidRequest(format!("Unknown status {}", v))),
+        }
+    }
+}
+
+/// A request for a payment to this wallet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentRequest {
+    /// Unique reference that the payer includes in the transaction message
+    pub reference: String,
+    /// The address the payment must be sent to
+    pub address: TariAddress,
+    pub amount: MicroMinotari,
+    pub memo: String,
+    pub created_at: NaiveDateTime,
+    pub expires_at: Option<NaiveDateTime>,
+    pub status: PaymentRequestStatus,
+    /// Sum of all the payments matched to this request so far
+    pub amount_received: MicroMinotari,
+    /// The part of `amount_received` that was matched by amount alone, without a reference
+    pub amount_inferred: MicroMinotari,
+}
+
+impl PaymentRequest {
+    pub fn new(
+        address: TariAddress,
+        amount: MicroMinotari,
+        memo: String,
+        created_at: NaiveDateTime,
+        expires_at: Option<NaiveDateTime>,
+    ) -> Self {
+        Self {
+            reference: generate_reference(),
+            address,
+            amount,
+            memo,
+            created_at,
+            expires_at,
+            status: PaymentRequestStatus::Pending,
+            amount_received: MicroMinotari::zero(),
+            amount_inferred: MicroMinotari::zero(),
+        }
+    }
+
+    /// The amount still outstanding
+    pub fn amount_remaining(&self) -> MicroMinotari {
+        self.amount.saturating_sub(self.amount_received)
+    }
+
+    pub fn is_expired_at(&self, now: NaiveDateTime) -> bool {
+        self.expires_at.map_or(false, |expires_at| expires_at <= now)
+    }
+
+    /// The status of the request after a payment of `amount` has been matched to it
+    pub fn status_after_payment(&self, amount: MicroMinotari) -> PaymentRequestStatus {
+        if self.amount_received + amount >= self.amount {
+            PaymentRequestStatus::Paid
+        } else {
+            PaymentRequestStatus::Underpaid
+        }
+    }
+
+    /// The status of the request after a payment of `amount` that was matched to it is reverted. Requests that were
+    /// closed in the meantime keep their status.
+    pub fn status_after_reversal(&self, amount: MicroMinotari) -> PaymentRequestStatus {
+        if !matches!(
+            self.status,
+            PaymentRequestStatus::Underpaid | PaymentRequestStatus::Paid
+        ) {
+            return self.status;
+        }
+        let amount_received = self.amount_received.saturating_sub(amount);
+        if amount_received == MicroMinotari::zero() {
+            PaymentRequestStatus::Pending
+        } else if amount_received >= self.amount {
+            PaymentRequestStatus::Paid
+        } else {
+            PaymentRequestStatus::Underpaid
+        }
+    }
+
+    /// Returns true if the transaction message refers to this request
+    pub fn is_referenced_by(&self, message: &str) -> bool {
+        message.to_lowercase().contains(&self.reference.to_lowercase())
+    }
+
+    /// A URI, with a hex encoded address, that can be shared with the payer or rendered as a QR code
+    pub fn to_uri(&self) -> String {
+        self.uri_with_address(&self.address.to_hex())
+    }
+
+    /// The same URI as [PaymentRequest::to_uri], but with the address encoded as an emoji id
+    pub fn to_emoji_uri(&self) -> String {
+        self.uri_with_address(&self.address.to_emoji_string())
+    }
+
+    fn uri_with_address(&self, address: &str) -> String {
+        let mut uri = format!(
+            "{}{}{}?tariAddress={}&amount={}&reference={}",
+            URI_SCHEME,
+            self.address.network(),
+            URI_PATH,
+            percent_encode(address),
+            self.amount.as_u64(),
+            percent_encode(&self.reference)
+        );
+        if !self.memo.is_empty() {
+            uri.push_str(&format!("&memo={}", percent_encode(&self.memo)));
+        }
+        if let Some(expires_at) = self.expires_at {
+            uri.push_str(&format!("&expires={}", expires_at.timestamp()));
+        }
+        uri
+    }
+}
+
+impl Display for PaymentRequest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} for {} ({}, received {})",
+            self.reference, self.amount, self.status, self.amount_received
+        )
+    }
+}
+
+/// The payer's view of a payment request, parsed from a URI created by [PaymentRequest::to_uri]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentRequestUri {
+    pub address: TariAddress,
+    pub amount: MicroMinotari,
+    pub reference: String,
+    pub memo: String,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+impl PaymentRequestUri {
+    /// The transaction message that lets the payee match the payment to the request
+    pub fn payment_message(&self) -> String {
+        if self.memo.is_empty() {
+            format!("[{}]", self.reference)
+        } else {
+            format!("{} [{}]", self.memo, self.reference)
+        }
+    }
+}
+
+impl FromStr for PaymentRequestUri {
+    type Err = PaymentRequestError;
+
+    fn from_str(uri: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| PaymentRequestError::InvalidUri(reason.to_string());
+        let rest = uri
+            .trim()
+            .strip_prefix(URI_SCHEME)
+            .ok_or_else(|| invalid("must start with tari://"))?;
+        let (path, query) = rest.split_once('?').ok_or_else(|| invalid("missing query"))?;
+        if !path.ends_with(URI_PATH) {
+            return Err(invalid("not a payment request"));
+        }
+
+        let mut address = None;
+        let mut amount = None;
+        let mut reference = None;
+        let mut memo = String::new();
+        let mut expires_at = None;
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value).ok_or_else(|| invalid("bad percent encoding"))?;
+            match key {
+                "tariAddress" => {
+                    address = Some(TariAddress::from_str(&value).map_err(|e| invalid(&e.to_string()))?);
+                },
+                "amount" => {
+                    amount = Some(MicroMinotari::from(
+                        value.parse::<u64>().map_err(|_| invalid("amount must be in µT"))?,
+                    ));
+                },
+                "reference" => reference = Some(value),
+                "memo" => memo = value,
+                "expires" => {
+                    let timestamp = value.parse::<i64>().map_err(|_| invalid("bad expiry"))?;
+                    expires_at =
+                        Some(NaiveDateTime::from_timestamp_opt(timestamp, 0).ok_or_else(|| invalid("bad expiry"))?);
+                },
+                // Unknown parameters are ignored so that the format can be extended
+                _ => {},
+            }
+        }
+
+        Ok(Self {
+            address: address.ok_or_else(|| invalid("missing tariAddress"))?,
+            amount: amount.ok_or_else(|| invalid("missing amount"))?,
+            reference: reference
+                .filter(|r| !r.is_empty())
+                .ok_or_else(|| invalid("missing reference"))?,
+            memo,
+            expires_at,
+        })
+    }
+}
+
+/// A payment request that an incoming payment was matched to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaymentMatch<'a> {
+    pub request: &'a PaymentRequest,
+    /// Set if the payment did not reference the request and was matched by amount alone
+    pub inferred: bool,
+}
+
+/// Finds the open request that an incoming payment of `amount` with `message` pays. A request referenced in the message
+/// wins. One-sided payments carry no message from the payer, so those fall back to the oldest open request with
+/// exactly `amount` outstanding, and the match is marked as inferred. Requests that have expired at `now` are never
+/// matched.
+pub fn find_matching_request<'a>(
+    requests: &'a [PaymentRequest],
+    amount: MicroMinotari,
+    message: &str,
+    one_sided: bool,
+    now: NaiveDateTime,
+) -> Option<PaymentMatch<'a>> {
+    let mut open = requests
+        .iter()
+        .filter(|r| r.status.is_open() && !r.is_expired_at(now))
+        .collect::<Vec<_>>();
+    open.sort_by_key(|r| r.created_at);
+    if let Some(request) = open.iter().find(|r| r.is_referenced_by(message)) {
+        return Some(PaymentMatch {
+            request,
+            inferred: false,
+        });
+    }
+    if !one_sided {
+        return None;
+    }
+    open.into_iter()
+        .find(|r| r.amount_remaining() == amount)
+        .map(|request| PaymentMatch {
+            request,
+            inferred: true,
+        })
+}
+
+fn generate_reference() -> String {
+    let mut bytes = [0u8; 6];
+    OsRng.fill_bytes(&mut bytes);
+    format!("PR-{}", bytes.iter().map(|b| format!("{:02X}", b)).collect::<String>())
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = value.get(i + 1..i + 3)?;
+                decoded.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            },
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            },
+            b => {
+                decoded.push(b);
+                i += 1;
+            },
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Duration;
+    use rand::rngs::OsRng;
+    use tari_common::configuration::Network;
+    use tari_common_types::types::{PrivateKey, PublicKey};
+    use tari_crypto::keys::{PublicKey as PublicKeyTrait, SecretKey};
+
+    use super::*;
+
+    fn address() -> TariAddress {
+        TariAddress::new(
+            PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+            Network::LocalNet,
+        )
+    }
+
+    fn request(amount: u64, created_at: NaiveDateTime) -> PaymentRequest {
+        PaymentRequest::new(address(), amount.into(), String::new(), created_at, None)
+    }
+
+    #[test]
+    fn it_round_trips_the_uri() {
+        let now = NaiveDateTime::from_timestamp_opt(1_694_000_000, 0).unwrap();
+        let request = PaymentRequest::new(
+            address(),
+            12_345.into(),
+            "Coffee & cake, 50% off".to_string(),
+            now,
+            Some(now + Duration::hours(1)),
+        );
+        for uri in [request.to_uri(), request.to_emoji_uri()] {
+            assert!(uri.starts_with("tari://localnet/transactions/send?tariAddress="));
+            let parsed = PaymentRequestUri::from_str(&uri).unwrap();
+            assert_eq!(parsed.address, request.address);
+            assert_eq!(parsed.amount, request.amount);
+            assert_eq!(parsed.reference, request.reference);
+            assert_eq!(parsed.memo, request.memo);
+            assert_eq!(parsed.expires_at, request.expires_at);
+            assert!(request.is_referenced_by(&parsed.payment_message()));
+        }
+
+        assert!(PaymentRequestUri::from_str("https://example.com").is_err());
+        assert!(PaymentRequestUri::from_str(&request.to_uri().replace("amount=12345", "amount=1T")).is_err());
+    }
+
+    #[test]
+    fn it_tracks_the_amount_received() {
+        let mut request = request(100, NaiveDateTime::from_timestamp_opt(0, 0).unwrap());
+        assert_eq!(request.status_after_payment(40.into()), PaymentRequestStatus::Underpaid);
+        request.amount_received = 40.into();
+        assert_eq!(request.amount_remaining(), 60.into());
+        assert_eq!(request.status_after_payment(60.into()), PaymentRequestStatus::Paid);
+        assert_eq!(request.status_after_payment(70.into()), PaymentRequestStatus::Paid);
+    }
+
+    #[test]
+    fn it_reverts_payments() {
+        let mut request = request(100, NaiveDateTime::from_timestamp_opt(0, 0).unwrap());
+        request.amount_received = 140.into();
+        request.status = PaymentRequestStatus::Paid;
+        assert_eq!(request.status_after_reversal(40.into()), PaymentRequestStatus::Paid);
+        assert_eq!(
+            request.status_after_reversal(60.into()),
+            PaymentRequestStatus::Underpaid
+        );
+        assert_eq!(request.status_after_reversal(140.into()), PaymentRequestStatus::Pending);
+        // Closed requests are left alone
+        request.status = PaymentRequestStatus::Cancelled;
+        assert_eq!(
+            request.status_after_reversal(140.into()),
+            PaymentRequestStatus::Cancelled
+        );
+    }
+
+    #[test]
+    fn it_matches_payments_to_requests() {
+        let now = NaiveDateTime::from_timestamp_opt(1_694_000_000, 0).unwrap();
+        let older = request(100, now - Duration::minutes(2));
+        let newer = request(100, now - Duration::minutes(1));
+        let mut expired = request(250, now - Duration::minutes(3));
+        expired.expires_at = Some(now);
+        let mut paid = request(300, now - Duration::minutes(4));
+        paid.status = PaymentRequestStatus::Paid;
+        let requests = vec![newer.clone(), older.clone(), expired.clone(), paid];
+
+        // The reference wins over the amount
+        let message = format!("Thanks! [{}]", newer.reference.to_lowercase());
+        let matched = find_matching_request(&requests, 1.into(), &message, true, now).unwrap();
+        assert_eq!(matched.request.reference, newer.reference);
+        assert!(!matched.inferred);
+        // Without a reference a one-sided payment pays the oldest request with the exact amount
+        let matched = find_matching_request(&requests, 100.into(), "", true, now).unwrap();
+        assert_eq!(matched.request.reference, older.reference);
+        assert!(matched.inferred);
+        // An interactive payment without a reference is not matched by amount
+        assert!(find_matching_request(&requests, 100.into(), "", false, now).is_none());
+        // Expired and closed requests are never matched
+        let message = format!("[{}]", expired.reference);
+        assert!(find_matching_request(&requests, 250.into(), &message, true, now).is_none());
+        assert!(find_matching_request(&requests, 300.into(), "", true, now).is_none());
+    }
+}
@@ -0,0 +1,388 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Payments that the transaction service sends at a later time or block height, once or on a recurring interval.
+
+use std::{
+    convert::TryFrom,
+    fmt,
+    fmt::{Display, Formatter},
+    time::Duration,
+};
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use tari_common_types::{tari_address::TariAddress, transaction::TxId};
+use tari_core::transactions::tari_amount::MicroMinotari;
+use thiserror::Error;
+
+/// The number of consecutive failed attempts after which an occurrence is given up on
+pub const MAX_SCHEDULED_PAYMENT_ATTEMPTS: u32 = 3;
+/// The time to wait before retrying a failed attempt
+pub const SCHEDULED_PAYMENT_RETRY_DELAY: Duration = Duration::from_secs(10 * 60);
+/// The shortest interval allowed between recurring payments
+pub const MIN_SCHEDULED_PAYMENT_INTERVAL: Duration = Duration::from_secs(60);
+
+pub type ScheduledPaymentId = u64;
+
+#[derive(Debug, Error)]
+pub enum ScheduledPaymentError {
+    #[error("Invalid scheduled payment: {0}")]
+    InvalidSchedule(String),
+    #[error("Scheduled payment {0} not found")]
+    NotFound(ScheduledPaymentId),
+    #[error("Scheduled payment {0} is {1}")]
+    NotActive(ScheduledPaymentId, ScheduledPaymentStatus),
+    #[error("Unknown scheduled payment status {0}")]
+    UnknownStatus(i32),
+}
+
+/// When a scheduled payment is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaymentSchedule {
+    /// Once, at or after the given time
+    AtTime(NaiveDateTime),
+    /// Once, when the chain reaches the given height
+    AtHeight(u64),
+    /// Every `interval`, starting at `start`
+    Recurring { start: NaiveDateTime, interval: Duration },
+}
+
+impl Display for PaymentSchedule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PaymentSchedule::AtTime(time) => write!(f, "at {}", time.format("%Y-%m-%d %H:%M:%S")),
+            PaymentSchedule::AtHeight(height) => write!(f, "at height {}", height),
+            PaymentSchedule::Recurring { start, interval } => write!(
+                f,
+                "every {}s from {}",
+                interval.as_secs(),
+                start.format("%Y-%m-%d %H:%M:%S")
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ScheduledPaymentStatus {
+    /// Waiting for the next occurrence
+    Active,
+    /// All payments have been sent
+    Completed,
+    Cancelled,
+    /// A one-off payment could not be sent after the maximum number of attempts
+    Failed,
+}
+
+impl Display for ScheduledPaymentStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduledPaymentStatus::Active => write!(f, "Active"),
+            ScheduledPaymentStatus::Completed => write!(f, "Completed"),
+            ScheduledPaymentStatus::Cancelled => write!(f, "Cancelled"),
+            ScheduledPaymentStatus::Failed => write!(f, "Failed"),
+        }
+    }
+}
+
+impl From<ScheduledPaymentStatus> for i32 {
+    fn from(status: ScheduledPaymentStatus) -> Self {
+        match status {
+            ScheduledPaymentStatus::Active => 0,
+            ScheduledPaymentStatus::Completed => 1,
+            ScheduledPaymentStatus::Cancelled => 2,
+            ScheduledPaymentStatus::Failed => 3,
+        }
+    }
+}
+
+impl TryFrom<i32> for ScheduledPaymentStatus {
+    type Error = ScheduledPaymentError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ScheduledPaymentStatus::Active),
+            1 => Ok(ScheduledPaymentStatus::Completed),
+            2 => Ok(ScheduledPaymentStatus::Cancelled),
+            3 => Ok(ScheduledPaymentStatus::Failed),
+            v => Err(ScheduledPaymentError::UnknownStatus(v)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledPayment {
+    pub id: ScheduledPaymentId,
+    pub destination: TariAddress,
+    pub amount: MicroMinotari,
+    pub fee_per_gram: MicroMinotari,
+    pub message: String,
+    /// Send a one-sided instead of an interactive transaction
+    pub one_sided: bool,
+    pub schedule: PaymentSchedule,
+    /// The number of payments after which a recurring payment completes, or `None` to continue until cancelled
+    pub max_runs: Option<u64>,
+    /// The time of the next attempt. `None` for a height based payment that has not failed yet.
+    pub next_run_at: Option<NaiveDateTime>,
+    /// The number of payments that have been sent
+    pub run_count: u64,
+    /// The number of failed attempts for the current occurrence
+    pub attempts: u32,
+    pub status: ScheduledPaymentStatus,
+    /// The transaction of the occurrence that is being sent. It is stored before the transaction is created, so that
+    /// an occurrence that was interrupted by a restart can be reconciled instead of sent again.
+    pub in_flight_tx_id: Option<TxId>,
+    pub last_tx_id: Option<TxId>,
+    pub last_error: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl ScheduledPayment {
+    /// Creates an active payment. The id is assigned when the payment is stored.
+    pub fn new(
+        destination: TariAddress,
+        amount: MicroMinotari,
+        fee_per_gram: MicroMinotari,
+        message: String,
+        one_sided: bool,
+        schedule: PaymentSchedule,
+        max_runs: Option<u64>,
+        created_at: NaiveDateTime,
+    ) -> Self {
+        let next_run_at = match schedule {
+            PaymentSchedule::AtTime(time) => Some(time),
+            PaymentSchedule::AtHeight(_) => None,
+            PaymentSchedule::Recurring { start, .. } => Some(start),
+        };
+        Self {
+            id: 0,
+            destination,
+            amount,
+            fee_per_gram,
+            message,
+            one_sided,
+            schedule,
+            max_runs,
+            next_run_at,
+            run_count: 0,
+            attempts: 0,
+            status: ScheduledPaymentStatus::Active,
+            in_flight_tx_id: None,
+            last_tx_id: None,
+            last_error: None,
+            created_at,
+        }
+    }
+
+    /// Returns true if the payment should be sent now
+    pub fn is_due(&self, now: NaiveDateTime, tip_height: Option<u64>) -> bool {
+        if self.status != ScheduledPaymentStatus::Active ||
+            self.in_flight_tx_id.is_some() ||
+            self.next_run_at.map_or(false, |next| next > now)
+        {
+            return false;
+        }
+        match self.schedule {
+            PaymentSchedule::AtHeight(height) => tip_height.map_or(false, |tip| tip >= height),
+            PaymentSchedule::AtTime(_) | PaymentSchedule::Recurring { .. } => true,
+        }
+    }
+
+    /// Records a payment that was sent and moves on to the next occurrence
+    pub fn record_success(&mut self, tx_id: TxId, now: NaiveDateTime) {
+        self.in_flight_tx_id = None;
+        self.run_count += 1;
+        self.attempts = 0;
+        self.last_tx_id = Some(tx_id);
+        self.last_error = None;
+        self.advance(now);
+    }
+
+    /// Records a failed attempt. The payment is retried after [SCHEDULED_PAYMENT_RETRY_DELAY] until
+    /// [MAX_SCHEDULED_PAYMENT_ATTEMPTS] is reached, after which a one-off payment fails and a recurring payment skips
+    /// to its next occurrence.
+    pub fn record_failure(&mut self, error: String, now: NaiveDateTime) {
+        self.in_flight_tx_id = None;
+        self.attempts += 1;
+        self.last_error = Some(error);
+        if self.attempts < MAX_SCHEDULED_PAYMENT_ATTEMPTS {
+            self.next_run_at = Some(now + chrono_duration(SCHEDULED_PAYMENT_RETRY_DELAY));
+            return;
+        }
+        self.attempts = 0;
+        match self.schedule {
+            PaymentSchedule::Recurring { .. } => self.advance(now),
+            PaymentSchedule::AtTime(_) | PaymentSchedule::AtHeight(_) => {
+                self.status = ScheduledPaymentStatus::Failed;
+                self.next_run_at = None;
+            },
+        }
+    }
+
+    fn advance(&mut self, now: NaiveDateTime) {
+        let runs_remaining = self.max_runs.map_or(true, |max| self.run_count < max);
+        match self.schedule {
+            PaymentSchedule::Recurring { start, interval } if runs_remaining => {
+                self.next_run_at = Some(next_occurrence(start, interval, now));
+            },
+            _ => {
+                self.status = ScheduledPaymentStatus::Completed;
+                self.next_run_at = None;
+            },
+        }
+    }
+}
+
+impl Display for ScheduledPayment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{} {} to {} {} ({})",
+            self.id,
+            self.amount,
+            self.destination.to_hex(),
+            self.schedule,
+            self.status
+        )
+    }
+}
+
+/// The first occurrence after `now` of a schedule that starts at `start` and repeats every `interval`. Occurrences that
+/// were missed, e.g. while the wallet was offline, are skipped rather than paid in a burst.
+fn next_occurrence(start: NaiveDateTime, interval: Duration, now: NaiveDateTime) -> NaiveDateTime {
+    if now < start {
+        return start;
+    }
+    let interval_secs = i64::try_from(interval.as_secs()).unwrap_or(i64::MAX).max(1);
+    let elapsed_intervals = (now - start).num_seconds() / interval_secs;
+    start + chrono::Duration::seconds((elapsed_intervals + 1).saturating_mul(interval_secs))
+}
+
+fn chrono_duration(duration: Duration) -> chrono::Duration {
+    chrono::Duration::from_std(duration).unwrap_or_else(|_| chrono::Duration::max_value())
+}
+
+#[cfg(test)]
+mod test {
+    use rand::rngs::OsRng;
+    use tari_common::configuration::Network;
+    use tari_common_types::types::{PrivateKey, PublicKey};
+    use tari_crypto::keys::{PublicKey as PublicKeyTrait, SecretKey};
+
+    use super::*;
+
+    fn time(secs: i64) -> NaiveDateTime {
+        NaiveDateTime::from_timestamp_opt(1_694_000_000 + secs, 0).unwrap()
+    }
+
+    fn payment(schedule: PaymentSchedule, max_runs: Option<u64>) -> ScheduledPayment {
+        let destination = TariAddress::new(
+            PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+            Network::LocalNet,
+        );
+        ScheduledPayment::new(
+            destination,
+            1000.into(),
+            5.into(),
+            String::new(),
+            true,
+            schedule,
+            max_runs,
+            time(0),
+        )
+    }
+
+    #[test]
+    fn it_is_due_at_the_scheduled_time_or_height() {
+        let at_time = payment(PaymentSchedule::AtTime(time(100)), None);
+        assert!(!at_time.is_due(time(99), None));
+        assert!(at_time.is_due(time(100), None));
+
+        let mut at_height = payment(PaymentSchedule::AtHeight(50), None);
+        assert!(!at_height.is_due(time(0), None));
+        assert!(!at_height.is_due(time(0), Some(49)));
+        assert!(at_height.is_due(time(0), Some(50)));
+        // A failed attempt is retried after the delay, even though the height has been reached
+        at_height.record_failure("Insufficient funds".to_string(), time(0));
+        assert!(!at_height.is_due(time(1), Some(51)));
+        assert!(at_height.is_due(time(600), Some(51)));
+    }
+
+    #[test]
+    fn it_is_not_due_while_an_occurrence_is_in_flight() {
+        let mut at_time = payment(PaymentSchedule::AtTime(time(0)), None);
+        at_time.in_flight_tx_id = Some(TxId::from(1u64));
+        assert!(!at_time.is_due(time(0), None));
+
+        at_time.record_failure("Insufficient funds".to_string(), time(0));
+        assert_eq!(at_time.in_flight_tx_id, None);
+        assert!(at_time.is_due(time(600), None));
+
+        at_time.in_flight_tx_id = Some(TxId::from(2u64));
+        at_time.record_success(TxId::from(2u64), time(600));
+        assert_eq!(at_time.in_flight_tx_id, None);
+        assert_eq!(at_time.last_tx_id, Some(TxId::from(2u64)));
+    }
+
+    #[test]
+    fn it_advances_recurring_payments() {
+        let interval = Duration::from_secs(3600);
+        let mut recurring = payment(
+            PaymentSchedule::Recurring {
+                start: time(0),
+                interval,
+            },
+            Some(2),
+        );
+        assert!(recurring.is_due(time(0), None));
+
+        recurring.record_success(TxId::from(1u64), time(10));
+        assert_eq!(recurring.next_run_at, Some(time(3600)));
+        assert_eq!(recurring.status, ScheduledPaymentStatus::Active);
+
+        // Missed occurrences are skipped
+        recurring.record_success(TxId::from(2u64), time(3 * 3600 + 5));
+        assert_eq!(recurring.run_count, 2);
+        assert_eq!(recurring.status, ScheduledPaymentStatus::Completed);
+        assert_eq!(recurring.next_run_at, None);
+
+        let mut unlimited = payment(
+            PaymentSchedule::Recurring {
+                start: time(0),
+                interval,
+            },
+            None,
+        );
+        unlimited.record_success(TxId::from(3u64), time(3 * 3600 + 5));
+        assert_eq!(unlimited.next_run_at, Some(time(4 * 3600)));
+    }
+
+    #[test]
+    fn it_gives_up_after_the_maximum_number_of_attempts() {
+        let mut one_off = payment(PaymentSchedule::AtTime(time(0)), None);
+        let mut recurring = payment(
+            PaymentSchedule::Recurring {
+                start: time(0),
+                interval: Duration::from_secs(86_400),
+            },
+            None,
+        );
+        for attempt in 1..MAX_SCHEDULED_PAYMENT_ATTEMPTS {
+            one_off.record_failure("Insufficient funds".to_string(), time(0));
+            recurring.record_failure("Insufficient funds".to_string(), time(0));
+            assert_eq!(one_off.attempts, attempt);
+            assert_eq!(one_off.next_run_at, Some(time(600)));
+            assert_eq!(recurring.next_run_at, Some(time(600)));
+        }
+
+        one_off.record_failure("Insufficient funds".to_string(), time(600));
+        assert_eq!(one_off.status, ScheduledPaymentStatus::Failed);
+        assert_eq!(one_off.last_error.as_deref(), Some("Insufficient funds"));
+
+        recurring.record_failure("Insufficient funds".to_string(), time(600));
+        assert_eq!(recurring.status, ScheduledPaymentStatus::Active);
+        assert_eq!(recurring.attempts, 0);
+        assert_eq!(recurring.run_count, 0);
+        assert_eq!(recurring.next_run_at, Some(time(86_400)));
+    }
+}
@@ -21,7 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     convert::TryInto,
     sync::Arc,
     time::{Duration, Instant},
@@ -53,7 +53,7 @@ use tari_core::{
     },
     proto::base_node as base_node_proto,
     transactions::{
-        key_manager::TransactionKeyManagerInterface,
+        key_manager::{TransactionKeyManagerBranch, TransactionKeyManagerInterface},
         tari_amount::MicroMinotari,
         transaction_components::{
             CodeTemplateRegistration,
@@ -71,6 +71,7 @@ use tari_core::{
         },
         CryptoFactories,
         ReceiverTransactionProtocol,
+        SenderTransactionProtocol,
     },
 };
 use tari_crypto::{
@@ -85,14 +86,17 @@ use tari_shutdown::ShutdownSignal;
 use tokio::{
     sync::{mpsc, mpsc::Sender, oneshot, Mutex},
     task::JoinHandle,
+    time::MissedTickBehavior,
 };
 
 use crate::{
     base_node_service::handle::{BaseNodeEvent, BaseNodeServiceHandle},
     connectivity_service::WalletConnectivityInterface,
     output_manager_service::{
+        dust_consolidation::DustConsolidation,
+        error::OutputManagerError,
         handle::{OutputManagerEvent, OutputManagerHandle},
-        storage::models::SpendingPriority,
+        storage::{models::SpendingPriority, OutputStatus},
         UtxoSelectionCriteria,
     },
     storage::database::{WalletBackend, WalletDatabase},
@@ -106,15 +110,37 @@ use crate::{
             TransactionServiceRequest,
             TransactionServiceResponse,
         },
+        payment_request::{find_matching_request, PaymentRequest, PaymentRequestError, PaymentRequestStatus},
         protocols::{
             transaction_broadcast_protocol::TransactionBroadcastProtocol,
             transaction_receive_protocol::{TransactionReceiveProtocol, TransactionReceiveProtocolStage},
             transaction_send_protocol::{TransactionSendProtocol, TransactionSendProtocolStage},
             transaction_validation_protocol::TransactionValidationProtocol,
         },
+        scheduled_payment::{
+            PaymentSchedule,
+            ScheduledPayment,
+            ScheduledPaymentError,
+            ScheduledPaymentId,
+            ScheduledPaymentStatus,
+            MIN_SCHEDULED_PAYMENT_INTERVAL,
+        },
+        spending_policy::{
+            pending_spend_reference,
+            spending_window_start,
+            unresolved_pending_spends,
+            PendingSpend,
+            PendingSpendId,
+            PolicyDecision,
+            SpendDecision,
+            SpendKind,
+            SpendingDecisionRecord,
+            SpendingPolicy,
+            SpendingPolicyError,
+        },
         storage::{
             database::{TransactionBackend, TransactionDatabase},
-            models::{CompletedTransaction, TxCancellationReason},
+            models::{CompletedTransaction, LabelTarget, TransactionLabels, TxCancellationReason},
         },
         tasks::{
             check_faux_transaction_status::check_faux_transactions,
@@ -123,6 +149,7 @@ use crate::{
             send_transaction_reply::send_transaction_reply,
         },
         utc::utc_duration_since,
+        vault::{hot_path_input_data, vault_script, Vault, VaultError, VaultId, VaultStatus},
     },
     util::{wallet_identity::WalletIdentity, watch::Watch},
     utxo_scanner_service::RECOVERY_KEY,
@@ -130,6 +157,14 @@ use crate::{
 };
 
 const LOG_TARGET: &str = "wallet::transaction_service::service";
+const MAX_LABEL_LENGTH: usize = 256;
+const MAX_TAG_LENGTH: usize = 64;
+const PAYMENT_REQUEST_EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const SCHEDULED_PAYMENT_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const DUST_CONSOLIDATION_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// The outcome of an interactive scheduled payment, resolved once the send protocol has sent the transaction
+type ScheduledPaymentResult = (ScheduledPaymentId, Result<TxId, TransactionServiceError>);
 
 /// TransactionService allows for the management of multiple inbound and outbound transaction protocols
 /// which are uniquely identified by a tx_id. The TransactionService generates and accepts the various protocol
@@ -156,6 +191,7 @@ pub struct TransactionService<
     TKeyManagerInterface,
 > {
     config: TransactionServiceConfig,
+    spending_policy: SpendingPolicy,
     db: TransactionDatabase<TBackend>,
     transaction_stream: Option<TTxStream>,
     transaction_reply_stream: Option<TTxReplyStream>,
@@ -173,6 +209,9 @@ pub struct TransactionService<
     finalized_transaction_senders: HashMap<TxId, Sender<(TariAddress, TxId, Transaction)>>,
     receiver_transaction_cancellation_senders: HashMap<TxId, oneshot::Sender<()>>,
     active_transaction_broadcast_protocols: HashSet<TxId>,
+    active_scheduled_payments: HashSet<ScheduledPaymentId>,
+    /// Transactions held by the spending policy until they are approved or rejected
+    pending_spends: BTreeMap<PendingSpendId, (PendingSpend, TransactionServiceRequest)>,
     timeout_update_watch: Watch<Duration>,
     wallet_db: WalletDatabase<TWalletBackend>,
     base_node_service: BaseNodeServiceHandle,
@@ -217,6 +256,7 @@ where
 {
     pub fn new(
         config: TransactionServiceConfig,
+        spending_policy: SpendingPolicy,
         db: TransactionDatabase<TBackend>,
         wallet_db: WalletDatabase<TWalletBackend>,
         request_stream: Receiver<
@@ -263,6 +303,7 @@ where
 
         Self {
             config,
+            spending_policy,
             db,
             transaction_stream: Some(transaction_stream),
             transaction_reply_stream: Some(transaction_reply_stream),
@@ -278,6 +319,8 @@ where
             finalized_transaction_senders: HashMap::new(),
             receiver_transaction_cancellation_senders: HashMap::new(),
             active_transaction_broadcast_protocols: HashSet::new(),
+            active_scheduled_payments: HashSet::new(),
+            pending_spends: BTreeMap::new(),
             timeout_update_watch,
             base_node_service,
             wallet_db,
@@ -354,6 +397,23 @@ where
         let mut base_node_service_event_stream = self.base_node_service.get_event_stream();
         let mut output_manager_event_stream = self.resources.output_manager_service.get_event_stream();
 
+        let mut scheduled_payment_handles: FuturesUnordered<JoinHandle<ScheduledPaymentResult>> =
+            FuturesUnordered::new();
+
+        let mut payment_request_expiry_interval = tokio::time::interval(PAYMENT_REQUEST_EXPIRY_CHECK_INTERVAL);
+        payment_request_expiry_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut scheduled_payment_interval = tokio::time::interval(SCHEDULED_PAYMENT_CHECK_INTERVAL);
+        scheduled_payment_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut dust_consolidation_interval = tokio::time::interval(DUST_CONSOLIDATION_CHECK_INTERVAL);
+        dust_consolidation_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        if let Err(e) = self.expire_held_spends() {
+            warn!(target: LOG_TARGET, "Could not expire the spends held before the wallet stopped: {}", e);
+        }
+        if let Err(e) = self.reconcile_scheduled_payments() {
+            warn!(target: LOG_TARGET, "Could not reconcile the scheduled payments being sent: {}", e);
+        }
+
         debug!(target: LOG_TARGET, "Transaction Service started");
         loop {
             tokio::select! {
@@ -553,6 +613,21 @@ where
                         Err(e) => error!(target: LOG_TARGET, "Error resolving Transaction Validation protocol: {:?}", e),
                     };
                 }
+                Some(join_result) = scheduled_payment_handles.next() => {
+                    match join_result {
+                        Ok((id, result)) => self.complete_scheduled_payment(id, result),
+                        Err(e) => error!(target: LOG_TARGET, "Error resolving scheduled payment: {:?}", e),
+                    };
+                }
+                _ = payment_request_expiry_interval.tick() => self.expire_payment_requests(),
+                _ = scheduled_payment_interval.tick() => self.send_due_scheduled_payments(
+                    &mut send_transaction_protocol_handles,
+                    &mut transaction_broadcast_protocol_handles,
+                    &mut scheduled_payment_handles,
+                ).await,
+                _ = dust_consolidation_interval.tick() => self.consolidate_dust(
+                    &mut transaction_broadcast_protocol_handles,
+                ).await,
                  _ = shutdown.wait() => {
                     info!(target: LOG_TARGET, "Transaction service shutting down because it received the shutdown signal");
                     break;
@@ -585,6 +660,25 @@ where
         let mut reply_channel = Some(reply_channel);
 
         trace!(target: LOG_TARGET, "Handling Service Request: {}", request);
+        // An approved spend is dispatched as the request that was held
+        let request = match request {
+            TransactionServiceRequest::ApprovePendingSpend(id) => self.approve_pending_spend(id),
+            request => self.apply_spending_policy(request),
+        };
+        let request = match request {
+            Ok(request) => request,
+            Err(e) => {
+                let _result = reply_channel
+                    .take()
+                    .expect("Cannot be missing")
+                    .send(Err(e))
+                    .map_err(|e| {
+                        warn!(target: LOG_TARGET, "Failed to send reply");
+                        e
+                    });
+                return Ok(());
+            },
+        };
         let response = match request {
             TransactionServiceRequest::SendTransaction {
                 destination,
@@ -596,6 +690,7 @@ where
             } => {
                 let rp = reply_channel.take().expect("Cannot be missing");
                 self.send_transaction(
+                    TxId::new_random(),
                     destination,
                     amount,
                     selection_criteria,
@@ -615,14 +710,17 @@ where
                 amount,
                 selection_criteria,
                 output_features,
+                covenant,
                 fee_per_gram,
                 message,
             } => self
                 .send_one_sided_transaction(
+                    TxId::new_random(),
                     destination,
                     amount,
                     selection_criteria,
                     *output_features,
+                    *covenant,
                     fee_per_gram,
                     message,
                     transaction_broadcast_join_handles,
@@ -634,6 +732,7 @@ where
                 amount,
                 selection_criteria,
                 output_features,
+                covenant,
                 fee_per_gram,
                 message,
             } => self
@@ -642,6 +741,7 @@ where
                     amount,
                     selection_criteria,
                     *output_features,
+                    *covenant,
                     fee_per_gram,
                     message,
                     transaction_broadcast_join_handles,
@@ -813,51 +913,1242 @@ where
                 self.set_power_mode(PowerMode::Low).await?;
                 Ok(TransactionServiceResponse::LowPowerModeSet)
             },
-            TransactionServiceRequest::SetNormalPowerMode => {
-                self.set_power_mode(PowerMode::Normal).await?;
-                Ok(TransactionServiceResponse::NormalPowerModeSet)
+            TransactionServiceRequest::SetNormalPowerMode => {
+                self.set_power_mode(PowerMode::Normal).await?;
+                Ok(TransactionServiceResponse::NormalPowerModeSet)
+            },
+            TransactionServiceRequest::RestartTransactionProtocols => self
+                .restart_transaction_negotiation_protocols(
+                    send_transaction_join_handles,
+                    receive_transaction_join_handles,
+                )
+                .map(|_| TransactionServiceResponse::ProtocolsRestarted),
+            TransactionServiceRequest::RestartBroadcastProtocols => self
+                .restart_broadcast_protocols(transaction_broadcast_join_handles)
+                .map(|_| TransactionServiceResponse::ProtocolsRestarted),
+            TransactionServiceRequest::GetNumConfirmationsRequired => Ok(
+                TransactionServiceResponse::NumConfirmationsRequired(self.resources.config.num_confirmations_required),
+            ),
+            TransactionServiceRequest::SetNumConfirmationsRequired(number) => {
+                self.resources.config.num_confirmations_required = number;
+                Ok(TransactionServiceResponse::NumConfirmationsSet)
+            },
+            TransactionServiceRequest::ValidateTransactions => self
+                .start_transaction_validation_protocol(transaction_validation_join_handles)
+                .await
+                .map(TransactionServiceResponse::ValidationStarted),
+            TransactionServiceRequest::ReValidateTransactions => self
+                .start_transaction_revalidation(transaction_validation_join_handles)
+                .await
+                .map(TransactionServiceResponse::ValidationStarted),
+            TransactionServiceRequest::GetFeePerGramStatsPerBlock { count } => {
+                let reply_channel = reply_channel.take().expect("reply_channel is Some");
+                self.handle_get_fee_per_gram_stats_per_block_request(count, reply_channel);
+                return Ok(());
+            },
+            TransactionServiceRequest::SetLabels(target, labels) => self
+                .set_labels(target, labels)
+                .map(|_| TransactionServiceResponse::LabelsSet),
+            TransactionServiceRequest::GetLabels(target) => {
+                Ok(TransactionServiceResponse::Labels(self.db.get_labels(&target)?))
+            },
+            TransactionServiceRequest::GetAllTransactionLabels => Ok(TransactionServiceResponse::AllTransactionLabels(
+                self.db.get_all_transaction_labels()?,
+            )),
+            TransactionServiceRequest::CreatePaymentRequest {
+                amount,
+                memo,
+                expires_at,
+            } => self
+                .create_payment_request(amount, memo, expires_at)
+                .map(|request| TransactionServiceResponse::PaymentRequestCreated(Box::new(request))),
+            TransactionServiceRequest::GetPaymentRequests => Ok(TransactionServiceResponse::PaymentRequests(
+                self.db.get_payment_requests()?,
+            )),
+            TransactionServiceRequest::CancelPaymentRequest(reference) => self
+                .cancel_payment_request(&reference)
+                .map(|_| TransactionServiceResponse::PaymentRequestCancelled),
+            TransactionServiceRequest::CreateScheduledPayment {
+                destination,
+                amount,
+                fee_per_gram,
+                message,
+                one_sided,
+                schedule,
+                max_runs,
+            } => self
+                .create_scheduled_payment(
+                    destination,
+                    amount,
+                    fee_per_gram,
+                    message,
+                    one_sided,
+                    schedule,
+                    max_runs,
+                )
+                .map(|payment| TransactionServiceResponse::ScheduledPaymentCreated(Box::new(payment))),
+            TransactionServiceRequest::GetScheduledPayments => Ok(TransactionServiceResponse::ScheduledPayments(
+                self.db.get_scheduled_payments()?,
+            )),
+            TransactionServiceRequest::CancelScheduledPayment(id) => self
+                .cancel_scheduled_payment(id)
+                .map(|_| TransactionServiceResponse::ScheduledPaymentCancelled),
+            TransactionServiceRequest::GetPendingSpends => Ok(TransactionServiceResponse::PendingSpends(
+                self.pending_spends.values().map(|(spend, _)| spend.clone()).collect(),
+            )),
+            // Approvals are resolved to the held request before it is dispatched
+            TransactionServiceRequest::ApprovePendingSpend(id) => {
+                Err(SpendingPolicyError::PendingSpendNotFound(id).into())
+            },
+            TransactionServiceRequest::RejectPendingSpend(id) => self
+                .reject_pending_spend(id)
+                .map(|_| TransactionServiceResponse::PendingSpendRejected),
+            TransactionServiceRequest::CreateVault {
+                amount,
+                recovery_public_key,
+                lock_blocks,
+                selection_criteria,
+                fee_per_gram,
+                message,
+            } => self
+                .create_vault(
+                    amount,
+                    recovery_public_key,
+                    lock_blocks,
+                    selection_criteria,
+                    fee_per_gram,
+                    message,
+                    transaction_broadcast_join_handles,
+                )
+                .await
+                .map(|vault| TransactionServiceResponse::VaultCreated(Box::new(vault))),
+            TransactionServiceRequest::GetVaults => Ok(TransactionServiceResponse::Vaults(self.db.get_vaults()?)),
+            TransactionServiceRequest::SpendVault { id, fee_per_gram } => self
+                .spend_vault(id, fee_per_gram, transaction_broadcast_join_handles)
+                .await
+                .map(TransactionServiceResponse::TransactionSent),
+        };
+
+        // If the individual handlers did not already send the API response then do it here.
+        if let Some(rp) = reply_channel {
+            let _result = rp.send(response).map_err(|e| {
+                warn!(target: LOG_TARGET, "Failed to send reply");
+                e
+            });
+        }
+        Ok(())
+    }
+
+    /// Normalises and stores the labels for a transaction or an output, replacing any existing ones.
+    fn set_labels(&self, target: LabelTarget, labels: TransactionLabels) -> Result<(), TransactionServiceError> {
+        let labels = TransactionLabels::new(labels.label, labels.tags);
+        if labels
+            .label
+            .as_ref()
+            .map_or(false, |l| l.chars().count() > MAX_LABEL_LENGTH)
+        {
+            return Err(TransactionServiceError::InvalidLabel(format!(
+                "Label is longer than {} characters",
+                MAX_LABEL_LENGTH
+            )));
+        }
+        if let Some(tag) = labels
+            .tags
+            .iter()
+            .find(|t| t.chars().count() > MAX_TAG_LENGTH || t.chars().any(char::is_whitespace))
+        {
+            return Err(TransactionServiceError::InvalidLabel(format!(
+                "Tag `{}` must not contain whitespace or be longer than {} characters",
+                tag, MAX_TAG_LENGTH
+            )));
+        }
+        if let LabelTarget::Transaction(tx_id) = target {
+            if !self.db.transaction_exists(tx_id)? {
+                return Err(TransactionServiceError::TransactionDoesNotExistError);
+            }
+        }
+        self.db.set_labels(&target, labels)?;
+        debug!(target: LOG_TARGET, "Updated labels for {}", target);
+        Ok(())
+    }
+
+    /// Issues a payment request to the wallet's own address
+    fn create_payment_request(
+        &self,
+        amount: MicroMinotari,
+        memo: String,
+        expires_at: Option<NaiveDateTime>,
+    ) -> Result<PaymentRequest, TransactionServiceError> {
+        let now = Utc::now().naive_utc();
+        let memo = memo.trim().to_string();
+        if amount == MicroMinotari::zero() {
+            return Err(PaymentRequestError::InvalidRequest("The amount must be greater than zero".to_string()).into());
+        }
+        if memo.chars().count() > MAX_LABEL_LENGTH {
+            return Err(PaymentRequestError::InvalidRequest(format!(
+                "The memo is longer than {} characters",
+                MAX_LABEL_LENGTH
+            ))
+            .into());
+        }
+        if expires_at.map_or(false, |expires_at| expires_at <= now) {
+            return Err(PaymentRequestError::InvalidRequest("The expiry must be in the future".to_string()).into());
+        }
+        let request = PaymentRequest::new(
+            self.resources.wallet_identity.address.clone(),
+            amount,
+            memo,
+            now,
+            expires_at,
+        );
+        self.db.insert_payment_request(&request)?;
+        debug!(target: LOG_TARGET, "Created payment request {}", request);
+        Ok(request)
+    }
+
+    fn cancel_payment_request(&self, reference: &str) -> Result<(), TransactionServiceError> {
+        let request = self
+            .db
+            .get_payment_request(reference)?
+            .ok_or_else(|| PaymentRequestError::NotFound(reference.to_string()))?;
+        if !request.status.is_open() {
+            return Err(PaymentRequestError::NotOpen(request.reference, request.status).into());
+        }
+        self.db
+            .set_payment_request_status(reference, PaymentRequestStatus::Cancelled)?;
+        self.publish_payment_request_update(request.reference, PaymentRequestStatus::Cancelled);
+        Ok(())
+    }
+
+    /// Matches an incoming payment that has been mined to an open payment request. Failures are only logged so that
+    /// they never affect the handling of the payment itself.
+    fn match_payment_request(&self, tx_id: TxId, amount: MicroMinotari, message: &str, one_sided: bool) {
+        match self.try_match_payment_request(tx_id, amount, message, one_sided) {
+            Ok(Some((reference, status))) => {
+                info!(
+                    target: LOG_TARGET,
+                    "Transaction {} of {} matched to payment request {} which is now {}", tx_id, amount, reference, status
+                );
+                self.publish_payment_request_update(reference, status);
+            },
+            Ok(None) => {},
+            Err(e) => warn!(
+                target: LOG_TARGET,
+                "Could not match transaction {} to a payment request: {}", tx_id, e
+            ),
+        }
+    }
+
+    fn try_match_payment_request(
+        &self,
+        tx_id: TxId,
+        amount: MicroMinotari,
+        message: &str,
+        one_sided: bool,
+    ) -> Result<Option<(String, PaymentRequestStatus)>, TransactionServiceError> {
+        let requests = self.db.get_payment_requests()?;
+        let matched = match find_matching_request(&requests, amount, message, one_sided, Utc::now().naive_utc()) {
+            Some(matched) => matched,
+            None => return Ok(None),
+        };
+        let status = matched.request.status_after_payment(amount);
+        if self.db.record_payment_request_payment(
+            &matched.request.reference,
+            tx_id,
+            amount,
+            matched.inferred,
+            status,
+        )? {
+            Ok(Some((matched.request.reference.clone(), status)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Brings payment requests in line with the chain after a transaction validation. Payments whose transaction is
+    /// no longer mined, because it was cancelled, rejected or reorged out, are reverted, and interactive payments that
+    /// have been mined since the last validation are matched.
+    fn reconcile_payment_requests(&self) -> Result<(), TransactionServiceError> {
+        let completed_transactions = self.db.get_completed_transactions()?;
+        let is_mined = |tx_id: &TxId| {
+            completed_transactions.get(tx_id).map_or(false, |tx| {
+                matches!(
+                    tx.status,
+                    TransactionStatus::MinedUnconfirmed |
+                        TransactionStatus::MinedConfirmed |
+                        TransactionStatus::FauxUnconfirmed |
+                        TransactionStatus::FauxConfirmed
+                )
+            })
+        };
+
+        let matched_tx_ids = self.db.get_payment_request_payment_tx_ids()?;
+        for tx_id in matched_tx_ids.iter().filter(|tx_id| !is_mined(tx_id)) {
+            if let Some((reference, status)) = self.db.revert_payment_request_payment(*tx_id)? {
+                info!(
+                    target: LOG_TARGET,
+                    "Transaction {} is no longer mined, payment request {} is now {}", tx_id, reference, status
+                );
+                self.publish_payment_request_update(reference, status);
+            }
+        }
+
+        // One-sided payments are matched when they are found on the chain
+        for tx in completed_transactions.values().filter(|tx| {
+            tx.direction == TransactionDirection::Inbound &&
+                matches!(
+                    tx.status,
+                    TransactionStatus::MinedUnconfirmed | TransactionStatus::MinedConfirmed
+                ) &&
+                !matched_tx_ids.contains(&tx.tx_id)
+        }) {
+            self.match_payment_request(tx.tx_id, tx.amount, &tx.message, false);
+        }
+        Ok(())
+    }
+
+    /// Marks all open payment requests that have passed their expiry as expired
+    fn expire_payment_requests(&self) {
+        let now = Utc::now().naive_utc();
+        let requests = match self.db.get_payment_requests() {
+            Ok(requests) => requests,
+            Err(e) => {
+                warn!(target: LOG_TARGET, "Could not check payment requests for expiry: {}", e);
+                return;
+            },
+        };
+        for request in requests
+            .into_iter()
+            .filter(|r| r.status.is_open() && r.is_expired_at(now))
+        {
+            match self
+                .db
+                .set_payment_request_status(&request.reference, PaymentRequestStatus::Expired)
+            {
+                Ok(()) => {
+                    debug!(target: LOG_TARGET, "Payment request {} expired", request.reference);
+                    self.publish_payment_request_update(request.reference, PaymentRequestStatus::Expired);
+                },
+                Err(e) => warn!(
+                    target: LOG_TARGET,
+                    "Could not expire payment request {}: {}", request.reference, e
+                ),
+            }
+        }
+    }
+
+    fn publish_payment_request_update(&self, reference: String, status: PaymentRequestStatus) {
+        let _size = self
+            .event_publisher
+            .send(Arc::new(TransactionEvent::PaymentRequestUpdated { reference, status }))
+            .map_err(|e| {
+                trace!(
+                    target: LOG_TARGET,
+                    "Error sending event, usually because there are no subscribers: {:?}",
+                    e
+                );
+                e
+            });
+    }
+
+    fn create_scheduled_payment(
+        &self,
+        destination: TariAddress,
+        amount: MicroMinotari,
+        fee_per_gram: MicroMinotari,
+        message: String,
+        one_sided: bool,
+        schedule: PaymentSchedule,
+        max_runs: Option<u64>,
+    ) -> Result<ScheduledPayment, TransactionServiceError> {
+        if destination.network() != self.resources.wallet_identity.network {
+            return Err(TransactionServiceError::InvalidNetwork);
+        }
+        if amount == MicroMinotari::zero() {
+            return Err(
+                ScheduledPaymentError::InvalidSchedule("The amount must be greater than zero".to_string()).into(),
+            );
+        }
+        match schedule {
+            PaymentSchedule::Recurring { interval, .. } if interval < MIN_SCHEDULED_PAYMENT_INTERVAL => {
+                return Err(ScheduledPaymentError::InvalidSchedule(format!(
+                    "The interval must be at least {} seconds",
+                    MIN_SCHEDULED_PAYMENT_INTERVAL.as_secs()
+                ))
+                .into());
+            },
+            PaymentSchedule::Recurring { .. } => {},
+            PaymentSchedule::AtTime(_) | PaymentSchedule::AtHeight(_) if max_runs.is_some() => {
+                return Err(ScheduledPaymentError::InvalidSchedule(
+                    "A maximum number of runs only applies to recurring payments".to_string(),
+                )
+                .into());
+            },
+            PaymentSchedule::AtTime(_) | PaymentSchedule::AtHeight(_) => {},
+        }
+        if max_runs == Some(0) {
+            return Err(ScheduledPaymentError::InvalidSchedule(
+                "The maximum number of runs must be at least 1".to_string(),
+            )
+            .into());
+        }
+        let payment = self.db.insert_scheduled_payment(ScheduledPayment::new(
+            destination,
+            amount,
+            fee_per_gram,
+            message,
+            one_sided,
+            schedule,
+            max_runs,
+            Utc::now().naive_utc(),
+        ))?;
+        info!(target: LOG_TARGET, "Created scheduled payment {}", payment);
+        Ok(payment)
+    }
+
+    fn cancel_scheduled_payment(&self, id: ScheduledPaymentId) -> Result<(), TransactionServiceError> {
+        let payment = self
+            .db
+            .get_scheduled_payment(id)?
+            .ok_or(ScheduledPaymentError::NotFound(id))?;
+        if payment.status != ScheduledPaymentStatus::Active {
+            return Err(ScheduledPaymentError::NotActive(id, payment.status).into());
+        }
+        self.db.update_scheduled_payment(&ScheduledPayment {
+            status: ScheduledPaymentStatus::Cancelled,
+            next_run_at: None,
+            ..payment
+        })?;
+        info!(target: LOG_TARGET, "Cancelled scheduled payment #{}", id);
+        Ok(())
+    }
+
+    /// Sends every active scheduled payment that is due. One-sided payments complete immediately, interactive payments
+    /// complete once the send protocol reports that the transaction was sent. The id of the transaction is stored
+    /// with the payment before the transaction is created, see [Self::reconcile_scheduled_payments].
+    async fn send_due_scheduled_payments(
+        &mut self,
+        send_transaction_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<TransactionSendResult, TransactionServiceProtocolError<TxId>>>,
+        >,
+        transaction_broadcast_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<TxId, TransactionServiceProtocolError<TxId>>>,
+        >,
+        scheduled_payment_join_handles: &mut FuturesUnordered<JoinHandle<ScheduledPaymentResult>>,
+    ) {
+        let now = Utc::now().naive_utc();
+        let tip_height = self.last_seen_tip_height;
+        let payments = match self.db.get_scheduled_payments() {
+            Ok(payments) => payments,
+            Err(e) => {
+                warn!(target: LOG_TARGET, "Could not check for due scheduled payments: {}", e);
+                return;
+            },
+        };
+        for mut payment in payments.into_iter().filter(|p| p.is_due(now, tip_height)) {
+            // A payment that is still being sent from a previous check is not sent twice
+            if !self.active_scheduled_payments.insert(payment.id) {
+                continue;
+            }
+            debug!(target: LOG_TARGET, "Sending scheduled payment {}", payment);
+            if let Err(e) = self.authorize_scheduled_payment(&payment) {
+                self.complete_scheduled_payment(payment.id, Err(e));
+                continue;
+            }
+            let tx_id = TxId::new_random();
+            payment.in_flight_tx_id = Some(tx_id);
+            if let Err(e) = self.db.update_scheduled_payment(&payment) {
+                warn!(
+                    target: LOG_TARGET,
+                    "Could not store the transaction of scheduled payment #{}, it is not sent: {}", payment.id, e
+                );
+                let _ = self.active_scheduled_payments.remove(&payment.id);
+                continue;
+            }
+            let selection_criteria = UtxoSelectionCriteria {
+                min_confirmations: self.spending_policy.min_input_confirmations(),
+                ..Default::default()
+            };
+            if payment.one_sided {
+                let result = self
+                    .send_one_sided_transaction(
+                        tx_id,
+                        payment.destination,
+                        payment.amount,
+                        selection_criteria,
+                        OutputFeatures::default(),
+                        Covenant::default(),
+                        payment.fee_per_gram,
+                        payment.message,
+                        transaction_broadcast_join_handles,
+                    )
+                    .await;
+                self.complete_scheduled_payment(payment.id, result);
+                continue;
+            }
+
+            let (reply_sender, reply_receiver) = oneshot::channel();
+            if let Err(e) = self
+                .send_transaction(
+                    tx_id,
+                    payment.destination,
+                    payment.amount,
+                    selection_criteria,
+                    OutputFeatures::default(),
+                    payment.fee_per_gram,
+                    payment.message,
+                    TransactionMetadata::default(),
+                    send_transaction_join_handles,
+                    transaction_broadcast_join_handles,
+                    reply_sender,
+                )
+                .await
+            {
+                self.complete_scheduled_payment(payment.id, Err(e));
+                continue;
+            }
+            let id = payment.id;
+            scheduled_payment_join_handles.push(tokio::spawn(async move {
+                let result = match reply_receiver.await {
+                    Ok(Ok(TransactionServiceResponse::TransactionSent(tx_id))) => Ok(tx_id),
+                    Ok(Ok(_)) => Err(TransactionServiceError::UnexpectedApiResponse),
+                    Ok(Err(e)) => Err(e),
+                    Err(_) => Err(TransactionServiceError::ProtocolChannelError),
+                };
+                (id, result)
+            }));
+        }
+    }
+
+    /// Submits the dust consolidation transactions created by the output manager, if any. The output manager decides
+    /// whether dust consolidation is enabled and worthwhile.
+    async fn consolidate_dust(
+        &mut self,
+        transaction_broadcast_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<TxId, TransactionServiceProtocolError<TxId>>>,
+        >,
+    ) {
+        let consolidations = match self.resources.output_manager_service.consolidate_dust().await {
+            Ok(consolidations) => consolidations,
+            Err(e) => {
+                warn!(target: LOG_TARGET, "Dust consolidation failed: {}", e);
+                return;
+            },
+        };
+        for DustConsolidation { transaction, report } in consolidations {
+            let tx_id = report.tx_id;
+            if let Err(e) = self.submit_transaction_to_self(
+                transaction_broadcast_join_handles,
+                tx_id,
+                transaction,
+                report.fee,
+                report.amount,
+                "Dust consolidation".to_string(),
+            ) {
+                warn!(
+                    target: LOG_TARGET,
+                    "Could not submit dust consolidation transaction {}: {}", tx_id, e
+                );
+                // Release the dust outputs again
+                if let Err(e) = self.resources.output_manager_service.cancel_transaction(tx_id).await {
+                    error!(
+                        target: LOG_TARGET,
+                        "Could not release the outputs of dust consolidation transaction {}: {}", tx_id, e
+                    );
+                }
+                continue;
+            }
+            let _size = self
+                .event_publisher
+                .send(Arc::new(TransactionEvent::DustConsolidated(report)))
+                .map_err(|e| {
+                    trace!(
+                        target: LOG_TARGET,
+                        "Error sending event, usually because there are no subscribers: {:?}",
+                        e
+                    );
+                    e
+                });
+        }
+    }
+
+    /// Records the outcome of sending a scheduled payment and schedules its next attempt or occurrence
+    fn complete_scheduled_payment(&mut self, id: ScheduledPaymentId, result: Result<TxId, TransactionServiceError>) {
+        let _ = self.active_scheduled_payments.remove(&id);
+        let mut payment = match self.db.get_scheduled_payment(id) {
+            Ok(Some(payment)) => payment,
+            Ok(None) => return,
+            Err(e) => {
+                error!(target: LOG_TARGET, "Could not load scheduled payment #{}: {}", id, e);
+                return;
+            },
+        };
+        // The payment may have been cancelled while it was being sent
+        let cancelled = payment.status == ScheduledPaymentStatus::Cancelled;
+        let now = Utc::now().naive_utc();
+        let event = match result {
+            Ok(tx_id) => {
+                info!(target: LOG_TARGET, "Scheduled payment #{} sent as transaction {}", id, tx_id);
+                payment.record_success(tx_id, now);
+                TransactionEvent::ScheduledPaymentExecuted { id, tx_id }
+            },
+            Err(e) => {
+                let reason = match e {
+                    TransactionServiceError::OutputManagerError(
+                        OutputManagerError::NotEnoughFunds | OutputManagerError::FundsPending,
+                    ) => "Insufficient funds".to_string(),
+                    e => e.to_string(),
+                };
+                warn!(target: LOG_TARGET, "Scheduled payment #{} failed: {}", id, reason);
+                payment.record_failure(reason.clone(), now);
+                TransactionEvent::ScheduledPaymentFailed { id, reason }
+            },
+        };
+        if cancelled {
+            payment.status = ScheduledPaymentStatus::Cancelled;
+            payment.next_run_at = None;
+        }
+        if let Err(e) = self.db.update_scheduled_payment(&payment) {
+            error!(target: LOG_TARGET, "Could not update scheduled payment #{}: {}", id, e);
+        }
+        let _size = self.event_publisher.send(Arc::new(event)).map_err(|e| {
+            trace!(
+                target: LOG_TARGET,
+                "Error sending event, usually because there are no subscribers: {:?}",
+                e
+            );
+            e
+        });
+    }
+
+    /// Resolves the scheduled payments that were being sent when the wallet stopped. An occurrence whose transaction
+    /// was stored has been sent, and its transaction is resumed with the other transaction protocols. An occurrence
+    /// whose transaction was never stored was not sent, so it is sent again.
+    fn reconcile_scheduled_payments(&mut self) -> Result<(), TransactionServiceError> {
+        let in_flight = self
+            .db
+            .get_scheduled_payments()?
+            .into_iter()
+            .filter(|p| p.status == ScheduledPaymentStatus::Active)
+            .filter_map(|p| p.in_flight_tx_id.map(|tx_id| (p, tx_id)));
+        for (mut payment, tx_id) in in_flight {
+            if self.db.get_any_transaction(tx_id)?.is_some() {
+                self.complete_scheduled_payment(payment.id, Ok(tx_id));
+            } else if self.db.get_any_cancelled_transaction(tx_id)?.is_some() {
+                self.complete_scheduled_payment(payment.id, Err(TransactionServiceError::TransactionCancelled));
+            } else {
+                info!(
+                    target: LOG_TARGET,
+                    "Scheduled payment #{} was not sent before the wallet stopped", payment.id
+                );
+                payment.in_flight_tx_id = None;
+                self.db.update_scheduled_payment(&payment)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks a send against the spending policy and logs the decision. Payments that need a second approval are held
+    /// and an error with the id of the pending spend is returned; everything else is returned to be dispatched.
+    fn apply_spending_policy(
+        &mut self,
+        mut request: TransactionServiceRequest,
+    ) -> Result<TransactionServiceRequest, TransactionServiceError> {
+        if !self.spending_policy.is_enabled() {
+            return Ok(request);
+        }
+        let min_confirmations = self.spending_policy.min_input_confirmations();
+        let spend = match &mut request {
+            TransactionServiceRequest::SendTransaction {
+                destination,
+                amount,
+                selection_criteria,
+                fee_per_gram,
+                message,
+                ..
+            } => Some((
+                SpendKind::Interactive,
+                Some(destination.clone()),
+                *amount,
+                Some(selection_criteria),
+                *fee_per_gram,
+                message.clone(),
+            )),
+            TransactionServiceRequest::SendOneSidedTransaction {
+                destination,
+                amount,
+                selection_criteria,
+                fee_per_gram,
+                message,
+                ..
+            } => Some((
+                SpendKind::OneSided,
+                Some(destination.clone()),
+                *amount,
+                Some(selection_criteria),
+                *fee_per_gram,
+                message.clone(),
+            )),
+            TransactionServiceRequest::SendOneSidedToStealthAddressTransaction {
+                destination,
+                amount,
+                selection_criteria,
+                fee_per_gram,
+                message,
+                ..
+            } => Some((
+                SpendKind::StealthOneSided,
+                Some(destination.clone()),
+                *amount,
+                Some(selection_criteria),
+                *fee_per_gram,
+                message.clone(),
+            )),
+            TransactionServiceRequest::BurnTari {
+                amount,
+                selection_criteria,
+                fee_per_gram,
+                message,
+                ..
+            } => Some((
+                SpendKind::Burn,
+                None,
+                *amount,
+                Some(selection_criteria),
+                *fee_per_gram,
+                message.clone(),
+            )),
+            TransactionServiceRequest::SendShaAtomicSwapTransaction(
+                destination,
+                amount,
+                selection_criteria,
+                fee_per_gram,
+                message,
+            ) => Some((
+                SpendKind::ShaAtomicSwap,
+                Some(destination.clone()),
+                *amount,
+                Some(selection_criteria),
+                *fee_per_gram,
+                message.clone(),
+            )),
+            // The holder of the recovery key can spend a vault at any time, so the funds are checked as a payment to
+            // the address of that key
+            TransactionServiceRequest::CreateVault {
+                amount,
+                recovery_public_key,
+                selection_criteria,
+                fee_per_gram,
+                message,
+                ..
+            } => Some((
+                SpendKind::Vault,
+                Some(TariAddress::new(
+                    recovery_public_key.clone(),
+                    self.resources.wallet_identity.network,
+                )),
+                *amount,
+                Some(selection_criteria),
+                *fee_per_gram,
+                message.clone(),
+            )),
+            // A withdrawal only needs the hot key, so it is checked like a payment to the wallet itself. Unknown
+            // vaults are left to fail when the request is handled.
+            TransactionServiceRequest::SpendVault { id, fee_per_gram } => {
+                self.db.get_vault(*id)?.map(|vault| {
+                    (
+                        SpendKind::Vault,
+                        Some(self.resources.wallet_identity.address.clone()),
+                        vault.amount,
+                        None,
+                        *fee_per_gram,
+                        format!("Vault #{} withdrawal", id),
+                    )
+                })
+            },
+            // Registrations pay the wallet itself, so only the fee leaves the wallet
+            TransactionServiceRequest::RegisterValidatorNode { .. } |
+            TransactionServiceRequest::RegisterCodeTemplate { .. } |
+            // Scheduled payments are checked every time they are sent
+            TransactionServiceRequest::CreateScheduledPayment { .. } |
+            // Completed transactions and imported outputs do not select inputs from the wallet
+            TransactionServiceRequest::SubmitTransactionToSelf(..) |
+            TransactionServiceRequest::ImportUtxoWithStatus { .. } |
+            TransactionServiceRequest::GenerateCoinbaseTransaction { .. } |
+            TransactionServiceRequest::GetPendingInboundTransactions |
+            TransactionServiceRequest::GetPendingOutboundTransactions |
+            TransactionServiceRequest::GetCompletedTransactions |
+            TransactionServiceRequest::GetCancelledPendingInboundTransactions |
+            TransactionServiceRequest::GetCancelledPendingOutboundTransactions |
+            TransactionServiceRequest::GetCancelledCompletedTransactions |
+            TransactionServiceRequest::GetCompletedTransaction(_) |
+            TransactionServiceRequest::GetAnyTransaction(_) |
+            TransactionServiceRequest::CancelTransaction(_) |
+            TransactionServiceRequest::SetLowPowerMode |
+            TransactionServiceRequest::SetNormalPowerMode |
+            TransactionServiceRequest::RestartTransactionProtocols |
+            TransactionServiceRequest::RestartBroadcastProtocols |
+            TransactionServiceRequest::GetNumConfirmationsRequired |
+            TransactionServiceRequest::SetNumConfirmationsRequired(_) |
+            TransactionServiceRequest::ValidateTransactions |
+            TransactionServiceRequest::ReValidateTransactions |
+            TransactionServiceRequest::GetFeePerGramStatsPerBlock { .. } |
+            TransactionServiceRequest::SetLabels(..) |
+            TransactionServiceRequest::GetLabels(_) |
+            TransactionServiceRequest::GetAllTransactionLabels |
+            TransactionServiceRequest::CreatePaymentRequest { .. } |
+            TransactionServiceRequest::GetPaymentRequests |
+            TransactionServiceRequest::CancelPaymentRequest(_) |
+            TransactionServiceRequest::GetScheduledPayments |
+            TransactionServiceRequest::CancelScheduledPayment(_) |
+            TransactionServiceRequest::GetPendingSpends |
+            TransactionServiceRequest::ApprovePendingSpend(_) |
+            TransactionServiceRequest::RejectPendingSpend(_) |
+            TransactionServiceRequest::GetVaults => None,
+        }
+        .map(
+            |(kind, destination, amount, selection_criteria, fee_per_gram, message)| {
+                if let Some(selection_criteria) = selection_criteria {
+                    selection_criteria.min_confirmations = selection_criteria.min_confirmations.max(min_confirmations);
+                }
+                (kind, destination, amount, fee_per_gram, message)
             },
-            TransactionServiceRequest::RestartTransactionProtocols => self
-                .restart_transaction_negotiation_protocols(
-                    send_transaction_join_handles,
-                    receive_transaction_join_handles,
-                )
-                .map(|_| TransactionServiceResponse::ProtocolsRestarted),
-            TransactionServiceRequest::RestartBroadcastProtocols => self
-                .restart_broadcast_protocols(transaction_broadcast_join_handles)
-                .map(|_| TransactionServiceResponse::ProtocolsRestarted),
-            TransactionServiceRequest::GetNumConfirmationsRequired => Ok(
-                TransactionServiceResponse::NumConfirmationsRequired(self.resources.config.num_confirmations_required),
-            ),
-            TransactionServiceRequest::SetNumConfirmationsRequired(number) => {
-                self.resources.config.num_confirmations_required = number;
-                Ok(TransactionServiceResponse::NumConfirmationsSet)
+        );
+        let (kind, destination, amount, fee_per_gram, message) = match spend {
+            Some(spend) => spend,
+            None => return Ok(request),
+        };
+
+        // Burn proofs, atomic swap pre-images and new vaults are only returned to the caller, so those cannot be held.
+        // A vault withdrawal returns its transaction id like a payment does.
+        let can_hold = matches!(
+            request,
+            TransactionServiceRequest::SendTransaction { .. } |
+                TransactionServiceRequest::SendOneSidedTransaction { .. } |
+                TransactionServiceRequest::SendOneSidedToStealthAddressTransaction { .. } |
+                TransactionServiceRequest::SpendVault { .. }
+        );
+        match self.evaluate_spend(destination.as_ref(), amount)? {
+            PolicyDecision::Allow => {
+                self.record_spend_decision(kind, destination, amount, SpendDecision::Allowed, None)?;
+                Ok(request)
             },
-            TransactionServiceRequest::ValidateTransactions => self
-                .start_transaction_validation_protocol(transaction_validation_join_handles)
-                .await
-                .map(TransactionServiceResponse::ValidationStarted),
-            TransactionServiceRequest::ReValidateTransactions => self
-                .start_transaction_revalidation(transaction_validation_join_handles)
-                .await
-                .map(TransactionServiceResponse::ValidationStarted),
-            TransactionServiceRequest::GetFeePerGramStatsPerBlock { count } => {
-                let reply_channel = reply_channel.take().expect("reply_channel is Some");
-                self.handle_get_fee_per_gram_stats_per_block_request(count, reply_channel);
-                return Ok(());
+            PolicyDecision::RequireApproval(reason) if can_hold => {
+                let record = self.record_spend_decision(
+                    kind,
+                    destination.clone(),
+                    amount,
+                    SpendDecision::Queued,
+                    Some(reason.clone()),
+                )?;
+                let spend = PendingSpend {
+                    id: record.id,
+                    kind,
+                    destination,
+                    amount,
+                    fee_per_gram,
+                    message,
+                    reason: reason.clone(),
+                    created_at: record.created_at,
+                };
+                let _ = self.pending_spends.insert(record.id, (spend, request));
+                self.publish_spending_policy_event(TransactionEvent::SpendHeldForApproval(record.id));
+                Err(SpendingPolicyError::ApprovalRequired(record.id, reason).into())
             },
+            PolicyDecision::RequireApproval(reason) => Err(self.deny_spend(
+                kind,
+                destination,
+                amount,
+                format!("{}, and only payments can be held for approval", reason),
+            )),
+            PolicyDecision::Deny(reason) => Err(self.deny_spend(kind, destination, amount, reason)),
+        }
+    }
+
+    /// Scheduled payments are sent without a caller to approve them, so a payment that needs approval is denied
+    fn authorize_scheduled_payment(&self, payment: &ScheduledPayment) -> Result<(), TransactionServiceError> {
+        if !self.spending_policy.is_enabled() {
+            return Ok(());
+        }
+        let kind = if payment.one_sided {
+            SpendKind::OneSided
+        } else {
+            SpendKind::Interactive
         };
+        let destination = Some(payment.destination.clone());
+        match self.evaluate_spend(destination.as_ref(), payment.amount)? {
+            PolicyDecision::Allow => {
+                self.record_spend_decision(kind, destination, payment.amount, SpendDecision::Allowed, None)?;
+                Ok(())
+            },
+            PolicyDecision::RequireApproval(reason) => Err(self.deny_spend(
+                kind,
+                destination,
+                payment.amount,
+                format!("{}, and scheduled payments cannot be held for approval", reason),
+            )),
+            PolicyDecision::Deny(reason) => Err(self.deny_spend(kind, destination, payment.amount, reason)),
+        }
+    }
 
-        // If the individual handlers did not already send the API response then do it here.
-        if let Some(rp) = reply_channel {
-            let _result = rp.send(response).map_err(|e| {
-                warn!(target: LOG_TARGET, "Failed to send reply");
+    fn evaluate_spend(
+        &self,
+        destination: Option<&TariAddress>,
+        amount: MicroMinotari,
+    ) -> Result<PolicyDecision, TransactionServiceError> {
+        let spent_in_window = self
+            .db
+            .get_spending_decisions_since(spending_window_start(Utc::now().naive_utc()))?
+            .iter()
+            .filter(|record| record.decision.is_spend())
+            .map(|record| record.amount)
+            .sum();
+        Ok(self.spending_policy.evaluate(destination, amount, spent_in_window))
+    }
+
+    fn record_spend_decision(
+        &self,
+        kind: SpendKind,
+        destination: Option<TariAddress>,
+        amount: MicroMinotari,
+        decision: SpendDecision,
+        reason: Option<String>,
+    ) -> Result<SpendingDecisionRecord, TransactionServiceError> {
+        let record = self.db.insert_spending_decision(SpendingDecisionRecord::new(
+            kind,
+            destination,
+            amount,
+            decision,
+            reason,
+            Utc::now().naive_utc(),
+        ))?;
+        info!(
+            target: LOG_TARGET,
+            "Spending policy decision #{}: {} {} transaction of {} to {}{}",
+            record.id,
+            record.decision,
+            record.kind,
+            record.amount,
+            record
+                .destination
+                .as_ref()
+                .map_or_else(|| "nobody".to_string(), |d| d.to_string()),
+            record.reason.as_ref().map(|r| format!(" ({})", r)).unwrap_or_default()
+        );
+        Ok(record)
+    }
+
+    /// Logs a denied spend and returns the error for the caller
+    fn deny_spend(
+        &self,
+        kind: SpendKind,
+        destination: Option<TariAddress>,
+        amount: MicroMinotari,
+        reason: String,
+    ) -> TransactionServiceError {
+        if let Err(e) =
+            self.record_spend_decision(kind, destination, amount, SpendDecision::Denied, Some(reason.clone()))
+        {
+            error!(target: LOG_TARGET, "Could not log a denied spend: {}", e);
+        }
+        self.publish_spending_policy_event(TransactionEvent::SpendDenied(reason.clone()));
+        SpendingPolicyError::Denied(reason).into()
+    }
+
+    /// Releases a held transaction to be sent
+    fn approve_pending_spend(
+        &mut self,
+        id: PendingSpendId,
+    ) -> Result<TransactionServiceRequest, TransactionServiceError> {
+        let (spend, request) = self
+            .pending_spends
+            .remove(&id)
+            .ok_or(SpendingPolicyError::PendingSpendNotFound(id))?;
+        self.record_spend_decision(
+            spend.kind,
+            spend.destination,
+            spend.amount,
+            SpendDecision::Approved,
+            Some(pending_spend_reference(id)),
+        )?;
+        Ok(request)
+    }
+
+    /// Held transactions only live in memory, so the ones that were still held when the wallet stopped are gone.
+    /// They are logged as expired so that the decision log does not show them as pending forever.
+    fn expire_held_spends(&self) -> Result<(), TransactionServiceError> {
+        let records = self.db.get_spending_decisions_since(NaiveDateTime::default())?;
+        for record in unresolved_pending_spends(&records) {
+            self.record_spend_decision(
+                record.kind,
+                record.destination.clone(),
+                record.amount,
+                SpendDecision::Expired,
+                Some(pending_spend_reference(record.id)),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn reject_pending_spend(&mut self, id: PendingSpendId) -> Result<(), TransactionServiceError> {
+        let (spend, _) = self
+            .pending_spends
+            .remove(&id)
+            .ok_or(SpendingPolicyError::PendingSpendNotFound(id))?;
+        self.record_spend_decision(
+            spend.kind,
+            spend.destination,
+            spend.amount,
+            SpendDecision::Rejected,
+            Some(pending_spend_reference(id)),
+        )?;
+        Ok(())
+    }
+
+    fn publish_spending_policy_event(&self, event: TransactionEvent) {
+        let _size = self.event_publisher.send(Arc::new(event)).map_err(|e| {
+            trace!(
+                target: LOG_TARGET,
+                "Error sending event, usually because there are no subscribers: {:?}",
                 e
+            );
+            e
+        });
+    }
+
+    /// The height of the chain tip as last seen by the wallet
+    fn chain_tip_height(&self) -> Option<u64> {
+        self.last_seen_tip_height.or_else(|| {
+            self.wallet_db
+                .get_chain_metadata()
+                .ok()
+                .flatten()
+                .map(|metadata| metadata.height_of_longest_chain())
+        })
+    }
+
+    /// Locks `amount` in a vault output that the wallet's hot key can spend `lock_blocks` after the current tip and
+    /// that `recovery_public_key` can spend at any time. The vault output is frozen so that it is never selected to
+    /// fund other transactions.
+    ///
+    /// The output is built like a one-sided payment to `recovery_public_key`: its mask and the key that encrypts its
+    /// value are derived from a Diffie-Hellman secret of the sender offset key and the recovery key. A wallet holding
+    /// the recovery key therefore finds the vault when it scans the chain, and can spend it through the recovery path
+    /// without the recovery key ever entering this wallet.
+    async fn create_vault(
+        &mut self,
+        amount: MicroMinotari,
+        recovery_public_key: PublicKey,
+        lock_blocks: u64,
+        selection_criteria: UtxoSelectionCriteria,
+        fee_per_gram: MicroMinotari,
+        message: String,
+        transaction_broadcast_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<TxId, TransactionServiceProtocolError<TxId>>>,
+        >,
+    ) -> Result<Vault, TransactionServiceError> {
+        if amount == MicroMinotari::zero() {
+            return Err(VaultError::InvalidVault("The vault amount must be greater than zero".to_string()).into());
+        }
+        if lock_blocks == 0 {
+            return Err(VaultError::InvalidVault("The vault must be locked for at least one block".to_string()).into());
+        }
+        if &recovery_public_key == self.resources.wallet_identity.address.public_key() {
+            return Err(
+                VaultError::InvalidVault("The recovery key must not be the key of this wallet".to_string()).into(),
+            );
+        }
+        let tip_height = self
+            .chain_tip_height()
+            .ok_or_else(|| VaultError::InvalidVault("The chain tip is not known yet".to_string()))?;
+        let unlock_height = tip_height.saturating_add(lock_blocks);
+
+        let key_manager = &self.resources.transaction_key_manager_service;
+        let (_, _, hot_key_id, hot_public_key) = key_manager.get_next_spend_and_script_key_ids().await?;
+        if hot_public_key == recovery_public_key {
+            return Err(VaultError::InvalidVault("The recovery key must differ from the hot key".to_string()).into());
+        }
+        let (sender_offset_key_id, _) = key_manager
+            .get_next_key(&TransactionKeyManagerBranch::SenderOffset.get_branch_key())
+            .await?;
+        let shared_secret = key_manager
+            .get_diffie_hellman_shared_secret(&sender_offset_key_id, &recovery_public_key)
+            .await?;
+        let spending_key_id = key_manager
+            .import_key(shared_secret_to_output_spending_key(&shared_secret)?)
+            .await?;
+        let encryption_key_id = key_manager
+            .import_key(shared_secret_to_output_encryption_key(&shared_secret)?)
+            .await?;
+
+        let script = vault_script(&recovery_public_key, &hot_public_key, unlock_height);
+        let output = WalletOutputBuilder::new(amount, spending_key_id)
+            .with_features(OutputFeatures::default())
+            .with_script(script.clone())
+            .encrypt_data_for_recovery(key_manager, Some(&encryption_key_id))
+            .await?
+            .with_input_data(hot_path_input_data())
+            .with_script_key(hot_key_id);
+
+        let (tx_id, transaction) = self
+            .resources
+            .output_manager_service
+            .create_send_to_self_with_sender_offset_output(
+                output,
+                sender_offset_key_id,
+                fee_per_gram,
+                selection_criteria,
+            )
+            .await?;
+        self.resources
+            .output_manager_service
+            .confirm_pending_transaction(tx_id)
+            .await?;
+
+        let vault_output = transaction
+            .body
+            .outputs()
+            .iter()
+            .find(|o| o.script == script)
+            .ok_or_else(|| VaultError::InvalidVault("The funding transaction has no vault output".to_string()))?;
+        let commitment = vault_output.commitment.clone();
+        let output_hash = vault_output.hash();
+        self.resources
+            .output_manager_service
+            .freeze_outputs(vec![commitment.clone()])
+            .await?;
+
+        let fee = transaction.body.get_total_fee();
+        self.submit_transaction_to_self(
+            transaction_broadcast_join_handles,
+            tx_id,
+            transaction,
+            fee,
+            amount,
+            message,
+        )?;
+
+        let vault = self.db.insert_vault(Vault {
+            id: 0,
+            tx_id,
+            commitment,
+            output_hash,
+            amount,
+            hot_public_key,
+            recovery_public_key,
+            unlock_height,
+            status: VaultStatus::Active,
+            spend_tx_id: None,
+            created_at: Utc::now().naive_utc(),
+        })?;
+        info!(target: LOG_TARGET, "Created vault {} in transaction {}", vault, tx_id);
+        Ok(vault)
+    }
+
+    fn get_active_vault(&self, id: VaultId) -> Result<Vault, TransactionServiceError> {
+        let vault = self.db.get_vault(id)?.ok_or(VaultError::NotFound(id))?;
+        if vault.status != VaultStatus::Active {
+            return Err(VaultError::NotActive(id, vault.status).into());
+        }
+        Ok(vault)
+    }
+
+    /// Returns the funds of an unlocked vault to the wallet with the hot key
+    async fn spend_vault(
+        &mut self,
+        id: VaultId,
+        fee_per_gram: MicroMinotari,
+        transaction_broadcast_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<TxId, TransactionServiceProtocolError<TxId>>>,
+        >,
+    ) -> Result<TxId, TransactionServiceError> {
+        let mut vault = self.get_active_vault(id)?;
+        let tip_height = self.chain_tip_height().unwrap_or(0);
+        // The spending transaction is mined at the earliest in the block after the tip
+        if !vault.is_unlocked(tip_height.saturating_add(1)) {
+            return Err(VaultError::Locked {
+                id,
+                unlock_height: vault.unlock_height,
+                tip_height,
+            }
+            .into());
+        }
+
+        let (tx_id, fee, amount, transaction) = self
+            .resources
+            .output_manager_service
+            .create_vault_spend_transaction(vault.output_hash, fee_per_gram)
+            .await?;
+        self.submit_transaction_to_self(
+            transaction_broadcast_join_handles,
+            tx_id,
+            transaction,
+            fee,
+            amount,
+            format!("Vault #{} withdrawal", id),
+        )?;
+
+        vault.status = VaultStatus::Spent;
+        vault.spend_tx_id = Some(tx_id);
+        self.db.update_vault(&vault)?;
+        info!(target: LOG_TARGET, "Spent vault #{} in transaction {}", id, tx_id);
+        Ok(tx_id)
+    }
+
+    /// Marks the active vaults whose output was spent outside of this wallet as recovered. Only the recovery key can
+    /// do that, since the hot path is spent by [Self::spend_vault], which marks the vault as spent.
+    async fn update_recovered_vaults(&mut self) -> Result<(), TransactionServiceError> {
+        let active_vaults = self
+            .db
+            .get_vaults()?
+            .into_iter()
+            .filter(|v| v.status == VaultStatus::Active)
+            .collect::<Vec<_>>();
+        if active_vaults.is_empty() {
+            return Ok(());
+        }
+        let frozen_outputs = self.resources.output_manager_service.get_frozen_outputs().await?;
+        for mut vault in active_vaults {
+            let is_spent = frozen_outputs.iter().any(|o| {
+                o.commitment == vault.commitment &&
+                    matches!(o.status, OutputStatus::Spent | OutputStatus::SpentMinedUnconfirmed)
             });
+            if is_spent {
+                vault.status = VaultStatus::Recovered;
+                self.db.update_vault(&vault)?;
+                info!(target: LOG_TARGET, "Vault #{} was recovered with its recovery key", vault.id);
+            }
         }
         Ok(())
     }
 
+    /// Publishes a `VaultUnlocked` event for every active vault whose hot path became spendable since `previous_height`
+    fn publish_unlocked_vaults(&self, previous_height: Option<u64>, height: u64) {
+        let vaults = match self.db.get_vaults() {
+            Ok(vaults) => vaults,
+            Err(e) => {
+                warn!(target: LOG_TARGET, "Could not check for unlocked vaults: {}", e);
+                return;
+            },
+        };
+        for vault in vaults.into_iter().filter(|v| v.status == VaultStatus::Active) {
+            let was_unlocked = previous_height.map_or(false, |h| vault.is_unlocked(h.saturating_add(1)));
+            if !was_unlocked && vault.is_unlocked(height.saturating_add(1)) {
+                let _size = self
+                    .event_publisher
+                    .send(Arc::new(TransactionEvent::VaultUnlocked(vault.id)))
+                    .map_err(|e| {
+                        trace!(
+                            target: LOG_TARGET,
+                            "Error sending event, usually because there are no subscribers: {:?}",
+                            e
+                        );
+                        e
+                    });
+            }
+        }
+    }
+
     fn handle_get_fee_per_gram_stats_per_block_request(
         &self,
         count: usize,
@@ -920,13 +2211,17 @@ where
                         e
                     });
 
-                self.last_seen_tip_height = Some(height);
+                let previous_height = self.last_seen_tip_height.replace(height);
+                self.publish_unlocked_vaults(previous_height, height);
             },
         }
     }
 
     async fn handle_output_manager_service_event(&mut self, event: Arc<OutputManagerEvent>) {
         if let OutputManagerEvent::TxoValidationSuccess(_) = (*event).clone() {
+            if let Err(e) = self.update_recovered_vaults().await {
+                warn!(target: LOG_TARGET, "Could not check for recovered vaults: {}", e);
+            }
             let db = self.db.clone();
             let output_manager_handle = self.resources.output_manager_service.clone();
             let metadata = match self.wallet_db.get_chain_metadata() {
@@ -949,11 +2244,13 @@ where
 
     /// Sends a new transaction to a single recipient
     /// # Arguments
+    /// 'tx_id': The id of the new transaction
     /// 'dest_pubkey': The Comms pubkey of the recipient node
     /// 'amount': The amount of Tari to send to the recipient
     /// 'fee_per_gram': The amount of fee per transaction gram to be included in transaction
     pub async fn send_transaction(
         &mut self,
+        tx_id: TxId,
         destination: TariAddress,
         amount: MicroMinotari,
         selection_criteria: UtxoSelectionCriteria,
@@ -969,7 +2266,6 @@ where
         >,
         reply_channel: oneshot::Sender<Result<TransactionServiceResponse, TransactionServiceError>>,
     ) -> Result<(), TransactionServiceError> {
-        let tx_id = TxId::new_random();
         if destination.network() != self.resources.wallet_identity.network {
             let _result = reply_channel
                 .send(Err(TransactionServiceError::InvalidNetwork))
@@ -1277,10 +2573,12 @@ where
     #[allow(clippy::too_many_lines)]
     async fn send_one_sided_or_stealth(
         &mut self,
+        tx_id: TxId,
         dest_address: TariAddress,
         amount: MicroMinotari,
         selection_criteria: UtxoSelectionCriteria,
         output_features: OutputFeatures,
+        covenant: Covenant,
         fee_per_gram: MicroMinotari,
         message: String,
         transaction_broadcast_join_handles: &mut FuturesUnordered<
@@ -1288,8 +2586,6 @@ where
         >,
         script: TariScript,
     ) -> Result<TxId, TransactionServiceError> {
-        let tx_id = TxId::new_random();
-
         // Prepare sender part of the transaction
         let mut stp = self
             .resources
@@ -1303,7 +2599,7 @@ where
                 TransactionMetadata::default(),
                 message.clone(),
                 script.clone(),
-                Covenant::default(),
+                covenant,
                 MicroMinotari::zero(),
             )
             .await?;
@@ -1321,6 +2617,33 @@ where
             .await
             .map_err(|e| TransactionServiceProtocolError::new(tx_id, e.into()))?;
 
+        self.complete_one_sided_transaction(
+            stp,
+            dest_address,
+            amount,
+            message,
+            script,
+            transaction_broadcast_join_handles,
+        )
+        .await
+    }
+
+    /// Adds the recipient's output to a one-sided transaction that has been prepared and confirmed by the output
+    /// manager, then finalizes and submits it
+    #[allow(clippy::too_many_lines)]
+    async fn complete_one_sided_transaction(
+        &mut self,
+        mut stp: SenderTransactionProtocol,
+        dest_address: TariAddress,
+        amount: MicroMinotari,
+        message: String,
+        script: TariScript,
+        transaction_broadcast_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<TxId, TransactionServiceProtocolError<TxId>>>,
+        >,
+    ) -> Result<TxId, TransactionServiceError> {
+        let tx_id = stp.get_tx_id()?;
+
         // Prepare receiver part of the transaction
 
         // Diffie-Hellman shared secret `k_Ob * K_Sb = K_Ob * k_Sb` results in a public key, which is fed into
@@ -1365,19 +2688,15 @@ where
             .get_public_key_at_key_id(&sender_offset_private_key)
             .await?;
 
+        let single_round_data = sender_message.single().ok_or(TransactionServiceProtocolError::new(
+            tx_id,
+            TransactionServiceError::InvalidMessageError("Sent invalid message type".to_string()),
+        ))?;
         let minimum_value_promise = MicroMinotari::zero();
         let output = WalletOutputBuilder::new(amount, spending_key_id)
-            .with_features(
-                sender_message
-                    .single()
-                    .ok_or(TransactionServiceProtocolError::new(
-                        tx_id,
-                        TransactionServiceError::InvalidMessageError("Sent invalid message type".to_string()),
-                    ))?
-                    .features
-                    .clone(),
-            )
+            .with_features(single_round_data.features.clone())
             .with_script(script)
+            .with_covenant(single_round_data.covenant.clone())
             .encrypt_data_for_recovery(&self.resources.transaction_key_manager_service, Some(&encryption_key))
             .await?
             .with_input_data(inputs!(PublicKey::from_secret_key(
@@ -1466,10 +2785,12 @@ where
     /// 'fee_per_gram': The amount of fee per transaction gram to be included in transaction
     pub async fn send_one_sided_transaction(
         &mut self,
+        tx_id: TxId,
         destination: TariAddress,
         amount: MicroMinotari,
         selection_criteria: UtxoSelectionCriteria,
         output_features: OutputFeatures,
+        covenant: Covenant,
         fee_per_gram: MicroMinotari,
         message: String,
         transaction_broadcast_join_handles: &mut FuturesUnordered<
@@ -1487,10 +2808,12 @@ where
         }
         let dest_pubkey = destination.public_key().clone();
         self.send_one_sided_or_stealth(
+            tx_id,
             destination,
             amount,
             selection_criteria,
             output_features,
+            covenant,
             fee_per_gram,
             message,
             transaction_broadcast_join_handles,
@@ -1747,6 +3070,7 @@ where
         let output_features =
             OutputFeatures::for_validator_node_registration(validator_node_public_key, validator_node_signature);
         self.send_transaction(
+            TxId::new_random(),
             self.resources.wallet_identity.address.clone(),
             amount,
             selection_criteria,
@@ -1776,6 +3100,7 @@ where
         reply_channel: oneshot::Sender<Result<TransactionServiceResponse, TransactionServiceError>>,
     ) -> Result<(), TransactionServiceError> {
         self.send_transaction(
+            TxId::new_random(),
             self.resources.wallet_identity.address.clone(),
             0.into(),
             selection_criteria,
@@ -1801,6 +3126,7 @@ where
         amount: MicroMinotari,
         selection_criteria: UtxoSelectionCriteria,
         output_features: OutputFeatures,
+        covenant: Covenant,
         fee_per_gram: MicroMinotari,
         message: String,
         transaction_broadcast_join_handles: &mut FuturesUnordered<
@@ -1825,10 +3151,12 @@ where
         let script_spending_key = stealth_address_script_spending_key(&c, &dest_pubkey);
 
         self.send_one_sided_or_stealth(
+            TxId::new_random(),
             destination,
             amount,
             selection_criteria,
             output_features,
+            covenant,
             fee_per_gram,
             message,
             transaction_broadcast_join_handles,
@@ -2625,6 +3953,9 @@ where
                 let _ = self
                     .restart_broadcast_protocols(transaction_broadcast_join_handles)
                     .map_err(|e| warn!(target: LOG_TARGET, "Error restarting broadcast protocols: {}", e));
+                let _ = self
+                    .reconcile_payment_requests()
+                    .map_err(|e| warn!(target: LOG_TARGET, "Error reconciling payment requests: {}", e));
             },
             Err(TransactionServiceProtocolError { id, error }) => {
                 if let TransactionServiceError::Shutdown = error {
@@ -2823,6 +4154,11 @@ where
         >,
     ) -> Result<TxId, TransactionServiceError> {
         let tx_id = if let Some(id) = tx_id { id } else { TxId::new_random() };
+        // Outputs found by the scanner outside of a recovery are incoming one-sided payments
+        let payment_message = match import_status {
+            ImportStatus::FauxUnconfirmed | ImportStatus::FauxConfirmed => Some(message.clone()),
+            ImportStatus::Imported | ImportStatus::Coinbase => None,
+        };
         self.db.add_utxo_import_transaction_with_status(
             tx_id,
             value,
@@ -2834,6 +4170,9 @@ where
             current_height,
             mined_timestamp,
         )?;
+        if let Some(message) = payment_message {
+            self.match_payment_request(tx_id, value, &message, true);
+        }
         let transaction_event = match import_status {
             ImportStatus::Imported => TransactionEvent::TransactionImported(tx_id),
             ImportStatus::FauxUnconfirmed => TransactionEvent::FauxTransactionUnconfirmed {
@@ -0,0 +1,475 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Limits on what the transaction service may send, so that a leaked gRPC credential cannot drain a hot wallet.
+//!
+//! Every send is checked against the configured destination lists, per-transaction and rolling 24 hour limits.
+//! Transactions above the approval threshold are held in memory until they are approved or rejected in the console
+//! wallet, which is deliberately not possible through gRPC. Held transactions that are still pending when the wallet
+//! stops are logged as expired on the next start. Every decision is written to the decision log.
+
+use std::{
+    collections::HashSet,
+    convert::TryFrom,
+    fmt,
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use tari_common_types::tari_address::TariAddress;
+use tari_core::transactions::tari_amount::MicroMinotari;
+use thiserror::Error;
+
+use crate::transaction_service::config::SpendingPolicyConfig;
+
+/// The length of the rolling window over which `max_daily_amount` is enforced
+pub const SPENDING_POLICY_WINDOW_HOURS: i64 = 24;
+
+/// A held transaction is identified by the id of the decision that queued it
+pub type PendingSpendId = u64;
+
+#[derive(Debug, Error)]
+pub enum SpendingPolicyError {
+    #[error("Transaction denied: {0}")]
+    Denied(String),
+    #[error("Transaction is held for approval as pending spend {0}: {1}")]
+    ApprovalRequired(PendingSpendId, String),
+    #[error("Pending spend {0} not found")]
+    PendingSpendNotFound(PendingSpendId),
+    #[error("Invalid spending policy address `{0}`")]
+    InvalidAddress(String),
+    #[error("Unknown spend kind {0}")]
+    UnknownKind(i32),
+    #[error("Unknown spending decision {0}")]
+    UnknownDecision(i32),
+}
+
+/// The kinds of transaction that the spending policy applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpendKind {
+    Interactive,
+    OneSided,
+    StealthOneSided,
+    Burn,
+    ShaAtomicSwap,
+    /// Funds moved into a vault, which the holder of its recovery key can take, or withdrawn from one
+    Vault,
+}
+
+impl Display for SpendKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SpendKind::Interactive => write!(f, "Interactive"),
+            SpendKind::OneSided => write!(f, "One-sided"),
+            SpendKind::StealthOneSided => write!(f, "Stealth one-sided"),
+            SpendKind::Burn => write!(f, "Burn"),
+            SpendKind::ShaAtomicSwap => write!(f, "SHA atomic swap"),
+            SpendKind::Vault => write!(f, "Vault"),
+        }
+    }
+}
+
+impl From<SpendKind> for i32 {
+    fn from(kind: SpendKind) -> Self {
+        match kind {
+            SpendKind::Interactive => 0,
+            SpendKind::OneSided => 1,
+            SpendKind::StealthOneSided => 2,
+            SpendKind::Burn => 3,
+            SpendKind::ShaAtomicSwap => 4,
+            SpendKind::Vault => 5,
+        }
+    }
+}
+
+impl TryFrom<i32> for SpendKind {
+    type Error = SpendingPolicyError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(SpendKind::Interactive),
+            1 => Ok(SpendKind::OneSided),
+            2 => Ok(SpendKind::StealthOneSided),
+            3 => Ok(SpendKind::Burn),
+            4 => Ok(SpendKind::ShaAtomicSwap),
+            5 => Ok(SpendKind::Vault),
+            v => Err(SpendingPolicyError::UnknownKind(v)),
+        }
+    }
+}
+
+/// A decision recorded in the decision log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpendDecision {
+    Allowed,
+    Denied,
+    /// Held until it is approved or rejected
+    Queued,
+    Approved,
+    Rejected,
+    /// Was still held when the wallet stopped. Held transactions are not stored, so it was never sent.
+    Expired,
+}
+
+impl SpendDecision {
+    /// Whether the amount of this decision counts towards the rolling 24 hour limit
+    pub fn is_spend(self) -> bool {
+        matches!(self, SpendDecision::Allowed | SpendDecision::Approved)
+    }
+}
+
+impl Display for SpendDecision {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SpendDecision::Allowed => write!(f, "Allowed"),
+            SpendDecision::Denied => write!(f, "Denied"),
+            SpendDecision::Queued => write!(f, "Queued"),
+            SpendDecision::Approved => write!(f, "Approved"),
+            SpendDecision::Rejected => write!(f, "Rejected"),
+            SpendDecision::Expired => write!(f, "Expired"),
+        }
+    }
+}
+
+impl From<SpendDecision> for i32 {
+    fn from(decision: SpendDecision) -> Self {
+        match decision {
+            SpendDecision::Allowed => 0,
+            SpendDecision::Denied => 1,
+            SpendDecision::Queued => 2,
+            SpendDecision::Approved => 3,
+            SpendDecision::Rejected => 4,
+            SpendDecision::Expired => 5,
+        }
+    }
+}
+
+impl TryFrom<i32> for SpendDecision {
+    type Error = SpendingPolicyError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(SpendDecision::Allowed),
+            1 => Ok(SpendDecision::Denied),
+            2 => Ok(SpendDecision::Queued),
+            3 => Ok(SpendDecision::Approved),
+            4 => Ok(SpendDecision::Rejected),
+            5 => Ok(SpendDecision::Expired),
+            v => Err(SpendingPolicyError::UnknownDecision(v)),
+        }
+    }
+}
+
+/// An entry of the decision log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpendingDecisionRecord {
+    pub id: u64,
+    pub kind: SpendKind,
+    /// `None` for burns
+    pub destination: Option<TariAddress>,
+    pub amount: MicroMinotari,
+    pub decision: SpendDecision,
+    pub reason: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl SpendingDecisionRecord {
+    /// Creates a log entry. The id is assigned when the entry is stored.
+    pub fn new(
+        kind: SpendKind,
+        destination: Option<TariAddress>,
+        amount: MicroMinotari,
+        decision: SpendDecision,
+        reason: Option<String>,
+        created_at: NaiveDateTime,
+    ) -> Self {
+        Self {
+            id: 0,
+            kind,
+            destination,
+            amount,
+            decision,
+            reason,
+            created_at,
+        }
+    }
+}
+
+/// The reason recorded with the decision that resolves the pending spend `id`
+pub fn pending_spend_reference(id: PendingSpendId) -> String {
+    format!("Pending spend #{}", id)
+}
+
+/// The queued decisions of the log that were never approved, rejected or expired
+pub fn unresolved_pending_spends(records: &[SpendingDecisionRecord]) -> Vec<&SpendingDecisionRecord> {
+    let resolved = records
+        .iter()
+        .filter(|record| record.decision != SpendDecision::Queued)
+        .filter_map(|record| record.reason.as_deref())
+        .collect::<HashSet<_>>();
+    records
+        .iter()
+        .filter(|record| record.decision == SpendDecision::Queued)
+        .filter(|record| !resolved.contains(pending_spend_reference(record.id).as_str()))
+        .collect()
+}
+
+/// A transaction that is held until it is approved or rejected
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingSpend {
+    pub id: PendingSpendId,
+    pub kind: SpendKind,
+    pub destination: Option<TariAddress>,
+    pub amount: MicroMinotari,
+    pub fee_per_gram: MicroMinotari,
+    pub message: String,
+    /// Why the transaction needs approval
+    pub reason: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyDecision {
+    Allow,
+    RequireApproval(String),
+    Deny(String),
+}
+
+/// The parsed form of [SpendingPolicyConfig]. The default policy allows everything.
+#[derive(Debug, Clone, Default)]
+pub struct SpendingPolicy {
+    max_transaction_amount: Option<MicroMinotari>,
+    max_daily_amount: Option<MicroMinotari>,
+    allowed_destinations: Vec<TariAddress>,
+    denied_destinations: Vec<TariAddress>,
+    min_input_confirmations: u64,
+    approval_threshold: Option<MicroMinotari>,
+}
+
+impl SpendingPolicy {
+    pub fn new(config: &SpendingPolicyConfig) -> Result<Self, SpendingPolicyError> {
+        Ok(Self {
+            max_transaction_amount: config.max_transaction_amount,
+            max_daily_amount: config.max_daily_amount,
+            allowed_destinations: parse_addresses(config.allowed_destinations.as_ref())?,
+            denied_destinations: parse_addresses(config.denied_destinations.as_ref())?,
+            min_input_confirmations: config.min_input_confirmations,
+            approval_threshold: config.approval_threshold,
+        })
+    }
+
+    /// Whether any spend needs to be checked and logged
+    pub fn is_enabled(&self) -> bool {
+        self.max_transaction_amount.is_some() ||
+            self.max_daily_amount.is_some() ||
+            !self.allowed_destinations.is_empty() ||
+            !self.denied_destinations.is_empty() ||
+            self.min_input_confirmations > 0 ||
+            self.approval_threshold.is_some()
+    }
+
+    pub fn min_input_confirmations(&self) -> u64 {
+        self.min_input_confirmations
+    }
+
+    /// Decides whether `amount` may be sent to `destination` (`None` for a burn), given the amount that was already
+    /// spent since [spending_window_start].
+    pub fn evaluate(
+        &self,
+        destination: Option<&TariAddress>,
+        amount: MicroMinotari,
+        spent_in_window: MicroMinotari,
+    ) -> PolicyDecision {
+        match destination {
+            Some(destination) => {
+                if self.denied_destinations.iter().any(|a| same_owner(a, destination)) {
+                    return PolicyDecision::Deny(format!("{} is a denied destination", destination));
+                }
+                if !self.allowed_destinations.is_empty() &&
+                    !self.allowed_destinations.iter().any(|a| same_owner(a, destination))
+                {
+                    return PolicyDecision::Deny(format!("{} is not an allowed destination", destination));
+                }
+            },
+            None => {
+                if !self.allowed_destinations.is_empty() {
+                    return PolicyDecision::Deny("Burns are not allowed with a destination allow list".to_string());
+                }
+            },
+        }
+        if let Some(max) = self.max_transaction_amount {
+            if amount > max {
+                return PolicyDecision::Deny(format!("{} is above the per-transaction limit of {}", amount, max));
+            }
+        }
+        if let Some(max) = self.max_daily_amount {
+            if spent_in_window.checked_add(amount).map_or(true, |total| total > max) {
+                return PolicyDecision::Deny(format!(
+                    "{} would exceed the 24 hour limit of {}, {} has been spent",
+                    amount, max, spent_in_window
+                ));
+            }
+        }
+        if let Some(threshold) = self.approval_threshold {
+            if amount > threshold {
+                return PolicyDecision::RequireApproval(format!(
+                    "{} is above the approval threshold of {}",
+                    amount, threshold
+                ));
+            }
+        }
+        PolicyDecision::Allow
+    }
+}
+
+/// The start of the rolling window that ends at `now`
+pub fn spending_window_start(now: NaiveDateTime) -> NaiveDateTime {
+    now - chrono::Duration::hours(SPENDING_POLICY_WINDOW_HOURS)
+}
+
+fn parse_addresses(addresses: &[String]) -> Result<Vec<TariAddress>, SpendingPolicyError> {
+    addresses
+        .iter()
+        .map(|a| TariAddress::from_str(a).map_err(|_| SpendingPolicyError::InvalidAddress(a.clone())))
+        .collect()
+}
+
+/// Addresses are compared by public key so that the lists do not depend on the network byte of the address
+fn same_owner(a: &TariAddress, b: &TariAddress) -> bool {
+    a.public_key() == b.public_key()
+}
+
+#[cfg(test)]
+mod test {
+    use rand::rngs::OsRng;
+    use tari_common::configuration::Network;
+    use tari_common_types::types::{PrivateKey, PublicKey};
+    use tari_crypto::keys::{PublicKey as PublicKeyTrait, SecretKey};
+
+    use super::*;
+
+    fn address() -> TariAddress {
+        TariAddress::new(
+            PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+            Network::LocalNet,
+        )
+    }
+
+    #[test]
+    fn it_allows_everything_by_default() {
+        let policy = SpendingPolicy::new(&SpendingPolicyConfig::default()).unwrap();
+        assert!(!policy.is_enabled());
+        assert_eq!(
+            policy.evaluate(
+                Some(&address()),
+                MicroMinotari::from(u64::MAX),
+                MicroMinotari::from(u64::MAX)
+            ),
+            PolicyDecision::Allow
+        );
+        assert_eq!(
+            policy.evaluate(None, 1.into(), MicroMinotari::from(0)),
+            PolicyDecision::Allow
+        );
+    }
+
+    #[test]
+    fn it_applies_the_destination_lists() {
+        let allowed = address();
+        let denied = address();
+        let config = SpendingPolicyConfig {
+            allowed_destinations: vec![allowed.to_hex(), denied.to_emoji_string()].into(),
+            denied_destinations: vec![denied.to_hex()].into(),
+            ..Default::default()
+        };
+        let policy = SpendingPolicy::new(&config).unwrap();
+        assert_eq!(
+            policy.evaluate(Some(&allowed), 1.into(), 0.into()),
+            PolicyDecision::Allow
+        );
+        // The deny list wins over the allow list
+        assert!(matches!(
+            policy.evaluate(Some(&denied), 1.into(), 0.into()),
+            PolicyDecision::Deny(_)
+        ));
+        assert!(matches!(
+            policy.evaluate(Some(&address()), 1.into(), 0.into()),
+            PolicyDecision::Deny(_)
+        ));
+        assert!(matches!(
+            policy.evaluate(None, 1.into(), 0.into()),
+            PolicyDecision::Deny(_)
+        ));
+
+        let config = SpendingPolicyConfig {
+            denied_destinations: vec!["not an address".to_string()].into(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            SpendingPolicy::new(&config),
+            Err(SpendingPolicyError::InvalidAddress(_))
+        ));
+    }
+
+    #[test]
+    fn it_applies_the_limits_and_approval_threshold() {
+        let config = SpendingPolicyConfig {
+            max_transaction_amount: Some(1000.into()),
+            max_daily_amount: Some(2500.into()),
+            approval_threshold: Some(500.into()),
+            ..Default::default()
+        };
+        let policy = SpendingPolicy::new(&config).unwrap();
+        let destination = address();
+        assert_eq!(
+            policy.evaluate(Some(&destination), 500.into(), 0.into()),
+            PolicyDecision::Allow
+        );
+        assert!(matches!(
+            policy.evaluate(Some(&destination), 501.into(), 0.into()),
+            PolicyDecision::RequireApproval(_)
+        ));
+        assert!(matches!(
+            policy.evaluate(Some(&destination), 1001.into(), 0.into()),
+            PolicyDecision::Deny(_)
+        ));
+        assert_eq!(
+            policy.evaluate(Some(&destination), 500.into(), 2000.into()),
+            PolicyDecision::Allow
+        );
+        assert!(matches!(
+            policy.evaluate(Some(&destination), 501.into(), 2000.into()),
+            PolicyDecision::Deny(_)
+        ));
+    }
+
+    #[test]
+    fn it_finds_unresolved_pending_spends() {
+        let record = |id, decision, reason: Option<String>| SpendingDecisionRecord {
+            id,
+            decision,
+            reason,
+            ..SpendingDecisionRecord::new(
+                SpendKind::OneSided,
+                Some(address()),
+                600.into(),
+                SpendDecision::Allowed,
+                None,
+                NaiveDateTime::MIN,
+            )
+        };
+        let records = vec![
+            record(1, SpendDecision::Queued, Some("Above threshold".to_string())),
+            record(2, SpendDecision::Queued, Some("Above threshold".to_string())),
+            record(3, SpendDecision::Approved, Some(pending_spend_reference(1))),
+            record(4, SpendDecision::Queued, Some("Above threshold".to_string())),
+            record(5, SpendDecision::Allowed, None),
+            record(6, SpendDecision::Expired, Some(pending_spend_reference(4))),
+        ];
+        let unresolved = unresolved_pending_spends(&records);
+        assert_eq!(unresolved.len(), 1);
+        assert_eq!(unresolved[0].id, 2);
+    }
+}
@@ -37,17 +37,26 @@ use tari_common_types::{
 };
 use tari_core::transactions::{tari_amount::MicroMinotari, transaction_components::Transaction};
 
-use crate::transaction_service::{
-    error::TransactionStorageError,
-    storage::{
-        models::{
-            CompletedTransaction,
-            InboundTransaction,
-            OutboundTransaction,
-            TxCancellationReason,
-            WalletTransaction,
+use crate::{
+    channel_service::protocol::{PaymentChannel, PaymentChannelId},
+    transaction_service::{
+        error::TransactionStorageError,
+        payment_request::{PaymentRequest, PaymentRequestStatus},
+        scheduled_payment::{ScheduledPayment, ScheduledPaymentId},
+        spending_policy::SpendingDecisionRecord,
+        storage::{
+            models::{
+                CompletedTransaction,
+                InboundTransaction,
+                LabelTarget,
+                OutboundTransaction,
+                TransactionLabels,
+                TxCancellationReason,
+                WalletTransaction,
+            },
+            sqlite_db::{InboundTransactionSenderInfo, UnconfirmedTransactionInfo},
         },
-        sqlite_db::{InboundTransactionSenderInfo, UnconfirmedTransactionInfo},
+        vault::{Vault, VaultId},
     },
 };
 
@@ -155,6 +164,79 @@ pub trait TransactionBackend: Send + Sync + Clone {
         height: u64,
     ) -> Result<Vec<CompletedTransaction>, TransactionStorageError>;
     fn abandon_coinbase_transaction(&self, tx_id: TxId) -> Result<(), TransactionStorageError>;
+    /// Replace the labels and tags attached to a transaction or an output
+    fn set_labels(&self, target: &LabelTarget, labels: TransactionLabels) -> Result<(), TransactionStorageError>;
+    /// Retrieve the labels and tags attached to a transaction or an output
+    fn fetch_labels(&self, target: &LabelTarget) -> Result<TransactionLabels, TransactionStorageError>;
+    /// Retrieve the labels and tags of every labelled transaction
+    fn fetch_all_transaction_labels(&self) -> Result<HashMap<TxId, TransactionLabels>, TransactionStorageError>;
+    /// Store a new payment request
+    fn insert_payment_request(&self, request: &PaymentRequest) -> Result<(), TransactionStorageError>;
+    /// Retrieve a payment request, including the amount received so far
+    fn fetch_payment_request(&self, reference: &str) -> Result<Option<PaymentRequest>, TransactionStorageError>;
+    /// Retrieve all payment requests, oldest first
+    fn fetch_payment_requests(&self) -> Result<Vec<PaymentRequest>, TransactionStorageError>;
+    /// Update the status of a payment request
+    fn set_payment_request_status(
+        &self,
+        reference: &str,
+        status: PaymentRequestStatus,
+    ) -> Result<(), TransactionStorageError>;
+    /// Record that a transaction paid `amount` towards a payment request and update its status. Returns false, without
+    /// changing anything, if the transaction has already been matched to a request.
+    fn record_payment_request_payment(
+        &self,
+        reference: &str,
+        tx_id: TxId,
+        amount: MicroMinotari,
+        inferred: bool,
+        status: PaymentRequestStatus,
+    ) -> Result<bool, TransactionStorageError>;
+    /// Retrieve the ids of all the transactions that were matched to a payment request
+    fn fetch_payment_request_payment_tx_ids(&self) -> Result<Vec<TxId>, TransactionStorageError>;
+    /// Remove the payment a transaction made towards a payment request and update the status of the request. Returns
+    /// the reference and new status of the request, or None if the transaction was not matched to a request.
+    fn revert_payment_request_payment(
+        &self,
+        tx_id: TxId,
+    ) -> Result<Option<(String, PaymentRequestStatus)>, TransactionStorageError>;
+    /// Store a new scheduled payment, assigning it the next free id
+    fn insert_scheduled_payment(&self, payment: ScheduledPayment) -> Result<ScheduledPayment, TransactionStorageError>;
+    /// Retrieve a scheduled payment
+    fn fetch_scheduled_payment(
+        &self,
+        id: ScheduledPaymentId,
+    ) -> Result<Option<ScheduledPayment>, TransactionStorageError>;
+    /// Retrieve all scheduled payments, in the order they were created
+    fn fetch_scheduled_payments(&self) -> Result<Vec<ScheduledPayment>, TransactionStorageError>;
+    /// Store the progress and status of a scheduled payment
+    fn update_scheduled_payment(&self, payment: &ScheduledPayment) -> Result<(), TransactionStorageError>;
+    /// Append a spending policy decision to the decision log, assigning it the next free id
+    fn insert_spending_decision(
+        &self,
+        record: SpendingDecisionRecord,
+    ) -> Result<SpendingDecisionRecord, TransactionStorageError>;
+    /// Retrieve the spending policy decisions made at or after `since`, oldest first
+    fn fetch_spending_decisions_since(
+        &self,
+        since: NaiveDateTime,
+    ) -> Result<Vec<SpendingDecisionRecord>, TransactionStorageError>;
+    /// Store a new vault, assigning it the next free id
+    fn insert_vault(&self, vault: Vault) -> Result<Vault, TransactionStorageError>;
+    /// Retrieve a vault
+    fn fetch_vault(&self, id: VaultId) -> Result<Option<Vault>, TransactionStorageError>;
+    /// Retrieve all vaults, in the order they were created
+    fn fetch_vaults(&self) -> Result<Vec<Vault>, TransactionStorageError>;
+    /// Store the status of a vault
+    fn update_vault(&self, vault: &Vault) -> Result<(), TransactionStorageError>;
+    /// Store a new payment channel under the id chosen by its opener
+    fn insert_payment_channel(&self, channel: &PaymentChannel) -> Result<(), TransactionStorageError>;
+    /// Retrieve a payment channel
+    fn fetch_payment_channel(&self, id: PaymentChannelId) -> Result<Option<PaymentChannel>, TransactionStorageError>;
+    /// Retrieve all payment channels, in the order they were created
+    fn fetch_payment_channels(&self) -> Result<Vec<PaymentChannel>, TransactionStorageError>;
+    /// Store the progress of a payment channel
+    fn update_payment_channel(&self, channel: &PaymentChannel) -> Result<(), TransactionStorageError>;
 }
 
 #[derive(Clone, PartialEq)]
@@ -745,6 +827,129 @@ where T: TransactionBackend + 'static
     pub fn abandon_coinbase_transaction(&self, tx_id: TxId) -> Result<(), TransactionStorageError> {
         self.db.abandon_coinbase_transaction(tx_id)
     }
+
+    pub fn set_labels(&self, target: &LabelTarget, labels: TransactionLabels) -> Result<(), TransactionStorageError> {
+        self.db.set_labels(target, labels)
+    }
+
+    pub fn get_labels(&self, target: &LabelTarget) -> Result<TransactionLabels, TransactionStorageError> {
+        self.db.fetch_labels(target)
+    }
+
+    pub fn get_all_transaction_labels(&self) -> Result<HashMap<TxId, TransactionLabels>, TransactionStorageError> {
+        self.db.fetch_all_transaction_labels()
+    }
+
+    pub fn insert_payment_request(&self, request: &PaymentRequest) -> Result<(), TransactionStorageError> {
+        self.db.insert_payment_request(request)
+    }
+
+    pub fn get_payment_request(&self, reference: &str) -> Result<Option<PaymentRequest>, TransactionStorageError> {
+        self.db.fetch_payment_request(reference)
+    }
+
+    pub fn get_payment_requests(&self) -> Result<Vec<PaymentRequest>, TransactionStorageError> {
+        self.db.fetch_payment_requests()
+    }
+
+    pub fn set_payment_request_status(
+        &self,
+        reference: &str,
+        status: PaymentRequestStatus,
+    ) -> Result<(), TransactionStorageError> {
+        self.db.set_payment_request_status(reference, status)
+    }
+
+    pub fn record_payment_request_payment(
+        &self,
+        reference: &str,
+        tx_id: TxId,
+        amount: MicroMinotari,
+        inferred: bool,
+        status: PaymentRequestStatus,
+    ) -> Result<bool, TransactionStorageError> {
+        self.db
+            .record_payment_request_payment(reference, tx_id, amount, inferred, status)
+    }
+
+    pub fn get_payment_request_payment_tx_ids(&self) -> Result<Vec<TxId>, TransactionStorageError> {
+        self.db.fetch_payment_request_payment_tx_ids()
+    }
+
+    pub fn revert_payment_request_payment(
+        &self,
+        tx_id: TxId,
+    ) -> Result<Option<(String, PaymentRequestStatus)>, TransactionStorageError> {
+        self.db.revert_payment_request_payment(tx_id)
+    }
+
+    pub fn insert_scheduled_payment(
+        &self,
+        payment: ScheduledPayment,
+    ) -> Result<ScheduledPayment, TransactionStorageError> {
+        self.db.insert_scheduled_payment(payment)
+    }
+
+    pub fn get_scheduled_payment(
+        &self,
+        id: ScheduledPaymentId,
+    ) -> Result<Option<ScheduledPayment>, TransactionStorageError> {
+        self.db.fetch_scheduled_payment(id)
+    }
+
+    pub fn get_scheduled_payments(&self) -> Result<Vec<ScheduledPayment>, TransactionStorageError> {
+        self.db.fetch_scheduled_payments()
+    }
+
+    pub fn update_scheduled_payment(&self, payment: &ScheduledPayment) -> Result<(), TransactionStorageError> {
+        self.db.update_scheduled_payment(payment)
+    }
+
+    pub fn insert_spending_decision(
+        &self,
+        record: SpendingDecisionRecord,
+    ) -> Result<SpendingDecisionRecord, TransactionStorageError> {
+        self.db.insert_spending_decision(record)
+    }
+
+    pub fn get_spending_decisions_since(
+        &self,
+        since: NaiveDateTime,
+    ) -> Result<Vec<SpendingDecisionRecord>, TransactionStorageError> {
+        self.db.fetch_spending_decisions_since(since)
+    }
+
+    pub fn insert_vault(&self, vault: Vault) -> Result<Vault, TransactionStorageError> {
+        self.db.insert_vault(vault)
+    }
+
+    pub fn get_vault(&self, id: VaultId) -> Result<Option<Vault>, TransactionStorageError> {
+        self.db.fetch_vault(id)
+    }
+
+    pub fn get_vaults(&self) -> Result<Vec<Vault>, TransactionStorageError> {
+        self.db.fetch_vaults()
+    }
+
+    pub fn update_vault(&self, vault: &Vault) -> Result<(), TransactionStorageError> {
+        self.db.update_vault(vault)
+    }
+
+    pub fn insert_payment_channel(&self, channel: &PaymentChannel) -> Result<(), TransactionStorageError> {
+        self.db.insert_payment_channel(channel)
+    }
+
+    pub fn get_payment_channel(&self, id: PaymentChannelId) -> Result<Option<PaymentChannel>, TransactionStorageError> {
+        self.db.fetch_payment_channel(id)
+    }
+
+    pub fn get_payment_channels(&self) -> Result<Vec<PaymentChannel>, TransactionStorageError> {
+        self.db.fetch_payment_channels()
+    }
+
+    pub fn update_payment_channel(&self, channel: &PaymentChannel) -> Result<(), TransactionStorageError> {
+        self.db.update_payment_channel(channel)
+    }
 }
 
 impl Display for DbKey {
@@ -30,7 +30,7 @@ use serde::{Deserialize, Serialize};
 use tari_common_types::{
     tari_address::TariAddress,
     transaction::{TransactionConversionError, TransactionDirection, TransactionStatus, TxId},
-    types::{BlockHash, PrivateKey, Signature},
+    types::{BlockHash, Commitment, PrivateKey, Signature},
 };
 use tari_core::transactions::{
     tari_amount::MicroMinotari,
@@ -38,6 +38,7 @@ use tari_core::transactions::{
     ReceiverTransactionProtocol,
     SenderTransactionProtocol,
 };
+use tari_utilities::hex::Hex;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct InboundTransaction {
@@ -376,3 +377,71 @@ impl Display for TxCancellationReason {
         fmt.write_str(response)
     }
 }
+
+/// User defined bookkeeping annotations attached to a transaction or an output
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionLabels {
+    /// A short description, e.g. an invoice reference
+    pub label: Option<String>,
+    /// Tags used to group related transactions, e.g. `payroll`. Kept sorted and free of duplicates.
+    pub tags: Vec<String>,
+}
+
+impl TransactionLabels {
+    /// Creates a normalised set of labels: surrounding whitespace is trimmed, empty values are dropped and the tags are
+    /// sorted and deduplicated.
+    pub fn new<I: IntoIterator<Item = String>>(label: Option<String>, tags: I) -> Self {
+        let label = label.map(|l| l.trim().to_string()).filter(|l| !l.is_empty());
+        let mut tags = tags
+            .into_iter()
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>();
+        tags.sort();
+        tags.dedup();
+        Self { label, tags }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.label.is_none() && self.tags.is_empty()
+    }
+
+    /// Returns true if the label contains `label` (case insensitive) and every one of `tags` is present. Empty
+    /// filters match everything.
+    pub fn matches(&self, label: &str, tags: &[String]) -> bool {
+        let label_matches = label.is_empty() ||
+            self.label
+                .as_ref()
+                .map_or(false, |l| l.to_lowercase().contains(&label.to_lowercase()));
+        label_matches &&
+            tags.iter()
+                .all(|t| self.tags.iter().any(|own| own.eq_ignore_ascii_case(t.trim())))
+    }
+}
+
+impl Display for TransactionLabels {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        let mut parts = Vec::with_capacity(self.tags.len() + 1);
+        if let Some(label) = &self.label {
+            parts.push(label.clone());
+        }
+        parts.extend(self.tags.iter().map(|t| format!("#{}", t)));
+        f.write_str(&parts.join(" "))
+    }
+}
+
+/// The item that labels are attached to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LabelTarget {
+    Transaction(TxId),
+    Output(Commitment),
+}
+
+impl Display for LabelTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            LabelTarget::Transaction(tx_id) => write!(f, "transaction {}", tx_id),
+            LabelTarget::Output(commitment) => write!(f, "output {}", commitment.to_hex()),
+        }
+    }
+}
@@ -25,6 +25,7 @@ use std::{
     convert::{TryFrom, TryInto},
     str::from_utf8,
     sync::{Arc, RwLock},
+    time::Duration,
 };
 
 use chacha20poly1305::XChaCha20Poly1305;
@@ -42,7 +43,7 @@ use tari_common_types::{
         TransactionStatus,
         TxId,
     },
-    types::{BlockHash, PrivateKey, PublicKey, Signature},
+    types::{BlockHash, Commitment, HashOutput, PrivateKey, PublicKey, Signature},
 };
 use tari_core::transactions::tari_amount::MicroMinotari;
 use tari_utilities::{
@@ -55,20 +56,44 @@ use tokio::time::Instant;
 use zeroize::Zeroize;
 
 use crate::{
-    schema::{completed_transactions, inbound_transactions, outbound_transactions},
+    channel_service::protocol::{PaymentChannel, PaymentChannelId},
+    schema::{
+        completed_transactions,
+        inbound_transactions,
+        outbound_transactions,
+        payment_channels,
+        payment_request_payments,
+        payment_requests,
+        scheduled_payments,
+        spending_decisions,
+        transaction_labels,
+        vaults,
+    },
     storage::sqlite_utilities::wallet_db_connection::WalletDbConnection,
     transaction_service::{
         error::{TransactionKeyError, TransactionStorageError},
+        payment_request::{PaymentRequest, PaymentRequestStatus},
+        scheduled_payment::{
+            PaymentSchedule,
+            ScheduledPayment,
+            ScheduledPaymentError,
+            ScheduledPaymentId,
+            ScheduledPaymentStatus,
+        },
+        spending_policy::{SpendDecision, SpendKind, SpendingDecisionRecord},
         storage::{
             database::{DbKey, DbKeyValuePair, DbValue, TransactionBackend, WriteOperation},
             models::{
                 CompletedTransaction,
                 InboundTransaction,
+                LabelTarget,
                 OutboundTransaction,
+                TransactionLabels,
                 TxCancellationReason,
                 WalletTransaction,
             },
         },
+        vault::{Vault, VaultId, VaultStatus},
     },
 };
 const LOG_TARGET: &str = "wallet::transaction_service::database::wallet";
@@ -1064,62 +1089,1012 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
         Ok(sender_info)
     }
 
-    fn fetch_imported_transactions(&self) -> Result<Vec<CompletedTransaction>, TransactionStorageError> {
-        let mut conn = self.database_connection.get_pooled_connection()?;
-        let cipher = acquire_read_lock!(self.cipher);
+    fn fetch_imported_transactions(&self) -> Result<Vec<CompletedTransaction>, TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        let cipher = acquire_read_lock!(self.cipher);
+
+        CompletedTransactionSql::index_by_status_and_cancelled(TransactionStatus::Imported, false, &mut conn)?
+            .into_iter()
+            .map(|ct: CompletedTransactionSql| {
+                CompletedTransaction::try_from(ct, &cipher).map_err(TransactionStorageError::from)
+            })
+            .collect::<Result<Vec<CompletedTransaction>, TransactionStorageError>>()
+    }
+
+    fn fetch_unconfirmed_faux_transactions(&self) -> Result<Vec<CompletedTransaction>, TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        let cipher = acquire_read_lock!(self.cipher);
+
+        CompletedTransactionSql::index_by_status_and_cancelled(TransactionStatus::FauxUnconfirmed, false, &mut conn)?
+            .into_iter()
+            .map(|ct: CompletedTransactionSql| {
+                CompletedTransaction::try_from(ct, &cipher).map_err(TransactionStorageError::from)
+            })
+            .collect::<Result<Vec<CompletedTransaction>, TransactionStorageError>>()
+    }
+
+    fn fetch_confirmed_faux_transactions_from_height(
+        &self,
+        height: u64,
+    ) -> Result<Vec<CompletedTransaction>, TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        let cipher = acquire_read_lock!(self.cipher);
+
+        CompletedTransactionSql::index_by_status_and_cancelled_from_block_height(
+            TransactionStatus::FauxConfirmed,
+            false,
+            height as i64,
+            &mut conn,
+        )?
+        .into_iter()
+        .map(|ct: CompletedTransactionSql| {
+            CompletedTransaction::try_from(ct, &cipher).map_err(TransactionStorageError::from)
+        })
+        .collect::<Result<Vec<CompletedTransaction>, TransactionStorageError>>()
+    }
+
+    fn abandon_coinbase_transaction(&self, tx_id: TxId) -> Result<(), TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        match CompletedTransactionSql::find_and_abandon_coinbase(tx_id, &mut conn) {
+            Ok(_) => {},
+            Err(TransactionStorageError::DieselError(DieselError::NotFound)) => {
+                return Err(TransactionStorageError::ValueNotFound(DbKey::CompletedTransaction(
+                    tx_id,
+                )));
+            },
+            Err(e) => return Err(e),
+        };
+
+        Ok(())
+    }
+
+    fn set_labels(&self, target: &LabelTarget, labels: TransactionLabels) -> Result<(), TransactionStorageError> {
+        let start = Instant::now();
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        let acquire_lock = start.elapsed();
+
+        conn.transaction::<_, TransactionStorageError, _>(|conn| {
+            TransactionLabelSql::delete_by_target(target, conn)?;
+            let label = labels.label.into_iter().map(|l| (LABEL_KIND, l));
+            let tags = labels.tags.into_iter().map(|t| (TAG_KIND, t));
+            for (kind, value) in label.chain(tags) {
+                NewTransactionLabelSql::new(target, kind, value).commit(conn)?;
+            }
+            Ok(())
+        })?;
+        if start.elapsed().as_millis() > 0 {
+            trace!(
+                target: LOG_TARGET,
+                "sqlite profile - set_labels ({}): lock {} + db_op {} = {} ms",
+                target,
+                acquire_lock.as_millis(),
+                (start.elapsed() - acquire_lock).as_millis(),
+                start.elapsed().as_millis()
+            );
+        }
+
+        Ok(())
+    }
+
+    fn fetch_labels(&self, target: &LabelTarget) -> Result<TransactionLabels, TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        let rows = TransactionLabelSql::index_by_target(target, &mut conn)?;
+        Ok(TransactionLabelSql::into_labels(rows))
+    }
+
+    fn fetch_all_transaction_labels(&self) -> Result<HashMap<TxId, TransactionLabels>, TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        let mut rows_by_tx_id: HashMap<TxId, Vec<TransactionLabelSql>> = HashMap::new();
+        for row in TransactionLabelSql::index_transactions(&mut conn)? {
+            if let Some(tx_id) = row.tx_id {
+                rows_by_tx_id.entry(TxId::from(tx_id as u64)).or_default().push(row);
+            }
+        }
+        Ok(rows_by_tx_id
+            .into_iter()
+            .map(|(tx_id, rows)| (tx_id, TransactionLabelSql::into_labels(rows)))
+            .collect())
+    }
+
+    fn insert_payment_request(&self, request: &PaymentRequest) -> Result<(), TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        PaymentRequestSql::from(request).commit(&mut conn)
+    }
+
+    fn fetch_payment_request(&self, reference: &str) -> Result<Option<PaymentRequest>, TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        match PaymentRequestSql::find(reference, &mut conn)? {
+            Some(request) => {
+                let payments = PaymentRequestPaymentSql::find_by_reference(reference, &mut conn)?;
+                Ok(Some(request.into_payment_request(&payments)?))
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn fetch_payment_requests(&self) -> Result<Vec<PaymentRequest>, TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        let mut payments_by_reference: HashMap<String, Vec<PaymentRequestPaymentSql>> = HashMap::new();
+        for payment in PaymentRequestPaymentSql::index(&mut conn)? {
+            payments_by_reference
+                .entry(payment.reference.clone())
+                .or_default()
+                .push(payment);
+        }
+        PaymentRequestSql::index(&mut conn)?
+            .into_iter()
+            .map(|request| {
+                let payments = payments_by_reference.remove(&request.reference).unwrap_or_default();
+                request.into_payment_request(&payments)
+            })
+            .collect()
+    }
+
+    fn set_payment_request_status(
+        &self,
+        reference: &str,
+        status: PaymentRequestStatus,
+    ) -> Result<(), TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        PaymentRequestSql::update_status(reference, status, &mut conn)
+    }
+
+    fn record_payment_request_payment(
+        &self,
+        reference: &str,
+        tx_id: TxId,
+        amount: MicroMinotari,
+        inferred: bool,
+        status: PaymentRequestStatus,
+    ) -> Result<bool, TransactionStorageError> {
+        let start = Instant::now();
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        let acquire_lock = start.elapsed();
+
+        let recorded = conn.transaction::<_, TransactionStorageError, _>(|conn| {
+            if PaymentRequestPaymentSql::exists(tx_id, conn)? {
+                return Ok(false);
+            }
+            PaymentRequestPaymentSql {
+                tx_id: tx_id.as_i64_wrapped(),
+                reference: reference.to_string(),
+                amount: amount.as_u64() as i64,
+                received_at: Utc::now().naive_utc(),
+                inferred: i32::from(inferred),
+            }
+            .commit(conn)?;
+            PaymentRequestSql::update_status(reference, status, conn)?;
+            Ok(true)
+        })?;
+        if start.elapsed().as_millis() > 0 {
+            trace!(
+                target: LOG_TARGET,
+                "sqlite profile - record_payment_request_payment ({}): lock {} + db_op {} = {} ms",
+                reference,
+                acquire_lock.as_millis(),
+                (start.elapsed() - acquire_lock).as_millis(),
+                start.elapsed().as_millis()
+            );
+        }
+
+        Ok(recorded)
+    }
+
+    fn fetch_payment_request_payment_tx_ids(&self) -> Result<Vec<TxId>, TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        Ok(PaymentRequestPaymentSql::index(&mut conn)?
+            .into_iter()
+            .map(|payment| TxId::from(payment.tx_id as u64))
+            .collect())
+    }
+
+    fn revert_payment_request_payment(
+        &self,
+        tx_id: TxId,
+    ) -> Result<Option<(String, PaymentRequestStatus)>, TransactionStorageError> {
+        let start = Instant::now();
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        let acquire_lock = start.elapsed();
+
+        let reverted = conn.transaction::<_, TransactionStorageError, _>(|conn| {
+            let payment = match PaymentRequestPaymentSql::find(tx_id, conn)? {
+                Some(payment) => payment,
+                None => return Ok(None),
+            };
+            let request = PaymentRequestSql::find(&payment.reference, conn)?
+                .ok_or(TransactionStorageError::DieselError(DieselError::NotFound))?;
+            let payments = PaymentRequestPaymentSql::find_by_reference(&payment.reference, conn)?;
+            let status = request
+                .into_payment_request(&payments)?
+                .status_after_reversal(MicroMinotari::from(payment.amount as u64));
+            PaymentRequestPaymentSql::delete(tx_id, conn)?;
+            PaymentRequestSql::update_status(&payment.reference, status, conn)?;
+            Ok(Some((payment.reference, status)))
+        })?;
+        if start.elapsed().as_millis() > 0 {
+            trace!(
+                target: LOG_TARGET,
+                "sqlite profile - revert_payment_request_payment ({}): lock {} + db_op {} = {} ms",
+                tx_id,
+                acquire_lock.as_millis(),
+                (start.elapsed() - acquire_lock).as_millis(),
+                start.elapsed().as_millis()
+            );
+        }
+
+        Ok(reverted)
+    }
+
+    fn insert_scheduled_payment(&self, payment: ScheduledPayment) -> Result<ScheduledPayment, TransactionStorageError> {
+        let start = Instant::now();
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        let acquire_lock = start.elapsed();
+
+        let payment = conn.transaction::<_, TransactionStorageError, _>(|conn| {
+            let id = ScheduledPaymentSql::max_id(conn)?.map_or(1, |id| id + 1);
+            let payment = ScheduledPayment {
+                id: id as u64,
+                ..payment
+            };
+            ScheduledPaymentSql::from(&payment).commit(conn)?;
+            Ok(payment)
+        })?;
+        if start.elapsed().as_millis() > 0 {
+            trace!(
+                target: LOG_TARGET,
+                "sqlite profile - insert_scheduled_payment: lock {} + db_op {} = {} ms",
+                acquire_lock.as_millis(),
+                (start.elapsed() - acquire_lock).as_millis(),
+                start.elapsed().as_millis()
+            );
+        }
+
+        Ok(payment)
+    }
+
+    fn fetch_scheduled_payment(
+        &self,
+        id: ScheduledPaymentId,
+    ) -> Result<Option<ScheduledPayment>, TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        ScheduledPaymentSql::find(id, &mut conn)?
+            .map(ScheduledPaymentSql::into_scheduled_payment)
+            .transpose()
+    }
+
+    fn fetch_scheduled_payments(&self) -> Result<Vec<ScheduledPayment>, TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        ScheduledPaymentSql::index(&mut conn)?
+            .into_iter()
+            .map(ScheduledPaymentSql::into_scheduled_payment)
+            .collect()
+    }
+
+    fn update_scheduled_payment(&self, payment: &ScheduledPayment) -> Result<(), TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        ScheduledPaymentSql::from(payment).update(&mut conn)
+    }
+
+    fn insert_spending_decision(
+        &self,
+        record: SpendingDecisionRecord,
+    ) -> Result<SpendingDecisionRecord, TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        conn.transaction::<_, TransactionStorageError, _>(|conn| {
+            let id = SpendingDecisionSql::max_id(conn)?.map_or(1, |id| id + 1);
+            let record = SpendingDecisionRecord {
+                id: id as u64,
+                ..record
+            };
+            SpendingDecisionSql::from(&record).commit(conn)?;
+            Ok(record)
+        })
+    }
+
+    fn fetch_spending_decisions_since(
+        &self,
+        since: NaiveDateTime,
+    ) -> Result<Vec<SpendingDecisionRecord>, TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        SpendingDecisionSql::index_since(since, &mut conn)?
+            .into_iter()
+            .map(SpendingDecisionSql::into_spending_decision)
+            .collect()
+    }
+
+    fn insert_vault(&self, vault: Vault) -> Result<Vault, TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        conn.transaction::<_, TransactionStorageError, _>(|conn| {
+            let id = VaultSql::max_id(conn)?.map_or(1, |id| id + 1);
+            let vault = Vault { id: id as u64, ..vault };
+            VaultSql::from(&vault).commit(conn)?;
+            Ok(vault)
+        })
+    }
+
+    fn fetch_vault(&self, id: VaultId) -> Result<Option<Vault>, TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        VaultSql::find(id, &mut conn)?.map(VaultSql::into_vault).transpose()
+    }
+
+    fn fetch_vaults(&self) -> Result<Vec<Vault>, TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        VaultSql::index(&mut conn)?
+            .into_iter()
+            .map(VaultSql::into_vault)
+            .collect()
+    }
+
+    fn update_vault(&self, vault: &Vault) -> Result<(), TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        VaultSql::from(vault).update(&mut conn)
+    }
+
+    fn insert_payment_channel(&self, channel: &PaymentChannel) -> Result<(), TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        let cipher = acquire_read_lock!(self.cipher);
+        PaymentChannelSql::try_from(channel, &cipher)?.commit(&mut conn)
+    }
+
+    fn fetch_payment_channel(&self, id: PaymentChannelId) -> Result<Option<PaymentChannel>, TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        let cipher = acquire_read_lock!(self.cipher);
+        PaymentChannelSql::find(id, &mut conn)?
+            .map(|channel| channel.into_payment_channel(&cipher))
+            .transpose()
+    }
+
+    fn fetch_payment_channels(&self) -> Result<Vec<PaymentChannel>, TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        let cipher = acquire_read_lock!(self.cipher);
+        PaymentChannelSql::index(&mut conn)?
+            .into_iter()
+            .map(|channel| channel.into_payment_channel(&cipher))
+            .collect()
+    }
+
+    fn update_payment_channel(&self, channel: &PaymentChannel) -> Result<(), TransactionStorageError> {
+        let mut conn = self.database_connection.get_pooled_connection()?;
+        let cipher = acquire_read_lock!(self.cipher);
+        PaymentChannelSql::try_from(channel, &cipher)?.update(&mut conn)
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, AsChangeset, PartialEq)]
+#[diesel(table_name = payment_channels)]
+struct PaymentChannelSql {
+    id: i64,
+    role: i32,
+    state: i32,
+    counterparty: String,
+    capacity: i64,
+    channel_data: String,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+}
+
+impl PaymentChannelSql {
+    fn try_from(channel: &PaymentChannel, cipher: &XChaCha20Poly1305) -> Result<Self, TransactionStorageError> {
+        let channel_sql = Self {
+            // Channel ids are random, so they are stored with the bits of the u64
+            id: channel.id as i64,
+            role: i32::from(channel.role),
+            state: i32::from(channel.state),
+            counterparty: channel.counterparty.to_hex(),
+            capacity: channel.parameters.capacity.as_u64() as i64,
+            channel_data: serde_json::to_string(channel)?,
+            created_at: channel.created_at,
+            updated_at: channel.updated_at,
+        };
+        channel_sql.encrypt(cipher).map_err(TransactionStorageError::AeadError)
+    }
+
+    pub fn commit(&self, conn: &mut SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::insert_into(payment_channels::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn update(&self, conn: &mut SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::update(payment_channels::table.filter(payment_channels::id.eq(self.id)))
+            .set(self.clone())
+            .execute(conn)
+            .num_rows_affected_or_not_found(1)?;
+        Ok(())
+    }
+
+    pub fn find(
+        id: PaymentChannelId,
+        conn: &mut SqliteConnection,
+    ) -> Result<Option<PaymentChannelSql>, TransactionStorageError> {
+        Ok(payment_channels::table
+            .filter(payment_channels::id.eq(id as i64))
+            .first::<PaymentChannelSql>(conn)
+            .optional()?)
+    }
+
+    pub fn index(conn: &mut SqliteConnection) -> Result<Vec<PaymentChannelSql>, TransactionStorageError> {
+        Ok(payment_channels::table
+            .order_by(payment_channels::created_at.asc())
+            .load::<PaymentChannelSql>(conn)?)
+    }
+
+    fn into_payment_channel(self, cipher: &XChaCha20Poly1305) -> Result<PaymentChannel, TransactionStorageError> {
+        let mut channel_sql = self.decrypt(cipher).map_err(TransactionStorageError::AeadError)?;
+        let channel = serde_json::from_str::<PaymentChannel>(&channel_sql.channel_data);
+        channel_sql.channel_data.zeroize();
+        Ok(channel?)
+    }
+}
+
+impl Encryptable<XChaCha20Poly1305> for PaymentChannelSql {
+    fn domain(&self, field_name: &'static str) -> Vec<u8> {
+        [
+            Self::PAYMENT_CHANNEL,
+            self.id.to_le_bytes().as_slice(),
+            field_name.as_bytes(),
+        ]
+        .concat()
+        .to_vec()
+    }
+
+    fn encrypt(mut self, cipher: &XChaCha20Poly1305) -> Result<Self, String> {
+        let mut channel_data = encrypt_bytes_integral_nonce(
+            cipher,
+            self.domain("channel_data"),
+            Hidden::hide(self.channel_data.as_bytes().to_vec()),
+        )?
+        .to_hex();
+        std::mem::swap(&mut self.channel_data, &mut channel_data);
+        // zeroize sensitive data
+        channel_data.zeroize();
+
+        Ok(self)
+    }
+
+    fn decrypt(mut self, cipher: &XChaCha20Poly1305) -> Result<Self, String> {
+        let mut decrypted_channel_data = decrypt_bytes_integral_nonce(
+            cipher,
+            self.domain("channel_data"),
+            &from_hex(self.channel_data.as_str()).map_err(|e| e.to_string())?,
+        )?;
+
+        self.channel_data = from_utf8(decrypted_channel_data.as_slice())
+            .map_err(|e| e.to_string())?
+            .to_string();
+
+        // zeroize sensitive data
+        decrypted_channel_data.zeroize();
+
+        Ok(self)
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, AsChangeset, PartialEq)]
+#[diesel(table_name = vaults)]
+#[diesel(treat_none_as_null = true)]
+struct VaultSql {
+    id: i64,
+    tx_id: i64,
+    commitment: Vec<u8>,
+    output_hash: Vec<u8>,
+    amount: i64,
+    hot_public_key: Vec<u8>,
+    recovery_public_key: Vec<u8>,
+    unlock_height: i64,
+    status: i32,
+    spend_tx_id: Option<i64>,
+    created_at: NaiveDateTime,
+}
+
+impl VaultSql {
+    pub fn commit(&self, conn: &mut SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::insert_into(vaults::table).values(self.clone()).execute(conn)?;
+        Ok(())
+    }
+
+    pub fn update(&self, conn: &mut SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::update(vaults::table.filter(vaults::id.eq(self.id)))
+            .set(self.clone())
+            .execute(conn)
+            .num_rows_affected_or_not_found(1)?;
+        Ok(())
+    }
+
+    pub fn find(id: VaultId, conn: &mut SqliteConnection) -> Result<Option<VaultSql>, TransactionStorageError> {
+        Ok(vaults::table
+            .filter(vaults::id.eq(id as i64))
+            .first::<VaultSql>(conn)
+            .optional()?)
+    }
+
+    pub fn index(conn: &mut SqliteConnection) -> Result<Vec<VaultSql>, TransactionStorageError> {
+        Ok(vaults::table.order_by(vaults::id.asc()).load::<VaultSql>(conn)?)
+    }
+
+    pub fn max_id(conn: &mut SqliteConnection) -> Result<Option<i64>, TransactionStorageError> {
+        Ok(vaults::table
+            .select(diesel::dsl::max(vaults::id))
+            .first::<Option<i64>>(conn)?)
+    }
+
+    fn into_vault(self) -> Result<Vault, TransactionStorageError> {
+        Ok(Vault {
+            id: self.id as u64,
+            tx_id: TxId::from(self.tx_id as u64),
+            commitment: Commitment::from_vec(&self.commitment)?,
+            output_hash: HashOutput::try_from(self.output_hash)
+                .map_err(|e| TransactionStorageError::ByteArrayError(e.to_string()))?,
+            amount: MicroMinotari::from(self.amount as u64),
+            hot_public_key: PublicKey::from_vec(&self.hot_public_key)?,
+            recovery_public_key: PublicKey::from_vec(&self.recovery_public_key)?,
+            unlock_height: self.unlock_height as u64,
+            status: VaultStatus::try_from(self.status)?,
+            spend_tx_id: self.spend_tx_id.map(|tx_id| TxId::from(tx_id as u64)),
+            created_at: self.created_at,
+        })
+    }
+}
+
+impl From<&Vault> for VaultSql {
+    fn from(vault: &Vault) -> Self {
+        Self {
+            id: vault.id as i64,
+            tx_id: vault.tx_id.as_i64_wrapped(),
+            commitment: vault.commitment.to_vec(),
+            output_hash: vault.output_hash.to_vec(),
+            amount: vault.amount.as_u64() as i64,
+            hot_public_key: vault.hot_public_key.to_vec(),
+            recovery_public_key: vault.recovery_public_key.to_vec(),
+            unlock_height: vault.unlock_height as i64,
+            status: i32::from(vault.status),
+            spend_tx_id: vault.spend_tx_id.map(|tx_id| tx_id.as_i64_wrapped()),
+            created_at: vault.created_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[diesel(table_name = payment_requests)]
+struct PaymentRequestSql {
+    reference: String,
+    address: Vec<u8>,
+    amount: i64,
+    memo: String,
+    created_at: NaiveDateTime,
+    expires_at: Option<NaiveDateTime>,
+    status: i32,
+}
+
+impl PaymentRequestSql {
+    pub fn commit(&self, conn: &mut SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::insert_into(payment_requests::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn find(
+        reference: &str,
+        conn: &mut SqliteConnection,
+    ) -> Result<Option<PaymentRequestSql>, TransactionStorageError> {
+        Ok(payment_requests::table
+            .filter(payment_requests::reference.eq(reference))
+            .first::<PaymentRequestSql>(conn)
+            .optional()?)
+    }
+
+    pub fn index(conn: &mut SqliteConnection) -> Result<Vec<PaymentRequestSql>, TransactionStorageError> {
+        Ok(payment_requests::table
+            .order_by(payment_requests::created_at.asc())
+            .load::<PaymentRequestSql>(conn)?)
+    }
+
+    pub fn update_status(
+        reference: &str,
+        status: PaymentRequestStatus,
+        conn: &mut SqliteConnection,
+    ) -> Result<(), TransactionStorageError> {
+        diesel::update(payment_requests::table.filter(payment_requests::reference.eq(reference)))
+            .set(payment_requests::status.eq(i32::from(status)))
+            .execute(conn)
+            .num_rows_affected_or_not_found(1)?;
+        Ok(())
+    }
+
+    fn into_payment_request(
+        self,
+        payments: &[PaymentRequestPaymentSql],
+    ) -> Result<PaymentRequest, TransactionStorageError> {
+        let amount_received = payments.iter().map(|p| p.amount).sum::<i64>();
+        let amount_inferred = payments
+            .iter()
+            .filter(|p| p.inferred != 0)
+            .map(|p| p.amount)
+            .sum::<i64>();
+        Ok(PaymentRequest {
+            reference: self.reference,
+            address: TariAddress::from_bytes(&self.address)?,
+            amount: MicroMinotari::from(self.amount as u64),
+            memo: self.memo,
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            status: PaymentRequestStatus::try_from(self.status)?,
+            amount_received: MicroMinotari::from(amount_received as u64),
+            amount_inferred: MicroMinotari::from(amount_inferred as u64),
+        })
+    }
+}
+
+impl From<&PaymentRequest> for PaymentRequestSql {
+    fn from(request: &PaymentRequest) -> Self {
+        Self {
+            reference: request.reference.clone(),
+            address: request.address.to_bytes().to_vec(),
+            amount: request.amount.as_u64() as i64,
+            memo: request.memo.clone(),
+            created_at: request.created_at,
+            expires_at: request.expires_at,
+            status: i32::from(request.status),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[diesel(table_name = payment_request_payments)]
+struct PaymentRequestPaymentSql {
+    tx_id: i64,
+    reference: String,
+    amount: i64,
+    received_at: NaiveDateTime,
+    inferred: i32,
+}
+
+impl PaymentRequestPaymentSql {
+    pub fn commit(&self, conn: &mut SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::insert_into(payment_request_payments::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn exists(tx_id: TxId, conn: &mut SqliteConnection) -> Result<bool, TransactionStorageError> {
+        Ok(diesel::select(diesel::dsl::exists(
+            payment_request_payments::table.filter(payment_request_payments::tx_id.eq(tx_id.as_i64_wrapped())),
+        ))
+        .get_result::<bool>(conn)?)
+    }
+
+    pub fn index(conn: &mut SqliteConnection) -> Result<Vec<PaymentRequestPaymentSql>, TransactionStorageError> {
+        Ok(payment_request_payments::table.load::<PaymentRequestPaymentSql>(conn)?)
+    }
+
+    pub fn find(
+        tx_id: TxId,
+        conn: &mut SqliteConnection,
+    ) -> Result<Option<PaymentRequestPaymentSql>, TransactionStorageError> {
+        Ok(payment_request_payments::table
+            .filter(payment_request_payments::tx_id.eq(tx_id.as_i64_wrapped()))
+            .first::<PaymentRequestPaymentSql>(conn)
+            .optional()?)
+    }
+
+    pub fn find_by_reference(
+        reference: &str,
+        conn: &mut SqliteConnection,
+    ) -> Result<Vec<PaymentRequestPaymentSql>, TransactionStorageError> {
+        Ok(payment_request_payments::table
+            .filter(payment_request_payments::reference.eq(reference))
+            .load::<PaymentRequestPaymentSql>(conn)?)
+    }
+
+    pub fn delete(tx_id: TxId, conn: &mut SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::delete(
+            payment_request_payments::table.filter(payment_request_payments::tx_id.eq(tx_id.as_i64_wrapped())),
+        )
+        .execute(conn)
+        .num_rows_affected_or_not_found(1)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, AsChangeset, PartialEq)]
+#[diesel(table_name = scheduled_payments)]
+#[diesel(treat_none_as_null = true)]
+struct ScheduledPaymentSql {
+    id: i64,
+    destination: Vec<u8>,
+    amount: i64,
+    fee_per_gram: i64,
+    message: String,
+    one_sided: i32,
+    start_at: Option<NaiveDateTime>,
+    start_height: Option<i64>,
+    interval_secs: Option<i64>,
+    max_runs: Option<i64>,
+    next_run_at: Option<NaiveDateTime>,
+    run_count: i64,
+    attempts: i32,
+    status: i32,
+    last_tx_id: Option<i64>,
+    last_error: Option<String>,
+    created_at: NaiveDateTime,
+    in_flight_tx_id: Option<i64>,
+}
+
+impl ScheduledPaymentSql {
+    pub fn commit(&self, conn: &mut SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::insert_into(scheduled_payments::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn update(&self, conn: &mut SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::update(scheduled_payments::table.filter(scheduled_payments::id.eq(self.id)))
+            .set(self.clone())
+            .execute(conn)
+            .num_rows_affected_or_not_found(1)?;
+        Ok(())
+    }
+
+    pub fn find(
+        id: ScheduledPaymentId,
+        conn: &mut SqliteConnection,
+    ) -> Result<Option<ScheduledPaymentSql>, TransactionStorageError> {
+        Ok(scheduled_payments::table
+            .filter(scheduled_payments::id.eq(id as i64))
+            .first::<ScheduledPaymentSql>(conn)
+            .optional()?)
+    }
+
+    pub fn index(conn: &mut SqliteConnection) -> Result<Vec<ScheduledPaymentSql>, TransactionStorageError> {
+        Ok(scheduled_payments::table
+            .order_by(scheduled_payments::id.asc())
+            .load::<ScheduledPaymentSql>(conn)?)
+    }
+
+    pub fn max_id(conn: &mut SqliteConnection) -> Result<Option<i64>, TransactionStorageError> {
+        Ok(scheduled_payments::table
+            .select(diesel::dsl::max(scheduled_payments::id))
+            .first::<Option<i64>>(conn)?)
+    }
+
+    fn into_scheduled_payment(self) -> Result<ScheduledPayment, TransactionStorageError> {
+        let schedule = match (self.start_at, self.start_height, self.interval_secs) {
+            (Some(start), None, Some(interval_secs)) => PaymentSchedule::Recurring {
+                start,
+                interval: Duration::from_secs(interval_secs as u64),
+            },
+            (Some(time), None, None) => PaymentSchedule::AtTime(time),
+            (None, Some(height), None) => PaymentSchedule::AtHeight(height as u64),
+            _ => {
+                return Err(ScheduledPaymentError::InvalidSchedule(format!(
+                    "Scheduled payment {} has an invalid schedule",
+                    self.id
+                ))
+                .into())
+            },
+        };
+        Ok(ScheduledPayment {
+            id: self.id as u64,
+            destination: TariAddress::from_bytes(&self.destination)?,
+            amount: MicroMinotari::from(self.amount as u64),
+            fee_per_gram: MicroMinotari::from(self.fee_per_gram as u64),
+            message: self.message,
+            one_sided: self.one_sided != 0,
+            schedule,
+            max_runs: self.max_runs.map(|max_runs| max_runs as u64),
+            next_run_at: self.next_run_at,
+            run_count: self.run_count as u64,
+            attempts: self.attempts as u32,
+            status: ScheduledPaymentStatus::try_from(self.status)?,
+            in_flight_tx_id: self.in_flight_tx_id.map(|tx_id| TxId::from(tx_id as u64)),
+            last_tx_id: self.last_tx_id.map(|tx_id| TxId::from(tx_id as u64)),
+            last_error: self.last_error,
+            created_at: self.created_at,
+        })
+    }
+}
+
+impl From<&ScheduledPayment> for ScheduledPaymentSql {
+    fn from(payment: &ScheduledPayment) -> Self {
+        let (start_at, start_height, interval_secs) = match payment.schedule {
+            PaymentSchedule::AtTime(time) => (Some(time), None, None),
+            PaymentSchedule::AtHeight(height) => (None, Some(height as i64), None),
+            PaymentSchedule::Recurring { start, interval } => (Some(start), None, Some(interval.as_secs() as i64)),
+        };
+        Self {
+            id: payment.id as i64,
+            destination: payment.destination.to_bytes().to_vec(),
+            amount: payment.amount.as_u64() as i64,
+            fee_per_gram: payment.fee_per_gram.as_u64() as i64,
+            message: payment.message.clone(),
+            one_sided: i32::from(payment.one_sided),
+            start_at,
+            start_height,
+            interval_secs,
+            max_runs: payment.max_runs.map(|max_runs| max_runs as i64),
+            next_run_at: payment.next_run_at,
+            run_count: payment.run_count as i64,
+            attempts: payment.attempts as i32,
+            status: i32::from(payment.status),
+            last_tx_id: payment.last_tx_id.map(|tx_id| tx_id.as_i64_wrapped()),
+            last_error: payment.last_error.clone(),
+            created_at: payment.created_at,
+            in_flight_tx_id: payment.in_flight_tx_id.map(|tx_id| tx_id.as_i64_wrapped()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[diesel(table_name = spending_decisions)]
+struct SpendingDecisionSql {
+    id: i64,
+    kind: i32,
+    destination: Option<Vec<u8>>,
+    amount: i64,
+    decision: i32,
+    reason: Option<String>,
+    created_at: NaiveDateTime,
+}
 
-        CompletedTransactionSql::index_by_status_and_cancelled(TransactionStatus::Imported, false, &mut conn)?
-            .into_iter()
-            .map(|ct: CompletedTransactionSql| {
-                CompletedTransaction::try_from(ct, &cipher).map_err(TransactionStorageError::from)
-            })
-            .collect::<Result<Vec<CompletedTransaction>, TransactionStorageError>>()
+impl SpendingDecisionSql {
+    pub fn commit(&self, conn: &mut SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::insert_into(spending_decisions::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
     }
 
-    fn fetch_unconfirmed_faux_transactions(&self) -> Result<Vec<CompletedTransaction>, TransactionStorageError> {
-        let mut conn = self.database_connection.get_pooled_connection()?;
-        let cipher = acquire_read_lock!(self.cipher);
-
-        CompletedTransactionSql::index_by_status_and_cancelled(TransactionStatus::FauxUnconfirmed, false, &mut conn)?
-            .into_iter()
-            .map(|ct: CompletedTransactionSql| {
-                CompletedTransaction::try_from(ct, &cipher).map_err(TransactionStorageError::from)
-            })
-            .collect::<Result<Vec<CompletedTransaction>, TransactionStorageError>>()
+    pub fn index_since(
+        since: NaiveDateTime,
+        conn: &mut SqliteConnection,
+    ) -> Result<Vec<SpendingDecisionSql>, TransactionStorageError> {
+        Ok(spending_decisions::table
+            .filter(spending_decisions::created_at.ge(since))
+            .order_by(spending_decisions::id.asc())
+            .load::<SpendingDecisionSql>(conn)?)
     }
 
-    fn fetch_confirmed_faux_transactions_from_height(
-        &self,
-        height: u64,
-    ) -> Result<Vec<CompletedTransaction>, TransactionStorageError> {
-        let mut conn = self.database_connection.get_pooled_connection()?;
-        let cipher = acquire_read_lock!(self.cipher);
+    pub fn max_id(conn: &mut SqliteConnection) -> Result<Option<i64>, TransactionStorageError> {
+        Ok(spending_decisions::table
+            .select(diesel::dsl::max(spending_decisions::id))
+            .first::<Option<i64>>(conn)?)
+    }
 
-        CompletedTransactionSql::index_by_status_and_cancelled_from_block_height(
-            TransactionStatus::FauxConfirmed,
-            false,
-            height as i64,
-            &mut conn,
-        )?
-        .into_iter()
-        .map(|ct: CompletedTransactionSql| {
-            CompletedTransaction::try_from(ct, &cipher).map_err(TransactionStorageError::from)
+    fn into_spending_decision(self) -> Result<SpendingDecisionRecord, TransactionStorageError> {
+        Ok(SpendingDecisionRecord {
+            id: self.id as u64,
+            kind: SpendKind::try_from(self.kind)?,
+            destination: self
+                .destination
+                .map(|destination| TariAddress::from_bytes(&destination))
+                .transpose()?,
+            amount: MicroMinotari::from(self.amount as u64),
+            decision: SpendDecision::try_from(self.decision)?,
+            reason: self.reason,
+            created_at: self.created_at,
         })
-        .collect::<Result<Vec<CompletedTransaction>, TransactionStorageError>>()
     }
+}
 
-    fn abandon_coinbase_transaction(&self, tx_id: TxId) -> Result<(), TransactionStorageError> {
-        let mut conn = self.database_connection.get_pooled_connection()?;
-        match CompletedTransactionSql::find_and_abandon_coinbase(tx_id, &mut conn) {
-            Ok(_) => {},
-            Err(TransactionStorageError::DieselError(DieselError::NotFound)) => {
-                return Err(TransactionStorageError::ValueNotFound(DbKey::CompletedTransaction(
-                    tx_id,
-                )));
+impl From<&SpendingDecisionRecord> for SpendingDecisionSql {
+    fn from(record: &SpendingDecisionRecord) -> Self {
+        Self {
+            id: record.id as i64,
+            kind: i32::from(record.kind),
+            destination: record.destination.as_ref().map(|d| d.to_bytes().to_vec()),
+            amount: record.amount.as_u64() as i64,
+            decision: i32::from(record.decision),
+            reason: record.reason.clone(),
+            created_at: record.created_at,
+        }
+    }
+}
+
+const LABEL_KIND: i32 = 0;
+const TAG_KIND: i32 = 1;
+
+/// A single label or tag row, selected without the id and commitment columns
+#[derive(Clone, Debug, Queryable, PartialEq)]
+struct TransactionLabelSql {
+    tx_id: Option<i64>,
+    kind: i32,
+    value: String,
+}
+
+impl TransactionLabelSql {
+    pub fn index_by_target(
+        target: &LabelTarget,
+        conn: &mut SqliteConnection,
+    ) -> Result<Vec<TransactionLabelSql>, TransactionStorageError> {
+        let query = transaction_labels::table
+            .select((
+                transaction_labels::tx_id,
+                transaction_labels::kind,
+                transaction_labels::value,
+            ))
+            .into_boxed();
+        let query = match target {
+            LabelTarget::Transaction(tx_id) => query.filter(transaction_labels::tx_id.eq(tx_id.as_i64_wrapped())),
+            LabelTarget::Output(commitment) => query.filter(transaction_labels::commitment.eq(commitment.to_vec())),
+        };
+        Ok(query.load::<TransactionLabelSql>(conn)?)
+    }
+
+    pub fn index_transactions(
+        conn: &mut SqliteConnection,
+    ) -> Result<Vec<TransactionLabelSql>, TransactionStorageError> {
+        Ok(transaction_labels::table
+            .filter(transaction_labels::tx_id.is_not_null())
+            .select((
+                transaction_labels::tx_id,
+                transaction_labels::kind,
+                transaction_labels::value,
+            ))
+            .load::<TransactionLabelSql>(conn)?)
+    }
+
+    pub fn delete_by_target(
+        target: &LabelTarget,
+        conn: &mut SqliteConnection,
+    ) -> Result<usize, TransactionStorageError> {
+        let num_deleted = match target {
+            LabelTarget::Transaction(tx_id) => {
+                diesel::delete(transaction_labels::table.filter(transaction_labels::tx_id.eq(tx_id.as_i64_wrapped())))
+                    .execute(conn)?
             },
-            Err(e) => return Err(e),
+            LabelTarget::Output(commitment) => {
+                diesel::delete(transaction_labels::table.filter(transaction_labels::commitment.eq(commitment.to_vec())))
+                    .execute(conn)?
+            },
+        };
+        Ok(num_deleted)
+    }
+
+    fn into_labels(rows: Vec<TransactionLabelSql>) -> TransactionLabels {
+        let mut label = None;
+        let mut tags = Vec::new();
+        for row in rows {
+            if row.kind == LABEL_KIND {
+                label = Some(row.value);
+            } else {
+                tags.push(row.value);
+            }
+        }
+        TransactionLabels::new(label, tags)
+    }
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = transaction_labels)]
+struct NewTransactionLabelSql {
+    tx_id: Option<i64>,
+    commitment: Option<Vec<u8>>,
+    kind: i32,
+    value: String,
+}
+
+impl NewTransactionLabelSql {
+    pub fn new(target: &LabelTarget, kind: i32, value: String) -> Self {
+        let (tx_id, commitment) = match target {
+            LabelTarget::Transaction(tx_id) => (Some(tx_id.as_i64_wrapped()), None),
+            LabelTarget::Output(commitment) => (None, Some(commitment.to_vec())),
         };
+        Self {
+            tx_id,
+            commitment,
+            kind,
+            value,
+        }
+    }
 
+    pub fn commit(&self, conn: &mut SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::insert_into(transaction_labels::table)
+            .values(self.clone())
+            .execute(conn)?;
         Ok(())
     }
 }
@@ -2258,13 +3233,14 @@ mod test {
         encryption::Encryptable,
         tari_address::TariAddress,
         transaction::{TransactionDirection, TransactionStatus, TxId},
-        types::{PrivateKey, PublicKey, Signature},
+        types::{Commitment, FixedHash, PrivateKey, PublicKey, Signature},
     };
     use tari_core::transactions::{
         tari_amount::MicroMinotari,
         test_helpers::{create_test_core_key_manager_with_memory_db, create_wallet_output_with_data, TestParams},
         transaction_components::{OutputFeatures, Transaction},
         transaction_protocol::sender::TransactionSenderMessage,
+        CryptoFactories,
         ReceiverTransactionProtocol,
         SenderTransactionProtocol,
     };
@@ -2274,19 +3250,33 @@ mod test {
     use tempfile::tempdir;
 
     use crate::{
+        channel_service::protocol::{ChannelParameters, PaymentChannel, PaymentChannelState},
         storage::sqlite_utilities::wallet_db_connection::WalletDbConnection,
         test_utils::create_consensus_constants,
-        transaction_service::storage::{
-            database::{DbKey, TransactionBackend},
-            models::{CompletedTransaction, InboundTransaction, OutboundTransaction, TxCancellationReason},
-            sqlite_db::{
-                CompletedTransactionSql,
-                InboundTransactionSenderInfo,
-                InboundTransactionSql,
-                OutboundTransactionSql,
-                TransactionServiceSqliteDatabase,
-                UpdateCompletedTransactionSql,
+        transaction_service::{
+            payment_request::{PaymentRequest, PaymentRequestStatus},
+            scheduled_payment::{PaymentSchedule, ScheduledPayment, ScheduledPaymentStatus},
+            spending_policy::{SpendDecision, SpendKind, SpendingDecisionRecord},
+            storage::{
+                database::{DbKey, TransactionBackend},
+                models::{
+                    CompletedTransaction,
+                    InboundTransaction,
+                    LabelTarget,
+                    OutboundTransaction,
+                    TransactionLabels,
+                    TxCancellationReason,
+                },
+                sqlite_db::{
+                    CompletedTransactionSql,
+                    InboundTransactionSenderInfo,
+                    InboundTransactionSql,
+                    OutboundTransactionSql,
+                    TransactionServiceSqliteDatabase,
+                    UpdateCompletedTransactionSql,
+                },
             },
+            vault::{Vault, VaultStatus},
         },
     };
 
@@ -3231,4 +4221,436 @@ mod test {
         assert_eq!(info_list.len(), 941);
         assert_eq!(info_list, info_list_reference);
     }
+
+    #[test]
+    fn test_transaction_labels() {
+        let db_name = format!("{}.sqlite3", string(8).as_str());
+        let temp_dir = tempdir().unwrap();
+        let db_folder = temp_dir.path().to_str().unwrap().to_string();
+        let db_path = format!("{}{}", db_folder, db_name);
+
+        const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+        let mut pool = SqliteConnectionPool::new(db_path.clone(), 1, true, true, Duration::from_secs(60));
+        pool.create_pool()
+            .unwrap_or_else(|_| panic!("Error connecting to {}", db_path));
+        pool.get_pooled_connection()
+            .unwrap_or_else(|_| panic!("Error connecting to {}", db_path))
+            .run_pending_migrations(MIGRATIONS)
+            .expect("Migrations failed");
+
+        let mut key = [0u8; size_of::<Key>()];
+        OsRng.fill_bytes(&mut key);
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+        let db = TransactionServiceSqliteDatabase::new(WalletDbConnection::new(pool, None), cipher);
+
+        let tx_id = TxId::from(42u64);
+        let tx_target = LabelTarget::Transaction(tx_id);
+        let output_target = LabelTarget::Output(Commitment::from_public_key(&PublicKey::from_secret_key(
+            &PrivateKey::random(&mut OsRng),
+        )));
+        assert!(db.fetch_labels(&tx_target).unwrap().is_empty());
+
+        let labels = TransactionLabels::new(Some(" Invoice 17 ".to_string()), vec![
+            "rent".to_string(),
+            "2023".to_string(),
+            "rent".to_string(),
+        ]);
+        db.set_labels(&tx_target, labels.clone()).unwrap();
+        db.set_labels(&output_target, TransactionLabels::new(None, vec!["cold".to_string()]))
+            .unwrap();
+
+        let fetched = db.fetch_labels(&tx_target).unwrap();
+        assert_eq!(fetched, labels);
+        assert_eq!(fetched.label.as_deref(), Some("Invoice 17"));
+        assert_eq!(fetched.tags, vec!["2023".to_string(), "rent".to_string()]);
+        assert!(fetched.matches("invoice", &["RENT".to_string()]));
+        assert!(!fetched.matches("", &["payroll".to_string()]));
+        assert_eq!(db.fetch_labels(&output_target).unwrap().tags, vec!["cold".to_string()]);
+
+        let all = db.fetch_all_transaction_labels().unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all.get(&tx_id), Some(&labels));
+
+        // Setting labels replaces the previous ones and empty labels remove them
+        db.set_labels(&tx_target, TransactionLabels::new(None, vec!["payroll".to_string()]))
+            .unwrap();
+        assert_eq!(db.fetch_labels(&tx_target).unwrap().label, None);
+        db.set_labels(&tx_target, TransactionLabels::default()).unwrap();
+        assert!(db.fetch_labels(&tx_target).unwrap().is_empty());
+        assert!(db.fetch_all_transaction_labels().unwrap().is_empty());
+        assert!(!db.fetch_labels(&output_target).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_payment_requests() {
+        let db_name = format!("{}.sqlite3", string(8).as_str());
+        let temp_dir = tempdir().unwrap();
+        let db_folder = temp_dir.path().to_str().unwrap().to_string();
+        let db_path = format!("{}{}", db_folder, db_name);
+
+        const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+        let mut pool = SqliteConnectionPool::new(db_path.clone(), 1, true, true, Duration::from_secs(60));
+        pool.create_pool()
+            .unwrap_or_else(|_| panic!("Error connecting to {}", db_path));
+        pool.get_pooled_connection()
+            .unwrap_or_else(|_| panic!("Error connecting to {}", db_path))
+            .run_pending_migrations(MIGRATIONS)
+            .expect("Migrations failed");
+
+        let mut key = [0u8; size_of::<Key>()];
+        OsRng.fill_bytes(&mut key);
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+        let db = TransactionServiceSqliteDatabase::new(WalletDbConnection::new(pool, None), cipher);
+
+        let address = TariAddress::new(
+            PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+            Network::LocalNet,
+        );
+        let now = Utc::now().naive_utc();
+        let request = PaymentRequest::new(address, MicroMinotari::from(1000), "Invoice 17".to_string(), now, None);
+        db.insert_payment_request(&request).unwrap();
+        assert!(db.fetch_payment_request("unknown").unwrap().is_none());
+        assert_eq!(
+            db.fetch_payment_request(&request.reference).unwrap(),
+            Some(request.clone())
+        );
+
+        let tx_id = TxId::from(1u64);
+        assert!(db
+            .record_payment_request_payment(
+                &request.reference,
+                tx_id,
+                MicroMinotari::from(400),
+                false,
+                PaymentRequestStatus::Underpaid
+            )
+            .unwrap());
+        // The same transaction is only counted once
+        assert!(!db
+            .record_payment_request_payment(
+                &request.reference,
+                tx_id,
+                MicroMinotari::from(400),
+                false,
+                PaymentRequestStatus::Paid
+            )
+            .unwrap());
+        assert!(db
+            .record_payment_request_payment(
+                &request.reference,
+                TxId::from(2u64),
+                MicroMinotari::from(600),
+                true,
+                PaymentRequestStatus::Paid
+            )
+            .unwrap());
+
+        let requests = db.fetch_payment_requests().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].status, PaymentRequestStatus::Paid);
+        assert_eq!(requests[0].amount_received, MicroMinotari::from(1000));
+        assert_eq!(requests[0].amount_inferred, MicroMinotari::from(600));
+        let mut tx_ids = db.fetch_payment_request_payment_tx_ids().unwrap();
+        tx_ids.sort_by_key(|tx_id| tx_id.as_u64());
+        assert_eq!(tx_ids, vec![tx_id, TxId::from(2u64)]);
+
+        // Reverting a payment takes it off the request and reopens the request
+        assert_eq!(
+            db.revert_payment_request_payment(TxId::from(2u64)).unwrap(),
+            Some((request.reference.clone(), PaymentRequestStatus::Underpaid))
+        );
+        assert!(db.revert_payment_request_payment(TxId::from(2u64)).unwrap().is_none());
+        let reverted = db.fetch_payment_request(&request.reference).unwrap().unwrap();
+        assert_eq!(reverted.status, PaymentRequestStatus::Underpaid);
+        assert_eq!(reverted.amount_received, MicroMinotari::from(400));
+        assert_eq!(reverted.amount_inferred, MicroMinotari::zero());
+
+        db.set_payment_request_status(&request.reference, PaymentRequestStatus::Cancelled)
+            .unwrap();
+        assert_eq!(
+            db.fetch_payment_request(&request.reference).unwrap().unwrap().status,
+            PaymentRequestStatus::Cancelled
+        );
+        assert!(db
+            .set_payment_request_status("unknown", PaymentRequestStatus::Expired)
+            .is_err());
+    }
+
+    #[test]
+    fn test_scheduled_payments() {
+        let db_name = format!("{}.sqlite3", string(8).as_str());
+        let temp_dir = tempdir().unwrap();
+        let db_folder = temp_dir.path().to_str().unwrap().to_string();
+        let db_path = format!("{}{}", db_folder, db_name);
+
+        const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+        let mut pool = SqliteConnectionPool::new(db_path.clone(), 1, true, true, Duration::from_secs(60));
+        pool.create_pool()
+            .unwrap_or_else(|_| panic!("Error connecting to {}", db_path));
+        pool.get_pooled_connection()
+            .unwrap_or_else(|_| panic!("Error connecting to {}", db_path))
+            .run_pending_migrations(MIGRATIONS)
+            .expect("Migrations failed");
+
+        let mut key = [0u8; size_of::<Key>()];
+        OsRng.fill_bytes(&mut key);
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+        let db = TransactionServiceSqliteDatabase::new(WalletDbConnection::new(pool, None), cipher);
+
+        let address = TariAddress::new(
+            PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+            Network::LocalNet,
+        );
+        let now = Utc::now().naive_utc();
+        let one_off = db
+            .insert_scheduled_payment(ScheduledPayment::new(
+                address.clone(),
+                MicroMinotari::from(1000),
+                MicroMinotari::from(5),
+                "Rent".to_string(),
+                false,
+                PaymentSchedule::AtHeight(100),
+                None,
+                now,
+            ))
+            .unwrap();
+        let mut recurring = db
+            .insert_scheduled_payment(ScheduledPayment::new(
+                address,
+                MicroMinotari::from(2000),
+                MicroMinotari::from(5),
+                "Salary".to_string(),
+                true,
+                PaymentSchedule::Recurring {
+                    start: now,
+                    interval: Duration::from_secs(3600),
+                },
+                Some(12),
+                now,
+            ))
+            .unwrap();
+        assert_eq!(one_off.id, 1);
+        assert_eq!(recurring.id, 2);
+        assert!(db.fetch_scheduled_payment(3).unwrap().is_none());
+        assert_eq!(db.fetch_scheduled_payment(1).unwrap(), Some(one_off.clone()));
+
+        recurring.in_flight_tx_id = Some(TxId::from(7u64));
+        db.update_scheduled_payment(&recurring).unwrap();
+        assert_eq!(db.fetch_scheduled_payment(2).unwrap(), Some(recurring.clone()));
+        recurring.record_success(TxId::from(7u64), now);
+        db.update_scheduled_payment(&recurring).unwrap();
+        assert_eq!(db.fetch_scheduled_payments().unwrap(), vec![one_off.clone(), recurring]);
+
+        let cancelled = ScheduledPayment {
+            status: ScheduledPaymentStatus::Cancelled,
+            ..one_off
+        };
+        db.update_scheduled_payment(&cancelled).unwrap();
+        assert_eq!(
+            db.fetch_scheduled_payment(1).unwrap().unwrap().status,
+            ScheduledPaymentStatus::Cancelled
+        );
+        assert!(db
+            .update_scheduled_payment(&ScheduledPayment { id: 3, ..cancelled })
+            .is_err());
+    }
+
+    #[test]
+    fn test_spending_decisions() {
+        let db_name = format!("{}.sqlite3", string(8).as_str());
+        let temp_dir = tempdir().unwrap();
+        let db_folder = temp_dir.path().to_str().unwrap().to_string();
+        let db_path = format!("{}{}", db_folder, db_name);
+
+        const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+        let mut pool = SqliteConnectionPool::new(db_path.clone(), 1, true, true, Duration::from_secs(60));
+        pool.create_pool()
+            .unwrap_or_else(|_| panic!("Error connecting to {}", db_path));
+        pool.get_pooled_connection()
+            .unwrap_or_else(|_| panic!("Error connecting to {}", db_path))
+            .run_pending_migrations(MIGRATIONS)
+            .expect("Migrations failed");
+
+        let mut key = [0u8; size_of::<Key>()];
+        OsRng.fill_bytes(&mut key);
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+        let db = TransactionServiceSqliteDatabase::new(WalletDbConnection::new(pool, None), cipher);
+
+        let address = TariAddress::new(
+            PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+            Network::LocalNet,
+        );
+        let now = Utc::now().naive_utc();
+        let old = db
+            .insert_spending_decision(SpendingDecisionRecord::new(
+                SpendKind::Interactive,
+                Some(address.clone()),
+                MicroMinotari::from(1000),
+                SpendDecision::Allowed,
+                None,
+                now - chrono::Duration::hours(25),
+            ))
+            .unwrap();
+        let denied = db
+            .insert_spending_decision(SpendingDecisionRecord::new(
+                SpendKind::OneSided,
+                Some(address),
+                MicroMinotari::from(2000),
+                SpendDecision::Denied,
+                Some("Above the per-transaction limit".to_string()),
+                now - chrono::Duration::hours(1),
+            ))
+            .unwrap();
+        let burn = db
+            .insert_spending_decision(SpendingDecisionRecord::new(
+                SpendKind::Burn,
+                None,
+                MicroMinotari::from(500),
+                SpendDecision::Queued,
+                Some("Above the approval threshold".to_string()),
+                now,
+            ))
+            .unwrap();
+        assert_eq!((old.id, denied.id, burn.id), (1, 2, 3));
+
+        assert_eq!(
+            db.fetch_spending_decisions_since(now - chrono::Duration::hours(24))
+                .unwrap(),
+            vec![denied, burn]
+        );
+        assert_eq!(
+            db.fetch_spending_decisions_since(now - chrono::Duration::hours(48))
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_vaults() {
+        let db_name = format!("{}.sqlite3", string(8).as_str());
+        let temp_dir = tempdir().unwrap();
+        let db_folder = temp_dir.path().to_str().unwrap().to_string();
+        let db_path = format!("{}{}", db_folder, db_name);
+
+        const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+        let mut pool = SqliteConnectionPool::new(db_path.clone(), 1, true, true, Duration::from_secs(60));
+        pool.create_pool()
+            .unwrap_or_else(|_| panic!("Error connecting to {}", db_path));
+        pool.get_pooled_connection()
+            .unwrap_or_else(|_| panic!("Error connecting to {}", db_path))
+            .run_pending_migrations(MIGRATIONS)
+            .expect("Migrations failed");
+
+        let mut key = [0u8; size_of::<Key>()];
+        OsRng.fill_bytes(&mut key);
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+        let db = TransactionServiceSqliteDatabase::new(WalletDbConnection::new(pool, None), cipher);
+
+        let vault = |amount: u64| Vault {
+            id: 0,
+            tx_id: TxId::new_random(),
+            commitment: Commitment::from_public_key(&PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng))),
+            output_hash: FixedHash::from([amount as u8; 32]),
+            amount: MicroMinotari::from(amount),
+            hot_public_key: PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+            recovery_public_key: PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+            unlock_height: 1_000,
+            status: VaultStatus::Active,
+            spend_tx_id: None,
+            created_at: Utc::now().naive_utc(),
+        };
+        let first = db.insert_vault(vault(5_000)).unwrap();
+        let second = db.insert_vault(vault(7_000)).unwrap();
+        assert_eq!((first.id, second.id), (1, 2));
+        assert!(db.fetch_vault(3).unwrap().is_none());
+
+        let fetched = db.fetch_vault(2).unwrap().unwrap();
+        assert_eq!(fetched.amount, MicroMinotari::from(7_000));
+        assert_eq!(fetched.commitment, second.commitment);
+        assert_eq!(fetched.output_hash, second.output_hash);
+        assert_eq!(fetched.recovery_public_key, second.recovery_public_key);
+
+        let recovered = Vault {
+            status: VaultStatus::Recovered,
+            spend_tx_id: Some(TxId::from(9u64)),
+            ..first
+        };
+        db.update_vault(&recovered).unwrap();
+        let vaults = db.fetch_vaults().unwrap();
+        assert_eq!(vaults.len(), 2);
+        assert_eq!(vaults[0].status, VaultStatus::Recovered);
+        assert_eq!(vaults[0].spend_tx_id, Some(TxId::from(9u64)));
+        assert_eq!(vaults[1].status, VaultStatus::Active);
+
+        assert!(db.update_vault(&Vault { id: 3, ..recovered }).is_err());
+    }
+
+    #[test]
+    fn test_payment_channels() {
+        let db_name = format!("{}.sqlite3", string(8).as_str());
+        let temp_dir = tempdir().unwrap();
+        let db_folder = temp_dir.path().to_str().unwrap().to_string();
+        let db_path = format!("{}{}", db_folder, db_name);
+
+        const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+        let mut pool = SqliteConnectionPool::new(db_path.clone(), 1, true, true, Duration::from_secs(60));
+        pool.create_pool()
+            .unwrap_or_else(|_| panic!("Error connecting to {}", db_path));
+        pool.get_pooled_connection()
+            .unwrap_or_else(|_| panic!("Error connecting to {}", db_path))
+            .run_pending_migrations(MIGRATIONS)
+            .expect("Migrations failed");
+
+        let mut key = [0u8; size_of::<Key>()];
+        OsRng.fill_bytes(&mut key);
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+        let db = TransactionServiceSqliteDatabase::new(WalletDbConnection::new(pool, None), cipher);
+
+        let parameters = ChannelParameters {
+            capacity: MicroMinotari::from(10_000_000),
+            fee: MicroMinotari::from(1_000),
+            state_lifetime: 1_000,
+            dispute_delay: 100,
+        };
+        let factories = CryptoFactories::default();
+        let (mut opener, open) = PaymentChannel::open(
+            TariAddress::default(),
+            parameters.clone(),
+            100,
+            &PrivateKey::random(&mut OsRng),
+        )
+        .unwrap();
+        let (_, accept) = PaymentChannel::accept(
+            opener.id,
+            TariAddress::default(),
+            open,
+            100,
+            &PrivateKey::random(&mut OsRng),
+            &factories,
+        )
+        .unwrap();
+        db.insert_payment_channel(&opener).unwrap();
+        assert!(db.insert_payment_channel(&opener).is_err());
+        assert!(db.fetch_payment_channel(opener.id.wrapping_add(1)).unwrap().is_none());
+
+        let fetched = db.fetch_payment_channel(opener.id).unwrap().unwrap();
+        assert_eq!(fetched.state, PaymentChannelState::Opening);
+        assert_eq!(fetched.parameters, parameters);
+        assert!(fetched.funding_script().is_err());
+
+        opener.accept_channel(accept, &factories).unwrap();
+        db.update_payment_channel(&opener).unwrap();
+        let channels = db.fetch_payment_channels().unwrap();
+        assert_eq!(channels.len(), 1);
+        assert_eq!(channels[0].state, PaymentChannelState::Funding);
+        assert_eq!(channels[0].sequence(), Some(0));
+        // The channel keys and signed close transactions survive the round trip
+        assert_eq!(channels[0].funding_mask(), opener.funding_mask());
+        assert_eq!(channels[0].funding_script().unwrap(), opener.funding_script().unwrap());
+        assert_eq!(channels[0].close_kernels(), opener.close_kernels());
+
+        opener.id = opener.id.wrapping_add(1);
+        assert!(db.update_payment_channel(&opener).is_err());
+    }
 }
@@ -0,0 +1,224 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Timelocked vaults: outputs that a hot key can only spend once the chain reaches an unlock height, and that a cold
+//! recovery key can spend at any time.
+//!
+//! The vault output is locked with the script
+//!
+//! ```text
+//! IfThen
+//!     PushPubKey(recovery)
+//! Else
+//!     CheckHeightVerify(unlock_height) PushPubKey(hot)
+//! EndIf
+//! ```
+//!
+//! The input data selects the path: `1` for the recovery path and `0` for the hot path. Either way the script leaves a
+//! single public key on the stack, so the input's script signature must be made with the matching secret key.
+//!
+//! The recovery key is the public key of the address of a cold wallet. The vault output is built like a one-sided
+//! payment to that address, so the cold wallet finds the vault when it scans the chain and adds it to its balance with
+//! the recovery path input data. The recovery secret key never enters the wallet that created the vault.
+
+use std::{
+    convert::TryFrom,
+    fmt,
+    fmt::{Display, Formatter},
+};
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use tari_common_types::{
+    transaction::TxId,
+    types::{Commitment, HashOutput, PublicKey},
+};
+use tari_core::transactions::tari_amount::MicroMinotari;
+use tari_script::{inputs, script, ExecutionStack, Opcode, TariScript};
+use thiserror::Error;
+
+pub type VaultId = u64;
+
+#[derive(Debug, Error)]
+pub enum VaultError {
+    #[error("Invalid vault: {0}")]
+    InvalidVault(String),
+    #[error("Vault {0} not found")]
+    NotFound(VaultId),
+    #[error("Vault {0} is {1}")]
+    NotActive(VaultId, VaultStatus),
+    #[error("Vault {id} is locked until height {unlock_height}, the chain is at height {tip_height}")]
+    Locked {
+        id: VaultId,
+        unlock_height: u64,
+        tip_height: u64,
+    },
+    #[error("The vault output for vault {0} is not in the wallet")]
+    OutputNotFound(VaultId),
+    #[error("Unknown vault status {0}")]
+    UnknownStatus(i32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum VaultStatus {
+    /// The funds are in the vault
+    Active,
+    /// The funds were returned to the wallet through the hot path
+    Spent,
+    /// The funds were spent by the wallet holding the recovery key
+    Recovered,
+}
+
+impl Display for VaultStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            VaultStatus::Active => write!(f, "Active"),
+            VaultStatus::Spent => write!(f, "Spent"),
+            VaultStatus::Recovered => write!(f, "Recovered"),
+        }
+    }
+}
+
+impl From<VaultStatus> for i32 {
+    fn from(status: VaultStatus) -> Self {
+        match status {
+            VaultStatus::Active => 0,
+            VaultStatus::Spent => 1,
+            VaultStatus::Recovered => 2,
+        }
+    }
+}
+
+impl TryFrom<i32> for VaultStatus {
+    type Error = VaultError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(VaultStatus::Active),
+            1 => Ok(VaultStatus::Spent),
+            2 => Ok(VaultStatus::Recovered),
+            v => Err(VaultError::UnknownStatus(v)),
+        }
+    }
+}
+
+/// The script that locks a vault output
+pub fn vault_script(recovery_public_key: &PublicKey, hot_public_key: &PublicKey, unlock_height: u64) -> TariScript {
+    script!(
+        IfThen
+            PushPubKey(Box::new(recovery_public_key.clone()))
+        Else
+            CheckHeightVerify(unlock_height) PushPubKey(Box::new(hot_public_key.clone()))
+        EndIf
+    )
+}
+
+/// Returns the recovery public key of `script` if it is a vault script
+pub fn vault_recovery_public_key(script: &TariScript) -> Option<&PublicKey> {
+    use Opcode::{CheckHeightVerify, Else, EndIf, IfThen, PushPubKey};
+    match script.as_slice() {
+        [IfThen, PushPubKey(recovery), Else, CheckHeightVerify(_), PushPubKey(_), EndIf] => Some(recovery.as_ref()),
+        _ => None,
+    }
+}
+
+/// The input data that spends a vault output with the hot key
+pub fn hot_path_input_data() -> ExecutionStack {
+    inputs!(0)
+}
+
+/// The input data that spends a vault output with the recovery key
+pub fn recovery_path_input_data() -> ExecutionStack {
+    inputs!(1)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Vault {
+    pub id: VaultId,
+    /// The transaction that funded the vault
+    pub tx_id: TxId,
+    pub commitment: Commitment,
+    pub output_hash: HashOutput,
+    pub amount: MicroMinotari,
+    pub hot_public_key: PublicKey,
+    pub recovery_public_key: PublicKey,
+    /// The first height at which the hot key can spend the vault
+    pub unlock_height: u64,
+    pub status: VaultStatus,
+    /// The transaction that spent or recovered the vault
+    pub spend_tx_id: Option<TxId>,
+    pub created_at: NaiveDateTime,
+}
+
+impl Vault {
+    pub fn script(&self) -> TariScript {
+        vault_script(&self.recovery_public_key, &self.hot_public_key, self.unlock_height)
+    }
+
+    /// Returns true if the hot key can spend the vault in a block at `height`
+    pub fn is_unlocked(&self, height: u64) -> bool {
+        height >= self.unlock_height
+    }
+
+    /// The number of blocks until the hot key can spend the vault
+    pub fn blocks_until_unlock(&self, tip_height: u64) -> u64 {
+        self.unlock_height.saturating_sub(tip_height)
+    }
+}
+
+impl Display for Vault {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{} {} unlocks at height {} ({})",
+            self.id, self.amount, self.unlock_height, self.status
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::rngs::OsRng;
+    use tari_common_types::types::PrivateKey;
+    use tari_crypto::keys::{PublicKey as PublicKeyTrait, SecretKey};
+    use tari_script::{ScriptContext, ScriptError, StackItem};
+
+    use super::*;
+
+    fn context(height: u64) -> ScriptContext {
+        ScriptContext::new(height, &[0u8; 32], &Commitment::default())
+    }
+
+    #[test]
+    fn it_unlocks_the_hot_path_at_the_unlock_height() {
+        let hot = PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng));
+        let recovery = PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng));
+        let script = vault_script(&recovery, &hot, 100);
+
+        assert_eq!(
+            script.execute_with_context(&hot_path_input_data(), &context(99)),
+            Err(ScriptError::VerifyFailed)
+        );
+        assert_eq!(
+            script.execute_with_context(&hot_path_input_data(), &context(100)),
+            Ok(StackItem::PublicKey(hot))
+        );
+        for height in [0, 100] {
+            assert_eq!(
+                script.execute_with_context(&recovery_path_input_data(), &context(height)),
+                Ok(StackItem::PublicKey(recovery.clone()))
+            );
+        }
+    }
+
+    #[test]
+    fn it_has_a_spendable_path_for_each_key() {
+        let hot = PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng));
+        let recovery = PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng));
+        let analysis = vault_script(&recovery, &hot, 100).analyse();
+        assert!(!analysis.is_malleable());
+        let mut min_heights = analysis.spendable_paths().map(|p| p.min_height).collect::<Vec<_>>();
+        min_heights.sort();
+        assert_eq!(min_heights, vec![None, Some(100)]);
+    }
+}
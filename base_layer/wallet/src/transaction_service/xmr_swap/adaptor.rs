@@ -0,0 +1,85 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Schnorr adaptor signatures over Ristretto.
+//!
+//! An adaptor signature is a partial signature `s' = r + e.x` whose challenge `e` commits to a nonce that includes an
+//! adaptor point `T = t.G`. It can only be turned into a valid signature `(R + T, s' + t)` by someone who knows `t`,
+//! and anyone holding both `s'` and the completed signature learns `t`.
+
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::{PrivateKey, PublicKey, Signature};
+use tari_crypto::keys::PublicKey as PublicKeyTrait;
+
+use crate::transaction_service::xmr_swap::XmrSwapError;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdaptorSignature {
+    /// The signer's public nonce `R`, without the adaptor point
+    public_nonce: PublicKey,
+    pre_signature: PrivateKey,
+}
+
+impl AdaptorSignature {
+    /// Signs `challenge` with `secret`. The challenge must already commit to the adaptor point.
+    pub fn sign(secret: &PrivateKey, nonce: PrivateKey, challenge: &[u8; 32]) -> Result<Self, XmrSwapError> {
+        let signature = Signature::sign_raw(secret, nonce, challenge)?;
+        Ok(Self {
+            public_nonce: signature.get_public_nonce().clone(),
+            pre_signature: signature.get_signature().clone(),
+        })
+    }
+
+    pub fn public_nonce(&self) -> &PublicKey {
+        &self.public_nonce
+    }
+
+    /// Checks that this adaptor signature can be completed into a signature by `public_key` on `challenge`
+    pub fn verify(&self, public_key: &PublicKey, challenge: &[u8; 32]) -> bool {
+        Signature::new(self.public_nonce.clone(), self.pre_signature.clone()).verify_challenge(public_key, challenge)
+    }
+
+    /// Completes the signature with the adaptor secret `t`
+    pub fn adapt(&self, adaptor_secret: &PrivateKey) -> Signature {
+        Signature::new(
+            &self.public_nonce + &PublicKey::from_secret_key(adaptor_secret),
+            &self.pre_signature + adaptor_secret,
+        )
+    }
+
+    /// Recovers the adaptor secret `t` from the completed signature
+    pub fn extract(&self, adapted: &Signature) -> PrivateKey {
+        adapted.get_signature() - &self.pre_signature
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::rngs::OsRng;
+    use tari_crypto::keys::SecretKey;
+
+    use super::*;
+
+    #[test]
+    fn it_adapts_and_extracts() {
+        let (secret, public_key) = PublicKey::random_keypair(&mut OsRng);
+        let adaptor_secret = PrivateKey::random(&mut OsRng);
+        let challenge = [7u8; 32];
+
+        let adaptor = AdaptorSignature::sign(&secret, PrivateKey::random(&mut OsRng), &challenge).unwrap();
+        assert!(adaptor.verify(&public_key, &challenge));
+        assert!(!adaptor.verify(&public_key, &[8u8; 32]));
+
+        let signature = adaptor.adapt(&adaptor_secret);
+        assert_eq!(
+            signature.get_public_nonce(),
+            &(adaptor.public_nonce() + &PublicKey::from_secret_key(&adaptor_secret))
+        );
+        assert!(signature.verify_challenge(&public_key, &challenge));
+        assert_eq!(adaptor.extract(&signature), adaptor_secret);
+
+        // The wrong adaptor secret leaks nothing useful
+        let other = adaptor.adapt(&PrivateKey::random(&mut OsRng));
+        assert_ne!(adaptor.extract(&other), adaptor_secret);
+    }
+}
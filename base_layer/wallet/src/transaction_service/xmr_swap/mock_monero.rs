@@ -0,0 +1,75 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! A minimal in-memory Monero chain for exercising the swap protocol without a Monero wallet. Outputs are locked to
+//! a public spend key and can only be swept with its secret key.
+
+use tari_common_types::types::PrivateKey;
+
+use crate::transaction_service::xmr_swap::{monero::MoneroPublicKey, XmrSwapError};
+
+#[derive(Debug, Clone)]
+struct MockMoneroOutput {
+    spend_public_key: MoneroPublicKey,
+    amount: u64,
+    height: u64,
+    spent: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MockMoneroChain {
+    height: u64,
+    outputs: Vec<MockMoneroOutput>,
+}
+
+impl MockMoneroChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mine(&mut self, blocks: u64) {
+        self.height += blocks;
+    }
+
+    /// Sends `amount` to the given spend key in the next block
+    pub fn lock(&mut self, spend_public_key: MoneroPublicKey, amount: u64) {
+        self.outputs.push(MockMoneroOutput {
+            spend_public_key,
+            amount,
+            height: self.height + 1,
+            spent: false,
+        });
+    }
+
+    /// The unspent amount locked to the given spend key with at least `min_confirmations` confirmations
+    pub fn locked_amount(&self, spend_public_key: &MoneroPublicKey, min_confirmations: u64) -> u64 {
+        self.outputs
+            .iter()
+            .filter(|o| !o.spent && &o.spend_public_key == spend_public_key)
+            .filter(|o| o.height <= self.height && self.height - o.height + 1 >= min_confirmations)
+            .map(|o| o.amount)
+            .sum()
+    }
+
+    /// Spends every confirmed output locked to the public key of `spend_secret`, returning the amount swept
+    pub fn sweep(&mut self, spend_secret: &PrivateKey) -> Result<u64, XmrSwapError> {
+        let spend_public_key = MoneroPublicKey::from_secret_key(spend_secret)?;
+        let height = self.height;
+        let amount: u64 = self
+            .outputs
+            .iter_mut()
+            .filter(|o| !o.spent && o.spend_public_key == spend_public_key && o.height <= height)
+            .map(|o| {
+                o.spent = true;
+                o.amount
+            })
+            .sum();
+        if amount == 0 {
+            return Err(XmrSwapError::MoneroError(format!(
+                "Nothing to sweep for spend key {}",
+                spend_public_key
+            )));
+        }
+        Ok(amount)
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Scriptless atomic swaps between Tari and Monero using adaptor signatures.
+//!
+//! Monero has no hash-locks, so the SHA-preimage HTLC swaps cannot be used with it. Instead, the Monero is locked to
+//! a spend key `s_s + s_b` that is split between the two parties, and the Tari is locked in an output whose commitment
+//! mask is split between them in the same way. Every spend of the Tari lock output needs a kernel signature from both
+//! parties, and each party hands over its part of that signature as an adaptor signature encrypted under its own
+//! Monero key share. Publishing the completed kernel signature therefore reveals that key share to the other party,
+//! who can then sweep the Monero.
+//!
+//! The Tari seller locks the Tari and the Tari buyer locks the Monero. The lock output script has three windows:
+//! - before `refund_height` the buyer can claim the Tari, which reveals the buyer's key share to the seller;
+//! - from `refund_height` the seller can refund the Tari, which reveals the seller's key share to the buyer;
+//! - from `punish_height` the buyer can take the Tari if the seller has not refunded it, so that a seller who stops
+//!   responding after the Monero was locked cannot hold the buyer's Monero hostage.
+//!
+//! [`protocol::XmrSwap`] is the state machine of one side of a swap. The module covers the protocol only: the wallet
+//! does not persist swaps, exchange the swap messages, build or broadcast the lock, claim, refund or punish
+//! transactions, or talk to a Monero wallet. A driver for the swap has to do these and persist the swap after every
+//! step. In tests, a mock Monero chain stands in for the Monero wallet.
+
+use std::{
+    fmt,
+    fmt::{Display, Formatter},
+};
+
+use serde::{Deserialize, Serialize};
+use tari_crypto::signatures::SchnorrSignatureError;
+use thiserror::Error;
+
+pub mod adaptor;
+#[cfg(test)]
+mod mock_monero;
+pub mod monero;
+pub mod protocol;
+
+#[derive(Debug, Error)]
+pub enum XmrSwapError {
+    #[error("Invalid swap parameters: {0}")]
+    InvalidParameters(String),
+    #[error("Cannot {action} while the swap is {state}")]
+    InvalidState { action: &'static str, state: XmrSwapState },
+    #[error("Only the {0} can do this")]
+    WrongRole(XmrSwapRole),
+    #[error("Invalid {0} from the counterparty")]
+    InvalidCounterpartyData(&'static str),
+    #[error("The {0} signature is missing")]
+    MissingSignature(&'static str),
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+    #[error("Signature error: {0}")]
+    SignatureError(#[from] SchnorrSignatureError),
+    #[error("Monero error: {0}")]
+    MoneroError(String),
+}
+
+/// The side of the swap this wallet is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum XmrSwapRole {
+    /// Locks Tari and receives Monero
+    TariSeller,
+    /// Locks Monero and receives Tari
+    TariBuyer,
+}
+
+impl Display for XmrSwapRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            XmrSwapRole::TariSeller => write!(f, "Tari seller"),
+            XmrSwapRole::TariBuyer => write!(f, "Tari buyer"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum XmrSwapState {
+    /// Waiting for the counterparty's keys
+    Created,
+    /// Keys exchanged, waiting for the counterparty's signatures
+    KeysExchanged,
+    /// All signatures needed to refund or punish have been exchanged, the Tari can be locked
+    ReadyToLock,
+    TariLocked,
+    MoneroLocked,
+    /// The seller has sent the claim adaptor signature to the buyer
+    ClaimAuthorized,
+    TariClaimed,
+    MoneroClaimed,
+    TariRefunded,
+    MoneroRefunded,
+    TariPunished,
+    /// Abandoned before any funds were locked
+    Aborted,
+}
+
+impl Display for XmrSwapState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            XmrSwapState::Created => write!(f, "Created"),
+            XmrSwapState::KeysExchanged => write!(f, "Keys exchanged"),
+            XmrSwapState::ReadyToLock => write!(f, "Ready to lock"),
+            XmrSwapState::TariLocked => write!(f, "Tari locked"),
+            XmrSwapState::MoneroLocked => write!(f, "Monero locked"),
+            XmrSwapState::ClaimAuthorized => write!(f, "Claim authorized"),
+            XmrSwapState::TariClaimed => write!(f, "Tari claimed"),
+            XmrSwapState::MoneroClaimed => write!(f, "Monero claimed"),
+            XmrSwapState::TariRefunded => write!(f, "Tari refunded"),
+            XmrSwapState::MoneroRefunded => write!(f, "Monero refunded"),
+            XmrSwapState::TariPunished => write!(f, "Tari punished"),
+            XmrSwapState::Aborted => write!(f, "Aborted"),
+        }
+    }
+}
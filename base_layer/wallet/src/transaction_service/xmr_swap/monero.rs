@@ -0,0 +1,184 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Monero keys and the cross-curve proof that links them to Tari keys.
+//!
+//! Ristretto and ed25519 share the same scalar field, so a Tari secret key is also a valid Monero secret key. The
+//! [`DleqProof`] shows that a Ristretto public key and an ed25519 public key have the same secret key, which lets
+//! each party check that the key share it will learn from the Tari adaptor signature also unlocks the Monero.
+
+use std::{
+    convert::TryInto,
+    fmt,
+    fmt::{Display, Formatter},
+};
+
+use blake2::Blake2b;
+use curve25519_dalek::{
+    constants::{ED25519_BASEPOINT_POINT, RISTRETTO_BASEPOINT_POINT},
+    edwards::{CompressedEdwardsY, EdwardsPoint},
+    ristretto::CompressedRistretto,
+    scalar::Scalar,
+};
+use digest::consts::U64;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::{PrivateKey, PublicKey};
+use tari_crypto::{hash_domain, hashing::DomainSeparatedHasher, keys::SecretKey};
+use tari_utilities::{hex::to_hex, ByteArray};
+
+use crate::transaction_service::xmr_swap::XmrSwapError;
+
+hash_domain!(XmrSwapHashDomain, "com.tari.base_layer.wallet.xmr_swap", 0);
+
+/// A Monero (ed25519) public key in compressed form
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MoneroPublicKey([u8; 32]);
+
+impl MoneroPublicKey {
+    pub fn from_secret_key(secret: &PrivateKey) -> Result<Self, XmrSwapError> {
+        let scalar = to_scalar(secret)?;
+        Ok(Self((scalar * ED25519_BASEPOINT_POINT).compress().to_bytes()))
+    }
+
+    /// Parses a compressed key, rejecting points outside the prime-order subgroup
+    pub fn from_bytes(bytes: [u8; 32]) -> Result<Self, XmrSwapError> {
+        let key = Self(bytes);
+        key.point()?;
+        Ok(key)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// The key of the sum of the two secret keys
+    pub fn combine(&self, other: &MoneroPublicKey) -> Result<Self, XmrSwapError> {
+        Ok(Self((self.point()? + other.point()?).compress().to_bytes()))
+    }
+
+    fn point(&self) -> Result<EdwardsPoint, XmrSwapError> {
+        CompressedEdwardsY(self.0)
+            .decompress()
+            .filter(EdwardsPoint::is_torsion_free)
+            .ok_or_else(|| XmrSwapError::InvalidKey(format!("{} is not a valid Monero public key", self)))
+    }
+}
+
+impl Display for MoneroPublicKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", to_hex(&self.0))
+    }
+}
+
+/// A discrete log equality proof across Ristretto and ed25519: a Chaum-Pedersen proof that `P = x.G` and
+/// `P' = x.B` for the same `x`, where `G` and `B` are the Ristretto and ed25519 base points.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DleqProof {
+    ristretto_nonce: [u8; 32],
+    ed25519_nonce: [u8; 32],
+    response: [u8; 32],
+}
+
+impl DleqProof {
+    pub fn prove(secret: &PrivateKey) -> Result<Self, XmrSwapError> {
+        let x = to_scalar(secret)?;
+        let r = to_scalar(&PrivateKey::random(&mut OsRng))?;
+        let ristretto_nonce = (r * RISTRETTO_BASEPOINT_POINT).compress().to_bytes();
+        let ed25519_nonce = (r * ED25519_BASEPOINT_POINT).compress().to_bytes();
+        let c = challenge(
+            &(x * RISTRETTO_BASEPOINT_POINT).compress().to_bytes(),
+            &(x * ED25519_BASEPOINT_POINT).compress().to_bytes(),
+            &ristretto_nonce,
+            &ed25519_nonce,
+        );
+        Ok(Self {
+            ristretto_nonce,
+            ed25519_nonce,
+            response: (r + c * x).to_bytes(),
+        })
+    }
+
+    pub fn verify(&self, public_key: &PublicKey, monero_public_key: &MoneroPublicKey) -> bool {
+        self.check(public_key, monero_public_key).unwrap_or(false)
+    }
+
+    fn check(&self, public_key: &PublicKey, monero_public_key: &MoneroPublicKey) -> Option<bool> {
+        let public_key_bytes: [u8; 32] = public_key.as_bytes().try_into().ok()?;
+        let p = CompressedRistretto(public_key_bytes).decompress()?;
+        let p_ed = monero_public_key.point().ok()?;
+        let r = CompressedRistretto(self.ristretto_nonce).decompress()?;
+        let r_ed = CompressedEdwardsY(self.ed25519_nonce).decompress()?;
+        let z = Option::<Scalar>::from(Scalar::from_canonical_bytes(self.response))?;
+        let c = challenge(
+            &public_key_bytes,
+            monero_public_key.as_bytes(),
+            &self.ristretto_nonce,
+            &self.ed25519_nonce,
+        );
+        let ristretto_ok: bool = z * RISTRETTO_BASEPOINT_POINT == r + c * p;
+        let ed25519_ok: bool = z * ED25519_BASEPOINT_POINT == r_ed + c * p_ed;
+        Some(ristretto_ok && ed25519_ok)
+    }
+}
+
+fn challenge(public_key: &[u8; 32], monero_public_key: &[u8; 32], nonce: &[u8; 32], monero_nonce: &[u8; 32]) -> Scalar {
+    let hash = DomainSeparatedHasher::<Blake2b<U64>, XmrSwapHashDomain>::new_with_label("dleq")
+        .chain(public_key)
+        .chain(monero_public_key)
+        .chain(nonce)
+        .chain(monero_nonce)
+        .finalize();
+    let mut bytes = [0u8; 64];
+    bytes.copy_from_slice(hash.as_ref());
+    Scalar::from_bytes_mod_order_wide(&bytes)
+}
+
+fn to_scalar(key: &PrivateKey) -> Result<Scalar, XmrSwapError> {
+    let bytes: [u8; 32] = key
+        .as_bytes()
+        .try_into()
+        .map_err(|_| XmrSwapError::InvalidKey("Secret key is not 32 bytes".to_string()))?;
+    Option::<Scalar>::from(Scalar::from_canonical_bytes(bytes))
+        .ok_or_else(|| XmrSwapError::InvalidKey("Secret key is not a canonical scalar".to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use tari_crypto::keys::PublicKey as PublicKeyTrait;
+
+    use super::*;
+
+    #[test]
+    fn it_proves_the_same_secret_key_on_both_curves() {
+        let (secret, public_key) = PublicKey::random_keypair(&mut OsRng);
+        let monero_public_key = MoneroPublicKey::from_secret_key(&secret).unwrap();
+        let proof = DleqProof::prove(&secret).unwrap();
+        assert!(proof.verify(&public_key, &monero_public_key));
+
+        let other_secret = PrivateKey::random(&mut OsRng);
+        let other_monero_public_key = MoneroPublicKey::from_secret_key(&other_secret).unwrap();
+        assert!(!proof.verify(&public_key, &other_monero_public_key));
+        assert!(!proof.verify(&PublicKey::from_secret_key(&other_secret), &monero_public_key));
+        assert!(!DleqProof::prove(&other_secret)
+            .unwrap()
+            .verify(&public_key, &monero_public_key));
+    }
+
+    #[test]
+    fn it_combines_monero_keys() {
+        let a = PrivateKey::random(&mut OsRng);
+        let b = PrivateKey::random(&mut OsRng);
+        let combined = MoneroPublicKey::from_secret_key(&a)
+            .unwrap()
+            .combine(&MoneroPublicKey::from_secret_key(&b).unwrap())
+            .unwrap();
+        assert_eq!(combined, MoneroPublicKey::from_secret_key(&(&a + &b)).unwrap());
+        assert_eq!(MoneroPublicKey::from_bytes(*combined.as_bytes()).unwrap(), combined);
+        // A point of order two is rejected
+        let mut small_order = [0xff; 32];
+        small_order[0] = 0xec;
+        small_order[31] = 0x7f;
+        assert!(MoneroPublicKey::from_bytes(small_order).is_err());
+    }
+}
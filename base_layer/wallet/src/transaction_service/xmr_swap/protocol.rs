@@ -0,0 +1,887 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! The swap state machine.
+//!
+//! The Tari lock output commits to the mask `x_s + x_b`, made up of a share from each party, so every spend of it
+//! needs a two-party kernel signature. The party receiving the Tari on a given path signs with the excess
+//! `k_out - x_i`, where `k_out` is the mask of its new output, and the other party signs with `-x_i`.
+//!
+//! Message flow:
+//! 1. Both parties exchange an [`XmrSwapSetup`].
+//! 2. The buyer sends [`BuyerSignatures`]: its claim partial signature and its refund partial signature as an adaptor
+//!    signature encrypted under the seller's key share. The seller sends [`SellerSignatures`]: its refund and punish
+//!    partial signatures.
+//! 3. The seller locks the Tari in an output with the [`XmrSwap::lock_script`], then the buyer locks the Monero to
+//!    [`XmrSwap::monero_lock_key`].
+//! 4. The seller sends its claim partial signature as an adaptor signature encrypted under the buyer's key share.
+//! 5. The buyer claims the Tari with [`XmrSwap::claim_kernel`], and the seller recovers the Monero spend key from the
+//!    published kernel signature with [`XmrSwap::recover_monero_key_from_claim`].
+//!
+//! If the buyer does not claim before `refund_height`, the seller refunds with [`XmrSwap::refund_kernel`] and the
+//! buyer recovers the Monero spend key with [`XmrSwap::recover_monero_key_from_refund`]. If the seller has not
+//! refunded by `punish_height`, the buyer takes the Tari with [`XmrSwap::punish_kernel`].
+
+use std::{fmt, fmt::Formatter};
+
+use chrono::{NaiveDateTime, Utc};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::{Commitment, PrivateKey, PublicKey, Signature};
+use tari_core::transactions::{
+    tari_amount::MicroMinotari,
+    transaction_components::{KernelFeatures, TransactionKernel, TransactionKernelVersion},
+};
+use tari_crypto::keys::{PublicKey as PublicKeyTrait, SecretKey};
+use tari_script::{script, TariScript};
+
+use crate::transaction_service::xmr_swap::{
+    adaptor::AdaptorSignature,
+    monero::{DleqProof, MoneroPublicKey},
+    XmrSwapError,
+    XmrSwapRole,
+    XmrSwapState,
+};
+
+/// The terms of a swap, agreed by both parties before it starts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct XmrSwapParameters {
+    pub tari_amount: MicroMinotari,
+    /// The Monero amount in piconero
+    pub xmr_amount: u64,
+    /// The fee of each transaction that spends the lock output
+    pub fee: MicroMinotari,
+    /// The height from which the seller can refund the Tari, and until which the buyer can claim it
+    pub refund_height: u64,
+    /// The height from which the buyer can take the Tari if the seller has not refunded it
+    pub punish_height: u64,
+}
+
+impl XmrSwapParameters {
+    pub fn validate(&self) -> Result<(), XmrSwapError> {
+        if self.tari_amount <= self.fee {
+            return Err(XmrSwapError::InvalidParameters(
+                "The Tari amount must be more than the fee".to_string(),
+            ));
+        }
+        if self.xmr_amount == 0 {
+            return Err(XmrSwapError::InvalidParameters(
+                "The Monero amount must be more than zero".to_string(),
+            ));
+        }
+        if self.punish_height <= self.refund_height {
+            return Err(XmrSwapError::InvalidParameters(
+                "The punish height must be after the refund height".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The ways the Tari lock output can be spent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpendPath {
+    /// The buyer takes the Tari, revealing its Monero key share
+    Claim,
+    /// The seller takes the Tari back, revealing its Monero key share
+    Refund,
+    /// The buyer takes the Tari after the seller failed to refund
+    Punish,
+}
+
+impl SpendPath {
+    fn receiver(self) -> XmrSwapRole {
+        match self {
+            SpendPath::Claim | SpendPath::Punish => XmrSwapRole::TariBuyer,
+            SpendPath::Refund => XmrSwapRole::TariSeller,
+        }
+    }
+}
+
+/// The public keys a party contributes to the swap
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartyPublicKeys {
+    /// The party's Monero key share on Ristretto
+    pub spend_public_key: PublicKey,
+    /// The party's share of the lock output mask
+    pub lock_public_key: PublicKey,
+    /// The party's kernel excess on the paths that pay it, `k_out - x_i`
+    pub receive_excess: PublicKey,
+    /// The key the lock script requires for the paths that pay the party
+    pub script_public_key: PublicKey,
+    pub claim_nonce: PublicKey,
+    pub refund_nonce: PublicKey,
+    pub punish_nonce: PublicKey,
+}
+
+impl PartyPublicKeys {
+    fn nonce(&self, path: SpendPath) -> &PublicKey {
+        match path {
+            SpendPath::Claim => &self.claim_nonce,
+            SpendPath::Refund => &self.refund_nonce,
+            SpendPath::Punish => &self.punish_nonce,
+        }
+    }
+
+    fn excess(&self, party: XmrSwapRole, path: SpendPath) -> PublicKey {
+        if path.receiver() == party {
+            self.receive_excess.clone()
+        } else {
+            &PublicKey::default() - &self.lock_public_key
+        }
+    }
+}
+
+/// The first message of the swap, sent by both parties
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct XmrSwapSetup {
+    pub keys: PartyPublicKeys,
+    pub monero_spend_public_key: MoneroPublicKey,
+    /// Proves that `monero_spend_public_key` and `keys.spend_public_key` have the same secret key
+    pub dleq_proof: DleqProof,
+}
+
+/// The buyer's signatures, sent once the keys are exchanged
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuyerSignatures {
+    pub claim_partial: Signature,
+    /// Encrypted under the seller's Monero key share
+    pub refund_adaptor: AdaptorSignature,
+}
+
+/// The seller's signatures, sent once the keys are exchanged
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SellerSignatures {
+    pub refund_partial: Signature,
+    pub punish_partial: Signature,
+}
+
+#[derive(Clone)]
+struct SwapSecrets {
+    spend_key: PrivateKey,
+    lock_key: PrivateKey,
+    receive_key: PrivateKey,
+    script_key: PrivateKey,
+    claim_nonce: PrivateKey,
+    refund_nonce: PrivateKey,
+    punish_nonce: PrivateKey,
+}
+
+impl SwapSecrets {
+    fn random() -> Self {
+        Self {
+            spend_key: PrivateKey::random(&mut OsRng),
+            lock_key: PrivateKey::random(&mut OsRng),
+            receive_key: PrivateKey::random(&mut OsRng),
+            script_key: PrivateKey::random(&mut OsRng),
+            claim_nonce: PrivateKey::random(&mut OsRng),
+            refund_nonce: PrivateKey::random(&mut OsRng),
+            punish_nonce: PrivateKey::random(&mut OsRng),
+        }
+    }
+
+    fn public_keys(&self) -> PartyPublicKeys {
+        PartyPublicKeys {
+            spend_public_key: PublicKey::from_secret_key(&self.spend_key),
+            lock_public_key: PublicKey::from_secret_key(&self.lock_key),
+            receive_excess: PublicKey::from_secret_key(&(&self.receive_key - &self.lock_key)),
+            script_public_key: PublicKey::from_secret_key(&self.script_key),
+            claim_nonce: PublicKey::from_secret_key(&self.claim_nonce),
+            refund_nonce: PublicKey::from_secret_key(&self.refund_nonce),
+            punish_nonce: PublicKey::from_secret_key(&self.punish_nonce),
+        }
+    }
+
+    fn nonce(&self, path: SpendPath) -> PrivateKey {
+        match path {
+            SpendPath::Claim => self.claim_nonce.clone(),
+            SpendPath::Refund => self.refund_nonce.clone(),
+            SpendPath::Punish => self.punish_nonce.clone(),
+        }
+    }
+
+    fn excess(&self, party: XmrSwapRole, path: SpendPath) -> PrivateKey {
+        if path.receiver() == party {
+            &self.receive_key - &self.lock_key
+        } else {
+            &PrivateKey::default() - &self.lock_key
+        }
+    }
+}
+
+impl fmt::Debug for SwapSecrets {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "SwapSecrets {{ .. }}")
+    }
+}
+
+/// The partial signatures of both parties. Each party keeps its own as well as the counterparty's, since its own
+/// adaptor signatures are needed to recover the counterparty's key share.
+#[derive(Debug, Clone, Default)]
+struct SwapSignatures {
+    buyer_claim: Option<Signature>,
+    buyer_refund_adaptor: Option<AdaptorSignature>,
+    buyer_punish: Option<Signature>,
+    seller_refund: Option<Signature>,
+    seller_punish: Option<Signature>,
+    seller_claim_adaptor: Option<AdaptorSignature>,
+}
+
+/// One side of a Tari-Monero swap
+#[derive(Debug, Clone)]
+pub struct XmrSwap {
+    pub role: XmrSwapRole,
+    pub state: XmrSwapState,
+    pub parameters: XmrSwapParameters,
+    secrets: SwapSecrets,
+    counterparty: Option<XmrSwapSetup>,
+    signatures: SwapSignatures,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl XmrSwap {
+    pub fn new(role: XmrSwapRole, parameters: XmrSwapParameters) -> Result<Self, XmrSwapError> {
+        parameters.validate()?;
+        let now = Utc::now().naive_utc();
+        Ok(Self {
+            role,
+            state: XmrSwapState::Created,
+            parameters,
+            secrets: SwapSecrets::random(),
+            counterparty: None,
+            signatures: SwapSignatures::default(),
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// The setup message for the counterparty
+    pub fn setup(&self) -> Result<XmrSwapSetup, XmrSwapError> {
+        Ok(XmrSwapSetup {
+            keys: self.secrets.public_keys(),
+            monero_spend_public_key: MoneroPublicKey::from_secret_key(&self.secrets.spend_key)?,
+            dleq_proof: DleqProof::prove(&self.secrets.spend_key)?,
+        })
+    }
+
+    pub fn accept_setup(&mut self, setup: XmrSwapSetup) -> Result<(), XmrSwapError> {
+        self.expect_state("accept the counterparty's keys", &[XmrSwapState::Created])?;
+        if !setup
+            .dleq_proof
+            .verify(&setup.keys.spend_public_key, &setup.monero_spend_public_key)
+        {
+            return Err(XmrSwapError::InvalidCounterpartyData("key proof"));
+        }
+        let own = self.secrets.public_keys();
+        if setup.keys.spend_public_key == own.spend_public_key || setup.keys.lock_public_key == own.lock_public_key {
+            return Err(XmrSwapError::InvalidCounterpartyData("keys"));
+        }
+        self.counterparty = Some(setup);
+        self.set_state(XmrSwapState::KeysExchanged);
+        Ok(())
+    }
+
+    /// The buyer's signatures for the seller
+    pub fn buyer_signatures(&mut self) -> Result<BuyerSignatures, XmrSwapError> {
+        self.expect_role(XmrSwapRole::TariBuyer)?;
+        self.expect_state("sign", &[XmrSwapState::KeysExchanged, XmrSwapState::ReadyToLock])?;
+        if self.signatures.buyer_claim.is_none() {
+            let claim_partial = self.sign_partial(SpendPath::Claim)?;
+            let refund_adaptor = AdaptorSignature::sign(
+                &self.secrets.excess(self.role, SpendPath::Refund),
+                self.secrets.nonce(SpendPath::Refund),
+                &self.challenge(SpendPath::Refund)?,
+            )?;
+            self.signatures.buyer_claim = Some(claim_partial);
+            self.signatures.buyer_refund_adaptor = Some(refund_adaptor);
+            self.update_ready();
+        }
+        Ok(BuyerSignatures {
+            claim_partial: self.signatures.buyer_claim.clone().expect("Set above"),
+            refund_adaptor: self.signatures.buyer_refund_adaptor.clone().expect("Set above"),
+        })
+    }
+
+    /// The seller's signatures for the buyer
+    pub fn seller_signatures(&mut self) -> Result<SellerSignatures, XmrSwapError> {
+        self.expect_role(XmrSwapRole::TariSeller)?;
+        self.expect_state("sign", &[XmrSwapState::KeysExchanged, XmrSwapState::ReadyToLock])?;
+        if self.signatures.seller_refund.is_none() {
+            let refund_partial = self.sign_partial(SpendPath::Refund)?;
+            let punish_partial = self.sign_partial(SpendPath::Punish)?;
+            self.signatures.seller_refund = Some(refund_partial);
+            self.signatures.seller_punish = Some(punish_partial);
+            self.update_ready();
+        }
+        Ok(SellerSignatures {
+            refund_partial: self.signatures.seller_refund.clone().expect("Set above"),
+            punish_partial: self.signatures.seller_punish.clone().expect("Set above"),
+        })
+    }
+
+    pub fn accept_buyer_signatures(&mut self, signatures: BuyerSignatures) -> Result<(), XmrSwapError> {
+        self.expect_role(XmrSwapRole::TariSeller)?;
+        self.expect_state("accept signatures", &[XmrSwapState::KeysExchanged])?;
+        let (_, buyer) = self.parties()?;
+        self.verify_partial(
+            &signatures.claim_partial,
+            &buyer,
+            XmrSwapRole::TariBuyer,
+            SpendPath::Claim,
+        )?;
+        let refund_excess = buyer.excess(XmrSwapRole::TariBuyer, SpendPath::Refund);
+        if signatures.refund_adaptor.public_nonce() != buyer.nonce(SpendPath::Refund) ||
+            !signatures
+                .refund_adaptor
+                .verify(&refund_excess, &self.challenge(SpendPath::Refund)?)
+        {
+            return Err(XmrSwapError::InvalidCounterpartyData("refund adaptor signature"));
+        }
+        self.signatures.buyer_claim = Some(signatures.claim_partial);
+        self.signatures.buyer_refund_adaptor = Some(signatures.refund_adaptor);
+        self.update_ready();
+        Ok(())
+    }
+
+    pub fn accept_seller_signatures(&mut self, signatures: SellerSignatures) -> Result<(), XmrSwapError> {
+        self.expect_role(XmrSwapRole::TariBuyer)?;
+        self.expect_state("accept signatures", &[XmrSwapState::KeysExchanged])?;
+        let (seller, _) = self.parties()?;
+        self.verify_partial(
+            &signatures.refund_partial,
+            &seller,
+            XmrSwapRole::TariSeller,
+            SpendPath::Refund,
+        )?;
+        self.verify_partial(
+            &signatures.punish_partial,
+            &seller,
+            XmrSwapRole::TariSeller,
+            SpendPath::Punish,
+        )?;
+        self.signatures.seller_refund = Some(signatures.refund_partial);
+        self.signatures.seller_punish = Some(signatures.punish_partial);
+        self.update_ready();
+        Ok(())
+    }
+
+    /// The script of the Tari lock output. Before `refund_height` it requires the buyer's key, then the seller's key
+    /// until `punish_height`, and the buyer's key after that.
+    pub fn lock_script(&self) -> Result<TariScript, XmrSwapError> {
+        let (seller, buyer) = self.parties()?;
+        Ok(script!(
+            CheckHeight(self.parameters.refund_height) LtZero IfThen
+                PushPubKey(Box::new(buyer.script_public_key.clone()))
+            Else
+                CheckHeight(self.parameters.punish_height) LtZero IfThen
+                    PushPubKey(Box::new(seller.script_public_key))
+                Else
+                    PushPubKey(Box::new(buyer.script_public_key))
+                EndIf
+            EndIf
+        ))
+    }
+
+    /// The secret key this party uses to satisfy the lock script
+    pub fn script_key(&self) -> &PrivateKey {
+        &self.secrets.script_key
+    }
+
+    /// The mask of the output this party receives the Tari in, should it spend the lock output
+    pub fn receive_key(&self) -> &PrivateKey {
+        &self.secrets.receive_key
+    }
+
+    /// The Monero spend key the buyer locks the Monero to
+    pub fn monero_lock_key(&self) -> Result<MoneroPublicKey, XmrSwapError> {
+        let counterparty = self.counterparty()?;
+        MoneroPublicKey::from_secret_key(&self.secrets.spend_key)?.combine(&counterparty.monero_spend_public_key)
+    }
+
+    /// Records that the Tari lock transaction was mined
+    pub fn tari_locked(&mut self) -> Result<(), XmrSwapError> {
+        self.expect_state("lock the Tari", &[XmrSwapState::ReadyToLock])?;
+        self.set_state(XmrSwapState::TariLocked);
+        Ok(())
+    }
+
+    /// Records that the Monero lock transaction was confirmed
+    pub fn monero_locked(&mut self) -> Result<(), XmrSwapError> {
+        self.expect_state("lock the Monero", &[XmrSwapState::TariLocked])?;
+        self.set_state(XmrSwapState::MoneroLocked);
+        Ok(())
+    }
+
+    /// The seller's claim adaptor signature for the buyer, to be sent once the Monero is locked
+    pub fn authorize_claim(&mut self) -> Result<AdaptorSignature, XmrSwapError> {
+        self.expect_role(XmrSwapRole::TariSeller)?;
+        self.expect_state("authorize the claim", &[XmrSwapState::MoneroLocked])?;
+        let adaptor = AdaptorSignature::sign(
+            &self.secrets.excess(self.role, SpendPath::Claim),
+            self.secrets.nonce(SpendPath::Claim),
+            &self.challenge(SpendPath::Claim)?,
+        )?;
+        self.signatures.seller_claim_adaptor = Some(adaptor.clone());
+        self.set_state(XmrSwapState::ClaimAuthorized);
+        Ok(adaptor)
+    }
+
+    pub fn accept_claim_authorization(&mut self, adaptor: AdaptorSignature) -> Result<(), XmrSwapError> {
+        self.expect_role(XmrSwapRole::TariBuyer)?;
+        self.expect_state("accept the claim authorization", &[XmrSwapState::MoneroLocked])?;
+        let (seller, _) = self.parties()?;
+        let excess = seller.excess(XmrSwapRole::TariSeller, SpendPath::Claim);
+        if adaptor.public_nonce() != seller.nonce(SpendPath::Claim) ||
+            !adaptor.verify(&excess, &self.challenge(SpendPath::Claim)?)
+        {
+            return Err(XmrSwapError::InvalidCounterpartyData("claim adaptor signature"));
+        }
+        self.signatures.seller_claim_adaptor = Some(adaptor);
+        self.set_state(XmrSwapState::ClaimAuthorized);
+        Ok(())
+    }
+
+    /// The buyer's kernel for the transaction that claims the Tari. Publishing it reveals the buyer's Monero key share.
+    pub fn claim_kernel(&mut self) -> Result<TransactionKernel, XmrSwapError> {
+        self.expect_role(XmrSwapRole::TariBuyer)?;
+        self.expect_state("claim the Tari", &[XmrSwapState::ClaimAuthorized])?;
+        let own = self.signature(&self.signatures.buyer_claim, "claim")?;
+        let seller = self
+            .adaptor(&self.signatures.seller_claim_adaptor, "claim")?
+            .adapt(&self.secrets.spend_key);
+        let kernel = self.kernel(SpendPath::Claim, &own + &seller)?;
+        self.set_state(XmrSwapState::TariClaimed);
+        Ok(kernel)
+    }
+
+    /// The seller's kernel for the transaction that refunds the Tari. Publishing it reveals the seller's Monero key
+    /// share.
+    pub fn refund_kernel(&mut self) -> Result<TransactionKernel, XmrSwapError> {
+        self.expect_role(XmrSwapRole::TariSeller)?;
+        self.expect_locked("refund the Tari")?;
+        let own = self.signature(&self.signatures.seller_refund, "refund")?;
+        let buyer = self
+            .adaptor(&self.signatures.buyer_refund_adaptor, "refund")?
+            .adapt(&self.secrets.spend_key);
+        let kernel = self.kernel(SpendPath::Refund, &own + &buyer)?;
+        self.set_state(XmrSwapState::TariRefunded);
+        Ok(kernel)
+    }
+
+    /// The buyer's kernel for the transaction that takes the Tari once the seller failed to refund it
+    pub fn punish_kernel(&mut self) -> Result<TransactionKernel, XmrSwapError> {
+        self.expect_role(XmrSwapRole::TariBuyer)?;
+        self.expect_locked("punish the seller")?;
+        if self.signatures.buyer_punish.is_none() {
+            self.signatures.buyer_punish = Some(self.sign_partial(SpendPath::Punish)?);
+        }
+        let own = self.signature(&self.signatures.buyer_punish, "punish")?;
+        let seller = self.signature(&self.signatures.seller_punish, "punish")?;
+        let kernel = self.kernel(SpendPath::Punish, &own + &seller)?;
+        self.set_state(XmrSwapState::TariPunished);
+        Ok(kernel)
+    }
+
+    /// Recovers the buyer's key share from the published claim kernel signature and returns the Monero spend key
+    pub fn recover_monero_key_from_claim(&mut self, kernel_signature: &Signature) -> Result<PrivateKey, XmrSwapError> {
+        self.expect_role(XmrSwapRole::TariSeller)?;
+        self.expect_state("recover the Monero key", &[XmrSwapState::ClaimAuthorized])?;
+        let buyer_partial = self.signature(&self.signatures.buyer_claim, "claim")?;
+        let own_adaptor = self.adaptor(&self.signatures.seller_claim_adaptor, "claim")?;
+        let spend_key = self.recover_monero_key(kernel_signature, &buyer_partial, &own_adaptor)?;
+        self.set_state(XmrSwapState::MoneroClaimed);
+        Ok(spend_key)
+    }
+
+    /// Recovers the seller's key share from the published refund kernel signature and returns the Monero spend key
+    pub fn recover_monero_key_from_refund(&mut self, kernel_signature: &Signature) -> Result<PrivateKey, XmrSwapError> {
+        self.expect_role(XmrSwapRole::TariBuyer)?;
+        self.expect_locked("recover the Monero key")?;
+        let seller_partial = self.signature(&self.signatures.seller_refund, "refund")?;
+        let own_adaptor = self.adaptor(&self.signatures.buyer_refund_adaptor, "refund")?;
+        let spend_key = self.recover_monero_key(kernel_signature, &seller_partial, &own_adaptor)?;
+        self.set_state(XmrSwapState::MoneroRefunded);
+        Ok(spend_key)
+    }
+
+    /// Gives up on a swap before any funds were locked
+    pub fn abort(&mut self) -> Result<(), XmrSwapError> {
+        self.expect_state("abort", &[
+            XmrSwapState::Created,
+            XmrSwapState::KeysExchanged,
+            XmrSwapState::ReadyToLock,
+        ])?;
+        self.set_state(XmrSwapState::Aborted);
+        Ok(())
+    }
+
+    /// Whether this side of the swap has run its course
+    pub fn is_finished(&self) -> bool {
+        match self.role {
+            XmrSwapRole::TariSeller => matches!(
+                self.state,
+                XmrSwapState::MoneroClaimed | XmrSwapState::TariRefunded | XmrSwapState::Aborted
+            ),
+            XmrSwapRole::TariBuyer => matches!(
+                self.state,
+                XmrSwapState::TariClaimed |
+                    XmrSwapState::MoneroRefunded |
+                    XmrSwapState::TariPunished |
+                    XmrSwapState::Aborted
+            ),
+        }
+    }
+
+    fn recover_monero_key(
+        &self,
+        kernel_signature: &Signature,
+        counterparty_partial: &Signature,
+        own_adaptor: &AdaptorSignature,
+    ) -> Result<PrivateKey, XmrSwapError> {
+        let own_part = Signature::new(
+            kernel_signature.get_public_nonce() - counterparty_partial.get_public_nonce(),
+            kernel_signature.get_signature() - counterparty_partial.get_signature(),
+        );
+        let counterparty_share = own_adaptor.extract(&own_part);
+        if PublicKey::from_secret_key(&counterparty_share) != self.counterparty()?.keys.spend_public_key {
+            return Err(XmrSwapError::InvalidCounterpartyData("kernel signature"));
+        }
+        Ok(&self.secrets.spend_key + &counterparty_share)
+    }
+
+    fn sign_partial(&self, path: SpendPath) -> Result<Signature, XmrSwapError> {
+        Ok(Signature::sign_raw(
+            &self.secrets.excess(self.role, path),
+            self.secrets.nonce(path),
+            &self.challenge(path)?,
+        )?)
+    }
+
+    fn verify_partial(
+        &self,
+        signature: &Signature,
+        keys: &PartyPublicKeys,
+        party: XmrSwapRole,
+        path: SpendPath,
+    ) -> Result<(), XmrSwapError> {
+        if signature.get_public_nonce() != keys.nonce(path) ||
+            !signature.verify_challenge(&keys.excess(party, path), &self.challenge(path)?)
+        {
+            return Err(XmrSwapError::InvalidCounterpartyData("partial signature"));
+        }
+        Ok(())
+    }
+
+    /// The kernel signature challenge of a spend path. The total nonce includes the adaptor point of the party whose
+    /// key share the path reveals.
+    fn challenge(&self, path: SpendPath) -> Result<[u8; 32], XmrSwapError> {
+        let (seller, buyer) = self.parties()?;
+        let nonce = seller.nonce(path) + buyer.nonce(path);
+        let nonce = match path {
+            SpendPath::Claim => &nonce + &buyer.spend_public_key,
+            SpendPath::Refund => &nonce + &seller.spend_public_key,
+            SpendPath::Punish => nonce,
+        };
+        Ok(TransactionKernel::build_kernel_signature_challenge(
+            &TransactionKernelVersion::get_current_version(),
+            &nonce,
+            &self.total_excess(path)?,
+            self.parameters.fee,
+            self.lock_height(path),
+            &KernelFeatures::empty(),
+            &None,
+        ))
+    }
+
+    fn total_excess(&self, path: SpendPath) -> Result<PublicKey, XmrSwapError> {
+        let (seller, buyer) = self.parties()?;
+        Ok(&seller.excess(XmrSwapRole::TariSeller, path) + &buyer.excess(XmrSwapRole::TariBuyer, path))
+    }
+
+    fn lock_height(&self, path: SpendPath) -> u64 {
+        match path {
+            SpendPath::Claim => 0,
+            SpendPath::Refund => self.parameters.refund_height,
+            SpendPath::Punish => self.parameters.punish_height,
+        }
+    }
+
+    fn kernel(&self, path: SpendPath, signature: Signature) -> Result<TransactionKernel, XmrSwapError> {
+        let kernel = TransactionKernel::new_current_version(
+            KernelFeatures::empty(),
+            self.parameters.fee,
+            self.lock_height(path),
+            Commitment::from_public_key(&self.total_excess(path)?),
+            signature,
+            None,
+        );
+        kernel
+            .verify_signature()
+            .map_err(|_| XmrSwapError::InvalidCounterpartyData("partial signature"))?;
+        Ok(kernel)
+    }
+
+    /// The public keys of the seller and the buyer
+    fn parties(&self) -> Result<(PartyPublicKeys, PartyPublicKeys), XmrSwapError> {
+        let own = self.secrets.public_keys();
+        let counterparty = self.counterparty()?.keys.clone();
+        Ok(match self.role {
+            XmrSwapRole::TariSeller => (own, counterparty),
+            XmrSwapRole::TariBuyer => (counterparty, own),
+        })
+    }
+
+    fn counterparty(&self) -> Result<&XmrSwapSetup, XmrSwapError> {
+        self.counterparty.as_ref().ok_or(XmrSwapError::InvalidState {
+            action: "continue without the counterparty's keys",
+            state: self.state,
+        })
+    }
+
+    fn signature(&self, signature: &Option<Signature>, path: &'static str) -> Result<Signature, XmrSwapError> {
+        signature.clone().ok_or(XmrSwapError::MissingSignature(path))
+    }
+
+    fn adaptor(
+        &self,
+        adaptor: &Option<AdaptorSignature>,
+        path: &'static str,
+    ) -> Result<AdaptorSignature, XmrSwapError> {
+        adaptor.clone().ok_or(XmrSwapError::MissingSignature(path))
+    }
+
+    fn update_ready(&mut self) {
+        let signatures = &self.signatures;
+        if signatures.buyer_claim.is_some() &&
+            signatures.buyer_refund_adaptor.is_some() &&
+            signatures.seller_refund.is_some() &&
+            signatures.seller_punish.is_some()
+        {
+            self.set_state(XmrSwapState::ReadyToLock);
+        }
+    }
+
+    fn expect_role(&self, role: XmrSwapRole) -> Result<(), XmrSwapError> {
+        if self.role == role {
+            Ok(())
+        } else {
+            Err(XmrSwapError::WrongRole(role))
+        }
+    }
+
+    fn expect_state(&self, action: &'static str, states: &[XmrSwapState]) -> Result<(), XmrSwapError> {
+        if states.contains(&self.state) {
+            Ok(())
+        } else {
+            Err(XmrSwapError::InvalidState {
+                action,
+                state: self.state,
+            })
+        }
+    }
+
+    fn expect_locked(&self, action: &'static str) -> Result<(), XmrSwapError> {
+        self.expect_state(action, &[
+            XmrSwapState::TariLocked,
+            XmrSwapState::MoneroLocked,
+            XmrSwapState::ClaimAuthorized,
+        ])
+    }
+
+    fn set_state(&mut self, state: XmrSwapState) {
+        self.state = state;
+        self.updated_at = Utc::now().naive_utc();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tari_script::{ExecutionStack, ScriptContext, StackItem};
+
+    use super::*;
+    use crate::transaction_service::xmr_swap::mock_monero::MockMoneroChain;
+
+    fn parameters() -> XmrSwapParameters {
+        XmrSwapParameters {
+            tari_amount: MicroMinotari::from(10_000_000),
+            xmr_amount: 1_000_000_000_000,
+            fee: MicroMinotari::from(1_000),
+            refund_height: 100,
+            punish_height: 200,
+        }
+    }
+
+    /// Runs the swap up to the point where the Tari is locked
+    fn ready_swaps() -> (XmrSwap, XmrSwap) {
+        let mut seller = XmrSwap::new(XmrSwapRole::TariSeller, parameters()).unwrap();
+        let mut buyer = XmrSwap::new(XmrSwapRole::TariBuyer, parameters()).unwrap();
+        seller.accept_setup(buyer.setup().unwrap()).unwrap();
+        buyer.accept_setup(seller.setup().unwrap()).unwrap();
+        assert_eq!(seller.monero_lock_key().unwrap(), buyer.monero_lock_key().unwrap());
+        assert_eq!(seller.lock_script().unwrap(), buyer.lock_script().unwrap());
+
+        seller
+            .accept_buyer_signatures(buyer.buyer_signatures().unwrap())
+            .unwrap();
+        buyer
+            .accept_seller_signatures(seller.seller_signatures().unwrap())
+            .unwrap();
+        assert_eq!(seller.state, XmrSwapState::ReadyToLock);
+        assert_eq!(buyer.state, XmrSwapState::ReadyToLock);
+
+        seller.tari_locked().unwrap();
+        buyer.tari_locked().unwrap();
+        (seller, buyer)
+    }
+
+    #[test]
+    fn it_validates_parameters() {
+        assert!(XmrSwap::new(XmrSwapRole::TariSeller, XmrSwapParameters {
+            punish_height: 100,
+            ..parameters()
+        })
+        .is_err());
+        assert!(XmrSwap::new(XmrSwapRole::TariSeller, XmrSwapParameters {
+            fee: MicroMinotari::from(10_000_000),
+            ..parameters()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn it_rejects_an_invalid_key_proof() {
+        let mut seller = XmrSwap::new(XmrSwapRole::TariSeller, parameters()).unwrap();
+        let buyer = XmrSwap::new(XmrSwapRole::TariBuyer, parameters()).unwrap();
+        let mut setup = buyer.setup().unwrap();
+        setup.monero_spend_public_key = MoneroPublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)).unwrap();
+        assert!(matches!(
+            seller.accept_setup(setup),
+            Err(XmrSwapError::InvalidCounterpartyData(_))
+        ));
+        assert_eq!(seller.state, XmrSwapState::Created);
+    }
+
+    #[test]
+    fn it_rejects_tampered_signatures() {
+        let mut seller = XmrSwap::new(XmrSwapRole::TariSeller, parameters()).unwrap();
+        let mut buyer = XmrSwap::new(XmrSwapRole::TariBuyer, parameters()).unwrap();
+        seller.accept_setup(buyer.setup().unwrap()).unwrap();
+        buyer.accept_setup(seller.setup().unwrap()).unwrap();
+
+        let mut signatures = buyer.buyer_signatures().unwrap();
+        signatures.claim_partial = Signature::new(
+            signatures.claim_partial.get_public_nonce().clone(),
+            PrivateKey::random(&mut OsRng),
+        );
+        assert!(seller.accept_buyer_signatures(signatures).is_err());
+
+        let mut signatures = seller.seller_signatures().unwrap();
+        std::mem::swap(&mut signatures.refund_partial, &mut signatures.punish_partial);
+        assert!(buyer.accept_seller_signatures(signatures).is_err());
+        assert_eq!(buyer.state, XmrSwapState::KeysExchanged);
+    }
+
+    #[test]
+    fn it_swaps_when_the_buyer_claims() {
+        let (mut seller, mut buyer) = ready_swaps();
+        let mut monero = MockMoneroChain::new();
+        monero.lock(buyer.monero_lock_key().unwrap(), parameters().xmr_amount);
+        monero.mine(10);
+        assert_eq!(
+            monero.locked_amount(&seller.monero_lock_key().unwrap(), 10),
+            parameters().xmr_amount
+        );
+        seller.monero_locked().unwrap();
+        buyer.monero_locked().unwrap();
+
+        // The buyer cannot claim before the seller authorizes it
+        assert!(buyer.claim_kernel().is_err());
+        buyer
+            .accept_claim_authorization(seller.authorize_claim().unwrap())
+            .unwrap();
+        let kernel = buyer.claim_kernel().unwrap();
+        assert_eq!(kernel.lock_height, 0);
+        assert!(kernel.verify_signature().is_ok());
+        assert!(buyer.is_finished());
+
+        let spend_key = seller.recover_monero_key_from_claim(&kernel.excess_sig).unwrap();
+        assert_eq!(monero.sweep(&spend_key).unwrap(), parameters().xmr_amount);
+        assert!(seller.is_finished());
+    }
+
+    #[test]
+    fn it_refunds_both_sides() {
+        let (mut seller, mut buyer) = ready_swaps();
+        let mut monero = MockMoneroChain::new();
+        monero.lock(buyer.monero_lock_key().unwrap(), parameters().xmr_amount);
+        monero.mine(1);
+        seller.monero_locked().unwrap();
+        buyer.monero_locked().unwrap();
+
+        // The seller never authorizes the claim and refunds once the claim window has passed
+        let kernel = seller.refund_kernel().unwrap();
+        assert_eq!(kernel.lock_height, parameters().refund_height);
+        assert!(kernel.verify_signature().is_ok());
+        assert!(seller.is_finished());
+
+        // A signature that does not reveal the seller's key share is rejected
+        assert!(buyer
+            .clone()
+            .recover_monero_key_from_refund(&Signature::new(
+                kernel.excess_sig.get_public_nonce().clone(),
+                PrivateKey::random(&mut OsRng)
+            ))
+            .is_err());
+        let spend_key = buyer.recover_monero_key_from_refund(&kernel.excess_sig).unwrap();
+        assert_eq!(monero.sweep(&spend_key).unwrap(), parameters().xmr_amount);
+        assert!(buyer.is_finished());
+    }
+
+    #[test]
+    fn it_lets_the_buyer_punish() {
+        let (_seller, mut buyer) = ready_swaps();
+        let kernel = buyer.punish_kernel().unwrap();
+        assert_eq!(kernel.lock_height, parameters().punish_height);
+        assert!(kernel.verify_signature().is_ok());
+        assert_eq!(buyer.state, XmrSwapState::TariPunished);
+    }
+
+    #[test]
+    fn it_enforces_the_lock_script_windows() {
+        let (seller, buyer) = ready_swaps();
+        let script = seller.lock_script().unwrap();
+        let key_at = |height| {
+            let context = ScriptContext::new(height, &[0u8; 32], &Commitment::default());
+            match script
+                .execute_with_context(&ExecutionStack::default(), &context)
+                .unwrap()
+            {
+                StackItem::PublicKey(key) => key,
+                item => panic!("Unexpected script result {:?}", item),
+            }
+        };
+        let seller_key = PublicKey::from_secret_key(seller.script_key());
+        let buyer_key = PublicKey::from_secret_key(buyer.script_key());
+        assert_eq!(key_at(99), buyer_key);
+        assert_eq!(key_at(100), seller_key);
+        assert_eq!(key_at(199), seller_key);
+        assert_eq!(key_at(200), buyer_key);
+    }
+
+    #[test]
+    fn it_enforces_roles_and_states() {
+        let (mut seller, mut buyer) = ready_swaps();
+        assert!(matches!(seller.claim_kernel(), Err(XmrSwapError::WrongRole(_))));
+        assert!(matches!(buyer.authorize_claim(), Err(XmrSwapError::WrongRole(_))));
+        assert!(matches!(
+            seller.authorize_claim(),
+            Err(XmrSwapError::InvalidState { .. })
+        ));
+        assert!(seller.abort().is_err());
+
+        let mut fresh = XmrSwap::new(XmrSwapRole::TariSeller, parameters()).unwrap();
+        fresh.abort().unwrap();
+        assert!(fresh.is_finished());
+    }
+}
@@ -63,6 +63,10 @@ pub enum UtxoScannerError {
     FixedHashSizeError(#[from] FixedHashSizeError),
     #[error("Connectivity has shut down")]
     ConnectivityShutdown,
+    #[error("UTXO scanner service has shut down")]
+    ServiceShutdown,
+    #[error("Invalid rescan range: {0}")]
+    InvalidRescanRange(String),
 }
 
 impl From<HexError> for UtxoScannerError {
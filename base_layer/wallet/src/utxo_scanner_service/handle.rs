@@ -24,9 +24,9 @@ use std::time::Duration;
 
 use tari_comms::peer_manager::NodeId;
 use tari_core::transactions::tari_amount::MicroMinotari;
-use tokio::sync::{broadcast, watch};
+use tokio::sync::{broadcast, mpsc, watch};
 
-use crate::util::watch::Watch;
+use crate::{util::watch::Watch, utxo_scanner_service::error::UtxoScannerError};
 
 #[derive(Debug, Clone)]
 pub enum UtxoScannerEvent {
@@ -57,6 +57,33 @@ pub enum UtxoScannerEvent {
     },
     /// Scanning process has failed and scanning process has exited
     ScanningFailed,
+    /// Progress of a requested rescan (current_block, last block of the rescan)
+    RescanProgress {
+        current_height: u64,
+        to_height: u64,
+    },
+    /// Completed rescan of a height range (Num of newly recovered outputs, Value of newly recovered outputs, Time
+    /// taken)
+    RescanCompleted {
+        from_height: u64,
+        to_height: u64,
+        num_recovered: u64,
+        value_recovered: MicroMinotari,
+        time_taken: Duration,
+    },
+    /// A requested rescan has failed and was abandoned
+    RescanFailed {
+        error: String,
+    },
+}
+
+/// A request to rescan a range of blocks for outputs belonging to the wallet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RescanRequest {
+    /// The first block to scan, or the wallet birthday if not provided
+    pub from_height: Option<u64>,
+    /// The last block to scan, or the chain tip if not provided
+    pub to_height: Option<u64>,
 }
 
 #[derive(Clone)]
@@ -64,6 +91,7 @@ pub struct UtxoScannerHandle {
     event_sender: broadcast::Sender<UtxoScannerEvent>,
     one_sided_message_watch: Watch<String>,
     recovery_message_watch: Watch<String>,
+    rescan_request_sender: mpsc::Sender<RescanRequest>,
 }
 
 impl UtxoScannerHandle {
@@ -71,11 +99,13 @@ impl UtxoScannerHandle {
         event_sender: broadcast::Sender<UtxoScannerEvent>,
         one_sided_message_watch: Watch<String>,
         recovery_message_watch: Watch<String>,
+        rescan_request_sender: mpsc::Sender<RescanRequest>,
     ) -> Self {
         UtxoScannerHandle {
             event_sender,
             one_sided_message_watch,
             recovery_message_watch,
+            rescan_request_sender,
         }
     }
 
@@ -91,6 +121,24 @@ impl UtxoScannerHandle {
         self.recovery_message_watch.send(note);
     }
 
+    /// Queue a rescan of the blocks from `from_height` to `to_height` (inclusive) that runs alongside normal scanning.
+    /// Outputs that are already known to the wallet are not imported again. The rescan reports its progress through
+    /// the `Rescan*` events.
+    pub async fn rescan(&mut self, from_height: Option<u64>, to_height: Option<u64>) -> Result<(), UtxoScannerError> {
+        if let (Some(from), Some(to)) = (from_height, to_height) {
+            if from > to {
+                return Err(UtxoScannerError::InvalidRescanRange(format!(
+                    "from height {} is greater than to height {}",
+                    from, to
+                )));
+            }
+        }
+        self.rescan_request_sender
+            .send(RescanRequest { from_height, to_height })
+            .await
+            .map_err(|_| UtxoScannerError::ServiceShutdown)
+    }
+
     pub(crate) fn get_one_sided_payment_message_watcher(&self) -> watch::Receiver<String> {
         self.one_sided_message_watch.get_receiver()
     }
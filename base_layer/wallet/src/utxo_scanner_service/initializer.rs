@@ -25,7 +25,7 @@ use log::*;
 use tari_comms::connectivity::ConnectivityRequester;
 use tari_core::transactions::CryptoFactories;
 use tari_service_framework::{async_trait, ServiceInitializationError, ServiceInitializer, ServiceInitializerContext};
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, mpsc};
 
 use crate::{
     base_node_service::handle::BaseNodeServiceHandle,
@@ -69,6 +69,7 @@ where T: WalletBackend + 'static
         trace!(target: LOG_TARGET, "Utxo scanner initialization");
 
         let (event_sender, _) = broadcast::channel(200);
+        let (rescan_request_sender, rescan_request_receiver) = mpsc::channel(10);
 
         let recovery_message_watch = Watch::new("Output found on blockchain during Wallet Recovery".to_string());
         let one_sided_message_watch = Watch::new("Detected one-sided payment on blockchain".to_string());
@@ -77,8 +78,12 @@ where T: WalletBackend + 'static
         let one_sided_message_watch_receiver = one_sided_message_watch.get_receiver();
 
         // Register handle before waiting for handles to be ready
-        let utxo_scanner_handle =
-            UtxoScannerHandle::new(event_sender.clone(), one_sided_message_watch, recovery_message_watch);
+        let utxo_scanner_handle = UtxoScannerHandle::new(
+            event_sender.clone(),
+            one_sided_message_watch,
+            recovery_message_watch,
+            rescan_request_sender,
+        );
         context.register_handle(utxo_scanner_handle);
 
         let backend = self
@@ -112,6 +117,7 @@ where T: WalletBackend + 'static
                     base_node_service_handle,
                     one_sided_message_watch_receiver,
                     recovery_message_watch_receiver,
+                    rescan_request_receiver,
                 )
                 .run();
 
@@ -20,6 +20,8 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use std::sync::Arc;
+
 use chrono::NaiveDateTime;
 use futures::FutureExt;
 use log::*;
@@ -28,7 +30,7 @@ use tari_comms::{connectivity::ConnectivityRequester, peer_manager::Peer, types:
 use tari_core::transactions::{tari_amount::MicroMinotari, CryptoFactories};
 use tari_shutdown::{Shutdown, ShutdownSignal};
 use tokio::{
-    sync::{broadcast, watch},
+    sync::{broadcast, mpsc, watch, Mutex},
     task,
 };
 
@@ -41,7 +43,7 @@ use crate::{
     transaction_service::handle::TransactionServiceHandle,
     util::wallet_identity::WalletIdentity,
     utxo_scanner_service::{
-        handle::UtxoScannerEvent,
+        handle::{RescanRequest, UtxoScannerEvent},
         utxo_scanner_task::UtxoScannerTask,
         uxto_scanner_service_builder::{UtxoScannerMode, UtxoScannerServiceBuilder},
     },
@@ -55,6 +57,7 @@ pub const SCANNED_BLOCK_CACHE_SIZE: u64 = 720;
 pub struct UtxoScannerService<TBackend, TWalletConnectivity> {
     pub(crate) resources: UtxoScannerResources<TBackend, TWalletConnectivity>,
     pub(crate) retry_limit: usize,
+    pub(crate) segment_size: u64,
     pub(crate) peer_seeds: Vec<CommsPublicKey>,
     pub(crate) mode: UtxoScannerMode,
     pub(crate) shutdown_signal: ShutdownSignal,
@@ -62,6 +65,9 @@ pub struct UtxoScannerService<TBackend, TWalletConnectivity> {
     pub(crate) base_node_service: BaseNodeServiceHandle,
     one_sided_message_watch: watch::Receiver<String>,
     recovery_message_watch: watch::Receiver<String>,
+    rescan_request_receiver: mpsc::Receiver<RescanRequest>,
+    // Held by a running rescan so that queued rescans are run one at a time
+    rescan_lock: Arc<Mutex<()>>,
 }
 
 impl<TBackend, TWalletConnectivity> UtxoScannerService<TBackend, TWalletConnectivity>
@@ -72,6 +78,7 @@ where
     pub fn new(
         peer_seeds: Vec<CommsPublicKey>,
         retry_limit: usize,
+        segment_size: u64,
         mode: UtxoScannerMode,
         resources: UtxoScannerResources<TBackend, TWalletConnectivity>,
         shutdown_signal: ShutdownSignal,
@@ -79,17 +86,21 @@ where
         base_node_service: BaseNodeServiceHandle,
         one_sided_message_watch: watch::Receiver<String>,
         recovery_message_watch: watch::Receiver<String>,
+        rescan_request_receiver: mpsc::Receiver<RescanRequest>,
     ) -> Self {
         Self {
             resources,
             peer_seeds,
             retry_limit,
+            segment_size,
             mode,
             shutdown_signal,
             event_sender,
             base_node_service,
             one_sided_message_watch,
             recovery_message_watch,
+            rescan_request_receiver,
+            rescan_lock: Arc::new(Mutex::new(())),
         }
     }
 
@@ -99,6 +110,7 @@ where
             peer_seeds: self.peer_seeds.clone(),
             event_sender: self.event_sender.clone(),
             retry_limit: self.retry_limit,
+            segment_size: self.segment_size,
             peer_index: 0,
             num_retries: 1,
             mode: self.mode.clone(),
@@ -106,6 +118,21 @@ where
         }
     }
 
+    /// Spawns a rescan of the requested height range. The rescan uses the main shutdown signal so that it is not
+    /// interrupted by the scanning rounds that restart on every new block.
+    fn spawn_rescan(&self, request: RescanRequest) {
+        let task = self.create_task(self.shutdown_signal.clone());
+        let event_sender = self.event_sender.clone();
+        let rescan_lock = self.rescan_lock.clone();
+        task::spawn(async move {
+            let _guard = rescan_lock.lock().await;
+            if let Err(err) = task.rescan(request.from_height, request.to_height).await {
+                error!(target: LOG_TARGET, "Error rescanning UTXOs: {}", err);
+                let _size = event_sender.send(UtxoScannerEvent::RescanFailed { error: err.to_string() });
+            }
+        });
+    }
+
     pub fn builder() -> UtxoScannerServiceBuilder {
         UtxoScannerServiceBuilder::default()
     }
@@ -180,6 +207,10 @@ where
                     Ok(_) = self.recovery_message_watch.changed() => {
                             self.resources.recovery_message = (*self.recovery_message_watch.borrow()).clone();
                     },
+                    Some(request) = self.rescan_request_receiver.recv() => {
+                        debug!(target: LOG_TARGET, "Rescan requested: {:?}", request);
+                        self.spawn_rescan(request);
+                    },
                 }
             }
         }
@@ -208,3 +239,21 @@ pub struct ScannedBlock {
     pub amount: Option<MicroMinotari>,
     pub timestamp: NaiveDateTime,
 }
+
+/// A range of block heights that is scanned by a single peer during parallel wallet recovery. `next_height` is the
+/// checkpoint that the scan resumes from, and the totals accumulate the outputs recovered in the segment so far.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecoverySegment {
+    pub start_height: u64,
+    pub end_height: u64,
+    pub end_header_hash: HashOutput,
+    pub next_height: u64,
+    pub num_outputs: u64,
+    pub amount: MicroMinotari,
+}
+
+impl RecoverySegment {
+    pub fn is_complete(&self) -> bool {
+        self.next_height > self.end_height
+    }
+}
@@ -21,12 +21,17 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use std::{
+    collections::VecDeque,
     convert::{TryFrom, TryInto},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
 
 use chrono::{NaiveDateTime, Utc};
-use futures::StreamExt;
+use futures::{future, StreamExt};
 use log::*;
 use tari_common_types::{
     tari_address::TariAddress,
@@ -52,17 +57,20 @@ use tari_core::{
 use tari_key_manager::get_birthday_from_unix_epoch_in_seconds;
 use tari_shutdown::ShutdownSignal;
 use tari_utilities::hex::Hex;
-use tokio::sync::broadcast;
+use tokio::{
+    sync::{broadcast, Mutex},
+    task,
+};
 
 use crate::{
     connectivity_service::WalletConnectivityInterface,
     error::WalletError,
-    storage::database::WalletBackend,
+    storage::database::{WalletBackend, WalletDatabase},
     transaction_service::error::{TransactionServiceError, TransactionStorageError},
     utxo_scanner_service::{
         error::UtxoScannerError,
         handle::UtxoScannerEvent,
-        service::{ScannedBlock, UtxoScannerResources, SCANNED_BLOCK_CACHE_SIZE},
+        service::{RecoverySegment, ScannedBlock, UtxoScannerResources, SCANNED_BLOCK_CACHE_SIZE},
         uxto_scanner_service_builder::UtxoScannerMode,
         RECOVERY_KEY,
     },
@@ -75,6 +83,7 @@ pub struct UtxoScannerTask<TBackend, TWalletConnectivity> {
     pub(crate) event_sender: broadcast::Sender<UtxoScannerEvent>,
     pub(crate) retry_limit: usize,
     pub(crate) num_retries: usize,
+    pub(crate) segment_size: u64,
     pub(crate) peer_seeds: Vec<CommsPublicKey>,
     pub(crate) peer_index: usize,
     pub(crate) mode: UtxoScannerMode,
@@ -88,6 +97,7 @@ where
     pub async fn run(mut self) -> Result<(), UtxoScannerError> {
         if self.mode == UtxoScannerMode::Recovery {
             self.set_recovery_mode()?;
+            self.recover_segments_in_parallel().await?;
         } else {
             let in_progress = self.check_recovery_mode()?;
             if in_progress {
@@ -146,6 +156,110 @@ where
         }
     }
 
+    /// Rescans the blocks from `from_height` (the wallet birthday if not provided) to `to_height` (the chain tip if
+    /// not provided) using the current base node. Outputs that are already known to the output manager are skipped
+    /// by the recoverers, so only newly found outputs are imported and counted.
+    // converting u64 to i64 is its only used for timestamps
+    #[allow(clippy::cast_possible_wrap)]
+    pub async fn rescan(mut self, from_height: Option<u64>, to_height: Option<u64>) -> Result<(), UtxoScannerError> {
+        // Setting how often the progress event should occur during scanning. Defined in blocks
+        const PROGRESS_REPORT_INTERVAL: u64 = 100;
+
+        if self.check_recovery_mode()? {
+            return Err(UtxoScannerError::UtxoScanningError(
+                "Cannot rescan while a wallet recovery is in progress".to_string(),
+            ));
+        }
+        let timer = Instant::now();
+        let mut client = self
+            .resources
+            .wallet_connectivity
+            .obtain_base_node_wallet_rpc_client()
+            .await
+            .ok_or(UtxoScannerError::ConnectivityShutdown)?;
+
+        let tip_header = self.get_chain_tip_header(&mut client).await?;
+        let from_height = match from_height {
+            Some(height) => height,
+            None => self.get_birthday_header_height_hash(&mut client).await?.height,
+        };
+        let to_height = to_height.unwrap_or(tip_header.height);
+        if from_height > to_height || to_height > tip_header.height {
+            return Err(UtxoScannerError::InvalidRescanRange(format!(
+                "heights {} to {} are not within the current chain (tip height: {})",
+                from_height, to_height, tip_header.height
+            )));
+        }
+        let start_header = BlockHeader::try_from(client.get_header_by_height(from_height).await?)
+            .map_err(UtxoScannerError::ConversionError)?;
+        let end_header = BlockHeader::try_from(client.get_header_by_height(to_height).await?)
+            .map_err(UtxoScannerError::ConversionError)?;
+        info!(target: LOG_TARGET, "Rescanning blocks {} to {}", from_height, to_height);
+
+        let request = SyncUtxosByBlockRequest {
+            start_header_hash: start_header.hash().to_vec(),
+            end_header_hash: end_header.hash().to_vec(),
+        };
+        let mut utxo_stream = client.sync_utxos_by_block(request).await?;
+
+        let mut num_recovered = 0u64;
+        let mut total_amount = MicroMinotari::from(0);
+        let mut last_height = None;
+        while let Some(response) = utxo_stream.next().await {
+            if self.shutdown_signal.is_triggered() {
+                return Ok(());
+            }
+
+            let response = response.map_err(|e| UtxoScannerError::RpcStatus(e.to_string()))?;
+            let current_height = response.height;
+            if last_height != Some(current_height) && current_height % PROGRESS_REPORT_INTERVAL == 0 {
+                self.publish_event(UtxoScannerEvent::RescanProgress {
+                    current_height,
+                    to_height,
+                });
+            }
+            last_height = Some(current_height);
+
+            let mined_timestamp =
+                NaiveDateTime::from_timestamp_opt(response.mined_timestamp as i64, 0).unwrap_or(NaiveDateTime::MIN);
+            let outputs = response
+                .outputs
+                .into_iter()
+                .map(|utxo| TransactionOutput::try_from(utxo).map_err(UtxoScannerError::ConversionError))
+                .collect::<Result<Vec<_>, _>>()?;
+            let found_outputs = self.scan_for_outputs(outputs).await?;
+            let (count, amount) = self
+                .import_utxos_to_transaction_service(found_outputs, current_height, mined_timestamp)
+                .await?;
+            num_recovered = num_recovered.saturating_add(count);
+            total_amount += amount;
+        }
+        if last_height != Some(to_height) {
+            return Err(UtxoScannerError::UtxoScanningError(format!(
+                "Base node ended the rescan before reaching height {}",
+                to_height
+            )));
+        }
+
+        info!(
+            target: LOG_TARGET,
+            "Rescan of blocks {} to {} completed in {:.2?} ({} outputs recovered with value {})",
+            from_height,
+            to_height,
+            timer.elapsed(),
+            num_recovered,
+            total_amount
+        );
+        self.publish_event(UtxoScannerEvent::RescanCompleted {
+            from_height,
+            to_height,
+            num_recovered,
+            value_recovered: total_amount,
+            time_taken: timer.elapsed(),
+        });
+        Ok(())
+    }
+
     fn finalize(
         &self,
         num_outputs_recovered: u64,
@@ -166,6 +280,7 @@ where
 
         // Presence of scanning keys are used to determine if a wallet is busy with recovery or not.
         if self.mode == UtxoScannerMode::Recovery {
+            self.resources.db.clear_recovery_segments()?;
             self.clear_recovery_mode()?;
         }
         Ok(())
@@ -197,11 +312,14 @@ where
         }
     }
 
-    async fn attempt_sync(&mut self, peer: NodeId) -> Result<(u64, u64, MicroMinotari, Duration), UtxoScannerError> {
+    async fn connect_rpc_client(
+        &mut self,
+        peer: &NodeId,
+    ) -> Result<RpcClientLease<BaseNodeWalletRpcClient>, UtxoScannerError> {
         self.publish_event(UtxoScannerEvent::ConnectingToBaseNode(peer.clone()));
         let selected_peer = self.resources.wallet_connectivity.get_current_base_node_id();
 
-        let mut client = if selected_peer.map(|p| p == peer).unwrap_or(false) {
+        let client = if selected_peer.map(|p| &p == peer).unwrap_or(false) {
             // Use the wallet connectivity service so that RPC pools are correctly managed
             self.resources
                 .wallet_connectivity
@@ -209,7 +327,7 @@ where
                 .await
                 .ok_or(UtxoScannerError::ConnectivityShutdown)?
         } else {
-            self.establish_new_rpc_connection(&peer).await?
+            self.establish_new_rpc_connection(peer).await?
         };
 
         let latency = client.get_last_request_latency();
@@ -217,7 +335,11 @@ where
             peer.clone(),
             latency.unwrap_or_default(),
         ));
+        Ok(client)
+    }
 
+    async fn attempt_sync(&mut self, peer: NodeId) -> Result<(u64, u64, MicroMinotari, Duration), UtxoScannerError> {
+        let mut client = self.connect_rpc_client(&peer).await?;
         let timer = Instant::now();
 
         loop {
@@ -311,6 +433,345 @@ where
         }
     }
 
+    /// Scans the wallet history concurrently from all peer seeds. The height range from the wallet birthday up to the
+    /// most recent blocks is split into segments of `segment_size` blocks, each of which is scanned by one peer at a
+    /// time. Progress is checkpointed per segment so that an interrupted recovery resumes where it left off. Once all
+    /// segments are scanned their totals are merged into a single scanned block, from which the sequential scan
+    /// continues to the chain tip.
+    async fn recover_segments_in_parallel(&mut self) -> Result<(), UtxoScannerError> {
+        if self.segment_size == 0 || self.peer_seeds.len() < 2 {
+            return Ok(());
+        }
+        let segments = match self.load_or_plan_segments().await {
+            Ok(Some(segments)) => segments,
+            Ok(None) => return Ok(()),
+            Err(e) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Could not plan parallel recovery, falling back to sequential scanning: {}", e
+                );
+                return Ok(());
+            },
+        };
+
+        let start_height = segments.first().map(|s| s.start_height).unwrap_or_default();
+        let end_height = segments.last().map(|s| s.end_height).unwrap_or_default();
+        let num_scanned = segments
+            .iter()
+            .map(|s| s.next_height.saturating_sub(s.start_height))
+            .sum::<u64>();
+        let pending = segments
+            .into_iter()
+            .filter(|s| !s.is_complete())
+            .collect::<VecDeque<_>>();
+        info!(
+            target: LOG_TARGET,
+            "Recovering heights {} to {} in parallel from {} peer(s) ({} segment(s) remaining)",
+            start_height,
+            end_height,
+            self.peer_seeds.len(),
+            pending.len()
+        );
+        let queue = Arc::new(Mutex::new(pending));
+        let progress = Arc::new(AtomicU64::new(start_height + num_scanned));
+
+        loop {
+            let workers = self
+                .peer_seeds
+                .iter()
+                .map(|public_key| {
+                    let worker = self.create_segment_worker();
+                    let peer = NodeId::from_public_key(public_key);
+                    task::spawn(worker.scan_segments(peer, queue.clone(), progress.clone(), end_height))
+                })
+                .collect::<Vec<_>>();
+            for result in future::join_all(workers).await {
+                match result {
+                    Ok(Ok(())) => {},
+                    Ok(Err(e)) => warn!(target: LOG_TARGET, "Recovery segment worker stopped: {}", e),
+                    Err(e) => warn!(target: LOG_TARGET, "Recovery segment worker panicked: {}", e),
+                }
+            }
+            if self.shutdown_signal.is_triggered() {
+                return Ok(());
+            }
+
+            let num_remaining = {
+                let mut queue = queue.lock().await;
+                if queue.is_empty() {
+                    // A segment can leave the queue without being completed, e.g. when the worker scanning it panicked.
+                    // The checkpoints are authoritative, so any segment they do not show as complete is scanned again.
+                    let incomplete = self.incomplete_recovery_segments()?;
+                    if incomplete.is_empty() {
+                        break;
+                    }
+                    warn!(
+                        target: LOG_TARGET,
+                        "Requeueing {} incomplete recovery segment(s)",
+                        incomplete.len()
+                    );
+                    queue.extend(incomplete);
+                }
+                queue.len()
+            };
+            self.publish_event(UtxoScannerEvent::ScanningRoundFailed {
+                num_retries: self.num_retries,
+                retry_limit: self.retry_limit,
+                error: format!("{} recovery segment(s) left unscanned after this round", num_remaining),
+            });
+            if self.num_retries >= self.retry_limit {
+                self.publish_event(UtxoScannerEvent::ScanningFailed);
+                return Err(UtxoScannerError::UtxoScanningError(format!(
+                    "Failed to scan {} recovery segment(s) after {} attempt(s) using sync peer(s). Aborting...",
+                    num_remaining, self.num_retries,
+                )));
+            }
+            self.num_retries += 1;
+        }
+
+        merge_recovery_segments(&self.resources.db)
+    }
+
+    /// Returns the checkpointed segments of an interrupted parallel recovery, or plans new segments for a fresh one.
+    /// `None` is returned when the recovery should be done sequentially.
+    async fn load_or_plan_segments(&mut self) -> Result<Option<Vec<RecoverySegment>>, UtxoScannerError> {
+        let segments = self.resources.db.get_recovery_segments()?;
+        if !segments.is_empty() {
+            debug!(
+                target: LOG_TARGET,
+                "Resuming parallel recovery with {} checkpointed segment(s)",
+                segments.len()
+            );
+            return Ok(Some(segments));
+        }
+        // A recovery that already made progress sequentially is also resumed sequentially
+        if !self.resources.db.get_scanned_blocks()?.is_empty() {
+            return Ok(None);
+        }
+
+        let mut client = None;
+        while let Some(peer) = self.get_next_peer() {
+            match self.connect_rpc_client(&peer).await {
+                Ok(c) => {
+                    client = Some(c);
+                    break;
+                },
+                Err(e) => warn!(target: LOG_TARGET, "Failed to connect to base node {}: {}", peer, e),
+            }
+        }
+        self.peer_index = 0;
+        let mut client = match client {
+            Some(client) => client,
+            None => return Ok(None),
+        };
+
+        let tip_header = self.get_chain_tip_header(&mut client).await?;
+        let birthday = self.get_birthday_header_height_hash(&mut client).await?;
+        // The most recent blocks are left to the sequential scan, which keeps a reorg-aware cache of scanned blocks
+        let end_height = tip_header.height.saturating_sub(SCANNED_BLOCK_CACHE_SIZE);
+        let ranges = plan_segments(birthday.height, end_height, self.segment_size);
+        if ranges.len() < 2 {
+            return Ok(None);
+        }
+
+        let mut segments = Vec::with_capacity(ranges.len());
+        for (start_height, end_height) in ranges {
+            let end_header = BlockHeader::try_from(client.get_header_by_height(end_height).await?)
+                .map_err(UtxoScannerError::ConversionError)?;
+            segments.push(RecoverySegment {
+                start_height,
+                end_height,
+                end_header_hash: end_header.hash(),
+                next_height: start_height,
+                num_outputs: 0,
+                amount: MicroMinotari::from(0),
+            });
+        }
+        for segment in &segments {
+            self.resources.db.save_recovery_segment(segment.clone())?;
+        }
+        debug!(
+            target: LOG_TARGET,
+            "Planned {} recovery segment(s) of {} block(s) from height {} to {}",
+            segments.len(),
+            self.segment_size,
+            birthday.height,
+            end_height
+        );
+        Ok(Some(segments))
+    }
+
+    fn incomplete_recovery_segments(&self) -> Result<Vec<RecoverySegment>, UtxoScannerError> {
+        Ok(self
+            .resources
+            .db
+            .get_recovery_segments()?
+            .into_iter()
+            .filter(|s| !s.is_complete())
+            .collect())
+    }
+
+    fn create_segment_worker(&self) -> Self {
+        Self {
+            resources: self.resources.clone(),
+            event_sender: self.event_sender.clone(),
+            retry_limit: self.retry_limit,
+            num_retries: self.num_retries,
+            segment_size: self.segment_size,
+            peer_seeds: self.peer_seeds.clone(),
+            peer_index: 0,
+            mode: self.mode.clone(),
+            shutdown_signal: self.shutdown_signal.clone(),
+        }
+    }
+
+    /// Scans segments from the shared queue using a single peer until the queue is empty. A segment that fails is put
+    /// back on the queue, from its last checkpoint, for another peer to pick up.
+    async fn scan_segments(
+        mut self,
+        peer: NodeId,
+        queue: Arc<Mutex<VecDeque<RecoverySegment>>>,
+        progress: Arc<AtomicU64>,
+        tip_height: u64,
+    ) -> Result<(), UtxoScannerError> {
+        let mut client = self.connect_rpc_client(&peer).await?;
+        loop {
+            if self.shutdown_signal.is_triggered() {
+                return Ok(());
+            }
+            let next_segment = queue.lock().await.pop_front();
+            let mut segment = match next_segment {
+                Some(segment) => segment,
+                None => return Ok(()),
+            };
+            if let Err(e) = self
+                .scan_segment(&mut client, &mut segment, &progress, tip_height)
+                .await
+            {
+                queue.lock().await.push_back(segment);
+                return Err(e);
+            }
+        }
+    }
+
+    // converting u64 to i64 is its only used for timestamps
+    #[allow(clippy::cast_possible_wrap)]
+    async fn scan_segment(
+        &mut self,
+        client: &mut BaseNodeWalletRpcClient,
+        segment: &mut RecoverySegment,
+        progress: &AtomicU64,
+        tip_height: u64,
+    ) -> Result<(), UtxoScannerError> {
+        let end_header = BlockHeader::try_from(client.get_header_by_height(segment.end_height).await?)
+            .map_err(UtxoScannerError::ConversionError)?;
+        if end_header.hash() != segment.end_header_hash {
+            return Err(UtxoScannerError::UtxoScanningError(format!(
+                "Header #{} ending recovery segment {}-{} is not on the peer's chain",
+                segment.end_height, segment.start_height, segment.end_height
+            )));
+        }
+        let start_header = BlockHeader::try_from(client.get_header_by_height(segment.next_height).await?)
+            .map_err(UtxoScannerError::ConversionError)?;
+        debug!(
+            target: LOG_TARGET,
+            "Scanning recovery segment {}-{} from height {}", segment.start_height, segment.end_height, segment.next_height
+        );
+
+        let request = SyncUtxosByBlockRequest {
+            start_header_hash: start_header.hash().to_vec(),
+            end_header_hash: segment.end_header_hash.to_vec(),
+        };
+        let mut utxo_stream = client.sync_utxos_by_block(request).await?;
+
+        // Outputs recovered from the block that is currently being streamed. They are added to the segment totals once
+        // the block is complete so that a checkpoint never contains a partially scanned block.
+        let mut block_outputs = 0u64;
+        let mut block_amount = MicroMinotari::from(0);
+        let mut last_height = None;
+        while let Some(response) = utxo_stream.next().await {
+            if self.shutdown_signal.is_triggered() {
+                return Ok(());
+            }
+
+            let response = response.map_err(|e| UtxoScannerError::RpcStatus(e.to_string()))?;
+            let current_height = response.height;
+            if current_height > segment.next_height {
+                self.checkpoint_segment(
+                    segment,
+                    current_height,
+                    block_outputs,
+                    block_amount,
+                    progress,
+                    tip_height,
+                )?;
+                block_outputs = 0;
+                block_amount = MicroMinotari::from(0);
+            }
+            last_height = Some(current_height);
+
+            let mined_timestamp =
+                NaiveDateTime::from_timestamp_opt(response.mined_timestamp as i64, 0).unwrap_or(NaiveDateTime::MIN);
+            let outputs = response
+                .outputs
+                .into_iter()
+                .map(|utxo| TransactionOutput::try_from(utxo).map_err(UtxoScannerError::ConversionError))
+                .collect::<Result<Vec<_>, _>>()?;
+            let found_outputs = self.scan_for_outputs(outputs).await?;
+            let (count, amount) = self
+                .import_utxos_to_transaction_service(found_outputs, current_height, mined_timestamp)
+                .await?;
+            block_outputs = block_outputs.saturating_add(count);
+            block_amount += amount;
+        }
+
+        if last_height != Some(segment.end_height) {
+            return Err(UtxoScannerError::UtxoScanningError(format!(
+                "Peer ended the stream of recovery segment {}-{} early",
+                segment.start_height, segment.end_height
+            )));
+        }
+        self.checkpoint_segment(
+            segment,
+            segment.end_height + 1,
+            block_outputs,
+            block_amount,
+            progress,
+            tip_height,
+        )?;
+        Ok(())
+    }
+
+    /// Records that all blocks of the segment below `next_height` have been scanned.
+    fn checkpoint_segment(
+        &self,
+        segment: &mut RecoverySegment,
+        next_height: u64,
+        num_outputs: u64,
+        amount: MicroMinotari,
+        progress: &AtomicU64,
+        tip_height: u64,
+    ) -> Result<(), UtxoScannerError> {
+        // Setting how often the progress event should occur during scanning. Defined in blocks
+        const PROGRESS_REPORT_INTERVAL: u64 = 100;
+
+        let num_blocks = next_height.saturating_sub(segment.next_height);
+        segment.next_height = next_height;
+        segment.num_outputs = segment.num_outputs.saturating_add(num_outputs);
+        segment.amount += amount;
+        self.resources.db.save_recovery_segment(segment.clone())?;
+
+        let previous = progress.fetch_add(num_blocks, Ordering::Relaxed);
+        let current_height = previous + num_blocks;
+        if current_height / PROGRESS_REPORT_INTERVAL > previous / PROGRESS_REPORT_INTERVAL {
+            self.publish_event(UtxoScannerEvent::Progress {
+                current_height,
+                tip_height,
+            });
+        }
+        Ok(())
+    }
+
     async fn establish_new_rpc_connection(
         &mut self,
         peer: &NodeId,
@@ -746,3 +1207,146 @@ struct HeightHash {
     height: u64,
     header_hash: HashOutput,
 }
+
+/// Replaces the scanned segments with a single scanned block at the end of the last segment that carries the
+/// totals of all segments. Segments are only merged once every one of them is complete, since merging would
+/// otherwise skip the blocks that are still unscanned.
+fn merge_recovery_segments<TBackend: WalletBackend>(db: &WalletDatabase<TBackend>) -> Result<(), UtxoScannerError> {
+    let segments = db.get_recovery_segments()?;
+    if let Some(segment) = segments.iter().find(|s| !s.is_complete()) {
+        return Err(UtxoScannerError::UtxoScanningError(format!(
+            "Recovery segment {}-{} is only scanned up to height {}",
+            segment.start_height, segment.end_height, segment.next_height
+        )));
+    }
+    let mut num_outputs = 0u64;
+    let mut amount = MicroMinotari::from(0);
+    for segment in &segments {
+        num_outputs = num_outputs.saturating_add(segment.num_outputs);
+        amount = amount
+            .checked_add(segment.amount)
+            .ok_or(UtxoScannerError::OverflowError)?;
+    }
+    if let Some(last) = segments.last() {
+        db.clear_scanned_blocks()?;
+        db.save_scanned_block(ScannedBlock {
+            header_hash: last.end_header_hash,
+            height: last.end_height,
+            num_outputs: Some(num_outputs),
+            amount: Some(amount),
+            timestamp: Utc::now().naive_utc(),
+        })?;
+        info!(
+            target: LOG_TARGET,
+            "Parallel recovery completed up to height {} ({} outputs recovered with value {})",
+            last.end_height,
+            num_outputs,
+            amount
+        );
+    }
+    db.clear_recovery_segments()?;
+    Ok(())
+}
+
+/// Splits the inclusive height range `start_height..=end_height` into consecutive inclusive ranges of at most
+/// `segment_size` blocks.
+fn plan_segments(start_height: u64, end_height: u64, segment_size: u64) -> Vec<(u64, u64)> {
+    let mut segments = Vec::new();
+    if segment_size == 0 {
+        return segments;
+    }
+    let mut start = start_height;
+    while start <= end_height {
+        let end = start.saturating_add(segment_size - 1).min(end_height);
+        segments.push((start, end));
+        start = match end.checked_add(1) {
+            Some(next) => next,
+            None => break,
+        };
+    }
+    segments
+}
+
+#[cfg(test)]
+mod test {
+    use tari_test_utils::random;
+    use tempfile::{tempdir, TempDir};
+
+    use super::*;
+    use crate::storage::{
+        sqlite_db::wallet::WalletSqliteDatabase,
+        sqlite_utilities::run_migration_and_create_sqlite_connection,
+    };
+
+    fn create_wallet_db() -> (WalletDatabase<WalletSqliteDatabase>, TempDir) {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join(format!("{}.sqlite3", random::string(8)));
+        let connection = run_migration_and_create_sqlite_connection(db_path, 16).unwrap();
+        let db = WalletDatabase::new(WalletSqliteDatabase::new(connection, "passphrase".to_string().into()).unwrap());
+        (db, temp_dir)
+    }
+
+    fn segment(start_height: u64, end_height: u64, next_height: u64, num_outputs: u64, amount: u64) -> RecoverySegment {
+        RecoverySegment {
+            start_height,
+            end_height,
+            end_header_hash: HashOutput::from([u8::try_from(end_height).unwrap(); 32]),
+            next_height,
+            num_outputs,
+            amount: MicroMinotari::from(amount),
+        }
+    }
+
+    #[test]
+    fn it_plans_contiguous_segments() {
+        assert_eq!(plan_segments(100, 349, 100), vec![(100, 199), (200, 299), (300, 349)]);
+        assert_eq!(plan_segments(0, 99, 100), vec![(0, 99)]);
+        assert_eq!(plan_segments(5, 5, 10), vec![(5, 5)]);
+        assert!(plan_segments(10, 9, 10).is_empty());
+        assert!(plan_segments(0, 1000, 0).is_empty());
+    }
+
+    #[test]
+    fn it_merges_the_totals_of_all_segments() {
+        let (db, _temp_dir) = create_wallet_db();
+        db.save_scanned_block(ScannedBlock {
+            header_hash: HashOutput::zero(),
+            height: 5,
+            num_outputs: Some(100),
+            amount: Some(MicroMinotari::from(100_000)),
+            timestamp: Utc::now().naive_utc(),
+        })
+        .unwrap();
+        db.save_recovery_segment(segment(0, 9, 10, 2, 1_500)).unwrap();
+        db.save_recovery_segment(segment(10, 19, 20, 0, 0)).unwrap();
+        db.save_recovery_segment(segment(20, 24, 25, 3, 2_250)).unwrap();
+
+        merge_recovery_segments(&db).unwrap();
+
+        assert!(db.get_recovery_segments().unwrap().is_empty());
+        let scanned_blocks = db.get_scanned_blocks().unwrap();
+        assert_eq!(scanned_blocks.len(), 1);
+        assert_eq!(scanned_blocks[0].height, 24);
+        assert_eq!(scanned_blocks[0].header_hash, segment(20, 24, 25, 0, 0).end_header_hash);
+        assert_eq!(scanned_blocks[0].num_outputs, Some(5));
+        assert_eq!(scanned_blocks[0].amount, Some(MicroMinotari::from(3_750)));
+    }
+
+    #[test]
+    fn it_does_not_merge_over_an_incomplete_segment() {
+        let (db, _temp_dir) = create_wallet_db();
+        let segments = vec![
+            segment(0, 9, 10, 2, 1_500),
+            segment(10, 19, 15, 1, 500),
+            segment(20, 24, 25, 3, 2_250),
+        ];
+        for segment in &segments {
+            db.save_recovery_segment(segment.clone()).unwrap();
+        }
+
+        let err = merge_recovery_segments(&db).unwrap_err();
+        assert!(err.to_string().contains("10-19 is only scanned up to height 15"));
+        assert_eq!(db.get_recovery_segments().unwrap(), segments);
+        assert!(db.get_scanned_blocks().unwrap().is_empty());
+    }
+}
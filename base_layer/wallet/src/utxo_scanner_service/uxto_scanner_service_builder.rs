@@ -23,7 +23,7 @@
 use tari_comms::{connectivity::ConnectivityRequester, types::CommsPublicKey};
 use tari_core::transactions::CryptoFactories;
 use tari_shutdown::ShutdownSignal;
-use tokio::sync::{broadcast, watch};
+use tokio::sync::{broadcast, mpsc, watch};
 
 use crate::{
     base_node_service::handle::BaseNodeServiceHandle,
@@ -36,7 +36,7 @@ use crate::{
     transaction_service::handle::TransactionServiceHandle,
     util::wallet_identity::WalletIdentity,
     utxo_scanner_service::{
-        handle::UtxoScannerEvent,
+        handle::{RescanRequest, UtxoScannerEvent},
         service::{UtxoScannerResources, UtxoScannerService},
     },
     WalletSqlite,
@@ -54,6 +54,7 @@ pub struct UtxoScannerServiceBuilder {
     retry_limit: usize,
     peers: Vec<CommsPublicKey>,
     mode: Option<UtxoScannerMode>,
+    recovery_segment_size: u64,
     one_sided_message: String,
     recovery_message: String,
 }
@@ -64,6 +65,7 @@ impl Default for UtxoScannerServiceBuilder {
             retry_limit: 0,
             peers: vec![],
             mode: None,
+            recovery_segment_size: 0,
             one_sided_message: "Detected one-sided payment on blockchain".to_string(),
             recovery_message: "Output found on blockchain during Wallet Recovery".to_string(),
         }
@@ -88,6 +90,14 @@ impl UtxoScannerServiceBuilder {
         self
     }
 
+    /// Set the number of blocks per segment when recovering from multiple peers concurrently. Each peer scans one
+    /// segment at a time and progress is checkpointed per segment. A size of 0 (the default) disables parallel
+    /// recovery.
+    pub fn with_recovery_segment_size(&mut self, segment_size: u64) -> &mut Self {
+        self.recovery_segment_size = segment_size;
+        self
+    }
+
     pub fn with_one_sided_message(&mut self, message: String) -> &mut Self {
         self.one_sided_message = message;
         self
@@ -118,10 +128,13 @@ impl UtxoScannerServiceBuilder {
         };
 
         let (event_sender, _) = broadcast::channel(200);
+        // Rescans are requested through the wallet's own scanner service handle, not this instance
+        let (_, rescan_request_receiver) = mpsc::channel(1);
 
         UtxoScannerService::new(
             self.peers.drain(..).collect(),
             self.retry_limit,
+            self.recovery_segment_size,
             self.mode.clone().unwrap_or_default(),
             resources,
             shutdown_signal,
@@ -129,6 +142,7 @@ impl UtxoScannerServiceBuilder {
             wallet.base_node_service.clone(),
             wallet.utxo_scanner_service.get_one_sided_payment_message_watcher(),
             wallet.utxo_scanner_service.get_recovery_message_watcher(),
+            rescan_request_receiver,
         )
     }
 
@@ -146,6 +160,7 @@ impl UtxoScannerServiceBuilder {
         base_node_service: BaseNodeServiceHandle,
         one_sided_message_watch: watch::Receiver<String>,
         recovery_message_watch: watch::Receiver<String>,
+        rescan_request_receiver: mpsc::Receiver<RescanRequest>,
     ) -> UtxoScannerService<TBackend, TWalletConnectivity> {
         let resources = UtxoScannerResources {
             db,
@@ -163,6 +178,7 @@ impl UtxoScannerServiceBuilder {
         UtxoScannerService::new(
             self.peers.drain(..).collect(),
             self.retry_limit,
+            self.recovery_segment_size,
             self.mode.clone().unwrap_or_default(),
             resources,
             shutdown_signal,
@@ -170,6 +186,7 @@ impl UtxoScannerServiceBuilder {
             base_node_service,
             one_sided_message_watch,
             recovery_message_watch,
+            rescan_request_receiver,
         )
     }
 }
@@ -80,6 +80,7 @@ use tari_utilities::{hex::Hex, ByteArray};
 
 use crate::{
     base_node_service::{handle::BaseNodeServiceHandle, BaseNodeServiceInitializer},
+    channel_service::{handle::ChannelServiceHandle, ChannelServiceInitializer},
     config::{WalletConfig, KEY_MANAGER_COMMS_SECRET_KEY_BRANCH_KEY},
     connectivity_service::{WalletConnectivityHandle, WalletConnectivityInitializer, WalletConnectivityInterface},
     consts,
@@ -121,6 +122,7 @@ pub struct Wallet<T, U, V, W, TKeyManagerInterface> {
     pub output_manager_service: OutputManagerHandle,
     pub key_manager_service: TKeyManagerInterface,
     pub transaction_service: TransactionServiceHandle,
+    pub channel_service: ChannelServiceHandle,
     pub wallet_connectivity: WalletConnectivityHandle,
     pub contacts_service: ContactsServiceHandle,
     pub base_node_service: BaseNodeServiceHandle,
@@ -200,12 +202,19 @@ where
             .add_initializer(TransactionServiceInitializer::<U, T, TKeyManagerInterface>::new(
                 config.transaction_service_config,
                 peer_message_subscription_factory.clone(),
-                transaction_backend,
+                transaction_backend.clone(),
                 wallet_identity.clone(),
                 consensus_manager,
                 factories.clone(),
                 wallet_database.clone(),
             ))
+            .add_initializer(ChannelServiceInitializer::<U, TKeyManagerInterface>::new(
+                config.channel_service_config.clone(),
+                peer_message_subscription_factory.clone(),
+                transaction_backend,
+                factories.clone(),
+                config.network,
+            ))
             .add_initializer(LivenessInitializer::new(
                 LivenessConfig {
                     auto_ping_interval: Some(config.contacts_auto_ping_interval),
@@ -256,6 +265,7 @@ where
         let mut output_manager_handle = handles.expect_handle::<OutputManagerHandle>();
         let key_manager_handle = handles.expect_handle::<TKeyManagerInterface>();
         let transaction_service_handle = handles.expect_handle::<TransactionServiceHandle>();
+        let channel_service_handle = handles.expect_handle::<ChannelServiceHandle>();
         let contacts_handle = handles.expect_handle::<ContactsServiceHandle>();
         let dht = handles.expect_handle::<Dht>();
         let store_and_forward_requester = dht.store_and_forward_requester();
@@ -305,6 +315,7 @@ where
             output_manager_service: output_manager_handle,
             key_manager_service: key_manager_handle,
             transaction_service: transaction_service_handle,
+            channel_service: channel_service_handle,
             contacts_service: contacts_handle,
             base_node_service: base_node_service_handle,
             utxo_scanner_service: utxo_scanner_service_handle,
@@ -769,6 +769,42 @@ async fn send_not_enough_funds() {
     }
 }
 
+#[tokio::test]
+async fn send_to_unspendable_script() {
+    let (connection, _tempdir) = get_temp_sqlite_database_connection();
+    let backend = OutputManagerSqliteDatabase::new(connection.clone());
+    let mut oms = setup_output_manager_service(backend, true).await;
+
+    let uo = make_input(
+        &mut OsRng.clone(),
+        MicroMinotari::from(10_000),
+        &OutputFeatures::default(),
+        &oms.key_manager_handle,
+    )
+    .await;
+    oms.output_manager_handle.add_output(uo, None).await.unwrap();
+
+    match oms
+        .output_manager_handle
+        .prepare_transaction_to_send(
+            TxId::new_random(),
+            MicroMinotari::from(1_000),
+            UtxoSelectionCriteria::default(),
+            OutputFeatures::default(),
+            MicroMinotari::from(4),
+            TransactionMetadata::default(),
+            "".to_string(),
+            script!(PushOne IfThen Return Else PushInt(1) EndIf),
+            Covenant::default(),
+            MicroMinotari::zero(),
+        )
+        .await
+    {
+        Err(OutputManagerError::UnspendableScript(_)) => {},
+        r => panic!("Unexpected result: {:?}", r.map(|_| ())),
+    }
+}
+
 #[tokio::test]
 async fn send_no_change() {
     let (connection, _tempdir) = get_temp_sqlite_database_connection();
@@ -25,10 +25,11 @@ use minotari_wallet::output_manager_service::{
     service::Balance,
     storage::{
         database::{OutputManagerBackend, OutputManagerDatabase},
-        models::DbWalletOutput,
+        models::{DbWalletOutput, DEFAULT_ACCOUNT_ID},
         sqlite_db::OutputManagerSqliteDatabase,
         OutputSource,
     },
+    UtxoSelectionCriteria,
 };
 use rand::{rngs::OsRng, RngCore};
 use tari_common_types::{transaction::TxId, types::FixedHash};
@@ -156,7 +157,8 @@ pub async fn test_db_backend<T: OutputManagerBackend + 'static>(backend: T) {
         available_balance,
         time_locked_balance: None,
         pending_incoming_balance,
-        pending_outgoing_balance
+        pending_outgoing_balance,
+        frozen_balance: MicroMinotari(0),
     });
 
     let balance = db.get_balance(Some(3)).unwrap();
@@ -164,7 +166,8 @@ pub async fn test_db_backend<T: OutputManagerBackend + 'static>(backend: T) {
         available_balance,
         time_locked_balance: Some(time_locked_balance),
         pending_incoming_balance,
-        pending_outgoing_balance
+        pending_outgoing_balance,
+        frozen_balance: MicroMinotari(0),
     });
 
     for v in &pending_txs {
@@ -176,7 +179,8 @@ pub async fn test_db_backend<T: OutputManagerBackend + 'static>(backend: T) {
         available_balance,
         time_locked_balance: None,
         pending_incoming_balance,
-        pending_outgoing_balance
+        pending_outgoing_balance,
+        frozen_balance: MicroMinotari(0),
     });
 
     // Set first pending tx to mined but unconfirmed
@@ -197,7 +201,8 @@ pub async fn test_db_backend<T: OutputManagerBackend + 'static>(backend: T) {
         available_balance,
         time_locked_balance: None,
         pending_incoming_balance,
-        pending_outgoing_balance
+        pending_outgoing_balance,
+        frozen_balance: MicroMinotari(0),
     });
 
     // Set second pending tx to mined and confirmed
@@ -246,7 +251,8 @@ pub async fn test_db_backend<T: OutputManagerBackend + 'static>(backend: T) {
             available_balance,
             time_locked_balance: None,
             pending_incoming_balance,
-            pending_outgoing_balance
+            pending_outgoing_balance,
+            frozen_balance: MicroMinotari(0),
         },
         "Balance should change"
     );
@@ -274,7 +280,8 @@ pub async fn test_db_backend<T: OutputManagerBackend + 'static>(backend: T) {
             available_balance,
             time_locked_balance: None,
             pending_incoming_balance,
-            pending_outgoing_balance
+            pending_outgoing_balance,
+            frozen_balance: MicroMinotari(0),
         },
         "Balance should reflect new output to be received"
     );
@@ -476,3 +483,174 @@ pub async fn test_mark_as_unmined() {
     assert!(o.mined_height.is_none());
     assert!(o.mined_in_block.is_none());
 }
+
+#[tokio::test]
+pub async fn test_freeze_outputs() {
+    let (connection, _tempdir) = get_temp_sqlite_database_connection();
+    let backend = OutputManagerSqliteDatabase::new(connection);
+    let db = OutputManagerDatabase::new(backend);
+
+    let mut unspent_outputs = Vec::new();
+    let key_manager = create_test_core_key_manager_with_memory_db();
+    for i in 0..3 {
+        let uo = make_input(
+            &mut OsRng,
+            MicroMinotari::from(1000 * (i + 1)),
+            &OutputFeatures::default(),
+            &key_manager,
+        )
+        .await;
+        let kmo = DbWalletOutput::from_wallet_output(uo, &key_manager, None, OutputSource::Unknown, None, None)
+            .await
+            .unwrap();
+        db.add_unspent_output(kmo.clone()).unwrap();
+        unspent_outputs.push(kmo);
+    }
+    assert!(db.fetch_frozen_outputs().unwrap().is_empty());
+
+    db.set_outputs_frozen(&[unspent_outputs[1].commitment.clone()], true)
+        .unwrap();
+    let frozen = db.fetch_frozen_outputs().unwrap();
+    assert_eq!(frozen.len(), 1);
+    assert_eq!(frozen[0].commitment, unspent_outputs[1].commitment);
+    assert!(frozen[0].frozen);
+
+    // The frozen output is reported separately from the available balance
+    let balance = db.get_balance(None).unwrap();
+    assert_eq!(balance.available_balance, MicroMinotari::from(4000));
+    assert_eq!(balance.frozen_balance, MicroMinotari::from(2000));
+
+    // Frozen outputs are never selected, not even when explicitly requested
+    let spendable = db
+        .fetch_unspent_outputs_for_spending(&UtxoSelectionCriteria::default(), MicroMinotari::from(500), None)
+        .unwrap();
+    assert_eq!(spendable.len(), 2);
+    assert!(spendable.iter().all(|o| o.commitment != unspent_outputs[1].commitment));
+    let specific = db
+        .fetch_unspent_outputs_for_spending(
+            &UtxoSelectionCriteria::specific(vec![unspent_outputs[1].commitment.clone()]),
+            MicroMinotari::from(500),
+            None,
+        )
+        .unwrap();
+    assert!(specific.is_empty());
+
+    // An unknown commitment leaves every output untouched
+    let unknown = make_input(
+        &mut OsRng,
+        MicroMinotari::from(1000),
+        &OutputFeatures::default(),
+        &key_manager,
+    )
+    .await;
+    let unknown = DbWalletOutput::from_wallet_output(unknown, &key_manager, None, OutputSource::Unknown, None, None)
+        .await
+        .unwrap();
+    let err = db
+        .set_outputs_frozen(
+            &[unspent_outputs[0].commitment.clone(), unknown.commitment.clone()],
+            true,
+        )
+        .unwrap_err();
+    assert!(matches!(err, OutputManagerStorageError::ValuesNotFound));
+    assert_eq!(db.fetch_frozen_outputs().unwrap().len(), 1);
+
+    db.set_outputs_frozen(&[unspent_outputs[1].commitment.clone()], false)
+        .unwrap();
+    assert!(db.fetch_frozen_outputs().unwrap().is_empty());
+    let balance = db.get_balance(None).unwrap();
+    assert_eq!(balance.available_balance, MicroMinotari::from(6000));
+    assert_eq!(balance.frozen_balance, MicroMinotari::from(0));
+    let spendable = db
+        .fetch_unspent_outputs_for_spending(&UtxoSelectionCriteria::default(), MicroMinotari::from(500), None)
+        .unwrap();
+    assert_eq!(spendable.len(), 3);
+}
+
+#[tokio::test]
+pub async fn test_accounts() {
+    let (connection, _tempdir) = get_temp_sqlite_database_connection();
+    let backend = OutputManagerSqliteDatabase::new(connection);
+    let db = OutputManagerDatabase::new(backend);
+
+    let accounts = db.fetch_accounts().unwrap();
+    assert_eq!(accounts.len(), 1);
+    assert!(accounts[0].is_default());
+    assert_eq!(accounts[0].name, "default");
+
+    let savings = db.create_account("savings").unwrap();
+    assert_eq!(savings.id, 1);
+    let err = db.create_account("savings").unwrap_err();
+    assert!(matches!(err, OutputManagerStorageError::DuplicateAccount(_)));
+    let names = db
+        .fetch_accounts()
+        .unwrap()
+        .into_iter()
+        .map(|a| a.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["default".to_string(), "savings".to_string()]);
+
+    let key_manager = create_test_core_key_manager_with_memory_db();
+    let mut outputs = Vec::new();
+    for i in 0..3u64 {
+        let uo = make_input(
+            &mut OsRng,
+            MicroMinotari::from(1000 * (i + 1)),
+            &OutputFeatures::default(),
+            &key_manager,
+        )
+        .await;
+        let mut kmo = DbWalletOutput::from_wallet_output(uo, &key_manager, None, OutputSource::Unknown, None, None)
+            .await
+            .unwrap();
+        if i > 0 {
+            kmo.account_id = savings.id;
+        }
+        db.add_unspent_output_with_tx_id(TxId::from(i + 10), kmo.clone())
+            .unwrap();
+        outputs.push(kmo);
+    }
+
+    let balance = db.get_account_balance(DEFAULT_ACCOUNT_ID, None).unwrap();
+    assert_eq!(balance.available_balance, MicroMinotari::from(1000));
+    let balance = db.get_account_balance(savings.id, None).unwrap();
+    assert_eq!(balance.available_balance, MicroMinotari::from(5000));
+    let balance = db.get_balance(None).unwrap();
+    assert_eq!(balance.available_balance, MicroMinotari::from(6000));
+
+    // Only the outputs of the selected account are candidates for spending
+    let spendable = db
+        .fetch_unspent_outputs_for_spending(
+            &UtxoSelectionCriteria::default().with_account(savings.id),
+            MicroMinotari::from(500),
+            None,
+        )
+        .unwrap();
+    assert_eq!(spendable.len(), 2);
+    assert!(spendable.iter().all(|o| o.account_id == savings.id));
+
+    // Change stays in the account that funded the transaction
+    let change = make_input(
+        &mut OsRng,
+        MicroMinotari::from(700),
+        &OutputFeatures::default(),
+        &key_manager,
+    )
+    .await;
+    let change = DbWalletOutput::from_wallet_output(change, &key_manager, None, OutputSource::Unknown, None, None)
+        .await
+        .unwrap();
+    db.encumber_outputs(TxId::from(20u64), vec![outputs[1].clone()], vec![change])
+        .unwrap();
+    let balance = db.get_account_balance(savings.id, None).unwrap();
+    assert_eq!(balance.available_balance, MicroMinotari::from(3000));
+    assert_eq!(balance.pending_incoming_balance, MicroMinotari::from(700));
+    assert_eq!(balance.pending_outgoing_balance, MicroMinotari::from(2000));
+    let balance = db.get_account_balance(DEFAULT_ACCOUNT_ID, None).unwrap();
+    assert_eq!(balance.pending_incoming_balance, MicroMinotari::from(0));
+
+    let tx_ids = db.fetch_account_tx_ids(savings.id).unwrap();
+    assert_eq!(tx_ids, vec![TxId::from(11u64), TxId::from(12u64), TxId::from(20u64)]);
+    let tx_ids = db.fetch_account_tx_ids(DEFAULT_ACCOUNT_ID).unwrap();
+    assert_eq!(tx_ids, vec![TxId::from(10u64)]);
+}
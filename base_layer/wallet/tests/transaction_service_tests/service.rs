@@ -31,7 +31,7 @@ use std::{
 
 use blake2::Blake2b;
 use chacha20poly1305::{Key, KeyInit, XChaCha20Poly1305};
-use chrono::{Duration as ChronoDuration, Utc};
+use chrono::{Duration as ChronoDuration, NaiveDateTime, Utc};
 use digest::consts::U32;
 use futures::{
     channel::{mpsc, mpsc::Sender},
@@ -66,15 +66,17 @@ use minotari_wallet::{
     },
     test_utils::{create_consensus_constants, make_wallet_database_connection, random_string},
     transaction_service::{
-        config::TransactionServiceConfig,
+        config::{SpendingPolicyConfig, TransactionServiceConfig},
         error::TransactionServiceError,
         handle::{TransactionEvent, TransactionSendStatus, TransactionServiceHandle},
         service::TransactionService,
+        spending_policy::{SpendDecision, SpendKind, SpendingPolicy, SpendingPolicyError},
         storage::{
             database::{DbKeyValuePair, TransactionBackend, TransactionDatabase, WriteOperation},
             models::{CompletedTransaction, InboundTransaction, OutboundTransaction, WalletTransaction},
             sqlite_db::TransactionServiceSqliteDatabase,
         },
+        vault::{Vault, VaultStatus},
         TransactionServiceInitializer,
     },
     util::wallet_identity::WalletIdentity,
@@ -86,7 +88,7 @@ use tari_common_types::{
     chain_metadata::ChainMetadata,
     tari_address::TariAddress,
     transaction::{ImportStatus, TransactionDirection, TransactionStatus, TxId},
-    types::{FixedHash, PrivateKey, PublicKey, Signature},
+    types::{Commitment, FixedHash, PrivateKey, PublicKey, Signature},
 };
 use tari_comms::{
     message::EnvelopeBody,
@@ -409,8 +411,10 @@ async fn setup_transaction_service_no_comms(
         ..Default::default()
     });
     let wallet_identity = WalletIdentity::new(node_identity.clone(), Network::LocalNet);
+    let spending_policy = SpendingPolicy::new(&test_config.spending_policy).unwrap();
     let ts_service = TransactionService::new(
         test_config,
+        spending_policy,
         ts_db.clone(),
         wallet_db.clone(),
         ts_request_receiver,
@@ -953,6 +957,7 @@ async fn send_one_sided_transaction_to_other() {
             value,
             UtxoSelectionCriteria::default(),
             OutputFeatures::default(),
+            Covenant::default(),
             20.into(),
             message.clone(),
         )
@@ -1092,6 +1097,7 @@ async fn recover_one_sided_transaction() {
             value,
             UtxoSelectionCriteria::default(),
             OutputFeatures::default(),
+            Covenant::default(),
             20.into(),
             message.clone(),
         )
@@ -1316,6 +1322,7 @@ async fn send_one_sided_transaction_to_self() {
             value,
             UtxoSelectionCriteria::default(),
             OutputFeatures::default(),
+            Covenant::default(),
             20.into(),
             message.clone(),
         )
@@ -4001,7 +4008,8 @@ async fn test_coinbase_abandoned() {
         available_balance: MicroMinotari(0),
         time_locked_balance: Some(MicroMinotari(0)),
         pending_incoming_balance: MicroMinotari(0),
-        pending_outgoing_balance: MicroMinotari(0)
+        pending_outgoing_balance: MicroMinotari(0),
+        frozen_balance: MicroMinotari(0),
     });
 
     let invalid_txs = alice_ts_interface
@@ -4231,7 +4239,8 @@ async fn test_coinbase_abandoned() {
         available_balance: MicroMinotari(0),
         time_locked_balance: Some(MicroMinotari(0)),
         pending_incoming_balance: MicroMinotari(0),
-        pending_outgoing_balance: MicroMinotari(0)
+        pending_outgoing_balance: MicroMinotari(0),
+        frozen_balance: MicroMinotari(0),
     });
 
     // Now reorg again and have tx2 be mined
@@ -6019,3 +6028,91 @@ async fn test_get_fee_per_gram_per_block_basic() {
     assert_eq!(estimates.stats, stats.into_iter().map(Into::into).collect::<Vec<_>>());
     assert_eq!(estimates.stats.len(), 1)
 }
+
+#[tokio::test]
+async fn test_vaults_are_checked_against_the_spending_policy() {
+    let factories = CryptoFactories::default();
+    let (connection, _temp_dir) = make_wallet_database_connection(None);
+    let config = TransactionServiceConfig {
+        spending_policy: SpendingPolicyConfig {
+            max_transaction_amount: Some(MicroMinotari::from(10_000)),
+            approval_threshold: Some(MicroMinotari::from(1_000)),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut alice_ts_interface = setup_transaction_service_no_comms(factories, connection, Some(config)).await;
+    let recovery_public_key = PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng));
+
+    // A new vault is only returned to the caller, so it cannot be held and is denied above the approval threshold
+    for amount in [5_000, 20_000] {
+        let err = alice_ts_interface
+            .transaction_service_handle
+            .create_vault(
+                MicroMinotari::from(amount),
+                recovery_public_key.clone(),
+                100,
+                MicroMinotari::from(5),
+                "vault".to_string(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            TransactionServiceError::SpendingPolicyError(SpendingPolicyError::Denied(_))
+        ));
+    }
+
+    // A withdrawal above the approval threshold is held
+    let vault = alice_ts_interface
+        .ts_db
+        .insert_vault(Vault {
+            id: 0,
+            tx_id: TxId::new_random(),
+            commitment: Commitment::from_public_key(&PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng))),
+            output_hash: FixedHash::zero(),
+            amount: MicroMinotari::from(5_000),
+            hot_public_key: PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+            recovery_public_key: recovery_public_key.clone(),
+            unlock_height: 0,
+            status: VaultStatus::Active,
+            spend_tx_id: None,
+            created_at: Utc::now().naive_utc(),
+        })
+        .unwrap();
+    let err = alice_ts_interface
+        .transaction_service_handle
+        .spend_vault(vault.id, MicroMinotari::from(5))
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        TransactionServiceError::SpendingPolicyError(SpendingPolicyError::ApprovalRequired(_, _))
+    ));
+    let pending = alice_ts_interface
+        .transaction_service_handle
+        .get_pending_spends()
+        .await
+        .unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].kind, SpendKind::Vault);
+    assert_eq!(pending[0].amount, vault.amount);
+
+    // Every decision is logged with the vault spend kind. A new vault is a payment to the recovery key.
+    let decisions = alice_ts_interface
+        .ts_db
+        .fetch_spending_decisions_since(NaiveDateTime::default())
+        .unwrap();
+    assert_eq!(
+        decisions.iter().map(|d| (d.kind, d.decision)).collect::<Vec<_>>(),
+        vec![
+            (SpendKind::Vault, SpendDecision::Denied),
+            (SpendKind::Vault, SpendDecision::Denied),
+            (SpendKind::Vault, SpendDecision::Queued)
+        ]
+    );
+    assert_eq!(
+        decisions[0].destination.as_ref().map(|d| d.public_key().clone()),
+        Some(recovery_public_key)
+    );
+}
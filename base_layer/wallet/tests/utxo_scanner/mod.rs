@@ -23,6 +23,7 @@
 use std::{
     collections::HashMap,
     convert::{TryFrom, TryInto},
+    ops::RangeInclusive,
     sync::Arc,
     time::Duration,
 };
@@ -40,8 +41,9 @@ use minotari_wallet::{
     transaction_service::handle::TransactionServiceRequest,
     util::{wallet_identity::WalletIdentity, watch::Watch},
     utxo_scanner_service::{
-        handle::{UtxoScannerEvent, UtxoScannerHandle},
-        service::{ScannedBlock, UtxoScannerService},
+        error::UtxoScannerError,
+        handle::{RescanRequest, UtxoScannerEvent, UtxoScannerHandle},
+        service::{RecoverySegment, ScannedBlock, UtxoScannerService},
         uxto_scanner_service_builder::UtxoScannerMode,
     },
 };
@@ -106,6 +108,18 @@ async fn setup(
     previous_db: Option<WalletDatabase<WalletSqliteDatabase>>,
     recovery_message: Option<String>,
     one_sided_message: Option<String>,
+) -> UtxoScannerTestInterface {
+    setup_with_recovery_segments(mode, previous_db, recovery_message, one_sided_message, 0).await
+}
+
+/// Sets up the scanner for parallel recovery in segments of `segment_size` blocks when it is not zero. The mock base
+/// node is then listed as two peer seeds so that two segment workers scan from it at the same time.
+async fn setup_with_recovery_segments(
+    mode: UtxoScannerMode,
+    previous_db: Option<WalletDatabase<WalletSqliteDatabase>>,
+    recovery_message: Option<String>,
+    one_sided_message: Option<String>,
+    segment_size: u64,
 ) -> UtxoScannerTestInterface {
     let shutdown = Shutdown::new();
     let factories = CryptoFactories::default();
@@ -176,13 +190,24 @@ async fn setup(
     let recovery_message_watch_receiver = recovery_message_watch.get_receiver();
     let one_sided_message_watch_receiver = one_sided_message_watch.get_receiver();
 
-    let scanner_handle = UtxoScannerHandle::new(event_sender.clone(), one_sided_message_watch, recovery_message_watch);
+    let (rescan_request_sender, rescan_request_receiver) = mpsc::channel(10);
+    let scanner_handle = UtxoScannerHandle::new(
+        event_sender.clone(),
+        one_sided_message_watch,
+        recovery_message_watch,
+        rescan_request_sender,
+    );
 
     let mut scanner_service_builder = UtxoScannerService::<WalletSqliteDatabase, WalletConnectivityMock>::builder();
 
+    let mut peers = vec![server_node_identity.public_key().clone()];
+    if segment_size > 0 {
+        peers.push(server_node_identity.public_key().clone());
+    }
     scanner_service_builder
-        .with_peers(vec![server_node_identity.public_key().clone()])
+        .with_peers(peers)
         .with_retry_limit(1)
+        .with_recovery_segment_size(segment_size)
         .with_mode(mode);
 
     if let Some(message) = one_sided_message {
@@ -206,6 +231,7 @@ async fn setup(
         base_node_service_handle,
         one_sided_message_watch_receiver,
         recovery_message_watch_receiver,
+        rescan_request_receiver,
     );
 
     UtxoScannerTestInterface {
@@ -1063,6 +1089,211 @@ async fn test_utxo_scanner_one_sided_payments() {
     }
 }
 
+/// Adds `num_blocks` blocks with a single output each to the mock base node and makes every output recoverable by the
+/// wallet. Returns the block headers and the value of the output in each block.
+async fn setup_chain_with_recoverable_outputs(
+    test_interface: &UtxoScannerTestInterface,
+    num_blocks: u64,
+) -> (HashMap<u64, BlockHeader>, HashMap<u64, MicroMinotari>) {
+    let cipher_seed = CipherSeed::new();
+    let birthday_epoch_time = get_birthday_from_unix_epoch_in_seconds(cipher_seed.birthday(), 14u16);
+    test_interface.wallet_db.set_master_seed(cipher_seed).unwrap();
+
+    let key_manager = create_test_core_key_manager_with_memory_db();
+    let TestBlockData {
+        block_headers,
+        wallet_outputs,
+        utxos_by_block,
+    } = generate_block_headers_and_utxos(0, num_blocks, birthday_epoch_time, 0, true, &key_manager).await;
+    test_interface.rpc_service_state.set_utxos_by_block(utxos_by_block);
+    test_interface.rpc_service_state.set_blocks(block_headers.clone());
+    test_interface.rpc_service_state.set_tip_info_response(TipInfoResponse {
+        metadata: Some(ChainMetadata {
+            height_of_longest_chain: Some(num_blocks - 1),
+            best_block: Some(block_headers.get(&(num_blocks - 1)).unwrap().hash().to_vec()),
+            accumulated_difficulty: Vec::new(),
+            pruned_height: 0,
+            timestamp: Some(0),
+        }),
+        is_synced: true,
+    });
+
+    let mut db_wallet_outputs = Vec::new();
+    let mut block_values = HashMap::new();
+    for (h, outputs) in wallet_outputs {
+        for output in outputs {
+            let dbo = DbWalletOutput::from_wallet_output(output, &key_manager, None, OutputSource::Unknown, None, None)
+                .await
+                .unwrap();
+            *block_values.entry(h).or_insert_with(|| MicroMinotari::from(0)) += dbo.wallet_output.value;
+            db_wallet_outputs.push(dbo);
+        }
+    }
+    test_interface.oms_mock_state.set_recoverable_outputs(db_wallet_outputs);
+    (block_headers, block_values)
+}
+
+/// Collects the scanner events up to and including the `Completed` event
+async fn wait_for_completed_event(events: &mut broadcast::Receiver<UtxoScannerEvent>) -> Vec<UtxoScannerEvent> {
+    let mut received = Vec::new();
+    let delay = time::sleep(Duration::from_secs(60));
+    tokio::pin!(delay);
+    loop {
+        tokio::select! {
+            _ = &mut delay => {
+                panic!("Completed event should have arrived by now.");
+            }
+            event = events.recv() => {
+                let event = event.unwrap();
+                let is_completed = matches!(event, UtxoScannerEvent::Completed { .. });
+                received.push(event);
+                if is_completed {
+                    return received;
+                }
+            }
+        }
+    }
+}
+
+/// The heights that the `sync_utxos_by_block` calls made to the mock base node started from, in ascending order
+fn sync_start_heights(
+    rpc_service_state: &BaseNodeWalletRpcMockState,
+    block_headers: &HashMap<u64, BlockHeader>,
+) -> Vec<u64> {
+    let mut heights = rpc_service_state
+        .take_sync_utxos_by_block_calls()
+        .into_iter()
+        .map(|(start_hash, _)| {
+            block_headers
+                .values()
+                .find(|header| header.hash() == start_hash)
+                .map(|header| header.height)
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    heights.sort_unstable();
+    heights
+}
+
+fn recovery_segment(
+    block_headers: &HashMap<u64, BlockHeader>,
+    start_height: u64,
+    end_height: u64,
+    next_height: u64,
+    num_outputs: u64,
+    amount: MicroMinotari,
+) -> RecoverySegment {
+    RecoverySegment {
+        start_height,
+        end_height,
+        end_header_hash: block_headers.get(&end_height).unwrap().hash(),
+        next_height,
+        num_outputs,
+        amount,
+    }
+}
+
+fn sum_values(block_values: &HashMap<u64, MicroMinotari>, heights: RangeInclusive<u64>) -> MicroMinotari {
+    heights.map(|h| *block_values.get(&h).unwrap()).sum()
+}
+
+#[tokio::test]
+async fn test_parallel_recovery_resumes_from_checkpointed_segments() {
+    const NUM_BLOCKS: u64 = 12;
+    let mut test_interface = setup_with_recovery_segments(UtxoScannerMode::Recovery, None, None, None, 4).await;
+    let (block_headers, block_values) = setup_chain_with_recoverable_outputs(&test_interface, NUM_BLOCKS).await;
+
+    // An interrupted recovery completed the first segment, checkpointed the second one at height 6 and did not start
+    // the third. The totals of the checkpoints stand in for the outputs that were recovered before the interruption.
+    let checkpointed_value = MicroMinotari::from(1_400);
+    for segment in [
+        recovery_segment(&block_headers, 0, 3, 4, 2, MicroMinotari::from(1_000)),
+        recovery_segment(&block_headers, 4, 7, 6, 1, MicroMinotari::from(400)),
+        recovery_segment(&block_headers, 8, 9, 8, 0, MicroMinotari::from(0)),
+    ] {
+        test_interface.wallet_db.save_recovery_segment(segment).unwrap();
+    }
+
+    let mut scanner_event_stream = test_interface.scanner_handle.get_event_receiver();
+    tokio::spawn(test_interface.scanner_service.take().unwrap().run());
+    let events = wait_for_completed_event(&mut scanner_event_stream).await;
+
+    // Only the unscanned blocks of the segments are synced, followed by the blocks after the last segment
+    assert_eq!(
+        sync_start_heights(&test_interface.rpc_service_state, &block_headers),
+        vec![6, 8, 10]
+    );
+    match events.last().unwrap() {
+        UtxoScannerEvent::Completed {
+            final_height,
+            num_recovered,
+            value_recovered,
+            time_taken: _,
+        } => {
+            assert_eq!(*final_height, NUM_BLOCKS - 1);
+            assert_eq!(*num_recovered, 3 + NUM_BLOCKS - 6);
+            assert_eq!(
+                *value_recovered,
+                checkpointed_value + sum_values(&block_values, 6..=NUM_BLOCKS - 1)
+            );
+        },
+        event => panic!("Unexpected event {:?}", event),
+    }
+    assert!(test_interface.wallet_db.get_recovery_segments().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_parallel_recovery_requeues_a_failed_segment_from_its_checkpoint() {
+    const NUM_BLOCKS: u64 = 12;
+    let mut test_interface = setup_with_recovery_segments(UtxoScannerMode::Recovery, None, None, None, 4).await;
+    let (block_headers, block_values) = setup_chain_with_recoverable_outputs(&test_interface, NUM_BLOCKS).await;
+
+    let checkpointed_value = MicroMinotari::from(250);
+    for segment in [
+        recovery_segment(&block_headers, 0, 3, 4, 1, checkpointed_value),
+        recovery_segment(&block_headers, 4, 7, 4, 0, MicroMinotari::from(0)),
+    ] {
+        test_interface.wallet_db.save_recovery_segment(segment).unwrap();
+    }
+
+    // The first sync of the second segment stops after block 5, so the worker scanning it fails with block 4
+    // checkpointed
+    let (tx, rx) = mpsc::channel(1);
+    test_interface.rpc_service_state.set_utxos_by_block_trigger_channel(rx);
+    tx.send(5).await.unwrap();
+    drop(tx);
+
+    let mut scanner_event_stream = test_interface.scanner_handle.get_event_receiver();
+    tokio::spawn(test_interface.scanner_service.take().unwrap().run());
+    let events = wait_for_completed_event(&mut scanner_event_stream).await;
+
+    // The segment is scanned again from its checkpoint in the next round
+    assert_eq!(
+        sync_start_heights(&test_interface.rpc_service_state, &block_headers),
+        vec![4, 5, 8]
+    );
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, UtxoScannerEvent::ScanningRoundFailed { .. })));
+    match events.last().unwrap() {
+        UtxoScannerEvent::Completed {
+            final_height,
+            num_recovered,
+            value_recovered,
+            time_taken: _,
+        } => {
+            assert_eq!(*final_height, NUM_BLOCKS - 1);
+            // Block 5 was imported by the failed sync as well, but is only counted once
+            assert_eq!(*num_recovered, 1 + NUM_BLOCKS - 4);
+            assert_eq!(
+                *value_recovered,
+                checkpointed_value + sum_values(&block_values, 4..=NUM_BLOCKS - 1)
+            );
+        },
+        event => panic!("Unexpected event {:?}", event),
+    }
+}
+
 #[tokio::test]
 async fn test_birthday_timestamp_over_chain() {
     let test_interface = setup(UtxoScannerMode::Recovery, None, None, None).await;
@@ -1115,3 +1346,33 @@ async fn test_birthday_timestamp_over_chain() {
         birthday_epoch_time >= before_birthday_block_timestamp && birthday_epoch_time <= after_birthday_block_timestamp
     );
 }
+
+#[tokio::test]
+async fn test_rescan_requests_are_queued() {
+    let (event_sender, _) = broadcast::channel(10);
+    let (rescan_request_sender, mut rescan_request_receiver) = mpsc::channel(10);
+    let mut handle = UtxoScannerHandle::new(
+        event_sender,
+        Watch::new("unset".to_string()),
+        Watch::new("unset".to_string()),
+        rescan_request_sender,
+    );
+
+    handle.rescan(Some(10), Some(20)).await.unwrap();
+    handle.rescan(None, None).await.unwrap();
+    assert_eq!(rescan_request_receiver.recv().await.unwrap(), RescanRequest {
+        from_height: Some(10),
+        to_height: Some(20)
+    });
+    assert_eq!(rescan_request_receiver.recv().await.unwrap(), RescanRequest {
+        from_height: None,
+        to_height: None
+    });
+
+    let err = handle.rescan(Some(20), Some(10)).await.unwrap_err();
+    assert!(matches!(err, UtxoScannerError::InvalidRescanRange(_)));
+
+    drop(rescan_request_receiver);
+    let err = handle.rescan(None, None).await.unwrap_err();
+    assert!(matches!(err, UtxoScannerError::ServiceShutdown));
+}
@@ -298,7 +298,8 @@ where TBackend: TransactionBackend + 'static
                                 },
                                 TransactionEvent::TransactionMinedRequestTimedOut(_tx_id) |
                                 TransactionEvent::TransactionImported(_tx_id)|
-                                TransactionEvent::TransactionCompletedImmediately(_tx_id)
+                                TransactionEvent::TransactionCompletedImmediately(_tx_id) |
+                                TransactionEvent::DustConsolidated(_)
                                 => {
                                     self.trigger_balance_refresh().await;
                                 },
@@ -328,6 +329,7 @@ where TBackend: TransactionBackend + 'static
                                 OutputManagerEvent::TxoValidationCommunicationFailure(request_key) => {
                                     self.output_validation_complete_event(request_key,  3);
                                 },
+                                OutputManagerEvent::BalanceSnapshotRecorded(_) => {},
                             }
                         },
                         Err(_e) => error!(target: LOG_TARGET, "Error reading from Output Manager Service event broadcast channel"),
@@ -447,6 +447,7 @@ mod test {
             time_locked_balance: None,
             pending_incoming_balance: inbound_tx.amount,
             pending_outgoing_balance: outbound_tx.amount + outbound_tx.fee,
+            frozen_balance: MicroMinotari::from(0),
         };
         let mut mock_output_manager_service_state = mock_output_manager_service.get_response_state();
         mock_output_manager_service_state.set_balance(balance.clone());
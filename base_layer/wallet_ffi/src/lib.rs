@@ -173,7 +173,7 @@ use crate::{
     callback_handler::CallbackHandler,
     enums::SeedWordPushResult,
     error::{InterfaceError, TransactionError},
-    tasks::recovery_event_monitoring,
+    tasks::{recovery_event_monitoring, rescan_event_monitoring},
 };
 
 mod callback_handler;
@@ -5787,6 +5787,156 @@ pub unsafe extern "C" fn wallet_get_all_utxos(wallet: *mut TariWallet, error_ptr
     }
 }
 
+/// This function freezes the given UTXOs. Frozen UTXOs are never selected as inputs for a transaction, including coin
+/// splits and coin joins, until they are unfrozen.
+///
+/// ## Arguments
+/// * `wallet` - The TariWallet pointer
+/// * `commitments` - A `TariVector` of "strings", tagged as `TariTypeTag::String`, containing commitment's hex values
+///   (see `Commitment::to_hex()`)
+/// * `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null.
+/// Functions as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns true if all the UTXOs were frozen. If any of the commitments is unknown none of the UTXOs are
+/// frozen.
+///
+/// # Safety
+/// `TariVector` must be freed after use with `destroy_tari_vector()`
+#[no_mangle]
+pub unsafe extern "C" fn wallet_freeze_utxos(
+    wallet: *mut TariWallet,
+    commitments: *mut TariVector,
+    error_ptr: *mut i32,
+) -> bool {
+    set_utxos_frozen(wallet, commitments, true, error_ptr)
+}
+
+/// This function unfreezes the given UTXOs so that they can be selected as transaction inputs again.
+///
+/// ## Arguments
+/// * `wallet` - The TariWallet pointer
+/// * `commitments` - A `TariVector` of "strings", tagged as `TariTypeTag::String`, containing commitment's hex values
+///   (see `Commitment::to_hex()`)
+/// * `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null.
+/// Functions as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns true if all the UTXOs were unfrozen. If any of the commitments is unknown none of the UTXOs are
+/// unfrozen.
+///
+/// # Safety
+/// `TariVector` must be freed after use with `destroy_tari_vector()`
+#[no_mangle]
+pub unsafe extern "C" fn wallet_unfreeze_utxos(
+    wallet: *mut TariWallet,
+    commitments: *mut TariVector,
+    error_ptr: *mut i32,
+) -> bool {
+    set_utxos_frozen(wallet, commitments, false, error_ptr)
+}
+
+unsafe fn set_utxos_frozen(
+    wallet: *mut TariWallet,
+    commitments: *mut TariVector,
+    frozen: bool,
+    error_ptr: *mut i32,
+) -> bool {
+    if wallet.is_null() {
+        error!(target: LOG_TARGET, "wallet pointer is null");
+        ptr::replace(
+            error_ptr,
+            LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code as c_int,
+        );
+        return false;
+    }
+
+    let commitments = match commitments.as_ref() {
+        None => {
+            error!(target: LOG_TARGET, "failed to obtain commitments as reference");
+            ptr::replace(
+                error_ptr,
+                LibWalletError::from(InterfaceError::NullError("commitments vector".to_string())).code as c_int,
+            );
+            return false;
+        },
+        Some(cs) => match cs.to_commitment_vec() {
+            Ok(cs) => cs,
+            Err(e) => {
+                error!(target: LOG_TARGET, "failed to convert from tari vector: {:?}", e);
+                ptr::replace(error_ptr, LibWalletError::from(e).code as c_int);
+                return false;
+            },
+        },
+    };
+
+    let mut output_manager_service = (*wallet).wallet.output_manager_service.clone();
+    let result = if frozen {
+        (*wallet)
+            .runtime
+            .block_on(output_manager_service.freeze_outputs(commitments))
+    } else {
+        (*wallet)
+            .runtime
+            .block_on(output_manager_service.unfreeze_outputs(commitments))
+    };
+    match result {
+        Ok(()) => {
+            ptr::replace(error_ptr, 0);
+            true
+        },
+        Err(e) => {
+            error!(target: LOG_TARGET, "failed to set outputs frozen to {}: {:#?}", frozen, e);
+            ptr::replace(error_ptr, LibWalletError::from(WalletError::OutputManagerError(e)).code);
+            false
+        },
+    }
+}
+
+/// This function returns a list of all the UTXOs that are currently frozen, sorted by value from smallest to largest.
+///
+/// ## Arguments
+/// * `wallet` - The TariWallet pointer,
+/// * `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null.
+/// Functions as an out parameter.
+///
+/// ## Returns
+/// `*mut TariVector` - Returns a struct with an array pointer, length and capacity (needed for proper destruction
+/// after use).
+///
+/// # Safety
+/// `destroy_tari_vector()` must be called after use.
+#[no_mangle]
+pub unsafe extern "C" fn wallet_get_frozen_utxos(wallet: *mut TariWallet, error_ptr: *mut i32) -> *mut TariVector {
+    if wallet.is_null() {
+        error!(target: LOG_TARGET, "wallet pointer is null");
+        ptr::replace(
+            error_ptr,
+            LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code,
+        );
+        return ptr::null_mut();
+    }
+
+    match (*wallet).wallet.output_db.fetch_frozen_outputs() {
+        Ok(outputs) => {
+            ptr::replace(error_ptr, 0);
+            Box::into_raw(Box::new(TariVector::from(outputs)))
+        },
+
+        Err(e) => {
+            error!(target: LOG_TARGET, "failed to obtain frozen outputs: {:#?}", e);
+            ptr::replace(
+                error_ptr,
+                LibWalletError::from(WalletError::OutputManagerError(
+                    OutputManagerError::OutputManagerStorageError(e),
+                ))
+                .code,
+            );
+            ptr::null_mut()
+        },
+    }
+}
+
 /// This function will tell the wallet to do a coin split.
 ///
 /// ## Arguments
@@ -6630,6 +6780,7 @@ pub unsafe extern "C" fn wallet_send_transaction(
                     MicroMinotari::from(amount),
                     selection_criteria,
                     OutputFeatures::default(),
+                    TariCovenant::default(),
                     MicroMinotari::from(fee_per_gram),
                     message_string,
                 ),
@@ -8137,6 +8288,79 @@ pub unsafe extern "C" fn wallet_set_one_sided_payment_message(
     true
 }
 
+/// Starts a rescan of a range of blocks for outputs belonging to the wallet, e.g. after adding a known one-sided
+/// payment script. The rescan runs alongside the normal operation of the wallet and outputs that are already known to
+/// the wallet are not imported again.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer.
+/// `from_birthday` - If true the rescan starts at the wallet birthday and `from_height` is ignored
+/// `from_height` - The first block height to scan
+/// `to_height` - The last block height to scan, or 0 to scan up to the current chain tip
+/// `rescan_progress_callback` - The callback function pointer that will be used to asynchronously communicate
+/// progress to the client. The first argument of the callback is an event enum encoded as a u8 as follows:
+/// ```
+/// enum RescanEvent {
+///     Progress,     // 0
+///     Completed,    // 1
+///     RescanFailed, // 2
+/// }
+/// ```
+/// The second and third arguments are u64 values that will contain different information depending on the event
+/// that triggered the callback. The meaning of the second and third argument for each event are as follows:
+///     - Progress, current block, last block of the rescan
+///     - Completed, number of new UTXO's recovered, MicroMinotari recovered
+///     - RescanFailed, 0, 0
+///
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Return a boolean value indicating whether the rescan was queued successfully or not, the rescan will
+/// run asynchronously and communicate its progress via the callback. An error will also produce a false result.
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn wallet_start_rescan(
+    wallet: *mut TariWallet,
+    from_birthday: bool,
+    from_height: c_ulonglong,
+    to_height: c_ulonglong,
+    rescan_progress_callback: unsafe extern "C" fn(u8, u64, u64),
+    error_out: *mut c_int,
+) -> bool {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    let from_height = if from_birthday { None } else { Some(from_height) };
+    let to_height = if to_height == 0 { None } else { Some(to_height) };
+    let mut utxo_scanner_service = (*wallet).wallet.utxo_scanner_service.clone();
+    let event_stream = utxo_scanner_service.get_event_receiver();
+
+    if let Err(e) = (*wallet)
+        .runtime
+        .block_on(utxo_scanner_service.rescan(from_height, to_height))
+    {
+        error = LibWalletError::from(WalletError::from(e)).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    // Spawn a task to monitor the rescan events and call the callback appropriately
+    (*wallet)
+        .runtime
+        .spawn(rescan_event_monitoring(event_stream, rescan_progress_callback));
+
+    true
+}
+
 /// Gets the current emoji set
 ///
 /// ## Arguments
@@ -38,6 +38,13 @@ enum RecoveryEvent {
     RecoveryFailed,             // 6
 }
 
+/// Events that a rescan will report via the callback
+enum RescanEvent {
+    Progress,     // 0
+    Completed,    // 1
+    RescanFailed, // 2
+}
+
 #[allow(clippy::too_many_lines)]
 pub async fn recovery_event_monitoring(
     mut event_stream: broadcast::Receiver<UtxoScannerEvent>,
@@ -144,6 +151,10 @@ pub async fn recovery_event_monitoring(
                 }
                 warn!(target: LOG_TARGET, "UTXO Scanner failed and exited",);
             },
+            // Rescans are requested through the wallet's scanner service handle and are never run by recovery
+            Ok(UtxoScannerEvent::RescanProgress { .. }) |
+            Ok(UtxoScannerEvent::RescanCompleted { .. }) |
+            Ok(UtxoScannerEvent::RescanFailed { .. }) => {},
             Err(broadcast::error::RecvError::Closed) => {
                 break;
             },
@@ -171,3 +182,58 @@ pub async fn recovery_event_monitoring(
         },
     }
 }
+
+pub async fn rescan_event_monitoring(
+    mut event_stream: broadcast::Receiver<UtxoScannerEvent>,
+    rescan_progress_callback: unsafe extern "C" fn(u8, u64, u64),
+) {
+    loop {
+        match event_stream.recv().await {
+            Ok(UtxoScannerEvent::RescanProgress {
+                current_height,
+                to_height,
+            }) => {
+                unsafe {
+                    (rescan_progress_callback)(RescanEvent::Progress as u8, current_height, to_height);
+                }
+                info!(target: LOG_TARGET, "Rescan progress: {}/{}", current_height, to_height);
+            },
+            Ok(UtxoScannerEvent::RescanCompleted {
+                from_height,
+                to_height,
+                num_recovered,
+                value_recovered,
+                time_taken,
+            }) => {
+                info!(
+                    target: LOG_TARGET,
+                    "Rescan of blocks {} to {} complete in {:.2?}, Recovered {} outputs worth {}",
+                    from_height,
+                    to_height,
+                    time_taken,
+                    num_recovered,
+                    value_recovered
+                );
+                unsafe {
+                    (rescan_progress_callback)(RescanEvent::Completed as u8, num_recovered, u64::from(value_recovered));
+                }
+                break;
+            },
+            Ok(UtxoScannerEvent::RescanFailed { error }) => {
+                unsafe {
+                    (rescan_progress_callback)(RescanEvent::RescanFailed as u8, 0u64, 0u64);
+                }
+                warn!(target: LOG_TARGET, "Rescan failed: {}", error);
+                break;
+            },
+            Ok(_) => {},
+            Err(broadcast::error::RecvError::Closed) => {
+                break;
+            },
+            Err(e) => {
+                // Event lagging
+                warn!(target: LOG_TARGET, "{}", e);
+            },
+        }
+    }
+}
@@ -2805,6 +2805,67 @@ struct TariVector *wallet_get_utxos(struct TariWallet *wallet,
 struct TariVector *wallet_get_all_utxos(struct TariWallet *wallet,
                                         int32_t *error_ptr);
 
+/**
+ * This function freezes the given UTXOs. Frozen UTXOs are never selected as inputs for a transaction, including coin
+ * splits and coin joins, until they are unfrozen.
+ *
+ * ## Arguments
+ * * `wallet` - The TariWallet pointer
+ * * `commitments` - A `TariVector` of "strings", tagged as `TariTypeTag::String`, containing commitment's hex values
+ *   (see `Commitment::to_hex()`)
+ * * `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null.
+ * Functions as an out parameter.
+ *
+ * ## Returns
+ * `bool` - Returns true if all the UTXOs were frozen. If any of the commitments is unknown none of the UTXOs are
+ * frozen.
+ *
+ * # Safety
+ * `TariVector` must be freed after use with `destroy_tari_vector()`
+ */
+bool wallet_freeze_utxos(struct TariWallet *wallet,
+                         struct TariVector *commitments,
+                         int32_t *error_ptr);
+
+/**
+ * This function unfreezes the given UTXOs so that they can be selected as transaction inputs again.
+ *
+ * ## Arguments
+ * * `wallet` - The TariWallet pointer
+ * * `commitments` - A `TariVector` of "strings", tagged as `TariTypeTag::String`, containing commitment's hex values
+ *   (see `Commitment::to_hex()`)
+ * * `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null.
+ * Functions as an out parameter.
+ *
+ * ## Returns
+ * `bool` - Returns true if all the UTXOs were unfrozen. If any of the commitments is unknown none of the UTXOs are
+ * unfrozen.
+ *
+ * # Safety
+ * `TariVector` must be freed after use with `destroy_tari_vector()`
+ */
+bool wallet_unfreeze_utxos(struct TariWallet *wallet,
+                           struct TariVector *commitments,
+                           int32_t *error_ptr);
+
+/**
+ * This function returns a list of all the UTXOs that are currently frozen, sorted by value from smallest to largest.
+ *
+ * ## Arguments
+ * * `wallet` - The TariWallet pointer,
+ * * `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null.
+ * Functions as an out parameter.
+ *
+ * ## Returns
+ * `*mut TariVector` - Returns a struct with an array pointer, length and capacity (needed for proper destruction
+ * after use).
+ *
+ * # Safety
+ * `destroy_tari_vector()` must be called after use.
+ */
+struct TariVector *wallet_get_frozen_utxos(struct TariWallet *wallet,
+                                           int32_t *error_ptr);
+
 /**
  * This function will tell the wallet to do a coin split.
  *
@@ -3660,6 +3721,48 @@ bool wallet_set_one_sided_payment_message(struct TariWallet *wallet,
                                           const char *message,
                                           int *error_out);
 
+/**
+ * Starts a rescan of a range of blocks for outputs belonging to the wallet, e.g. after adding a known one-sided
+ * payment script. The rescan runs alongside the normal operation of the wallet and outputs that are already known to
+ * the wallet are not imported again.
+ *
+ * ## Arguments
+ * `wallet` - The TariWallet pointer.
+ * `from_birthday` - If true the rescan starts at the wallet birthday and `from_height` is ignored
+ * `from_height` - The first block height to scan
+ * `to_height` - The last block height to scan, or 0 to scan up to the current chain tip
+ * `rescan_progress_callback` - The callback function pointer that will be used to asynchronously communicate
+ * progress to the client. The first argument of the callback is an event enum encoded as a u8 as follows:
+ * ```
+ * enum RescanEvent {
+ *     Progress,     // 0
+ *     Completed,    // 1
+ *     RescanFailed, // 2
+ * }
+ * ```
+ * The second and third arguments are u64 values that will contain different information depending on the event
+ * that triggered the callback. The meaning of the second and third argument for each event are as follows:
+ *     - Progress, current block, last block of the rescan
+ *     - Completed, number of new UTXO's recovered, MicroMinotari recovered
+ *     - RescanFailed, 0, 0
+ *
+ * `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+ * as an out parameter.
+ *
+ * ## Returns
+ * `bool` - Return a boolean value indicating whether the rescan was queued successfully or not, the rescan will
+ * run asynchronously and communicate its progress via the callback. An error will also produce a false result.
+ *
+ * # Safety
+ * None
+ */
+bool wallet_start_rescan(struct TariWallet *wallet,
+                         bool from_birthday,
+                         unsigned long long from_height,
+                         unsigned long long to_height,
+                         void (*rescan_progress_callback)(uint8_t, uint64_t, uint64_t),
+                         int *error_out);
+
 /**
  * Gets the current emoji set
  *
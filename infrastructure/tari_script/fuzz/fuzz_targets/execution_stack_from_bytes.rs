@@ -0,0 +1,35 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Decodes arbitrary bytes as an execution stack. Any stack that decodes must stay within the stack size limit and
+//! re-encode to canonical bytes that decode to the same stack, through both `from_bytes` and Borsh.
+
+#![no_main]
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use libfuzzer_sys::fuzz_target;
+use tari_script::ExecutionStack;
+
+/// Mirrors `MAX_STACK_SIZE` in `tari_script`. It limits the number of items on a stack, and Borsh also uses it to
+/// limit the length of an encoded stack.
+const MAX_STACK_SIZE: usize = 255;
+
+fuzz_target!(|data: &[u8]| {
+    let stack = match ExecutionStack::from_bytes(data) {
+        Ok(stack) => stack,
+        Err(_) => return,
+    };
+    assert!(stack.size() <= MAX_STACK_SIZE);
+
+    let bytes = stack.to_bytes();
+    assert!(bytes.len() <= data.len());
+    let decoded = ExecutionStack::from_bytes(&bytes).expect("canonical stack bytes must decode");
+    assert_eq!(decoded, stack);
+    assert_eq!(decoded.to_bytes(), bytes);
+
+    let serialized = stack.try_to_vec().expect("stacks always serialize");
+    match ExecutionStack::try_from_slice(&serialized) {
+        Ok(deserialized) => assert_eq!(deserialized, stack),
+        Err(_) => assert!(bytes.len() > MAX_STACK_SIZE),
+    }
+});
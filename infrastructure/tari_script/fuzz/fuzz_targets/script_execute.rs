@@ -0,0 +1,63 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Executes arbitrary scripts against arbitrary input stacks. The input is laid out as
+//!
+//! | bytes | content                                 |
+//! |-------|-----------------------------------------|
+//! | 8     | block height, little endian             |
+//! | 8     | transaction fee, little endian          |
+//! | 2     | length `n` of the script, little endian |
+//! | `n`   | script                                  |
+//! | rest  | input stack                             |
+//!
+//! Execution must not panic, and is checked against the stepper and the static analysis: a traced execution must
+//! end with the same result, and a script that the analysis finds unspendable must never leave a public key.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tari_crypto::ristretto::pedersen::PedersenCommitment;
+use tari_script::{ExecutionStack, ScriptAnalysis, ScriptContext, StackItem, TariScript};
+
+const HEADER_SIZE: usize = 18;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < HEADER_SIZE {
+        return;
+    }
+    let (header, body) = data.split_at(HEADER_SIZE);
+    let height = u64::from_le_bytes(header[0..8].try_into().unwrap());
+    let total_fee = u64::from_le_bytes(header[8..16].try_into().unwrap());
+    let script_len = usize::from(u16::from_le_bytes(header[16..18].try_into().unwrap()));
+    if body.len() < script_len {
+        return;
+    }
+    let (script_bytes, stack_bytes) = body.split_at(script_len);
+    let (script, inputs) = match (
+        TariScript::from_bytes(script_bytes),
+        ExecutionStack::from_bytes(stack_bytes),
+    ) {
+        (Ok(script), Ok(inputs)) => (script, inputs),
+        _ => return,
+    };
+
+    let context = ScriptContext::new(height, &[0u8; 32], &PedersenCommitment::default()).with_transaction_data(
+        Vec::new(),
+        height,
+        total_fee,
+    );
+    let result = script.execute_with_context(&inputs, &context);
+
+    let trace = script.execute_with_trace(&inputs, &context);
+    assert_eq!(trace.result, result);
+
+    if ScriptAnalysis::analyse(&script).is_unspendable() {
+        assert!(
+            !matches!(result, Ok(StackItem::PublicKey(_))),
+            "the analysis found no spendable path, but {} was spent with {}",
+            script,
+            inputs
+        );
+    }
+});
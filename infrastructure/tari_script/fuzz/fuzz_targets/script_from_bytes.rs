@@ -0,0 +1,41 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Decodes arbitrary bytes as a script. Any script that decodes must re-encode to canonical bytes that are no longer
+//! than the input and that decode to the same script, through both `from_bytes` and Borsh. Scripts that can be written
+//! as assembly must compile back to the same script.
+
+#![no_main]
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use libfuzzer_sys::fuzz_target;
+use tari_script::TariScript;
+
+/// The largest script that Borsh will decode, mirrors `MAX_SCRIPT_BYTES` in `tari_script`
+const MAX_SCRIPT_BYTES: usize = 4096;
+
+fuzz_target!(|data: &[u8]| {
+    let script = match TariScript::from_bytes(data) {
+        Ok(script) => script,
+        Err(_) => return,
+    };
+
+    // Varints may be decoded from non-canonical encodings, so the input is only required to be at least as long as
+    // the canonical encoding, which must be a fixed point
+    let bytes = script.to_bytes();
+    assert!(bytes.len() <= data.len());
+    let decoded = TariScript::from_bytes(&bytes).expect("canonical script bytes must decode");
+    assert_eq!(decoded, script);
+    assert_eq!(decoded.to_bytes(), bytes);
+
+    let serialized = script.try_to_vec().expect("scripts always serialize");
+    match TariScript::try_from_slice(&serialized) {
+        Ok(deserialized) => assert_eq!(deserialized, script),
+        Err(_) => assert!(bytes.len() > MAX_SCRIPT_BYTES),
+    }
+
+    // Unbalanced conditionals and oversized scripts are written as assembly that does not compile
+    if let Ok(compiled) = TariScript::from_assembly(&script.to_assembly()) {
+        assert_eq!(compiled, script);
+    }
+});
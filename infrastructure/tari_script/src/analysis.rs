@@ -0,0 +1,828 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Static analysis of Tari scripts.
+//!
+//! The analyser follows every path through a script's `IfThen` branches without knowing the input stack, tracking the
+//! type of each stack item and the value of constant numbers. For each path it works out the input stack the path
+//! needs, the block height it requires, an estimate of its execution cost, and whether it fails for every possible
+//! input. A path is also counted as failing if it leaves something other than a public key on the stack, since a
+//! transaction input cannot use it as the script key.
+//!
+//! Scripts that match one of the templates the wallets create are classified as a [ScriptTemplate].
+
+use std::{cmp::Ordering, collections::HashMap, fmt};
+
+use tari_crypto::ristretto::RistrettoPublicKey;
+
+use crate::{
+    script::{Branch, MAX_MULTISIG_LIMIT},
+    stack::MAX_STACK_SIZE,
+    HashValue,
+    Opcode,
+    ScriptError,
+    StackItem,
+    TariScript,
+};
+
+/// The maximum number of paths that are analysed. Each `IfThen` with an unknown condition doubles the number of paths,
+/// so the analysis of scripts with many branches is cut short.
+pub const MAX_ANALYSED_PATHS: usize = 1024;
+
+/// The type of a stack item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StackItemType {
+    Number,
+    Hash,
+    Scalar,
+    Commitment,
+    PublicKey,
+    Signature,
+}
+
+impl From<&StackItem> for StackItemType {
+    fn from(item: &StackItem) -> Self {
+        match item {
+            StackItem::Number(_) => StackItemType::Number,
+            StackItem::Hash(_) => StackItemType::Hash,
+            StackItem::Scalar(_) => StackItemType::Scalar,
+            StackItem::Commitment(_) => StackItemType::Commitment,
+            StackItem::PublicKey(_) => StackItemType::PublicKey,
+            StackItem::Signature(_) => StackItemType::Signature,
+        }
+    }
+}
+
+impl fmt::Display for StackItemType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// The standard scripts created by Tari wallets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptTemplate {
+    /// `Nop`: the spender provides the script key
+    Nop,
+    /// `PushPubKey(key)`: a one-sided payment
+    OneSided(RistrettoPublicKey),
+    /// `PushPubKey(nonce) Drop PushPubKey(key)`: a one-sided payment to a stealth address
+    Stealth {
+        nonce: RistrettoPublicKey,
+        key: RistrettoPublicKey,
+    },
+    /// `CheckMultiSigVerifyAggregatePubKey(m, n, keys, msg)`: spendable with m of the n keys
+    MultiSig { m: u8, keys: Vec<RistrettoPublicKey> },
+    /// A SHA-256 hash time-locked contract: spendable by the receiver with the preimage of the hash, or by the sender
+    /// from the timeout height
+    Htlc {
+        hash: HashValue,
+        receiver: RistrettoPublicKey,
+        sender: RistrettoPublicKey,
+        timeout: u64,
+    },
+    /// `CheckHeightVerify(height) PushPubKey(key)`: spendable from the given height
+    TimeLock { height: u64, key: RistrettoPublicKey },
+}
+
+impl ScriptTemplate {
+    pub fn classify(script: &TariScript) -> Option<ScriptTemplate> {
+        #[allow(clippy::enum_glob_use)]
+        use Opcode::*;
+        match script.as_slice() {
+            [Nop] => Some(ScriptTemplate::Nop),
+            [PushPubKey(key)] => Some(ScriptTemplate::OneSided(*key.clone())),
+            [PushPubKey(nonce), Drop, PushPubKey(key)] => Some(ScriptTemplate::Stealth {
+                nonce: *nonce.clone(),
+                key: *key.clone(),
+            }),
+            [CheckMultiSigVerifyAggregatePubKey(m, _, keys, _)] => Some(ScriptTemplate::MultiSig {
+                m: *m,
+                keys: keys.clone(),
+            }),
+            [HashSha256, PushHash(hash), Equal, IfThen, PushPubKey(receiver), Else, refund @ ..] => match refund {
+                [CheckHeightVerify(timeout), PushPubKey(sender), EndIf] => Some(ScriptTemplate::Htlc {
+                    hash: **hash,
+                    receiver: *receiver.clone(),
+                    sender: *sender.clone(),
+                    timeout: *timeout,
+                }),
+                _ => None,
+            },
+            [CheckHeightVerify(height), PushPubKey(key)] => Some(ScriptTemplate::TimeLock {
+                height: *height,
+                key: *key.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ScriptTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptTemplate::Nop => write!(f, "Nop"),
+            ScriptTemplate::OneSided(_) => write!(f, "One-sided"),
+            ScriptTemplate::Stealth { .. } => write!(f, "Stealth one-sided"),
+            ScriptTemplate::MultiSig { m, keys } => write!(f, "{}-of-{} multisig", m, keys.len()),
+            ScriptTemplate::Htlc { timeout, .. } => write!(f, "HTLC with timeout at height {}", timeout),
+            ScriptTemplate::TimeLock { height, .. } => write!(f, "Time-locked until height {}", height),
+        }
+    }
+}
+
+/// Why a path fails for every input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathFailure {
+    /// The opcode at this position fails
+    Opcode { index: usize, error: ScriptError },
+    /// The path reaches the end of the script and fails there, e.g. with more than one item on the stack
+    End(ScriptError),
+    /// The path leaves an item that is not a public key on the stack
+    NotAPublicKey(StackItemType),
+}
+
+impl fmt::Display for PathFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathFailure::Opcode { index, error } => write!(f, "Opcode {} fails: {}", index, error),
+            PathFailure::End(error) => write!(f, "Fails at the end of the script: {}", error),
+            PathFailure::NotAPublicKey(t) => write!(f, "Leaves a {} instead of a public key", t),
+        }
+    }
+}
+
+/// The analysis of a single path through the script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathAnalysis {
+    /// The branch taken at each `IfThen` on the path, with the position of the `IfThen`
+    pub branches: Vec<(usize, Branch)>,
+    /// The input stack the path needs, from the top of the stack down. The type is `None` if the path accepts
+    /// more than one type.
+    pub inputs: Vec<Option<StackItemType>>,
+    /// The inputs that are dropped without being checked, which anyone relaying the transaction could change
+    pub malleable_inputs: Vec<usize>,
+    /// The minimum block height required by `CheckHeightVerify` or a constant `CompareHeightVerify`
+    pub min_height: Option<u64>,
+    /// An estimate of the cost of executing the path, see [opcode_cost]
+    pub cost: u64,
+    pub failure: Option<PathFailure>,
+}
+
+impl PathAnalysis {
+    pub fn is_spendable(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// The result of analysing a script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptAnalysis {
+    pub template: Option<ScriptTemplate>,
+    pub paths: Vec<PathAnalysis>,
+    /// The branches that no input can take, with the position of their `IfThen`
+    pub unreachable_branches: Vec<(usize, Branch)>,
+    /// False if the script has more than [MAX_ANALYSED_PATHS] paths and only some of them were analysed
+    pub complete: bool,
+}
+
+impl ScriptAnalysis {
+    pub fn analyse(script: &TariScript) -> ScriptAnalysis {
+        let mut paths = Vec::new();
+        let mut pending = vec![PathState::default()];
+        let mut started = 1;
+        let mut complete = true;
+        while let Some(mut state) = pending.pop() {
+            let forked = state.run(script);
+            for fork in forked {
+                if started < MAX_ANALYSED_PATHS {
+                    started += 1;
+                    pending.push(fork);
+                } else {
+                    complete = false;
+                }
+            }
+            paths.push(state.into_analysis());
+        }
+
+        let mut taken = HashMap::<usize, Vec<Branch>>::new();
+        for (index, branch) in paths.iter().flat_map(|p| p.branches.iter()) {
+            taken.entry(*index).or_default().push(*branch);
+        }
+        let mut unreachable_branches = Vec::new();
+        if complete {
+            for (index, branches) in taken {
+                for branch in [Branch::ExecuteIf, Branch::ExecuteElse] {
+                    if !branches.contains(&branch) {
+                        unreachable_branches.push((index, branch));
+                    }
+                }
+            }
+            unreachable_branches.sort_by_key(|(index, _)| *index);
+        }
+
+        ScriptAnalysis {
+            template: ScriptTemplate::classify(script),
+            paths,
+            unreachable_branches,
+            complete,
+        }
+    }
+
+    pub fn is_standard(&self) -> bool {
+        self.template.is_some()
+    }
+
+    /// True if every path fails, whatever the inputs. Incomplete analyses are never unspendable.
+    pub fn is_unspendable(&self) -> bool {
+        self.complete && self.paths.iter().all(|p| !p.is_spendable())
+    }
+
+    pub fn spendable_paths(&self) -> impl Iterator<Item = &PathAnalysis> {
+        self.paths.iter().filter(|p| p.is_spendable())
+    }
+
+    pub fn failing_paths(&self) -> impl Iterator<Item = &PathAnalysis> {
+        self.paths.iter().filter(|p| !p.is_spendable())
+    }
+
+    /// The cost of the most expensive path
+    pub fn max_cost(&self) -> u64 {
+        self.paths.iter().map(|p| p.cost).max().unwrap_or_default()
+    }
+
+    /// Whether any spendable path drops an input without checking it
+    pub fn is_malleable(&self) -> bool {
+        self.spendable_paths().any(|p| !p.malleable_inputs.is_empty())
+    }
+}
+
+impl TariScript {
+    pub fn analyse(&self) -> ScriptAnalysis {
+        ScriptAnalysis::analyse(self)
+    }
+}
+
+/// An estimate of the cost of executing an opcode, in units of a simple stack operation
+pub fn opcode_cost(opcode: &Opcode) -> u64 {
+    #[allow(clippy::enum_glob_use)]
+    use Opcode::*;
+    match opcode {
+        HashBlake256 | HashSha256 | HashSha3 => 10,
+        ToRistrettoPoint => 50,
+        CheckSig(_) | CheckSigVerify(_) => 100,
+        // Every signature can be checked against every key
+        CheckMultiSig(m, n, ..) | CheckMultiSigVerify(m, n, ..) | CheckMultiSigVerifyAggregatePubKey(m, n, ..) => {
+            100 * u64::from(*m) * u64::from(*n)
+        },
+        _ => 1,
+    }
+}
+
+/// A stack item whose value is not known
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    /// The input at this depth from the top of the input stack
+    Input(usize),
+    Number(i64),
+    /// An item computed by the script, with its type if it is known
+    Item(Option<StackItemType>),
+}
+
+#[derive(Debug, Clone, Default)]
+struct InputState {
+    item_type: Option<StackItemType>,
+    inspected: bool,
+    dropped: bool,
+}
+
+#[derive(Debug, Clone)]
+struct IfFrame {
+    branch: Branch,
+    else_expected: bool,
+}
+
+#[derive(Debug, Clone)]
+struct PathState {
+    position: usize,
+    stack: Vec<Value>,
+    inputs: Vec<InputState>,
+    executing: bool,
+    if_stack: Vec<IfFrame>,
+    branches: Vec<(usize, Branch)>,
+    min_height: Option<u64>,
+    cost: u64,
+    failure: Option<PathFailure>,
+}
+
+impl Default for PathState {
+    fn default() -> Self {
+        Self {
+            position: 0,
+            stack: Vec::new(),
+            inputs: Vec::new(),
+            executing: true,
+            if_stack: Vec::new(),
+            branches: Vec::new(),
+            min_height: None,
+            cost: 0,
+            failure: None,
+        }
+    }
+}
+
+impl PathState {
+    /// Follows the path to the end of the script, returning the paths that fork from it
+    fn run(&mut self, script: &TariScript) -> Vec<PathState> {
+        let mut forks = Vec::new();
+        while let Some(opcode) = script.opcode(self.position) {
+            let index = self.position;
+            self.position += 1;
+            match self.execute(opcode, index) {
+                Ok(Some(fork)) => forks.push(fork),
+                Ok(None) => {},
+                Err(error) => {
+                    self.failure = Some(PathFailure::Opcode { index, error });
+                    return forks;
+                },
+            }
+        }
+        if !self.if_stack.is_empty() {
+            self.failure = Some(PathFailure::End(ScriptError::MissingOpcode));
+            return forks;
+        }
+        match self.stack.len() {
+            0 => {
+                // The script leaves one untouched input on the stack
+                self.inputs.push(InputState {
+                    item_type: Some(StackItemType::PublicKey),
+                    inspected: true,
+                    dropped: false,
+                });
+            },
+            1 => {
+                let result = self.stack[0].clone();
+                if self
+                    .require(&result, &[StackItemType::PublicKey], ScriptError::IncompatibleTypes)
+                    .is_err()
+                {
+                    let item_type = self.item_type(&result).unwrap_or(StackItemType::Number);
+                    self.failure = Some(PathFailure::NotAPublicKey(item_type));
+                }
+            },
+            _ => self.failure = Some(PathFailure::End(ScriptError::NonUnitLengthStack)),
+        }
+        forks
+    }
+
+    fn into_analysis(self) -> PathAnalysis {
+        let malleable_inputs = self
+            .inputs
+            .iter()
+            .enumerate()
+            .filter(|(_, input)| input.dropped && !input.inspected)
+            .map(|(i, _)| i)
+            .collect();
+        PathAnalysis {
+            branches: self.branches,
+            inputs: self.inputs.iter().map(|input| input.item_type).collect(),
+            malleable_inputs,
+            min_height: self.min_height,
+            cost: self.cost,
+            failure: self.failure,
+        }
+    }
+
+    /// Executes the opcode, returning the state that takes the `Else` branch if it is an `IfThen` with an unknown
+    /// condition
+    #[allow(clippy::too_many_lines)]
+    fn execute(&mut self, opcode: &Opcode, index: usize) -> Result<Option<PathState>, ScriptError> {
+        #[allow(clippy::enum_glob_use)]
+        use Opcode::*;
+        use StackItemType as T;
+
+        if !self.executing && !matches!(opcode, IfThen | Else | EndIf) {
+            return Ok(None);
+        }
+        self.cost += opcode_cost(opcode);
+        match opcode {
+            CheckHeightVerify(height) => self.require_height(*height),
+            CheckHeight(height) => {
+                i64::try_from(*height)?;
+                self.push(Value::Item(Some(T::Number)))?;
+            },
+            CompareHeightVerify => {
+                let value = self.pop();
+                self.require(&value, &[T::Number], ScriptError::InvalidInput)?;
+                if let Value::Number(height) = value {
+                    let height = u64::try_from(height).map_err(|_| ScriptError::ValueExceedsBounds)?;
+                    self.require_height(height);
+                }
+            },
+            CompareHeight => {
+                let value = self.pop();
+                self.require(&value, &[T::Number], ScriptError::InvalidInput)?;
+                self.push(Value::Item(Some(T::Number)))?;
+            },
+            CheckKernelExcessVerify(_) | CheckLockHeightVerify(_) | CheckTotalFeeVerify(_) => {},
+            Nop => {},
+            PushZero => self.push(Value::Number(0))?,
+            PushOne => self.push(Value::Number(1))?,
+            PushInt(n) => self.push(Value::Number(*n))?,
+            PushHash(_) => self.push(Value::Item(Some(T::Hash)))?,
+            PushPubKey(_) => self.push(Value::Item(Some(T::PublicKey)))?,
+            Drop => {
+                if let Value::Input(i) = self.pop() {
+                    self.inputs[i].dropped = true;
+                }
+            },
+            Dup => {
+                let value = self.pop();
+                self.push(value.clone())?;
+                self.push(value)?;
+            },
+            RevRot => {
+                let top = self.pop();
+                let second = self.pop();
+                let third = self.pop();
+                self.push(top)?;
+                self.push(third)?;
+                self.push(second)?;
+            },
+            GeZero | GtZero | LeZero | LtZero => {
+                let value = self.pop();
+                self.require(&value, &[T::Number], ScriptError::InvalidInput)?;
+                let result = match value {
+                    Value::Number(n) => {
+                        let valid: &[Ordering] = match opcode {
+                            GeZero => &[Ordering::Greater, Ordering::Equal],
+                            GtZero => &[Ordering::Greater],
+                            LeZero => &[Ordering::Less, Ordering::Equal],
+                            _ => &[Ordering::Less],
+                        };
+                        Value::Number(i64::from(valid.contains(&n.cmp(&0))))
+                    },
+                    _ => Value::Item(Some(T::Number)),
+                };
+                self.push(result)?;
+            },
+            Add | Sub => {
+                let top = self.pop();
+                let two = self.pop();
+                let allowed: &[StackItemType] = if opcode == &Add {
+                    &[T::Number, T::Commitment, T::PublicKey, T::Signature]
+                } else {
+                    &[T::Number, T::Commitment]
+                };
+                let item_type = self.require_same(&[&top, &two], allowed, ScriptError::IncompatibleTypes)?;
+                let result = match (top, two) {
+                    (Value::Number(a), Value::Number(b)) => {
+                        let result = if opcode == &Add {
+                            b.checked_add(a)
+                        } else {
+                            b.checked_sub(a)
+                        };
+                        Value::Number(result.ok_or(ScriptError::ValueExceedsBounds)?)
+                    },
+                    _ => Value::Item(item_type),
+                };
+                self.push(result)?;
+            },
+            Equal | EqualVerify => {
+                let top = self.pop();
+                let two = self.pop();
+                let allowed = [T::Number, T::Hash, T::Commitment, T::PublicKey, T::Signature];
+                self.require_same(&[&top, &two], &allowed, ScriptError::IncompatibleTypes)?;
+                let equal = match (top, two) {
+                    (Value::Number(a), Value::Number(b)) => Some(a == b),
+                    _ => None,
+                };
+                match (opcode, equal) {
+                    (Equal, Some(equal)) => self.push(Value::Number(i64::from(equal)))?,
+                    (Equal, None) => self.push(Value::Item(Some(T::Number)))?,
+                    (_, Some(false)) => return Err(ScriptError::VerifyFailed),
+                    _ => {},
+                }
+            },
+            Or(n) | OrVerify(n) => {
+                let values = (0..=*n).map(|_| self.pop()).collect::<Vec<_>>();
+                let allowed = [T::Number, T::Hash, T::Scalar, T::Commitment, T::PublicKey, T::Signature];
+                self.require_same(&values.iter().collect::<Vec<_>>(), &allowed, ScriptError::InvalidInput)?;
+                if matches!(opcode, Or(_)) {
+                    self.push(Value::Item(Some(T::Number)))?;
+                }
+            },
+            HashBlake256 | HashSha256 | HashSha3 => {
+                let value = self.pop();
+                let allowed = [T::Commitment, T::PublicKey, T::Hash];
+                self.require(&value, &allowed, ScriptError::IncompatibleTypes)?;
+                self.push(Value::Item(Some(T::Hash)))?;
+            },
+            CheckSig(_) | CheckSigVerify(_) => {
+                let public_key = self.pop();
+                let signature = self.pop();
+                self.require(&public_key, &[T::PublicKey], ScriptError::IncompatibleTypes)?;
+                self.require(&signature, &[T::Signature], ScriptError::IncompatibleTypes)?;
+                if matches!(opcode, CheckSig(_)) {
+                    self.push(Value::Item(Some(T::Number)))?;
+                }
+            },
+            CheckMultiSig(m, n, keys, _) |
+            CheckMultiSigVerify(m, n, keys, _) |
+            CheckMultiSigVerifyAggregatePubKey(m, n, keys, _) => {
+                if *m == 0 || *n == 0 || m > n || *n > MAX_MULTISIG_LIMIT || keys.len() != usize::from(*n) {
+                    return Err(ScriptError::ValueExceedsBounds);
+                }
+                for _ in 0..*m {
+                    let signature = self.pop();
+                    self.require(&signature, &[T::Signature], ScriptError::IncompatibleTypes)?;
+                }
+                match opcode {
+                    CheckMultiSig(..) => self.push(Value::Item(Some(T::Number)))?,
+                    CheckMultiSigVerifyAggregatePubKey(..) => self.push(Value::Item(Some(T::PublicKey)))?,
+                    _ => {},
+                }
+            },
+            ToRistrettoPoint => {
+                let value = self.pop();
+                self.require(&value, &[T::Hash, T::Scalar], ScriptError::IncompatibleTypes)?;
+                self.push(Value::Item(Some(T::PublicKey)))?;
+            },
+            Return => return Err(ScriptError::Return),
+            IfThen => {
+                if !self.executing {
+                    self.if_stack.push(IfFrame {
+                        branch: Branch::NotExecuted,
+                        else_expected: true,
+                    });
+                    return Ok(None);
+                }
+                let condition = self.pop();
+                self.require(&condition, &[T::Number], ScriptError::InvalidInput)?;
+                match condition {
+                    Value::Number(1) => self.take_branch(index, Branch::ExecuteIf),
+                    Value::Number(0) => self.take_branch(index, Branch::ExecuteElse),
+                    Value::Number(_) => return Err(ScriptError::InvalidInput),
+                    _ => {
+                        let mut fork = self.clone();
+                        fork.take_branch(index, Branch::ExecuteElse);
+                        self.take_branch(index, Branch::ExecuteIf);
+                        return Ok(Some(fork));
+                    },
+                }
+            },
+            Else => {
+                let frame = self.if_stack.last_mut().ok_or(ScriptError::InvalidOpcode)?;
+                if !frame.else_expected {
+                    return Err(ScriptError::InvalidOpcode);
+                }
+                frame.else_expected = false;
+                self.executing = frame.branch == Branch::ExecuteElse;
+            },
+            EndIf => {
+                let frame = self.if_stack.pop().ok_or(ScriptError::InvalidOpcode)?;
+                if frame.else_expected {
+                    return Err(ScriptError::MissingOpcode);
+                }
+                self.executing = frame.branch != Branch::NotExecuted;
+            },
+        }
+        Ok(None)
+    }
+
+    fn take_branch(&mut self, index: usize, branch: Branch) {
+        self.executing = branch == Branch::ExecuteIf;
+        self.if_stack.push(IfFrame {
+            branch,
+            else_expected: true,
+        });
+        self.branches.push((index, branch));
+    }
+
+    fn require_height(&mut self, height: u64) {
+        self.min_height = Some(self.min_height.map_or(height, |h| h.max(height)));
+    }
+
+    /// Pops a value, taking the next input if the script has consumed everything it pushed
+    fn pop(&mut self) -> Value {
+        match self.stack.pop() {
+            Some(value) => value,
+            None => {
+                self.inputs.push(InputState::default());
+                Value::Input(self.inputs.len() - 1)
+            },
+        }
+    }
+
+    fn push(&mut self, value: Value) -> Result<(), ScriptError> {
+        if self.stack.len() >= MAX_STACK_SIZE {
+            return Err(ScriptError::StackOverflow);
+        }
+        self.stack.push(value);
+        Ok(())
+    }
+
+    fn item_type(&self, value: &Value) -> Option<StackItemType> {
+        match value {
+            Value::Input(i) => self.inputs[*i].item_type,
+            Value::Number(_) => Some(StackItemType::Number),
+            Value::Item(item_type) => *item_type,
+        }
+    }
+
+    /// Checks that the value can be one of the allowed types, fixing the type of an input if only one is allowed
+    fn require(
+        &mut self,
+        value: &Value,
+        allowed: &[StackItemType],
+        error: ScriptError,
+    ) -> Result<Option<StackItemType>, ScriptError> {
+        if let Value::Input(i) = value {
+            self.inputs[*i].inspected = true;
+        }
+        match (self.item_type(value), value) {
+            (Some(item_type), _) if allowed.contains(&item_type) => Ok(Some(item_type)),
+            (Some(_), _) => Err(error),
+            (None, Value::Input(i)) if allowed.len() == 1 => {
+                self.inputs[*i].item_type = Some(allowed[0]);
+                Ok(Some(allowed[0]))
+            },
+            (None, _) => Ok(None),
+        }
+    }
+
+    /// Checks that the values can all be the same one of the allowed types
+    fn require_same(
+        &mut self,
+        values: &[&Value],
+        allowed: &[StackItemType],
+        error: ScriptError,
+    ) -> Result<Option<StackItemType>, ScriptError> {
+        let mut item_type = None;
+        for value in values {
+            match (self.require(value, allowed, error.clone())?, item_type) {
+                (Some(a), Some(b)) if a != b => return Err(error),
+                (Some(a), _) => item_type = Some(a),
+                _ => {},
+            }
+        }
+        if let Some(item_type) = item_type {
+            for value in values {
+                self.require(value, &[item_type], error.clone())?;
+            }
+        }
+        Ok(item_type)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tari_crypto::{
+        keys::{PublicKey, SecretKey},
+        ristretto::RistrettoSecretKey,
+    };
+
+    use super::*;
+    use crate::{
+        script,
+        Branch::{ExecuteElse, ExecuteIf},
+    };
+
+    fn public_key() -> RistrettoPublicKey {
+        RistrettoPublicKey::from_secret_key(&RistrettoSecretKey::random(&mut rand::thread_rng()))
+    }
+
+    #[test]
+    fn it_classifies_standard_scripts() {
+        let key = public_key();
+        let nonce = public_key();
+        assert_eq!(script!(Nop).analyse().template, Some(ScriptTemplate::Nop));
+        assert_eq!(
+            script!(PushPubKey(Box::new(key.clone()))).analyse().template,
+            Some(ScriptTemplate::OneSided(key.clone()))
+        );
+        assert_eq!(
+            crate::stealth_payment_script(&nonce, &key).analyse().template,
+            Some(ScriptTemplate::Stealth {
+                nonce,
+                key: key.clone()
+            })
+        );
+        let htlc = script!(
+            HashSha256 PushHash(Box::new([1u8; 32])) Equal IfThen
+                PushPubKey(Box::new(key.clone()))
+            Else
+                CheckHeightVerify(100) PushPubKey(Box::new(key.clone()))
+            EndIf
+        );
+        let analysis = htlc.analyse();
+        assert!(matches!(
+            analysis.template,
+            Some(ScriptTemplate::Htlc { timeout: 100, .. })
+        ));
+        assert_eq!(analysis.spendable_paths().count(), 2);
+        let time_lock = script!(CheckHeightVerify(50) PushPubKey(Box::new(key)));
+        assert_eq!(time_lock.analyse().paths[0].min_height, Some(50));
+        assert!(!script!(Nop Nop).analyse().is_standard());
+    }
+
+    #[test]
+    fn it_computes_the_input_shape() {
+        let analysis = script!(Nop).analyse();
+        assert_eq!(analysis.paths.len(), 1);
+        assert_eq!(analysis.paths[0].inputs, vec![Some(StackItemType::PublicKey)]);
+
+        // A signature and a public key, then a number that selects the branch
+        let script = script!(
+            IfThen
+                CheckSigVerify(Box::new([0u8; 32])) PushPubKey(Box::new(public_key()))
+            Else
+                Drop Drop PushPubKey(Box::new(public_key()))
+            EndIf
+        );
+        let analysis = script.analyse();
+        assert_eq!(analysis.paths.len(), 2);
+        let if_path = analysis
+            .paths
+            .iter()
+            .find(|p| p.branches == vec![(0, ExecuteIf)])
+            .unwrap();
+        assert_eq!(if_path.inputs, vec![
+            Some(StackItemType::Number),
+            Some(StackItemType::PublicKey),
+            Some(StackItemType::Signature)
+        ]);
+        assert!(if_path.malleable_inputs.is_empty());
+        assert_eq!(if_path.cost, 1 + 100 + 1 + 1 + 1);
+        let else_path = analysis
+            .paths
+            .iter()
+            .find(|p| p.branches == vec![(0, ExecuteElse)])
+            .unwrap();
+        assert_eq!(else_path.inputs, vec![Some(StackItemType::Number), None, None]);
+        assert_eq!(else_path.malleable_inputs, vec![1, 2]);
+        assert!(analysis.is_malleable());
+        assert!(analysis.unreachable_branches.is_empty());
+        assert!(!analysis.is_unspendable());
+    }
+
+    #[test]
+    fn it_detects_unreachable_branches_and_failing_paths() {
+        let key = Box::new(public_key());
+        let script = script!(PushOne IfThen PushPubKey(key.clone()) Else Return EndIf);
+        let analysis = script.analyse();
+        assert_eq!(analysis.paths.len(), 1);
+        assert_eq!(analysis.unreachable_branches, vec![(1, ExecuteElse)]);
+        assert!(!analysis.is_unspendable());
+
+        // The if branch leaves both the duplicated number and the key on the stack
+        let script = script!(Dup IfThen PushPubKey(key.clone()) Else Return EndIf);
+        let analysis = script.analyse();
+        assert!(analysis.is_unspendable());
+        let failures = analysis.failing_paths().map(|p| p.failure.clone()).collect::<Vec<_>>();
+        assert_eq!(failures, vec![
+            Some(PathFailure::End(ScriptError::NonUnitLengthStack)),
+            Some(PathFailure::Opcode {
+                index: 4,
+                error: ScriptError::Return
+            }),
+        ]);
+
+        let unspendable = [
+            script!(PushInt(2) IfThen PushPubKey(key.clone()) Else PushPubKey(key.clone()) EndIf),
+            script!(PushPubKey(key.clone()) PushInt(1) Add),
+            script!(PushInt(1) PushInt(2) EqualVerify PushPubKey(key.clone())),
+            script!(PushPubKey(key.clone()) PushPubKey(key.clone())),
+            script!(PushInt(1)),
+            script!(PushOne IfThen PushPubKey(key.clone()) EndIf),
+            script!(PushPubKey(key.clone()) Else),
+            script!(PushHash(Box::new([0u8; 32])) Dup Add),
+            script!(CheckMultiSig(
+                3,
+                2,
+                vec![public_key(), public_key()],
+                Box::new([0u8; 32])
+            )),
+        ];
+        for script in unspendable {
+            assert!(script.analyse().is_unspendable(), "{}", script);
+        }
+        let analysis = script!(PushInt(1)).analyse();
+        assert_eq!(
+            analysis.paths[0].failure,
+            Some(PathFailure::NotAPublicKey(StackItemType::Number))
+        );
+    }
+
+    #[test]
+    fn it_limits_the_number_of_paths() {
+        let mut opcodes = Vec::new();
+        for _ in 0..12 {
+            opcodes.extend([Opcode::IfThen, Opcode::Nop, Opcode::Else, Opcode::Nop, Opcode::EndIf]);
+        }
+        let analysis = TariScript::new(opcodes).analyse();
+        assert!(!analysis.complete);
+        assert_eq!(analysis.paths.len(), MAX_ANALYSED_PATHS);
+        assert!(!analysis.is_unspendable());
+    }
+}
@@ -0,0 +1,668 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! A textual assembly language for Tari scripts.
+//!
+//! A script is a sequence of opcodes separated by whitespace, written with the same names and arguments as the
+//! `Display` output of [`Opcode`], so `script.to_string()` is itself valid assembly:
+//!
+//! ```text
+//! # Anyone holding the preimage can spend before height 5000, the sender afterwards
+//! HashSha256
+//! PushHash(0x6c1c4f38b3a3c1d5dd9ff4e3dfb20f9b8f1e8d4fdd0c9e5b4a1c2d3e4f5a6b7c)
+//! Equal
+//! IfThen @preimage
+//!     PushPubKey(b58:4Jt3HLxdxS8VSqECgCaYcMNaHNCuAJnbfTmmS5bXkGuQ)
+//! Else @preimage
+//!     CheckHeightVerify(5000)
+//!     PushPubKey(0x3a9b...)
+//! EndIf @preimage
+//! ```
+//!
+//! - Comments start with `#` or `//` and run to the end of the line.
+//! - Integers are decimal or `0x`-prefixed hexadecimal, with an optional `-` for `PushInt`.
+//! - Hashes, messages, public keys and commitments are 32 bytes, written as 64 hex digits with an optional `0x` prefix,
+//!   or as base58 with a `b58:` prefix.
+//! - `CheckMultiSig`, `CheckMultiSigVerify` and `CheckMultiSigVerifyAggregatePubKey` take `(m, n, [key, ...], msg)`,
+//!   with `1 <= m <= n <= 32`, and the number of keys must be `n`.
+//! - `IfThen`, `Else` and `EndIf` can be followed by a label such as `@claim`. A labelled `Else` or `EndIf` must close
+//!   the innermost open `IfThen` with the same label, which catches misplaced branches in long scripts. Labels are not
+//!   part of the compiled script.
+//!
+//! Every `IfThen` must have exactly one `Else` and be closed by an `EndIf`; scripts that do not are rejected, as they
+//! can never be executed successfully, as are scripts longer than the 4096 bytes a transaction output can hold. Parse
+//! errors carry the line and column of the offending token.
+
+use std::{fmt::Write, str::FromStr};
+
+use tari_crypto::ristretto::{pedersen::PedersenCommitment, RistrettoPublicKey};
+use tari_utilities::{hex::from_hex, ByteArray};
+
+use crate::{
+    error::ScriptParseError,
+    op_codes::Message,
+    script::{MAX_MULTISIG_LIMIT, MAX_SCRIPT_BYTES},
+    HashValue,
+    Opcode,
+    TariScript,
+};
+
+const INDENT: &str = "    ";
+
+impl TariScript {
+    /// Compiles a script from its assembly text
+    pub fn from_assembly(text: &str) -> Result<TariScript, ScriptParseError> {
+        let tokens = tokenize(text)?;
+        Parser::new(tokens).parse()
+    }
+
+    /// Disassembles the script into assembly text with one opcode per line, indented by branch depth and with
+    /// labelled branches. Scripts with unbalanced branches are written without indentation or labels.
+    pub fn to_assembly(&self) -> String {
+        let balanced = is_balanced(self.as_slice());
+        let mut text = String::new();
+        let mut labels = Vec::new();
+        let mut next_label = 1;
+        for opcode in self.as_slice() {
+            if balanced && matches!(opcode, Opcode::Else | Opcode::EndIf) {
+                let depth = labels.len() - 1;
+                let label = if matches!(opcode, Opcode::EndIf) {
+                    labels.pop().unwrap_or_default()
+                } else {
+                    labels.last().copied().unwrap_or_default()
+                };
+                let _ = writeln!(text, "{}{} @if{}", INDENT.repeat(depth), opcode, label);
+                continue;
+            }
+            let depth = if balanced { labels.len() } else { 0 };
+            if balanced && matches!(opcode, Opcode::IfThen) {
+                let _ = writeln!(text, "{}{} @if{}", INDENT.repeat(depth), opcode, next_label);
+                labels.push(next_label);
+                next_label += 1;
+                continue;
+            }
+            let _ = writeln!(text, "{}{}", INDENT.repeat(depth), opcode);
+        }
+        text
+    }
+}
+
+impl FromStr for TariScript {
+    type Err = ScriptParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TariScript::from_assembly(s)
+    }
+}
+
+fn is_balanced(opcodes: &[Opcode]) -> bool {
+    // Whether an `Else` has been seen, for each open `IfThen`
+    let mut open = Vec::new();
+    for opcode in opcodes {
+        match opcode {
+            Opcode::IfThen => open.push(false),
+            Opcode::Else => match open.last_mut() {
+                Some(seen_else) if !*seen_else => *seen_else = true,
+                _ => return false,
+            },
+            Opcode::EndIf => {
+                if open.pop() != Some(true) {
+                    return false;
+                }
+            },
+            _ => {},
+        }
+    }
+    open.is_empty()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TokenKind {
+    Word(String),
+    Label(String),
+    OpenParen,
+    CloseParen,
+    OpenBracket,
+    CloseBracket,
+    Comma,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    line: usize,
+    column: usize,
+}
+
+impl Token {
+    fn error<T: Into<String>>(&self, message: T) -> ScriptParseError {
+        ScriptParseError::new(self.line, self.column, message)
+    }
+
+    /// The number of characters the token spans
+    fn len(&self) -> usize {
+        match &self.kind {
+            TokenKind::Word(word) => word.chars().count(),
+            TokenKind::Label(label) => label.chars().count() + 1,
+            _ => 1,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match &self.kind {
+            TokenKind::Word(word) => format!("`{}`", word),
+            TokenKind::Label(label) => format!("label `@{}`", label),
+            TokenKind::OpenParen => "`(`".to_string(),
+            TokenKind::CloseParen => "`)`".to_string(),
+            TokenKind::OpenBracket => "`[`".to_string(),
+            TokenKind::CloseBracket => "`]`".to_string(),
+            TokenKind::Comma => "`,`".to_string(),
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !matches!(c, '(' | ')' | '[' | ']' | ',' | '#' | '@')
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, ScriptParseError> {
+    let mut tokens = Vec::new();
+    for (line_index, line_text) in text.lines().enumerate() {
+        let chars = line_text.chars().collect::<Vec<_>>();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let line = line_index + 1;
+            let column = i + 1;
+            if c.is_whitespace() {
+                i += 1;
+                continue;
+            }
+            if c == '#' || (c == '/' && chars.get(i + 1) == Some(&'/')) {
+                break;
+            }
+            let kind = match c {
+                '(' => Some(TokenKind::OpenParen),
+                ')' => Some(TokenKind::CloseParen),
+                '[' => Some(TokenKind::OpenBracket),
+                ']' => Some(TokenKind::CloseBracket),
+                ',' => Some(TokenKind::Comma),
+                _ => None,
+            };
+            if let Some(kind) = kind {
+                tokens.push(Token { kind, line, column });
+                i += 1;
+                continue;
+            }
+            let start = if c == '@' { i + 1 } else { i };
+            let mut end = start;
+            while end < chars.len() &&
+                is_word_char(chars[end]) &&
+                !(chars[end] == '/' && chars.get(end + 1) == Some(&'/'))
+            {
+                end += 1;
+            }
+            let word = chars[start..end].iter().collect::<String>();
+            if c == '@' {
+                if word.is_empty() || !word.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                    return Err(ScriptParseError::new(
+                        line,
+                        column,
+                        "Labels must be `@` followed by letters, digits, `_` or `-`",
+                    ));
+                }
+                tokens.push(Token {
+                    kind: TokenKind::Label(word),
+                    line,
+                    column,
+                });
+            } else {
+                tokens.push(Token {
+                    kind: TokenKind::Word(word),
+                    line,
+                    column,
+                });
+            }
+            i = end;
+        }
+    }
+    Ok(tokens)
+}
+
+/// An open `IfThen` block
+struct OpenBranch {
+    label: Option<String>,
+    token: Token,
+    seen_else: bool,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    /// The location reported for errors at the end of the input
+    end: (usize, usize),
+}
+
+impl Parser {
+    fn new(tokens: Vec<Token>) -> Self {
+        let end = tokens.last().map(|t| (t.line, t.column + t.len())).unwrap_or((1, 1));
+        Self {
+            tokens,
+            position: 0,
+            end,
+        }
+    }
+
+    fn parse(mut self) -> Result<TariScript, ScriptParseError> {
+        let mut opcodes = Vec::new();
+        let mut bytes = Vec::new();
+        let mut open = Vec::<OpenBranch>::new();
+        while let Some(token) = self.next() {
+            let name = match &token.kind {
+                TokenKind::Word(name) => name.clone(),
+                TokenKind::Label(_) => return Err(token.error("Labels can only follow IfThen, Else or EndIf")),
+                _ => return Err(token.error(format!("Expected an opcode, found {}", token.describe()))),
+            };
+            let opcode = self.opcode(&name, &token)?;
+            let label = self.label();
+            match opcode {
+                Opcode::IfThen => open.push(OpenBranch {
+                    label,
+                    token: token.clone(),
+                    seen_else: false,
+                }),
+                Opcode::Else | Opcode::EndIf => {
+                    let branch = open
+                        .last_mut()
+                        .ok_or_else(|| token.error(format!("{} without a matching IfThen", name)))?;
+                    if let Some(label) = &label {
+                        if branch.label.as_ref() != Some(label) {
+                            return Err(token.error(format!(
+                                "{} @{} does not match the open IfThen{}",
+                                name,
+                                label,
+                                branch.label.as_ref().map(|l| format!(" @{}", l)).unwrap_or_default()
+                            )));
+                        }
+                    }
+                    if opcode == Opcode::Else {
+                        if branch.seen_else {
+                            return Err(token.error("IfThen already has an Else branch"));
+                        }
+                        branch.seen_else = true;
+                    } else {
+                        if !branch.seen_else {
+                            return Err(token.error("IfThen has no Else branch"));
+                        }
+                        open.pop();
+                    }
+                },
+                _ => {
+                    if label.is_some() {
+                        return Err(
+                            self.tokens[self.position - 1].error("Labels can only follow IfThen, Else or EndIf")
+                        );
+                    }
+                },
+            }
+            opcode.to_bytes(&mut bytes);
+            if bytes.len() > MAX_SCRIPT_BYTES {
+                return Err(token.error(format!(
+                    "The script is longer than the maximum of {} bytes",
+                    MAX_SCRIPT_BYTES
+                )));
+            }
+            opcodes.push(opcode);
+        }
+        if let Some(branch) = open.last() {
+            return Err(branch.token.error("IfThen is never closed by an EndIf"));
+        }
+        Ok(TariScript::new(opcodes))
+    }
+
+    fn opcode(&mut self, name: &str, token: &Token) -> Result<Opcode, ScriptParseError> {
+        #[allow(clippy::enum_glob_use)]
+        use Opcode::*;
+        let opcode = match name {
+            "CheckHeightVerify" => CheckHeightVerify(self.single(|p| p.integer())?),
+            "CheckHeight" => CheckHeight(self.single(|p| p.integer())?),
+            "CheckKernelExcessVerify" => CheckKernelExcessVerify(Box::new(self.single(|p| p.commitment())?)),
+            "CheckLockHeightVerify" => CheckLockHeightVerify(self.single(|p| p.integer())?),
+            "CheckTotalFeeVerify" => CheckTotalFeeVerify(self.single(|p| p.integer())?),
+            "PushHash" => PushHash(Box::new(self.single(|p| p.bytes32())?)),
+            "PushInt" => PushInt(self.single(|p| p.integer())?),
+            "PushPubKey" => PushPubKey(Box::new(self.single(|p| p.public_key())?)),
+            "Or" => Or(self.single(|p| p.integer())?),
+            "OrVerify" => OrVerify(self.single(|p| p.integer())?),
+            "CheckSig" => CheckSig(Box::new(self.single(|p| p.bytes32())?)),
+            "CheckSigVerify" => CheckSigVerify(Box::new(self.single(|p| p.bytes32())?)),
+            "CheckMultiSig" => {
+                let (m, n, keys, msg) = self.multisig()?;
+                CheckMultiSig(m, n, keys, msg)
+            },
+            "CheckMultiSigVerify" => {
+                let (m, n, keys, msg) = self.multisig()?;
+                CheckMultiSigVerify(m, n, keys, msg)
+            },
+            "CheckMultiSigVerifyAggregatePubKey" => {
+                let (m, n, keys, msg) = self.multisig()?;
+                CheckMultiSigVerifyAggregatePubKey(m, n, keys, msg)
+            },
+            _ => {
+                let opcode = match name {
+                    "CompareHeightVerify" => CompareHeightVerify,
+                    "CompareHeight" => CompareHeight,
+                    "Nop" => Nop,
+                    "PushZero" => PushZero,
+                    "PushOne" => PushOne,
+                    "Drop" => Drop,
+                    "Dup" => Dup,
+                    "RevRot" => RevRot,
+                    "GeZero" => GeZero,
+                    "GtZero" => GtZero,
+                    "LeZero" => LeZero,
+                    "LtZero" => LtZero,
+                    "Add" => Add,
+                    "Sub" => Sub,
+                    "Equal" => Equal,
+                    "EqualVerify" => EqualVerify,
+                    "HashBlake256" => HashBlake256,
+                    "HashSha256" => HashSha256,
+                    "HashSha3" => HashSha3,
+                    "ToRistrettoPoint" => ToRistrettoPoint,
+                    "Return" => Return,
+                    "IfThen" => IfThen,
+                    "Else" => Else,
+                    "EndIf" => EndIf,
+                    _ => return Err(token.error(format!("Unknown opcode `{}`", name))),
+                };
+                if let Some(paren) = self.peek().filter(|t| t.kind == TokenKind::OpenParen) {
+                    return Err(paren.error(format!("{} takes no arguments", name)));
+                }
+                opcode
+            },
+        };
+        Ok(opcode)
+    }
+
+    /// Parses `(argument)`
+    fn single<T, F>(&mut self, argument: F) -> Result<T, ScriptParseError>
+    where F: FnOnce(&mut Self) -> Result<T, ScriptParseError> {
+        self.expect(&TokenKind::OpenParen)?;
+        let value = argument(self)?;
+        self.expect(&TokenKind::CloseParen)?;
+        Ok(value)
+    }
+
+    /// Parses `(m, n, [key, ...], msg)`
+    #[allow(clippy::type_complexity)]
+    fn multisig(&mut self) -> Result<(u8, u8, Vec<RistrettoPublicKey>, Box<Message>), ScriptParseError> {
+        self.expect(&TokenKind::OpenParen)?;
+        let m_token = self.peek_or_end()?;
+        let m: u8 = self.integer()?;
+        self.expect(&TokenKind::Comma)?;
+        let n_token = self.peek_or_end()?;
+        let n: u8 = self.integer()?;
+        if n == 0 || n > MAX_MULTISIG_LIMIT {
+            return Err(n_token.error(format!("n must be between 1 and {}", MAX_MULTISIG_LIMIT)));
+        }
+        if m == 0 || m > n {
+            return Err(m_token.error(format!("m must be between 1 and n ({})", n)));
+        }
+        self.expect(&TokenKind::Comma)?;
+        self.expect(&TokenKind::OpenBracket)?;
+        let mut keys = Vec::new();
+        if self.peek().map(|t| &t.kind) == Some(&TokenKind::CloseBracket) {
+            self.next();
+        } else {
+            loop {
+                keys.push(self.public_key()?);
+                let token = self.next_or_end()?;
+                match token.kind {
+                    TokenKind::Comma => {},
+                    TokenKind::CloseBracket => break,
+                    _ => return Err(token.error(format!("Expected `,` or `]`, found {}", token.describe()))),
+                }
+            }
+        }
+        if keys.len() != usize::from(n) {
+            return Err(n_token.error(format!("Expected {} public keys but {} are listed", n, keys.len())));
+        }
+        self.expect(&TokenKind::Comma)?;
+        let msg = self.bytes32()?;
+        self.expect(&TokenKind::CloseParen)?;
+        Ok((m, n, keys, Box::new(msg)))
+    }
+
+    fn integer<T: TryFrom<i128>>(&mut self) -> Result<T, ScriptParseError> {
+        let token = self.word()?;
+        let word = match &token.kind {
+            TokenKind::Word(word) => word.as_str(),
+            _ => unreachable!("word() only returns words"),
+        };
+        let (negative, digits) = match word.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, word),
+        };
+        if digits.starts_with(|c| c == '+' || c == '-') {
+            return Err(token.error(format!("`{}` is not an integer", word)));
+        }
+        let value = match digits.strip_prefix("0x") {
+            Some(hex) => i128::from_str_radix(hex, 16),
+            None => digits.parse::<i128>(),
+        }
+        .map_err(|_| token.error(format!("`{}` is not an integer", word)))?;
+        let value = if negative { -value } else { value };
+        T::try_from(value).map_err(|_| token.error(format!("`{}` is out of range", word)))
+    }
+
+    fn bytes32(&mut self) -> Result<HashValue, ScriptParseError> {
+        let token = self.word()?;
+        let word = match &token.kind {
+            TokenKind::Word(word) => word.as_str(),
+            _ => unreachable!("word() only returns words"),
+        };
+        let bytes = match word.strip_prefix("b58:") {
+            Some(base58) => bs58::decode(base58)
+                .into_vec()
+                .map_err(|e| token.error(format!("Invalid base58: {}", e)))?,
+            None => from_hex(word.strip_prefix("0x").unwrap_or(word))
+                .map_err(|_| token.error(format!("`{}` is not valid hex", word)))?,
+        };
+        HashValue::try_from(bytes.as_slice())
+            .map_err(|_| token.error(format!("Expected 32 bytes but `{}` is {} bytes", word, bytes.len())))
+    }
+
+    fn public_key(&mut self) -> Result<RistrettoPublicKey, ScriptParseError> {
+        let token = self.peek_or_end()?;
+        let bytes = self.bytes32()?;
+        RistrettoPublicKey::from_bytes(&bytes).map_err(|_| token.error("Not a valid public key"))
+    }
+
+    fn commitment(&mut self) -> Result<PedersenCommitment, ScriptParseError> {
+        let token = self.peek_or_end()?;
+        let bytes = self.bytes32()?;
+        PedersenCommitment::from_bytes(&bytes).map_err(|_| token.error("Not a valid commitment"))
+    }
+
+    fn label(&mut self) -> Option<String> {
+        match self.peek().map(|t| t.kind.clone()) {
+            Some(TokenKind::Label(label)) => {
+                self.next();
+                Some(label)
+            },
+            _ => None,
+        }
+    }
+
+    fn word(&mut self) -> Result<Token, ScriptParseError> {
+        let token = self.next_or_end()?;
+        match token.kind {
+            TokenKind::Word(_) => Ok(token),
+            _ => Err(token.error(format!("Expected a value, found {}", token.describe()))),
+        }
+    }
+
+    fn expect(&mut self, kind: &TokenKind) -> Result<(), ScriptParseError> {
+        let token = self.next_or_end()?;
+        if &token.kind == kind {
+            Ok(())
+        } else {
+            let expected = Token {
+                kind: kind.clone(),
+                line: 0,
+                column: 0,
+            };
+            Err(token.error(format!("Expected {}, found {}", expected.describe(), token.describe())))
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn peek_or_end(&self) -> Result<Token, ScriptParseError> {
+        self.peek().cloned().ok_or_else(|| self.end_error())
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        if token.is_some() {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn next_or_end(&mut self) -> Result<Token, ScriptParseError> {
+        self.next().ok_or_else(|| self.end_error())
+    }
+
+    fn end_error(&self) -> ScriptParseError {
+        ScriptParseError::new(self.end.0, self.end.1, "Unexpected end of script")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tari_crypto::{
+        keys::{PublicKey, SecretKey},
+        ristretto::RistrettoSecretKey,
+    };
+    use tari_utilities::hex::Hex;
+
+    use super::*;
+    use crate::script;
+
+    fn public_key() -> RistrettoPublicKey {
+        RistrettoPublicKey::from_secret_key(&RistrettoSecretKey::random(&mut rand::thread_rng()))
+    }
+
+    #[test]
+    fn it_parses_assembly() {
+        let key = public_key();
+        let hash = [7u8; 32];
+        let text = format!(
+            "# A hash time lock
+             HashSha256 PushHash(0x{}) Equal
+             IfThen @preimage   // spend with the preimage
+                 PushPubKey({})
+             Else @preimage
+                 CheckHeightVerify(0x10) PushPubKey(b58:{})
+             EndIf @preimage
+             PushInt(-42) Drop",
+            hash.to_hex(),
+            key.to_hex(),
+            bs58::encode(key.as_bytes()).into_string()
+        );
+        let expected = script!(
+            HashSha256 PushHash(Box::new(hash)) Equal
+            IfThen
+                PushPubKey(Box::new(key.clone()))
+            Else
+                CheckHeightVerify(16) PushPubKey(Box::new(key))
+            EndIf
+            PushInt(-42) Drop
+        );
+        assert_eq!(TariScript::from_assembly(&text).unwrap(), expected);
+        assert_eq!(text.parse::<TariScript>().unwrap(), expected);
+    }
+
+    #[test]
+    fn it_round_trips() {
+        let keys = vec![public_key(), public_key()];
+        let msg = Box::new([1u8; 32]);
+        let excess = Box::new(PedersenCommitment::from_public_key(&public_key()));
+        let script = script!(
+            Dup CheckHeight(100) LtZero
+            IfThen
+                PushOne IfThen Nop Else PushZero EndIf
+            Else
+                CheckMultiSig(1, 2, keys.clone(), msg.clone())
+                CheckMultiSigVerifyAggregatePubKey(2, 2, keys, msg)
+            EndIf
+            CheckKernelExcessVerify(excess) CheckLockHeightVerify(100) CheckTotalFeeVerify(25)
+            Or(3) OrVerify(2) CheckSig(Box::new([2u8; 32])) HashBlake256 PushInt(i64::MIN) Return
+        );
+        let assembly = script.to_assembly();
+        assert!(assembly.contains("IfThen @if1\n    PushOne\n    IfThen @if2\n"));
+        let parsed = TariScript::from_assembly(&assembly).unwrap();
+        assert_eq!(parsed, script);
+        assert_eq!(parsed.to_bytes(), script.to_bytes());
+        // The single-line Display form is also valid assembly
+        assert_eq!(TariScript::from_assembly(&script.to_string()).unwrap(), script);
+        assert_eq!(TariScript::from_assembly("").unwrap(), TariScript::new(vec![]));
+    }
+
+    #[test]
+    fn it_writes_unbalanced_scripts_without_labels() {
+        let script = script!(PushOne Else EndIf);
+        assert_eq!(script.to_assembly(), "PushOne\nElse\nEndIf\n");
+        let script = script!(PushOne IfThen PushOne EndIf);
+        assert_eq!(script.to_assembly(), "PushOne\nIfThen\nPushOne\nEndIf\n");
+    }
+
+    fn error(text: &str) -> (usize, usize, String) {
+        let err = TariScript::from_assembly(text).unwrap_err();
+        (err.line, err.column, err.message)
+    }
+
+    #[test]
+    fn it_reports_error_locations() {
+        assert_eq!(error("Dup\n  Bogus"), (2, 3, "Unknown opcode `Bogus`".to_string()));
+        assert_eq!(error("PushInt(12x)").0, 1);
+        assert_eq!(error("PushInt(12x)").1, 9);
+        assert_eq!(error("Or(256)"), (1, 4, "`256` is out of range".to_string()));
+        assert_eq!(error("Dup(1)"), (1, 4, "Dup takes no arguments".to_string()));
+        assert_eq!(error("PushHash(0x1234)").1, 10);
+        assert_eq!(error("CheckHeight(5").2, "Unexpected end of script");
+        assert_eq!(error("Drop @a").1, 6);
+        assert_eq!(
+            error("PushOne\nEndIf"),
+            (2, 1, "EndIf without a matching IfThen".to_string())
+        );
+        assert_eq!(error("PushOne IfThen @a Else @b EndIf").1, 19);
+        assert_eq!(error("PushOne IfThen Else Else EndIf").1, 21);
+        assert_eq!(
+            error("PushOne IfThen Nop EndIf"),
+            (1, 20, "IfThen has no Else branch".to_string())
+        );
+        assert_eq!(
+            error("PushOne\n IfThen @x\n PushOne IfThen Else EndIf"),
+            (2, 2, "IfThen is never closed by an EndIf".to_string())
+        );
+        assert_eq!(
+            error(&format!(
+                "CheckMultiSig(1, 2, [{}], {})",
+                public_key().to_hex(),
+                [0u8; 32].to_hex()
+            ))
+            .1,
+            18
+        );
+        assert_eq!(error("PushPubKey(b58:0OIl)").1, 12);
+        assert_eq!(error("PushInt(--5)").1, 9);
+        let too_long = format!("PushHash({})\n", [0u8; 32].to_hex()).repeat(125);
+        assert_eq!(error(&too_long).0, 125);
+        assert!(TariScript::from_assembly(&too_long[..too_long.len() - 75]).is_ok());
+    }
+}
@@ -0,0 +1,297 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Steps through a Tari script interactively.
+//!
+//! ```text
+//! cargo run -p tari_script --features debugger -- my_script.tasm --inputs <stack hex> --height 1200
+//! ```
+//!
+//! The script file holds either assembly text or the hex encoded script bytes. Pass `--trace` to print the whole
+//! execution trace without stopping.
+
+use std::{
+    collections::BTreeSet,
+    error::Error,
+    fs,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::Parser;
+use tari_crypto::ristretto::pedersen::PedersenCommitment;
+use tari_script::{ExecutionStack, HashValue, ScriptContext, ScriptStepper, TariScript};
+use tari_utilities::hex::{from_hex, Hex};
+
+#[derive(Debug, Parser)]
+#[clap(about = "Steps through a Tari script")]
+struct Cli {
+    /// File with the script, as assembly text or hex
+    script: PathBuf,
+    /// The input stack, hex encoded
+    #[clap(long, default_value = "")]
+    inputs: String,
+    /// The block height the script is executed at
+    #[clap(long, default_value_t = 0)]
+    height: u64,
+    /// The previous block hash, hex encoded
+    #[clap(long)]
+    prev_hash: Option<String>,
+    /// The commitment of the output being spent, hex encoded
+    #[clap(long)]
+    commitment: Option<String>,
+    /// The excess of a kernel of the spending transaction, hex encoded. Can be given more than once.
+    #[clap(long)]
+    kernel_excess: Vec<String>,
+    /// The lock height of the spending transaction
+    #[clap(long, default_value_t = 0)]
+    lock_height: u64,
+    /// The total fee of the spending transaction, in MicroMinotari
+    #[clap(long, default_value_t = 0)]
+    fee: u64,
+    /// Print the execution trace and exit
+    #[clap(long)]
+    trace: bool,
+}
+
+const HELP: &str = "Commands:
+  s, step          execute the next opcode (also an empty line)
+  c, continue      run to the next breakpoint or the end of the script
+  b, break <n>     toggle a breakpoint on opcode n
+  p, print         show the stack and the open branches
+  l, list          list the script
+  t, trace         show the opcodes executed so far
+  q, quit          exit";
+
+fn load_script(path: &Path) -> Result<TariScript, Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+    let trimmed = text.trim();
+    if !trimmed.is_empty() && trimmed.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Ok(TariScript::from_hex(trimmed)?);
+    }
+    Ok(TariScript::from_assembly(&text)?)
+}
+
+fn load_inputs(cli: &Cli) -> Result<ExecutionStack, Box<dyn Error>> {
+    if cli.inputs.is_empty() {
+        return Ok(ExecutionStack::default());
+    }
+    Ok(ExecutionStack::from_hex(&cli.inputs)?)
+}
+
+fn load_context(cli: &Cli) -> Result<ScriptContext, Box<dyn Error>> {
+    let prev_hash = match &cli.prev_hash {
+        Some(hex) => HashValue::try_from(from_hex(hex)?.as_slice()).map_err(|_| "The block hash must be 32 bytes")?,
+        None => HashValue::default(),
+    };
+    let commitment = match &cli.commitment {
+        Some(hex) => PedersenCommitment::from_hex(hex)?,
+        None => PedersenCommitment::default(),
+    };
+    let kernel_excesses = cli
+        .kernel_excess
+        .iter()
+        .map(|hex| PedersenCommitment::from_hex(hex))
+        .collect::<Result<Vec<_>, _>>()?;
+    let context = ScriptContext::new(cli.height, &prev_hash, &commitment);
+    Ok(context.with_transaction_data(kernel_excesses, cli.lock_height, cli.fee))
+}
+
+/// An interactive debugging session of a single script
+struct Session<'a> {
+    script: &'a TariScript,
+    stepper: ScriptStepper<'a>,
+    breakpoints: BTreeSet<usize>,
+}
+
+impl<'a> Session<'a> {
+    fn new(script: &'a TariScript, inputs: &ExecutionStack, context: &'a ScriptContext) -> Self {
+        Self {
+            script,
+            stepper: ScriptStepper::new(script, inputs, context),
+            breakpoints: BTreeSet::new(),
+        }
+    }
+
+    /// Reads commands from `input` until it ends or the session is quit
+    fn run<R: BufRead, W: Write>(&mut self, mut input: R, out: &mut W) -> io::Result<()> {
+        self.list(out)?;
+        writeln!(out, "{}", HELP)?;
+        loop {
+            write!(out, "> ")?;
+            out.flush()?;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 || !self.execute(&line, out)? {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Executes a single command. Returns false if the session should end.
+    fn execute<W: Write>(&mut self, line: &str, out: &mut W) -> io::Result<bool> {
+        let mut words = line.split_whitespace();
+        match words.next().unwrap_or("s") {
+            "s" | "step" => {
+                if self.stepper.is_finished() {
+                    self.print_result(out)?;
+                } else {
+                    self.step(out)?;
+                }
+            },
+            "c" | "continue" => {
+                if self.stepper.is_finished() {
+                    self.print_result(out)?;
+                }
+                while !self.stepper.is_finished() {
+                    self.step(out)?;
+                    if self.breakpoints.contains(&self.stepper.position()) && !self.stepper.is_finished() {
+                        writeln!(out, "Breakpoint at {}", self.stepper.position())?;
+                        break;
+                    }
+                }
+            },
+            "b" | "break" => match words.next().map(str::parse::<usize>) {
+                Some(Ok(i)) if i < self.script.size() => {
+                    if !self.breakpoints.remove(&i) {
+                        self.breakpoints.insert(i);
+                    }
+                    self.list(out)?;
+                },
+                _ => writeln!(out, "Usage: break <opcode number less than {}>", self.script.size())?,
+            },
+            "p" | "print" => self.print_state(out)?,
+            "l" | "list" => self.list(out)?,
+            "t" | "trace" => {
+                for step in self.stepper.steps() {
+                    writeln!(out, "{}", step)?;
+                }
+            },
+            "q" | "quit" => return Ok(false),
+            _ => writeln!(out, "{}", HELP)?,
+        }
+        Ok(true)
+    }
+
+    fn list<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        for (i, opcode) in self.script.as_slice().iter().enumerate() {
+            let cursor = if i == self.stepper.position() { '>' } else { ' ' };
+            let breakpoint = if self.breakpoints.contains(&i) { '*' } else { ' ' };
+            writeln!(out, "{}{}{:>4}  {}", cursor, breakpoint, i, opcode)?;
+        }
+        Ok(())
+    }
+
+    fn print_state<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "stack:    {}", self.stepper.stack())?;
+        writeln!(out, "branches: {:?}", self.stepper.branches())?;
+        match self.stepper.next_opcode() {
+            Some(opcode) => writeln!(out, "next:     {} {}", self.stepper.position(), opcode),
+            None => self.print_result(out),
+        }
+    }
+
+    fn print_result<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        match self.stepper.result() {
+            Some(Ok(item)) => writeln!(out, "Success: {}", item),
+            Some(Err(err)) => writeln!(out, "Failed: {}", err),
+            None => Ok(()),
+        }
+    }
+
+    /// Executes one opcode, printing its step or the result once the script finishes
+    fn step<W: Write>(&mut self, out: &mut W) -> io::Result<()> {
+        match self.stepper.step() {
+            Some(step) => writeln!(out, "{}", step),
+            None => self.print_result(out),
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+    let script = load_script(&cli.script)?;
+    let inputs = load_inputs(&cli)?;
+    let context = load_context(&cli)?;
+
+    if cli.trace {
+        println!("{}", script.execute_with_trace(&inputs, &context));
+        return Ok(());
+    }
+
+    let stdin = io::stdin();
+    Session::new(&script, &inputs, &context).run(stdin.lock(), &mut io::stdout())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use clap::CommandFactory;
+    use tari_script::StackItem;
+
+    use super::*;
+
+    #[test]
+    fn it_parses_the_arguments() {
+        Cli::command().debug_assert();
+        let excess = PedersenCommitment::default().to_hex();
+        let cli = Cli::try_parse_from([
+            "tari_script_debugger",
+            "script.tasm",
+            "--height",
+            "1200",
+            "--kernel-excess",
+            &excess,
+            "--kernel-excess",
+            &excess,
+            "--lock-height",
+            "10",
+            "--fee",
+            "25",
+        ])
+        .unwrap();
+        assert_eq!(cli.script, PathBuf::from("script.tasm"));
+        assert_eq!(cli.height, 1200);
+        assert_eq!(cli.kernel_excess.len(), 2);
+        assert!(!cli.trace);
+        assert!(load_inputs(&cli).unwrap().is_empty());
+        let context = load_context(&cli).unwrap();
+        assert_eq!(context.block_height(), 1200);
+        assert_eq!(context.kernel_excesses().len(), 2);
+
+        assert!(Cli::try_parse_from(["tari_script_debugger"]).is_err());
+        let cli = Cli::try_parse_from(["tari_script_debugger", "script.tasm", "--prev-hash", "00ff"]).unwrap();
+        assert!(load_context(&cli).is_err());
+    }
+
+    #[test]
+    fn it_loads_assembly_and_hex_scripts() {
+        let script = TariScript::from_assembly("PushInt(2) PushInt(3) Sub").unwrap();
+        let path = std::env::temp_dir().join(format!("script_debugger_{}.tasm", rand::random::<u64>()));
+        fs::write(&path, script.to_assembly()).unwrap();
+        assert_eq!(load_script(&path).unwrap(), script);
+        fs::write(&path, format!("{}\n", script.to_hex())).unwrap();
+        assert_eq!(load_script(&path).unwrap(), script);
+        fs::write(&path, "PushInt(").unwrap();
+        assert!(load_script(&path).is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn it_steps_to_a_breakpoint_and_on_to_the_end() {
+        let script = TariScript::from_assembly("PushInt(2) PushInt(3) Sub").unwrap();
+        let context = ScriptContext::default();
+        let mut session = Session::new(&script, &ExecutionStack::default(), &context);
+        let mut out = Vec::new();
+        session.run("b 2\nc\np\n\ns\nb 9\nq\ns\n".as_bytes(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.contains(" *   2  Sub"));
+        assert!(out.contains("Breakpoint at 2"));
+        assert!(out.contains("stack:    [2, 3]"));
+        assert!(out.contains("Success: -1"));
+        assert!(out.contains("Usage: break <opcode number less than 3>"));
+        // The session ends at `quit`, so the last step is never read
+        assert_eq!(session.stepper.steps().len(), 3);
+        assert_eq!(session.stepper.result(), Some(&Ok(StackItem::Number(-1))));
+    }
+}
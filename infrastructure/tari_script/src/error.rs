@@ -51,6 +51,25 @@ pub enum ScriptError {
     InvalidDigest,
 }
 
+/// An error in script assembly text, with the 1-based line and column where it was found
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+#[error("{line}:{column}: {message}")]
+pub struct ScriptParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ScriptParseError {
+    pub fn new<T: Into<String>>(line: usize, column: usize, message: T) -> Self {
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
 impl From<TryFromIntError> for ScriptError {
     fn from(_err: TryFromIntError) -> ScriptError {
         ScriptError::ValueExceedsBounds
@@ -15,19 +15,32 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+mod analysis;
+mod assembly;
 mod error;
 mod op_codes;
 mod script;
 mod script_context;
 mod serde;
 mod stack;
+mod trace;
 
-pub use error::ScriptError;
+pub use analysis::{
+    opcode_cost,
+    PathAnalysis,
+    PathFailure,
+    ScriptAnalysis,
+    ScriptTemplate,
+    StackItemType,
+    MAX_ANALYSED_PATHS,
+};
+pub use error::{ScriptError, ScriptParseError};
 pub use op_codes::{slice_to_boxed_hash, slice_to_hash, HashValue, Message, Opcode, OpcodeVersion, ScalarValue};
-pub use script::TariScript;
+pub use script::{Branch, TariScript};
 pub use script_context::ScriptContext;
 pub use stack::{ExecutionStack, StackItem};
 use tari_crypto::ristretto::RistrettoPublicKey;
+pub use trace::{ScriptStepper, ScriptTrace, TraceStep};
 
 /// The standard payment script to be used for one-sided payment to stealth addresses
 pub fn stealth_payment_script(
@@ -18,7 +18,10 @@
 use std::{fmt, ops::Deref};
 
 use integer_encoding::VarInt;
-use tari_crypto::{ristretto::RistrettoPublicKey, tari_utilities::ByteArray};
+use tari_crypto::{
+    ristretto::{pedersen::PedersenCommitment, RistrettoPublicKey},
+    tari_utilities::ByteArray,
+};
 use tari_utilities::{hex::Hex, ByteArrayError};
 
 use super::ScriptError;
@@ -84,6 +87,11 @@ const OP_CHECK_HEIGHT: u8 = 0x67;
 const OP_COMPARE_HEIGHT_VERIFY: u8 = 0x68;
 const OP_COMPARE_HEIGHT: u8 = 0x69;
 
+// Opcode constants: Transaction Checks
+const OP_CHECK_KERNEL_EXCESS_VERIFY: u8 = 0x6a;
+const OP_CHECK_LOCK_HEIGHT_VERIFY: u8 = 0x6b;
+const OP_CHECK_TOTAL_FEE_VERIFY: u8 = 0x6c;
+
 // Opcode constants: Stack Manipulation
 const OP_DROP: u8 = 0x70;
 const OP_DUP: u8 = 0x71;
@@ -126,6 +134,14 @@ const OP_IF_THEN: u8 = 0x61;
 const OP_ELSE: u8 = 0x62;
 const OP_END_IF: u8 = 0x63;
 
+/// The instructions of a TariScript.
+///
+/// The transaction checks `CheckKernelExcessVerify`, `CheckLockHeightVerify` and `CheckTotalFeeVerify` do not bind an
+/// output to a specific kernel. Inputs are not linked to kernels, so once a transaction is mined a check is evaluated
+/// against the kernels of every transaction in the block, and it can be met by the kernel of another transaction in
+/// that block. A check only guarantees that some kernel mined with the input has the property. There are no opcodes
+/// that push the kernel excess, lock height or fee, since the pushed value could differ between the mempool and the
+/// block.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Opcode {
     // Block Height Checks
@@ -149,6 +165,21 @@ pub enum Opcode {
     /// with `StackUnderflow` if the stack is empty.
     CompareHeight,
 
+    // Transaction Checks
+    // Inputs are not linked to kernels, so a transaction is checked against its own kernels in the mempool and
+    // against all the kernels of the block once it is mined. Every check only passes more often as kernels are
+    // added, so a transaction that passes on its own also passes in any block. In a block a check can be met by
+    // the kernel of another transaction.
+    /// Checks that a kernel with the given excess is spent with the UTXO. Fails with `VerifyFailed` if there is no
+    /// such kernel.
+    CheckKernelExcessVerify(Box<PedersenCommitment>),
+    /// Checks that the highest lock height of the kernels spent with the UTXO is at least the given height. Fails with
+    /// `VerifyFailed` if it is lower.
+    CheckLockHeightVerify(u64),
+    /// Checks that the kernels spent with the UTXO pay a total fee of at least the given amount in MicroMinotari.
+    /// Fails with `VerifyFailed` if the fee is lower.
+    CheckTotalFeeVerify(u64),
+
     // Stack Manipulation
     /// No op. Does nothing. Never fails.
     Nop,
@@ -322,6 +353,9 @@ impl Opcode {
             Opcode::IfThen |
             Opcode::Else |
             Opcode::EndIf => OpcodeVersion::V0,
+            Opcode::CheckKernelExcessVerify(..) |
+            Opcode::CheckLockHeightVerify(..) |
+            Opcode::CheckTotalFeeVerify(..) => OpcodeVersion::V1,
         }
     }
 
@@ -355,6 +389,18 @@ impl Opcode {
             },
             OP_COMPARE_HEIGHT_VERIFY => Ok((CompareHeightVerify, &bytes[1..])),
             OP_COMPARE_HEIGHT => Ok((CompareHeight, &bytes[1..])),
+            OP_CHECK_KERNEL_EXCESS_VERIFY => {
+                let (excess, bytes_left) = Opcode::read_commitment(bytes)?;
+                Ok((CheckKernelExcessVerify(excess), bytes_left))
+            },
+            OP_CHECK_LOCK_HEIGHT_VERIFY => {
+                let (height, size) = u64::decode_var(&bytes[1..]).ok_or(ScriptError::InvalidData)?;
+                Ok((CheckLockHeightVerify(height), &bytes[size + 1..]))
+            },
+            OP_CHECK_TOTAL_FEE_VERIFY => {
+                let (fee, size) = u64::decode_var(&bytes[1..]).ok_or(ScriptError::InvalidData)?;
+                Ok((CheckTotalFeeVerify(fee), &bytes[size + 1..]))
+            },
             OP_NOP => Ok((Nop, &bytes[1..])),
             OP_PUSH_ZERO => Ok((PushZero, &bytes[1..])),
             OP_PUSH_ONE => Ok((PushOne, &bytes[1..])),
@@ -439,6 +485,14 @@ impl Opcode {
         }
     }
 
+    fn read_commitment(bytes: &[u8]) -> Result<(Box<PedersenCommitment>, &[u8]), ScriptError> {
+        if bytes.len() < 33 {
+            return Err(ScriptError::InvalidData);
+        }
+        let commitment = PedersenCommitment::from_bytes(&bytes[1..33])?;
+        Ok((Box::new(commitment), &bytes[33..]))
+    }
+
     fn read_multisig_args(bytes: &[u8]) -> Result<MultiSigArgs, ScriptError> {
         if bytes.len() < 3 {
             return Err(ScriptError::InvalidData);
@@ -478,6 +532,22 @@ impl Opcode {
             },
             CompareHeightVerify => array.push(OP_COMPARE_HEIGHT_VERIFY),
             CompareHeight => array.push(OP_COMPARE_HEIGHT),
+            CheckKernelExcessVerify(excess) => {
+                array.push(OP_CHECK_KERNEL_EXCESS_VERIFY);
+                array.extend_from_slice(excess.as_bytes());
+            },
+            CheckLockHeightVerify(height) => {
+                array.push(OP_CHECK_LOCK_HEIGHT_VERIFY);
+                let mut buf = [0u8; 10];
+                let used = height.encode_var(&mut buf[..]);
+                array.extend_from_slice(&buf[0..used]);
+            },
+            CheckTotalFeeVerify(fee) => {
+                array.push(OP_CHECK_TOTAL_FEE_VERIFY);
+                let mut buf = [0u8; 10];
+                let used = fee.encode_var(&mut buf[..]);
+                array.extend_from_slice(&buf[0..used]);
+            },
             Nop => array.push(OP_NOP),
             PushZero => array.push(OP_PUSH_ZERO),
             PushOne => array.push(OP_PUSH_ONE),
@@ -566,6 +636,9 @@ impl fmt::Display for Opcode {
             CheckHeight(height) => write!(fmt, "CheckHeight({})", *height),
             CompareHeightVerify => write!(fmt, "CompareHeightVerify"),
             CompareHeight => write!(fmt, "CompareHeight"),
+            CheckKernelExcessVerify(excess) => write!(fmt, "CheckKernelExcessVerify({})", excess.to_hex()),
+            CheckLockHeightVerify(height) => write!(fmt, "CheckLockHeightVerify({})", *height),
+            CheckTotalFeeVerify(fee) => write!(fmt, "CheckTotalFeeVerify({})", *fee),
             Nop => write!(fmt, "Nop"),
             PushZero => write!(fmt, "PushZero"),
             PushOne => write!(fmt, "PushOne"),
@@ -636,6 +709,7 @@ impl fmt::Display for Opcode {
 #[repr(u8)]
 pub enum OpcodeVersion {
     V0 = 0,
+    V1 = 1,
 }
 
 #[cfg(test)]
@@ -689,6 +763,16 @@ mod test {
         }
         test_check_height(&Opcode::CheckHeight(63), 0x67, "CheckHeight(63)");
         test_check_height(&Opcode::CheckHeightVerify(63), 0x66, "CheckHeightVerify(63)");
+        test_check_height(
+            &Opcode::CheckLockHeightVerify(63),
+            OP_CHECK_LOCK_HEIGHT_VERIFY,
+            "CheckLockHeightVerify(63)",
+        );
+        test_check_height(
+            &Opcode::CheckTotalFeeVerify(63),
+            OP_CHECK_TOTAL_FEE_VERIFY,
+            "CheckTotalFeeVerify(63)",
+        );
     }
 
     #[test]
@@ -710,6 +794,34 @@ mod test {
         assert_eq!(format!("{}", op).as_str(), "PushInt(257)");
     }
 
+    #[test]
+    fn check_kernel_excess() {
+        fn test_check_kernel_excess(op: &Opcode, excess: &PedersenCommitment, val: u8, display: &str) {
+            // Serialise
+            assert!(matches!(Opcode::read_next(&[val, 1, 2]), Err(ScriptError::InvalidData)));
+            let mut s = vec![val];
+            s.extend_from_slice(excess.as_bytes());
+            s.push(83);
+            let (opcode, rem) = Opcode::read_next(&s).unwrap();
+            assert_eq!(opcode, *op);
+            assert_eq!(rem, &[83]);
+            // Deserialise
+            let mut arr = vec![];
+            op.to_bytes(&mut arr);
+            assert_eq!(&arr, &s[..33]);
+            // Format
+            assert_eq!(format!("{}", op).as_str(), display);
+        }
+        let excess =
+            PedersenCommitment::from_hex("6c9cb4d3e57351462122310fa22c90b1e6dfb528d64615363d1261a75da3e401").unwrap();
+        test_check_kernel_excess(
+            &Opcode::CheckKernelExcessVerify(Box::new(excess.clone())),
+            &excess,
+            OP_CHECK_KERNEL_EXCESS_VERIFY,
+            "CheckKernelExcessVerify(6c9cb4d3e57351462122310fa22c90b1e6dfb528d64615363d1261a75da3e401)",
+        );
+    }
+
     #[test]
     fn push_pubkey() {
         // Serialise
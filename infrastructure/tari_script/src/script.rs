@@ -36,6 +36,7 @@ use tari_utilities::{
 use crate::{
     op_codes::Message,
     slice_to_hash,
+    trace::{ScriptStepper, ScriptTrace},
     ExecutionStack,
     HashValue,
     Opcode,
@@ -54,8 +55,8 @@ macro_rules! script {
     }}
 }
 
-const MAX_MULTISIG_LIMIT: u8 = 32;
-const MAX_SCRIPT_BYTES: usize = 4096;
+pub(crate) const MAX_MULTISIG_LIMIT: u8 = 32;
+pub(crate) const MAX_SCRIPT_BYTES: usize = 4096;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct TariScript {
@@ -144,13 +145,36 @@ impl TariScript {
         let mut state = ExecutionState::default();
 
         for opcode in &self.script {
-            if self.should_execute(opcode, &state)? {
-                self.execute_opcode(opcode, &mut stack, context, &mut state)?
-            } else {
-                continue;
-            }
+            self.step(opcode, &mut stack, context, &mut state)?;
         }
 
+        TariScript::finish(stack, &state)
+    }
+
+    /// Executes the script one opcode at a time, recording the stack and branch state after every opcode. The trace
+    /// ends with the same result as [TariScript::execute_with_context].
+    pub fn execute_with_trace(&self, inputs: &ExecutionStack, context: &ScriptContext) -> ScriptTrace {
+        ScriptStepper::new(self, inputs, context).into_trace()
+    }
+
+    /// Executes the opcode if it is not in a branch that is skipped, returning whether it was executed
+    pub(crate) fn step(
+        &self,
+        opcode: &Opcode,
+        stack: &mut ExecutionStack,
+        context: &ScriptContext,
+        state: &mut ExecutionState,
+    ) -> Result<bool, ScriptError> {
+        if self.should_execute(opcode, state)? {
+            self.execute_opcode(opcode, stack, context, state)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Checks the final state once every opcode has been executed, returning the final stack item
+    pub(crate) fn finish(mut stack: ExecutionStack, state: &ExecutionState) -> Result<StackItem, ScriptError> {
         // the script has finished but there was an open IfThen or Else!
         if !state.if_stack.is_empty() {
             return Err(ScriptError::MissingOpcode);
@@ -261,6 +285,21 @@ impl TariScript {
             CheckHeight(height) => TariScript::handle_check_height(stack, *height, ctx.block_height()),
             CompareHeightVerify => TariScript::handle_compare_height_verify(stack, ctx.block_height()),
             CompareHeight => TariScript::handle_compare_height(stack, ctx.block_height()),
+            CheckKernelExcessVerify(excess) => {
+                if ctx.has_kernel_excess(excess) {
+                    Ok(())
+                } else {
+                    Err(ScriptError::VerifyFailed)
+                }
+            },
+            CheckLockHeightVerify(height) => TariScript::handle_check_height_verify(*height, ctx.lock_height()),
+            CheckTotalFeeVerify(fee) => {
+                if ctx.total_fee() >= *fee {
+                    Ok(())
+                } else {
+                    Err(ScriptError::VerifyFailed)
+                }
+            },
             Nop => Ok(()),
             PushZero => stack.push(Number(0)),
             PushOne => stack.push(Number(1)),
@@ -674,8 +713,10 @@ impl fmt::Display for TariScript {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-enum Branch {
+/// The branch of an `IfThen` block that is executed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Branch {
+    /// The whole block is inside a branch that is skipped
     NotExecuted,
     ExecuteIf,
     ExecuteElse,
@@ -688,11 +729,22 @@ struct IfState {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
-struct ExecutionState {
+pub(crate) struct ExecutionState {
     executing: bool,
     if_stack: Vec<IfState>,
 }
 
+impl ExecutionState {
+    pub(crate) fn is_executing(&self) -> bool {
+        self.executing
+    }
+
+    /// The branches of the open `IfThen` blocks, outermost first
+    pub(crate) fn branches(&self) -> Vec<Branch> {
+        self.if_stack.iter().map(|s| s.branch).collect()
+    }
+}
+
 impl Default for ExecutionState {
     fn default() -> Self {
         Self {
@@ -938,6 +990,40 @@ mod test {
         }
     }
 
+    #[test]
+    fn op_check_kernel_excess() {
+        let mut rng = rand::thread_rng();
+        let inputs = ExecutionStack::default();
+        let (_, p) = RistrettoPublicKey::random_keypair(&mut rng);
+        let excess = PedersenCommitment::from_public_key(&p);
+        let (_, p) = RistrettoPublicKey::random_keypair(&mut rng);
+        let other = PedersenCommitment::from_public_key(&p);
+        let ctx = context_with_height(1).with_transaction_data(vec![other.clone(), excess.clone()], 0, 0);
+
+        let script = script!(CheckKernelExcessVerify(Box::new(excess)) PushOne);
+        assert_eq!(script.execute_with_context(&inputs, &ctx).unwrap(), Number(1));
+        let ctx = context_with_height(1).with_transaction_data(vec![other], 0, 0);
+        let err = script.execute_with_context(&inputs, &ctx).unwrap_err();
+        assert!(matches!(err, ScriptError::VerifyFailed));
+    }
+
+    #[test]
+    fn op_check_lock_height_and_total_fee_verify() {
+        let inputs = ExecutionStack::default();
+        let script = script!(CheckLockHeightVerify(42) CheckTotalFeeVerify(1000) PushOne);
+        let ctx = context_with_height(1).with_transaction_data(vec![], 42, 1000);
+        assert_eq!(script.execute_with_context(&inputs, &ctx).unwrap(), Number(1));
+        let ctx = context_with_height(1).with_transaction_data(vec![], u64::MAX, u64::MAX);
+        assert_eq!(script.execute_with_context(&inputs, &ctx).unwrap(), Number(1));
+
+        let ctx = context_with_height(1).with_transaction_data(vec![], 41, 1000);
+        let err = script.execute_with_context(&inputs, &ctx).unwrap_err();
+        assert!(matches!(err, ScriptError::VerifyFailed));
+        let ctx = context_with_height(1).with_transaction_data(vec![], 42, 999);
+        let err = script.execute_with_context(&inputs, &ctx).unwrap_err();
+        assert!(matches!(err, ScriptError::VerifyFailed));
+    }
+
     #[test]
     fn op_drop_push() {
         let inputs = inputs!(420);
@@ -15,6 +15,8 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use std::sync::Arc;
+
 use tari_crypto::ristretto::pedersen::PedersenCommitment;
 
 use crate::HashValue;
@@ -29,6 +31,12 @@ pub struct ScriptContext {
     prev_block_hash: HashValue,
     /// The commitment of the UTXO that is attached to this script
     commitment: PedersenCommitment,
+    /// The excesses of the kernels of the transaction spending the UTXO, shared by the contexts of all its inputs
+    kernel_excesses: Arc<Vec<PedersenCommitment>>,
+    /// The highest lock height of the kernels of the transaction spending the UTXO
+    lock_height: u64,
+    /// The total fee of the kernels of the transaction spending the UTXO
+    total_fee: u64,
 }
 
 impl ScriptContext {
@@ -37,9 +45,21 @@ impl ScriptContext {
             block_height: height,
             prev_block_hash: *prev_hash,
             commitment: com.clone(),
+            ..Default::default()
         }
     }
 
+    /// Adds the kernel data of the transaction spending the UTXO. Mimblewimble does not link inputs to kernels, so when
+    /// a block is validated this is the data of all the kernels in the block. This is why scripts can only check for
+    /// the presence of a kernel and lower bounds on the lock height and fee.
+    pub fn with_transaction_data<K>(mut self, kernel_excesses: K, lock_height: u64, total_fee: u64) -> Self
+    where K: Into<Arc<Vec<PedersenCommitment>>> {
+        self.kernel_excesses = kernel_excesses.into();
+        self.lock_height = lock_height;
+        self.total_fee = total_fee;
+        self
+    }
+
     pub fn block_height(&self) -> u64 {
         self.block_height
     }
@@ -51,4 +71,20 @@ impl ScriptContext {
     pub fn commitment(&self) -> &PedersenCommitment {
         &self.commitment
     }
+
+    pub fn kernel_excesses(&self) -> &[PedersenCommitment] {
+        &self.kernel_excesses
+    }
+
+    pub fn has_kernel_excess(&self, excess: &PedersenCommitment) -> bool {
+        self.kernel_excesses.contains(excess)
+    }
+
+    pub fn lock_height(&self) -> u64 {
+        self.lock_height
+    }
+
+    pub fn total_fee(&self) -> u64 {
+        self.total_fee
+    }
 }
@@ -15,7 +15,7 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::{convert::TryFrom, io};
+use std::{convert::TryFrom, fmt, io};
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use integer_encoding::{VarIntReader, VarIntWriter};
@@ -69,6 +69,24 @@ pub enum StackItem {
     Signature(RistrettoSchnorr),
 }
 
+impl fmt::Display for StackItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StackItem::Number(n) => write!(f, "{}", n),
+            StackItem::Hash(h) => write!(f, "Hash({})", h.to_hex()),
+            StackItem::Scalar(s) => write!(f, "Scalar({})", s.to_hex()),
+            StackItem::Commitment(c) => write!(f, "Commitment({})", c.to_hex()),
+            StackItem::PublicKey(p) => write!(f, "PublicKey({})", p.to_hex()),
+            StackItem::Signature(sig) => write!(
+                f,
+                "Signature({}, {})",
+                sig.get_public_nonce().to_hex(),
+                sig.get_signature().to_hex()
+            ),
+        }
+    }
+}
+
 impl StackItem {
     /// Convert an input item into its binary representation and append it to the array. The function returns the byte
     /// slice that matches the item as a convenience
@@ -215,6 +233,14 @@ impl BorshDeserialize for ExecutionStack {
     }
 }
 
+/// Displays the items from the bottom of the stack to the top
+impl fmt::Display for ExecutionStack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let items = self.items.iter().map(|item| item.to_string()).collect::<Vec<_>>();
+        write!(f, "[{}]", items.join(", "))
+    }
+}
+
 impl ExecutionStack {
     /// Return a new `ExecutionStack` using the vector of [StackItem] in `items`
     pub fn new(items: Vec<StackItem>) -> Self {
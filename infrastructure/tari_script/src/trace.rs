@@ -0,0 +1,272 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Step-by-step script execution, for debugging scripts that fail.
+//!
+//! [ScriptStepper] executes a script one opcode at a time and records a [TraceStep] for every opcode, including the
+//! ones that are skipped because they are in a branch that is not taken. [TariScript::execute_with_trace] runs a
+//! stepper to the end and returns the whole [ScriptTrace].
+
+use std::fmt;
+
+use crate::{
+    script::{Branch, ExecutionState},
+    ExecutionStack,
+    Opcode,
+    ScriptContext,
+    ScriptError,
+    StackItem,
+    TariScript,
+};
+
+/// The record of a single opcode in a traced execution
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceStep {
+    /// The position of the opcode in the script
+    pub index: usize,
+    pub opcode: Opcode,
+    /// False if the opcode was skipped because it is in a branch that is not taken
+    pub executed: bool,
+    pub stack_before: ExecutionStack,
+    pub stack_after: ExecutionStack,
+    /// The branches of the open `IfThen` blocks after the opcode, outermost first
+    pub branches: Vec<Branch>,
+    /// Whether the opcodes that follow will be executed
+    pub executing: bool,
+    /// The error that aborted the script on this opcode
+    pub error: Option<ScriptError>,
+}
+
+impl fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let marker = match (&self.error, self.executed) {
+            (Some(_), _) => '!',
+            (None, true) => ' ',
+            (None, false) => '-',
+        };
+        write!(
+            f,
+            "{:>4} {} {}{}",
+            self.index,
+            marker,
+            "  ".repeat(self.branches.len()),
+            self.opcode
+        )?;
+        match &self.error {
+            Some(err) => write!(f, "  => {}", err),
+            None if self.executed => write!(f, "  => {}", self.stack_after),
+            None => write!(f, "  (skipped)"),
+        }
+    }
+}
+
+/// The full record of a traced execution
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptTrace {
+    pub steps: Vec<TraceStep>,
+    pub result: Result<StackItem, ScriptError>,
+}
+
+impl ScriptTrace {
+    pub fn is_success(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    /// The opcode that aborted the script. This is `None` if the script succeeded or ran to the end and then failed,
+    /// e.g. with more than one item left on the stack.
+    pub fn failed_step(&self) -> Option<&TraceStep> {
+        self.steps.iter().find(|step| step.error.is_some())
+    }
+}
+
+impl fmt::Display for ScriptTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            writeln!(f, "{}", step)?;
+        }
+        match &self.result {
+            Ok(item) => write!(f, "Success: {}", item),
+            Err(err) => write!(f, "Failed: {}", err),
+        }
+    }
+}
+
+/// Executes a script one opcode at a time
+pub struct ScriptStepper<'a> {
+    script: &'a TariScript,
+    context: &'a ScriptContext,
+    stack: ExecutionStack,
+    state: ExecutionState,
+    position: usize,
+    steps: Vec<TraceStep>,
+    result: Option<Result<StackItem, ScriptError>>,
+}
+
+impl<'a> ScriptStepper<'a> {
+    pub fn new(script: &'a TariScript, inputs: &ExecutionStack, context: &'a ScriptContext) -> Self {
+        Self {
+            script,
+            context,
+            stack: inputs.clone(),
+            state: ExecutionState::default(),
+            position: 0,
+            steps: Vec::new(),
+            result: None,
+        }
+    }
+
+    /// The position of the next opcode to execute
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The next opcode to execute, or `None` if the script has finished
+    pub fn next_opcode(&self) -> Option<&Opcode> {
+        if self.is_finished() {
+            return None;
+        }
+        self.script.opcode(self.position)
+    }
+
+    pub fn stack(&self) -> &ExecutionStack {
+        &self.stack
+    }
+
+    /// The branches of the open `IfThen` blocks, outermost first
+    pub fn branches(&self) -> Vec<Branch> {
+        self.state.branches()
+    }
+
+    pub fn steps(&self) -> &[TraceStep] {
+        &self.steps
+    }
+
+    /// The result of the script, once it has finished
+    pub fn result(&self) -> Option<&Result<StackItem, ScriptError>> {
+        self.result.as_ref()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// Executes the next opcode and returns its trace step. Once every opcode has been executed, the next call
+    /// checks the final stack, sets the result and returns `None`.
+    pub fn step(&mut self) -> Option<&TraceStep> {
+        if self.is_finished() {
+            return None;
+        }
+        let script = self.script;
+        let opcode = match script.opcode(self.position) {
+            Some(opcode) => opcode,
+            None => {
+                self.result = Some(TariScript::finish(self.stack.clone(), &self.state));
+                return None;
+            },
+        };
+        let stack_before = self.stack.clone();
+        let outcome = script.step(opcode, &mut self.stack, self.context, &mut self.state);
+        let (executed, error) = match outcome {
+            Ok(executed) => (executed, None),
+            Err(err) => {
+                self.result = Some(Err(err.clone()));
+                (true, Some(err))
+            },
+        };
+        self.steps.push(TraceStep {
+            index: self.position,
+            opcode: opcode.clone(),
+            executed,
+            stack_before,
+            stack_after: self.stack.clone(),
+            branches: self.state.branches(),
+            executing: self.state.is_executing(),
+            error,
+        });
+        self.position += 1;
+        self.steps.last()
+    }
+
+    /// Executes the remaining opcodes and returns the trace
+    pub fn into_trace(mut self) -> ScriptTrace {
+        while !self.is_finished() {
+            self.step();
+        }
+        ScriptTrace {
+            steps: self.steps,
+            result: self.result.unwrap_or(Err(ScriptError::InvalidOpcode)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tari_crypto::ristretto::pedersen::PedersenCommitment;
+
+    use super::*;
+    use crate::{inputs, script};
+
+    fn context(height: u64) -> ScriptContext {
+        ScriptContext::new(height, &[0u8; 32], &PedersenCommitment::default())
+    }
+
+    #[test]
+    fn it_traces_a_successful_script() {
+        let script = script!(Dup IfThen PushInt(5) Else PushInt(6) EndIf Add);
+        let inputs = inputs!(1);
+        let ctx = context(0);
+        let trace = script.execute_with_trace(&inputs, &ctx);
+        assert_eq!(trace.result, script.execute_with_context(&inputs, &ctx));
+        assert!(trace.is_success());
+        assert_eq!(trace.result, Ok(StackItem::Number(6)));
+        assert_eq!(trace.steps.len(), 7);
+        assert!(trace.failed_step().is_none());
+
+        let if_then = &trace.steps[1];
+        assert_eq!(if_then.stack_before, inputs!(1, 1));
+        assert_eq!(if_then.stack_after, inputs!(1));
+        assert_eq!(if_then.branches, vec![Branch::ExecuteIf]);
+        let push_six = &trace.steps[4];
+        assert!(!push_six.executed);
+        assert!(!push_six.executing);
+        assert_eq!(push_six.stack_after, inputs!(1, 5));
+        assert_eq!(trace.steps[5].branches, vec![]);
+        assert_eq!(trace.to_string().lines().last(), Some("Success: 6"));
+    }
+
+    #[test]
+    fn it_records_why_a_script_failed() {
+        let script = script!(CheckHeightVerify(10) PushOne);
+        let trace = script.execute_with_trace(&ExecutionStack::default(), &context(5));
+        assert_eq!(trace.result, Err(ScriptError::VerifyFailed));
+        assert_eq!(trace.steps.len(), 1);
+        let failed = trace.failed_step().unwrap();
+        assert_eq!(failed.opcode, Opcode::CheckHeightVerify(10));
+        assert_eq!(failed.error, Some(ScriptError::VerifyFailed));
+
+        // Scripts can also fail after the last opcode
+        let script = script!(PushOne PushZero);
+        let trace = script.execute_with_trace(&ExecutionStack::default(), &context(5));
+        assert_eq!(trace.result, Err(ScriptError::NonUnitLengthStack));
+        assert_eq!(trace.steps.len(), 2);
+        assert!(trace.failed_step().is_none());
+    }
+
+    #[test]
+    fn it_steps_through_a_script() {
+        let script = script!(PushInt(2) PushInt(3) Sub);
+        let ctx = context(0);
+        let mut stepper = ScriptStepper::new(&script, &ExecutionStack::default(), &ctx);
+        assert_eq!(stepper.next_opcode(), Some(&Opcode::PushInt(2)));
+        stepper.step();
+        stepper.step();
+        assert_eq!(stepper.position(), 2);
+        assert_eq!(stepper.stack(), &inputs!(2, 3));
+        assert_eq!(stepper.step().unwrap().stack_after, inputs!(-1));
+        assert!(!stepper.is_finished());
+        assert!(stepper.step().is_none());
+        assert_eq!(stepper.result(), Some(&Ok(StackItem::Number(-1))));
+        assert!(stepper.next_opcode().is_none());
+        assert!(stepper.step().is_none());
+    }
+}
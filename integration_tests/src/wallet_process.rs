@@ -215,6 +215,8 @@ pub fn get_default_cli() -> Cli {
         recovery: false,
         seed_words: None,
         seed_words_file_name: None,
+        restore_backup: None,
+        backup_passphrase: None,
         non_interactive_mode: true,
         input_file: None,
         command: None,
@@ -222,6 +224,8 @@ pub fn get_default_cli() -> Cli {
         command_mode_auto_exit: false,
         grpc_enabled: true,
         grpc_address: None,
+        json_rpc_enabled: false,
+        json_rpc_address: None,
         command2: None,
         profile_with_tokio_console: false,
     }
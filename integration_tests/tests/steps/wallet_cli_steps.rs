@@ -36,6 +36,7 @@ use minotari_console_wallet::{
     SetBaseNodeArgs,
     WhoisArgs,
 };
+use minotari_wallet::output_manager_service::UtxoSelectionOrdering;
 use tari_common_types::tari_address::TariAddress;
 use tari_comms::multiaddr::Multiaddr;
 use tari_core::transactions::tari_amount::MicroMinotari;
@@ -154,6 +155,8 @@ async fn send_from_cli(world: &mut TariWorld, amount: u64, wallet_a: String, wal
         amount: MicroMinotari(amount),
         message: format!("Send amount {} from {} to {}", amount, wallet_a, wallet_b),
         destination: wallet_b_address,
+        utxo_selection: UtxoSelectionOrdering::Default,
+        account: None,
     };
     cli.command2 = Some(CliCommands::SendMinotari(args));
 
@@ -175,6 +178,8 @@ async fn create_burn_tx_via_cli(world: &mut TariWorld, amount: u64, wallet: Stri
     let args = BurnMinotariArgs {
         amount: MicroMinotari(amount),
         message: format!("Burn, burn amount {} !!!", amount,),
+        utxo_selection: UtxoSelectionOrdering::Default,
+        account: None,
     };
     cli.command2 = Some(CliCommands::BurnMinotari(args));
 
@@ -207,6 +212,8 @@ async fn send_one_sided_tx_via_cli(world: &mut TariWorld, amount: u64, wallet_a:
         amount: MicroMinotari(amount),
         message: format!("Send one sided amount {} from {} to {}", amount, wallet_a, wallet_b),
         destination: wallet_b_address,
+        utxo_selection: UtxoSelectionOrdering::Default,
+        account: None,
     };
     cli.command2 = Some(CliCommands::SendOneSided(args));
 
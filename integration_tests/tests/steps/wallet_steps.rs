@@ -110,7 +110,7 @@ async fn wait_for_wallet_to_have_micro_tari(world: &mut TariWorld, wallet: Strin
 
     for _ in 0..=num_retries {
         curr_amount = client
-            .get_balance(GetBalanceRequest {})
+            .get_balance(GetBalanceRequest::default())
             .await
             .unwrap()
             .into_inner()
@@ -157,7 +157,7 @@ async fn wallet_detects_all_txs_as_mined_confirmed(world: &mut TariWorld, wallet
     let mut client = create_wallet_client(world, wallet_name.clone()).await.unwrap();
 
     let mut completed_tx_stream = client
-        .get_completed_transactions(GetCompletedTransactionsRequest {})
+        .get_completed_transactions(GetCompletedTransactionsRequest::default())
         .await
         .unwrap()
         .into_inner();
@@ -472,7 +472,7 @@ async fn list_all_txs_for_wallet(world: &mut TariWorld, transaction_type: String
     }
     let mut client = create_wallet_client(world, wallet.clone()).await.unwrap();
 
-    let request = GetCompletedTransactionsRequest {};
+    let request = GetCompletedTransactionsRequest::default();
     let mut completed_txs = client.get_completed_transactions(request).await.unwrap().into_inner();
 
     while let Some(tx) = completed_txs.next().await {
@@ -516,7 +516,7 @@ async fn wallet_has_at_least_num_txs(world: &mut TariWorld, wallet: String, num_
 
     for _ in 0..num_retries {
         let mut txs = client
-            .get_completed_transactions(grpc::GetCompletedTransactionsRequest {})
+            .get_completed_transactions(grpc::GetCompletedTransactionsRequest::default())
             .await
             .unwrap()
             .into_inner();
@@ -597,7 +597,7 @@ async fn wait_for_wallet_to_have_less_than_micro_tari(world: &mut TariWorld, wal
     println!("Waiting for wallet {} to have less than {} uT", wallet, amount);
 
     let num_retries = 100;
-    let request = GetBalanceRequest {};
+    let request = GetBalanceRequest::default();
 
     for _ in 0..num_retries {
         let balance_res = client.get_balance(request.clone()).await.unwrap().into_inner();
@@ -684,9 +684,12 @@ async fn send_amount_from_source_wallet_to_dest_wallet_without_broadcast(
             dest_wallet.as_str()
         ),
         payment_type: 0, // normal mimblewimble payment type
+        utxo_selection_ordering: 0,
+        covenant: String::new(),
     };
     let transfer_req = TransferRequest {
         recipients: vec![payment_recipient],
+        account: String::new(),
     };
     let tx_res = source_client.transfer(transfer_req).await.unwrap().into_inner();
     let tx_res = tx_res.results;
@@ -744,9 +747,12 @@ async fn send_one_sided_transaction_from_source_wallet_to_dest_wallt(
             dest_wallet.as_str()
         ),
         payment_type: 1, // one sided transaction
+        utxo_selection_ordering: 0,
+        covenant: String::new(),
     };
     let transfer_req = TransferRequest {
         recipients: vec![payment_recipient],
+        account: String::new(),
     };
     let tx_res = source_client.transfer(transfer_req).await.unwrap().into_inner();
     let tx_res = tx_res.results;
@@ -843,9 +849,12 @@ async fn send_amount_from_wallet_to_wallet_at_fee(
             fee_per_gram
         ),
         payment_type: 0, // mimblewimble transaction
+        utxo_selection_ordering: 0,
+        covenant: String::new(),
     };
     let transfer_req = TransferRequest {
         recipients: vec![payment_recipient],
+        account: String::new(),
     };
     let tx_res = sender_wallet_client.transfer(transfer_req).await.unwrap().into_inner();
     let tx_res = tx_res.results;
@@ -920,7 +929,7 @@ async fn send_amount_from_wallet_to_wallet_at_fee(
 async fn wallet_detects_at_least_coinbase_transactions(world: &mut TariWorld, wallet_name: String, coinbases: u64) {
     let mut client = create_wallet_client(world, wallet_name.clone()).await.unwrap();
     let mut completed_tx_res = client
-        .get_completed_transactions(GetCompletedTransactionsRequest {})
+        .get_completed_transactions(GetCompletedTransactionsRequest::default())
         .await
         .unwrap()
         .into_inner();
@@ -972,7 +981,7 @@ async fn wallet_detects_at_least_coinbase_transactions(world: &mut TariWorld, wa
 async fn wallet_detects_at_least_unmined_transactions(world: &mut TariWorld, wallet_name: String, coinbases: u64) {
     let mut client = create_wallet_client(world, wallet_name.clone()).await.unwrap();
     let mut completed_tx_res = client
-        .get_completed_transactions(GetCompletedTransactionsRequest {})
+        .get_completed_transactions(GetCompletedTransactionsRequest::default())
         .await
         .unwrap()
         .into_inner();
@@ -1186,7 +1195,7 @@ async fn wallets_should_have_at_least_num_spendable_coinbase_outs(
 
         'inner: for _ in 0..num_retries {
             let mut stream = client
-                .get_completed_transactions(GetCompletedTransactionsRequest {})
+                .get_completed_transactions(GetCompletedTransactionsRequest::default())
                 .await
                 .unwrap()
                 .into_inner();
@@ -1260,9 +1269,12 @@ async fn send_num_transactions_to_wallets_at_fee(
                 receiver_wallet.as_str()
             ),
             payment_type: 0, // standard mimblewimble transaction
+            utxo_selection_ordering: 0,
+            covenant: String::new(),
         };
         let transfer_req = TransferRequest {
             recipients: vec![payment_recipient],
+            account: String::new(),
         };
         let transfer_res = sender_wallet_client.transfer(transfer_req).await.unwrap().into_inner();
         let transfer_res = transfer_res.results.first().unwrap();
@@ -1401,9 +1413,12 @@ async fn transfer_tari_from_wallet_to_receiver(world: &mut TariWorld, amount: u6
             receiver.as_str()
         ),
         payment_type: 0, // normal mimblewimble payment type
+        utxo_selection_ordering: 0,
+        covenant: String::new(),
     };
     let transfer_req = TransferRequest {
         recipients: vec![payment_recipient],
+        account: String::new(),
     };
     let tx_res = sender_wallet_client.transfer(transfer_req).await.unwrap().into_inner();
     let tx_res = tx_res.results;
@@ -1485,7 +1500,7 @@ async fn wallet_has_tari(world: &mut TariWorld, wallet: String, amount: u64) {
 
     for _ in 0..num_retries {
         let balance_res = wallet_client
-            .get_balance(GetBalanceRequest {})
+            .get_balance(GetBalanceRequest::default())
             .await
             .unwrap()
             .into_inner();
@@ -1552,7 +1567,7 @@ async fn wallet_with_tari_connected_to_base_node(
 
     for _ in 0..num_retries {
         let balance_res = wallet_client
-            .get_balance(GetBalanceRequest {})
+            .get_balance(GetBalanceRequest::default())
             .await
             .unwrap()
             .into_inner();
@@ -1594,6 +1609,8 @@ async fn transfer_from_wallet_to_two_recipients_at_fee(
             receiver1.as_str()
         ),
         payment_type: 0, // normal mimblewimble payment type
+        utxo_selection_ordering: 0,
+        covenant: String::new(),
     };
 
     let payment_recipient2 = PaymentRecipient {
@@ -1607,9 +1624,12 @@ async fn transfer_from_wallet_to_two_recipients_at_fee(
             receiver2.as_str()
         ),
         payment_type: 0, // normal mimblewimble payment type
+        utxo_selection_ordering: 0,
+        covenant: String::new(),
     };
     let transfer_req = TransferRequest {
         recipients: vec![payment_recipient1, payment_recipient2],
+        account: String::new(),
     };
     let tx_res = sender_client.transfer(transfer_req).await.unwrap().into_inner();
     let tx_res = tx_res.results;
@@ -1718,9 +1738,12 @@ async fn transfer_tari_to_self(world: &mut TariWorld, amount: u64, sender: Strin
         fee_per_gram,
         message: format!("transfer amount {} from {} to self", amount, sender.as_str(),),
         payment_type: 0, // normal mimblewimble payment type
+        utxo_selection_ordering: 0,
+        covenant: String::new(),
     };
     let transfer_req = TransferRequest {
         recipients: vec![payment_recipient],
+        account: String::new(),
     };
     let tx_res = sender_wallet_client.transfer(transfer_req).await.unwrap().into_inner();
     let tx_res = tx_res.results;
@@ -1803,6 +1826,8 @@ async fn htlc_transaction(world: &mut TariWorld, amount: u64, sender: String, re
             fee_per_gram
         ),
         payment_type: 0, // normal mimblewimble transaction
+        utxo_selection_ordering: 0,
+        covenant: String::new(),
     };
 
     let atomic_swap_request = SendShaAtomicSwapRequest {
@@ -2032,7 +2057,7 @@ async fn wait_for_wallet_to_have_less_than_amount(world: &mut TariWorld, wallet:
 
     for _ in 0..=num_retries {
         curr_amount = client
-            .get_balance(GetBalanceRequest {})
+            .get_balance(GetBalanceRequest::default())
             .await
             .unwrap()
             .into_inner()
@@ -2091,9 +2116,12 @@ async fn send_one_sided_stealth_transaction(
             receiver.as_str()
         ),
         payment_type: 2, // one sided stealth transaction
+        utxo_selection_ordering: 0,
+        covenant: String::new(),
     };
     let transfer_req = TransferRequest {
         recipients: vec![payment_recipient],
+        account: String::new(),
     };
     let tx_res = sender_client.transfer(transfer_req).await.unwrap().into_inner();
     let tx_res = tx_res.results;
@@ -2509,7 +2537,7 @@ async fn restart_wallet(world: &mut TariWorld, wallet: String) {
 async fn check_if_wallet_has_num_transactions(world: &mut TariWorld, wallet: String, num_txs: u64) {
     let mut client = create_wallet_client(world, wallet.clone()).await.unwrap();
     let mut get_completed_txs_res = client
-        .get_completed_transactions(GetCompletedTransactionsRequest {})
+        .get_completed_transactions(GetCompletedTransactionsRequest::default())
         .await
         .unwrap()
         .into_inner();
@@ -2572,10 +2600,13 @@ async fn multi_send_txs_from_wallet(
                 fee_per_gram
             ),
             payment_type: 0, // mimblewimble transaction
+            utxo_selection_ordering: 0,
+            covenant: String::new(),
         };
 
         let transfer_req = TransferRequest {
             recipients: vec![payment_recipient],
+            account: String::new(),
         };
         let tx_res = sender_wallet_client.transfer(transfer_req).await.unwrap().into_inner();
         let tx_res = tx_res.results;
@@ -2656,7 +2687,7 @@ async fn multi_send_txs_from_wallet(
 async fn check_if_last_imported_txs_are_invalid_in_wallet(world: &mut TariWorld, wallet: String) {
     let mut client = create_wallet_client(world, wallet.clone()).await.unwrap();
     let mut get_completed_txs_res = client
-        .get_completed_transactions(GetCompletedTransactionsRequest {})
+        .get_completed_transactions(GetCompletedTransactionsRequest::default())
         .await
         .unwrap()
         .into_inner();
@@ -2679,7 +2710,7 @@ async fn check_if_last_imported_txs_are_invalid_in_wallet(world: &mut TariWorld,
 async fn check_if_last_imported_txs_are_valid_in_wallet(world: &mut TariWorld, wallet: String) {
     let mut client = create_wallet_client(world, wallet.clone()).await.unwrap();
     let mut get_completed_txs_res = client
-        .get_completed_transactions(GetCompletedTransactionsRequest {})
+        .get_completed_transactions(GetCompletedTransactionsRequest::default())
         .await
         .unwrap()
         .into_inner();
@@ -2732,6 +2763,8 @@ async fn burn_transaction(world: &mut TariWorld, amount: u64, wallet: String, fe
         fee_per_gram: fee,
         message: "Burning some tari".to_string(),
         claim_public_key: identity.public_key,
+        utxo_selection_ordering: 0,
+        covenant: String::new(),
     };
 
     let result = client.create_burn_transaction(req).await.unwrap();